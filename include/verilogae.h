@@ -102,7 +102,7 @@ typedef struct VAEOpts {
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_functions(const void *lib);
 
@@ -112,7 +112,7 @@ const char *const *verilogae_functions(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_function_symbols(const void *lib);
 
@@ -122,7 +122,7 @@ const char *const *verilogae_function_symbols(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_opvars(const void *lib);
 
@@ -132,7 +132,7 @@ const char *const *verilogae_opvars(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_real_params(const void *lib);
 
@@ -142,7 +142,7 @@ const char *const *verilogae_real_params(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_real_param_units(const void *lib);
 
@@ -152,7 +152,7 @@ const char *const *verilogae_real_param_units(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_real_param_descriptions(const void *lib);
 
@@ -162,7 +162,7 @@ const char *const *verilogae_real_param_descriptions(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_real_param_groups(const void *lib);
 
@@ -172,7 +172,7 @@ const char *const *verilogae_real_param_groups(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_int_params(const void *lib);
 
@@ -182,7 +182,7 @@ const char *const *verilogae_int_params(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_int_param_units(const void *lib);
 
@@ -192,7 +192,7 @@ const char *const *verilogae_int_param_units(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_int_param_descriptions(const void *lib);
 
@@ -202,7 +202,7 @@ const char *const *verilogae_int_param_descriptions(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_int_param_groups(const void *lib);
 
@@ -212,7 +212,7 @@ const char *const *verilogae_int_param_groups(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_str_params(const void *lib);
 
@@ -222,7 +222,7 @@ const char *const *verilogae_str_params(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_str_param_units(const void *lib);
 
@@ -232,7 +232,7 @@ const char *const *verilogae_str_param_units(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_str_param_descriptions(const void *lib);
 
@@ -242,7 +242,7 @@ const char *const *verilogae_str_param_descriptions(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_str_param_groups(const void *lib);
 
@@ -252,7 +252,7 @@ const char *const *verilogae_str_param_groups(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 const char *const *verilogae_nodes(const void *lib);
 
@@ -262,7 +262,7 @@ const char *const *verilogae_nodes(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_function_cnt(const void *lib);
 
@@ -272,7 +272,7 @@ uintptr_t verilogae_function_cnt(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_opvars_cnt(const void *lib);
 
@@ -282,7 +282,7 @@ uintptr_t verilogae_opvars_cnt(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_real_param_cnt(const void *lib);
 
@@ -292,7 +292,7 @@ uintptr_t verilogae_real_param_cnt(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_int_param_cnt(const void *lib);
 
@@ -302,7 +302,7 @@ uintptr_t verilogae_int_param_cnt(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_str_param_cnt(const void *lib);
 
@@ -312,7 +312,7 @@ uintptr_t verilogae_str_param_cnt(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_node_cnt(const void *lib);
 
@@ -322,7 +322,7 @@ uintptr_t verilogae_node_cnt(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}params.real
  */
 const char *const *verilogae_real_fun_params(const void *lib, const char *fun);
@@ -333,7 +333,7 @@ const char *const *verilogae_real_fun_params(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}params.integer
  */
 const char *const *verilogae_int_fun_params(const void *lib, const char *fun);
@@ -344,7 +344,7 @@ const char *const *verilogae_int_fun_params(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}params.string
  */
 const char *const *verilogae_str_fun_params(const void *lib, const char *fun);
@@ -355,7 +355,7 @@ const char *const *verilogae_str_fun_params(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}depbreak.real
  */
 const char *const *verilogae_real_fun_depbreak(const void *lib, const char *fun);
@@ -366,7 +366,7 @@ const char *const *verilogae_real_fun_depbreak(const void *lib, const char *fun)
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}depbreak.integer
  */
 const char *const *verilogae_int_fun_depbreak(const void *lib, const char *fun);
@@ -377,7 +377,7 @@ const char *const *verilogae_int_fun_depbreak(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}voltages
  */
 const char *const *verilogae_fun_voltages(const void *lib, const char *fun);
@@ -388,7 +388,7 @@ const char *const *verilogae_fun_voltages(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}currents
  */
 const char *const *verilogae_fun_currents(const void *lib, const char *fun);
@@ -399,7 +399,7 @@ const char *const *verilogae_fun_currents(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}voltages.default
  */
 const double *verilogae_fun_voltage_defaults(const void *lib, const char *fun);
@@ -410,7 +410,7 @@ const double *verilogae_fun_voltage_defaults(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  *`sym_name` must batch the schema fun.{NUM}currents.default
  */
 const double *verilogae_fun_current_defaults(const void *lib, const char *fun);
@@ -421,7 +421,7 @@ const double *verilogae_fun_current_defaults(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_real_fun_param_cnt(const void *lib, const char *fun);
 
@@ -431,7 +431,7 @@ uintptr_t verilogae_real_fun_param_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_int_fun_param_cnt(const void *lib, const char *fun);
 
@@ -441,7 +441,7 @@ uintptr_t verilogae_int_fun_param_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_str_fun_param_cnt(const void *lib, const char *fun);
 
@@ -451,7 +451,7 @@ uintptr_t verilogae_str_fun_param_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_real_fun_depbreak_cnt(const void *lib, const char *fun);
 
@@ -461,7 +461,7 @@ uintptr_t verilogae_real_fun_depbreak_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_int_fun_depbreak_cnt(const void *lib, const char *fun);
 
@@ -471,7 +471,7 @@ uintptr_t verilogae_int_fun_depbreak_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_fun_voltage_cnt(const void *lib, const char *fun);
 
@@ -481,7 +481,7 @@ uintptr_t verilogae_fun_voltage_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_fun_current_cnt(const void *lib, const char *fun);
 
@@ -491,7 +491,7 @@ uintptr_t verilogae_fun_current_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_fun_voltage_default_cnt(const void *lib, const char *fun);
 
@@ -501,7 +501,7 @@ uintptr_t verilogae_fun_voltage_default_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 uintptr_t verilogae_fun_current_default_cnt(const void *lib, const char *fun);
 
@@ -510,7 +510,7 @@ uintptr_t verilogae_fun_current_default_cnt(const void *lib, const char *fun);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 VAEModelcardInit verilogae_init_modelcard(const void *lib);
 
@@ -519,7 +519,7 @@ VAEModelcardInit verilogae_init_modelcard(const void *lib);
  *
  * # Safety
  *
- * `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+ * `lib` must be a valid pointer returned by the `load` functions
  */
 VAEVaeFun verilogae_fun_ptr(const void *lib, const char *fun);
 
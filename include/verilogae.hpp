@@ -80,7 +80,7 @@ extern "C" {
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_functions(const void *lib);
 
 ///This function returns a pointer to the `functions.sym` global
@@ -88,7 +88,7 @@ const char *const *verilogae_functions(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_function_symbols(const void *lib);
 
 ///This function returns a pointer to the `opvars` global
@@ -96,7 +96,7 @@ const char *const *verilogae_function_symbols(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_opvars(const void *lib);
 
 ///This function returns a pointer to the `params.real` global
@@ -104,7 +104,7 @@ const char *const *verilogae_opvars(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_real_params(const void *lib);
 
 ///This function returns a pointer to the `params.unit.real` global
@@ -112,7 +112,7 @@ const char *const *verilogae_real_params(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_real_param_units(const void *lib);
 
 ///This function returns a pointer to the `params.desc.real` global
@@ -120,7 +120,7 @@ const char *const *verilogae_real_param_units(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_real_param_descriptions(const void *lib);
 
 ///This function returns a pointer to the `params.group.real` global
@@ -128,7 +128,7 @@ const char *const *verilogae_real_param_descriptions(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_real_param_groups(const void *lib);
 
 ///This function returns a pointer to the `params.integer` global
@@ -136,7 +136,7 @@ const char *const *verilogae_real_param_groups(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_int_params(const void *lib);
 
 ///This function returns a pointer to the `params.unit.integer` global
@@ -144,7 +144,7 @@ const char *const *verilogae_int_params(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_int_param_units(const void *lib);
 
 ///This function returns a pointer to the `params.desc.integer` global
@@ -152,7 +152,7 @@ const char *const *verilogae_int_param_units(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_int_param_descriptions(const void *lib);
 
 ///This function returns a pointer to the `params.group.integer` global
@@ -160,7 +160,7 @@ const char *const *verilogae_int_param_descriptions(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_int_param_groups(const void *lib);
 
 ///This function returns a pointer to the `params.string` global
@@ -168,7 +168,7 @@ const char *const *verilogae_int_param_groups(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_str_params(const void *lib);
 
 ///This function returns a pointer to the `params.unit.string` global
@@ -176,7 +176,7 @@ const char *const *verilogae_str_params(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_str_param_units(const void *lib);
 
 ///This function returns a pointer to the `params.desc.string` global
@@ -184,7 +184,7 @@ const char *const *verilogae_str_param_units(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_str_param_descriptions(const void *lib);
 
 ///This function returns a pointer to the `params.group.string` global
@@ -192,7 +192,7 @@ const char *const *verilogae_str_param_descriptions(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_str_param_groups(const void *lib);
 
 ///This function returns a pointer to the `nodes` global
@@ -200,7 +200,7 @@ const char *const *verilogae_str_param_groups(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 const char *const *verilogae_nodes(const void *lib);
 
 ///This function returns the value stored in the `functions.cnt` global
@@ -208,7 +208,7 @@ const char *const *verilogae_nodes(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_function_cnt(const void *lib);
 
 ///This function returns the value stored in the `opvars.cnt` global
@@ -216,7 +216,7 @@ uintptr_t verilogae_function_cnt(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_opvars_cnt(const void *lib);
 
 ///This function returns the value stored in the `params.real.cnt` global
@@ -224,7 +224,7 @@ uintptr_t verilogae_opvars_cnt(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_real_param_cnt(const void *lib);
 
 ///This function returns the value stored in the `params.integer.cnt` global
@@ -232,7 +232,7 @@ uintptr_t verilogae_real_param_cnt(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_int_param_cnt(const void *lib);
 
 ///This function returns the value stored in the `params.string.cnt` global
@@ -240,7 +240,7 @@ uintptr_t verilogae_int_param_cnt(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_str_param_cnt(const void *lib);
 
 ///This function returns the value stored in the `nodes.cnt` global
@@ -248,7 +248,7 @@ uintptr_t verilogae_str_param_cnt(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_node_cnt(const void *lib);
 
 ///This function returns a pointer to the `params.real` global
@@ -256,7 +256,7 @@ uintptr_t verilogae_node_cnt(const void *lib);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}params.real
 const char *const *verilogae_real_fun_params(const void *lib, const char *fun);
 
@@ -265,7 +265,7 @@ const char *const *verilogae_real_fun_params(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}params.integer
 const char *const *verilogae_int_fun_params(const void *lib, const char *fun);
 
@@ -274,7 +274,7 @@ const char *const *verilogae_int_fun_params(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}params.string
 const char *const *verilogae_str_fun_params(const void *lib, const char *fun);
 
@@ -283,7 +283,7 @@ const char *const *verilogae_str_fun_params(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}depbreak.real
 const char *const *verilogae_real_fun_depbreak(const void *lib, const char *fun);
 
@@ -292,7 +292,7 @@ const char *const *verilogae_real_fun_depbreak(const void *lib, const char *fun)
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}depbreak.integer
 const char *const *verilogae_int_fun_depbreak(const void *lib, const char *fun);
 
@@ -301,7 +301,7 @@ const char *const *verilogae_int_fun_depbreak(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}voltages
 const char *const *verilogae_fun_voltages(const void *lib, const char *fun);
 
@@ -310,7 +310,7 @@ const char *const *verilogae_fun_voltages(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}currents
 const char *const *verilogae_fun_currents(const void *lib, const char *fun);
 
@@ -319,7 +319,7 @@ const char *const *verilogae_fun_currents(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}voltages.default
 const double *verilogae_fun_voltage_defaults(const void *lib, const char *fun);
 
@@ -328,7 +328,7 @@ const double *verilogae_fun_voltage_defaults(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ///`sym_name` must batch the schema fun.{NUM}currents.default
 const double *verilogae_fun_current_defaults(const void *lib, const char *fun);
 
@@ -337,7 +337,7 @@ const double *verilogae_fun_current_defaults(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_real_fun_param_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `params.integer.cnt` global
@@ -345,7 +345,7 @@ uintptr_t verilogae_real_fun_param_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_int_fun_param_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `params.string.cnt` global
@@ -353,7 +353,7 @@ uintptr_t verilogae_int_fun_param_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_str_fun_param_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `depbreak.real.cnt` global
@@ -361,7 +361,7 @@ uintptr_t verilogae_str_fun_param_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_real_fun_depbreak_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `depbreak.integer.cnt` global
@@ -369,7 +369,7 @@ uintptr_t verilogae_real_fun_depbreak_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_int_fun_depbreak_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `voltages.cnt` global
@@ -377,7 +377,7 @@ uintptr_t verilogae_int_fun_depbreak_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_fun_voltage_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `currents.cnt` global
@@ -385,7 +385,7 @@ uintptr_t verilogae_fun_voltage_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_fun_current_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `voltages.default.cnt` global
@@ -393,7 +393,7 @@ uintptr_t verilogae_fun_current_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_fun_voltage_default_cnt(const void *lib, const char *fun);
 
 ///This function returns a pointer to the `currents.default.cnt` global
@@ -401,21 +401,21 @@ uintptr_t verilogae_fun_voltage_default_cnt(const void *lib, const char *fun);
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 uintptr_t verilogae_fun_current_default_cnt(const void *lib, const char *fun);
 
 /// Obtains a pointer to the modelcard initialization function of a VerilogAE model loaded with `load`.
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 ModelcardInit verilogae_init_modelcard(const void *lib);
 
 /// Obtains a pointer to a model functions of a VerilogAE model loaded with `load`.
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 VaeFun verilogae_fun_ptr(const void *lib, const char *fun);
 
 /// # Safety
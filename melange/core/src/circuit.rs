@@ -31,6 +31,9 @@ pub struct Circuit {
     devices: TiMap<DeviceId, &'static str, DeviceInfo>,
     models: TiVec<ModelId, CircuitModel>,
     instances: TiVec<InstanceId, CircuitInstance>,
+    scopes: TiVec<ScopeId, CircuitScope>,
+    /// Hierarchical names of subcircuit ports, these refer to the node the port is connected to
+    node_aliases: AHashMap<String, Node>,
     pub(crate) namespace: AHashMap<String, NameSpaceEntry>,
     pub(crate) param_assignments: IndexMap<CircuitParam, Expr, ahash::RandomState>,
 }
@@ -38,13 +41,20 @@ pub struct Circuit {
 impl Circuit {
     /// Creates a new empty circuit
     pub fn new(name: String, earena: &mut Arena) -> Circuit {
+        Circuit::new_in_ctx(name, earena.add_ctx())
+    }
+
+    /// Creates a new empty circuit whose parameters are defined in `ctx`
+    pub(crate) fn new_in_ctx(name: String, ctx: CircuitParamCtx) -> Circuit {
         let mut circ = Circuit {
             name,
-            ctx: earena.add_ctx(),
+            ctx,
             nodes: TiSet::with_capacity(16),
             devices: TiMap::with_capacity(32),
             models: TiVec::with_capacity(16),
             instances: TiVec::with_capacity(16),
+            scopes: TiVec::new(),
+            node_aliases: AHashMap::new(),
             namespace: AHashMap::with_capacity(64),
             param_assignments: IndexMap::default(),
         };
@@ -96,6 +106,33 @@ impl Circuit {
         self.instances.len() as u32
     }
 
+    pub fn scopes(&self) -> impl Iterator<Item = ScopeId> {
+        self.scopes.keys()
+    }
+
+    /// Creates a new [`CircuitScope`] for a subcircuit instance.
+    ///
+    /// # Parameters
+    ///
+    /// * **`name`** - the hierarchical name of the subcircuit instance
+    /// * **`parent`** - the scope the subcircuit is instantiated in (`None` for the top level)
+    /// * **`parameters`** - the parameters of the subcircuit and their default values
+    /// * **`overrides`** - parameter values specified by the subcircuit instance
+    ///
+    /// # Returns
+    ///
+    /// A [`ScopeId`] that uniquely identifies the created scope within the circuit.
+    /// Models and instances are placed into the scope by setting their `scope` field.
+    pub fn new_scope(
+        &mut self,
+        name: String,
+        parent: Option<ScopeId>,
+        parameters: Vec<(CircuitParam, Expr)>,
+        overrides: Vec<(CircuitParam, Expr)>,
+    ) -> ScopeId {
+        self.scopes.push_and_get_key(CircuitScope { name, parent, parameters, overrides })
+    }
+
     /// Inserts an item into the namespace
     ///
     /// # Returns
@@ -145,6 +182,7 @@ impl Circuit {
             src: CircuitModelSrc::Explicit(name.clone()),
             device,
             parameters: ParamList::default(),
+            scope: None,
         };
         let id = self.models.push_and_get_key(model);
         self.insert_into_namespace(name, id)?;
@@ -192,6 +230,7 @@ impl Circuit {
             src: CircuitModelSrc::Implicit(instance_id),
             device,
             parameters: ParamList::default(),
+            scope: None,
         };
         let model = self.models.push_and_get_key(model);
         let id = self.new_model_instance(name, model, terminal_connections)?;
@@ -223,6 +262,7 @@ impl Circuit {
             model,
             parameters: ParamList::default(),
             connections: terminal_connections,
+            scope: None,
         };
         let id = self.instances.push_and_get_key(instance);
         self.insert_into_namespace(name, id)?;
@@ -237,18 +277,60 @@ impl Circuit {
     ///
     /// If no such node exists, a new node is created and returned
     pub fn node(&mut self, name: String) -> Node {
+        if let Some(&node) = self.node_aliases.get(&name) {
+            return node;
+        }
         self.nodes.ensure(name).0
     }
 
     /// Lookup a node by name
     ///
+    /// Nodes inside subcircuits are named by their hierarchical path (`x1.x2.out`).
+    /// The path of a subcircuit port resolves to the node the port is connected to.
+    ///
     /// # Returns
     ///
     /// The Node in this circuit that has the name `name`
     ///
     /// If no such node exists returns `None`
     pub fn lookup_node(&self, name: &str) -> Option<Node> {
-        self.nodes.index(name)
+        self.nodes.index(name).or_else(|| self.node_aliases.get(name).copied())
+    }
+
+    /// Lookup an instance by its (hierarchical) name
+    ///
+    /// # Returns
+    ///
+    /// The instance in this circuit that has the name `name`
+    ///
+    /// If no such instance exists returns `None`
+    pub fn lookup_instance(&self, name: &str) -> Option<InstanceId> {
+        match self.namespace.get(name) {
+            Some(&NameSpaceEntry::Instance(inst)) => Some(inst),
+            _ => None,
+        }
+    }
+
+    /// Lookup an explicitly created model by its name
+    ///
+    /// # Returns
+    ///
+    /// The model in this circuit that has the name `name`
+    ///
+    /// If no such model exists returns `None`
+    pub fn lookup_model(&self, name: &str) -> Option<ModelId> {
+        match self.namespace.get(name) {
+            Some(&NameSpaceEntry::Model(model)) => Some(model),
+            _ => None,
+        }
+    }
+
+    /// Makes `node` available under the (hierarchical) name `name`.
+    /// Used for the ports of subcircuit instances.
+    pub fn alias_node(&mut self, name: String, node: Node) {
+        if self.nodes.index(&name).is_none() {
+            self.node_aliases.insert(name, node);
+        }
     }
 
     /// returns the name of a node
@@ -475,7 +557,7 @@ impl Circuit {
     ///
     /// The parameters index and an expression that can be used to read the parameter.
     /// `None` if no parameter `name` was found
-    pub fn lookup_param(&mut self, name: &str, earena: &Arena) -> Option<(CircuitParam, Expr)> {
+    pub fn lookup_param(&self, name: &str, earena: &Arena) -> Option<(CircuitParam, Expr)> {
         earena.lookup_param_by_name(self.ctx, name)
     }
 }
@@ -496,6 +578,14 @@ impl Index<InstanceId> for Circuit {
     }
 }
 
+impl Index<ScopeId> for Circuit {
+    type Output = CircuitScope;
+
+    fn index(&self, scope: ScopeId) -> &CircuitScope {
+        &self.scopes[scope]
+    }
+}
+
 impl Index<ModelId> for Circuit {
     type Output = CircuitModel;
 
@@ -544,6 +634,11 @@ pub struct ModelId(u32);
 impl_debug_display!(match ModelId{ ModelId(id) => "model{:?}", id;});
 impl_idx_from!(ModelId(u32));
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScopeId(u32);
+impl_debug_display!(match ScopeId{ ScopeId(id) => "scope{:?}", id;});
+impl_idx_from!(ScopeId(u32));
+
 /// A list of parameter values of a model or instance
 pub type ParamList = Vec<(ParamId, Expr)>;
 
@@ -600,6 +695,10 @@ pub struct CircuitModel {
     /// Initializing default values is done by the device implementation in
     /// [`new_model`](crate::devices::DeviceImpl::new_model)
     pub parameters: ParamList,
+
+    /// The subcircuit instance this model was defined in (`None` for the top level).
+    /// Parameters are evaluated within this scope.
+    pub scope: Option<ScopeId>,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -637,4 +736,30 @@ pub struct CircuitInstance {
     /// While linear devices (vsource, resistor, isource) will emit an error, Verilog-A devices
     /// usually handle this case using the `$terminal_connected` function.
     pub connections: Vec<Node>,
+
+    /// The subcircuit instance this instance belongs to (`None` for the top level).
+    /// Parameters are evaluated within this scope.
+    pub scope: Option<ScopeId>,
+}
+
+/// A subcircuit instance in a [`Circuit`].
+/// Subcircuits are flattened during elaboration: their models, instances and nodes are added
+/// to the circuit with hierarchical names (`x1.r1`). The scope only retains the values of the
+/// subcircuit parameters which differ between instances of the same subcircuit.
+#[derive(PartialEq, Clone, Debug)]
+#[non_exhaustive]
+pub struct CircuitScope {
+    /// The hierarchical name of the subcircuit instance
+    pub name: String,
+
+    /// The scope the subcircuit was instantiated in (`None` for the top level)
+    pub parent: Option<ScopeId>,
+
+    /// The parameters of the subcircuit and their default values in the order they were
+    /// declared. Default values are evaluated within this scope.
+    pub parameters: Vec<(CircuitParam, Expr)>,
+
+    /// Parameter values specified by the subcircuit instance.
+    /// These are evaluated within the parent scope and take precedence over the defaults.
+    pub overrides: Vec<(CircuitParam, Expr)>,
 }
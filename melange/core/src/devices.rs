@@ -9,13 +9,26 @@ use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::TiSlice;
 
 use crate::circuit::Node;
+use crate::devices::capacitor::Capacitor;
+use crate::devices::controlled::ControlledSrc;
+use crate::devices::inductor::Inductor;
+use crate::devices::isource::CurrentSrc;
+use crate::devices::mutual_inductor::MutualInductor;
 pub use crate::devices::params::{DeviceParams, ParamId, Type};
 use crate::devices::resistor::Resistor;
+use crate::devices::switch::Switch;
 use crate::devices::vsource::VoltageSrc;
 use crate::simulation::{MatrixEntryIter, SimBuilder, SimInfo};
 
+mod capacitor;
+mod controlled;
+mod inductor;
+mod isource;
+mod mutual_inductor;
 mod params;
 mod resistor;
+mod source;
+mod switch;
 mod vsource;
 
 pub trait DeviceImpl {
@@ -65,11 +78,48 @@ impl MatrixEntry<'_> {
     }
 }
 
+/// A noise source of a device instance: a current source between `hi` and `lo` whose power
+/// spectral density is computed by [`InstanceImpl::load_noise`].
+#[derive(Debug, Clone)]
+pub struct NoiseSource {
+    pub name: &'static str,
+    pub hi: Node,
+    pub lo: Node,
+}
+
 pub fn update_matrix_entry(dst: &Cell<f64>, val: f64) {
     let res = dst.get() + val;
     dst.set(res)
 }
 
+/// Loads the conductance (or capacitance) `val` between two nodes. The first four `entries`
+/// must be `(anode, anode)`, `(anode, cathode)`, `(cathode, anode)` and `(cathode, cathode)`.
+///
+/// # Safety
+///
+/// The entries must point to a valid matrix.
+pub(crate) unsafe fn load_two_terminal(entries: &[NonNull<Cell<f64>>], val: f64) {
+    update_matrix_entry(entries[0].as_ref(), val);
+    update_matrix_entry(entries[1].as_ref(), -val);
+    update_matrix_entry(entries[2].as_ref(), -val);
+    update_matrix_entry(entries[3].as_ref(), val);
+}
+
+/// Loads the incidence of a branch current between two nodes: the current flows out of the
+/// anode into the cathode and the branch equation contains `V(anode) - V(cathode)`. The first
+/// four `entries` must be `(anode, branch)`, `(branch, anode)`, `(cathode, branch)` and
+/// `(branch, cathode)`.
+///
+/// # Safety
+///
+/// The entries must point to a valid matrix.
+pub(crate) unsafe fn load_branch_incidence(entries: &[NonNull<Cell<f64>>]) {
+    update_matrix_entry(entries[0].as_ref(), 1.0);
+    update_matrix_entry(entries[1].as_ref(), 1.0);
+    update_matrix_entry(entries[2].as_ref(), -1.0);
+    update_matrix_entry(entries[3].as_ref(), -1.0);
+}
+
 pub trait InstanceImpl {
     fn process_params(
         &mut self,
@@ -94,6 +144,13 @@ pub trait InstanceImpl {
     unsafe fn load_matrix_resist(&self);
     unsafe fn load_matrix_react(&self, alpha: f64);
 
+    /// Loads the resistive jacobian plus the reactive jacobian scaled by `alpha` into the
+    /// (real) matrix. Used during transient analysis where `alpha` is the coefficient of the
+    /// integration method. Only devices without reactive contributions can rely on the default.
+    unsafe fn load_matrix_tran(&self, _alpha: f64) {
+        self.load_matrix_resist()
+    }
+
     fn load_residual_react(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>);
     fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>);
     fn load_ac_residual(
@@ -103,9 +160,47 @@ pub trait InstanceImpl {
     ) {
     }
 
+    /// Loads the correction of the resistive residual required when `$limit` changed the
+    /// voltages the device was evaluated at (see [`is_limited`](InstanceImpl::is_limited)).
+    fn load_limit_rhs_resist(&self, _rhs: &mut TiSlice<Node, f64>) {}
+    /// Loads the correction of the reactive residual, see
+    /// [`load_limit_rhs_resist`](InstanceImpl::load_limit_rhs_resist).
+    fn load_limit_rhs_react(&self, _rhs: &mut TiSlice<Node, f64>) {}
+
+    /// Whether `$limit` changed any voltage during the last [`eval`](InstanceImpl::eval).
+    /// Newton's method can not converge while limiting is active.
+    fn is_limited(&self) -> bool {
+        false
+    }
+
     fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]);
     fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]);
     fn load_ac_lead_current(&self, _ac_solve: &TiSlice<Node, Complex64>, _dst: &mut [Complex64]) {}
+
+    /// The first time after `time` at which the device behaves discontinuously (for example
+    /// a corner of a piecewise linear source). Transient analysis places a time point there.
+    fn next_breakpoint(&self, _time: f64) -> Option<f64> {
+        None
+    }
+
+    /// The largest time step the device allows after the last [`eval`](InstanceImpl::eval).
+    fn bound_step(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    /// Called during transient analysis once a time point was accepted.
+    fn accept_step(&mut self) {}
+
+    /// The noise sources of the instance, only valid after
+    /// [`process_params`](InstanceImpl::process_params).
+    fn noise_sources(&self) -> Vec<NoiseSource> {
+        Vec::new()
+    }
+
+    /// Writes the power spectral density at `freq` of each source returned by
+    /// [`noise_sources`](InstanceImpl::noise_sources) into `dst`. The instance must have been
+    /// evaluated with `CALC_NOISE` before.
+    fn load_noise(&self, _freq: f64, _dst: &mut [f64]) {}
 }
 
 pub struct DeviceInfo {
@@ -117,5 +212,15 @@ pub struct DeviceInfo {
 }
 
 pub(crate) fn default_devices() -> impl Iterator<Item = Box<dyn DeviceImpl>> {
-    [VoltageSrc::init_dev(), Resistor::init_dev()].into_iter()
+    [
+        VoltageSrc::init_dev(),
+        CurrentSrc::init_dev(),
+        Resistor::init_dev(),
+        Capacitor::init_dev(),
+        Inductor::init_dev(),
+        MutualInductor::init_dev(),
+        Switch::init_dev(),
+    ]
+    .into_iter()
+    .chain(ControlledSrc::init_devs())
 }
@@ -0,0 +1,180 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{load_two_terminal, DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::simulation::{MatrixEntryIter, SimBuilder};
+
+pub struct Capacitor;
+
+impl Capacitor {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+impl DeviceImpl for Capacitor {
+    fn get_name(&self) -> &'static str {
+        "capacitor"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["A", "C"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("c", Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(CapacitorModel::default())
+    }
+}
+
+const C: ParamId = ParamId(0u32);
+
+#[derive(Default)]
+struct CapacitorModel {
+    cap: Cell<Option<f64>>,
+}
+
+impl ModelImpl for CapacitorModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        match param {
+            C => self.cap.set(Some(val)),
+            _ => unreachable!("capacitor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn InstanceImpl> {
+        Box::new(CapacitorInstance {
+            model: self,
+            anode: Node::GROUND,
+            cathode: Node::GROUND,
+            cap_param: None,
+            cap: 0.0,
+            omega: Cell::new(0.0),
+            resist_entries: [NonNull::dangling(); 4],
+            react_entries: [NonNull::dangling(); 4],
+        })
+    }
+}
+
+struct CapacitorInstance {
+    model: Rc<CapacitorModel>,
+    anode: Node,
+    cathode: Node,
+    /// The capacitance specified as instance parameter (overwrites the model parameter)
+    cap_param: Option<f64>,
+    cap: f64,
+    /// The angular frequency of the last ac analysis, required for ac lead currents
+    omega: Cell<f64>,
+    resist_entries: [NonNull<Cell<f64>>; 4],
+    react_entries: [NonNull<Cell<f64>>; 4],
+}
+
+impl CapacitorInstance {
+    fn charge(&self, solve: &TiSlice<Node, f64>) -> f64 {
+        self.cap * (solve[self.anode] - solve[self.cathode])
+    }
+}
+
+impl InstanceImpl for CapacitorInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        sim_builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let &[anode, cathode] = terminals else {
+            bail!("capacitor: all terminals must be connected")
+        };
+
+        self.anode = anode;
+        self.cathode = cathode;
+
+        // IMPORTANT: keep the order in sync with load_two_terminal
+        sim_builder.ensure_matrix_entry(anode, anode);
+        sim_builder.ensure_matrix_entry(anode, cathode);
+        sim_builder.ensure_matrix_entry(cathode, anode);
+        sim_builder.ensure_matrix_entry(cathode, cathode);
+
+        match self.cap_param.or(self.model.cap.get()) {
+            Some(cap) => self.cap = cap,
+            None => bail!("capacitor: capacitance must be set"),
+        }
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            C => self.cap_param = Some(val),
+            _ => unreachable!("capacitor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        for (i, entry) in matrix_entries.enumerate() {
+            self.resist_entries[i] = entry.resist();
+            self.react_entries[i] = entry.react();
+        }
+    }
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<()> {
+        Ok(())
+    }
+
+    unsafe fn load_matrix_resist(&self) {}
+
+    unsafe fn load_matrix_react(&self, alpha: f64) {
+        self.omega.set(alpha);
+        load_two_terminal(&self.react_entries, alpha * self.cap);
+    }
+
+    unsafe fn load_matrix_tran(&self, alpha: f64) {
+        load_two_terminal(&self.resist_entries, alpha * self.cap);
+    }
+
+    fn load_residual_react(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        let charge = self.charge(prev_solve);
+        rhs[self.anode] += charge;
+        rhs[self.cathode] -= charge;
+    }
+
+    fn load_residual_resist(
+        &self,
+        _prev_solve: &TiSlice<Node, f64>,
+        _rhs: &mut TiSlice<Node, f64>,
+    ) {
+    }
+
+    fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = 0.0;
+        dst[1] = 0.0;
+    }
+
+    fn load_lead_current_react(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        let charge = self.charge(dc_solve);
+        dst[0] = charge;
+        dst[1] = -charge;
+    }
+
+    fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        let voltage = ac_solve[self.anode] - ac_solve[self.cathode];
+        let current = Complex64::new(0.0, self.omega.get() * self.cap) * voltage;
+        dst[0] = current;
+        dst[1] = -current;
+    }
+}
@@ -0,0 +1,343 @@
+use std::cell::{Cell, RefCell};
+use std::ops::{Mul, Sub};
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use stdx::iter::zip;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{
+    load_branch_incidence, load_two_terminal, update_matrix_entry, DeviceImpl, DeviceParams,
+    InstanceImpl, Type,
+};
+use crate::simulation::{MatrixEntryIter, SimBuilder};
+
+/// The devices whose current is available as a branch unknown and can therefore control a
+/// current controlled source.
+const BRANCH_DEVICES: [&str; 4] = ["vsource", "inductor", "vcvs", "ccvs"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Kind {
+    /// voltage controlled voltage source
+    Vcvs,
+    /// voltage controlled current source
+    Vccs,
+    /// current controlled current source
+    Cccs,
+    /// current controlled voltage source
+    Ccvs,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Vcvs => "vcvs",
+            Kind::Vccs => "vccs",
+            Kind::Cccs => "cccs",
+            Kind::Ccvs => "ccvs",
+        }
+    }
+
+    fn is_current_controlled(self) -> bool {
+        matches!(self, Kind::Cccs | Kind::Ccvs)
+    }
+
+    fn has_branch(self) -> bool {
+        matches!(self, Kind::Vcvs | Kind::Ccvs)
+    }
+}
+
+/// A linear controlled source. Voltage controlled sources sense `V(CP, CN)`, current
+/// controlled sources sense the current through the instance `ctrl`. Output currents flow from
+/// `P` through the source to `N`.
+pub struct ControlledSrc {
+    kind: Kind,
+}
+
+impl ControlledSrc {
+    pub fn init_devs() -> impl Iterator<Item = Box<dyn DeviceImpl>> {
+        [Kind::Vcvs, Kind::Vccs, Kind::Cccs, Kind::Ccvs]
+            .into_iter()
+            .map(|kind| Box::new(ControlledSrc { kind }) as Box<dyn DeviceImpl>)
+    }
+}
+
+const GAIN: ParamId = ParamId(0u32);
+const CTRL: ParamId = ParamId(1u32);
+
+// entries shared by all sources with a branch (vcvs/ccvs), see load_branch_incidence
+const MATRIX_BR_CTRL: usize = 4;
+
+impl DeviceImpl for ControlledSrc {
+    fn get_name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        if self.kind.is_current_controlled() {
+            vec!["P", "N"].into_boxed_slice()
+        } else {
+            vec!["P", "N", "CP", "CN"].into_boxed_slice()
+        }
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("gain", Type::Real);
+        if self.kind.is_current_controlled() {
+            res.insert_instance_param("ctrl", Type::String);
+        }
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(ControlledSrcModel {
+            kind: self.kind,
+            gain: Cell::new(1.0),
+            ctrl: RefCell::new(None),
+        })
+    }
+}
+
+struct ControlledSrcModel {
+    kind: Kind,
+    gain: Cell<f64>,
+    ctrl: RefCell<Option<String>>,
+}
+
+impl ModelImpl for ControlledSrcModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        match param {
+            GAIN => self.gain.set(val),
+            _ => unreachable!("{}: unknown numeric parameter {param:?}", self.kind.name()),
+        };
+    }
+
+    fn set_str_param(&self, param: ParamId, val: &str) {
+        match param {
+            CTRL => *self.ctrl.borrow_mut() = Some(val.to_owned()),
+            _ => unreachable!("{}: unknown str parameter {param:?}", self.kind.name()),
+        }
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn InstanceImpl> {
+        Box::new(ControlledSrcInstance {
+            kind: self.kind,
+            model: self,
+            gain_param: None,
+            ctrl_param: None,
+            gain: 0.0,
+            pos: Node::GROUND,
+            neg: Node::GROUND,
+            ctrl_pos: Node::GROUND,
+            ctrl_neg: Node::GROUND,
+            ctrl_branch: Node::GROUND,
+            branch: Node::GROUND,
+            matrix_entries: [NonNull::dangling(); 6],
+        })
+    }
+}
+
+struct ControlledSrcInstance {
+    kind: Kind,
+    model: Rc<ControlledSrcModel>,
+    /// The parameters specified for the instance (overwrite the model parameters)
+    gain_param: Option<f64>,
+    ctrl_param: Option<String>,
+    gain: f64,
+    pos: Node,
+    neg: Node,
+    ctrl_pos: Node,
+    ctrl_neg: Node,
+    /// The current through the controlling instance of current controlled sources
+    ctrl_branch: Node,
+    /// The output current of sources with a voltage output
+    branch: Node,
+    matrix_entries: [NonNull<Cell<f64>>; 6],
+}
+
+impl ControlledSrcInstance {
+    /// The controlling quantity: a voltage for voltage controlled and a current for current
+    /// controlled sources
+    fn ctrl<T>(&self, solve: &TiSlice<Node, T>) -> T
+    where
+        T: Copy + Sub<Output = T>,
+    {
+        if self.kind.is_current_controlled() {
+            solve[self.ctrl_branch]
+        } else {
+            solve[self.ctrl_pos] - solve[self.ctrl_neg]
+        }
+    }
+
+    /// The current flowing from `P` through the source to `N`
+    fn current<T>(&self, solve: &TiSlice<Node, T>) -> T
+    where
+        T: Copy + Sub<Output = T> + Mul<f64, Output = T>,
+    {
+        if self.kind.has_branch() {
+            solve[self.branch]
+        } else {
+            self.ctrl(solve) * self.gain
+        }
+    }
+
+    fn resolve_ctrl(&self, builder: &mut SimBuilder) -> Result<Node> {
+        let name = self.kind.name();
+        let ctrl = self.ctrl_param.clone().or_else(|| self.model.ctrl.borrow().clone());
+        let Some(ctrl) = ctrl else { bail!("{name}: the controlling instance (ctrl) must be set") };
+        let Some(inst) = builder.lookup_instance(&ctrl) else {
+            bail!("{name}: controlling instance '{ctrl}' does not exist")
+        };
+        let device = builder.device_name(inst);
+        if !BRANCH_DEVICES.contains(&device) {
+            bail!(
+                "{name}: the current through '{ctrl}' is not available, \
+                 a {device} can not control a current controlled source"
+            )
+        }
+        Ok(builder.instance_branch(inst, "branch"))
+    }
+}
+
+impl InstanceImpl for ControlledSrcInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let name = self.kind.name();
+        match *terminals {
+            [pos, neg] if self.kind.is_current_controlled() => {
+                self.pos = pos;
+                self.neg = neg;
+                self.ctrl_branch = self.resolve_ctrl(builder)?;
+            }
+            [pos, neg, ctrl_pos, ctrl_neg] if !self.kind.is_current_controlled() => {
+                self.pos = pos;
+                self.neg = neg;
+                self.ctrl_pos = ctrl_pos;
+                self.ctrl_neg = ctrl_neg;
+            }
+            _ => bail!("{name}: all terminals must be connected"),
+        }
+        self.gain = self.gain_param.unwrap_or(self.model.gain.get());
+
+        let (pos, neg) = (self.pos, self.neg);
+        // IMPORTANT: keep the order here in sync with the load_matrix_resist
+        match self.kind {
+            Kind::Vcvs | Kind::Ccvs => {
+                let branch = builder.new_internal_branch("branch");
+                self.branch = branch;
+                builder.ensure_matrix_entry(pos, branch);
+                builder.ensure_matrix_entry(branch, pos);
+                builder.ensure_matrix_entry(neg, branch);
+                builder.ensure_matrix_entry(branch, neg);
+                if self.kind == Kind::Vcvs {
+                    builder.ensure_matrix_entry(branch, self.ctrl_pos);
+                    builder.ensure_matrix_entry(branch, self.ctrl_neg);
+                } else {
+                    builder.ensure_matrix_entry(branch, self.ctrl_branch);
+                }
+            }
+            Kind::Vccs => {
+                builder.ensure_matrix_entry(pos, self.ctrl_pos);
+                builder.ensure_matrix_entry(pos, self.ctrl_neg);
+                builder.ensure_matrix_entry(neg, self.ctrl_pos);
+                builder.ensure_matrix_entry(neg, self.ctrl_neg);
+            }
+            Kind::Cccs => {
+                builder.ensure_matrix_entry(pos, self.ctrl_branch);
+                builder.ensure_matrix_entry(neg, self.ctrl_branch);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            GAIN => self.gain_param = Some(val),
+            _ => unreachable!("{}: unknown numeric parameter {param:?}", self.kind.name()),
+        };
+    }
+
+    fn set_str_param(&mut self, param: ParamId, val: &str) {
+        match param {
+            CTRL => self.ctrl_param = Some(val.to_owned()),
+            _ => unreachable!("{}: unknown str parameter {param:?}", self.kind.name()),
+        }
+    }
+
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        for (dst, entry) in zip(&mut self.matrix_entries, matrix_entries) {
+            *dst = entry.resist();
+        }
+    }
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<()> {
+        Ok(())
+    }
+
+    unsafe fn load_matrix_resist(&self) {
+        let entries = &self.matrix_entries;
+        match self.kind {
+            Kind::Vcvs => {
+                load_branch_incidence(entries);
+                update_matrix_entry(entries[MATRIX_BR_CTRL].as_ref(), -self.gain);
+                update_matrix_entry(entries[MATRIX_BR_CTRL + 1].as_ref(), self.gain);
+            }
+            Kind::Ccvs => {
+                load_branch_incidence(entries);
+                update_matrix_entry(entries[MATRIX_BR_CTRL].as_ref(), -self.gain);
+            }
+            Kind::Vccs => load_two_terminal(entries, self.gain),
+            Kind::Cccs => {
+                update_matrix_entry(entries[0].as_ref(), self.gain);
+                update_matrix_entry(entries[1].as_ref(), -self.gain);
+            }
+        }
+    }
+
+    unsafe fn load_matrix_react(&self, _alpha: f64) {}
+
+    fn load_residual_react(&self, _prev_solve: &TiSlice<Node, f64>, _rhs: &mut TiSlice<Node, f64>) {
+    }
+
+    fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        let current = self.current(prev_solve);
+        rhs[self.pos] += current;
+        rhs[self.neg] -= current;
+        if self.kind.has_branch() {
+            rhs[self.branch] += prev_solve[self.pos] - prev_solve[self.neg];
+            rhs[self.branch] -= self.gain * self.ctrl(prev_solve);
+        }
+    }
+
+    fn load_lead_current_resist(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        let current = self.current(dc_solve);
+        dst[0] = current;
+        dst[1] = -current;
+        dst[2..].fill(0.0);
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst.fill(0.0);
+    }
+
+    fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        let current = self.current(ac_solve);
+        dst[0] = current;
+        dst[1] = -current;
+        dst[2..].fill(Complex64::default());
+    }
+}
@@ -0,0 +1,178 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{
+    load_branch_incidence, update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type,
+};
+use crate::simulation::{MatrixEntryIter, SimBuilder};
+
+/// An inductor is modeled with its current as an additional unknown (branch) so that it
+/// becomes a short at DC: `V(A, C) - d/dt (L * I) = 0`
+pub struct Inductor;
+
+impl Inductor {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+impl DeviceImpl for Inductor {
+    fn get_name(&self) -> &'static str {
+        "inductor"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["A", "C"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("l", Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(InductorModel::default())
+    }
+}
+
+const L: ParamId = ParamId(0u32);
+
+const MATRIX_BR_BR: usize = 4;
+
+#[derive(Default)]
+struct InductorModel {
+    ind: Cell<Option<f64>>,
+}
+
+impl ModelImpl for InductorModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        match param {
+            L => self.ind.set(Some(val)),
+            _ => unreachable!("inductor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn InstanceImpl> {
+        Box::new(InductorInstance {
+            model: self,
+            anode: Node::GROUND,
+            cathode: Node::GROUND,
+            branch: Node::GROUND,
+            ind_param: None,
+            ind: 0.0,
+            resist_entries: [NonNull::dangling(); 5],
+            react_entries: [NonNull::dangling(); 5],
+        })
+    }
+}
+
+struct InductorInstance {
+    model: Rc<InductorModel>,
+    anode: Node,
+    cathode: Node,
+    branch: Node,
+    /// The inductance specified as instance parameter (overwrites the model parameter)
+    ind_param: Option<f64>,
+    ind: f64,
+    resist_entries: [NonNull<Cell<f64>>; 5],
+    react_entries: [NonNull<Cell<f64>>; 5],
+}
+
+impl InstanceImpl for InductorInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        sim_builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let &[anode, cathode] = terminals else {
+            bail!("inductor: all terminals must be connected")
+        };
+
+        let branch = sim_builder.new_internal_branch("branch");
+        self.anode = anode;
+        self.cathode = cathode;
+        self.branch = branch;
+
+        // IMPORTANT: keep the order in sync with load_branch_incidence and the MATRIX_ indices
+        sim_builder.ensure_matrix_entry(anode, branch);
+        sim_builder.ensure_matrix_entry(branch, anode);
+        sim_builder.ensure_matrix_entry(cathode, branch);
+        sim_builder.ensure_matrix_entry(branch, cathode);
+        sim_builder.ensure_matrix_entry(branch, branch);
+
+        match self.ind_param.or(self.model.ind.get()) {
+            Some(ind) => self.ind = ind,
+            None => bail!("inductor: inductance must be set"),
+        }
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            L => self.ind_param = Some(val),
+            _ => unreachable!("inductor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        for (i, entry) in matrix_entries.enumerate() {
+            self.resist_entries[i] = entry.resist();
+            self.react_entries[i] = entry.react();
+        }
+    }
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<()> {
+        Ok(())
+    }
+
+    unsafe fn load_matrix_resist(&self) {
+        load_branch_incidence(&self.resist_entries);
+    }
+
+    unsafe fn load_matrix_react(&self, alpha: f64) {
+        update_matrix_entry(self.react_entries[MATRIX_BR_BR].as_ref(), -alpha * self.ind);
+    }
+
+    unsafe fn load_matrix_tran(&self, alpha: f64) {
+        load_branch_incidence(&self.resist_entries);
+        update_matrix_entry(self.resist_entries[MATRIX_BR_BR].as_ref(), -alpha * self.ind);
+    }
+
+    fn load_residual_react(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        rhs[self.branch] -= self.ind * prev_solve[self.branch];
+    }
+
+    fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        rhs[self.anode] += prev_solve[self.branch];
+        rhs[self.cathode] -= prev_solve[self.branch];
+        rhs[self.branch] += prev_solve[self.anode] - prev_solve[self.cathode];
+    }
+
+    fn load_lead_current_resist(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = dc_solve[self.branch];
+        dst[1] = -dc_solve[self.branch];
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = 0.0;
+        dst[1] = 0.0;
+    }
+
+    fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        dst[0] = ac_solve[self.branch];
+        dst[1] = -ac_solve[self.branch];
+    }
+}
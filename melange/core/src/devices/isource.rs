@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::source::{self, Source, SourceParams, Waveform};
+use crate::devices::{DeviceImpl, DeviceParams, InstanceImpl};
+use crate::simulation::{MatrixEntryIter, SimBuilder};
+
+/// An independent current source, the current flows from `A` through the source to `C`. See the
+/// `source` module for the supported waveforms.
+pub struct CurrentSrc;
+
+impl CurrentSrc {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+impl DeviceImpl for CurrentSrc {
+    fn get_name(&self) -> &'static str {
+        "isource"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["A", "C"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        source::insert_params(&mut res);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(CurrentSrcModel::default())
+    }
+}
+
+#[derive(Default)]
+struct CurrentSrcModel {
+    params: RefCell<SourceParams>,
+}
+
+impl ModelImpl for CurrentSrcModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        self.params.borrow_mut().set_real(param, val)
+    }
+
+    fn set_str_param(&self, param: ParamId, val: &str) {
+        self.params.borrow_mut().set_str(param, val)
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn InstanceImpl> {
+        Box::new(CurrentSrcInstance {
+            model: self,
+            anode: Node::GROUND,
+            cathode: Node::GROUND,
+            params: SourceParams::default(),
+            src: Source { dc: 0.0, ac: Complex64::default(), waveform: Waveform::Const(0.0) },
+            val: 0.0,
+        })
+    }
+}
+
+struct CurrentSrcInstance {
+    model: Rc<CurrentSrcModel>,
+    anode: Node,
+    cathode: Node,
+    /// The parameters specified for the instance (overwrite the model parameters)
+    params: SourceParams,
+    src: Source,
+    /// The current of the source during the last evaluation
+    val: f64,
+}
+
+impl InstanceImpl for CurrentSrcInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        _builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let &[anode, cathode] = terminals else {
+            bail!("isource: all terminals must be connected")
+        };
+        self.anode = anode;
+        self.cathode = cathode;
+
+        self.src = self.params.build(&self.model.params.borrow(), "isource")?;
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        self.params.set_real(param, val)
+    }
+
+    fn set_str_param(&mut self, param: ParamId, val: &str) {
+        self.params.set_str(param, val)
+    }
+
+    fn populate_matrix_ptrs(&mut self, _matrix_entries: MatrixEntryIter) {}
+
+    fn eval(&mut self, sim_info: SimInfo<'_>) -> Result<()> {
+        self.val = self.src.eval(sim_info);
+        Ok(())
+    }
+
+    fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        self.src.waveform.next_breakpoint(time)
+    }
+
+    unsafe fn load_matrix_resist(&self) {}
+
+    unsafe fn load_matrix_react(&self, _alpha: f64) {}
+
+    fn load_residual_react(&self, _prev_solve: &TiSlice<Node, f64>, _rhs: &mut TiSlice<Node, f64>) {
+    }
+
+    fn load_residual_resist(&self, _prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        rhs[self.anode] += self.val;
+        rhs[self.cathode] -= self.val;
+    }
+
+    fn load_ac_residual(
+        &self,
+        _prev_solve: &TiSlice<Node, f64>,
+        rhs: &mut TiSlice<Node, Complex64>,
+    ) {
+        rhs[self.anode] -= self.src.ac;
+        rhs[self.cathode] += self.src.ac;
+    }
+
+    fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = self.val;
+        dst[1] = -self.val;
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = 0.0;
+        dst[1] = 0.0;
+    }
+
+    fn load_ac_lead_current(&self, _ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        dst[0] = self.src.ac;
+        dst[1] = -self.src.ac;
+    }
+}
@@ -0,0 +1,240 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use stdx::iter::zip;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{
+    load_branch_incidence, update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type,
+};
+use crate::simulation::{MatrixEntryIter, SimBuilder};
+
+/// Two magnetically coupled inductors `L1` between `P1` and `N1` and `L2` between `P2` and
+/// `N2` with the mutual inductance `M = K * sqrt(L1 * L2)`. Like the inductor the currents
+/// through both windings are additional unknowns (branches).
+pub struct MutualInductor;
+
+impl MutualInductor {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+impl DeviceImpl for MutualInductor {
+    fn get_name(&self) -> &'static str {
+        "mutual_inductor"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["P1", "N1", "P2", "N2"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("l1", Type::Real);
+        res.insert_instance_param("l2", Type::Real);
+        res.insert_instance_param("k", Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(MutualInductorModel::default())
+    }
+}
+
+const L1: ParamId = ParamId(0u32);
+const L2: ParamId = ParamId(1u32);
+const K: ParamId = ParamId(2u32);
+
+const MATRIX_WINDING1: usize = 0;
+const MATRIX_WINDING2: usize = 4;
+const MATRIX_BR1_BR1: usize = 8;
+const MATRIX_BR1_BR2: usize = 9;
+const MATRIX_BR2_BR1: usize = 10;
+const MATRIX_BR2_BR2: usize = 11;
+
+#[derive(Default)]
+struct MutualInductorModel {
+    params: [Cell<Option<f64>>; 3],
+}
+
+impl ModelImpl for MutualInductorModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        match param {
+            L1 | L2 | K => self.params[usize::from(param)].set(Some(val)),
+            _ => unreachable!("mutual_inductor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn InstanceImpl> {
+        Box::new(MutualInductorInstance {
+            model: self,
+            pos: [Node::GROUND; 2],
+            neg: [Node::GROUND; 2],
+            branches: [Node::GROUND; 2],
+            params: [None; 3],
+            ind: [0.0; 2],
+            mutual: 0.0,
+            resist_entries: [NonNull::dangling(); 12],
+            react_entries: [NonNull::dangling(); 12],
+        })
+    }
+}
+
+struct MutualInductorInstance {
+    model: Rc<MutualInductorModel>,
+    pos: [Node; 2],
+    neg: [Node; 2],
+    branches: [Node; 2],
+    /// `l1`, `l2` and `k` if specified as instance parameters
+    params: [Option<f64>; 3],
+    ind: [f64; 2],
+    mutual: f64,
+    resist_entries: [NonNull<Cell<f64>>; 12],
+    react_entries: [NonNull<Cell<f64>>; 12],
+}
+
+impl MutualInductorInstance {
+    /// The magnetic flux through both windings
+    fn flux(&self, solve: &TiSlice<Node, f64>) -> [f64; 2] {
+        let [br1, br2] = self.branches;
+        [
+            self.ind[0] * solve[br1] + self.mutual * solve[br2],
+            self.mutual * solve[br1] + self.ind[1] * solve[br2],
+        ]
+    }
+
+    unsafe fn load_inductance(&self, entries: &[NonNull<Cell<f64>>; 12], alpha: f64) {
+        update_matrix_entry(entries[MATRIX_BR1_BR1].as_ref(), -alpha * self.ind[0]);
+        update_matrix_entry(entries[MATRIX_BR1_BR2].as_ref(), -alpha * self.mutual);
+        update_matrix_entry(entries[MATRIX_BR2_BR1].as_ref(), -alpha * self.mutual);
+        update_matrix_entry(entries[MATRIX_BR2_BR2].as_ref(), -alpha * self.ind[1]);
+    }
+
+    unsafe fn load_incidence(&self) {
+        load_branch_incidence(&self.resist_entries[MATRIX_WINDING1..]);
+        load_branch_incidence(&self.resist_entries[MATRIX_WINDING2..]);
+    }
+}
+
+impl InstanceImpl for MutualInductorInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        sim_builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let &[pos1, neg1, pos2, neg2] = terminals else {
+            bail!("mutual_inductor: all terminals must be connected")
+        };
+
+        let branches = [
+            sim_builder.new_internal_branch("branch1"),
+            sim_builder.new_internal_branch("branch2"),
+        ];
+        self.pos = [pos1, pos2];
+        self.neg = [neg1, neg2];
+        self.branches = branches;
+
+        // IMPORTANT: keep the order in sync with load_branch_incidence and the MATRIX_ indices
+        for ((pos, neg), branch) in zip(zip(self.pos, self.neg), branches) {
+            sim_builder.ensure_matrix_entry(pos, branch);
+            sim_builder.ensure_matrix_entry(branch, pos);
+            sim_builder.ensure_matrix_entry(neg, branch);
+            sim_builder.ensure_matrix_entry(branch, neg);
+        }
+        for row in branches {
+            for column in branches {
+                sim_builder.ensure_matrix_entry(row, column);
+            }
+        }
+
+        let [l1, l2, k] = [L1, L2, K].map(|param| {
+            let i = usize::from(param);
+            self.params[i].or(self.model.params[i].get())
+        });
+        let (Some(l1), Some(l2), Some(k)) = (l1, l2, k) else {
+            bail!("mutual_inductor: l1, l2 and k must be set")
+        };
+        if !(-1.0..=1.0).contains(&k) {
+            bail!("mutual_inductor: the coupling coefficient k = {k} must be within [-1, 1]")
+        }
+        self.ind = [l1, l2];
+        self.mutual = k * (l1 * l2).sqrt();
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            L1 | L2 | K => self.params[usize::from(param)] = Some(val),
+            _ => unreachable!("mutual_inductor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        for (i, entry) in matrix_entries.enumerate() {
+            self.resist_entries[i] = entry.resist();
+            self.react_entries[i] = entry.react();
+        }
+    }
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<()> {
+        Ok(())
+    }
+
+    unsafe fn load_matrix_resist(&self) {
+        self.load_incidence();
+    }
+
+    unsafe fn load_matrix_react(&self, alpha: f64) {
+        self.load_inductance(&self.react_entries, alpha);
+    }
+
+    unsafe fn load_matrix_tran(&self, alpha: f64) {
+        self.load_incidence();
+        self.load_inductance(&self.resist_entries, alpha);
+    }
+
+    fn load_residual_react(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        for (branch, flux) in zip(self.branches, self.flux(prev_solve)) {
+            rhs[branch] -= flux;
+        }
+    }
+
+    fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        for ((pos, neg), branch) in zip(zip(self.pos, self.neg), self.branches) {
+            rhs[pos] += prev_solve[branch];
+            rhs[neg] -= prev_solve[branch];
+            rhs[branch] += prev_solve[pos] - prev_solve[neg];
+        }
+    }
+
+    fn load_lead_current_resist(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        let [br1, br2] = self.branches;
+        dst[0] = dc_solve[br1];
+        dst[1] = -dc_solve[br1];
+        dst[2] = dc_solve[br2];
+        dst[3] = -dc_solve[br2];
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[..4].fill(0.0);
+    }
+
+    fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        let [br1, br2] = self.branches;
+        dst[0] = ac_solve[br1];
+        dst[1] = -ac_solve[br1];
+        dst[2] = ac_solve[br2];
+        dst[3] = -ac_solve[br2];
+    }
+}
@@ -8,7 +8,9 @@ use typed_index_collections::TiSlice;
 
 use super::{ModelImpl, ParamId, SimInfo};
 use crate::circuit::Node;
-use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::devices::{
+    update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, NoiseSource, Type,
+};
 use crate::simulation::{MatrixEntryIter, SimBuilder};
 
 pub struct Resistor;
@@ -41,6 +43,9 @@ impl DeviceImpl for Resistor {
 
 const R: ParamId = ParamId(0u32);
 
+/// Boltzmann constant in J/K
+const KB: f64 = 1.380649e-23;
+
 const MATRIX_ANODE_ANODE: usize = 0;
 const MATRIX_ANODE_CATHODE: usize = 1;
 const MATRIX_CATHODE_ANODE: usize = 2;
@@ -65,19 +70,24 @@ impl ModelImpl for ResistorModel {
 
     fn new_instance(self: Rc<Self>) -> Box<dyn super::InstanceImpl> {
         Box::new(ResistorInstance {
+            model: self,
             anode: Node::GROUND,
             cathode: Node::GROUND,
-            res: self.res.get(),
+            res: None,
             matrix_entries: [NonNull::dangling(); 4],
             conductance: 0.0,
+            temp: 0.0,
         })
     }
 }
 
 struct ResistorInstance {
+    model: Rc<ResistorModel>,
     anode: Node,
     cathode: Node,
     conductance: f64,
+    temp: f64,
+    /// The resistance specified as instance parameter (overwrites the model parameter)
     res: Option<f64>,
     matrix_entries: [NonNull<Cell<f64>>; 4],
 }
@@ -119,9 +129,17 @@ impl InstanceImpl for ResistorInstance {
 
     fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, _dst: &mut [f64]) {}
 
+    fn noise_sources(&self) -> Vec<NoiseSource> {
+        vec![NoiseSource { name: "thermal", hi: self.anode, lo: self.cathode }]
+    }
+
+    fn load_noise(&self, _freq: f64, dst: &mut [f64]) {
+        dst[0] = 4.0 * KB * self.temp * self.conductance;
+    }
+
     fn process_params(
         &mut self,
-        _temp: f64,
+        temp: f64,
         sim_builder: &mut SimBuilder,
         terminals: &[Node],
     ) -> Result<()> {
@@ -133,13 +151,14 @@ impl InstanceImpl for ResistorInstance {
 
         self.anode = anode;
         self.cathode = cathode;
+        self.temp = temp;
 
         sim_builder.ensure_matrix_entry(anode, anode);
         sim_builder.ensure_matrix_entry(anode, cathode);
         sim_builder.ensure_matrix_entry(cathode, anode);
         sim_builder.ensure_matrix_entry(cathode, cathode);
 
-        match self.res {
+        match self.res.or(self.model.res.get()) {
             Some(res) => self.conductance = 1.0 / res,
             None => bail!("resistor: resistance must be set"),
         };
@@ -0,0 +1,352 @@
+//! The parameters and time dependent waveforms shared by the independent sources (`vsource` and
+//! `isource`). The parameter names follow Spectre: the waveform is selected with the `type`
+//! parameter (`dc`, `pulse`, `sine`, `exp`, `pwl` or `sffm`).
+
+use std::f64::consts::TAU;
+use std::fs;
+
+use anyhow::{bail, Context, Result};
+use num_complex::Complex64;
+
+use crate::devices::{DeviceParams, ParamId, Type};
+use crate::simulation::{EvalFlags, SimInfo};
+
+const REAL_PARAMS: [&str; 21] = [
+    "dc",
+    "mag",
+    "phase",
+    "val0",
+    "val1",
+    "delay",
+    "rise",
+    "fall",
+    "width",
+    "period",
+    "sinedc",
+    "ampl",
+    "freq",
+    "damp",
+    "sinephase",
+    "fmmodindex",
+    "fmmodfreq",
+    "td1",
+    "tau1",
+    "td2",
+    "tau2",
+];
+const STR_PARAMS: [&str; 3] = ["type", "wave", "file"];
+
+const DC: usize = 0;
+const MAG: usize = 1;
+const PHASE: usize = 2;
+const VAL0: usize = 3;
+const VAL1: usize = 4;
+const DELAY: usize = 5;
+const RISE: usize = 6;
+const FALL: usize = 7;
+const WIDTH: usize = 8;
+const PERIOD: usize = 9;
+const SINEDC: usize = 10;
+const AMPL: usize = 11;
+const FREQ: usize = 12;
+const DAMP: usize = 13;
+const SINEPHASE: usize = 14;
+const FMMODINDEX: usize = 15;
+const FMMODFREQ: usize = 16;
+const TD1: usize = 17;
+const TAU1: usize = 18;
+const TD2: usize = 19;
+const TAU2: usize = 20;
+
+const TYPE: usize = 0;
+const WAVE: usize = 1;
+const FILE: usize = 2;
+
+/// Breakpoints closer than this (relative) distance to the current time are ignored, they are
+/// only the result of rounding errors.
+const BREAKPOINT_RTOL: f64 = 1e-12;
+
+/// Registers the parameters of an independent source.
+pub(super) fn insert_params(params: &mut DeviceParams) {
+    for name in REAL_PARAMS {
+        params.insert_instance_param(name, Type::Real);
+    }
+    for name in STR_PARAMS {
+        params.insert_instance_param(name, Type::String);
+    }
+}
+
+/// The parameters of a source that were specified explicitly.
+#[derive(Default, Clone)]
+pub(super) struct SourceParams {
+    real: [Option<f64>; REAL_PARAMS.len()],
+    str: [Option<Box<str>>; STR_PARAMS.len()],
+}
+
+impl SourceParams {
+    pub fn set_real(&mut self, param: ParamId, val: f64) {
+        match self.real.get_mut(usize::from(param)) {
+            Some(dst) => *dst = Some(val),
+            None => unreachable!("unknown num param {param:?}"),
+        }
+    }
+
+    pub fn set_str(&mut self, param: ParamId, val: &str) {
+        match self.str.get_mut(usize::from(param).wrapping_sub(REAL_PARAMS.len())) {
+            Some(dst) => *dst = Some(val.into()),
+            None => unreachable!("unknown str param {param:?}"),
+        }
+    }
+
+    /// Creates the source described by these (instance) parameters. Parameters that were not
+    /// specified are taken from the `model`.
+    pub fn build(&self, model: &SourceParams, dev: &str) -> Result<Source> {
+        let real = |param: usize| self.real[param].or(model.real[param]);
+        let str = |param: usize| self.str[param].as_deref().or(model.str[param].as_deref());
+        let positive = |param: usize| match real(param) {
+            Some(val) if val <= 0.0 => {
+                bail!("{dev}: {} must be positive", REAL_PARAMS[param])
+            }
+            val => Ok(val),
+        };
+        let not_negative = |param: usize, default: f64| {
+            let val = real(param).unwrap_or(default);
+            if val < 0.0 {
+                bail!("{dev}: {} must not be negative", REAL_PARAMS[param])
+            }
+            Ok(val)
+        };
+
+        let has_pwl = str(WAVE).is_some() || str(FILE).is_some();
+        let ty = str(TYPE).unwrap_or(if has_pwl { "pwl" } else { "dc" });
+        let waveform = match ty {
+            "dc" => Waveform::Const(real(DC).unwrap_or(0.0)),
+            "pulse" => Waveform::Pulse {
+                val0: real(VAL0).unwrap_or(0.0),
+                val1: real(VAL1).unwrap_or(0.0),
+                delay: not_negative(DELAY, 0.0)?,
+                rise: not_negative(RISE, 0.0)?,
+                fall: not_negative(FALL, 0.0)?,
+                width: not_negative(WIDTH, f64::INFINITY)?,
+                period: positive(PERIOD)?.unwrap_or(f64::INFINITY),
+            },
+            "sine" | "sin" | "sffm" => Waveform::Sine {
+                offset: real(SINEDC).unwrap_or(0.0),
+                ampl: real(AMPL).unwrap_or(0.0),
+                freq: real(FREQ).unwrap_or(0.0),
+                delay: not_negative(DELAY, 0.0)?,
+                damp: real(DAMP).unwrap_or(0.0),
+                phase: real(SINEPHASE).unwrap_or(0.0).to_radians(),
+                mod_index: real(FMMODINDEX).unwrap_or(0.0),
+                mod_freq: real(FMMODFREQ).unwrap_or(0.0),
+            },
+            "exp" => {
+                let Some(tau1) = positive(TAU1)? else { bail!("{dev}: tau1 must be set") };
+                let td1 = not_negative(TD1, 0.0)?;
+                let td2 = real(TD2).unwrap_or(f64::INFINITY);
+                if td2 < td1 {
+                    bail!("{dev}: td2 must not be smaller than td1")
+                }
+                Waveform::Exp {
+                    val0: real(VAL0).unwrap_or(0.0),
+                    val1: real(VAL1).unwrap_or(0.0),
+                    td1,
+                    tau1,
+                    td2,
+                    tau2: positive(TAU2)?.unwrap_or(tau1),
+                }
+            }
+            "pwl" => {
+                let points = match (str(WAVE), str(FILE)) {
+                    (Some(wave), None) => parse_pwl(wave, false),
+                    (None, Some(file)) => fs::read_to_string(file)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|src| parse_pwl(&src, true))
+                        .with_context(|| format!("while reading the waveform file '{file}'")),
+                    (Some(_), Some(_)) => bail!("{dev}: only one of wave and file can be set"),
+                    (None, None) => bail!("{dev}: a pwl source requires either wave or file"),
+                };
+                Waveform::Pwl(points.with_context(|| format!("invalid pwl waveform of {dev}"))?)
+            }
+            _ => bail!("{dev}: unknown source type '{ty}'"),
+        };
+
+        // the dc value defaults to the value at the start of transient analysis
+        let dc = real(DC).unwrap_or_else(|| waveform.eval(0.0));
+        let mag = real(MAG).unwrap_or(0.0);
+        let phase = real(PHASE).unwrap_or(0.0);
+        Ok(Source { dc, ac: Complex64::from_polar(mag, phase), waveform })
+    }
+}
+
+/// The value of an independent source
+pub(super) struct Source {
+    pub dc: f64,
+    pub ac: Complex64,
+    pub waveform: Waveform,
+}
+
+impl Source {
+    /// The value of the source (including source stepping) during the evaluation described by
+    /// `sim_info`. The waveform is only used during transient analysis.
+    pub fn eval(&self, sim_info: SimInfo<'_>) -> f64 {
+        let val = if sim_info.flags.contains(EvalFlags::ANALYSIS_TRAN) {
+            self.waveform.eval(sim_info.abstime)
+        } else {
+            self.dc
+        };
+        val * sim_info.source_factor
+    }
+}
+
+pub(super) enum Waveform {
+    Const(f64),
+    /// A trapezoidal pulse from `val0` to `val1` that is repeated every `period` (infinite for a
+    /// single pulse)
+    Pulse {
+        val0: f64,
+        val1: f64,
+        delay: f64,
+        rise: f64,
+        fall: f64,
+        width: f64,
+        period: f64,
+    },
+    /// A (damped) sinusoid, frequency modulated with `mod_index * sin(2 pi mod_freq t)`
+    Sine {
+        offset: f64,
+        ampl: f64,
+        freq: f64,
+        delay: f64,
+        damp: f64,
+        phase: f64,
+        mod_index: f64,
+        mod_freq: f64,
+    },
+    /// Exponential rise towards `val1` starting at `td1` and fall back to `val0` starting at
+    /// `td2`
+    Exp {
+        val0: f64,
+        val1: f64,
+        td1: f64,
+        tau1: f64,
+        td2: f64,
+        tau2: f64,
+    },
+    /// Piecewise linear interpolation between `(time, value)` pairs
+    Pwl(Box<[(f64, f64)]>),
+}
+
+impl Waveform {
+    /// The value of the waveform at `time`. Instantaneous steps (for example a pulse without
+    /// rise time) take effect directly after the time at which they occur.
+    pub fn eval(&self, time: f64) -> f64 {
+        match *self {
+            Waveform::Const(val) => val,
+            Waveform::Pulse { val0, val1, delay, rise, fall, width, period } => {
+                if time <= delay {
+                    return val0;
+                }
+                let time = (time - delay) % period;
+                if time <= 0.0 {
+                    val0
+                } else if time < rise {
+                    val0 + (val1 - val0) * time / rise
+                } else if time <= rise + width {
+                    val1
+                } else if time < rise + width + fall {
+                    val1 + (val0 - val1) * (time - rise - width) / fall
+                } else {
+                    val0
+                }
+            }
+            Waveform::Sine { offset, ampl, freq, delay, damp, phase, mod_index, mod_freq } => {
+                if time <= delay {
+                    return offset + ampl * phase.sin();
+                }
+                let time = time - delay;
+                let arg = TAU * freq * time + phase + mod_index * (TAU * mod_freq * time).sin();
+                offset + ampl * (-damp * time).exp() * arg.sin()
+            }
+            Waveform::Exp { val0, val1, td1, tau1, td2, tau2 } => {
+                let mut val = val0;
+                if time > td1 {
+                    val += (val1 - val0) * (1.0 - (-(time - td1) / tau1).exp());
+                }
+                if time > td2 {
+                    val += (val0 - val1) * (1.0 - (-(time - td2) / tau2).exp());
+                }
+                val
+            }
+            Waveform::Pwl(ref points) => {
+                let i = points.partition_point(|&(t, _)| t < time);
+                if i == 0 {
+                    return points[0].1;
+                }
+                let Some(&(t1, v1)) = points.get(i) else {
+                    return points[i - 1].1;
+                };
+                let (t0, v0) = points[i - 1];
+                if t1 == t0 {
+                    v1
+                } else {
+                    v0 + (v1 - v0) * (time - t0) / (t1 - t0)
+                }
+            }
+        }
+    }
+
+    /// The first time after `time` at which the waveform has a corner.
+    pub fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        let is_after = |breakpoint: f64| breakpoint - time > BREAKPOINT_RTOL * time.abs();
+        match *self {
+            Waveform::Const(_) => None,
+            Waveform::Pulse { delay, rise, fall, width, period, .. } => {
+                if is_after(delay) {
+                    return Some(delay);
+                }
+                let corners = [0.0, rise, rise + width, rise + width + fall];
+                let starts = if period.is_finite() {
+                    // the following periods guard against rounding errors in the division
+                    let first = ((time - delay) / period).floor();
+                    [0.0, 1.0, 2.0].map(|i| delay + (first + i) * period)
+                } else {
+                    [delay; 3]
+                };
+                starts
+                    .into_iter()
+                    .flat_map(|start| corners.map(|corner| start + corner))
+                    .find(|&breakpoint| breakpoint.is_finite() && is_after(breakpoint))
+            }
+            Waveform::Sine { delay, .. } => Some(delay).filter(|&delay| is_after(delay)),
+            Waveform::Exp { td1, td2, .. } => {
+                [td1, td2].into_iter().find(|&td| td.is_finite() && is_after(td))
+            }
+            Waveform::Pwl(ref points) => {
+                points.iter().map(|&(t, _)| t).find(|&breakpoint| is_after(breakpoint))
+            }
+        }
+    }
+}
+
+/// Parses the `(time, value)` pairs of a pwl waveform. The numbers may be separated by
+/// whitespace or commas, files may contain comments starting with `#`.
+fn parse_pwl(src: &str, is_file: bool) -> Result<Box<[(f64, f64)]>> {
+    let mut vals = Vec::new();
+    for line in src.lines() {
+        let line = if is_file { line.split('#').next().unwrap_or_default() } else { line };
+        for val in line.split(|c: char| c.is_whitespace() || c == ',').filter(|s| !s.is_empty()) {
+            let val: f64 = val.parse().with_context(|| format!("invalid number '{val}'"))?;
+            vals.push(val);
+        }
+    }
+
+    if vals.is_empty() || vals.len() % 2 != 0 {
+        bail!("expected pairs of time and value")
+    }
+    let points: Box<[_]> = vals.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+    if points.windows(2).any(|window| window[1].0 < window[0].0) {
+        bail!("time points must be increasing")
+    }
+    Ok(points)
+}
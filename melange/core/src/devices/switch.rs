@@ -0,0 +1,223 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use stdx::iter::zip;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{load_two_terminal, DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::simulation::{MatrixEntryIter, SimBuilder};
+
+/// An ideal voltage controlled switch: the resistance between `P` and `N` is `ron` while
+/// `V(CP, CN)` is above the threshold `vt` and `roff` otherwise. To avoid oscillation the
+/// threshold is shifted by the hysteresis `vh` away from the state of the last accepted
+/// time point.
+pub struct Switch;
+
+impl Switch {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+impl DeviceImpl for Switch {
+    fn get_name(&self) -> &'static str {
+        "switch"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["P", "N", "CP", "CN"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("ron", Type::Real);
+        res.insert_instance_param("roff", Type::Real);
+        res.insert_instance_param("vt", Type::Real);
+        res.insert_instance_param("vh", Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(SwitchModel {
+            ron: Cell::new(1.0),
+            roff: Cell::new(1e12),
+            vt: Cell::new(0.0),
+            vh: Cell::new(0.0),
+        })
+    }
+}
+
+const RON: ParamId = ParamId(0u32);
+const ROFF: ParamId = ParamId(1u32);
+const VT: ParamId = ParamId(2u32);
+const VH: ParamId = ParamId(3u32);
+
+struct SwitchModel {
+    ron: Cell<f64>,
+    roff: Cell<f64>,
+    vt: Cell<f64>,
+    vh: Cell<f64>,
+}
+
+impl ModelImpl for SwitchModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        let dst = match param {
+            RON => &self.ron,
+            ROFF => &self.roff,
+            VT => &self.vt,
+            VH => &self.vh,
+            _ => unreachable!("switch: unknown numeric parameter {param:?}"),
+        };
+        dst.set(val);
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn InstanceImpl> {
+        Box::new(SwitchInstance {
+            model: self,
+            pos: Node::GROUND,
+            neg: Node::GROUND,
+            ctrl_pos: Node::GROUND,
+            ctrl_neg: Node::GROUND,
+            params: [None; 4],
+            g_on: 0.0,
+            g_off: 0.0,
+            vt: 0.0,
+            vh: 0.0,
+            is_on: false,
+            accepted_is_on: false,
+            matrix_entries: [NonNull::dangling(); 4],
+        })
+    }
+}
+
+struct SwitchInstance {
+    model: Rc<SwitchModel>,
+    pos: Node,
+    neg: Node,
+    ctrl_pos: Node,
+    ctrl_neg: Node,
+    /// `ron`, `roff`, `vt` and `vh` if specified as instance parameters
+    params: [Option<f64>; 4],
+    g_on: f64,
+    g_off: f64,
+    vt: f64,
+    vh: f64,
+    is_on: bool,
+    /// The state at the last accepted time point
+    accepted_is_on: bool,
+    matrix_entries: [NonNull<Cell<f64>>; 4],
+}
+
+impl SwitchInstance {
+    fn conductance(&self) -> f64 {
+        if self.is_on {
+            self.g_on
+        } else {
+            self.g_off
+        }
+    }
+}
+
+impl InstanceImpl for SwitchInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        sim_builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let &[pos, neg, ctrl_pos, ctrl_neg] = terminals else {
+            bail!("switch: all terminals must be connected")
+        };
+        self.pos = pos;
+        self.neg = neg;
+        self.ctrl_pos = ctrl_pos;
+        self.ctrl_neg = ctrl_neg;
+
+        // IMPORTANT: keep the order in sync with load_two_terminal
+        sim_builder.ensure_matrix_entry(pos, pos);
+        sim_builder.ensure_matrix_entry(pos, neg);
+        sim_builder.ensure_matrix_entry(neg, pos);
+        sim_builder.ensure_matrix_entry(neg, neg);
+
+        let model = &self.model;
+        let [ron, roff, vt, vh] = self.params;
+        let ron = ron.unwrap_or(model.ron.get());
+        let roff = roff.unwrap_or(model.roff.get());
+        if ron <= 0.0 || roff <= 0.0 {
+            bail!("switch: ron and roff must be positive")
+        }
+        self.g_on = 1.0 / ron;
+        self.g_off = 1.0 / roff;
+        self.vt = vt.unwrap_or(model.vt.get());
+        self.vh = vh.unwrap_or(model.vh.get());
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            RON | ROFF | VT | VH => self.params[usize::from(param)] = Some(val),
+            _ => unreachable!("switch: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        for (dst, entry) in zip(&mut self.matrix_entries, matrix_entries) {
+            *dst = entry.resist();
+        }
+    }
+
+    fn eval(&mut self, sim_info: SimInfo<'_>) -> Result<()> {
+        let vc = sim_info.prev_solve[self.ctrl_pos] - sim_info.prev_solve[self.ctrl_neg];
+        self.is_on =
+            if self.accepted_is_on { vc > self.vt - self.vh } else { vc > self.vt + self.vh };
+        Ok(())
+    }
+
+    fn accept_step(&mut self) {
+        self.accepted_is_on = self.is_on;
+    }
+
+    unsafe fn load_matrix_resist(&self) {
+        load_two_terminal(&self.matrix_entries, self.conductance());
+    }
+
+    unsafe fn load_matrix_react(&self, _alpha: f64) {}
+
+    fn load_residual_react(&self, _prev_solve: &TiSlice<Node, f64>, _rhs: &mut TiSlice<Node, f64>) {
+    }
+
+    fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        let current = self.conductance() * (prev_solve[self.pos] - prev_solve[self.neg]);
+        rhs[self.pos] += current;
+        rhs[self.neg] -= current;
+    }
+
+    fn load_lead_current_resist(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        let current = self.conductance() * (dc_solve[self.pos] - dc_solve[self.neg]);
+        dst[0] = current;
+        dst[1] = -current;
+        dst[2] = 0.0;
+        dst[3] = 0.0;
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[..4].fill(0.0);
+    }
+
+    fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        let current = (ac_solve[self.pos] - ac_solve[self.neg]) * self.conductance();
+        dst[0] = current;
+        dst[1] = -current;
+        dst[2] = Complex64::default();
+        dst[3] = Complex64::default();
+    }
+}
@@ -1,5 +1,4 @@
-use std::cell::Cell;
-
+use std::cell::{Cell, RefCell};
 use std::ptr::NonNull;
 use std::rc::Rc;
 
@@ -9,11 +8,14 @@ use stdx::iter::zip;
 use typed_index_collections::TiSlice;
 
 use crate::circuit::Node;
-use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::devices::source::{self, Source, SourceParams, Waveform};
+use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl};
 use crate::simulation::{MatrixEntryIter, SimBuilder};
 
 use super::{ModelImpl, ParamId, SimInfo};
 
+/// An independent voltage source `V(A, C)`, the current through the source (flowing from `A` to
+/// `C`) is an additional unknown. See the `source` module for the supported waveforms.
 pub struct VoltageSrc;
 
 impl VoltageSrc {
@@ -22,10 +24,6 @@ impl VoltageSrc {
     }
 }
 
-const DC: ParamId = ParamId(0u32);
-const MAG: ParamId = ParamId(1u32);
-const PHASE: ParamId = ParamId(2u32);
-
 const MATRIX_ANODE_BR: usize = 0;
 const MATRIX_BR_ANODE: usize = 1;
 const MATRIX_CATHODE_BR: usize = 2;
@@ -42,9 +40,7 @@ impl DeviceImpl for VoltageSrc {
 
     fn get_params(&self) -> DeviceParams {
         let mut res = DeviceParams::default();
-        res.insert_instance_param("dc", Type::Real);
-        res.insert_instance_param("mag", Type::Real);
-        res.insert_instance_param("phase", Type::Real);
+        source::insert_params(&mut res);
         res
     }
 
@@ -55,9 +51,7 @@ impl DeviceImpl for VoltageSrc {
 
 #[derive(Default)]
 struct VoltageSrcModel {
-    dc: Cell<f64>,
-    mag: Cell<f64>,
-    phase: Cell<f64>,
+    params: RefCell<SourceParams>,
 }
 
 impl ModelImpl for VoltageSrcModel {
@@ -66,33 +60,37 @@ impl ModelImpl for VoltageSrcModel {
     }
 
     fn set_real_param(&self, param: ParamId, val: f64) {
-        let dst = match param {
-            DC => &self.dc,
-            MAG => &self.mag,
-            PHASE => &self.phase,
-            _ => unreachable!("vsource: unknown num param {param:?}"),
-        };
-        dst.set(val);
+        self.params.borrow_mut().set_real(param, val)
+    }
+
+    fn set_str_param(&self, param: ParamId, val: &str) {
+        self.params.borrow_mut().set_str(param, val)
     }
 
     fn new_instance(self: Rc<Self>) -> Box<dyn super::InstanceImpl> {
         Box::new(VoltageSrcInstance {
+            model: self,
             anode: Node::GROUND,
             cathode: Node::GROUND,
             branch: Node::GROUND,
-            dc: self.dc.get(),
-            ac: Complex64::from_polar(self.mag.get(), self.phase.get()),
+            params: SourceParams::default(),
+            src: Source { dc: 0.0, ac: Complex64::default(), waveform: Waveform::Const(0.0) },
+            val: 0.0,
             matrix_entries: [NonNull::dangling(); 4],
         })
     }
 }
 
 struct VoltageSrcInstance {
+    model: Rc<VoltageSrcModel>,
     anode: Node,
     cathode: Node,
     branch: Node,
-    dc: f64,
-    ac: Complex64,
+    /// The parameters specified for the instance (overwrite the model parameters)
+    params: SourceParams,
+    src: Source,
+    /// The voltage of the source during the last evaluation
+    val: f64,
     matrix_entries: [NonNull<Cell<f64>>; 4],
 }
 
@@ -120,16 +118,16 @@ impl InstanceImpl for VoltageSrcInstance {
         builder.ensure_matrix_entry(cathode, branch);
         builder.ensure_matrix_entry(branch, cathode);
 
+        self.src = self.params.build(&self.model.params.borrow(), "vsource")?;
         Ok(())
     }
 
     fn set_real_param(&mut self, param: ParamId, val: f64) {
-        match param {
-            DC => self.dc = val,
-            MAG => self.ac = Complex64::from_polar(val, self.ac.arg()),
-            PHASE => self.ac = Complex64::from_polar(self.ac.norm_sqr(), val),
-            _ => unreachable!("vsource: unknown num param {param:?}"),
-        };
+        self.params.set_real(param, val)
+    }
+
+    fn set_str_param(&mut self, param: ParamId, val: &str) {
+        self.params.set_str(param, val)
     }
 
     fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
@@ -138,10 +136,15 @@ impl InstanceImpl for VoltageSrcInstance {
         }
     }
 
-    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<()> {
+    fn eval(&mut self, sim_info: SimInfo<'_>) -> Result<()> {
+        self.val = self.src.eval(sim_info);
         Ok(())
     }
 
+    fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        self.src.waveform.next_breakpoint(time)
+    }
+
     unsafe fn load_matrix_resist(&self) {
         update_matrix_entry(self.matrix_entries[MATRIX_ANODE_BR].as_ref(), 1.0);
         update_matrix_entry(self.matrix_entries[MATRIX_BR_ANODE].as_ref(), 1.0);
@@ -157,7 +160,7 @@ impl InstanceImpl for VoltageSrcInstance {
     fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
         rhs[self.anode] += prev_solve[self.branch];
         rhs[self.cathode] -= prev_solve[self.branch];
-        rhs[self.branch] -= self.dc;
+        rhs[self.branch] -= self.val;
         rhs[self.branch] += prev_solve[self.anode] - prev_solve[self.cathode];
     }
 
@@ -166,7 +169,7 @@ impl InstanceImpl for VoltageSrcInstance {
         _prev_solve: &TiSlice<Node, f64>,
         rhs: &mut TiSlice<Node, Complex64>,
     ) {
-        rhs[self.branch] += self.ac;
+        rhs[self.branch] += self.src.ac;
     }
 
     fn load_lead_current_resist(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
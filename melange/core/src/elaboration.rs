@@ -5,12 +5,14 @@
 //! [description]: crate::elaboration::CircuitDescription
 //! [circuit]: crate::circuit::Circuit
 
+use ahash::AHashMap;
 use anyhow::{bail, Context, Result};
 use camino::Utf8PathBuf;
+use stdx::iter::zip;
 use typed_index_collections::TiVec;
 
-use crate::circuit::{Circuit, DeviceId, InstanceId, ModelId, NameSpaceEntry, Node};
-use crate::{veriloga, Arena, Expr};
+use crate::circuit::{Circuit, DeviceId, InstanceId, ModelId, NameSpaceEntry, Node, ScopeId};
+use crate::{veriloga, Arena, CircuitParam, CircuitParamCtx, Expr};
 
 /// A textual description of a circuit from which a circuit can be built.
 /// This serves primarily as an intermediate step for the netlist parser.
@@ -28,11 +30,31 @@ pub struct CircuitDescription {
     pub instances: TiVec<InstanceId, CircuitInstanceDescription>,
     /// A listing of all models within the described circuit
     pub models: TiVec<ModelId, CircuitModelDescription>,
-    /// A list of Verilog-A files that need to be compiled
+    /// A listing of all subcircuit definitions within the described circuit
+    pub subcircuits: Vec<SubcircuitDescription>,
+    /// Nodes that refer to the same top level node inside every subcircuit (like `ground`)
+    pub global_nodes: Vec<String>,
+    /// A list of Verilog-A files that need to be compiled (or precompiled `.osdi` libraries)
     pub va_files: Vec<Utf8PathBuf>,
 
-    /// Arena allocator for expressions
-    pub earena: Arena,
+    /// The context (in the [`Arena`](crate::Arena) all expressions were allocated in) that the
+    /// parameters of the circuit are defined in.
+    pub ctx: CircuitParamCtx,
+    /// The parameters of the circuit and their default values in the order they were declared.
+    pub parameters: Vec<(CircuitParam, Expr)>,
+}
+
+/// A subcircuit definition inside a [`CircuitDescription`].
+pub struct SubcircuitDescription {
+    pub name: String,
+    /// Names of the nodes that are connected to the terminals of the subcircuit instances.
+    pub ports: Vec<String>,
+    /// The context that the subcircuit parameters are defined in.
+    pub ctx: CircuitParamCtx,
+    /// The parameters of the subcircuit and their default values.
+    pub parameters: Vec<(CircuitParam, Expr)>,
+    pub instances: Vec<CircuitInstanceDescription>,
+    pub models: Vec<CircuitModelDescription>,
 }
 
 /// A device instance inside a [`CircuitDescription`](create::circuit::CircuitDescription).
@@ -90,11 +112,15 @@ impl CircuitDescription {
     /// * create implicit models for instances without separate model definition
     /// * match model/instance parameters to parameter ids provided by device
     /// * for each node name connected to a device terminal create a node
+    /// * flatten subcircuit instances into the circuit using hierarchical names (`x1.r1`)
     ///
     /// All these tasks can fail if the user provided an invalid circuit descriptor.
     /// Currently only the first error is returned using anyhow. In the future all errors should be
     /// reterminaled similar to OpenVAF.
     ///
+    /// `earena` must be the arena the expressions of the descriptor were allocated in. It is
+    /// used to resolve the names of subcircuit parameters.
+    ///
     /// # Returns
     ///
     /// The information obtained during elaboration inside a [`Circuit`](crate::circuit::Circuit)
@@ -103,47 +129,200 @@ impl CircuitDescription {
     /// If any of the following conditions occurs, an error is returned instead:
     /// * Verilog-A compilation fails
     /// * A model/subcircuit/device is not found
+    /// * A subcircuit is instantiated recursively
     pub fn elaborate(self, earena: &mut Arena, opts: &veriloga::Opts) -> Result<Circuit> {
-        let mut res = Circuit::new(self.name, earena);
+        if self.parameters.iter().any(|&(param, _)| earena.lookup_param_info(param).is_none()) {
+            bail!("the parameters of circuit '{}' were not allocated in this arena", self.name)
+        }
+        let mut res = Circuit::new_in_ctx(self.name, self.ctx);
+        res.param_assignments.extend(self.parameters);
         for va_file in self.va_files {
             res.load_veriloga_file(va_file, opts)?;
         }
 
+        let mut subcircuits = AHashMap::with_capacity(self.subcircuits.len());
+        for subckt in &self.subcircuits {
+            if subcircuits.insert(&*subckt.name, subckt).is_some() {
+                bail!("subcircuit '{}' was defined multiple times", subckt.name)
+            }
+        }
+
         for model in self.models {
             let name = model.name.clone();
             res.elaborate_model(model)
                 .with_context(|| format!("while elaborating model '{name}'"))?;
         }
 
-        for inst in self.instances {
-            let name = inst.name.clone();
-            res.elaborate_instance(inst)
-                .with_context(|| format!("while elaborating instance '{name}'"))?;
+        let mut elaborator = Elaborator { circ: &mut res, earena: &*earena, subcircuits };
+        let top = Hierarchy { global_nodes: &self.global_nodes, ..Hierarchy::default() };
+        for inst in &self.instances {
+            elaborator
+                .instance(inst, &top)
+                .with_context(|| format!("while elaborating instance '{}'", inst.name))?;
         }
 
         Ok(res)
     }
 }
 
+/// The subcircuit instance that items are currently elaborated in (or the top level).
+#[derive(Default)]
+struct Hierarchy<'a> {
+    parent: Option<&'a Hierarchy<'a>>,
+    /// The subcircuit that is instantiated (`None` for the top level)
+    subckt: Option<&'a str>,
+    /// Prepended to the names of all items (`x1.x2.`), empty for the top level
+    prefix: String,
+    scope: Option<ScopeId>,
+    /// The nodes (in the parent) that the ports of the subcircuit are connected to
+    ports: AHashMap<&'a str, Node>,
+    /// Nodes that are never local to a subcircuit (in addition to `ground`)
+    global_nodes: &'a [String],
+}
+
+impl Hierarchy<'_> {
+    fn name(&self, name: &str) -> String {
+        format!("{}{name}", self.prefix)
+    }
+
+    fn ancestors(&self) -> impl Iterator<Item = &Hierarchy<'_>> {
+        std::iter::successors(Some(self), |hier| hier.parent)
+    }
+}
+
+struct Elaborator<'a> {
+    circ: &'a mut Circuit,
+    earena: &'a Arena,
+    subcircuits: AHashMap<&'a str, &'a SubcircuitDescription>,
+}
+
+impl<'a> Elaborator<'a> {
+    fn instance(
+        &mut self,
+        instance: &'a CircuitInstanceDescription,
+        hier: &Hierarchy<'_>,
+    ) -> Result<()> {
+        if self.circ.resolve_master(&instance.master, hier).is_none() {
+            if let Some(subckt) = self.subcircuits.get(&*instance.master).copied() {
+                return self.subckt_instance(instance, subckt, hier);
+            }
+        }
+        self.circ.elaborate_instance_in(instance, hier)?;
+        Ok(())
+    }
+
+    fn subckt_instance(
+        &mut self,
+        instance: &'a CircuitInstanceDescription,
+        subckt: &'a SubcircuitDescription,
+        hier: &Hierarchy<'_>,
+    ) -> Result<()> {
+        if hier.ancestors().any(|hier| hier.subckt == Some(&*subckt.name)) {
+            bail!("subcircuit '{}' is instantiated recursively", subckt.name)
+        }
+        if instance.terminal_connections.len() != subckt.ports.len() {
+            bail!(
+                "subcircuit '{}' has {} ports {:?} but {} terminals were connected",
+                subckt.name,
+                subckt.ports.len(),
+                subckt.ports,
+                instance.terminal_connections.len(),
+            );
+        }
+
+        let mut overrides = Vec::with_capacity(instance.parameters.len());
+        for (param_name, val) in &instance.parameters {
+            match self.earena.lookup_param_by_name(subckt.ctx, param_name) {
+                Some((param, _)) => overrides.push((param, *val)),
+                None => bail!("unknown parameter '{param_name}' for subcircuit '{}'", subckt.name),
+            }
+        }
+
+        let name = hier.name(&instance.name);
+        let scope =
+            self.circ.new_scope(name.clone(), hier.scope, subckt.parameters.clone(), overrides);
+        let ports = zip(&subckt.ports, &instance.terminal_connections)
+            .map(|(port, conn)| (&**port, self.circ.elaborate_node(conn, hier)))
+            .collect();
+        let child = Hierarchy {
+            parent: Some(hier),
+            subckt: Some(&subckt.name),
+            prefix: format!("{name}."),
+            scope: Some(scope),
+            ports,
+            global_nodes: hier.global_nodes,
+        };
+        for (port, &node) in &child.ports {
+            self.circ.alias_node(child.name(port), node);
+        }
+
+        for model in &subckt.models {
+            self.circ.elaborate_model_in(model, &child).with_context(|| {
+                format!("while elaborating model '{}'", child.name(&model.name))
+            })?;
+        }
+
+        for inst in &subckt.instances {
+            self.instance(inst, &child).with_context(|| {
+                format!("while elaborating instance '{}'", child.name(&inst.name))
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Circuit {
     /// Creates a circuit model from a [`CircuitDescription`]
     pub fn elaborate_model(&mut self, descr: CircuitModelDescription) -> Result<ModelId> {
+        self.elaborate_model_in(&descr, &Hierarchy::default())
+    }
+
+    fn elaborate_model_in(
+        &mut self,
+        descr: &CircuitModelDescription,
+        hier: &Hierarchy,
+    ) -> Result<ModelId> {
         let device = match self.lookup_device(&descr.device) {
             Some(dev) => dev,
             None => bail!("device '{}' not found", descr.device),
         };
 
-        let model = self.new_model(descr.name, device)?;
-        for (param_name, val) in descr.parameters {
-            self.set_model_param(model, &param_name, val)?;
+        let model = self.new_model(hier.name(&descr.name), device)?;
+        self[model].scope = hier.scope;
+        for (param_name, val) in &descr.parameters {
+            self.set_model_param(model, param_name, *val)?;
         }
         Ok(model)
     }
 
+    /// Resolves the node `name` within a subcircuit: ground and global nodes refer to the top
+    /// level node of the same name (or the node it is an alias for), ports refer to the nodes
+    /// they are connected to and all other nodes are local to the subcircuit instance.
+    fn elaborate_node(&mut self, name: &str, hier: &Hierarchy) -> Node {
+        if name == "ground" {
+            return Node::GROUND;
+        }
+        if hier.global_nodes.iter().any(|node| node == name) {
+            return self.node(name.to_owned());
+        }
+        match hier.ports.get(name) {
+            Some(&node) => node,
+            None => self.node(hier.name(name)),
+        }
+    }
+
+    /// Resolves a model or device by name. Models defined within (enclosing) subcircuits take
+    /// precedence over the models defined at the top level.
+    fn resolve_master(&self, name: &str, hier: &Hierarchy) -> Option<NameSpaceEntry> {
+        hier.ancestors().find_map(|hier| self.namespace.get(&hier.name(name)).copied())
+    }
+
     fn elaborate_dev_terminals(
         &mut self,
         dev: DeviceId,
-        connected_terminals: Vec<String>,
+        connected_terminals: &[String],
+        hier: &Hierarchy,
     ) -> Result<Vec<Node>> {
         let dev_info = &self[dev];
         if connected_terminals.len() > dev_info.terminals.len() {
@@ -155,33 +334,49 @@ impl Circuit {
             );
         }
 
-        Ok(self.elaborate_terminals(connected_terminals))
+        Ok(self.elaborate_terminals(connected_terminals, hier))
     }
 
-    fn elaborate_terminals(&mut self, connected_terminals: Vec<String>) -> Vec<Node> {
-        connected_terminals.into_iter().map(|terminal| self.node(terminal)).collect()
+    fn elaborate_terminals(
+        &mut self,
+        connected_terminals: &[String],
+        hier: &Hierarchy,
+    ) -> Vec<Node> {
+        connected_terminals.iter().map(|terminal| self.elaborate_node(terminal, hier)).collect()
     }
 
     pub fn elaborate_instance(
         &mut self,
         instance: CircuitInstanceDescription,
     ) -> Result<InstanceId> {
-        let inst = match self.namespace.get(&instance.master).copied() {
+        self.elaborate_instance_in(&instance, &Hierarchy::default())
+    }
+
+    fn elaborate_instance_in(
+        &mut self,
+        instance: &CircuitInstanceDescription,
+        hier: &Hierarchy,
+    ) -> Result<InstanceId> {
+        let name = hier.name(&instance.name);
+        let inst = match self.resolve_master(&instance.master, hier) {
             Some(NameSpaceEntry::Model(model)) => {
                 let dev = self[model].device;
-                let terminals = self.elaborate_dev_terminals(dev, instance.terminal_connections)?;
-                let inst = self.new_model_instance(instance.name, model, terminals)?;
-                for (param_name, val) in instance.parameters {
-                    self.set_instance_param(inst, &param_name, val)?;
+                let terminals =
+                    self.elaborate_dev_terminals(dev, &instance.terminal_connections, hier)?;
+                let inst = self.new_model_instance(name, model, terminals)?;
+                for (param_name, val) in &instance.parameters {
+                    self.set_instance_param(inst, param_name, *val)?;
                 }
                 inst
             }
 
             Some(NameSpaceEntry::Device(dev)) => {
-                let terminals = self.elaborate_dev_terminals(dev, instance.terminal_connections)?;
-                let (inst, model) = self.new_device_instance(instance.name, dev, terminals)?;
-                for (param_name, val) in instance.parameters {
-                    self.set_model_param(model, &param_name, val)?;
+                let terminals =
+                    self.elaborate_dev_terminals(dev, &instance.terminal_connections, hier)?;
+                let (inst, model) = self.new_device_instance(name, dev, terminals)?;
+                self[model].scope = hier.scope;
+                for (param_name, val) in &instance.parameters {
+                    self.set_model_param(model, param_name, *val)?;
                 }
                 inst
             }
@@ -194,10 +389,11 @@ impl Circuit {
             }
 
             None => {
-                bail!("'{}' not found", instance.name);
+                bail!("'{}' not found", instance.master);
             }
         };
 
+        self[inst].scope = hier.scope;
         Ok(inst)
     }
 }
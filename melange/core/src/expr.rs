@@ -30,6 +30,10 @@ impl Expr {
         Ok(ctx.intern.resolve(&self.eval(ctx)?.to_str()?))
     }
 
+    pub fn str(arena: &mut Arena, val: &str) -> Expr {
+        Expr::Value(Value::Str(arena.intern.get_or_intern(val)))
+    }
+
     pub fn param(arena: &mut Arena, param: CircuitParam) -> Expr {
         Expr::Eval(arena.alloc(ExprData::Param(param)))
     }
@@ -154,7 +158,7 @@ impl Expr {
                 };
                 Ok(ptr.into())
             }
-            Expr::Value(arg) => Ok((1.0 / arg.to_num()?).into()),
+            Expr::Value(arg) => Ok((-arg.to_num()?).into()),
         }
     }
 
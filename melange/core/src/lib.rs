@@ -1,6 +1,6 @@
 pub use crate::circuit::Circuit;
 pub use crate::elaboration::CircuitDescription;
-pub use crate::expr::{Arena, Expr, ExprEvalCtx, Value};
+pub use crate::expr::{Arena, CircuitParam, CircuitParamCtx, Expr, ExprEvalCtx, Value};
 
 // #[macro_use]
 // mod utils;
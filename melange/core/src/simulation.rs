@@ -1,6 +1,6 @@
-use std::mem::replace;
 use std::rc::Rc;
 
+use ahash::AHashMap;
 use anyhow::{bail, Context, Result};
 use cli_table::{print_stdout, Cell, Style, Table, TableStruct};
 use klu_rs::{FixedKluMatrix, KluData};
@@ -8,17 +8,30 @@ use num_complex::Complex64;
 use stdx::iter::zip;
 use typed_index_collections::{TiSlice, TiVec};
 
-use crate::circuit::{CircuitModelSrc, InstanceId, ModelId, Node};
+use crate::circuit::{CircuitModelSrc, InstanceId, ModelId, Node, ScopeId};
+pub use crate::devices::NoiseSource;
 use crate::devices::{InstanceImpl, ModelImpl, Type};
 use crate::expr::{CircuitParam, ExprEvalCtxRef};
-use crate::simulation::flags::{EvalFlags, OperatingPointAnalysis, SimulationState};
+use crate::simulation::dc_sweep::SweepTarget;
+pub use crate::simulation::dc_sweep::{DcSweep, DcSweepResult, SweepParam};
+pub use crate::simulation::flags::EvalFlags;
+use crate::simulation::flags::{OperatingPointAnalysis, SimulationState};
 pub use crate::simulation::matrix::MatrixEntryIter;
 use crate::simulation::matrix::{MatrixBuilder, SimulationMatrix};
+pub use crate::simulation::noise::{NoiseContribution, NoiseResult};
+pub use crate::simulation::tran::{IntegrationMethod, TranResult};
 use crate::utils::PrettyPrint;
 use crate::{Arena, Circuit, Value};
 
+mod continuation;
+mod dc_sweep;
 mod flags;
 mod matrix;
+mod noise;
+mod tran;
+
+/// The values of the parameters of a subcircuit instance, see [`Circuit::eval_scopes`].
+type ScopeValues = Box<[(CircuitParam, Value)]>;
 
 pub struct Simulation<'a> {
     circ: &'a Circuit,
@@ -43,7 +56,10 @@ pub struct Simulation<'a> {
 #[derive(Debug, Clone)]
 pub struct NodeInfo {
     pub name: String,
+    /// The absolute tolerance of the unknown.
     pub atol: f64,
+    /// The absolute tolerance of the residual (the equation) of the unknown.
+    pub residual_atol: f64,
     pub units: &'static str,
     pub residual_units: &'static str,
 }
@@ -52,6 +68,9 @@ pub struct SimBuilder<'a> {
     instance: InstanceId,
     matrix_builder: &'a mut MatrixBuilder,
     node_info: &'a mut TiVec<Node, NodeInfo>,
+    /// The internal unknowns created so far, an instance may request the unknowns of another
+    /// instance (see [`instance_branch`](SimBuilder::instance_branch)) before it was processed
+    internal_unknowns: AHashMap<(InstanceId, &'static str), Node>,
     circ: &'a Circuit,
     pub config: &'a SimConfig,
 }
@@ -63,26 +82,69 @@ impl<'a> SimBuilder<'a> {
     }
 
     pub fn new_internal_branch(&mut self, name: &'static str) -> Node {
-        self.new_internal_unknown(name, self.config.voltage_atol, "V", "A")
+        let config = self.config;
+        self.new_internal_unknown(name, config.current_atol, config.voltage_atol, "V", "A")
     }
 
     pub fn new_internal_node(&mut self, name: &'static str) -> Node {
-        self.new_internal_unknown(name, self.config.current_atol, "A", "V")
+        let config = self.config;
+        self.new_internal_unknown(name, config.voltage_atol, config.current_atol, "A", "V")
     }
     pub fn new_internal_unknown(
         &mut self,
         name: &'static str,
         atol: f64,
+        residual_atol: f64,
         units: &'static str,
         residual_units: &'static str,
     ) -> Node {
-        let name = format!("{}::{name}", self.circ[self.instance].name);
-        self.node_info.push_and_get_key(NodeInfo { atol, name, units, residual_units })
+        let circ = self.circ;
+        let instance = self.instance;
+        let node_info = &mut *self.node_info;
+        *self.internal_unknowns.entry((instance, name)).or_insert_with(|| {
+            let name = format!("{}::{name}", circ[instance].name);
+            node_info.push_and_get_key(NodeInfo {
+                atol,
+                residual_atol,
+                name,
+                units,
+                residual_units,
+            })
+        })
     }
 
     pub fn ensure_matrix_entry(&mut self, column: Node, row: Node) {
         self.matrix_builder.insert(self.instance, column, row)
     }
+
+    /// Resolves the name of an instance referenced by the current instance (for example the
+    /// controlling voltage source of a current controlled source). The name is first looked up
+    /// in the subcircuit the current instance belongs to and then in its parents.
+    pub fn lookup_instance(&self, name: &str) -> Option<InstanceId> {
+        let mut prefix = &*self.circ[self.instance].name;
+        while let Some(pos) = prefix.rfind('.') {
+            prefix = &prefix[..pos];
+            if let Some(inst) = self.circ.lookup_instance(&format!("{prefix}.{name}")) {
+                return Some(inst);
+            }
+        }
+        self.circ.lookup_instance(name)
+    }
+
+    /// The name of the device `instance` belongs to.
+    pub fn device_name(&self, instance: InstanceId) -> &'static str {
+        let model = self.circ[instance].model;
+        self.circ[self.circ[model].device].name
+    }
+
+    /// The branch current unknown `name` of another `instance`, created with
+    /// [`new_internal_branch`](SimBuilder::new_internal_branch).
+    pub fn instance_branch(&mut self, instance: InstanceId, name: &'static str) -> Node {
+        let current = std::mem::replace(&mut self.instance, instance);
+        let branch = self.new_internal_branch(name);
+        self.instance = current;
+        branch
+    }
 }
 
 impl Circuit {
@@ -97,6 +159,70 @@ impl Circuit {
         Ok(res)
     }
 
+    /// Evaluates the parameters of all subcircuit instances. Each instance of a subcircuit
+    /// requires different parameter values, these are therefore stored per scope and only
+    /// written to `eval_ctx` (with [`enter_scope`](Circuit::enter_scope)) while the parameters
+    /// of the models and instances inside the scope are evaluated.
+    fn eval_scopes(
+        &self,
+        mut eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+    ) -> Result<TiVec<ScopeId, ScopeValues>> {
+        let mut res: TiVec<ScopeId, ScopeValues> = TiVec::new();
+        // scopes are always created after their parent
+        for scope in self.scopes() {
+            let scope_info = &self[scope];
+            let context = |param: CircuitParam| {
+                let (name, _) =
+                    arena.lookup_param_info(param).expect("parameter belongs to the same arena");
+                format!("while evaluating parameter '{name}' of subcircuit '{}'", scope_info.name)
+            };
+
+            self.enter_scope(scope_info.parent, &res, eval_ctx.borrow());
+            let mut overrides = Vec::with_capacity(scope_info.overrides.len());
+            for &(param, val) in &scope_info.overrides {
+                let val = val.eval(eval_ctx.borrow()).with_context(|| context(param))?;
+                overrides.push((param, val));
+            }
+
+            let mut values = Vec::with_capacity(scope_info.parameters.len());
+            for &(param, default_val) in &scope_info.parameters {
+                let val = match overrides.iter().find(|(overriden, _)| *overriden == param) {
+                    Some(&(_, val)) => val,
+                    None => default_val.eval(eval_ctx.borrow()).with_context(|| context(param))?,
+                };
+                if val == Value::UNDEF {
+                    let (name, _) = arena
+                        .lookup_param_info(param)
+                        .expect("parameter belongs to the same arena");
+                    bail!(
+                        "required parameter {name} of subcircuit '{}' was not provided",
+                        scope_info.name
+                    )
+                }
+                eval_ctx.set_param(param, val);
+                values.push((param, val));
+            }
+            res.push(values.into_boxed_slice());
+        }
+        Ok(res)
+    }
+
+    /// Writes the parameter values of `scope` (and all enclosing scopes) to `eval_ctx`
+    fn enter_scope(
+        &self,
+        scope: Option<ScopeId>,
+        scope_values: &TiSlice<ScopeId, ScopeValues>,
+        mut eval_ctx: ExprEvalCtxRef,
+    ) {
+        let scopes = std::iter::successors(scope, |&scope| self[scope].parent);
+        for scope in scopes {
+            for &(param, val) in &*scope_values[scope] {
+                eval_ctx.set_param(param, val);
+            }
+        }
+    }
+
     pub fn setup_simulation(&self, config: SimConfig) -> Result<Simulation> {
         let model_data: Box<TiSlice<_, _>> = self
             .models()
@@ -118,7 +244,8 @@ impl Circuit {
             .nodes()
             .map(|node| NodeInfo {
                 name: self.node_name(node).to_owned(),
-                atol: config.current_atol,
+                atol: config.voltage_atol,
+                residual_atol: config.current_atol,
                 units: "V",
                 residual_units: "A",
             })
@@ -201,7 +328,18 @@ impl Simulation<'_> {
         Ok(Self::matrix_table(&self.nodes, matrix))
     }
 
-    pub fn prepare_solver(&mut self, mut eval_ctx: ExprEvalCtxRef, arena: &Arena) -> Result<()> {
+    pub fn prepare_solver(&mut self, eval_ctx: ExprEvalCtxRef, arena: &Arena) -> Result<()> {
+        self.prepare_solver_with(eval_ctx, arena, &[])
+    }
+
+    /// Like [`prepare_solver`](Simulation::prepare_solver) but the parameters in `overrides`
+    /// are set to the given values instead (used by [`dc_sweep`](Simulation::dc_sweep)).
+    fn prepare_solver_with(
+        &mut self,
+        mut eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+        overrides: &[(SweepTarget, f64)],
+    ) -> Result<()> {
         self.wipe_solution();
         for param in arena.ctx_params(self.circ.ctx) {
             if self.circ.param_assignments.contains_key(&param) {
@@ -219,9 +357,21 @@ impl Simulation<'_> {
             let val = val.eval(eval_ctx.borrow());
             eval_ctx.set_param(param, val?);
         }
+        for &(target, val) in overrides {
+            if let SweepTarget::Circuit(param) = target {
+                eval_ctx.set_param(param, val.into());
+            }
+        }
+
+        let scope_values = self.circ.eval_scopes(eval_ctx.borrow(), arena)?;
+        let mut scope = None;
 
         for model in self.circ.models() {
             let model_info = &self.circ[model];
+            if model_info.scope != scope {
+                scope = model_info.scope;
+                self.circ.enter_scope(scope, &scope_values, eval_ctx.borrow());
+            }
             let model_data = &self.model_data[model];
             let dev = &self.circ[model_info.device];
             for &(param, val) in &model_info.parameters {
@@ -267,6 +417,7 @@ impl Simulation<'_> {
             instance: 0u32.into(),
             matrix_builder: &mut self.matrix_builder,
             node_info: &mut self.nodes,
+            internal_unknowns: AHashMap::new(),
             config: &self.config,
         };
 
@@ -274,6 +425,10 @@ impl Simulation<'_> {
 
         for inst in self.circ.instances() {
             let instance_info = &self.circ[inst];
+            if instance_info.scope != scope {
+                scope = instance_info.scope;
+                self.circ.enter_scope(scope, &scope_values, eval_ctx.borrow());
+            }
             let instance_data = &mut self.instance_data[inst];
             let dev = &self.circ[self.circ[instance_info.model].device];
             for &(param, val) in &instance_info.parameters {
@@ -302,11 +457,20 @@ impl Simulation<'_> {
                 }
             }
 
+            for &(target, val) in overrides {
+                if let SweepTarget::Instance(overridden, param) = target {
+                    if overridden == inst {
+                        instance_data.set_real_param(param, val);
+                    }
+                }
+            }
+
             builder.process_instance(inst);
             instance_data.process_params(temp, &mut builder, &self.circ[inst].connections)?;
         }
 
         let num_nodes = self.nodes.len();
+        self.matrix_builder.ensure_diagonal(num_nodes);
         self.solution.resize(num_nodes, 0f64);
         self.residual_resist.resize(num_nodes, 0f64);
         self.residual_react.resize(num_nodes, 0f64);
@@ -347,15 +511,15 @@ impl Simulation<'_> {
         self.state = SimulationState::AT_AC_OP;
     }
 
-    // pub fn noise_op(&mut self) -> Result<&TiSlice<Node, f64>> {
-    //     self.solve_op(OperatingPointAnalysis::Noise)?;
-    //     Ok(&self.solution)
-    // }
+    pub fn noise_op(&mut self) -> Result<&TiSlice<Node, f64>> {
+        self.solve_op(OperatingPointAnalysis::Noise)?;
+        Ok(&self.solution)
+    }
 
-    // pub fn restore_noise_op(&mut self, op: &TiSlice<Node, f64>) {
-    //     self.solution.copy_from_slice(op);
-    //     self.stat = SimulationState::AT_NOISE_OP;
-    // }
+    pub fn restore_noise_op(&mut self, op: &TiSlice<Node, f64>) {
+        self.solution.copy_from_slice(op);
+        self.state = SimulationState::AT_NOISE_OP;
+    }
 
     pub fn set_initial_guess(&mut self, guess: &TiSlice<Node, f64>) {
         self.solution.copy_from_slice(guess);
@@ -370,37 +534,99 @@ impl Simulation<'_> {
         }
 
         let flags = analysis.eval_flags();
+        let start = self.solution.clone();
+        let mut res = self.newton(flags, NewtonOpts { init_lim: true, ..NewtonOpts::default() })?;
+        if !res.is_converged() {
+            res = self.continuation(flags, &start, res)?;
+        }
+
+        match res {
+            NewtonResult::Converged(_) => (),
+            NewtonResult::NotConverged => {
+                bail!("Simulation failed to converge after {} iterations", self.config.maxiters)
+            }
+            NewtonResult::Singular => bail!("matrix is singular"),
+        }
+
+        self.state = op_flag;
+        Ok(())
+    }
 
+    /// Solves the nonlinear system with Newton's method starting at the current solution.
+    /// `opts` contains additional terms added to the system (see [`NewtonOpts`]).
+    ///
+    /// The solution is only accepted once both the residual and the last update are within
+    /// the tolerances and `$limit` is no longer active.
+    fn newton(&mut self, flags: EvalFlags, opts: NewtonOpts<'_>) -> Result<NewtonResult> {
         let debug = self.config.debug;
         let matrix =
             self.matrix.as_mut().context("Simulation must be populated before it can run")?;
 
         let mut i = 0;
         loop {
-            let sim_info = SimInfo { abstime: 0f64, prev_solve: &self.solution, flags };
+            matrix.nonlinear_matrix.write_zero();
+            self.residual_resist.raw.fill(0f64);
+            self.residual_react.raw.fill(0f64);
+
+            let flags = if opts.init_lim && i == 0 { flags | EvalFlags::INIT_LIM } else { flags };
+            let sim_info = SimInfo {
+                abstime: opts.abstime,
+                prev_solve: &self.solution,
+                flags,
+                source_factor: opts.source_factor,
+            };
+            let mut limited = false;
             for inst in &mut *self.instance_data {
                 inst.eval(sim_info)?;
+                limited |= inst.is_limited();
 
                 // this is save because we call populate_matrix_ptrs during Simulation construction
-                unsafe { inst.load_matrix_resist() }
+                match opts.integration {
+                    Some((alpha, _)) => unsafe { inst.load_matrix_tran(alpha) },
+                    None => unsafe { inst.load_matrix_resist() },
+                }
                 inst.load_residual_resist(&self.solution, &mut self.residual_resist);
-
-                if analysis.time_integration() {
-                    let alpha = 0.0;
-                    unsafe { inst.load_matrix_react(alpha) }
+                if flags.contains(EvalFlags::CALC_RESIST_LIM_RHS) {
+                    inst.load_limit_rhs_resist(&mut self.residual_resist);
+                }
+                if flags.contains(EvalFlags::CALC_REACT_RESIDUAL) {
                     inst.load_residual_react(&self.solution, &mut self.residual_react);
                 }
+                if flags.contains(EvalFlags::CALC_REACT_LIM_RHS) {
+                    inst.load_limit_rhs_react(&mut self.residual_react);
+                }
             }
 
-            // TODO time integration for tran analysis
+            if let Some((alpha, history)) = opts.integration {
+                for ((dst, charge), history) in
+                    zip(&mut self.residual_resist.raw, &self.residual_react.raw).zip(&history.raw)
+                {
+                    *dst += alpha * charge + history;
+                }
+            }
+
+            if let Some((conductance, reference)) = opts.shunt {
+                matrix.load_shunt(conductance, self.nodes.len());
+                for ((dst, val), reference) in
+                    zip(&mut self.residual_resist.raw[1..], &self.solution.raw[1..])
+                        .zip(&reference.raw[1..])
+                {
+                    *dst += conductance * (val - reference);
+                }
+            }
 
             if debug {
                 print_stdout(Self::matrix_table(&self.nodes, &matrix.nonlinear_matrix)).unwrap();
             }
 
+            // the residual is overwritten by the solution of the linear system
+            let mut found_solution = !limited
+                && zip(&self.residual_resist.raw[1..], &self.nodes.raw[1..])
+                    .all(|(residual, node_info)| residual.abs() <= node_info.residual_atol);
+
             let singular = matrix.nonlinear_matrix.lu_factorize(None);
             if singular {
-                bail!("matrix is singular")
+                return Ok(NewtonResult::Singular);
             }
 
             matrix.nonlinear_matrix.solve_linear_system(&mut self.residual_resist.raw[1..]);
@@ -409,18 +635,25 @@ impl Simulation<'_> {
                 print_stdout(Self::vec_table(&self.residual_resist.raw, &self.nodes.raw)).unwrap();
             }
 
-            // reset matrix
-            matrix.nonlinear_matrix.write_zero();
-            let mut found_solution = true;
-            for ((dst, delta), node_info) in
-                zip(&mut self.solution.raw[1..], &mut self.residual_resist.raw[1..])
+            // damping: large updates are scaled down (the direction is preserved)
+            let max_delta =
+                self.residual_resist.raw[1..].iter().fold(0f64, |max, delta| max.max(delta.abs()));
+            let damping = if max_delta > self.config.max_update {
+                found_solution = false;
+                self.config.max_update / max_delta
+            } else {
+                1.0
+            };
+
+            for ((dst, &delta), node_info) in
+                zip(&mut self.solution.raw[1..], &self.residual_resist.raw[1..])
                     .zip(&self.nodes.raw[1..])
             {
-                let delta = replace(delta, 0f64);
+                let delta = damping * delta;
                 let new_val = *dst - delta;
-                let atol = node_info.atol;
-                let tol = atol.max(new_val * self.config.rtol);
-                if delta > tol {
+                let tol = node_info.atol.max(new_val.abs() * self.config.rtol);
+                // also catches NaN
+                if delta.abs() > tol || delta.is_nan() {
                     found_solution = false;
                 }
                 *dst = new_val;
@@ -430,18 +663,15 @@ impl Simulation<'_> {
                 print_stdout(Self::vec_table(&self.solution.raw, &self.nodes.raw)).unwrap();
             }
 
-            if found_solution && i > 0 {
-                break;
-            }
             i += 1;
+            if found_solution {
+                return Ok(NewtonResult::Converged(i));
+            }
 
             if i == self.config.maxiters {
-                bail!("Simulation failed to converge after {i} iterations")
+                return Ok(NewtonResult::NotConverged);
             }
         }
-
-        self.state = op_flag;
-        Ok(())
     }
 
     pub fn set_omega(&mut self, omega: f64) {
@@ -466,13 +696,18 @@ impl Simulation<'_> {
         if self.state.contains(SimulationState::HAS_AC_EVAL) {
             for inst in &mut *self.instance_data {
                 unsafe {
+                    inst.load_matrix_resist();
                     inst.load_matrix_react(self.omega);
                 }
                 inst.load_ac_residual(&self.solution, &mut self.ac_solution);
             }
         } else {
-            let sim_info =
-                SimInfo { abstime: 0f64, prev_solve: &self.solution, flags: EvalFlags::AC };
+            let sim_info = SimInfo {
+                abstime: 0f64,
+                prev_solve: &self.solution,
+                flags: EvalFlags::AC,
+                source_factor: 1.0,
+            };
             for inst in &mut *self.instance_data {
                 inst.eval(sim_info)?;
 
@@ -483,11 +718,11 @@ impl Simulation<'_> {
                 inst.load_ac_residual(&self.solution, &mut self.ac_solution);
             }
             self.state.insert(SimulationState::HAS_AC_EVAL);
+        }
 
-            for (dst, src) in zip(matrix.ac_matrix.data(), matrix.nonlinear_matrix.data()) {
-                let val = Complex64::new(src.get(), dst.get().im);
-                dst.set(val);
-            }
+        for (dst, src) in zip(matrix.ac_matrix.data(), matrix.nonlinear_matrix.data()) {
+            let val = Complex64::new(src.get(), dst.get().im);
+            dst.set(val);
         }
 
         if self.config.debug {
@@ -527,6 +762,26 @@ pub struct SimConfig {
     pub voltage_atol: f64,
     pub current_atol: f64,
     pub rtol: f64,
+    /// The method used to integrate the reactive residuals during transient analysis.
+    pub integration: IntegrationMethod,
+    /// The factor by which the local truncation error may exceed the Newton tolerances
+    /// (`rtol`/`atol`) during transient analysis.
+    pub trtol: f64,
+    /// Newton updates whose largest component exceeds this value are scaled down (damped).
+    /// Disabled (infinite) by default.
+    pub max_update: f64,
+    /// Continuation methods tried (in this order) if Newton's method fails to find an
+    /// operating point.
+    pub gmin_stepping: bool,
+    pub source_stepping: bool,
+    pub pseudo_transient: bool,
+    /// The conductance (from every unknown to ground) gmin stepping starts at.
+    pub gmin_start: f64,
+    /// Gmin stepping (and pseudo transient analysis) removes the conductance once it falls
+    /// below this value.
+    pub gmin_stop: f64,
+    /// The maximum number of steps (Newton solves) of each continuation method.
+    pub max_continuation_steps: u32,
 }
 
 impl Default for SimConfig {
@@ -537,6 +792,15 @@ impl Default for SimConfig {
             voltage_atol: 1e-6,
             current_atol: 1e-12,
             rtol: 1e-3,
+            integration: IntegrationMethod::Trapezoidal,
+            trtol: 7.0,
+            max_update: f64::INFINITY,
+            gmin_stepping: true,
+            source_stepping: true,
+            pseudo_transient: true,
+            gmin_start: 1e-3,
+            gmin_stop: 1e-12,
+            max_continuation_steps: 200,
         }
     }
 }
@@ -546,4 +810,49 @@ pub struct SimInfo<'a> {
     pub abstime: f64,
     pub prev_solve: &'a TiSlice<Node, f64>,
     pub flags: EvalFlags,
+    /// The factor by which all independent sources are scaled (used for source stepping).
+    pub source_factor: f64,
+}
+
+/// The additional terms of the system solved by [`Simulation::newton`].
+#[derive(Clone, Copy)]
+struct NewtonOpts<'a> {
+    abstime: f64,
+    /// The coefficients that approximate the time derivative of the reactive residual (the
+    /// charges) during transient analysis: `alpha * q + history`.
+    integration: Option<(f64, &'a TiSlice<Node, f64>)>,
+    /// A conductance from every unknown to a reference solution: `g * (x - reference)`.
+    /// Used by gmin stepping and pseudo transient analysis.
+    shunt: Option<(f64, &'a TiSlice<Node, f64>)>,
+    source_factor: f64,
+    /// Evaluate the first iteration with `INIT_LIM` so that `$limit` starts at the initial
+    /// junction voltages provided by the models.
+    init_lim: bool,
+}
+
+impl Default for NewtonOpts<'_> {
+    fn default() -> Self {
+        NewtonOpts {
+            abstime: 0f64,
+            integration: None,
+            shunt: None,
+            source_factor: 1.0,
+            init_lim: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NewtonResult {
+    /// Converged after the given number of iterations.
+    Converged(u32),
+    /// Did not converge within `maxiters` iterations.
+    NotConverged,
+    Singular,
+}
+
+impl NewtonResult {
+    fn is_converged(self) -> bool {
+        matches!(self, NewtonResult::Converged(_))
+    }
 }
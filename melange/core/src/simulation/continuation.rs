@@ -0,0 +1,173 @@
+//! Continuation (homotopy) methods used when Newton's method fails to find an operating point
+//! from the initial guess. Each method solves a sequence of modified problems that starts at
+//! an easy problem and ends at the original circuit, every solution is the initial guess of
+//! the next step.
+
+use anyhow::Result;
+use typed_index_collections::{TiSlice, TiVec};
+
+use crate::circuit::Node;
+use crate::simulation::flags::EvalFlags;
+use crate::simulation::{NewtonOpts, NewtonResult, Simulation};
+
+/// The largest conductance gmin stepping tries to find an initial solution.
+const GMIN_MAX: f64 = 1.0;
+/// The largest factor by which gmin is reduced in a single step.
+const GMIN_MAX_FACTOR: f64 = 10.0;
+/// The initial step of source stepping.
+const SOURCE_STEP: f64 = 0.1;
+/// Source stepping gives up once the step becomes smaller than this.
+const SOURCE_MIN_STEP: f64 = 1e-6;
+/// The conductance that corresponds to the first pseudo time step.
+const PTRAN_START: f64 = 1.0;
+/// Pseudo transient analysis gives up once the conductance grows larger than this.
+const PTRAN_MAX: f64 = 1e9;
+
+type ContinuationMethod<'a> = fn(&mut Simulation<'a>, EvalFlags) -> Result<NewtonResult>;
+
+impl<'a> Simulation<'a> {
+    /// Tries all enabled continuation methods (starting at `start`) until one converges.
+    /// Returns `failure` if all methods are disabled.
+    pub(super) fn continuation(
+        &mut self,
+        flags: EvalFlags,
+        start: &TiSlice<Node, f64>,
+        failure: NewtonResult,
+    ) -> Result<NewtonResult> {
+        let mut res = failure;
+        let methods: [(bool, ContinuationMethod<'a>); 3] = [
+            (self.config.gmin_stepping, Self::gmin_stepping),
+            (self.config.source_stepping, Self::source_stepping),
+            (self.config.pseudo_transient, Self::pseudo_transient),
+        ];
+        for (enabled, method) in methods {
+            if !enabled {
+                continue;
+            }
+            self.solution.copy_from_slice(start);
+            res = method(self, flags)?;
+            if res.is_converged() {
+                break;
+            }
+        }
+        Ok(res)
+    }
+
+    /// Adds a conductance from every unknown to ground that is reduced step by step until it
+    /// can be removed.
+    fn gmin_stepping(&mut self, flags: EvalFlags) -> Result<NewtonResult> {
+        let ground: TiVec<Node, f64> = vec![0f64; self.solution.len()].into();
+        let start = self.solution.clone();
+
+        // find a conductance that is large enough for Newton's method to converge
+        let mut gmin = self.config.gmin_start;
+        loop {
+            let opts = NewtonOpts {
+                shunt: Some((gmin, &ground)),
+                init_lim: true,
+                ..NewtonOpts::default()
+            };
+            let res = self.newton(flags, opts)?;
+            if res.is_converged() {
+                break;
+            }
+            gmin *= GMIN_MAX_FACTOR;
+            if gmin > GMIN_MAX {
+                return Ok(res);
+            }
+            self.solution.copy_from_slice(&start);
+        }
+
+        let mut factor = GMIN_MAX_FACTOR;
+        let mut prev = self.solution.clone();
+        for _ in 0..self.config.max_continuation_steps {
+            let next = gmin / factor;
+            let shunt = (next >= self.config.gmin_stop).then_some((next, &*ground));
+            let res = self.newton(flags, NewtonOpts { shunt, ..NewtonOpts::default() })?;
+            match (res.is_converged(), shunt.is_some()) {
+                (true, false) => return Ok(res),
+                (true, true) => {
+                    gmin = next;
+                    factor = (factor * 2.0).min(GMIN_MAX_FACTOR);
+                    prev.copy_from_slice(&self.solution);
+                }
+                (false, _) => {
+                    self.solution.copy_from_slice(&prev);
+                    factor = factor.sqrt();
+                    if factor < 1.001 {
+                        return Ok(res);
+                    }
+                }
+            }
+        }
+        Ok(NewtonResult::NotConverged)
+    }
+
+    /// Ramps all independent sources from zero to their full value.
+    fn source_stepping(&mut self, flags: EvalFlags) -> Result<NewtonResult> {
+        let opts = NewtonOpts { source_factor: 0.0, init_lim: true, ..NewtonOpts::default() };
+        let mut res = self.newton(flags, opts)?;
+        if !res.is_converged() {
+            return Ok(res);
+        }
+
+        let mut factor = 0.0;
+        let mut step = SOURCE_STEP;
+        let mut prev = self.solution.clone();
+        for _ in 0..self.config.max_continuation_steps {
+            let next = (factor + step).min(1.0);
+            res =
+                self.newton(flags, NewtonOpts { source_factor: next, ..NewtonOpts::default() })?;
+            if res.is_converged() {
+                if next == 1.0 {
+                    return Ok(res);
+                }
+                factor = next;
+                step *= 2.0;
+                prev.copy_from_slice(&self.solution);
+            } else {
+                self.solution.copy_from_slice(&prev);
+                step /= 4.0;
+                if step < SOURCE_MIN_STEP {
+                    return Ok(res);
+                }
+            }
+        }
+        Ok(NewtonResult::NotConverged)
+    }
+
+    /// Adds a capacitance from every unknown to ground and integrates (with backward euler)
+    /// until a steady state is reached. The capacitor is represented by its companion
+    /// conductance (`C/h`) which is reduced as the pseudo time step grows.
+    fn pseudo_transient(&mut self, flags: EvalFlags) -> Result<NewtonResult> {
+        let mut prev = self.solution.clone();
+        let mut conductance = PTRAN_START;
+        let mut init_lim = true;
+        for _ in 0..self.config.max_continuation_steps {
+            // once the step is large enough the capacitors are removed (steady state)
+            let shunt = (conductance >= self.config.gmin_stop).then_some((conductance, &*prev));
+            let res =
+                self.newton(flags, NewtonOpts { shunt, init_lim, ..NewtonOpts::default() })?;
+            match (res.is_converged(), shunt.is_some()) {
+                (true, false) => return Ok(res),
+                (true, true) => {
+                    init_lim = false;
+                    conductance /= 4.0;
+                    prev.copy_from_slice(&self.solution);
+                }
+                (false, false) => {
+                    self.solution.copy_from_slice(&prev);
+                    conductance = self.config.gmin_stop;
+                }
+                (false, true) => {
+                    self.solution.copy_from_slice(&prev);
+                    conductance *= 8.0;
+                    if conductance > PTRAN_MAX {
+                        return Ok(res);
+                    }
+                }
+            }
+        }
+        Ok(NewtonResult::NotConverged)
+    }
+}
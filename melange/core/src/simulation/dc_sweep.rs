@@ -0,0 +1,249 @@
+//! DC sweeps: the operating point is solved for every value of one or multiple (nested) swept
+//! parameters. Each point starts from the solution of the previous point. Steps for which
+//! Newton's method fails to converge are subdivided so that the solution can follow the
+//! parameter change.
+
+use anyhow::{bail, Context, Result};
+use stdx::iter::zip;
+use typed_index_collections::TiVec;
+
+use crate::circuit::{InstanceId, Node};
+use crate::devices::{ParamId, Type};
+use crate::expr::{CircuitParam, ExprEvalCtxRef};
+use crate::simulation::flags::OperatingPointAnalysis;
+use crate::simulation::{NewtonOpts, Simulation};
+use crate::Arena;
+
+/// Steps are subdivided at most until they are this fraction of the distance between two
+/// sweep points, afterwards the continuation methods of the operating point analysis are used.
+const MIN_STEP: f64 = 1e-3;
+
+/// A parameter changed by a DC sweep.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SweepParam {
+    /// A circuit parameter (see [`Circuit::def_param`](crate::Circuit::def_param)). The
+    /// swept value overwrites the default of the parameter.
+    Circuit(CircuitParam),
+    /// A real instance parameter, for example the `dc` value of a source.
+    Instance(InstanceId, String),
+}
+
+/// The values assigned to a parameter by a DC sweep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcSweep {
+    pub param: SweepParam,
+    pub values: Vec<f64>,
+}
+
+/// The result of [`Simulation::dc_sweep`].
+#[derive(Debug, Clone)]
+pub struct DcSweepResult {
+    /// The value of every swept parameter (in the order of the sweeps) at every point.
+    pub sweep_values: Vec<Vec<f64>>,
+    /// The value of each unknown at every point.
+    pub solutions: TiVec<Node, Vec<f64>>,
+    /// The current into every terminal of each instance at every point.
+    pub lead_currents: TiVec<InstanceId, Box<[Vec<f64>]>>,
+}
+
+impl DcSweepResult {
+    pub fn num_points(&self) -> usize {
+        self.sweep_values.first().map_or(0, Vec::len)
+    }
+
+    pub fn waveform(&self, node: Node) -> &[f64] {
+        &self.solutions[node]
+    }
+
+    pub fn lead_current(&self, instance: InstanceId, terminal: usize) -> &[f64] {
+        &self.lead_currents[instance][terminal]
+    }
+}
+
+/// A [`SweepParam`] after the name of instance parameters has been resolved.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum SweepTarget {
+    Circuit(CircuitParam),
+    Instance(InstanceId, ParamId),
+}
+
+impl Simulation<'_> {
+    /// Solves the DC operating point for every combination of the values of `sweeps`.
+    /// `sweeps[0]` is the innermost sweep: its values are swept for each value of
+    /// `sweeps[1]` and so on.
+    ///
+    /// Afterwards the circuit parameters in `eval_ctx` are restored and the simulation is
+    /// prepared for the original parameter values again.
+    pub fn dc_sweep(
+        &mut self,
+        mut eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+        sweeps: &[DcSweep],
+    ) -> Result<DcSweepResult> {
+        if sweeps.is_empty() || sweeps.iter().any(|sweep| sweep.values.is_empty()) {
+            bail!("a dc sweep requires at least one value for every swept parameter")
+        }
+        let targets = sweeps
+            .iter()
+            .map(|sweep| self.resolve_sweep_param(&sweep.param))
+            .collect::<Result<Vec<_>>>()?;
+        let defaults: Vec<_> = targets
+            .iter()
+            .filter_map(|&target| match target {
+                SweepTarget::Circuit(param) => Some((param, eval_ctx[param])),
+                SweepTarget::Instance(..) => None,
+            })
+            .collect();
+
+        let res = self.run_dc_sweep(eval_ctx.borrow(), arena, sweeps, &targets);
+        for (param, val) in defaults {
+            eval_ctx.set_param(param, val);
+        }
+        self.prepare_solver(eval_ctx, arena)?;
+        res
+    }
+
+    fn resolve_sweep_param(&self, param: &SweepParam) -> Result<SweepTarget> {
+        let (instance, name) = match *param {
+            SweepParam::Circuit(param) => return Ok(SweepTarget::Circuit(param)),
+            SweepParam::Instance(instance, ref name) => (instance, name),
+        };
+        let info = self.circ.instance_info(instance).context("unknown instance")?;
+        let dev = &self.circ[self.circ[info.model].device];
+        let Some((param, param_info)) = dev.parameters.lookup_param(name) else {
+            bail!("unknown parameter '{name}' for {}", dev.name)
+        };
+        if !param_info.is_instance_param || param_info.ty != Type::Real {
+            bail!("'{name}' is not a real instance parameter of {} and can not be swept", dev.name)
+        }
+        Ok(SweepTarget::Instance(instance, param))
+    }
+
+    fn run_dc_sweep(
+        &mut self,
+        mut eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+        sweeps: &[DcSweep],
+        targets: &[SweepTarget],
+    ) -> Result<DcSweepResult> {
+        let num_points = sweeps.iter().map(|sweep| sweep.values.len()).product();
+        let mut res = DcSweepResult {
+            sweep_values: vec![Vec::with_capacity(num_points); sweeps.len()],
+            solutions: TiVec::new(),
+            lead_currents: TiVec::new(),
+        };
+
+        let mut prev: Option<Vec<f64>> = None;
+        for i in 0..num_points {
+            let mut stride = 1;
+            let point: Vec<f64> = sweeps
+                .iter()
+                .map(|sweep| {
+                    let val = sweep.values[(i / stride) % sweep.values.len()];
+                    stride *= sweep.values.len();
+                    val
+                })
+                .collect();
+
+            let solved = match prev {
+                Some(ref prev) => self.sweep_step(eval_ctx.borrow(), arena, targets, prev, &point),
+                None => self
+                    .prepare_sweep_point(eval_ctx.borrow(), arena, targets, &point)
+                    .and_then(|_| self.solve_op(OperatingPointAnalysis::DC)),
+            };
+            solved.with_context(|| format!("dc sweep failed at {point:?}"))?;
+            self.record_sweep_point(&mut res, &point, num_points);
+            prev = Some(point);
+        }
+
+        Ok(res)
+    }
+
+    /// Moves the swept parameters from `from` (where the current solution was found) to `to`.
+    /// Steps for which Newton's method does not converge are halved until they become
+    /// smaller than [`MIN_STEP`].
+    fn sweep_step(
+        &mut self,
+        mut eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+        targets: &[SweepTarget],
+        from: &[f64],
+        to: &[f64],
+    ) -> Result<()> {
+        let analysis = OperatingPointAnalysis::DC;
+        let mut point = to.to_vec();
+        let mut done = 0.0;
+        let mut step = 1.0;
+        while step >= MIN_STEP {
+            let fraction = f64::min(done + step, 1.0);
+            for ((dst, &from), &to) in zip(&mut point, from).zip(to) {
+                *dst = from + fraction * (to - from);
+            }
+
+            let start = self.solution.clone();
+            self.prepare_sweep_point(eval_ctx.borrow(), arena, targets, &point)?;
+            let opts = NewtonOpts { init_lim: true, ..NewtonOpts::default() };
+            if self.newton(analysis.eval_flags(), opts)?.is_converged() {
+                self.state = analysis.solution_flags();
+                if fraction == 1.0 {
+                    return Ok(());
+                }
+                done = fraction;
+                step *= 2.0;
+            } else {
+                self.solution.copy_from_slice(&start);
+                step /= 2.0;
+            }
+        }
+
+        self.prepare_sweep_point(eval_ctx, arena, targets, to)?;
+        self.solve_op(analysis)
+    }
+
+    /// Prepares the simulation for the swept parameters `values`, the current solution is
+    /// kept as the initial guess.
+    fn prepare_sweep_point(
+        &mut self,
+        eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+        targets: &[SweepTarget],
+        values: &[f64],
+    ) -> Result<()> {
+        let guess = self.solution.clone();
+        let overrides: Vec<_> = zip(targets.iter().copied(), values.iter().copied()).collect();
+        self.prepare_solver_with(eval_ctx, arena, &overrides)?;
+        self.set_initial_guess(&guess);
+        Ok(())
+    }
+
+    fn record_sweep_point(&self, res: &mut DcSweepResult, point: &[f64], num_points: usize) {
+        for (dst, &val) in zip(&mut res.sweep_values, point) {
+            dst.push(val);
+        }
+
+        if res.solutions.is_empty() {
+            res.solutions = vec![Vec::with_capacity(num_points); self.solution.len()].into();
+            res.lead_currents = self
+                .circ
+                .instances()
+                .map(|inst| {
+                    let terminals = self.circ[inst].connections.len();
+                    vec![Vec::with_capacity(num_points); terminals].into_boxed_slice()
+                })
+                .collect();
+        }
+        for (dst, &val) in zip(&mut res.solutions, &self.solution) {
+            dst.push(val);
+        }
+
+        let mut currents = Vec::new();
+        for (inst, dst) in res.lead_currents.iter_mut_enumerated() {
+            currents.clear();
+            currents.resize(dst.len(), 0.0);
+            self.instance_data[inst].load_lead_current_resist(&self.solution, &mut currents);
+            for (dst, &current) in zip(dst.iter_mut(), &currents) {
+                dst.push(current);
+            }
+        }
+    }
+}
@@ -8,6 +8,10 @@ bitflags! {
         const CALC_REACT_JACOBIAN = CALC_REACT_JACOBIAN;
         const CALC_RESIST_RESIDUAL = CALC_RESIST_RESIDUAL;
         const CALC_REACT_RESIDUAL = CALC_REACT_RESIDUAL;
+        const CALC_RESIST_LIM_RHS = CALC_RESIST_LIM_RHS;
+        const CALC_REACT_LIM_RHS = CALC_REACT_LIM_RHS;
+        const ENABLE_LIM = ENABLE_LIM;
+        const INIT_LIM = INIT_LIM;
         const CALC_NOISE = CALC_NOISE;
         const ANALYSIS_DC = ANALYSIS_DC;
         const ANALYSIS_AC = ANALYSIS_AC;
@@ -33,36 +37,42 @@ macro_rules! private_flags {
 }
 
 private_flags! {
-    pub(super) const OP =
-        CALC_RESIST_JACOBIAN | CALC_RESIST_RESIDUAL | ANALYSIS_STATIC;
+    pub(super) const OP = CALC_RESIST_JACOBIAN
+        | CALC_RESIST_RESIDUAL
+        | CALC_RESIST_LIM_RHS
+        | ENABLE_LIM
+        | ANALYSIS_STATIC;
     pub(super) const DC_OP = OP | ANALYSIS_DC;
     pub(super) const AC_OP = OP | ANALYSIS_AC;
-    // pub(super) const NOISE_OP = Self::OP.0.bits | ANALYSIS_NOISE;
-    // pub(super) const LARGE_SIGNAL_IC_OP = Self::OP.0.bits | ANALYSIS_TRAN | ANALYSIS_IC;
+    pub(super) const NOISE_OP = OP | ANALYSIS_NOISE;
+    // the charges at the initial operating point are the starting point of the time integration
+    pub(super) const LARGE_SIGNAL_IC_OP = OP | CALC_REACT_RESIDUAL | ANALYSIS_TRAN | ANALYSIS_IC;
 
     pub(super) const AC = CALC_RESIST_JACOBIAN | CALC_REACT_JACOBIAN | ANALYSIS_AC;
-    // pub(super) const NOISE = CALC_RESIST_JACOBIAN | CALC_REACT_JACOBIAN | CALC_NOISE | ANALYSIS_NOISE;
-    // pub(super) const LARGE_SIGNAL = ANALYSIS_TRAN
-    //     | CALC_RESIST_JACOBIAN
-    //     | CALC_RESIST_RESIDUAL
-    //     | CALC_REACT_JACOBIAN
-    //     | CALC_REACT_RESIDUAL;
+    pub(super) const NOISE = CALC_RESIST_JACOBIAN | CALC_REACT_JACOBIAN | CALC_NOISE | ANALYSIS_NOISE;
+    pub(super) const LARGE_SIGNAL = ANALYSIS_TRAN
+        | CALC_RESIST_JACOBIAN
+        | CALC_RESIST_RESIDUAL
+        | CALC_RESIST_LIM_RHS
+        | CALC_REACT_JACOBIAN
+        | CALC_REACT_RESIDUAL
+        | CALC_REACT_LIM_RHS
+        | ENABLE_LIM;
 }
 
-// impl EvalFlags {
-//     pub(super) const TRAN_IC_OP: Self = Self::LARGE_SIGNAL_IC_OP;
-//     pub(super) const HB_IC_OP: Self = Self::LARGE_SIGNAL_IC_OP;
-//     pub(super) const HB: Self = Self::LARGE_SIGNAL;
-//     pub(super) const TRAN: Self = Self::LARGE_SIGNAL;
-// }
+impl EvalFlags {
+    pub(super) const TRAN_IC_OP: Self = Self::LARGE_SIGNAL_IC_OP;
+    // pub(super) const HB_IC_OP: Self = Self::LARGE_SIGNAL_IC_OP;
+    // pub(super) const HB: Self = Self::LARGE_SIGNAL;
+    pub(super) const TRAN: Self = Self::LARGE_SIGNAL;
+}
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub(super) enum OperatingPointAnalysis {
     DC,
     AC,
-    // Noise,
-    // TranIc,
-    // Tran,
+    Noise,
+    TranIc,
     // HBIc,
 }
 
@@ -71,25 +81,18 @@ impl OperatingPointAnalysis {
         match self {
             OperatingPointAnalysis::DC => EvalFlags::DC_OP,
             OperatingPointAnalysis::AC => EvalFlags::AC_OP,
-            // OperatingPointAnalysis::Noise => EvalFlags::NOISE_OP,
-            // OperatingPointAnalysis::TranIc => EvalFlags::TRAN_IC_OP,
-            // OperatingPointAnalysis::Tran => EvalFlags::TRAN,
+            OperatingPointAnalysis::Noise => EvalFlags::NOISE_OP,
+            OperatingPointAnalysis::TranIc => EvalFlags::TRAN_IC_OP,
             // OperatingPointAnalysis::HBIc => EvalFlags::HB_IC_OP,
         }
     }
 
-    pub fn time_integration(self) -> bool {
-        // matches!(self, OperatingPointAnalysis::Tran)
-        false
-    }
-
     pub fn solution_flags(self) -> SimulationState {
         match self {
             OperatingPointAnalysis::DC => SimulationState::AT_DC_OP,
             OperatingPointAnalysis::AC => SimulationState::AT_AC_OP,
-            // OperatingPointAnalysis::Noise => SimulationState::AT_NOISE_OP,
-            // OperatingPointAnalysis::TranIc => todo!(),
-            // OperatingPointAnalysis::Tran => todo!(),
+            OperatingPointAnalysis::Noise => SimulationState::AT_NOISE_OP,
+            OperatingPointAnalysis::TranIc => SimulationState::AT_TRAN_IC_OP,
             // OperatingPointAnalysis::HBIc => todo!(),
         }
     }
@@ -100,10 +103,14 @@ bitflags! {
     pub(super) struct SimulationState: u32 {
         const AT_DC_OP = 0b00000001;
         const AT_AC_OP = 0b00000010;
-        // const AT_NOISE_OP = 0b00000100;
+        const AT_NOISE_OP = 0b00000100;
         const HAS_AC_EVAL = 0b00001000;
         const AT_AC = 0b00010000;
-        const AT_OP = Self::AT_DC_OP.0.bits() | Self::AT_AC_OP.0.bits();// | Self::AT_NOISE_OP.0.bits;
+        const AT_TRAN_IC_OP = 0b00100000;
+        const AT_OP = Self::AT_DC_OP.0.bits()
+            | Self::AT_AC_OP.0.bits()
+            | Self::AT_NOISE_OP.0.bits()
+            | Self::AT_TRAN_IC_OP.0.bits();
     }
 }
 
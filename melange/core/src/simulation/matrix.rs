@@ -8,7 +8,7 @@ use num_complex::Complex64;
 use typed_index_collections::TiSlice;
 
 use crate::circuit::{InstanceId, Node};
-use crate::devices::MatrixEntry;
+use crate::devices::{update_matrix_entry, MatrixEntry};
 use crate::Circuit;
 
 pub type RealMatrix = FixedKluMatrix<i32, f64>;
@@ -51,6 +51,15 @@ impl SimulationMatrix {
             .expect("matrix is not empty");
         SimulationMatrix { spec: self.spec, nonlinear_matrix, ac_matrix }
     }
+
+    /// Adds `conductance` to the diagonal entry of every unknown of the nonlinear matrix.
+    /// The entries are created by [`MatrixBuilder::ensure_diagonal`].
+    pub fn load_shunt(&self, conductance: f64, num_nodes: usize) {
+        for node in 1..num_nodes as u32 {
+            let idx = Node::from(node).matrix_idx();
+            update_matrix_entry(&self.nonlinear_matrix[(idx, idx)], conductance);
+        }
+    }
 }
 
 pub(crate) struct MatrixBuilder {
@@ -96,6 +105,15 @@ impl MatrixBuilder {
         }
     }
 
+    /// Ensures that the diagonal entries of all unknowns exist, they are required by
+    /// [`SimulationMatrix::load_shunt`].
+    pub fn ensure_diagonal(&mut self, num_nodes: usize) {
+        for node in 1..num_nodes as u32 {
+            let idx = Node::from(node).matrix_idx();
+            self.inner.add_entry(idx, idx);
+        }
+    }
+
     pub fn clear_instance(&mut self, instance: InstanceId) {
         self.instance_entries[instance].clear()
     }
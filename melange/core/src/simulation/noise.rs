@@ -0,0 +1,123 @@
+//! Small-signal noise analysis: every noise source is a current source (between two nodes)
+//! with a frequency dependent power spectral density (white, flicker, ...). At each frequency
+//! the transfer functions from all sources to the output are obtained from a single solve of the
+//! transposed (adjoint) AC matrix and the contributions are summed up.
+//!
+//! Sources are treated as uncorrelated. Verilog-A models express correlated noise by
+//! connecting multiple noise sources to a shared (internal) node, the correlation is therefore
+//! accounted for by the circuit equations.
+
+use std::f64::consts::TAU;
+
+use anyhow::{bail, Context, Result};
+use num_complex::Complex64;
+use stdx::iter::zip;
+use typed_index_collections::TiVec;
+
+use crate::circuit::{InstanceId, Node};
+use crate::devices::NoiseSource;
+use crate::simulation::flags::{EvalFlags, OperatingPointAnalysis};
+use crate::simulation::{SimInfo, Simulation};
+
+/// The contribution of a single noise source to the output noise.
+#[derive(Debug, Clone)]
+pub struct NoiseContribution {
+    pub instance: InstanceId,
+    pub source: NoiseSource,
+    /// The power spectral density at the output caused by this source at every frequency.
+    pub psd: Vec<f64>,
+}
+
+/// The result of [`Simulation::noise`].
+#[derive(Debug, Clone)]
+pub struct NoiseResult {
+    pub freq: Vec<f64>,
+    /// The total power spectral density of the output voltage at every frequency.
+    pub output: Vec<f64>,
+    pub contributions: Vec<NoiseContribution>,
+}
+
+impl Simulation<'_> {
+    /// Computes the noise of the voltage between `output.0` and `output.1` at the frequencies
+    /// `freqs` (in Hz) around the noise operating point.
+    pub fn noise(&mut self, output: (Node, Node), freqs: &[f64]) -> Result<NoiseResult> {
+        self.solve_op(OperatingPointAnalysis::Noise)?;
+
+        let sim_info = SimInfo {
+            abstime: 0f64,
+            prev_solve: &self.solution,
+            flags: EvalFlags::NOISE,
+            source_factor: 1.0,
+        };
+        let mut contributions = Vec::new();
+        let mut num_sources = Vec::with_capacity(self.instance_data.len());
+        for (instance, inst) in self.instance_data.iter_mut_enumerated() {
+            inst.eval(sim_info)?;
+            let sources = inst.noise_sources();
+            num_sources.push(sources.len());
+            contributions.extend(sources.into_iter().map(|source| NoiseContribution {
+                instance,
+                source,
+                psd: Vec::with_capacity(freqs.len()),
+            }));
+        }
+
+        let mut res = NoiseResult {
+            freq: freqs.to_vec(),
+            output: Vec::with_capacity(freqs.len()),
+            contributions,
+        };
+        let mut density = Vec::new();
+        let mut adjoint: TiVec<Node, Complex64> =
+            vec![Complex64::default(); self.nodes.len()].into();
+        let matrix =
+            self.matrix.as_mut().context("simulation must be setup before noise() is called")?;
+
+        for &freq in freqs {
+            matrix.nonlinear_matrix.write_zero();
+            matrix.ac_matrix.write_zero();
+            for inst in &*self.instance_data {
+                // this is save because we call populate_matrix_ptrs during Simulation construction
+                unsafe {
+                    inst.load_matrix_resist();
+                    inst.load_matrix_react(TAU * freq);
+                }
+            }
+            for (dst, src) in zip(matrix.ac_matrix.data(), matrix.nonlinear_matrix.data()) {
+                let val = Complex64::new(src.get(), dst.get().im);
+                dst.set(val);
+            }
+
+            let is_singular = matrix.ac_matrix.lu_factorize(None);
+            if is_singular {
+                bail!("ac matrix is singular!")
+            }
+
+            // the row of the inverse AC matrix that maps currents to the output voltage:
+            // `A^T adjoint = e_out` so that `v_out = adjoint^T i`
+            adjoint.raw.fill(Complex64::default());
+            adjoint[output.0] += 1.0;
+            adjoint[output.1] -= 1.0;
+            matrix.ac_matrix.solve_linear_tranose_system(&mut adjoint.raw[1..]);
+            adjoint[Node::GROUND] = Complex64::default();
+
+            let mut total = 0f64;
+            let mut contributions = res.contributions.iter_mut();
+            for (inst, &num_sources) in zip(&*self.instance_data, &num_sources) {
+                density.clear();
+                density.resize(num_sources, 0f64);
+                inst.load_noise(freq, &mut density);
+
+                for (&density, contribution) in zip(&density, contributions.by_ref()) {
+                    let source = &contribution.source;
+                    let gain = (adjoint[source.hi] - adjoint[source.lo]).norm_sqr();
+                    contribution.psd.push(gain * density);
+                    total += gain * density;
+                }
+            }
+            res.output.push(total);
+        }
+
+        Ok(res)
+    }
+}
@@ -0,0 +1,298 @@
+//! Transient analysis: the circuit is solved at a sequence of time points, the time derivatives
+//! of the reactive residuals (charges/fluxes) are approximated by a linear multistep method.
+//! The step size is controlled by estimating the local truncation error (LTE) from divided
+//! differences of the solution.
+
+use anyhow::{bail, Result};
+use typed_index_collections::{TiSlice, TiVec};
+
+use crate::circuit::Node;
+use crate::simulation::flags::{EvalFlags, OperatingPointAnalysis, SimulationState};
+use crate::simulation::{NewtonOpts, Simulation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrationMethod {
+    /// First order, strongly damped (numerically dissipative).
+    BackwardEuler,
+    /// Second order, does not dampen oscillations but may ring for stiff circuits.
+    Trapezoidal,
+    /// Second order backward differentiation formula (BDF2).
+    Gear2,
+}
+
+impl IntegrationMethod {
+    fn order(self) -> usize {
+        match self {
+            IntegrationMethod::BackwardEuler => 1,
+            IntegrationMethod::Trapezoidal | IntegrationMethod::Gear2 => 2,
+        }
+    }
+
+    /// The error constant `C` of the method: `lte = C * h^(k+1) * x^(k+1)`
+    fn error_constant(self) -> f64 {
+        match self {
+            IntegrationMethod::BackwardEuler => 1.0 / 2.0,
+            IntegrationMethod::Trapezoidal => 1.0 / 12.0,
+            IntegrationMethod::Gear2 => 2.0 / 9.0,
+        }
+    }
+}
+
+/// The waveforms computed by [`Simulation::tran`].
+#[derive(Debug, Clone)]
+pub struct TranResult {
+    /// All accepted time points.
+    pub time: Vec<f64>,
+    /// The value of each unknown at every time point.
+    pub waveforms: TiVec<Node, Vec<f64>>,
+}
+
+impl TranResult {
+    pub fn waveform(&self, node: Node) -> &[f64] {
+        &self.waveforms[node]
+    }
+}
+
+/// An accepted time point.
+struct TimePoint {
+    time: f64,
+    solution: TiVec<Node, f64>,
+    charge: TiVec<Node, f64>,
+    /// The derivative of `charge` computed by the integration method.
+    charge_deriv: TiVec<Node, f64>,
+}
+
+/// The number of time points kept for integration and LTE estimation.
+const HISTORY: usize = 3;
+/// The factor by which the time step may grow after each accepted time point.
+const MAX_GROWTH: f64 = 2.0;
+/// The factor by which the time step is reduced when Newton's method fails to converge.
+const NEWTON_FAILURE_REDUCTION: f64 = 8.0;
+
+impl Simulation<'_> {
+    /// Runs a transient analysis from `0` to `tstop`, starting at the initial (DC) operating
+    /// point. `tstep` is the largest allowed time step, the actual step size is chosen based on
+    /// the local truncation error and placed so that breakpoints (of sources) are hit exactly.
+    pub fn tran(&mut self, tstop: f64, tstep: f64) -> Result<TranResult> {
+        if !(tstop > 0.0 && tstep > 0.0) {
+            bail!("tran: tstop and tstep must be positive")
+        }
+
+        // the operating point is always recomputed since the charges are required
+        self.state.remove(SimulationState::AT_TRAN_IC_OP);
+        self.solve_op(OperatingPointAnalysis::TranIc)?;
+        for inst in &mut *self.instance_data {
+            inst.accept_step()
+        }
+
+        let max_step = tstep.min(tstop);
+        let first_step = max_step * 1e-2;
+        let min_step = max_step * 1e-9;
+
+        let mut res = TranResult {
+            time: vec![0f64],
+            waveforms: self.solution.iter().map(|&val| vec![val]).collect(),
+        };
+        // points since the start/last breakpoint (newest last), integration and LTE
+        // estimation never reach across a breakpoint
+        let mut history = vec![TimePoint {
+            time: 0f64,
+            solution: self.solution.clone(),
+            charge: self.residual_react.clone(),
+            charge_deriv: vec![0f64; self.solution.len()].into(),
+        }];
+        let mut history_term: TiVec<Node, f64> = vec![0f64; self.solution.len()].into();
+
+        let mut time = 0f64;
+        let mut step = first_step;
+        while time < tstop {
+            let breakpoint = self
+                .instance_data
+                .iter()
+                .filter_map(|inst| inst.next_breakpoint(time))
+                .filter(|&breakpoint| breakpoint > time)
+                .fold(tstop, f64::min);
+            let bound_step =
+                self.instance_data.iter().map(|inst| inst.bound_step()).fold(max_step, f64::min);
+            step = step.min(bound_step.max(min_step));
+
+            // land exactly on the breakpoint without creating a tiny step in front of it
+            let mut next_time = time + step;
+            let hit_breakpoint = next_time >= breakpoint;
+            if hit_breakpoint {
+                next_time = breakpoint;
+            } else if time + 1.5 * step > breakpoint {
+                next_time = time + (breakpoint - time) / 2.0;
+            }
+            step = next_time - time;
+
+            // the first step after a breakpoint has no (smooth) history
+            let method = if history.len() < 2 {
+                IntegrationMethod::BackwardEuler
+            } else {
+                self.config.integration
+            };
+            let last = history.last().unwrap();
+            let alpha = integration_coefficients(method, &history, step, &mut history_term);
+
+            let opts = NewtonOpts {
+                abstime: next_time,
+                integration: Some((alpha, &history_term)),
+                ..NewtonOpts::default()
+            };
+            if !self.newton(EvalFlags::TRAN, opts)?.is_converged() {
+                self.solution.copy_from_slice(&last.solution);
+                step /= NEWTON_FAILURE_REDUCTION;
+                if step < min_step {
+                    bail!("tran: time step too small at t = {time:e} (Newton's method failed to converge)")
+                }
+                continue;
+            }
+
+            // check the local truncation error, this requires k+1 previous points
+            let order = method.order();
+            let mut growth = MAX_GROWTH;
+            if history.len() > order {
+                let ratio = self.lte_ratio(method, &history, next_time, step);
+                let factor = 0.9 * ratio.powf(-1.0 / (order + 1) as f64);
+                if ratio > 1.0 {
+                    self.solution.copy_from_slice(&last.solution);
+                    step *= factor.max(1.0 / NEWTON_FAILURE_REDUCTION);
+                    if step < min_step {
+                        bail!("tran: time step too small at t = {time:e}")
+                    }
+                    continue;
+                }
+                growth = factor.min(MAX_GROWTH);
+            }
+
+            // accept the time point
+            for inst in &mut *self.instance_data {
+                inst.accept_step()
+            }
+            let charge_deriv = self
+                .residual_react
+                .iter()
+                .zip(&history_term)
+                .map(|(charge, history)| alpha * charge + history)
+                .collect();
+            let point = TimePoint {
+                time: next_time,
+                solution: self.solution.clone(),
+                charge: self.residual_react.clone(),
+                charge_deriv,
+            };
+            if hit_breakpoint {
+                history.clear();
+            } else if history.len() == HISTORY {
+                history.remove(0);
+            }
+            history.push(point);
+
+            res.time.push(next_time);
+            for (waveform, &val) in res.waveforms.iter_mut().zip(&self.solution) {
+                waveform.push(val);
+            }
+
+            time = next_time;
+            step = if hit_breakpoint { step.min(first_step) } else { step * growth };
+        }
+
+        // the solution is no longer an operating point
+        self.state.clear();
+        Ok(res)
+    }
+
+    /// Estimates the local truncation error of the last step from the divided differences of
+    /// the solution and returns the largest ratio between the error and its tolerance.
+    fn lte_ratio(
+        &self,
+        method: IntegrationMethod,
+        history: &[TimePoint],
+        time: f64,
+        step: f64,
+    ) -> f64 {
+        let order = method.order();
+        let points = &history[history.len() - order - 1..];
+        let mut times: Vec<f64> = points.iter().map(|point| point.time).collect();
+        times.push(time);
+
+        // x^(k+1) ~ (k+1)! * divided difference of order k+1
+        let factorial: f64 = (1..=order + 1).map(|i| i as f64).product();
+        let scale = method.error_constant() * step.powi(order as i32 + 1) * factorial;
+
+        let mut ratio = 0f64;
+        let mut vals = vec![0f64; order + 2];
+        for (node, node_info) in self.nodes.iter_enumerated().skip(1) {
+            for (dst, point) in vals.iter_mut().zip(points) {
+                *dst = point.solution[node];
+            }
+            vals[order + 1] = self.solution[node];
+            let lte = scale * divided_difference(&times, &mut vals).abs();
+
+            let prev = points.last().unwrap().solution[node];
+            let val = self.solution[node].abs().max(prev.abs());
+            let tol = self.config.trtol * (self.config.rtol * val + node_info.atol);
+            ratio = ratio.max(lte / tol);
+        }
+        ratio
+    }
+}
+
+/// Computes the coefficients of the integration `method` such that the derivative of the
+/// charges at the new time point is `alpha * q + history_term`.
+///
+/// # Returns
+///
+/// `alpha`
+fn integration_coefficients(
+    method: IntegrationMethod,
+    history: &[TimePoint],
+    step: f64,
+    history_term: &mut TiSlice<Node, f64>,
+) -> f64 {
+    let last = history.last().unwrap();
+    match method {
+        IntegrationMethod::BackwardEuler => {
+            let alpha = 1.0 / step;
+            for (dst, &charge) in history_term.iter_mut().zip(&last.charge) {
+                *dst = -alpha * charge;
+            }
+            alpha
+        }
+        IntegrationMethod::Trapezoidal => {
+            let alpha = 2.0 / step;
+            for ((dst, &charge), &deriv) in
+                history_term.iter_mut().zip(&last.charge).zip(&last.charge_deriv)
+            {
+                *dst = -alpha * charge - deriv;
+            }
+            alpha
+        }
+        IntegrationMethod::Gear2 => {
+            // variable step BDF2
+            let prev = &history[history.len() - 2];
+            let ratio = step / (last.time - prev.time);
+            let alpha = (1.0 + 2.0 * ratio) / ((1.0 + ratio) * step);
+            let c1 = -(1.0 + ratio) / step;
+            let c2 = ratio * ratio / ((1.0 + ratio) * step);
+            for ((dst, &charge), &prev_charge) in
+                history_term.iter_mut().zip(&last.charge).zip(&prev.charge)
+            {
+                *dst = c1 * charge + c2 * prev_charge;
+            }
+            alpha
+        }
+    }
+}
+
+/// Computes the divided difference `f[t_0, ..., t_n]` in place (`vals` is overwritten).
+fn divided_difference(times: &[f64], vals: &mut [f64]) -> f64 {
+    let n = vals.len();
+    for level in 1..n {
+        for i in 0..n - level {
+            vals[i] = (vals[i + 1] - vals[i]) / (times[i + level] - times[i]);
+        }
+    }
+    vals[0]
+}
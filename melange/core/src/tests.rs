@@ -1,11 +1,16 @@
+use std::f64::consts::TAU;
+use std::fs;
+
 use anyhow::Result;
 use camino::Utf8PathBuf;
 use stdx::project_root;
 
+use crate::circuit::Node;
+use crate::elaboration::{CircuitInstanceDescription, ParamDescription, SubcircuitDescription};
 use crate::expr::CircuitParam;
-use crate::simulation::SimConfig;
+use crate::simulation::{DcSweep, IntegrationMethod, SimConfig, SweepParam};
 use crate::utils::PrettyPrint;
-use crate::{veriloga, Arena, Circuit, ExprEvalCtx};
+use crate::{veriloga, Arena, Circuit, CircuitDescription, Expr, ExprEvalCtx};
 
 const ATOL: f64 = 1e-9;
 const RTOL: f64 = 1e-2;
@@ -81,6 +86,26 @@ fn smoke_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn negated_constants() -> Result<()> {
+    let mut arena = Arena::new();
+    let ctx = arena.add_ctx();
+    let (param, read_param) = arena.def_param(ctx, "x".to_owned())?;
+
+    // constants are folded
+    let neg_const = Expr::neg(&mut arena, 4.0.into())?;
+    let neg_param = Expr::neg(&mut arena, read_param)?;
+    let double_neg = Expr::neg(&mut arena, neg_param)?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(param, 2.0.into());
+    assert_approx_eq!(neg_const.eval_num(ctx.borrow())?, -4.0);
+    assert_approx_eq!(neg_param.eval_num(ctx.borrow())?, -2.0);
+    assert_approx_eq!(double_neg.eval_num(ctx.borrow())?, 2.0);
+
+    Ok(())
+}
+
 #[test]
 fn veriloga() -> Result<()> {
     let mut arena = Arena::new();
@@ -137,3 +162,738 @@ fn veriloga() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn tran_dc_circuit() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_x = circ.node("X".to_owned());
+
+    let (vsrc1, _) =
+        circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_x, gnd])?;
+    circ.set_instance_param(vsrc1, "dc", 1f64.into())?;
+
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_x, gnd])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(1e-6, 1e-7)?;
+    assert_approx_eq!(*res.time.last().unwrap(), 1e-6);
+    for &val in res.waveform(node_x) {
+        assert_approx_eq!(val, 1.0);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tran_pulse_source() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_x = circ.node("X".to_owned());
+
+    let (vsrc1, _) =
+        circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_x, gnd])?;
+    let pulse = Expr::str(&mut arena, "pulse");
+    circ.set_instance_param(vsrc1, "type", pulse)?;
+    circ.set_instance_param(vsrc1, "val1", 1f64.into())?;
+    circ.set_instance_param(vsrc1, "delay", 1e-7.into())?;
+    circ.set_instance_param(vsrc1, "rise", 1e-7.into())?;
+    circ.set_instance_param(vsrc1, "width", 2e-7.into())?;
+    circ.set_instance_param(vsrc1, "fall", 1e-7.into())?;
+
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_x, gnd])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(1e-6, 1e-7)?;
+    // the corners of the pulse are breakpoints and must be hit exactly
+    for corner in [1e-7, 2e-7, 4e-7, 5e-7] {
+        assert!(res.time.iter().any(|&t| approx_eq(t, corner)), "missing time point {corner}");
+    }
+    for (&t, &val) in res.time.iter().zip(res.waveform(node_x)) {
+        let expected = ((t - 1e-7) / 1e-7).clamp(0.0, 1.0).min(((5e-7 - t) / 1e-7).max(0.0));
+        assert_approx_eq!(val, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tran_sources() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+    let gnd = circ.lookup_node("ground").expect("ground node");
+
+    let file = std::env::temp_dir().join(format!("melange_pwl_{}.txt", std::process::id()));
+    fs::write(&file, "# time value\n0 0\n2e-6, 2\n")?;
+    let file = Utf8PathBuf::from_path_buf(file).expect("only utf8 paths are supported");
+
+    let mut source = |name: &str, params: Vec<(&str, Expr)>| -> Result<Node> {
+        let node = circ.node(name.to_owned());
+        let (vsrc, _) =
+            circ.new_device_instance_by_name(format!("v{name}"), "vsource", vec![node, gnd])?;
+        for (param, val) in params {
+            circ.set_instance_param(vsrc, param, val)?;
+        }
+        let (res, _) =
+            circ.new_device_instance_by_name(format!("r{name}"), "resistor", vec![node, gnd])?;
+        circ.set_instance_param(res, "r", 1e3.into())?;
+        Ok(node)
+    };
+    let sine = vec![
+        ("type", Expr::str(&mut arena, "sine")),
+        ("sinedc", 0.5.into()),
+        ("ampl", 1.0.into()),
+        ("freq", 1e6.into()),
+        ("delay", 1e-6.into()),
+        ("damp", 1e5.into()),
+    ];
+    let exp = vec![
+        ("type", Expr::str(&mut arena, "exp")),
+        ("val1", 1.0.into()),
+        ("td1", 0.5e-6.into()),
+        ("tau1", 0.5e-6.into()),
+        ("td2", 2e-6.into()),
+        ("tau2", 0.2e-6.into()),
+    ];
+    let pwl = vec![("wave", Expr::str(&mut arena, "0 0 1e-6 1 1.5e-6 1 2.5e-6 -1"))];
+    let sffm = vec![
+        ("type", Expr::str(&mut arena, "sffm")),
+        ("ampl", 1.0.into()),
+        ("freq", 1e6.into()),
+        ("fmmodindex", 2.0.into()),
+        ("fmmodfreq", 2e5.into()),
+    ];
+    let pwl_file = vec![
+        ("type", Expr::str(&mut arena, "pwl")),
+        ("file", Expr::str(&mut arena, file.as_str())),
+    ];
+    let sine = source("sine", sine)?;
+    let exp = source("exp", exp)?;
+    let pwl = source("pwl", pwl)?;
+    let sffm = source("sffm", sffm)?;
+    let pwl_file = source("pwl_file", pwl_file)?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default());
+    fs::remove_file(&file)?;
+    let res = sim?.tran(3e-6, 2e-8)?;
+
+    // the corners of all waveforms are breakpoints and must be hit exactly
+    for corner in [1e-6, 0.5e-6, 2e-6, 1.5e-6, 2.5e-6] {
+        assert!(res.time.contains(&corner), "missing time point {corner}");
+    }
+
+    let interpolate = |points: &[(f64, f64)], t: f64| {
+        let i = points.iter().position(|&(time, _)| time >= t).unwrap_or(points.len() - 1);
+        if i == 0 || points[i].0 < t {
+            return points[i].1;
+        }
+        let ((t0, v0), (t1, v1)) = (points[i - 1], points[i]);
+        v0 + (v1 - v0) * (t - t0) / (t1 - t0)
+    };
+    for (i, &t) in res.time.iter().enumerate() {
+        let expected_sine = if t <= 1e-6 {
+            0.5
+        } else {
+            0.5 + (-1e5 * (t - 1e-6)).exp() * (TAU * 1e6 * (t - 1e-6)).sin()
+        };
+        let mut expected_exp = 0.0;
+        if t > 0.5e-6 {
+            expected_exp += 1.0 - (-(t - 0.5e-6) / 0.5e-6).exp();
+        }
+        if t > 2e-6 {
+            expected_exp -= 1.0 - (-(t - 2e-6) / 0.2e-6).exp();
+        }
+        let expected_pwl =
+            interpolate(&[(0.0, 0.0), (1e-6, 1.0), (1.5e-6, 1.0), (2.5e-6, -1.0)], t);
+        let expected_sffm = (TAU * 1e6 * t + 2.0 * (TAU * 2e5 * t).sin()).sin();
+        let expected_file = interpolate(&[(0.0, 0.0), (2e-6, 2.0)], t);
+
+        for (node, expected) in [
+            (sine, expected_sine),
+            (exp, expected_exp),
+            (pwl, expected_pwl),
+            (sffm, expected_sffm),
+            (pwl_file, expected_file),
+        ] {
+            let val = res.waveform(node)[i];
+            assert!(
+                approx_eq(val, expected),
+                "v({}) is {val} at {t} but should be {expected}",
+                circ.node_name(node)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tran_rc_step() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_in = circ.node("in".to_owned());
+    let node_out = circ.node("out".to_owned());
+
+    // a unit step at t = 0 into an RC lowpass with a time constant of 1 us
+    let (vsrc1, _) =
+        circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_in, gnd])?;
+    let pulse = Expr::str(&mut arena, "pulse");
+    circ.set_instance_param(vsrc1, "type", pulse)?;
+    circ.set_instance_param(vsrc1, "val1", 1f64.into())?;
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_in, node_out])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+    let (cap1, _) =
+        circ.new_device_instance_by_name("cap1".to_owned(), "capacitor", vec![node_out, gnd])?;
+    circ.set_instance_param(cap1, "c", 1e-9.into())?;
+
+    let tau = 1e-6;
+    // backward euler is only first order accurate
+    let methods = [
+        (IntegrationMethod::BackwardEuler, 2e-2),
+        (IntegrationMethod::Trapezoidal, 2e-3),
+        (IntegrationMethod::Gear2, 2e-3),
+    ];
+    for (method, tol) in methods {
+        let mut ctx = ExprEvalCtx::new(&arena);
+        ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+        let config = SimConfig { integration: method, ..SimConfig::default() };
+        let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, config)?;
+        let res = sim.tran(5.0 * tau, 1e-7)?;
+        assert_approx_eq!(*res.time.last().unwrap(), 5.0 * tau);
+        for (&t, &val) in res.time.iter().zip(res.waveform(node_out)) {
+            let expected = 1.0 - (-t / tau).exp();
+            assert!(
+                (val - expected).abs() < tol,
+                "{method:?}: v(out) is {val} at {t} but should be {expected}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tran_reactive_devices() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_in = circ.node("in".to_owned());
+    let node_rc = circ.node("rc".to_owned());
+    let node_rl = circ.node("rl".to_owned());
+    let node_p1 = circ.node("p1".to_owned());
+    let node_p2 = circ.node("p2".to_owned());
+
+    let (vsrc1, _) =
+        circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_in, gnd])?;
+    let pulse = Expr::str(&mut arena, "pulse");
+    circ.set_instance_param(vsrc1, "type", pulse)?;
+    circ.set_instance_param(vsrc1, "val1", 1f64.into())?;
+
+    // RC lowpass with a time constant of 2 us
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_in, node_rc])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+    let (cap1, _) =
+        circ.new_device_instance_by_name("cap1".to_owned(), "capacitor", vec![node_rc, gnd])?;
+    circ.set_instance_param(cap1, "c", 2e-9.into())?;
+
+    // LR lowpass with a time constant of 1 us
+    let (ind1, _) =
+        circ.new_device_instance_by_name("ind1".to_owned(), "inductor", vec![node_in, node_rl])?;
+    circ.set_instance_param(ind1, "l", 1e-3.into())?;
+    let (res2, _) =
+        circ.new_device_instance_by_name("res2".to_owned(), "resistor", vec![node_rl, gnd])?;
+    circ.set_instance_param(res2, "r", 1e3.into())?;
+
+    // RL highpass (1 us) whose inductor is the primary winding of a transformer with an open
+    // secondary: V(p2) = M/L1 * V(p1) = 1.8 * V(p1)
+    let (res3, _) =
+        circ.new_device_instance_by_name("res3".to_owned(), "resistor", vec![node_in, node_p1])?;
+    circ.set_instance_param(res3, "r", 1e3.into())?;
+    let (k1, _) = circ.new_device_instance_by_name(
+        "k1".to_owned(),
+        "mutual_inductor",
+        vec![node_p1, gnd, node_p2, gnd],
+    )?;
+    circ.set_instance_param(k1, "l1", 1e-3.into())?;
+    circ.set_instance_param(k1, "l2", 4e-3.into())?;
+    circ.set_instance_param(k1, "k", 0.9.into())?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(5e-6, 1e-7)?;
+    for (i, &t) in res.time.iter().enumerate() {
+        let check = |node, expected: f64| {
+            let val = res.waveform(node)[i];
+            assert!(
+                (val - expected).abs() < 2e-3,
+                "v({}) is {val} at {t} but should be {expected}",
+                circ.node_name(node)
+            );
+        };
+        check(node_rc, 1.0 - (-t / 2e-6).exp());
+        check(node_rl, 1.0 - (-t / 1e-6).exp());
+        if t > 0.0 {
+            check(node_p1, (-t / 1e-6).exp());
+            check(node_p2, 1.8 * (-t / 1e-6).exp());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dc_sweep() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+    let (rload, rload_expr) = circ.def_param("rload".to_owned(), Some(1e3.into()), &mut arena)?;
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_x = circ.node("X".to_owned());
+    let node_y = circ.node("Y".to_owned());
+
+    let (vsrc1, _) =
+        circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_x, gnd])?;
+    circ.set_instance_param(vsrc1, "dc", 1f64.into())?;
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_x, node_y])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+    let (res2, _) =
+        circ.new_device_instance_by_name("res2".to_owned(), "resistor", vec![node_y, gnd])?;
+    circ.set_instance_param(res2, "r", rload_expr)?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let sweeps = [
+        DcSweep {
+            param: SweepParam::Instance(vsrc1, "dc".to_owned()),
+            values: vec![0.0, 1.0, 2.0],
+        },
+        DcSweep { param: SweepParam::Circuit(rload), values: vec![1e3, 3e3] },
+    ];
+    let res = sim.dc_sweep(ctx.borrow(), &arena, &sweeps)?;
+    assert_eq!(res.num_points(), 6);
+    assert_eq!(res.sweep_values[0], [0.0, 1.0, 2.0, 0.0, 1.0, 2.0]);
+    assert_eq!(res.sweep_values[1], [1e3, 1e3, 1e3, 3e3, 3e3, 3e3]);
+    for i in 0..res.num_points() {
+        let (vdc, rload) = (res.sweep_values[0][i], res.sweep_values[1][i]);
+        assert_approx_eq!(res.waveform(node_y)[i], vdc * rload / (1e3 + rload));
+        assert_approx_eq!(res.lead_current(vsrc1, 0)[i], -vdc / (1e3 + rload));
+    }
+
+    // the original parameters are restored after the sweep
+    let solution = sim.dc_op()?;
+    assert_approx_eq!(solution[node_y], 0.5);
+
+    Ok(())
+}
+
+#[test]
+fn resistor_noise() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_x = circ.node("X".to_owned());
+
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_x, gnd])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.noise((node_x, gnd), &[1.0, 1e3, 1e6])?;
+    // 4kTR
+    for &psd in &res.output {
+        assert_approx_eq!(psd * 1e17, 1.6568);
+    }
+    assert_eq!(res.contributions.len(), 1);
+    assert_eq!(res.contributions[0].source.name, "thermal");
+
+    Ok(())
+}
+
+#[test]
+fn controlled_source_noise() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_x = circ.node("X".to_owned());
+    let node_y = circ.node("Y".to_owned());
+
+    // the noise voltage of res1 is amplified into Y but the noise of res2 does not reach X
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_x, gnd])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+    let (g1, _) =
+        circ.new_device_instance_by_name("g1".to_owned(), "vccs", vec![node_y, gnd, node_x, gnd])?;
+    circ.set_instance_param(g1, "gain", 1e-2.into())?;
+    let (res2, _) =
+        circ.new_device_instance_by_name("res2".to_owned(), "resistor", vec![node_y, gnd])?;
+    circ.set_instance_param(res2, "r", 1e3.into())?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+
+    let res = sim.noise((node_y, gnd), &[1e3])?;
+    let psd: Vec<_> = res.contributions.iter().map(|contrib| contrib.psd[0]).collect();
+    assert_eq!(res.contributions.len(), 2);
+    // 4kTR * (gain * R)^2
+    assert_approx_eq!(psd[0] * 1e15, 1.6568);
+    // 4kTR
+    assert_approx_eq!(psd[1] * 1e17, 1.6568);
+    assert_approx_eq!(res.output[0] * 1e15, (psd[0] + psd[1]) * 1e15);
+
+    let res = sim.noise((node_x, gnd), &[1e3])?;
+    assert_approx_eq!(res.contributions[0].psd[0] * 1e17, 1.6568);
+    assert_approx_eq!(res.contributions[1].psd[0] * 1e17, 0.0);
+
+    Ok(())
+}
+
+#[test]
+fn veriloga_noise() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_in = circ.node("in".to_owned());
+    let node_x = circ.node("X".to_owned());
+
+    let path = Utf8PathBuf::from_path_buf(project_root())
+        .expect("only utf8 paths are supported")
+        .join("openvaf")
+        .join("test_data")
+        .join("osdi")
+        .join("noise.va");
+    circ.load_veriloga_file(path, &veriloga::Opts::default())?;
+
+    let (vsrc1, _) =
+        circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_in, gnd])?;
+    circ.set_instance_param(vsrc1, "dc", 1f64.into())?;
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_in, node_x])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+    // only contributes noise (proportional to V(X) = 1) and therefore draws no current
+    circ.new_device_instance_by_name("noise1".to_owned(), "noise_test", vec![node_x, gnd])?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+
+    let freqs = [1.0, 1e3];
+    let res = sim.noise((node_x, gnd), &freqs)?;
+    let names: Vec<_> = res.contributions.iter().map(|contrib| contrib.source.name).collect();
+    assert_eq!(names, ["thermal", "white1", "white2", "flickr1", "flickr2"]);
+    for (i, freq) in freqs.into_iter().enumerate() {
+        // the current noise of the model (1e-14 A^2/Hz) sees an impedance of 1k, in 1e-8 V^2/Hz
+        let white = 1.0;
+        let flicker = white / freq;
+        assert_approx_eq!(res.contributions[0].psd[i] * 1e17, 1.6568);
+        assert_approx_eq!(res.contributions[1].psd[i] * 1e8, white);
+        assert_approx_eq!(res.contributions[2].psd[i] * 1e8, white);
+        assert_approx_eq!(res.contributions[3].psd[i] * 1e8, flicker);
+        assert_approx_eq!(res.contributions[4].psd[i] * 1e8, flicker);
+        assert_approx_eq!(res.output[i] * 1e8, 2.0 * white + 2.0 * flicker);
+    }
+
+    Ok(())
+}
+
+fn instance(
+    name: &str,
+    master: &str,
+    parameters: ParamDescription,
+    terminals: &[&str],
+) -> CircuitInstanceDescription {
+    CircuitInstanceDescription {
+        name: name.to_owned(),
+        master: master.to_owned(),
+        parameters,
+        terminal_connections: terminals.iter().map(|&node| node.to_owned()).collect(),
+    }
+}
+
+#[test]
+fn subcircuits() -> Result<()> {
+    let mut arena = Arena::new();
+    let top_ctx = arena.add_ctx();
+
+    // divider (in out): r1 in out r*(1-ratio); r2 out 0 r*ratio
+    let divider_ctx = arena.add_ctx();
+    let (ratio_param, ratio) = arena.def_param(divider_ctx, "ratio".to_owned())?;
+    let (r_param, r) = arena.def_param(divider_ctx, "r".to_owned())?;
+    let neg_ratio = Expr::neg(&mut arena, ratio)?;
+    let one_minus_ratio = Expr::add(&mut arena, 1.0.into(), neg_ratio)?;
+    let r1 = Expr::mul(&mut arena, r, one_minus_ratio)?;
+    let r2 = Expr::mul(&mut arena, r, ratio)?;
+    let divider = SubcircuitDescription {
+        name: "divider".to_owned(),
+        ports: vec!["in".to_owned(), "out".to_owned()],
+        ctx: divider_ctx,
+        parameters: vec![(ratio_param, 0.5.into()), (r_param, 1e3.into())],
+        instances: vec![
+            instance("r1", "resistor", vec![("r".to_owned(), r1)], &["in", "out"]),
+            instance("r2", "resistor", vec![("r".to_owned(), r2)], &["out", "ground"]),
+        ],
+        models: Vec::new(),
+    };
+
+    // two dividers in series that share the ratio parameter
+    let double_ctx = arena.add_ctx();
+    let (p_param, p) = arena.def_param(double_ctx, "p".to_owned())?;
+    let double = SubcircuitDescription {
+        name: "double".to_owned(),
+        ports: vec!["in".to_owned(), "out".to_owned()],
+        ctx: double_ctx,
+        parameters: vec![(p_param, 0.25.into())],
+        instances: vec![
+            instance("x1", "divider", vec![("ratio".to_owned(), p)], &["in", "mid"]),
+            instance("x2", "divider", vec![("ratio".to_owned(), p)], &["mid", "out"]),
+        ],
+        models: Vec::new(),
+    };
+
+    let descr = CircuitDescription {
+        name: "test_circ".to_owned(),
+        instances: vec![
+            instance("v1", "vsource", vec![("dc".to_owned(), 1.0.into())], &["vin", "ground"]),
+            instance("x1", "divider", vec![("ratio".to_owned(), 0.25.into())], &["vin", "a"]),
+            instance("x2", "divider", Vec::new(), &["vin", "b"]),
+            instance("x3", "double", vec![("p".to_owned(), 0.5.into())], &["vin", "c"]),
+        ]
+        .into(),
+        models: Vec::new().into(),
+        subcircuits: vec![divider, double],
+        global_nodes: Vec::new(),
+        va_files: Vec::new(),
+        ctx: top_ctx,
+        parameters: Vec::new(),
+    };
+    let circ = descr.elaborate(&mut arena, &veriloga::Opts::default())?;
+
+    let node_a = circ.lookup_node("a").expect("node a");
+    let node_b = circ.lookup_node("b").expect("node b");
+    let node_c = circ.lookup_node("c").expect("node c");
+    let node_mid = circ.lookup_node("x3.mid").expect("internal node x3.mid");
+    assert_eq!(circ.lookup_node("x1.out"), Some(node_a));
+    assert_eq!(circ.lookup_node("x3.x2.in"), Some(node_mid));
+    assert_eq!(circ.lookup_node("x3.x2.out"), Some(node_c));
+    assert_eq!(circ.instances().count(), 9);
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    assert_approx_eq!(solution[node_a], 0.25);
+    assert_approx_eq!(solution[node_b], 0.5);
+    assert_approx_eq!(solution[node_mid], 0.4);
+    assert_approx_eq!(solution[node_c], 0.2);
+
+    Ok(())
+}
+
+#[test]
+fn global_nodes() -> Result<()> {
+    let mut arena = Arena::new();
+    let top_ctx = arena.add_ctx();
+
+    // pullup (out): r1 vdd out 1k
+    let pullup = SubcircuitDescription {
+        name: "pullup".to_owned(),
+        ports: vec!["out".to_owned()],
+        ctx: arena.add_ctx(),
+        parameters: Vec::new(),
+        instances: vec![instance(
+            "r1",
+            "resistor",
+            vec![("r".to_owned(), 1e3.into())],
+            &["vdd", "out"],
+        )],
+        models: Vec::new(),
+    };
+
+    let descr = CircuitDescription {
+        name: "test_circ".to_owned(),
+        instances: vec![
+            instance("v1", "vsource", vec![("dc".to_owned(), 1.0.into())], &["vdd", "ground"]),
+            instance("x1", "pullup", Vec::new(), &["a"]),
+            instance("r2", "resistor", vec![("r".to_owned(), 1e3.into())], &["a", "ground"]),
+        ]
+        .into(),
+        models: Vec::new().into(),
+        subcircuits: vec![pullup],
+        global_nodes: vec!["vdd".to_owned()],
+        va_files: Vec::new(),
+        ctx: top_ctx,
+        parameters: Vec::new(),
+    };
+    let circ = descr.elaborate(&mut arena, &veriloga::Opts::default())?;
+
+    let node_a = circ.lookup_node("a").expect("node a");
+    assert_eq!(circ.lookup_node("x1.vdd"), None);
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    assert_approx_eq!(solution[node_a], 0.5);
+
+    Ok(())
+}
+
+#[test]
+fn controlled_sources() -> Result<()> {
+    let mut arena = Arena::new();
+    let top_ctx = arena.add_ctx();
+
+    // current mirror (in out): the current through the ammeter vs is mirrored into out
+    let mirror_ctx = arena.add_ctx();
+    let ctrl = Expr::str(&mut arena, "vs");
+    let mirror = SubcircuitDescription {
+        name: "mirror".to_owned(),
+        ports: vec!["in".to_owned(), "out".to_owned()],
+        ctx: mirror_ctx,
+        parameters: Vec::new(),
+        instances: vec![
+            instance("vs", "vsource", Vec::new(), &["in", "mid"]),
+            instance("r1", "resistor", vec![("r".to_owned(), 1e3.into())], &["mid", "ground"]),
+            instance(
+                "f1",
+                "cccs",
+                vec![("ctrl".to_owned(), ctrl), ("gain".to_owned(), 2.0.into())],
+                &["ground", "out"],
+            ),
+            instance("r2", "resistor", vec![("r".to_owned(), 1e3.into())], &["out", "ground"]),
+        ],
+        models: Vec::new(),
+    };
+
+    let ctrl = Expr::str(&mut arena, "v1");
+    let descr = CircuitDescription {
+        name: "test_circ".to_owned(),
+        instances: vec![
+            instance("v1", "vsource", vec![("dc".to_owned(), 1.0.into())], &["vin", "ground"]),
+            instance("x1", "mirror", Vec::new(), &["vin", "mirrored"]),
+            instance(
+                "e1",
+                "vcvs",
+                vec![("gain".to_owned(), 2.0.into())],
+                &["e", "ground", "vin", "ground"],
+            ),
+            instance(
+                "g1",
+                "vccs",
+                vec![("gain".to_owned(), 1e-3.into())],
+                &["g", "ground", "vin", "ground"],
+            ),
+            instance("rg", "resistor", vec![("r".to_owned(), 1e3.into())], &["g", "ground"]),
+            instance(
+                "h1",
+                "ccvs",
+                vec![("ctrl".to_owned(), ctrl), ("gain".to_owned(), 1e3.into())],
+                &["h", "ground"],
+            ),
+            instance(
+                "s1",
+                "switch",
+                vec![("vt".to_owned(), 1.5.into())],
+                &["sw", "ground", "e", "ground"],
+            ),
+            instance("rs", "resistor", vec![("r".to_owned(), 1e3.into())], &["vin", "sw"]),
+        ]
+        .into(),
+        models: Vec::new().into(),
+        subcircuits: vec![mirror],
+        global_nodes: Vec::new(),
+        va_files: Vec::new(),
+        ctx: top_ctx,
+        parameters: Vec::new(),
+    };
+    let circ = descr.elaborate(&mut arena, &veriloga::Opts::default())?;
+
+    let node = |name| circ.lookup_node(name).expect("node exists");
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    assert_approx_eq!(solution[node("mirrored")], 2.0);
+    assert_approx_eq!(solution[node("e")], 2.0);
+    assert_approx_eq!(solution[node("g")], -1.0);
+    // the switch is closed (ron = 1)
+    assert_approx_eq!(solution[node("sw")], 1.0 / 1001.0);
+    // v1 supplies the mirror and the series resistor of the switch
+    assert_approx_eq!(solution[node("h")], -2.0 + 1.0 / 1001.0);
+
+    Ok(())
+}
+
+#[test]
+fn reactive_ac() -> Result<()> {
+    let mut arena = Arena::new();
+    let mut circ = Circuit::new("test_circ".to_owned(), &mut arena);
+
+    let gnd = circ.lookup_node("ground").expect("ground node");
+    let node_in = circ.node("in".to_owned());
+    let node_rc = circ.node("rc".to_owned());
+    let node_rl = circ.node("rl".to_owned());
+
+    let (vsrc1, _) =
+        circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_in, gnd])?;
+    circ.set_instance_param(vsrc1, "mag", 1f64.into())?;
+
+    // RC and RL lowpass with a corner frequency of 1 krad/s
+    let (res1, _) =
+        circ.new_device_instance_by_name("res1".to_owned(), "resistor", vec![node_in, node_rc])?;
+    circ.set_instance_param(res1, "r", 1e3.into())?;
+    let (cap1, _) =
+        circ.new_device_instance_by_name("cap1".to_owned(), "capacitor", vec![node_rc, gnd])?;
+    circ.set_instance_param(cap1, "c", 1e-6.into())?;
+    let (ind1, _) =
+        circ.new_device_instance_by_name("ind1".to_owned(), "inductor", vec![node_in, node_rl])?;
+    circ.set_instance_param(ind1, "l", 1.0.into())?;
+    let (res2, _) =
+        circ.new_device_instance_by_name("res2".to_owned(), "resistor", vec![node_rl, gnd])?;
+    circ.set_instance_param(res2, "r", 1e3.into())?;
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circ.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    sim.set_omega(1e3);
+    let solution = sim.ac()?;
+    assert_approx_eq_cmplx!(solution[node_rc], 0.5 - j 0.5);
+    assert_approx_eq_cmplx!(solution[node_rl], 0.5 - j 0.5);
+    let curr = sim.ac_lead_current(cap1)?[0];
+    assert_approx_eq_cmplx!(curr, 0.5e-3 + j 0.5e-3);
+
+    // the second frequency reuses the evaluation at the operating point
+    sim.set_omega(2e3);
+    let solution = sim.ac()?;
+    assert_approx_eq_cmplx!(solution[node_rc], 0.2 - j 0.4);
+    assert_approx_eq_cmplx!(solution[node_rl], 0.2 - j 0.4);
+
+    Ok(())
+}
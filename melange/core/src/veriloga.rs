@@ -9,32 +9,38 @@ use libc::c_void;
 use libloading::Library;
 use log::{debug, error, info, warn};
 use openvaf::{
-    AbsPathBuf, CompilationDestination, CompilationTermination, LintLevel, OptLevel, Target,
+    AbsPathBuf, CompilationDestination, CompilationTermination, DebugOpts, DiffMode, JitLibrary,
+    JitTermination, LintLevel, OptLevel, Target,
 };
 
 use crate::devices::DeviceImpl;
-use crate::veriloga::osdi_0_3::{
+use crate::veriloga::osdi_0_4::{
     OsdiDescriptor, LOG_FMT_ERR, LOG_LVL_DEBUG, LOG_LVL_DISPLAY, LOG_LVL_ERR, LOG_LVL_FATAL,
     LOG_LVL_INFO, LOG_LVL_MASK, LOG_LVL_WARN,
 };
 use crate::veriloga::osdi_device::OsdiDevice;
 
-pub(crate) use osdi_0_3::{
+pub(crate) use osdi_0_4::{
     ANALYSIS_AC, ANALYSIS_DC, ANALYSIS_IC, ANALYSIS_NOISE, ANALYSIS_STATIC, ANALYSIS_TRAN,
-    CALC_NOISE, CALC_REACT_JACOBIAN, CALC_REACT_RESIDUAL, CALC_RESIST_JACOBIAN,
-    CALC_RESIST_RESIDUAL,
+    CALC_NOISE, CALC_REACT_JACOBIAN, CALC_REACT_LIM_RHS, CALC_REACT_RESIDUAL, CALC_RESIST_JACOBIAN,
+    CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, ENABLE_LIM, INIT_LIM,
 };
 
 // autogenerated
 #[allow(warnings)]
-mod osdi_0_3;
+mod osdi_0_4;
 mod osdi_device;
 
 #[derive(Default)]
 pub struct Opts {
     pub defines: Vec<String>,
     pub codegen_opts: Vec<String>,
+    /// Directory in which the compiled models are cached, defaults to the cache
+    /// directory of the current user.
     pub cache_dir: Option<Utf8PathBuf>,
+    /// Compile models into memory instead of linking shared libraries. This does not
+    /// require a system linker but the compiled models are not cached.
+    pub jit: bool,
     pub lints: Vec<(String, LintLevel)>,
     include: Vec<AbsPathBuf>,
     pub opt_lvl: Option<OptLevel>,
@@ -49,68 +55,110 @@ impl Opts {
 }
 
 pub fn compile_va(path: &Utf8Path, opts: &Opts) -> Result<Vec<Box<dyn DeviceImpl>>> {
-    let cache_dir = if let Some(dir) = &opts.cache_dir {
-        dir.clone()
-    } else {
-        let path = directories_next::ProjectDirs::from("com", "semimod", "melange")
-            .context("failed to find cache directory\nhelp: consider setting it manually")?
-            .cache_dir()
-            .to_owned();
-        if let Ok(path) = Utf8PathBuf::from_path_buf(path) {
-            path
-        } else {
-            bail!("failed to find cache directory\nhelp: consider setting it manually")
-        }
-    };
-    let openvaf_opts = openvaf::Opts {
+    // models that were already compiled (with openvaf) are loaded directly
+    if path.extension() == Some("osdi") {
+        let descriptors = unsafe { load_osdi_lib(path)? };
+        return Ok(osdi_devices(descriptors));
+    }
+
+    let mut openvaf_opts = openvaf::Opts {
         defines: opts.defines.clone(),
         codegen_opts: opts.codegen_opts.clone(),
+        sensitivities: Vec::new(),
+        second_derivatives: Vec::new(),
+        diff_mode: DiffMode::Forward,
         lints: opts.lints.clone(),
-        input: path.to_owned(),
-        output: CompilationDestination::Cache { cache_dir },
+        inputs: vec![openvaf::Input::new(path.to_owned())],
+        output: CompilationDestination::Cache { cache_dir: Utf8PathBuf::new() },
         include: opts.include.clone(),
         opt_lvl: opts.opt_lvl.unwrap_or(OptLevel::Aggressive),
         target: Target::host_target()
             .context("openvaf does currently not support this hardware/os")?,
         target_cpu: "native".to_owned(),
         dry_run: false,
+        debug: DebugOpts::from_env().map_err(anyhow::Error::msg)?,
     };
 
-    let res = openvaf::compile(&openvaf_opts);
-    let res = res.with_context(|| format!("openvaf: compilation of {path} failed"))?;
-    let lib_file = match res {
-        CompilationTermination::Compiled { lib_file } => lib_file,
-        CompilationTermination::FatalDiagnostic => {
-            bail!("openvaf: compilation of {path} failed");
-        }
+    let descriptors = if opts.jit {
+        let res = openvaf::compile_jit(&openvaf_opts);
+        let res = res.with_context(|| format!("openvaf: compilation of {path} failed"))?;
+        let lib = match res {
+            JitTermination::Compiled { lib } => lib,
+            JitTermination::FatalDiagnostic => {
+                bail!("openvaf: compilation of {path} failed");
+            }
+        };
+        unsafe { load_osdi_jit(lib)? }
+    } else {
+        openvaf_opts.output = CompilationDestination::Cache { cache_dir: cache_dir(opts)? };
+        let res = openvaf::compile(&openvaf_opts);
+        let res = res.with_context(|| format!("openvaf: compilation of {path} failed"))?;
+        let lib_file = match res {
+            CompilationTermination::Compiled { lib_file } => lib_file,
+            CompilationTermination::FatalDiagnostic => {
+                bail!("openvaf: compilation of {path} failed");
+            }
+        };
+        unsafe { load_osdi_lib(&lib_file)? }
     };
-    let libs = unsafe { load_osdi_lib(&lib_file)? };
-    let libs = libs.iter().map(|descriptor| Box::new(OsdiDevice { descriptor }) as _).collect();
-    Ok(libs)
+    Ok(osdi_devices(descriptors))
+}
+
+fn cache_dir(opts: &Opts) -> Result<Utf8PathBuf> {
+    if let Some(dir) = &opts.cache_dir {
+        return Ok(dir.clone());
+    }
+    let path = directories_next::ProjectDirs::from("com", "semimod", "melange")
+        .context("failed to find cache directory\nhelp: consider setting it manually")?
+        .cache_dir()
+        .to_owned();
+    if let Ok(path) = Utf8PathBuf::from_path_buf(path) {
+        Ok(path)
+    } else {
+        bail!("failed to find cache directory\nhelp: consider setting it manually")
+    }
+}
+
+fn osdi_devices(descriptors: &'static [OsdiDescriptor]) -> Vec<Box<dyn DeviceImpl>> {
+    descriptors.iter().map(|descriptor| Box::new(OsdiDevice { descriptor }) as _).collect()
 }
 
 unsafe fn load_osdi_lib(path: &Utf8Path) -> Result<&'static [OsdiDescriptor]> {
     let lib = Library::new(path)?;
     let lib = Box::leak(Box::new(lib));
+    load_osdi_symbols(path.as_str(), |sym| {
+        let sym = format!("{sym}\0");
+        Ok(*lib.get::<*mut c_void>(sym.as_bytes())?)
+    })
+}
+
+unsafe fn load_osdi_jit(lib: JitLibrary) -> Result<&'static [OsdiDescriptor]> {
+    // the descriptors must remain valid for the rest of the program
+    let lib = Box::leak(Box::new(lib));
+    load_osdi_symbols("JIT compiled library", |sym| lib.get(sym))
+}
 
-    let major_version: &u32 = *lib.get(b"OSDI_VERSION_MAJOR\0")?;
-    let minor_version: &u32 = *lib.get(b"OSDI_VERSION_MINOR\0")?;
+unsafe fn load_osdi_symbols(
+    name: &str,
+    get: impl Fn(&str) -> Result<*mut c_void>,
+) -> Result<&'static [OsdiDescriptor]> {
+    let major_version = *(get("OSDI_VERSION_MAJOR")? as *const u32);
+    let minor_version = *(get("OSDI_VERSION_MINOR")? as *const u32);
 
-    if *major_version != 0 || *minor_version != 3 {
+    if major_version != 0 || minor_version != 4 {
         bail!(
-            "melange only supports OSDI v0.3 but {path} targets v{major_version}.{minor_version}",
+            "melange only supports OSDI v0.4 but {name} targets v{major_version}.{minor_version}",
         );
     }
 
-    let num_descriptors: &u32 = *lib.get(b"OSDI_NUM_DESCRIPTORS\0")?;
-    let descriptors: *const OsdiDescriptor = *lib.get(b"OSDI_DESCRIPTORS\0")?;
+    let num_descriptors = *(get("OSDI_NUM_DESCRIPTORS")? as *const u32);
+    let descriptors = get("OSDI_DESCRIPTORS")? as *const OsdiDescriptor;
 
     let descriptors: &[OsdiDescriptor] =
-        slice::from_raw_parts(descriptors, *num_descriptors as usize);
+        slice::from_raw_parts(descriptors, num_descriptors as usize);
 
-    if let Ok(osdi_log_ptr) =
-        lib.get::<*mut unsafe fn(*mut c_void, *const c_char, u32)>(b"osdi_log\0")
-    {
+    if let Ok(osdi_log_ptr) = get("osdi_log") {
+        let osdi_log_ptr = osdi_log_ptr as *mut unsafe fn(*mut c_void, *const c_char, u32);
         osdi_log_ptr.write(osdi_log)
     }
     Ok(descriptors)
@@ -11,12 +11,15 @@ use stdx::iter::zip;
 use typed_index_collections::TiSlice;
 
 use crate::circuit::Node;
-use crate::devices::{DeviceImpl, DeviceParams, InstanceImpl, ModelImpl, ParamId, Type};
+use crate::devices::{
+    DeviceImpl, DeviceParams, InstanceImpl, ModelImpl, NoiseSource, ParamId, Type,
+};
 use crate::simulation::{MatrixEntryIter, SimBuilder, SimInfo};
-use crate::veriloga::osdi_0_3::{
-    OsdiDescriptor, OsdiInitInfo, OsdiJacobianEntry, OsdiNode, OsdiNodePair, OsdiParamOpvar,
-    OsdiSimInfo, OsdiSimParas, ACCESS_FLAG_SET, EVAL_RET_FLAG_FATAL, INIT_ERR_OUT_OF_BOUNDS,
-    PARA_KIND_INST, PARA_TY_INT, PARA_TY_MASK, PARA_TY_REAL, PARA_TY_STR,
+use crate::veriloga::osdi_0_4::{
+    OsdiDescriptor, OsdiInitInfo, OsdiJacobianEntry, OsdiNode, OsdiNodePair, OsdiNoiseSource,
+    OsdiParamOpvar, OsdiSimInfo, OsdiSimParas, ACCESS_FLAG_SET, ANALYSIS_TRAN, EVAL_RET_FLAG_FATAL,
+    EVAL_RET_FLAG_FINISH, EVAL_RET_FLAG_LIM, INIT_ERR_OUT_OF_BOUNDS, PARA_KIND_INST, PARA_TY_INT,
+    PARA_TY_MASK, PARA_TY_REAL, PARA_TY_STR,
 };
 
 impl OsdiDescriptor {
@@ -46,6 +49,11 @@ impl OsdiDescriptor {
         unsafe { slice::from_raw_parts(self.jacobian_entries, self.num_jacobian_entries as usize) }
     }
 
+    fn noise_sources(&self) -> &[OsdiNoiseSource] {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe { slice::from_raw_parts(self.noise_sources, self.num_noise_src as usize) }
+    }
+
     fn check_init_result(&self, res: OsdiInitInfo) -> Result<()> {
         if (res.flags & EVAL_RET_FLAG_FATAL) != 0 {
             bail!("Verilog-A $fatal was called")
@@ -246,6 +254,8 @@ impl ModelImpl for OsdiModel {
             descriptor: self.descriptor,
             data: alloc(self.descriptor.instance_size as usize),
             model_data: self.data,
+            state: Vec::new(),
+            limited: false,
             _model: self,
         })
     }
@@ -255,6 +265,11 @@ struct OsdiInstance {
     descriptor: &'static OsdiDescriptor,
     data: *mut c_void,
     model_data: *mut c_void,
+    /// The states used by `$limit`: the limited voltages of the previous Newton iteration.
+    /// `eval` reads and overwrites them in place.
+    state: Vec<f64>,
+    /// Whether `$limit` was active during the last call to `eval`.
+    limited: bool,
     _model: Rc<OsdiModel>, // only kept to ensure the data stays live
 }
 
@@ -283,6 +298,15 @@ impl OsdiInstance {
         }
     }
 
+    fn state_idx(&self) -> &[Cell<u32>] {
+        let ptr = self.data as *mut u8;
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            let ptr = ptr.add(self.descriptor.state_idx_off as usize) as *mut Cell<u32>;
+            slice::from_raw_parts_mut(ptr, self.descriptor.num_states as usize)
+        }
+    }
+
     fn collapsed(&self) -> &[bool] {
         let ptr = self.data as *mut u8;
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
@@ -382,16 +406,19 @@ impl InstanceImpl for OsdiInstance {
         for node in &mut internal_nodes {
             let node_info = &self.descriptor.nodes()[*node as usize];
             // TODO: tolerance based upon natures: #2
-            let tol = if node_info.is_flow {
-                sim_builder.config.voltage_atol
+            let config = sim_builder.config;
+            let (atol, residual_atol) = if node_info.is_flow {
+                (config.current_atol, config.voltage_atol)
             } else {
-                sim_builder.config.current_atol
+                (config.voltage_atol, config.current_atol)
             };
 
             let name = unsafe { osdi_str(node_info.name) };
             let units = unsafe { osdi_str(node_info.units) };
             let residual_units = unsafe { osdi_str(node_info.residual_units) };
-            *node = sim_builder.new_internal_unknown(name, tol, units, residual_units).into();
+            *node = sim_builder
+                .new_internal_unknown(name, atol, residual_atol, units, residual_units)
+                .into();
         }
 
         let node_mapping = self.node_mapping();
@@ -412,6 +439,12 @@ impl InstanceImpl for OsdiInstance {
             sim_builder.ensure_matrix_entry(column, row)
         }
 
+        // every instance stores its own states so they can simply be numbered
+        for (i, idx) in self.state_idx().iter().enumerate() {
+            idx.set(i as u32)
+        }
+        self.state = vec![0f64; self.descriptor.num_states as usize];
+
         Ok(())
     }
 
@@ -471,8 +504,8 @@ impl InstanceImpl for OsdiInstance {
             paras: sim_params,
             abstime: sim_info.abstime,
             prev_solve: sim_info.prev_solve.as_ptr() as *mut f64,
-            prev_state: ptr::null_mut(),
-            next_state: ptr::null_mut(),
+            prev_state: self.state.as_mut_ptr(),
+            next_state: self.state.as_mut_ptr(),
             flags: sim_info.flags.bits(),
         };
 
@@ -486,10 +519,10 @@ impl InstanceImpl for OsdiInstance {
             bail!("Simulation aborted with $fatal")
         }
 
-        // TODO only during tran
-        // if (ret_flags & EVAL_RET_FLAG_FINISH) != 0 {
-        //     bail!("Simulation aborted with $finish")
-        // }
+        if (ret_flags & EVAL_RET_FLAG_FINISH) != 0 && (sim_info.flags.bits() & ANALYSIS_TRAN) != 0 {
+            bail!("Simulation aborted with $finish")
+        }
+        self.limited = (ret_flags & EVAL_RET_FLAG_LIM) != 0;
 
         Ok(())
     }
@@ -502,6 +535,10 @@ impl InstanceImpl for OsdiInstance {
         self.descriptor.load_jacobian_react(self.data, self.model_data, alpha)
     }
 
+    unsafe fn load_matrix_tran(&self, alpha: f64) {
+        self.descriptor.load_jacobian_tran(self.data, self.model_data, alpha)
+    }
+
     fn load_residual_react(
         &self,
         _prev_solve: &TiSlice<Node, f64>,
@@ -518,6 +555,54 @@ impl InstanceImpl for OsdiInstance {
         self.descriptor.load_residual_resist(self.data, self.model_data, residual.as_mut_ptr())
     }
 
+    fn bound_step(&self) -> f64 {
+        if self.descriptor.bound_step_offset == u32::MAX {
+            return f64::INFINITY;
+        }
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            let ptr = (self.data as *mut u8).add(self.descriptor.bound_step_offset as usize);
+            (ptr as *const f64).read()
+        }
+    }
+
+    fn load_limit_rhs_resist(&self, rhs: &mut TiSlice<Node, f64>) {
+        self.descriptor.load_limit_rhs_resist(self.data, self.model_data, rhs.as_mut_ptr())
+    }
+
+    fn load_limit_rhs_react(&self, rhs: &mut TiSlice<Node, f64>) {
+        self.descriptor.load_limit_rhs_react(self.data, self.model_data, rhs.as_mut_ptr())
+    }
+
+    fn is_limited(&self) -> bool {
+        self.limited
+    }
+
+    fn noise_sources(&self) -> Vec<NoiseSource> {
+        let node_mapping = self.node_mapping();
+        let node = |node: u32| {
+            if node == u32::MAX {
+                Node::GROUND
+            } else {
+                node_mapping[node as usize].get().into()
+            }
+        };
+        self.descriptor
+            .noise_sources()
+            .iter()
+            .map(|src| NoiseSource {
+                name: unsafe { osdi_str(src.name) },
+                hi: node(src.nodes.node_1),
+                lo: node(src.nodes.node_2),
+            })
+            .collect()
+    }
+
+    fn load_noise(&self, freq: f64, dst: &mut [f64]) {
+        debug_assert_eq!(dst.len(), self.descriptor.num_noise_src as usize);
+        self.descriptor.load_noise(self.data, self.model_data, freq, dst.as_mut_ptr())
+    }
+
     fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
         for (node, dst) in zip(self.descriptor.terminals(), dst) {
             unsafe {
@@ -1,5 +1,4 @@
-# MOCKUP currently melange is not complete
-# This file represents a goal API of melange but some details may change
+# Requires the python bindings in melange/melange_py
 from melange import Circuit, CircuitInstance
 
 import numpy as np
@@ -16,28 +15,29 @@ temp = 300
 # create circuit
 circ = Circuit("test_circuit")
 circ.load_veriloga_file("bsimbulk.va")
-fet = CircuitInstance(circ, "test_fet", "bsimbulk", ports=["drain", "gate" ,"gnd", "gnd"])
+circ.def_param("gate_bias")
+fet = CircuitInstance(circ, "test_fet", "bsimbulk", ports=["drain", "gate" ,"ground", "ground"])
 fet.set_param("RSH", 1e-3) # real model has many more parameter
-vdd = CircuitInstance(circ, "vdd", "vsource", ports=["drain", "gnd"])
+vdd = CircuitInstance(circ, "vdd", "vsource", ports=["drain", "ground"])
 vdd.set_param("dc", 2)
-vdd.set_param("ac", 0)
-vin = CircuitInstance(circ, "vin", "vsource", ports=["gate", "gnd"])
+vdd.set_param("mag", 0)
+vin = CircuitInstance(circ, "vin", "vsource", ports=["gate", "ground"])
 vin.set_param("dc", "gate_bias")
-vin.set_param("ac", 1)
+vin.set_param("mag", 1)
 
 # set circuit parameters and setup simulation for each parameter
 simulation = circ.prepare_sim(temp=temp, gate_bias=vg)
 
 # obain drain current from dc simulations
 simulation.dc_op()
-id = simulation.lead_current("vdd", "anode");
+id = simulation.lead_current("vdd", "A")
 # plot transfer characteristic Id(Vg)
 plt.plot(vg, id)
 
 # deterime ft from ac simulations (using the previously simulated operating points)
 simulation.ac(freq=freq)
-y21 = simulation.ac_lead_current("vdd", "anode")
-y11 = simulation.ac_lead_current("vin", "anode")
+y21 = simulation.ac_lead_current("vdd", "A")
+y11 = simulation.ac_lead_current("vin", "A")
 ft = freq/np.imag(y11/y21)
 # plot ft characteristic ft(Vg)
 plt.plot(vg, ft)
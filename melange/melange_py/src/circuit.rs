@@ -0,0 +1,484 @@
+use std::os::raw::c_char;
+use std::ptr::{self, addr_of_mut};
+
+use anyhow::{bail, Result};
+use camino::Utf8PathBuf;
+use melange_core::circuit::{CircuitModelSrc, InstanceId};
+use melange_core::{Arena, Circuit, CircuitParam, Expr};
+use pyo3_ffi::structmember::{PyMemberDef, READONLY, T_OBJECT};
+use pyo3_ffi::*;
+
+use crate::ffi::{free_object, new_type};
+use crate::simulation::PySimulation;
+use crate::util::{
+    float_arg, none, parse_args, py_str, raise_exception, raise_runtime_exception,
+    raise_type_exception, str_arg, str_list_arg, unlikely,
+};
+
+pub static mut CIRCUIT_TY: PyTypeObject = {
+    let mut res = new_type::<PyCircuit>();
+    res.tp_name = "melange.Circuit\0".as_ptr() as *const c_char;
+    res.tp_doc = "A circuit that can be simulated with melange\0".as_ptr() as *const c_char;
+    res.tp_members = unsafe { &mut CIRCUIT_MEMBERS } as *mut _;
+    res.tp_methods = unsafe { &mut CIRCUIT_METHODS } as *mut _;
+    res.tp_new = Some(PyCircuit::new);
+    res.tp_dealloc = Some(PyCircuit::dealloc);
+    res
+};
+
+static mut CIRCUIT_MEMBERS: [PyMemberDef; 2] = [
+    PyMemberDef {
+        name: "name\0".as_ptr() as *mut c_char,
+        type_code: T_OBJECT,
+        offset: PyCircuit::offset_to.name as isize,
+        flags: READONLY,
+        doc: "The name of the circuit\0".as_ptr() as *mut c_char,
+    },
+    unsafe { zero!(PyMemberDef) },
+];
+
+static mut CIRCUIT_METHODS: [PyMethodDef; 6] = [
+    PyMethodDef {
+        ml_name: "load_veriloga_file\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PyCircuit::load_veriloga_file },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "load_veriloga_file(path)\n--\n\nCompiles a Verilog-A file and makes all modules within available as devices.\nReturns the names of the newly added devices.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "def_param\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PyCircuit::def_param },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "def_param(name, default=None)\n--\n\nDefines a circuit parameter. Its value is passed to `prepare_sim` (or taken from `default`).\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "new_model\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PyCircuit::new_model },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "new_model(name, device)\n--\n\nCreates a model for a device that can be shared by multiple instances.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "set_model_param\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PyCircuit::set_model_param },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "set_model_param(model, name, value)\n--\n\nSets a parameter of a model created with `new_model`.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "prepare_sim\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PyCircuit::prepare_sim },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "prepare_sim(temp=300.15, **params)\n--\n\nCreates a simulation of the circuit. Each circuit parameter can be set to a float or\nan array of floats. All arrays must have the same length, every analysis runs once for\neach of their values.\0"
+            .as_ptr() as *const c_char,
+    },
+    unsafe { zero!(PyMethodDef) },
+];
+
+with_offsets! {
+    #[repr(C)]
+    pub struct PyCircuit {
+        ob_base: PyObject,
+        name: *mut PyObject,
+        pub arena: Box<Arena>,
+        pub circ: Box<Circuit>,
+        /// The defaults of the parameters defined with `def_param`. These are only used if
+        /// no value is passed to `prepare_sim`.
+        pub param_defaults: Vec<(CircuitParam, Expr)>,
+        /// The number of simulations that borrow `circ` and `arena`, the circuit can not be
+        /// modified while any simulation is alive.
+        pub active_sims: usize,
+    }
+}
+
+impl PyCircuit {
+    #[allow(clippy::new_ret_no_self)]
+    unsafe extern "C" fn new(
+        subtype: *mut PyTypeObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let Some([name]) = parse_args("Circuit", args, kwds, ["name"], 1) else {
+            return ptr::null_mut();
+        };
+        let Some(name_str) = str_arg("Circuit", "name", name) else {
+            return ptr::null_mut();
+        };
+
+        let ptr = (*subtype).tp_alloc.unwrap()(subtype, 0);
+        if ptr.is_null() {
+            return ptr::null_mut();
+        }
+
+        let mut arena = Box::new(Arena::new());
+        let circ = Box::new(Circuit::new(name_str.to_owned(), &mut arena));
+        Py_INCREF(name);
+        let res = ptr as *mut Self;
+        addr_of_mut!((*res).name).write(name);
+        addr_of_mut!((*res).arena).write(arena);
+        addr_of_mut!((*res).circ).write(circ);
+        addr_of_mut!((*res).param_defaults).write(Vec::new());
+        addr_of_mut!((*res).active_sims).write(0);
+        ptr
+    }
+
+    unsafe extern "C" fn dealloc(sel: *mut PyObject) {
+        let res = sel as *mut Self;
+        Py_XDECREF((*res).name);
+        ptr::drop_in_place(addr_of_mut!((*res).circ));
+        ptr::drop_in_place(addr_of_mut!((*res).arena));
+        ptr::drop_in_place(addr_of_mut!((*res).param_defaults));
+        free_object(sel)
+    }
+
+    /// Simulations borrow the circuit, so it must not be modified while they are alive.
+    ///
+    /// # Returns
+    ///
+    /// `false` (and raises an exception) if the circuit can not be modified
+    unsafe fn ensure_unlocked(&self, fun: &str) -> bool {
+        if unlikely(self.active_sims != 0) {
+            raise_exception(
+                PyExc_RuntimeError,
+                &format!("{fun}() can not modify a circuit that is simulated"),
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Converts a parameter value, a `str` that names a circuit parameter refers to that
+    /// parameter, all other strings are used as a string value.
+    unsafe fn value_arg(&mut self, fun: &str, name: &str, obj: *mut PyObject) -> Option<Expr> {
+        if PyUnicode_Check(obj) != 0 {
+            let val = str_arg(fun, name, obj)?;
+            let expr = match self.circ.lookup_param(val, &self.arena) {
+                Some((_, read_expr)) => read_expr,
+                None => Expr::str(&mut self.arena, val),
+            };
+            return Some(expr);
+        }
+        float_arg(fun, name, obj).map(Expr::from)
+    }
+
+    unsafe extern "C" fn load_veriloga_file(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        const FUN: &str = "load_veriloga_file";
+        let self_ = &mut *(self_ as *mut Self);
+        let Some([path]) = parse_args(FUN, args, kwds, ["path"], 1) else {
+            return ptr::null_mut();
+        };
+        if !self_.ensure_unlocked(FUN) {
+            return ptr::null_mut();
+        }
+
+        // accept both str and pathlib.Path
+        let path = PyOS_FSPath(path);
+        if path.is_null() {
+            return ptr::null_mut();
+        }
+        let path_str = str_arg(FUN, "path", path).map(Utf8PathBuf::from);
+        Py_DECREF(path);
+        let Some(path) = path_str else {
+            return ptr::null_mut();
+        };
+
+        match self_.circ.load_veriloga_file(path, &Default::default()) {
+            Ok(devices) => {
+                let res = PyList_New(devices.len() as isize);
+                for (i, dev) in devices.into_iter().enumerate() {
+                    PyList_SetItem(res, i as isize, py_str(self_.circ[dev].name));
+                }
+                res
+            }
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+
+    unsafe extern "C" fn def_param(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        const FUN: &str = "def_param";
+        let self_ = &mut *(self_ as *mut Self);
+        let Some([name, default]) = parse_args(FUN, args, kwds, ["name", "default"], 1) else {
+            return ptr::null_mut();
+        };
+        let Some(name) = str_arg(FUN, "name", name) else {
+            return ptr::null_mut();
+        };
+        if !self_.ensure_unlocked(FUN) {
+            return ptr::null_mut();
+        }
+
+        let default = if default.is_null() || default == Py_None() {
+            None
+        } else {
+            match self_.value_arg(FUN, "default", default) {
+                Some(val) => Some(val),
+                None => return ptr::null_mut(),
+            }
+        };
+
+        match self_.circ.def_param(name.to_owned(), None, &mut self_.arena) {
+            Ok((param, _)) => {
+                if let Some(default) = default {
+                    self_.param_defaults.push((param, default));
+                }
+                none()
+            }
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+
+    unsafe extern "C" fn new_model(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        const FUN: &str = "new_model";
+        let self_ = &mut *(self_ as *mut Self);
+        let Some([name, device]) = parse_args(FUN, args, kwds, ["name", "device"], 2) else {
+            return ptr::null_mut();
+        };
+        let (Some(name), Some(device)) =
+            (str_arg(FUN, "name", name), str_arg(FUN, "device", device))
+        else {
+            return ptr::null_mut();
+        };
+        if !self_.ensure_unlocked(FUN) {
+            return ptr::null_mut();
+        }
+
+        let res = self_
+            .ensure_undeclared(name)
+            .and_then(|_| self_.circ.new_model_by_name(name.to_owned(), device));
+        match res {
+            Ok(_) => none(),
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+
+    unsafe extern "C" fn set_model_param(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        const FUN: &str = "set_model_param";
+        let self_ = &mut *(self_ as *mut Self);
+        let Some([model, name, value]) = parse_args(FUN, args, kwds, ["model", "name", "value"], 3)
+        else {
+            return ptr::null_mut();
+        };
+        let (Some(model), Some(name)) = (str_arg(FUN, "model", model), str_arg(FUN, "name", name))
+        else {
+            return ptr::null_mut();
+        };
+        if !self_.ensure_unlocked(FUN) {
+            return ptr::null_mut();
+        }
+        let Some(value) = self_.value_arg(FUN, "value", value) else {
+            return ptr::null_mut();
+        };
+
+        let res = match self_.circ.lookup_model(model) {
+            Some(model) => self_.circ.set_model_param(model, name, value),
+            None => Err(anyhow::anyhow!("unknown model '{model}'")),
+        };
+        match res {
+            Ok(()) => none(),
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+
+    unsafe extern "C" fn prepare_sim(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        if unlikely(PyTuple_GET_SIZE(args) != 0) {
+            return raise_type_exception(
+                "prepare_sim() only accepts keyword arguments (circuit parameters)",
+            );
+        }
+        PySimulation::new(self_, kwds)
+    }
+
+    /// The core API expects unique names, duplicates are reported as an error here instead
+    fn ensure_undeclared(&mut self, name: &str) -> Result<()> {
+        let circ = &mut *self.circ;
+        if circ.lookup_instance(name).is_some()
+            || circ.lookup_model(name).is_some()
+            || circ.lookup_device(name).is_some()
+        {
+            bail!("'{name}' was already declared in this circuit")
+        }
+        Ok(())
+    }
+
+    fn new_instance(&mut self, name: &str, master: &str, ports: Vec<String>) -> Result<InstanceId> {
+        self.ensure_undeclared(name)?;
+        let circ = &mut *self.circ;
+        if let Some(device) = circ.lookup_device(master) {
+            let ports = ports.into_iter().map(|port| circ.node(port)).collect();
+            let (instance, _) = circ.new_device_instance(name.to_owned(), device, ports)?;
+            return Ok(instance);
+        }
+        let Some(model) = circ.lookup_model(master) else {
+            bail!("unknown device or model '{master}'")
+        };
+        let ports = ports.into_iter().map(|port| circ.node(port)).collect();
+        circ.new_model_instance(name.to_owned(), model, ports)
+    }
+
+    /// Sets an instance parameter. Instances of a device (instead of a model) have their own
+    /// model so its parameters can be set here too.
+    fn set_instance_param(&mut self, instance: InstanceId, name: &str, val: Expr) -> Result<()> {
+        let circ = &mut *self.circ;
+        let model = circ[instance].model;
+        match circ.set_instance_param(instance, name, val) {
+            Err(_) if circ[model].src == CircuitModelSrc::Implicit(instance) => {
+                circ.set_model_param(model, name, val)
+            }
+            res => res,
+        }
+    }
+}
+
+pub static mut CIRCUIT_INSTANCE_TY: PyTypeObject = {
+    let mut res = new_type::<PyCircuitInstance>();
+    res.tp_name = "melange.CircuitInstance\0".as_ptr() as *const c_char;
+    res.tp_doc =
+        "CircuitInstance(circuit, name, master, ports)\n--\n\nAn instance of a device (or model) within a circuit\0"
+            .as_ptr() as *const c_char;
+    res.tp_members = unsafe { &mut CIRCUIT_INSTANCE_MEMBERS } as *mut _;
+    res.tp_methods = unsafe { &mut CIRCUIT_INSTANCE_METHODS } as *mut _;
+    res.tp_new = Some(PyCircuitInstance::new);
+    res.tp_dealloc = Some(PyCircuitInstance::dealloc);
+    res
+};
+
+static mut CIRCUIT_INSTANCE_MEMBERS: [PyMemberDef; 3] = [
+    PyMemberDef {
+        name: "circuit\0".as_ptr() as *mut c_char,
+        type_code: T_OBJECT,
+        offset: PyCircuitInstance::offset_to.circ as isize,
+        flags: READONLY,
+        doc: "The circuit this instance belongs to\0".as_ptr() as *mut c_char,
+    },
+    PyMemberDef {
+        name: "name\0".as_ptr() as *mut c_char,
+        type_code: T_OBJECT,
+        offset: PyCircuitInstance::offset_to.name as isize,
+        flags: READONLY,
+        doc: "The name of the instance\0".as_ptr() as *mut c_char,
+    },
+    unsafe { zero!(PyMemberDef) },
+];
+
+static mut CIRCUIT_INSTANCE_METHODS: [PyMethodDef; 2] = [
+    PyMethodDef {
+        ml_name: "set_param\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PyCircuitInstance::set_param },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "set_param(name, value)\n--\n\nSets an instance parameter (or a model parameter if the instance was created for a device).\nA str value that names a circuit parameter refers to that parameter.\0"
+            .as_ptr() as *const c_char,
+    },
+    unsafe { zero!(PyMethodDef) },
+];
+
+with_offsets! {
+    #[repr(C)]
+    pub struct PyCircuitInstance {
+        ob_base: PyObject,
+        circ: *mut PyObject,
+        name: *mut PyObject,
+        instance: InstanceId,
+    }
+}
+
+impl PyCircuitInstance {
+    #[allow(clippy::new_ret_no_self)]
+    unsafe extern "C" fn new(
+        subtype: *mut PyTypeObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        const FUN: &str = "CircuitInstance";
+        let Some([circ, name, master, ports]) =
+            parse_args(FUN, args, kwds, ["circuit", "name", "master", "ports"], 4)
+        else {
+            return ptr::null_mut();
+        };
+        if unlikely(PyObject_TypeCheck(circ, addr_of_mut!(CIRCUIT_TY)) == 0) {
+            return raise_type_exception("CircuitInstance() argument 'circuit' must be a Circuit");
+        }
+        let (Some(name_str), Some(master), Some(ports)) = (
+            str_arg(FUN, "name", name),
+            str_arg(FUN, "master", master),
+            str_list_arg(FUN, "ports", ports),
+        ) else {
+            return ptr::null_mut();
+        };
+
+        let py_circ = &mut *(circ as *mut PyCircuit);
+        if !py_circ.ensure_unlocked(FUN) {
+            return ptr::null_mut();
+        }
+        let instance = match py_circ.new_instance(name_str, master, ports) {
+            Ok(instance) => instance,
+            Err(err) => return raise_runtime_exception(err),
+        };
+
+        let ptr = (*subtype).tp_alloc.unwrap()(subtype, 0);
+        if ptr.is_null() {
+            return ptr::null_mut();
+        }
+        Py_INCREF(circ);
+        Py_INCREF(name);
+        let res = &mut *(ptr as *mut Self);
+        res.circ = circ;
+        res.name = name;
+        res.instance = instance;
+        ptr
+    }
+
+    unsafe extern "C" fn dealloc(sel: *mut PyObject) {
+        let res = &mut *(sel as *mut Self);
+        Py_XDECREF(res.circ);
+        Py_XDECREF(res.name);
+        free_object(sel)
+    }
+
+    unsafe extern "C" fn set_param(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        const FUN: &str = "set_param";
+        let self_ = &mut *(self_ as *mut Self);
+        let Some([name, value]) = parse_args(FUN, args, kwds, ["name", "value"], 2) else {
+            return ptr::null_mut();
+        };
+        let Some(name) = str_arg(FUN, "name", name) else {
+            return ptr::null_mut();
+        };
+        let circ = &mut *(self_.circ as *mut PyCircuit);
+        if !circ.ensure_unlocked(FUN) {
+            return ptr::null_mut();
+        }
+        let Some(value) = circ.value_arg(FUN, "value", value) else {
+            return ptr::null_mut();
+        };
+
+        match circ.set_instance_param(self_.instance, name, value) {
+            Ok(()) => none(),
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+}
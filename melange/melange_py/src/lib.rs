@@ -0,0 +1,81 @@
+#[macro_use]
+mod offsets;
+#[macro_use]
+mod ffi;
+mod circuit;
+mod numpy;
+mod simulation;
+mod typeref;
+mod util;
+
+use std::os::raw::c_char;
+use std::ptr::{self, addr_of_mut};
+
+use crate::circuit::{CIRCUIT_INSTANCE_TY, CIRCUIT_TY};
+use crate::simulation::SIMULATION_TY;
+use crate::typeref::init_typerefs;
+use pyo3_ffi::*;
+
+static mut FUNCTIONS: [PyMethodDef; 1] = [unsafe { zero!(PyMethodDef) }];
+
+#[allow(clippy::missing_safety_doc)]
+#[allow(non_snake_case)]
+#[no_mangle]
+#[cold]
+pub unsafe extern "C" fn PyInit_melange() -> *mut PyObject {
+    let init = PyModuleDef {
+        m_base: PyModuleDef_HEAD_INIT,
+        m_name: "melange\0".as_ptr() as *const c_char,
+        m_doc: "A circuit simulator for Verilog-A compact models\0".as_ptr() as *const c_char,
+        m_size: 0,
+        m_methods: addr_of_mut!(FUNCTIONS) as *mut PyMethodDef,
+        m_slots: std::ptr::null_mut(),
+        m_traverse: None,
+        m_clear: None,
+        m_free: None,
+    };
+
+    let types = [
+        ("Circuit\0", addr_of_mut!(CIRCUIT_TY)),
+        ("CircuitInstance\0", addr_of_mut!(CIRCUIT_INSTANCE_TY)),
+        ("Simulation\0", addr_of_mut!(SIMULATION_TY)),
+    ];
+
+    for (_, ty) in types {
+        if PyType_Ready(ty) < 0 {
+            return ptr::null_mut();
+        }
+    }
+
+    let mptr = PyModule_Create(Box::into_raw(Box::new(init)));
+    if mptr.is_null() {
+        return ptr::null_mut();
+    }
+    init_typerefs();
+    let version = env!("CARGO_PKG_VERSION");
+    PyModule_AddObject(
+        mptr,
+        "__version__\0".as_ptr() as *const c_char,
+        PyUnicode_FromStringAndSize(version.as_ptr() as *const c_char, version.len() as isize),
+    );
+
+    for (name, ty) in types {
+        Py_INCREF(ty as *mut PyObject);
+        PyModule_AddObject(mptr, name.as_ptr() as *const c_char, ty as *mut PyObject);
+    }
+
+    let all = ["__all__\0", "__version__\0", "Circuit\0", "CircuitInstance\0", "Simulation\0"];
+
+    let pyall = PyTuple_New(all.len() as isize);
+    for (i, obj) in all.iter().enumerate() {
+        PyTuple_SET_ITEM(
+            pyall,
+            i as isize,
+            PyUnicode_InternFromString(obj.as_ptr() as *const c_char),
+        )
+    }
+
+    PyModule_AddObject(mptr, "__all__\0".as_ptr() as *const c_char, pyall);
+
+    mptr
+}
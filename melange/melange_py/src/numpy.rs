@@ -0,0 +1,149 @@
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use num_complex::Complex64;
+use pyo3_ffi::{
+    PyErr_Clear, PyExc_ImportError, PyObject, PyObject_GetAttr, PyTypeObject, Py_DECREF, Py_INCREF,
+    Py_intptr_t, Py_ssize_t,
+};
+
+use crate::typeref::{
+    ARRAY_STRUCT_STR, NUMPY_API, NUMPY_ARR_TYPE, NUMPY_CDOUBLE_DESCR, NUMPY_DOUBLE_DESCR,
+};
+use crate::util::raise_exception;
+
+// https://docs.scipy.org/doc/numpy/reference/arrays.interface.html#c.__array_struct__
+
+#[repr(C)]
+struct PyArrayInterface {
+    pub two: c_int,
+    pub nd: c_int,
+    pub typekind: c_char,
+    pub itemsize: c_int,
+    pub flags: c_int,
+    pub shape: *mut Py_intptr_t,
+    pub strides: *mut Py_intptr_t,
+    pub data: *mut c_void,
+    pub descr: *mut PyObject,
+}
+
+#[repr(C)]
+struct PyCapsule {
+    pub ob_refcnt: Py_ssize_t,
+    pub ob_type: *mut PyTypeObject,
+    pub pointer: *mut c_void,
+    pub name: *const c_char,
+    pub context: *mut c_void,
+    pub destructor: *mut c_void, // should be typedef void (*PyCapsule_Destructor)(PyObject *);
+}
+
+/// The start of `PyArrayObject_fields`, the memory layout of every numpy array
+#[repr(C)]
+struct PyArrayObject {
+    pub ob_base: PyObject,
+    pub data: *mut c_char,
+}
+
+/// A one dimensional float64 numpy array
+pub struct NumpyArray {
+    array: *mut PyArrayInterface,
+    capsule: *mut PyCapsule,
+}
+
+impl NumpyArray {
+    /// # Returns
+    ///
+    /// `None` if `ptr` is not a one dimensional float64 array
+    pub fn new(ptr: *mut PyObject) -> Option<Self> {
+        let capsule = unsafe { PyObject_GetAttr(ptr, ARRAY_STRUCT_STR) };
+        if capsule.is_null() {
+            // not an array, the caller falls back to other types
+            unsafe { PyErr_Clear() };
+            return None;
+        }
+        let array = unsafe { (*(capsule as *mut PyCapsule)).pointer as *mut PyArrayInterface };
+        let supported = unsafe {
+            (*array).two == 2
+                && (*array).nd == 1
+                && (*array).typekind == b'f' as c_char
+                && (*array).itemsize == 8
+                && *(*array).strides % 8 == 0
+        };
+        if supported {
+            Some(NumpyArray { array, capsule: capsule as *mut PyCapsule })
+        } else {
+            unsafe { Py_DECREF(capsule) };
+            None
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<f64> {
+        unsafe {
+            let len = *(*self.array).shape as usize;
+            let stride = *(*self.array).strides / 8;
+            let data = (*self.array).data as *const f64;
+            (0..len).map(|i| *data.offset(i as isize * stride)).collect()
+        }
+    }
+}
+
+impl Drop for NumpyArray {
+    fn drop(&mut self) {
+        unsafe { Py_DECREF(self.capsule as *mut pyo3_ffi::PyObject) }
+    }
+}
+
+#[inline(always)]
+pub fn is_array(ty: *mut PyTypeObject) -> bool {
+    match unsafe { NUMPY_ARR_TYPE } {
+        Some(arr_type) => ty == arr_type,
+        None => false,
+    }
+}
+
+/// Element types that can be copied into a newly created numpy array
+pub trait Element: Copy {
+    unsafe fn descr() -> *mut PyObject;
+}
+
+impl Element for f64 {
+    unsafe fn descr() -> *mut PyObject {
+        NUMPY_DOUBLE_DESCR
+    }
+}
+
+impl Element for Complex64 {
+    unsafe fn descr() -> *mut PyObject {
+        NUMPY_CDOUBLE_DESCR
+    }
+}
+
+/// Creates a new (C contiguous) numpy array with shape `dims` that contains a copy of `data`
+pub unsafe fn new_array<T: Element>(dims: &[isize], data: &[T]) -> *mut PyObject {
+    debug_assert_eq!(dims.iter().product::<isize>(), data.len() as isize);
+    let (new_arr, arr_type) = match (NUMPY_API, NUMPY_ARR_TYPE) {
+        (Some(new_arr), Some(arr_type)) => (new_arr, arr_type),
+        _ => return raise_exception(PyExc_ImportError, "numpy is required to access results"),
+    };
+
+    let descr = T::descr();
+    Py_INCREF(descr);
+    let mut dims = dims.to_vec();
+    let arr = new_arr(
+        arr_type,            // base_type (normal numpy array)
+        descr,               // type descriptor
+        dims.len() as c_int, //nd
+        dims.as_mut_ptr(),   //dims
+        ptr::null_mut(),     // strides (C contiguous)
+        ptr::null_mut(),     //data (to be allocated)
+        0,                   // flags
+        ptr::null_mut(),     // obj (to be created)
+    );
+    if arr.is_null() {
+        return arr;
+    }
+    let dst = (*(arr as *mut PyArrayObject)).data as *mut T;
+    ptr::copy_nonoverlapping(data.as_ptr(), dst, data.len());
+    arr
+}
@@ -0,0 +1,620 @@
+use std::f64::consts::TAU;
+use std::os::raw::{c_char, c_long};
+use std::ptr::{self, addr_of_mut};
+use std::slice;
+
+use anyhow::{bail, Context, Result};
+use melange_core::circuit::{InstanceId, Node};
+use melange_core::simulation::{DcSweep, SimConfig, Simulation, SweepParam};
+use melange_core::{Arena, Circuit, CircuitParam, Expr, ExprEvalCtx};
+use num_complex::Complex64;
+use pyo3_ffi::*;
+use stdx::iter::zip;
+use typed_index_collections::TiVec;
+
+use crate::circuit::PyCircuit;
+use crate::ffi::{free_object, new_type};
+use crate::numpy::{new_array, Element};
+use crate::util::{
+    float_arg, float_list_arg, none, parse_args, raise_exception, raise_runtime_exception,
+    raise_type_exception, str_arg, unlikely,
+};
+
+/// The temperature (27°C) used if `temp` is not passed to `prepare_sim`
+const DEFAULT_TEMP: f64 = 300.15;
+
+pub static mut SIMULATION_TY: PyTypeObject = {
+    let mut res = new_type::<PySimulation>();
+    res.tp_name = "melange.Simulation\0".as_ptr() as *const c_char;
+    res.tp_doc = "A simulation of a circuit created with `Circuit.prepare_sim`.\nAll results are numpy arrays whose first axis are the values of the circuit parameters.\0"
+        .as_ptr() as *const c_char;
+    res.tp_methods = unsafe { &mut SIMULATION_METHODS } as *mut _;
+    res.tp_dealloc = Some(PySimulation::dealloc);
+    res
+};
+
+static mut SIMULATION_METHODS: [PyMethodDef; 10] = [
+    PyMethodDef {
+        ml_name: "dc_op\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::dc_op },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "dc_op()\n--\n\nSolves the DC operating point.\0".as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "voltage\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::voltage },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "voltage(node)\n--\n\nThe voltage of a node at the DC operating point.\0".as_ptr()
+            as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "lead_current\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::lead_current },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "lead_current(instance, terminal)\n--\n\nThe current into a terminal (name or index) of an instance at the DC operating point.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "ac\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::ac },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "ac(freq)\n--\n\nRuns a small signal analysis at one frequency (float) or multiple frequencies (array).\nFor multiple frequencies the results have an additional axis.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "ac_voltage\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::ac_voltage },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "ac_voltage(node)\n--\n\nThe (complex) small signal voltage of a node.\0".as_ptr()
+            as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "ac_lead_current\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::ac_lead_current },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "ac_lead_current(instance, terminal)\n--\n\nThe (complex) small signal current into a terminal of an instance.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "dc_sweep\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::dc_sweep },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "dc_sweep(*sweeps)\n--\n\nSolves the DC operating point for every combination of the swept values.\nEach sweep is a tuple (param, values) where param is the name of a circuit parameter or a\ntuple (instance, param). The first sweep is the innermost sweep.\nThe results have an additional axis with all points of the sweep.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "sweep_voltage\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer { PyCFunctionWithKeywords: PySimulation::sweep_voltage },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "sweep_voltage(node)\n--\n\nThe voltage of a node during the last DC sweep.\0"
+            .as_ptr() as *const c_char,
+    },
+    PyMethodDef {
+        ml_name: "sweep_lead_current\0".as_ptr() as *const c_char,
+        ml_meth: PyMethodDefPointer {
+            PyCFunctionWithKeywords: PySimulation::sweep_lead_current,
+        },
+        ml_flags: METH_VARARGS | METH_KEYWORDS,
+        ml_doc: "sweep_lead_current(instance, terminal)\n--\n\nThe current into a terminal of an instance during the last DC sweep.\0"
+            .as_ptr() as *const c_char,
+    },
+    unsafe { zero!(PyMethodDef) },
+];
+
+with_offsets! {
+    #[repr(C)]
+    pub struct PySimulation {
+        ob_base: PyObject,
+        circ: *mut PyObject,
+        data: Box<SimData>,
+    }
+}
+
+/// The state of a simulation. `circ`, `arena` (and therefore `sim` and `ctx`) borrow from the
+/// `PyCircuit` that is kept alive by the simulation object. The circuit can not be modified
+/// while the simulation exists.
+struct SimData {
+    sim: Simulation<'static>,
+    ctx: ExprEvalCtx<'static>,
+    circ: &'static Circuit,
+    arena: &'static Arena,
+    /// The values of the circuit parameters passed to `prepare_sim`. Each has either one value
+    /// or a value for every point.
+    params: Box<[(CircuitParam, Box<[f64]>)]>,
+    /// The parameters that were not passed to `prepare_sim` and use their default instead.
+    defaults: Box<[(CircuitParam, Expr)]>,
+    num_points: usize,
+    /// The solution at the previous point, used as the initial guess for the next point
+    guess: Option<TiVec<Node, f64>>,
+    dc: Option<Waveforms<f64>>,
+    ac: Option<Waveforms<Complex64>>,
+    sweep: Option<Waveforms<f64>>,
+}
+
+/// The results of an analysis for every point
+struct Waveforms<T> {
+    /// The shape of the numpy arrays, the first axis are the points of the simulation.
+    shape: Vec<isize>,
+    voltages: TiVec<Node, Vec<T>>,
+    lead_currents: TiVec<InstanceId, Box<[Vec<T>]>>,
+}
+
+impl<T: Copy> Waveforms<T> {
+    fn new(circ: &Circuit, shape: Vec<isize>) -> Self {
+        let lead_currents = circ
+            .instances()
+            .map(|inst| vec![Vec::new(); circ[inst].connections.len()].into_boxed_slice())
+            .collect();
+        Waveforms { shape, voltages: TiVec::new(), lead_currents }
+    }
+
+    fn record_voltages<'a>(&mut self, voltages: impl ExactSizeIterator<Item = &'a [T]>)
+    where
+        T: 'a,
+    {
+        if self.voltages.is_empty() {
+            self.voltages.resize(voltages.len(), Vec::new());
+        }
+        for (dst, src) in zip(&mut self.voltages, voltages) {
+            dst.extend_from_slice(src);
+        }
+    }
+
+    fn record_lead_currents<'a>(
+        &mut self,
+        inst: InstanceId,
+        currents: impl Iterator<Item = &'a [T]>,
+    ) where
+        T: 'a,
+    {
+        for (dst, src) in zip(self.lead_currents[inst].iter_mut(), currents) {
+            dst.extend_from_slice(src);
+        }
+    }
+}
+
+impl SimData {
+    fn prepare_point(&mut self, point: usize) -> Result<()> {
+        for (param, values) in &*self.params {
+            self.ctx.set_param(*param, values[point % values.len()].into());
+        }
+        for &(param, default) in &*self.defaults {
+            let val = default.eval(self.ctx.borrow())?;
+            self.ctx.set_param(param, val);
+        }
+        self.sim.prepare_solver(self.ctx.borrow(), self.arena)?;
+        if let Some(guess) = &self.guess {
+            self.sim.set_initial_guess(guess);
+        }
+        Ok(())
+    }
+
+    fn dc_op(&mut self) -> Result<()> {
+        let mut res = Waveforms::new(self.circ, vec![self.num_points as isize]);
+        for point in 0..self.num_points {
+            self.prepare_point(point)
+                .and_then(|_| {
+                    let solution = self.sim.dc_op()?;
+                    res.record_voltages(solution.iter().map(slice::from_ref));
+                    self.guess = Some(solution.to_owned());
+                    for inst in self.circ.instances() {
+                        let currents = self.sim.dc_lead_current(inst)?;
+                        res.record_lead_currents(inst, currents.iter().map(slice::from_ref));
+                    }
+                    Ok(())
+                })
+                .with_context(|| format!("dc_op() failed at point {point}"))?;
+        }
+        self.dc = Some(res);
+        Ok(())
+    }
+
+    fn ac(&mut self, freqs: &[f64], shape: Vec<isize>) -> Result<()> {
+        let mut res = Waveforms::new(self.circ, shape);
+        for point in 0..self.num_points {
+            self.prepare_point(point)
+                .and_then(|_| {
+                    self.guess = Some(self.sim.ac_op()?.to_owned());
+                    for &freq in freqs {
+                        self.sim.set_omega(TAU * freq);
+                        let solution = self.sim.ac()?;
+                        res.record_voltages(solution.iter().map(slice::from_ref));
+                        for inst in self.circ.instances() {
+                            let currents = self.sim.ac_lead_current(inst)?;
+                            res.record_lead_currents(inst, currents.iter().map(slice::from_ref));
+                        }
+                    }
+                    Ok(())
+                })
+                .with_context(|| format!("ac() failed at point {point}"))?;
+        }
+        self.ac = Some(res);
+        Ok(())
+    }
+
+    fn dc_sweep(&mut self, sweeps: &[DcSweep]) -> Result<()> {
+        let len: usize = sweeps.iter().map(|sweep| sweep.values.len()).product();
+        let mut res = Waveforms::new(self.circ, vec![self.num_points as isize, len as isize]);
+        for point in 0..self.num_points {
+            self.prepare_point(point)
+                .and_then(|_| self.sim.dc_sweep(self.ctx.borrow(), self.arena, sweeps))
+                .map(|sweep| {
+                    res.record_voltages(sweep.solutions.iter().map(Vec::as_slice));
+                    for (inst, currents) in sweep.lead_currents.iter_enumerated() {
+                        res.record_lead_currents(inst, currents.iter().map(Vec::as_slice));
+                    }
+                })
+                .with_context(|| format!("dc_sweep() failed at point {point}"))?;
+        }
+        self.sweep = Some(res);
+        Ok(())
+    }
+
+    /// Resolves the target of a sweep: either the name of a circuit parameter or a tuple
+    /// `(instance, param)`
+    unsafe fn sweep_param(&self, obj: *mut PyObject) -> Option<SweepParam> {
+        if PyTuple_Check(obj) != 0 && PyTuple_GET_SIZE(obj) == 2 {
+            let instance = str_arg("dc_sweep", "instance", PyTuple_GET_ITEM(obj, 0))?;
+            let param = str_arg("dc_sweep", "param", PyTuple_GET_ITEM(obj, 1))?;
+            let Some(instance) = self.circ.lookup_instance(instance) else {
+                raise_exception(PyExc_RuntimeError, &format!("unknown instance '{instance}'"));
+                return None;
+            };
+            return Some(SweepParam::Instance(instance, param.to_owned()));
+        }
+
+        let name = str_arg("dc_sweep", "param", obj)?;
+        match self.lookup_param(name) {
+            Some(param) => Some(SweepParam::Circuit(param)),
+            None => {
+                raise_exception(PyExc_RuntimeError, &format!("unknown circuit parameter '{name}'"));
+                None
+            }
+        }
+    }
+
+    fn lookup_param(&self, name: &str) -> Option<CircuitParam> {
+        if name == "temp" {
+            return Some(CircuitParam::TEMPERATURE);
+        }
+        self.circ.lookup_param(name, self.arena).map(|(param, _)| param)
+    }
+}
+
+impl PySimulation {
+    /// Creates a simulation for the circuit `circ`, see `Circuit.prepare_sim`
+    #[allow(clippy::new_ret_no_self)]
+    pub unsafe fn new(circ: *mut PyObject, kwds: *mut PyObject) -> *mut PyObject {
+        const FUN: &str = "prepare_sim";
+        let py_circ = &mut *(circ as *mut PyCircuit);
+        // The circuit and the arena are boxed and are only freed (or modified) once all
+        // simulations that reference `circ` have been deallocated.
+        let circ_ref: &'static Circuit = &*(&*py_circ.circ as *const Circuit);
+        let arena: &'static Arena = &*(&*py_circ.arena as *const Arena);
+
+        let mut params = vec![(CircuitParam::TEMPERATURE, vec![DEFAULT_TEMP].into_boxed_slice())];
+        let mut num_points = 1;
+        if !kwds.is_null() {
+            let mut pos = 0;
+            let mut key = ptr::null_mut();
+            let mut val = ptr::null_mut();
+            while PyDict_Next(kwds, &mut pos, &mut key, &mut val) != 0 {
+                let Some(name) = str_arg(FUN, "**params", key) else {
+                    return ptr::null_mut();
+                };
+                let param = if name == "temp" {
+                    params.clear();
+                    CircuitParam::TEMPERATURE
+                } else if let Some((param, _)) = circ_ref.lookup_param(name, arena) {
+                    param
+                } else {
+                    return raise_type_exception(&format!(
+                        "{FUN}() got an unknown circuit parameter '{name}'"
+                    ));
+                };
+                let Some(values) = float_list_arg(FUN, name, val) else {
+                    return ptr::null_mut();
+                };
+                if unlikely(values.is_empty()) {
+                    return raise_type_exception(&format!(
+                        "{FUN}() received no values for '{name}'"
+                    ));
+                }
+                if values.len() != 1 {
+                    if unlikely(num_points != 1 && num_points != values.len()) {
+                        return raise_type_exception(&format!(
+                            "{FUN}() all arrays must have the same length but '{name}' has length {}\n\thelp: previous arrays have length {num_points}",
+                            values.len()
+                        ));
+                    }
+                    num_points = values.len();
+                }
+                params.push((param, values.into_boxed_slice()));
+            }
+        }
+
+        let defaults = py_circ
+            .param_defaults
+            .iter()
+            .filter(|(param, _)| params.iter().all(|(given, _)| given != param))
+            .copied()
+            .collect();
+
+        let sim = match circ_ref.setup_simulation(SimConfig::default()) {
+            Ok(sim) => sim,
+            Err(err) => return raise_runtime_exception(err),
+        };
+        let mut data = Box::new(SimData {
+            sim,
+            ctx: ExprEvalCtx::new(arena),
+            circ: circ_ref,
+            arena,
+            params: params.into_boxed_slice(),
+            defaults,
+            num_points,
+            guess: None,
+            dc: None,
+            ac: None,
+            sweep: None,
+        });
+        // report missing parameters and invalid values right away
+        if let Err(err) = data.prepare_point(0) {
+            return raise_runtime_exception(err);
+        }
+
+        let ptr = SIMULATION_TY.tp_alloc.unwrap()(addr_of_mut!(SIMULATION_TY), 0);
+        if ptr.is_null() {
+            return ptr::null_mut();
+        }
+        Py_INCREF(circ);
+        py_circ.active_sims += 1;
+        let res = ptr as *mut Self;
+        addr_of_mut!((*res).circ).write(circ);
+        addr_of_mut!((*res).data).write(data);
+        ptr
+    }
+
+    unsafe extern "C" fn dealloc(sel: *mut PyObject) {
+        let res = sel as *mut Self;
+        // the simulation borrows the circuit so it must be dropped first
+        ptr::drop_in_place(addr_of_mut!((*res).data));
+        let circ = (*res).circ;
+        (*(circ as *mut PyCircuit)).active_sims -= 1;
+        Py_DECREF(circ);
+        free_object(sel)
+    }
+
+    unsafe extern "C" fn dc_op(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let self_ = &mut *(self_ as *mut Self);
+        if parse_args("dc_op", args, kwds, [], 0).is_none() {
+            return ptr::null_mut();
+        }
+        match self_.data.dc_op() {
+            Ok(()) => none(),
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+
+    unsafe extern "C" fn ac(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let self_ = &mut *(self_ as *mut Self);
+        let Some([freq]) = parse_args("ac", args, kwds, ["freq"], 1) else {
+            return ptr::null_mut();
+        };
+        let num_points = self_.data.num_points as isize;
+        let (freqs, shape) = if PyFloat_Check(freq) != 0 || PyLong_Check(freq) != 0 {
+            let Some(freq) = float_arg("ac", "freq", freq) else {
+                return ptr::null_mut();
+            };
+            (vec![freq], vec![num_points])
+        } else {
+            let Some(freqs) = float_list_arg("ac", "freq", freq) else {
+                return ptr::null_mut();
+            };
+            let shape = vec![num_points, freqs.len() as isize];
+            (freqs, shape)
+        };
+        match self_.data.ac(&freqs, shape) {
+            Ok(()) => none(),
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+
+    unsafe extern "C" fn dc_sweep(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let self_ = &mut *(self_ as *mut Self);
+        if unlikely(!kwds.is_null() && PyDict_Size(kwds) != 0) {
+            return raise_type_exception("dc_sweep() does not accept keyword arguments");
+        }
+        let len = PyTuple_GET_SIZE(args);
+        if unlikely(len == 0) {
+            return raise_type_exception("dc_sweep() requires at least one sweep");
+        }
+
+        let mut sweeps = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            let sweep = PyTuple_GET_ITEM(args, i);
+            if unlikely(PyTuple_Check(sweep) == 0 || PyTuple_GET_SIZE(sweep) != 2) {
+                return raise_type_exception(
+                    "dc_sweep() expects sweeps of the form (param, values)",
+                );
+            }
+            let Some(param) = self_.data.sweep_param(PyTuple_GET_ITEM(sweep, 0)) else {
+                return ptr::null_mut();
+            };
+            let Some(values) = float_list_arg("dc_sweep", "values", PyTuple_GET_ITEM(sweep, 1))
+            else {
+                return ptr::null_mut();
+            };
+            sweeps.push(DcSweep { param, values });
+        }
+
+        match self_.data.dc_sweep(&sweeps) {
+            Ok(()) => none(),
+            Err(err) => raise_runtime_exception(err),
+        }
+    }
+
+    unsafe extern "C" fn voltage(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let data = &*(*(self_ as *mut Self)).data;
+        node_waveform("voltage", "dc_op", data.circ, data.dc.as_ref(), args, kwds)
+    }
+
+    unsafe extern "C" fn ac_voltage(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let data = &*(*(self_ as *mut Self)).data;
+        node_waveform("ac_voltage", "ac", data.circ, data.ac.as_ref(), args, kwds)
+    }
+
+    unsafe extern "C" fn sweep_voltage(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let data = &*(*(self_ as *mut Self)).data;
+        node_waveform("sweep_voltage", "dc_sweep", data.circ, data.sweep.as_ref(), args, kwds)
+    }
+
+    unsafe extern "C" fn lead_current(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let data = &*(*(self_ as *mut Self)).data;
+        lead_current_waveform("lead_current", "dc_op", data.circ, data.dc.as_ref(), args, kwds)
+    }
+
+    unsafe extern "C" fn ac_lead_current(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let data = &*(*(self_ as *mut Self)).data;
+        lead_current_waveform("ac_lead_current", "ac", data.circ, data.ac.as_ref(), args, kwds)
+    }
+
+    unsafe extern "C" fn sweep_lead_current(
+        self_: *mut PyObject,
+        args: *mut PyObject,
+        kwds: *mut PyObject,
+    ) -> *mut PyObject {
+        let data = &*(*(self_ as *mut Self)).data;
+        lead_current_waveform(
+            "sweep_lead_current",
+            "dc_sweep",
+            data.circ,
+            data.sweep.as_ref(),
+            args,
+            kwds,
+        )
+    }
+}
+
+unsafe fn node_waveform<T: Element>(
+    fun: &str,
+    analysis: &str,
+    circ: &Circuit,
+    res: Option<&Waveforms<T>>,
+    args: *mut PyObject,
+    kwds: *mut PyObject,
+) -> *mut PyObject {
+    let Some([node]) = parse_args(fun, args, kwds, ["node"], 1) else {
+        return ptr::null_mut();
+    };
+    let Some(node) = str_arg(fun, "node", node) else {
+        return ptr::null_mut();
+    };
+    let Some(res) = res else {
+        return raise_exception(
+            PyExc_RuntimeError,
+            &format!("{analysis}() must be run before {fun}()"),
+        );
+    };
+    match circ.lookup_node(node) {
+        Some(node) => new_array(&res.shape, &res.voltages[node]),
+        None => raise_exception(PyExc_RuntimeError, &format!("unknown node '{node}'")),
+    }
+}
+
+unsafe fn lead_current_waveform<T: Element>(
+    fun: &str,
+    analysis: &str,
+    circ: &Circuit,
+    res: Option<&Waveforms<T>>,
+    args: *mut PyObject,
+    kwds: *mut PyObject,
+) -> *mut PyObject {
+    let Some([instance, terminal]) = parse_args(fun, args, kwds, ["instance", "terminal"], 2)
+    else {
+        return ptr::null_mut();
+    };
+    let Some(instance) = str_arg(fun, "instance", instance) else {
+        return ptr::null_mut();
+    };
+    let Some(res) = res else {
+        return raise_exception(
+            PyExc_RuntimeError,
+            &format!("{analysis}() must be run before {fun}()"),
+        );
+    };
+    let Some(inst) = circ.lookup_instance(instance) else {
+        return raise_exception(PyExc_RuntimeError, &format!("unknown instance '{instance}'"));
+    };
+
+    let currents = &res.lead_currents[inst];
+    let terminal = if PyLong_Check(terminal) != 0 {
+        let terminal = PyLong_AsLong(terminal);
+        if !PyErr_Occurred().is_null() {
+            return ptr::null_mut();
+        }
+        terminal_by_index(instance, terminal, currents.len())
+    } else {
+        let Some(terminal) = str_arg(fun, "terminal", terminal) else {
+            return ptr::null_mut();
+        };
+        let terminals = &circ[circ[circ[inst].model].device].terminals;
+        terminal_by_name(instance, terminal, terminals, currents.len())
+    };
+
+    match terminal {
+        Ok(terminal) => new_array(&res.shape, &currents[terminal]),
+        Err(err) => raise_runtime_exception(err),
+    }
+}
+
+fn terminal_by_index(instance: &str, terminal: c_long, connected: usize) -> Result<usize> {
+    match usize::try_from(terminal) {
+        Ok(terminal) if terminal < connected => Ok(terminal),
+        _ => bail!("'{instance}' has no connected terminal {terminal}"),
+    }
+}
+
+fn terminal_by_name(
+    instance: &str,
+    terminal: &str,
+    terminals: &[&str],
+    connected: usize,
+) -> Result<usize> {
+    match terminals.iter().position(|&it| it == terminal) {
+        Some(i) if i < connected => Ok(i),
+        Some(_) => bail!("terminal '{terminal}' of '{instance}' is not connected"),
+        None => bail!("'{instance}' has no terminal '{terminal}'"),
+    }
+}
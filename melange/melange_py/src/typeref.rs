@@ -0,0 +1,93 @@
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::sync::Once;
+
+use pyo3_ffi::{
+    PyCapsule_GetPointer, PyErr_Clear, PyImport_ImportModule, PyMapping_GetItemString, PyObject,
+    PyObject_GenericGetDict, PyObject_GetAttrString, PyTypeObject, PyUnicode_InternFromString,
+    Py_XDECREF, Py_intptr_t,
+};
+
+const NPY_DOUBLE: c_int = 12;
+const NPY_CDOUBLE: c_int = 15;
+
+type PyArrayNewFromDescr = extern "C" fn(
+    subtype: *mut PyTypeObject,
+    descr: *mut PyObject,
+    nd: c_int,
+    dims: *mut Py_intptr_t,
+    strides: *mut Py_intptr_t,
+    data: *mut c_void,
+    flags: c_int,
+    obj: *mut PyObject,
+) -> *mut PyObject;
+
+pub static mut NUMPY_ARR_TYPE: Option<*mut PyTypeObject> = None;
+pub static mut NUMPY_API: Option<PyArrayNewFromDescr> = None;
+pub static mut NUMPY_DOUBLE_DESCR: *mut PyObject = ptr::null_mut();
+pub static mut NUMPY_CDOUBLE_DESCR: *mut PyObject = ptr::null_mut();
+
+pub static mut ARRAY_STRUCT_STR: *mut PyObject = ptr::null_mut();
+
+static INIT: Once = Once::new();
+
+#[cold]
+pub fn init_typerefs() {
+    INIT.call_once(|| unsafe {
+        NUMPY_ARR_TYPE = load_numpy_types();
+
+        ARRAY_STRUCT_STR =
+            PyUnicode_InternFromString("__array_struct__\0".as_ptr() as *const c_char);
+        if let Some(numpy_api) = get_numpy_api() {
+            let api = *(numpy_api.offset(94) as *const PyArrayNewFromDescr);
+            let py_array_descr_from_type =
+                *(numpy_api.offset(45) as *const extern "C" fn(type_: c_int) -> *mut PyObject);
+
+            NUMPY_DOUBLE_DESCR = py_array_descr_from_type(NPY_DOUBLE);
+            assert!(!NUMPY_DOUBLE_DESCR.is_null());
+            NUMPY_CDOUBLE_DESCR = py_array_descr_from_type(NPY_CDOUBLE);
+            assert!(!NUMPY_CDOUBLE_DESCR.is_null());
+
+            NUMPY_API = Some(api);
+        }
+    });
+}
+
+#[cold]
+unsafe fn lookup_module_type(module: *mut PyObject, name: &str) -> *mut PyTypeObject {
+    let mod_dict = PyObject_GenericGetDict(module, std::ptr::null_mut());
+    let ptr = PyMapping_GetItemString(mod_dict, name.as_ptr() as *const c_char);
+    Py_XDECREF(ptr);
+    Py_XDECREF(mod_dict);
+    ptr as *mut PyTypeObject
+}
+
+#[cold]
+unsafe fn load_numpy_types() -> Option<*mut PyTypeObject> {
+    let numpy = PyImport_ImportModule("numpy\0".as_ptr() as *const c_char);
+    if numpy.is_null() {
+        PyErr_Clear();
+        return None;
+    }
+    let array = lookup_module_type(numpy, "ndarray\0");
+    Py_XDECREF(numpy);
+    Some(array)
+}
+
+#[cold]
+fn get_numpy_api() -> Option<*const *const c_void> {
+    unsafe {
+        let numpy = PyImport_ImportModule("numpy.core.multiarray\0".as_ptr() as *const c_char);
+        if numpy.is_null() {
+            PyErr_Clear();
+            return None;
+        }
+        let capsule = PyObject_GetAttrString(numpy as _, "_ARRAY_API\0".as_ptr() as *const c_char);
+        if capsule.is_null() {
+            PyErr_Clear();
+            return None;
+        }
+        Some(PyCapsule_GetPointer(capsule, ptr::null_mut()) as _)
+    }
+}
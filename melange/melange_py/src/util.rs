@@ -0,0 +1,194 @@
+use std::os::raw::c_char;
+use std::{ptr, slice};
+
+use pyo3_ffi::*;
+
+use crate::numpy::{is_array, NumpyArray};
+
+#[inline]
+#[cold]
+fn cold() {}
+
+#[inline]
+pub fn likely(b: bool) -> bool {
+    if !b {
+        cold()
+    }
+    b
+}
+
+#[inline]
+pub fn unlikely(b: bool) -> bool {
+    if b {
+        cold()
+    }
+    b
+}
+
+/// Returns a new reference to `None`
+pub unsafe fn none() -> *mut PyObject {
+    let none = Py_None();
+    Py_INCREF(none);
+    none
+}
+
+pub unsafe fn py_str(val: &str) -> *mut PyObject {
+    PyUnicode_FromStringAndSize(val.as_ptr() as *const c_char, val.len() as isize)
+}
+
+#[cold]
+#[inline(never)]
+pub fn raise_exception(kind: *mut PyObject, msg: &str) -> *mut PyObject {
+    unsafe {
+        let err_msg =
+            PyUnicode_FromStringAndSize(msg.as_ptr() as *const c_char, msg.len() as isize);
+        PyErr_SetObject(kind, err_msg);
+        Py_DECREF(err_msg);
+    };
+    ptr::null_mut()
+}
+
+#[cold]
+#[inline(never)]
+pub fn raise_type_exception(msg: &str) -> *mut PyObject {
+    raise_exception(unsafe { PyExc_TypeError }, msg)
+}
+
+/// Raises errors produced by melange as a `RuntimeError` that contains the whole error chain
+#[cold]
+#[inline(never)]
+pub fn raise_runtime_exception(err: anyhow::Error) -> *mut PyObject {
+    raise_exception(unsafe { PyExc_RuntimeError }, &format!("{err:#}"))
+}
+
+/// Matches the positional arguments in `args` and the keyword arguments in `kwds` to `names`.
+/// The first `required` arguments must be provided, all other arguments are set to null if
+/// they were not provided.
+///
+/// # Returns
+///
+/// The (borrowed) arguments in the order of `names` or `None` if a `TypeError` was raised
+pub unsafe fn parse_args<const N: usize>(
+    fun: &str,
+    args: *mut PyObject,
+    kwds: *mut PyObject,
+    names: [&str; N],
+    required: usize,
+) -> Option<[*mut PyObject; N]> {
+    let mut res = [ptr::null_mut(); N];
+    let num_args = PyTuple_GET_SIZE(args) as usize;
+    if unlikely(num_args > N) {
+        raise_type_exception(&format!("{fun}() takes at most {N} arguments ({num_args} given)"));
+        return None;
+    }
+    for (i, dst) in res.iter_mut().enumerate().take(num_args) {
+        *dst = PyTuple_GET_ITEM(args, i as Py_ssize_t);
+    }
+
+    if !kwds.is_null() {
+        let mut pos = 0;
+        let mut key = ptr::null_mut();
+        let mut val = ptr::null_mut();
+        while PyDict_Next(kwds, &mut pos, &mut key, &mut val) != 0 {
+            let name = str_arg(fun, "**kwargs", key)?;
+            match names.iter().position(|&it| it == name) {
+                Some(i) if res[i].is_null() => res[i] = val,
+                Some(_) => {
+                    raise_type_exception(&format!(
+                        "{fun}() got multiple values for argument '{name}'"
+                    ));
+                    return None;
+                }
+                None => {
+                    raise_type_exception(&format!(
+                        "{fun}() got an unexpected keyword argument '{name}'"
+                    ));
+                    return None;
+                }
+            }
+        }
+    }
+
+    if let Some(missing) = res[..required].iter().position(|arg| arg.is_null()) {
+        raise_type_exception(&format!("{fun}() missing required argument '{}'", names[missing]));
+        return None;
+    }
+
+    Some(res)
+}
+
+/// Reads a `str` argument, the returned string is only valid while `obj` is alive.
+pub unsafe fn str_arg<'a>(fun: &str, name: &str, obj: *mut PyObject) -> Option<&'a str> {
+    if likely(PyUnicode_Check(obj) != 0) {
+        let mut size = 0;
+        let data = PyUnicode_AsUTF8AndSize(obj, &mut size);
+        if likely(!data.is_null()) {
+            let data = slice::from_raw_parts(data as *const u8, size as usize);
+            return Some(std::str::from_utf8_unchecked(data));
+        }
+        PyErr_Clear();
+    }
+    raise_type_exception(&format!("{fun}() argument '{name}' must be str"));
+    None
+}
+
+/// Reads a `float` argument, all objects that can be converted to a float (like int) are
+/// accepted.
+pub unsafe fn float_arg(fun: &str, name: &str, obj: *mut PyObject) -> Option<f64> {
+    let val = PyFloat_AsDouble(obj);
+    if unlikely(val == -1.0 && !PyErr_Occurred().is_null()) {
+        PyErr_Clear();
+        raise_type_exception(&format!("{fun}() argument '{name}' must be float"));
+        return None;
+    }
+    Some(val)
+}
+
+/// Reads a list of `str` arguments (any sequence is accepted)
+pub unsafe fn str_list_arg(fun: &str, name: &str, obj: *mut PyObject) -> Option<Vec<String>> {
+    if unlikely(PyUnicode_Check(obj) != 0 || PySequence_Check(obj) == 0) {
+        raise_type_exception(&format!("{fun}() argument '{name}' must be a list of str"));
+        return None;
+    }
+    sequence_arg(obj, |item| str_arg(fun, name, item).map(str::to_owned))
+}
+
+/// Reads a `float` or a sequence of floats (like a one dimensional numpy array)
+pub unsafe fn float_list_arg(fun: &str, name: &str, obj: *mut PyObject) -> Option<Vec<f64>> {
+    if PyFloat_Check(obj) != 0 || PyLong_Check(obj) != 0 {
+        return float_arg(fun, name, obj).map(|val| vec![val]);
+    }
+    if is_array((*obj).ob_type) {
+        if let Some(arr) = NumpyArray::new(obj) {
+            return Some(arr.to_vec());
+        }
+    }
+    if unlikely(PyUnicode_Check(obj) != 0 || PySequence_Check(obj) == 0) {
+        raise_type_exception(&format!(
+            "{fun}() argument '{name}' must be a float or a sequence of floats"
+        ));
+        return None;
+    }
+    sequence_arg(obj, |item| float_arg(fun, name, item))
+}
+
+unsafe fn sequence_arg<T>(
+    obj: *mut PyObject,
+    mut read_item: impl FnMut(*mut PyObject) -> Option<T>,
+) -> Option<Vec<T>> {
+    let len = PySequence_Size(obj);
+    if unlikely(len < 0) {
+        return None;
+    }
+    let mut res = Vec::with_capacity(len as usize);
+    for i in 0..len {
+        let item = PySequence_GetItem(obj, i);
+        if unlikely(item.is_null()) {
+            return None;
+        }
+        let val = read_item(item);
+        Py_DECREF(item);
+        res.push(val?);
+    }
+    Some(res)
+}
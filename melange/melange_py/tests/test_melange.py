@@ -0,0 +1,99 @@
+import numpy as np
+import pytest
+from melange import Circuit, CircuitInstance
+
+
+def divider():
+    # v1 in 0 dc=vdd; r1 in out 1k; r2 out 0 rload
+    circ = Circuit("divider")
+    circ.def_param("vdd", 1.0)
+    circ.def_param("rload", 1e3)
+    v1 = CircuitInstance(circ, "v1", "vsource", ports=["in", "ground"])
+    v1.set_param("dc", "vdd")
+    r1 = CircuitInstance(circ, "r1", "resistor", ports=["in", "out"])
+    r1.set_param("r", 1e3)
+    r2 = CircuitInstance(circ, "r2", "resistor", ports=["out", "ground"])
+    r2.set_param("r", "rload")
+    return circ
+
+
+def test_construction():
+    circ = divider()
+    assert circ.name == "divider"
+    inst = CircuitInstance(circ, "r3", "resistor", ports=["in", "ground"])
+    assert inst.name == "r3"
+    assert inst.circuit is circ
+    with pytest.raises(RuntimeError):
+        CircuitInstance(circ, "r1", "resistor", ports=["in", "ground"])
+    with pytest.raises(RuntimeError):
+        CircuitInstance(circ, "x1", "unknown_device", ports=["in", "ground"])
+    with pytest.raises(TypeError):
+        circ.prepare_sim(unknown_param=1.0)
+
+
+def test_dc_op():
+    sim = divider().prepare_sim()
+    with pytest.raises(RuntimeError):
+        sim.voltage("out")
+    sim.dc_op()
+    out = sim.voltage("out")
+    assert isinstance(out, np.ndarray)
+    assert out.shape == (1,)
+    np.testing.assert_allclose(out, [0.5])
+    np.testing.assert_allclose(sim.lead_current("v1", "A"), [-0.5e-3])
+    np.testing.assert_allclose(sim.lead_current("v1", 0), [-0.5e-3])
+
+
+def test_dc_op_arrays():
+    circ = divider()
+    vdd = np.array([0.5, 1.0, 2.0])
+    rload = [1e3, 3e3, 1e3]
+    for sim in [
+        circ.prepare_sim(vdd=vdd, rload=rload),
+        # integer arrays are not float64 arrays and are read as a sequence instead
+        circ.prepare_sim(vdd=vdd, rload=np.array([1000, 3000, 1000])),
+    ]:
+        sim.dc_op()
+        out = sim.voltage("out")
+        assert out.shape == (3,)
+        np.testing.assert_allclose(out, vdd * np.array(rload) / (1e3 + np.array(rload)))
+    with pytest.raises(TypeError):
+        circ.prepare_sim(vdd=vdd, rload=[1e3, 3e3])
+
+
+def test_ac():
+    # RC lowpass with a corner frequency of 1 krad/s
+    circ = Circuit("rc")
+    v1 = CircuitInstance(circ, "v1", "vsource", ports=["in", "ground"])
+    v1.set_param("mag", 1.0)
+    r1 = CircuitInstance(circ, "r1", "resistor", ports=["in", "out"])
+    r1.set_param("r", 1e3)
+    c1 = CircuitInstance(circ, "c1", "capacitor", ports=["out", "ground"])
+    c1.set_param("c", 1e-6)
+    sim = circ.prepare_sim()
+
+    corner = 1e3 / (2 * np.pi)
+    sim.ac(corner)
+    out = sim.ac_voltage("out")
+    assert np.iscomplexobj(out)
+    assert out.shape == (1,)
+    np.testing.assert_allclose(out, [0.5 - 0.5j])
+
+    sim.ac(np.array([corner, 2 * corner]))
+    out = sim.ac_voltage("out")
+    assert out.shape == (1, 2)
+    np.testing.assert_allclose(out[0], [0.5 - 0.5j, 0.2 - 0.4j])
+    np.testing.assert_allclose(sim.ac_lead_current("c1", "A")[0], [0.5e-3 + 0.5e-3j, 0.8e-3 + 0.4e-3j])
+
+
+def test_dc_sweep():
+    sim = divider().prepare_sim(vdd=[1.0, 2.0])
+    sim.dc_sweep((("v1", "dc"), [0.0, 1.0, 2.0]), ("rload", [1e3, 3e3]))
+    out = sim.sweep_voltage("out")
+    assert out.shape == (2, 6)
+    # the first sweep is the innermost sweep, the swept instance parameter overrides vdd
+    vdc = np.tile([0.0, 1.0, 2.0], 2)
+    rload = np.repeat([1e3, 3e3], 3)
+    for point in range(2):
+        np.testing.assert_allclose(out[point], vdc * rload / (1e3 + rload))
+        np.testing.assert_allclose(sim.sweep_lead_current("v1", "A")[point], -vdc / (1e3 + rload))
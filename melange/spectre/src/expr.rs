@@ -0,0 +1,290 @@
+//! Parsing of spectre expressions into the [`melange_core::Arena`].
+
+use anyhow::Result;
+use melange_core::{Arena, Expr};
+
+use crate::lexer::{Token, TokenKind};
+use crate::parser::{Cursor, Parser};
+
+type UnaryBuilder = fn(&mut Arena, Expr) -> Result<Expr>;
+type BinaryBuilder = fn(&mut Arena, Expr, Expr) -> Result<Expr>;
+
+const UNARY_FUNCTIONS: [(&str, UnaryBuilder); 20] = [
+    ("exp", Expr::exp),
+    ("log", Expr::log),
+    ("ln", Expr::log),
+    ("log10", Expr::log10),
+    ("sqrt", Expr::sqrt),
+    ("abs", Expr::abs),
+    ("sin", Expr::sin),
+    ("cos", Expr::cos),
+    ("tan", Expr::tan),
+    ("atan", Expr::atam),
+    ("asin", Expr::asin),
+    ("acos", Expr::acos),
+    ("sinh", Expr::sinh),
+    ("cosh", Expr::cosh),
+    ("tanh", Expr::tanh),
+    ("atanh", Expr::atanh),
+    ("asinh", Expr::asinh),
+    ("ceil", Expr::ceil),
+    ("floor", Expr::floor),
+    ("int", Expr::int),
+];
+
+const BINARY_FUNCTIONS: [(&str, BinaryBuilder); 7] = [
+    ("pow", Expr::pow),
+    ("fmod", Expr::fmod),
+    ("atan2", Expr::atan2),
+    ("hypot", Expr::hypot),
+    ("min", Expr::min),
+    ("max", Expr::max),
+    ("mod", Expr::modulo),
+];
+
+/// Mathematical and physical constants predefined by spectre
+const CONSTANTS: [(&str, f64); 9] = [
+    ("M_PI", std::f64::consts::PI),
+    ("M_TWO_PI", std::f64::consts::TAU),
+    ("M_PI_2", std::f64::consts::FRAC_PI_2),
+    ("M_E", std::f64::consts::E),
+    ("M_SQRT2", std::f64::consts::SQRT_2),
+    ("P_Q", 1.602176634e-19),
+    ("P_K", 1.380649e-23),
+    ("P_H", 6.62607015e-34),
+    ("P_C", 2.99792458e8),
+];
+
+#[derive(Clone, Copy)]
+enum BinaryOp {
+    OrOr,
+    AndAnd,
+    Or,
+    Xor,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// Returns the precedence and operator of binary operator tokens
+fn binary_op(kind: &TokenKind) -> Option<(u8, BinaryOp)> {
+    let res = match kind {
+        TokenKind::OrOr => (0, BinaryOp::OrOr),
+        TokenKind::AndAnd => (1, BinaryOp::AndAnd),
+        TokenKind::Pipe => (2, BinaryOp::Or),
+        TokenKind::Caret => (3, BinaryOp::Xor),
+        TokenKind::Amp => (4, BinaryOp::And),
+        TokenKind::Eq => (5, BinaryOp::Eq),
+        TokenKind::Ne => (5, BinaryOp::Ne),
+        TokenKind::Lt => (6, BinaryOp::Lt),
+        TokenKind::Le => (6, BinaryOp::Le),
+        TokenKind::Gt => (6, BinaryOp::Gt),
+        TokenKind::Ge => (6, BinaryOp::Ge),
+        TokenKind::Shl => (7, BinaryOp::Shl),
+        TokenKind::Shr => (7, BinaryOp::Shr),
+        TokenKind::Plus => (8, BinaryOp::Add),
+        TokenKind::Minus => (8, BinaryOp::Sub),
+        TokenKind::Star => (9, BinaryOp::Mul),
+        TokenKind::Slash => (9, BinaryOp::Div),
+        TokenKind::Percent => (9, BinaryOp::Mod),
+        _ => return None,
+    };
+    Some(res)
+}
+
+impl Parser<'_> {
+    /// Parses an expression. The expression ends at the first token that can not continue it.
+    pub(crate) fn expr(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        let cond = self.binary_expr(cursor, 0)?;
+        if !cursor.eat(&TokenKind::Question) {
+            return Ok(cond);
+        }
+        let pos = cursor.pos();
+        let then_val = self.expr(cursor)?;
+        cursor.expect(&TokenKind::Colon, "':'")?;
+        let else_val = self.expr(cursor)?;
+        Expr::cond(self.arena, cond, then_val, else_val).map_err(|err| cursor.error_at(pos, err))
+    }
+
+    fn binary_expr(&mut self, cursor: &mut Cursor, min_prec: u8) -> Result<Expr> {
+        let mut lhs = self.unary_expr(cursor)?;
+        while let Some((prec, op)) = cursor.peek_kind().and_then(binary_op) {
+            if prec < min_prec {
+                break;
+            }
+            let pos = cursor.pos();
+            cursor.bump();
+            let rhs = self.binary_expr(cursor, prec + 1)?;
+            lhs = self.binary(op, lhs, rhs).map_err(|err| cursor.error_at(pos, err))?;
+        }
+        Ok(lhs)
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: Expr, rhs: Expr) -> Result<Expr> {
+        let arena = &mut *self.arena;
+        match op {
+            BinaryOp::OrOr => Expr::logic_or(arena, lhs, rhs),
+            BinaryOp::AndAnd => Expr::logic_and(arena, lhs, rhs),
+            BinaryOp::Or => Expr::or(arena, lhs, rhs),
+            BinaryOp::Xor => Expr::xolr(arena, lhs, rhs),
+            BinaryOp::And => Expr::and(arena, lhs, rhs),
+            BinaryOp::Eq => Ok(Expr::eq(arena, lhs, rhs)),
+            BinaryOp::Ne => Ok(Expr::neq(arena, lhs, rhs)),
+            BinaryOp::Lt => Expr::lt(arena, lhs, rhs),
+            BinaryOp::Le => Expr::le(arena, lhs, rhs),
+            BinaryOp::Gt => Expr::lt(arena, rhs, lhs),
+            BinaryOp::Ge => Expr::le(arena, rhs, lhs),
+            BinaryOp::Shl => Expr::shl(arena, lhs, rhs),
+            BinaryOp::Shr => Expr::shr(arena, lhs, rhs),
+            BinaryOp::Add => Expr::add(arena, lhs, rhs),
+            BinaryOp::Sub => {
+                let rhs = Expr::neg(arena, rhs)?;
+                Expr::add(arena, lhs, rhs)
+            }
+            BinaryOp::Mul => Expr::mul(arena, lhs, rhs),
+            BinaryOp::Div => {
+                let rhs = Expr::inv(arena, rhs)?;
+                Expr::mul(arena, lhs, rhs)
+            }
+            BinaryOp::Mod => Expr::modulo(arena, lhs, rhs),
+        }
+    }
+
+    fn unary_expr(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        let pos = cursor.pos();
+        match cursor.peek_kind() {
+            Some(TokenKind::Minus) => {
+                cursor.bump();
+                let arg = self.unary_expr(cursor)?;
+                Expr::neg(self.arena, arg).map_err(|err| cursor.error_at(pos, err))
+            }
+            Some(TokenKind::Plus) => {
+                cursor.bump();
+                self.unary_expr(cursor)
+            }
+            Some(TokenKind::Not) => {
+                cursor.bump();
+                let arg = self.unary_expr(cursor)?;
+                Ok(Expr::eq(self.arena, arg, 0.0.into()))
+            }
+            Some(TokenKind::Tilde) => Err(cursor.error("bitwise negation is not supported")),
+            _ => self.pow_expr(cursor),
+        }
+    }
+
+    /// `**` is right associative and binds stronger than unary operators
+    fn pow_expr(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        let base = self.primary_expr(cursor)?;
+        let pos = cursor.pos();
+        if !cursor.eat(&TokenKind::Pow) {
+            return Ok(base);
+        }
+        let exp = self.unary_expr(cursor)?;
+        Expr::pow(self.arena, base, exp).map_err(|err| cursor.error_at(pos, err))
+    }
+
+    fn primary_expr(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        let Some(tok) = cursor.peek() else {
+            return Err(cursor.error("expected an expression but found end of line"));
+        };
+        match &tok.kind {
+            TokenKind::Number(val) => {
+                cursor.bump();
+                Ok((*val).into())
+            }
+            TokenKind::Str(val) => {
+                cursor.bump();
+                Ok(Expr::str(self.arena, val))
+            }
+            TokenKind::LParen => {
+                cursor.bump();
+                let res = self.expr(cursor)?;
+                cursor.expect(&TokenKind::RParen, "')'")?;
+                Ok(res)
+            }
+            TokenKind::Ident if cursor.nth_kind(1) == Some(&TokenKind::LParen) => {
+                self.call_expr(cursor)
+            }
+            TokenKind::Ident => {
+                cursor.bump();
+                self.ident(cursor, tok)
+            }
+            _ => Err(cursor.error(format!("expected an expression but found '{}'", tok.text))),
+        }
+    }
+
+    fn ident(&mut self, cursor: &Cursor, tok: &Token) -> Result<Expr> {
+        let name = tok.text;
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(cursor.error_at(tok.pos, format!("invalid number '{name}'")));
+        }
+        self.lookup_ident(name)
+            .ok_or_else(|| cursor.error_at(tok.pos, format!("unknown parameter '{name}'")))
+    }
+
+    /// Resolves a parameter (or a builtin constant) by name.
+    pub(crate) fn lookup_ident(&self, name: &str) -> Option<Expr> {
+        for ctx in self.param_scopes() {
+            if let Some((_, read_expr)) = self.arena.lookup_param_by_name(ctx, name) {
+                return Some(read_expr);
+            }
+        }
+        let (_, val) = CONSTANTS.iter().find(|(constant, _)| *constant == name)?;
+        Some((*val).into())
+    }
+
+    fn call_expr(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        let tok = cursor.bump().unwrap();
+        let name = tok.text;
+        cursor.bump();
+        let mut args = Vec::new();
+        if !cursor.eat(&TokenKind::RParen) {
+            loop {
+                args.push(self.expr(cursor)?);
+                if cursor.eat(&TokenKind::RParen) {
+                    break;
+                }
+                cursor.expect(&TokenKind::Comma, "',' or ')'")?;
+            }
+        }
+
+        let res = if let Some((_, builder)) = UNARY_FUNCTIONS.iter().find(|(func, _)| *func == name)
+        {
+            let &[arg] = &*args else {
+                return Err(cursor.error_at(
+                    tok.pos,
+                    format!(
+                        "function '{name}' expects 1 argument but {} were provided",
+                        args.len()
+                    ),
+                ));
+            };
+            builder(self.arena, arg)
+        } else if let Some((_, builder)) = BINARY_FUNCTIONS.iter().find(|(func, _)| *func == name) {
+            let &[lhs, rhs] = &*args else {
+                return Err(cursor.error_at(
+                    tok.pos,
+                    format!(
+                        "function '{name}' expects 2 arguments but {} were provided",
+                        args.len()
+                    ),
+                ));
+            };
+            builder(self.arena, lhs, rhs)
+        } else {
+            return Err(cursor.error_at(tok.pos, format!("unknown function '{name}'")));
+        };
+        res.map_err(|err| cursor.error_at(tok.pos, err))
+    }
+}
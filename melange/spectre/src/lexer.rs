@@ -0,0 +1,312 @@
+//! Splits a Spectre netlist into tokens.
+//!
+//! Spectre netlists are line oriented: the end of a line terminates a statement unless it is
+//! escaped with a backslash. Newlines are therefore emitted as [`TokenKind::Newline`] tokens.
+//! Comments (`// ...` and lines starting with `*`) are removed by the lexer.
+
+use std::fmt;
+
+/// A position within a source file (1-based)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Pos {
+    pub line: u32,
+    pub col: u32,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TokenKind {
+    Ident,
+    Number(f64),
+    Str(String),
+    Newline,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Assign,
+    Question,
+    Colon,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Pow,
+    Caret,
+    Amp,
+    Pipe,
+    Tilde,
+    Not,
+    AndAnd,
+    OrOr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The source text of this token
+    pub text: &'a str,
+    pub pos: Pos,
+}
+
+/// Scale factor suffixes supported by spectre (these are case-sensitive)
+fn scale_factor(suffix: &str) -> Option<f64> {
+    let val = match suffix {
+        "T" => 1e12,
+        "G" => 1e9,
+        "M" => 1e6,
+        "K" | "k" => 1e3,
+        "_" => 1.0,
+        "%" | "c" => 1e-2,
+        "m" => 1e-3,
+        "u" => 1e-6,
+        "n" => 1e-9,
+        "p" => 1e-12,
+        "f" => 1e-15,
+        "a" => 1e-18,
+        _ => return None,
+    };
+    Some(val)
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, b'_' | b'$' | b'#')
+}
+
+fn is_ident_continue(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'!' | b'$' | b'#' | b'.')
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    line: u32,
+    line_start: usize,
+    /// whether only whitespace was encountered since the last newline
+    at_line_start: bool,
+    tokens: Vec<Token<'a>>,
+}
+
+/// Tokenizes `src`. Returns the position and a description if an invalid character or an
+/// unterminated string is encountered.
+pub fn tokenize(src: &str) -> Result<Vec<Token<'_>>, (Pos, String)> {
+    let mut lexer =
+        Lexer { src, pos: 0, line: 1, line_start: 0, at_line_start: true, tokens: Vec::new() };
+    lexer.run()?;
+    Ok(lexer.tokens)
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self, off: usize) -> u8 {
+        self.src.as_bytes().get(self.pos + off).copied().unwrap_or(0)
+    }
+
+    fn cur_pos(&self) -> Pos {
+        Pos { line: self.line, col: (self.pos - self.line_start) as u32 + 1 }
+    }
+
+    fn newline(&mut self) {
+        self.pos += 1;
+        self.line += 1;
+        self.line_start = self.pos;
+        self.at_line_start = true;
+    }
+
+    fn skip_line(&mut self) {
+        while !matches!(self.peek(0), b'\n' | 0) {
+            self.pos += 1;
+        }
+    }
+
+    fn run(&mut self) -> Result<(), (Pos, String)> {
+        while self.pos < self.src.len() {
+            let c = self.peek(0);
+            match c {
+                b'\n' => {
+                    let pos = self.cur_pos();
+                    self.push(TokenKind::Newline, self.pos, 1, pos);
+                    self.newline();
+                }
+                b' ' | b'\t' | b'\r' => self.pos += 1,
+                // line continuation
+                b'\\' if matches!(self.peek(1), b'\n') => {
+                    self.pos += 1;
+                    self.newline();
+                    self.at_line_start = false;
+                }
+                b'\\' if matches!(self.peek(1), b'\r') && matches!(self.peek(2), b'\n') => {
+                    self.pos += 2;
+                    self.newline();
+                    self.at_line_start = false;
+                }
+                b'*' if self.at_line_start => self.skip_line(),
+                b'/' if self.peek(1) == b'/' => self.skip_line(),
+                _ => {
+                    self.at_line_start = false;
+                    self.token()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, len: usize, pos: Pos) {
+        self.tokens.push(Token { kind, text: &self.src[start..start + len], pos });
+    }
+
+    fn token(&mut self) -> Result<(), (Pos, String)> {
+        let pos = self.cur_pos();
+        let start = self.pos;
+        let c = self.peek(0);
+
+        if c.is_ascii_digit() || (c == b'.' && self.peek(1).is_ascii_digit()) {
+            self.number();
+            return Ok(());
+        }
+
+        if is_ident_start(c) {
+            while is_ident_continue(self.peek(0)) {
+                self.pos += 1;
+            }
+            self.push(TokenKind::Ident, start, self.pos - start, pos);
+            return Ok(());
+        }
+
+        if c == b'"' {
+            return self.string();
+        }
+
+        let (kind, len) = match (c, self.peek(1)) {
+            (b'*', b'*') => (TokenKind::Pow, 2),
+            (b'&', b'&') => (TokenKind::AndAnd, 2),
+            (b'|', b'|') => (TokenKind::OrOr, 2),
+            (b'=', b'=') => (TokenKind::Eq, 2),
+            (b'!', b'=') => (TokenKind::Ne, 2),
+            (b'<', b'=') => (TokenKind::Le, 2),
+            (b'>', b'=') => (TokenKind::Ge, 2),
+            (b'<', b'<') => (TokenKind::Shl, 2),
+            (b'>', b'>') => (TokenKind::Shr, 2),
+            (b'(', _) => (TokenKind::LParen, 1),
+            (b')', _) => (TokenKind::RParen, 1),
+            (b'[', _) => (TokenKind::LBracket, 1),
+            (b']', _) => (TokenKind::RBracket, 1),
+            (b'{', _) => (TokenKind::LBrace, 1),
+            (b'}', _) => (TokenKind::RBrace, 1),
+            (b',', _) => (TokenKind::Comma, 1),
+            (b'=', _) => (TokenKind::Assign, 1),
+            (b'?', _) => (TokenKind::Question, 1),
+            (b':', _) => (TokenKind::Colon, 1),
+            (b'+', _) => (TokenKind::Plus, 1),
+            (b'-', _) => (TokenKind::Minus, 1),
+            (b'*', _) => (TokenKind::Star, 1),
+            (b'/', _) => (TokenKind::Slash, 1),
+            (b'%', _) => (TokenKind::Percent, 1),
+            (b'^', _) => (TokenKind::Caret, 1),
+            (b'&', _) => (TokenKind::Amp, 1),
+            (b'|', _) => (TokenKind::Pipe, 1),
+            (b'~', _) => (TokenKind::Tilde, 1),
+            (b'!', _) => (TokenKind::Not, 1),
+            (b'<', _) => (TokenKind::Lt, 1),
+            (b'>', _) => (TokenKind::Gt, 1),
+            _ => {
+                let c = self.src[start..].chars().next().unwrap();
+                return Err((pos, format!("unexpected character '{c}'")));
+            }
+        };
+        self.pos += len;
+        self.push(kind, start, len, pos);
+        Ok(())
+    }
+
+    fn eat_digits(&mut self) {
+        while self.peek(0).is_ascii_digit() {
+            self.pos += 1;
+        }
+    }
+
+    /// Lexes a number with an optional scale factor suffix. If the suffix is not a valid scale
+    /// factor the token is lexed as an identifier instead (names like `1n1` are valid node names).
+    fn number(&mut self) {
+        let pos = self.cur_pos();
+        let start = self.pos;
+        self.eat_digits();
+        if self.peek(0) == b'.' {
+            self.pos += 1;
+            self.eat_digits();
+        }
+        if matches!(self.peek(0), b'e' | b'E') {
+            let off = if matches!(self.peek(1), b'+' | b'-') { 2 } else { 1 };
+            if self.peek(off).is_ascii_digit() {
+                self.pos += off;
+                self.eat_digits();
+            }
+        }
+        let num_end = self.pos;
+        // the scale factor
+        if self.peek(0) == b'%' && !is_ident_continue(self.peek(1)) {
+            self.pos += 1;
+        } else {
+            while is_ident_continue(self.peek(0)) {
+                self.pos += 1;
+            }
+        }
+
+        let suffix = &self.src[num_end..self.pos];
+        let val: f64 = self.src[start..num_end].parse().unwrap();
+        let kind = if suffix.is_empty() {
+            TokenKind::Number(val)
+        } else if let Some(scale) = scale_factor(suffix) {
+            TokenKind::Number(val * scale)
+        } else {
+            TokenKind::Ident
+        };
+        self.push(kind, start, self.pos - start, pos);
+    }
+
+    fn string(&mut self) -> Result<(), (Pos, String)> {
+        let pos = self.cur_pos();
+        let start = self.pos;
+        self.pos += 1;
+        let mut val = String::new();
+        loop {
+            match self.peek(0) {
+                b'"' => break,
+                b'\n' | 0 => return Err((pos, "unterminated string literal".to_owned())),
+                b'\\' if self.peek(1) != 0 => {
+                    let escaped = match self.peek(1) {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        c => c as char,
+                    };
+                    val.push(escaped);
+                    self.pos += 2;
+                }
+                _ => {
+                    let c = self.src[self.pos..].chars().next().unwrap();
+                    val.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        self.pos += 1;
+        self.push(TokenKind::Str(val), start, self.pos - start, pos);
+        Ok(())
+    }
+}
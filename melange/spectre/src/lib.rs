@@ -0,0 +1,45 @@
+//! A parser for Spectre netlists that produces a [`CircuitDescription`].
+//!
+//! The following statements are supported:
+//!
+//! * instances (with and without parenthesis around the terminal connections)
+//! * `model` statements
+//! * `parameters` statements
+//! * subcircuit definitions (`subckt`/`inline subckt` ... `ends`)
+//! * `include` statements (optionally only including one `section` of a library)
+//! * `ahdl_include` statements which add a Verilog-A file to [`CircuitDescription::va_files`]
+//!
+//! Parameter values are parsed into expressions that are allocated in an [`Arena`]. Analyses
+//! and other simulator control statements are skipped (with a warning) as melange exposes these
+//! through its API instead. Errors contain the file, line and column they occurred at.
+
+use anyhow::Result;
+use camino::Utf8Path;
+use melange_core::{Arena, CircuitDescription};
+
+use crate::parser::Parser;
+
+mod expr;
+mod lexer;
+mod parser;
+#[cfg(test)]
+mod tests;
+
+/// Parses the spectre netlist at `path`. All expressions are allocated in `arena`.
+pub fn parse_file(path: &Utf8Path, arena: &mut Arena) -> Result<CircuitDescription> {
+    let mut parser = Parser::new(arena);
+    parser.parse_file(path, None)?;
+    parser.finish(circuit_name(path))
+}
+
+/// Parses the spectre netlist `src`. `path` is used in error messages and to resolve
+/// included files.
+pub fn parse_str(src: &str, path: &Utf8Path, arena: &mut Arena) -> Result<CircuitDescription> {
+    let mut parser = Parser::new(arena);
+    parser.parse_src(src, path, None)?;
+    parser.finish(circuit_name(path))
+}
+
+fn circuit_name(path: &Utf8Path) -> String {
+    path.file_stem().unwrap_or(path.as_str()).to_owned()
+}
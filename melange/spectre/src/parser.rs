@@ -0,0 +1,607 @@
+use std::fs::read_to_string;
+
+use anyhow::{anyhow, bail, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use melange_core::elaboration::{
+    CircuitInstanceDescription, CircuitModelDescription, SubcircuitDescription,
+};
+use melange_core::{Arena, CircuitDescription, CircuitParam, CircuitParamCtx, Expr};
+
+use crate::lexer::{tokenize, Pos, Token, TokenKind};
+
+/// Statements (and instance masters) for analyses and simulator control. Melange exposes these
+/// through its API instead of the netlist so they are skipped.
+const CONTROL_STATEMENTS: [&str; 6] = ["save", "ic", "nodeset", "statistics", "export", "real"];
+const ANALYSES: [&str; 31] = [
+    "dc",
+    "ac",
+    "tran",
+    "noise",
+    "xf",
+    "sp",
+    "pz",
+    "stb",
+    "sens",
+    "fourier",
+    "pss",
+    "pac",
+    "pnoise",
+    "pxf",
+    "psp",
+    "pstb",
+    "qpss",
+    "hb",
+    "hbac",
+    "hbnoise",
+    "envlp",
+    "montecarlo",
+    "sweep",
+    "options",
+    "set",
+    "info",
+    "shell",
+    "alter",
+    "altergroup",
+    "check",
+    "checklimit",
+];
+
+/// The parameters, models and instances defined at the top level or inside a subcircuit.
+struct Scope {
+    ctx: CircuitParamCtx,
+    parameters: Vec<(CircuitParam, Expr)>,
+    instances: Vec<CircuitInstanceDescription>,
+    models: Vec<CircuitModelDescription>,
+}
+
+impl Scope {
+    fn new(arena: &mut Arena) -> Scope {
+        Scope {
+            ctx: arena.add_ctx(),
+            parameters: Vec::new(),
+            instances: Vec::new(),
+            models: Vec::new(),
+        }
+    }
+}
+
+struct Subcircuit {
+    name: String,
+    ports: Vec<String>,
+    scope: Scope,
+}
+
+/// A single statement (logical line) of a netlist.
+pub(crate) struct Cursor<'t, 's> {
+    tokens: &'t [Token<'s>],
+    /// position after the last token, used to report unexpected end of statements
+    end: Pos,
+    file: &'t Utf8Path,
+}
+
+impl<'t, 's> Cursor<'t, 's> {
+    pub(crate) fn peek(&self) -> Option<&'t Token<'s>> {
+        self.tokens.first()
+    }
+
+    pub(crate) fn peek_kind(&self) -> Option<&'t TokenKind> {
+        self.peek().map(|tok| &tok.kind)
+    }
+
+    pub(crate) fn nth_kind(&self, n: usize) -> Option<&'t TokenKind> {
+        self.tokens.get(n).map(|tok| &tok.kind)
+    }
+
+    pub(crate) fn bump(&mut self) -> Option<&'t Token<'s>> {
+        let (first, rem) = self.tokens.split_first()?;
+        self.tokens = rem;
+        Some(first)
+    }
+
+    pub(crate) fn at(&self, kind: &TokenKind) -> bool {
+        self.peek_kind() == Some(kind)
+    }
+
+    pub(crate) fn eat(&mut self, kind: &TokenKind) -> bool {
+        let res = self.at(kind);
+        if res {
+            self.bump();
+        }
+        res
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// The position of the next token (or the end of the statement).
+    pub(crate) fn pos(&self) -> Pos {
+        self.peek().map_or(self.end, |tok| tok.pos)
+    }
+
+    pub(crate) fn error_at(&self, pos: Pos, msg: impl std::fmt::Display) -> anyhow::Error {
+        anyhow!("{}:{}: {}", self.file, pos, msg)
+    }
+
+    pub(crate) fn error(&self, msg: impl std::fmt::Display) -> anyhow::Error {
+        self.error_at(self.pos(), msg)
+    }
+
+    pub(crate) fn expect(&mut self, kind: &TokenKind, what: &str) -> Result<&'t Token<'s>> {
+        match self.peek() {
+            Some(tok) if &tok.kind == kind => {
+                self.bump();
+                Ok(tok)
+            }
+            Some(tok) => Err(self.error(format!("expected {what} but found '{}'", tok.text))),
+            None => Err(self.error(format!("expected {what} but found end of line"))),
+        }
+    }
+
+    pub(crate) fn expect_ident(&mut self, what: &str) -> Result<&'s str> {
+        self.expect(&TokenKind::Ident, what).map(|tok| tok.text)
+    }
+
+    fn expect_str(&mut self, what: &str) -> Result<String> {
+        match self.peek() {
+            Some(Token { kind: TokenKind::Str(val), .. }) => {
+                self.bump();
+                Ok(val.clone())
+            }
+            Some(tok) => Err(self.error(format!("expected {what} but found '{}'", tok.text))),
+            None => Err(self.error(format!("expected {what} but found end of line"))),
+        }
+    }
+
+    fn expect_end(&self) -> Result<()> {
+        match self.peek() {
+            Some(tok) => Err(self.error(format!("expected end of line but found '{}'", tok.text))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the next tokens are `<name> =`
+    fn at_assignment(&self) -> bool {
+        self.nth_kind(0) == Some(&TokenKind::Ident) && self.nth_kind(1) == Some(&TokenKind::Assign)
+    }
+}
+
+pub(crate) struct Parser<'a> {
+    pub(crate) arena: &'a mut Arena,
+    top: Scope,
+    subckt: Option<Subcircuit>,
+    subcircuits: Vec<SubcircuitDescription>,
+    global_nodes: Vec<String>,
+    va_files: Vec<Utf8PathBuf>,
+    /// the files currently being parsed, used to detect recursive includes
+    include_stack: Vec<Utf8PathBuf>,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(arena: &'a mut Arena) -> Parser<'a> {
+        let top = Scope::new(arena);
+        Parser {
+            arena,
+            top,
+            subckt: None,
+            subcircuits: Vec::new(),
+            global_nodes: Vec::new(),
+            va_files: Vec::new(),
+            include_stack: Vec::new(),
+        }
+    }
+
+    pub(crate) fn finish(self, name: String) -> Result<CircuitDescription> {
+        if let Some(subckt) = self.subckt {
+            bail!("subcircuit '{}' is missing 'ends'", subckt.name)
+        }
+        Ok(CircuitDescription {
+            name,
+            instances: self.top.instances.into(),
+            models: self.top.models.into(),
+            subcircuits: self.subcircuits,
+            global_nodes: self.global_nodes,
+            va_files: self.va_files,
+            ctx: self.top.ctx,
+            parameters: self.top.parameters,
+        })
+    }
+
+    /// The parameter contexts that are searched (in order) when a parameter is referenced.
+    pub(crate) fn param_scopes(&self) -> impl Iterator<Item = CircuitParamCtx> {
+        let subckt = self.subckt.as_ref().map(|subckt| subckt.scope.ctx);
+        subckt.into_iter().chain([self.top.ctx, CircuitParamCtx::ROOT])
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        match &mut self.subckt {
+            Some(subckt) => &mut subckt.scope,
+            None => &mut self.top,
+        }
+    }
+
+    pub(crate) fn parse_file(&mut self, path: &Utf8Path, section: Option<&str>) -> Result<()> {
+        let src = read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+        self.parse_src(&src, path, section)
+    }
+
+    pub(crate) fn parse_src(
+        &mut self,
+        src: &str,
+        path: &Utf8Path,
+        section: Option<&str>,
+    ) -> Result<()> {
+        if self.include_stack.iter().any(|file| file == path) {
+            bail!("{path} includes itself recursively")
+        }
+        self.include_stack.push(path.to_owned());
+
+        let tokens = tokenize(src).map_err(|(pos, msg)| anyhow!("{path}:{pos}: {msg}"))?;
+
+        // the state for library sections
+        let mut in_section: Option<&str> = None;
+        let mut skip_section = false;
+        let mut found_section = false;
+        // nesting depth of a skipped `{ ... }` block
+        let mut brace_depth = 0usize;
+
+        for line in tokens.split(|tok| tok.kind == TokenKind::Newline) {
+            let Some(last) = line.last() else { continue };
+            let end = Pos { line: last.pos.line, col: last.pos.col + last.text.len() as u32 };
+            let mut cursor = Cursor { tokens: line, end, file: path };
+
+            if brace_depth != 0 {
+                brace_depth = (brace_depth + count_braces(line, TokenKind::LBrace))
+                    .saturating_sub(count_braces(line, TokenKind::RBrace));
+                continue;
+            }
+
+            let keyword = match cursor.peek() {
+                Some(Token { kind: TokenKind::Ident, text, .. }) => *text,
+                Some(_) => "",
+                None => continue,
+            };
+
+            match keyword {
+                "library" => continue,
+                "endlibrary" => continue,
+                "section" => {
+                    cursor.bump();
+                    let name = cursor.expect_ident("a section name")?;
+                    if let Some(outer) = in_section {
+                        return Err(cursor.error(format!(
+                            "section '{name}' can not be nested inside section '{outer}'"
+                        )));
+                    }
+                    in_section = Some(name);
+                    skip_section = section != Some(name);
+                    found_section |= !skip_section;
+                    continue;
+                }
+                "endsection" => {
+                    if in_section.is_none() {
+                        return Err(cursor.error("'endsection' without matching 'section'"));
+                    }
+                    in_section = None;
+                    skip_section = false;
+                    continue;
+                }
+                _ if skip_section => continue,
+                _ => (),
+            }
+
+            if self.statement(&mut cursor)? {
+                brace_depth = count_braces(line, TokenKind::LBrace)
+                    .saturating_sub(count_braces(line, TokenKind::RBrace));
+            }
+        }
+
+        if let Some(name) = in_section {
+            bail!("{path}: section '{name}' is missing 'endsection'")
+        }
+        if let (Some(section), false) = (section, found_section) {
+            bail!("{path}: section '{section}' not found")
+        }
+
+        self.include_stack.pop();
+        Ok(())
+    }
+
+    /// Parses a single statement. Returns `true` if the statement was skipped because
+    /// it is not relevant for the circuit description
+    fn statement(&mut self, cursor: &mut Cursor) -> Result<bool> {
+        let first = cursor.peek().unwrap();
+        if first.kind != TokenKind::Ident {
+            return Err(cursor.error(format!("expected a statement but found '{}'", first.text)));
+        }
+
+        match first.text {
+            "simulator" => {
+                cursor.bump();
+                self.simulator(cursor)?;
+            }
+            "parameters" => {
+                cursor.bump();
+                self.parameters(cursor)?;
+            }
+            "model" => {
+                cursor.bump();
+                self.model(cursor)?;
+            }
+            "inline" if cursor.nth_kind(1) == Some(&TokenKind::Ident) => {
+                cursor.bump();
+                if cursor.expect_ident("'subckt'")? != "subckt" {
+                    return Err(cursor.error_at(first.pos, "expected 'inline subckt'"));
+                }
+                self.subckt(cursor)?;
+            }
+            "subckt" => {
+                cursor.bump();
+                self.subckt(cursor)?;
+            }
+            "ends" => {
+                cursor.bump();
+                self.ends(cursor)?;
+            }
+            "include" => {
+                cursor.bump();
+                self.include(cursor)?;
+            }
+            "ahdl_include" => {
+                cursor.bump();
+                let file = cursor.expect_str("a file name")?;
+                let path = resolve_path(cursor.file, &file);
+                self.va_files.push(path);
+            }
+            "global" => {
+                cursor.bump();
+                let nodes = self.node_list(cursor)?;
+                self.global_nodes.extend(nodes);
+            }
+            "if" => return Err(cursor.error("conditional statements are not supported")),
+            keyword if CONTROL_STATEMENTS.contains(&keyword) => {
+                log::warn!("{}:{}: ignoring '{keyword}' statement", cursor.file, first.pos);
+                return Ok(true);
+            }
+            _ => return self.instance(cursor),
+        }
+        Ok(false)
+    }
+
+    fn simulator(&mut self, cursor: &mut Cursor) -> Result<()> {
+        while !cursor.is_empty() {
+            let pos = cursor.pos();
+            let name = cursor.expect_ident("an option")?;
+            cursor.expect(&TokenKind::Assign, "'='")?;
+            let val = cursor.expect_ident("a value")?;
+            match (name, val) {
+                ("lang", "spectre") => (),
+                ("lang", "spice") => {
+                    return Err(cursor.error_at(
+                        pos,
+                        "SPICE compatibility mode is not supported, use the SPICE netlist parser instead",
+                    ))
+                }
+                ("lang", _) => {
+                    return Err(cursor.error_at(pos, format!("unknown netlist language '{val}'")))
+                }
+                _ => log::warn!("{}:{pos}: ignoring simulator option '{name}'", cursor.file),
+            }
+        }
+        Ok(())
+    }
+
+    fn parameters(&mut self, cursor: &mut Cursor) -> Result<()> {
+        while !cursor.is_empty() {
+            let pos = cursor.pos();
+            let name = cursor.expect_ident("a parameter name")?;
+            cursor.expect(&TokenKind::Assign, "'='")?;
+            let val = self.param_value(cursor)?;
+            let ctx = self.scope().ctx;
+            let (param, _) = self
+                .arena
+                .def_param(ctx, name.to_owned())
+                .map_err(|err| cursor.error_at(pos, err))?;
+            self.scope().parameters.push((param, val));
+        }
+        Ok(())
+    }
+
+    /// Parses `<name>=<value>` pairs until the end of the statement
+    fn param_assignments(&mut self, cursor: &mut Cursor) -> Result<Vec<(String, Expr)>> {
+        let mut res = Vec::new();
+        while !cursor.is_empty() {
+            let name = cursor.expect_ident("a parameter name")?;
+            cursor.expect(&TokenKind::Assign, "'='")?;
+            let val = match cursor.peek_kind() {
+                // files (of pwl sources) are relative to the netlist that references them
+                Some(TokenKind::Str(file)) if name == "file" => {
+                    let path = resolve_path(cursor.file, file);
+                    cursor.bump();
+                    Expr::str(self.arena, path.as_str())
+                }
+                _ => self.param_value(cursor)?,
+            };
+            res.push((name.to_owned(), val));
+        }
+        Ok(res)
+    }
+
+    fn param_value(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        match cursor.peek_kind() {
+            Some(TokenKind::Str(val)) => {
+                cursor.bump();
+                Ok(Expr::str(self.arena, val))
+            }
+            Some(TokenKind::LBracket) => Err(cursor.error("vector values are not supported")),
+            // enum values like `type=dc` are passed as strings
+            Some(TokenKind::Ident)
+                if matches!(cursor.nth_kind(1), None | Some(TokenKind::Ident))
+                    && self.lookup_ident(cursor.peek().unwrap().text).is_none() =>
+            {
+                let val = cursor.bump().unwrap().text;
+                Ok(Expr::str(self.arena, val))
+            }
+            _ => self.expr(cursor),
+        }
+    }
+
+    fn model(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let name = cursor.expect_ident("a model name")?.to_owned();
+        let device = cursor.expect_ident("a device name")?.to_owned();
+        let parameters = self.param_assignments(cursor)?;
+        self.scope().models.push(CircuitModelDescription { name, device, parameters });
+        Ok(())
+    }
+
+    fn subckt(&mut self, cursor: &mut Cursor) -> Result<()> {
+        if let Some(outer) = &self.subckt {
+            return Err(cursor.error(format!(
+                "subcircuit definitions can not be nested (inside subcircuit '{}')",
+                outer.name
+            )));
+        }
+        let name = cursor.expect_ident("a subcircuit name")?.to_owned();
+        let ports = self.node_list(cursor)?;
+        cursor.expect_end()?;
+        let scope = Scope::new(self.arena);
+        self.subckt = Some(Subcircuit { name, ports, scope });
+        Ok(())
+    }
+
+    fn ends(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let Some(subckt) = self.subckt.take() else {
+            return Err(cursor.error("'ends' without matching 'subckt'"));
+        };
+        if !cursor.is_empty() {
+            let name = cursor.expect_ident("a subcircuit name")?;
+            if name != subckt.name {
+                return Err(cursor
+                    .error(format!("expected 'ends {}' but found 'ends {name}'", subckt.name)));
+            }
+            cursor.expect_end()?;
+        }
+        self.subcircuits.push(SubcircuitDescription {
+            name: subckt.name,
+            ports: subckt.ports,
+            ctx: subckt.scope.ctx,
+            parameters: subckt.scope.parameters,
+            instances: subckt.scope.instances,
+            models: subckt.scope.models,
+        });
+        Ok(())
+    }
+
+    fn include(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let file = cursor.expect_str("a file name")?;
+        let mut section = None;
+        if !cursor.is_empty() {
+            let pos = cursor.pos();
+            if cursor.expect_ident("'section'")? != "section" {
+                return Err(cursor.error_at(pos, "expected 'section'"));
+            }
+            cursor.expect(&TokenKind::Assign, "'='")?;
+            section = Some(cursor.expect_ident("a section name")?);
+        }
+        cursor.expect_end()?;
+        let path = resolve_path(cursor.file, &file);
+        self.parse_file(&path, section)
+    }
+
+    /// Parses an optionally parenthesized list of node names. Without parenthesis all remaining
+    /// tokens are consumed.
+    fn node_list(&mut self, cursor: &mut Cursor) -> Result<Vec<String>> {
+        let parens = cursor.eat(&TokenKind::LParen);
+        let mut nodes = Vec::new();
+        loop {
+            match cursor.peek() {
+                Some(Token { kind: TokenKind::RParen, .. }) if parens => {
+                    cursor.bump();
+                    break;
+                }
+                Some(Token { kind: TokenKind::Comma, .. }) if parens => {
+                    cursor.bump();
+                }
+                Some(tok @ Token { kind: TokenKind::Ident | TokenKind::Number(_), .. }) => {
+                    cursor.bump();
+                    nodes.push(node_name(tok.text));
+                }
+                Some(tok) => {
+                    return Err(
+                        cursor.error(format!("expected a node name but found '{}'", tok.text))
+                    )
+                }
+                None if parens => return Err(cursor.error("expected ')' but found end of line")),
+                None => break,
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Parses an instance statement:
+    ///
+    /// `<name> [(] <node>* [)] <master> <param>=<value>*`
+    fn instance(&mut self, cursor: &mut Cursor) -> Result<bool> {
+        let start = cursor.pos();
+        let name = cursor.expect_ident("an instance name")?.to_owned();
+        let (terminal_connections, master) = if cursor.at(&TokenKind::LParen) {
+            let nodes = self.node_list(cursor)?;
+            (nodes, cursor.expect_ident("a master name")?.to_owned())
+        } else {
+            // without parenthesis the last name before the parameters is the master
+            let mut names = Vec::new();
+            while !cursor.is_empty() && !cursor.at_assignment() {
+                match cursor.bump().unwrap() {
+                    tok @ Token { kind: TokenKind::Ident | TokenKind::Number(_), .. } => {
+                        names.push(tok.text)
+                    }
+                    tok => {
+                        return Err(cursor.error_at(
+                            tok.pos,
+                            format!("expected a node or master name but found '{}'", tok.text),
+                        ))
+                    }
+                }
+            }
+            let Some(master) = names.pop() else {
+                return Err(cursor.error("expected a master name"));
+            };
+            (names.into_iter().map(node_name).collect(), master.to_owned())
+        };
+
+        if ANALYSES.contains(&&*master) {
+            log::warn!("{}:{start}: ignoring analysis '{name}' ({master})", cursor.file);
+            return Ok(true);
+        }
+
+        let parameters = self.param_assignments(cursor)?;
+        self.scope().instances.push(CircuitInstanceDescription {
+            name,
+            master,
+            parameters,
+            terminal_connections,
+        });
+        Ok(false)
+    }
+}
+
+fn count_braces(line: &[Token], kind: TokenKind) -> usize {
+    line.iter().filter(|tok| tok.kind == kind).count()
+}
+
+/// Node `0` is the ground node
+fn node_name(name: &str) -> String {
+    if name == "0" {
+        "ground".to_owned()
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Resolves paths relative to the directory of the file that references them.
+fn resolve_path(file: &Utf8Path, path: &str) -> Utf8PathBuf {
+    match file.parent() {
+        Some(dir) => dir.join(path),
+        None => Utf8PathBuf::from(path),
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use melange_core::{Arena, CircuitParam, Expr, ExprEvalCtx};
+
+use crate::{parse_file, parse_str};
+
+fn eval(arena: &Arena, expr: Expr) -> f64 {
+    let mut ctx = ExprEvalCtx::new(arena);
+    expr.eval_num(ctx.borrow()).unwrap()
+}
+
+/// Evaluates parameter defaults in declaration order
+fn eval_params(arena: &Arena, params: &[(CircuitParam, Expr)]) -> Vec<f64> {
+    let mut ctx = ExprEvalCtx::new(arena);
+    params
+        .iter()
+        .map(|&(param, val)| {
+            let val = val.eval_num(ctx.borrow()).unwrap();
+            ctx.set_param(param, val.into());
+            val
+        })
+        .collect()
+}
+
+#[test]
+fn instances_and_models() {
+    let src = r#"
+// a simple diode circuit
+simulator lang=spectre
+ahdl_include "diode.va"
+parameters vdd=1.2 rval=2*vdd*1k
+
+model d1 diode_va is=1e-14 \
+    n=1.05
+v1 (in 0) vsource dc=vdd type=dc
+r1 in out resistor r=rval
+* diode to ground
+d0 (out 0) d1
+dcOp dc write="spectre.dc"
+"#;
+    let mut arena = Arena::new();
+    let desc = parse_str(src, Utf8Path::new("netlists/test.scs"), &mut arena).unwrap();
+    assert_eq!(desc.name, "test");
+    assert_eq!(desc.va_files, vec![Utf8PathBuf::from("netlists/diode.va")]);
+    assert_eq!(desc.parameters.len(), 2);
+    assert_eq!(eval_params(&arena, &desc.parameters), vec![1.2, 2400.0]);
+
+    assert_eq!(desc.models.len(), 1);
+    let model = &desc.models.raw[0];
+    assert_eq!((&*model.name, &*model.device), ("d1", "diode_va"));
+    assert_eq!(model.parameters.len(), 2);
+    assert_eq!(eval(&arena, model.parameters[1].1), 1.05);
+
+    let instances: Vec<_> = desc
+        .instances
+        .iter()
+        .map(|inst| (&*inst.name, &*inst.master, inst.terminal_connections.clone()))
+        .collect();
+    assert_eq!(
+        instances,
+        vec![
+            ("v1", "vsource", vec!["in".to_owned(), "ground".to_owned()]),
+            ("r1", "resistor", vec!["in".to_owned(), "out".to_owned()]),
+            ("d0", "d1", vec!["out".to_owned(), "ground".to_owned()]),
+        ]
+    );
+    let vsrc = &desc.instances.raw[0];
+    assert_eq!(vsrc.parameters[0].0, "dc");
+    assert_eq!(vsrc.parameters[1].0, "type");
+    let mut ctx = ExprEvalCtx::new(&arena);
+    assert_eq!(vsrc.parameters[1].1.eval_str(ctx.borrow()).unwrap(), "dc");
+}
+
+#[test]
+fn expressions() {
+    let src = "parameters a=(1+2*3**2-4/2)%5 b=-2**2 c=1u<2m?max(1,3):0 d=M_PI/2 e=5%\n";
+    let mut arena = Arena::new();
+    let desc = parse_str(src, Utf8Path::new("test.scs"), &mut arena).unwrap();
+    let vals = eval_params(&arena, &desc.parameters);
+    assert_eq!(vals, vec![2.0, -4.0, 3.0, std::f64::consts::FRAC_PI_2, 0.05]);
+}
+
+#[test]
+fn subcircuits() {
+    let src = r#"
+subckt divider (in out)
+parameters ratio=0.5 r=1k
+r1 (in out) resistor r=r*(1-ratio)
+r2 (out 0) resistor r=r*ratio
+ends divider
+x1 (a b) divider ratio=0.25
+global 0 vdd
+"#;
+    let mut arena = Arena::new();
+    let desc = parse_str(src, Utf8Path::new("test.scs"), &mut arena).unwrap();
+    assert_eq!(desc.subcircuits.len(), 1);
+    let subckt = &desc.subcircuits[0];
+    assert_eq!(subckt.name, "divider");
+    assert_eq!(subckt.ports, vec!["in".to_owned(), "out".to_owned()]);
+    assert_eq!(subckt.parameters.len(), 2);
+    assert_eq!(subckt.instances.len(), 2);
+    assert_eq!(desc.instances.len(), 1);
+    assert_eq!(desc.instances.raw[0].master, "divider");
+    assert_eq!(desc.global_nodes, vec!["ground".to_owned(), "vdd".to_owned()]);
+}
+
+#[test]
+fn include_section() {
+    let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+        .unwrap()
+        .join(format!("melange_spectre_include_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("models.scs"),
+        "library models\nsection tt\nparameters vth=0.4\nendsection\nsection ff\nparameters vth=0.3\nendsection\nendlibrary\n",
+    )
+    .unwrap();
+    fs::write(dir.join("top.scs"), "include \"models.scs\" section=ff\nparameters v=2*vth\n")
+        .unwrap();
+
+    let mut arena = Arena::new();
+    let desc = parse_file(&dir.join("top.scs"), &mut arena).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let vals = eval_params(&arena, &desc.parameters);
+    assert_eq!(vals, vec![0.3, 0.6]);
+}
+
+#[test]
+fn pwl_file() {
+    let src = r#"
+v1 (in 0) vsource type=pwl file="wave.txt"
+v2 (out 0) vsource type=pwl file="/abs/wave.txt"
+"#;
+    let mut arena = Arena::new();
+    let desc = parse_str(src, Utf8Path::new("netlists/top.scs"), &mut arena).unwrap();
+    let mut ctx = ExprEvalCtx::new(&arena);
+    let files: Vec<_> = desc
+        .instances
+        .iter()
+        .map(|inst| {
+            let (name, file) = &inst.parameters[1];
+            assert_eq!(name, "file");
+            file.eval_str(ctx.borrow()).unwrap().to_owned()
+        })
+        .collect();
+    assert_eq!(files, ["netlists/wave.txt", "/abs/wave.txt"]);
+}
+
+#[test]
+fn errors() {
+    let check = |src: &str, expected: &str| {
+        let mut arena = Arena::new();
+        let err = parse_str(src, Utf8Path::new("test.scs"), &mut arena).err().unwrap();
+        assert_eq!(err.to_string(), expected);
+    };
+    check("parameters a=b+1\n", "test.scs:1:14: unknown parameter 'b'");
+    check("r1 (a 0\n", "test.scs:1:8: expected ')' but found end of line");
+    check("\n simulator lang=spice\n", "test.scs:2:12: SPICE compatibility mode is not supported, use the SPICE netlist parser instead");
+    check("ends\n", "test.scs:1:5: 'ends' without matching 'subckt'");
+    check("subckt foo a b\n", "subcircuit 'foo' is missing 'ends'");
+}
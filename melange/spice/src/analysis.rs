@@ -0,0 +1,65 @@
+//! Analysis cards (`.op`, `.dc`, `.ac`, `.tran` and `.noise`) of a SPICE deck.
+
+/// The frequencies of a small-signal analysis
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrequencySweep {
+    /// `dec <points per decade> <start> <stop>`
+    Dec { points: u32, start: f64, stop: f64 },
+    /// `oct <points per octave> <start> <stop>`
+    Oct { points: u32, start: f64, stop: f64 },
+    /// `lin <points> <start> <stop>`
+    Lin { points: u32, start: f64, stop: f64 },
+}
+
+impl FrequencySweep {
+    /// Returns all frequencies (in Hz) of the sweep in increasing order.
+    pub fn frequencies(&self) -> Vec<f64> {
+        match *self {
+            FrequencySweep::Dec { points, start, stop } => log_sweep(10.0, points, start, stop),
+            FrequencySweep::Oct { points, start, stop } => log_sweep(2.0, points, start, stop),
+            FrequencySweep::Lin { points, start, stop } => {
+                if points < 2 {
+                    return vec![start];
+                }
+                let step = (stop - start) / (points - 1) as f64;
+                (0..points).map(|i| start + i as f64 * step).collect()
+            }
+        }
+    }
+}
+
+fn log_sweep(base: f64, points: u32, start: f64, stop: f64) -> Vec<f64> {
+    let num_steps = ((stop / start).log(base) * points as f64).round() as u32;
+    let ratio = base.powf(1.0 / points as f64);
+    (0..=num_steps).map(|i| start * ratio.powi(i as i32)).collect()
+}
+
+/// A (linear) sweep of a source value with `.dc`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcSweep {
+    /// The name of the swept source (in lowercase)
+    pub source: String,
+    pub start: f64,
+    pub stop: f64,
+    pub step: f64,
+}
+
+/// An analysis requested by the deck. Melange does not run analyses from netlists, instead
+/// they are handed to the caller which maps them to the corresponding simulation API.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Analysis {
+    /// `.op`: [`Simulation::dc_op`](melange_core::simulation::Simulation::dc_op)
+    Op,
+    /// `.dc <src> <start> <stop> <step> [<src2> <start2> <stop2> <step2>]`: the first sweep is
+    /// the inner one.
+    Dc { sweeps: Vec<DcSweep> },
+    /// `.ac <sweep>`: [`Simulation::set_omega`](melange_core::simulation::Simulation::set_omega)
+    /// and [`Simulation::ac`](melange_core::simulation::Simulation::ac) at every frequency
+    Ac { sweep: FrequencySweep },
+    /// `.tran <tstep> <tstop> [<tstart> [<tmax>]] [uic]`:
+    /// [`Simulation::tran`](melange_core::simulation::Simulation::tran)
+    Tran { tstep: f64, tstop: f64, tstart: f64, tmax: Option<f64>, uic: bool },
+    /// `.noise v(<out>[,<ref>]) <src> <sweep>`:
+    /// [`Simulation::noise`](melange_core::simulation::Simulation::noise)
+    Noise { output: (String, String), source: String, sweep: FrequencySweep },
+}
@@ -0,0 +1,271 @@
+//! Parsing of SPICE expressions into the [`melange_core::Arena`].
+//!
+//! Expressions enclosed in braces (`{...}`) or single quotes (`'...'`) can contain
+//! whitespace. Without delimiters an expression ends at the next whitespace.
+
+use anyhow::Result;
+use melange_core::{Arena, CircuitParamCtx, Expr};
+
+use crate::lexer::{Token, TokenKind};
+use crate::parser::{Cursor, Parser};
+
+type UnaryBuilder = fn(&mut Arena, Expr) -> Result<Expr>;
+type BinaryBuilder = fn(&mut Arena, Expr, Expr) -> Result<Expr>;
+
+const UNARY_FUNCTIONS: [(&str, UnaryBuilder); 20] = [
+    ("exp", Expr::exp),
+    ("ln", Expr::log),
+    ("log", Expr::log),
+    ("log10", Expr::log10),
+    ("sqrt", Expr::sqrt),
+    ("abs", Expr::abs),
+    ("sin", Expr::sin),
+    ("cos", Expr::cos),
+    ("tan", Expr::tan),
+    ("atan", Expr::atam),
+    ("asin", Expr::asin),
+    ("acos", Expr::acos),
+    ("sinh", Expr::sinh),
+    ("cosh", Expr::cosh),
+    ("tanh", Expr::tanh),
+    ("atanh", Expr::atanh),
+    ("asinh", Expr::asinh),
+    ("ceil", Expr::ceil),
+    ("floor", Expr::floor),
+    ("int", Expr::int),
+];
+
+const BINARY_FUNCTIONS: [(&str, BinaryBuilder); 6] = [
+    ("pow", Expr::pow),
+    ("fmod", Expr::fmod),
+    ("atan2", Expr::atan2),
+    ("hypot", Expr::hypot),
+    ("min", Expr::min),
+    ("max", Expr::max),
+];
+
+#[derive(Clone, Copy)]
+enum BinaryOp {
+    OrOr,
+    AndAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Returns the precedence and operator of binary operator tokens
+fn binary_op(kind: &TokenKind) -> Option<(u8, BinaryOp)> {
+    let res = match kind {
+        TokenKind::OrOr => (0, BinaryOp::OrOr),
+        TokenKind::AndAnd => (1, BinaryOp::AndAnd),
+        TokenKind::Eq => (2, BinaryOp::Eq),
+        TokenKind::Ne => (2, BinaryOp::Ne),
+        TokenKind::Lt => (3, BinaryOp::Lt),
+        TokenKind::Le => (3, BinaryOp::Le),
+        TokenKind::Gt => (3, BinaryOp::Gt),
+        TokenKind::Ge => (3, BinaryOp::Ge),
+        TokenKind::Plus => (4, BinaryOp::Add),
+        TokenKind::Minus => (4, BinaryOp::Sub),
+        TokenKind::Star => (5, BinaryOp::Mul),
+        TokenKind::Slash => (5, BinaryOp::Div),
+        _ => return None,
+    };
+    Some(res)
+}
+
+impl Parser<'_> {
+    /// Parses an expression. If `word` is set the expression ends at the next whitespace.
+    pub(crate) fn expr(&mut self, cursor: &mut Cursor, word: bool) -> Result<Expr> {
+        let cond = self.binary_expr(cursor, 0, word)?;
+        if !self.continues(cursor, word, &TokenKind::Question) {
+            return Ok(cond);
+        }
+        cursor.bump();
+        let pos = cursor.pos();
+        let then_val = self.expr(cursor, word)?;
+        cursor.expect(&TokenKind::Colon, "':'")?;
+        let else_val = self.expr(cursor, word)?;
+        Expr::cond(self.arena, cond, then_val, else_val).map_err(|err| cursor.error_at(pos, err))
+    }
+
+    /// Whether the next token continues the current expression with `kind`
+    fn continues(&self, cursor: &Cursor, word: bool, kind: &TokenKind) -> bool {
+        matches!(cursor.peek(), Some(tok) if &tok.kind == kind && !(word && tok.space_before))
+    }
+
+    fn binary_expr(&mut self, cursor: &mut Cursor, min_prec: u8, word: bool) -> Result<Expr> {
+        let mut lhs = self.unary_expr(cursor, word)?;
+        while let Some(tok) = cursor.peek() {
+            let Some((prec, op)) = binary_op(&tok.kind) else { break };
+            if prec < min_prec || (word && tok.space_before) {
+                break;
+            }
+            cursor.bump();
+            let rhs = self.binary_expr(cursor, prec + 1, word)?;
+            lhs = self.binary(op, lhs, rhs).map_err(|err| cursor.error_at(tok.pos, err))?;
+        }
+        Ok(lhs)
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: Expr, rhs: Expr) -> Result<Expr> {
+        let arena = &mut *self.arena;
+        match op {
+            BinaryOp::OrOr => Expr::logic_or(arena, lhs, rhs),
+            BinaryOp::AndAnd => Expr::logic_and(arena, lhs, rhs),
+            BinaryOp::Eq => Ok(Expr::eq(arena, lhs, rhs)),
+            BinaryOp::Ne => Ok(Expr::neq(arena, lhs, rhs)),
+            BinaryOp::Lt => Expr::lt(arena, lhs, rhs),
+            BinaryOp::Le => Expr::le(arena, lhs, rhs),
+            BinaryOp::Gt => Expr::lt(arena, rhs, lhs),
+            BinaryOp::Ge => Expr::le(arena, rhs, lhs),
+            BinaryOp::Add => Expr::add(arena, lhs, rhs),
+            BinaryOp::Sub => {
+                let rhs = Expr::neg(arena, rhs)?;
+                Expr::add(arena, lhs, rhs)
+            }
+            BinaryOp::Mul => Expr::mul(arena, lhs, rhs),
+            BinaryOp::Div => {
+                let rhs = Expr::inv(arena, rhs)?;
+                Expr::mul(arena, lhs, rhs)
+            }
+        }
+    }
+
+    fn unary_expr(&mut self, cursor: &mut Cursor, word: bool) -> Result<Expr> {
+        let pos = cursor.pos();
+        match cursor.peek_kind() {
+            Some(TokenKind::Minus) => {
+                cursor.bump();
+                let arg = self.unary_expr(cursor, word)?;
+                Expr::neg(self.arena, arg).map_err(|err| cursor.error_at(pos, err))
+            }
+            Some(TokenKind::Plus) => {
+                cursor.bump();
+                self.unary_expr(cursor, word)
+            }
+            Some(TokenKind::Not) => {
+                cursor.bump();
+                let arg = self.unary_expr(cursor, word)?;
+                Ok(Expr::eq(self.arena, arg, 0.0.into()))
+            }
+            _ => self.pow_expr(cursor, word),
+        }
+    }
+
+    /// `**` and `^` are right associative and bind stronger than unary operators
+    fn pow_expr(&mut self, cursor: &mut Cursor, word: bool) -> Result<Expr> {
+        let base = self.primary_expr(cursor)?;
+        let pos = cursor.pos();
+        if !self.continues(cursor, word, &TokenKind::Pow)
+            && !self.continues(cursor, word, &TokenKind::Caret)
+        {
+            return Ok(base);
+        }
+        cursor.bump();
+        let exp = self.unary_expr(cursor, word)?;
+        Expr::pow(self.arena, base, exp).map_err(|err| cursor.error_at(pos, err))
+    }
+
+    fn primary_expr(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        let Some(tok) = cursor.peek() else {
+            return Err(cursor.error("expected an expression but found end of line"));
+        };
+        let delimiter = match &tok.kind {
+            TokenKind::Number(val) => {
+                cursor.bump();
+                return Ok((*val).into());
+            }
+            TokenKind::Ident if cursor.nth_kind(1) == Some(&TokenKind::LParen) => {
+                return self.call_expr(cursor);
+            }
+            TokenKind::Ident => {
+                cursor.bump();
+                return self.ident(cursor, tok);
+            }
+            TokenKind::LParen => (TokenKind::RParen, "')'"),
+            TokenKind::LBrace => (TokenKind::RBrace, "'}'"),
+            TokenKind::Quote => (TokenKind::Quote, "'"),
+            _ => {
+                return Err(cursor.error(format!("expected an expression but found '{}'", tok.text)))
+            }
+        };
+        cursor.bump();
+        let res = self.expr(cursor, false)?;
+        cursor.expect(&delimiter.0, delimiter.1)?;
+        Ok(res)
+    }
+
+    fn ident(&mut self, cursor: &Cursor, tok: &Token) -> Result<Expr> {
+        let name = tok.name();
+        self.lookup_ident(&name)
+            .ok_or_else(|| cursor.error_at(tok.pos, format!("unknown parameter '{name}'")))
+    }
+
+    /// Resolves a parameter (or a builtin constant) by its lowercase name.
+    pub(crate) fn lookup_ident(&self, name: &str) -> Option<Expr> {
+        match name {
+            "pi" => return Some(std::f64::consts::PI.into()),
+            "temper" => {
+                return self
+                    .arena
+                    .lookup_param_by_name(CircuitParamCtx::ROOT, "temp")
+                    .map(|(_, expr)| expr)
+            }
+            _ => (),
+        }
+        self.param_scopes()
+            .find_map(|ctx| self.arena.lookup_param_by_name(ctx, name))
+            .map(|(_, read_expr)| read_expr)
+    }
+
+    fn call_expr(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        let tok = cursor.bump().unwrap();
+        let name = tok.name();
+        cursor.bump();
+        let mut args = Vec::new();
+        if !cursor.eat(&TokenKind::RParen) {
+            loop {
+                args.push(self.expr(cursor, false)?);
+                if cursor.eat(&TokenKind::RParen) {
+                    break;
+                }
+                cursor.expect(&TokenKind::Comma, "',' or ')'")?;
+            }
+        }
+
+        let res = if let Some((_, builder)) = UNARY_FUNCTIONS.iter().find(|(func, _)| *func == name)
+        {
+            let &[arg] = &*args else {
+                return Err(cursor.error_at(
+                    tok.pos,
+                    format!(
+                        "function '{name}' expects 1 argument but {} were provided",
+                        args.len()
+                    ),
+                ));
+            };
+            builder(self.arena, arg)
+        } else if let Some((_, builder)) = BINARY_FUNCTIONS.iter().find(|(func, _)| *func == name) {
+            let &[lhs, rhs] = &*args else {
+                return Err(cursor.error_at(
+                    tok.pos,
+                    format!(
+                        "function '{name}' expects 2 arguments but {} were provided",
+                        args.len()
+                    ),
+                ));
+            };
+            builder(self.arena, lhs, rhs)
+        } else {
+            return Err(cursor.error_at(tok.pos, format!("unknown function '{name}'")));
+        };
+        res.map_err(|err| cursor.error_at(tok.pos, err))
+    }
+}
@@ -0,0 +1,285 @@
+//! Splits a SPICE deck into logical lines and tokens.
+//!
+//! SPICE is line oriented: every line is a statement, except for lines starting with `+`
+//! which continue the previous line. Lines starting with `*` are comments and `;` or ` $ `
+//! start a comment that extends to the end of the line. Whitespace is significant: values
+//! that are not enclosed in braces or quotes end at the next whitespace, therefore every
+//! token records whether it was preceded by whitespace.
+
+use std::fmt;
+
+/// A position within a source file (1-based)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Pos {
+    pub line: u32,
+    pub col: u32,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TokenKind {
+    Ident,
+    Number(f64),
+    Str(String),
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Quote,
+    Comma,
+    Assign,
+    Question,
+    Colon,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Pow,
+    Caret,
+    Not,
+    AndAnd,
+    OrOr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The source text of this token
+    pub text: String,
+    pub pos: Pos,
+    pub space_before: bool,
+}
+
+impl Token {
+    /// SPICE is case-insensitive, names are therefore normalized to lowercase
+    pub fn name(&self) -> String {
+        self.text.to_ascii_lowercase()
+    }
+}
+
+/// A statement that may span multiple physical lines (joined with `+`).
+pub struct Line {
+    pub text: String,
+    /// Offsets into `text` where a new physical line starts and its position.
+    segments: Vec<(usize, Pos)>,
+}
+
+impl Line {
+    pub fn pos(&self) -> Pos {
+        self.segments[0].1
+    }
+
+    pub fn pos_at(&self, off: usize) -> Pos {
+        let i = self.segments.partition_point(|(start, _)| *start <= off) - 1;
+        let (start, pos) = self.segments[i];
+        Pos { line: pos.line, col: pos.col + (off - start) as u32 }
+    }
+
+    /// The first word of the line (in lowercase)
+    pub fn keyword(&self) -> String {
+        self.text.split_whitespace().next().unwrap_or("").to_ascii_lowercase()
+    }
+
+    /// The whitespace separated words of the line, quotes around words are removed.
+    /// Used for statements like `.include` whose arguments are file paths.
+    pub fn words(&self) -> Vec<&str> {
+        self.text
+            .split_whitespace()
+            .map(|word| word.trim_matches(|c| c == '"' || c == '\''))
+            .collect()
+    }
+}
+
+/// Returns the start of a trailing comment in `line`
+fn comment_start(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut in_str = false;
+    for (i, &c) in bytes.iter().enumerate() {
+        match c {
+            b'"' => in_str = !in_str,
+            b';' if !in_str => return Some(i),
+            b'$' if !in_str && (i == 0 || bytes[i - 1].is_ascii_whitespace()) => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Splits `src` into logical lines. If `has_title` is set the first line is the title of the
+/// deck and is returned separately.
+pub fn logical_lines(src: &str, has_title: bool) -> (Option<String>, Vec<Line>) {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut title = None;
+    for (i, line) in src.lines().enumerate() {
+        if i == 0 && has_title {
+            title = Some(line.trim().to_owned());
+            continue;
+        }
+        let line = match comment_start(line) {
+            Some(end) => &line[..end],
+            None => line,
+        };
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('*') {
+            continue;
+        }
+        let mut col = (line.len() - content.len()) as u32 + 1;
+        let content = content.trim_end();
+        match (content.strip_prefix('+'), lines.last_mut()) {
+            (Some(continuation), Some(prev)) => {
+                col += 1;
+                prev.text.push(' ');
+                prev.segments.push((prev.text.len(), Pos { line: i as u32 + 1, col }));
+                prev.text.push_str(continuation);
+            }
+            _ => lines.push(Line {
+                text: content.to_owned(),
+                segments: vec![(0, Pos { line: i as u32 + 1, col })],
+            }),
+        }
+    }
+    (title, lines)
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, b'_' | b'#')
+}
+
+fn is_ident_continue(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'#' | b'$' | b'.')
+}
+
+/// Scale factors are case-insensitive and any letters following them (units) are ignored.
+fn scale_factor(suffix: &str) -> f64 {
+    let suffix = suffix.to_ascii_lowercase();
+    if suffix.starts_with("meg") {
+        return 1e6;
+    }
+    if suffix.starts_with("mil") {
+        return 25.4e-6;
+    }
+    match suffix.as_bytes().first() {
+        Some(b't') => 1e12,
+        Some(b'g') => 1e9,
+        Some(b'k') => 1e3,
+        Some(b'm') => 1e-3,
+        Some(b'u') => 1e-6,
+        Some(b'n') => 1e-9,
+        Some(b'p') => 1e-12,
+        Some(b'f') => 1e-15,
+        Some(b'a') => 1e-18,
+        _ => 1.0,
+    }
+}
+
+/// Tokenizes a logical line. Returns the position and a description if an invalid
+/// character or an unterminated string is encountered.
+pub fn tokenize(line: &Line) -> Result<Vec<Token>, (Pos, String)> {
+    let src = line.text.as_bytes();
+    let peek = |i: usize| src.get(i).copied().unwrap_or(0);
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut space_before = true;
+    while pos < src.len() {
+        let start = pos;
+        let c = src[pos];
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            space_before = true;
+            continue;
+        }
+
+        let kind = if c.is_ascii_digit() || (c == b'.' && peek(pos + 1).is_ascii_digit()) {
+            while peek(pos).is_ascii_digit() {
+                pos += 1;
+            }
+            if peek(pos) == b'.' {
+                pos += 1;
+                while peek(pos).is_ascii_digit() {
+                    pos += 1;
+                }
+            }
+            if matches!(peek(pos), b'e' | b'E') {
+                let off = if matches!(peek(pos + 1), b'+' | b'-') { 2 } else { 1 };
+                if peek(pos + off).is_ascii_digit() {
+                    pos += off;
+                    while peek(pos).is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+            }
+            let num_end = pos;
+            while is_ident_continue(peek(pos)) && peek(pos) != b'.' {
+                pos += 1;
+            }
+            let val: f64 = line.text[start..num_end].parse().unwrap();
+            TokenKind::Number(val * scale_factor(&line.text[num_end..pos]))
+        } else if is_ident_start(c) || (c == b'.' && peek(pos + 1).is_ascii_alphabetic()) {
+            // control cards start with a dot
+            pos += 1;
+            while is_ident_continue(peek(pos)) {
+                pos += 1;
+            }
+            TokenKind::Ident
+        } else if c == b'"' {
+            let end = line.text[pos + 1..]
+                .find('"')
+                .ok_or_else(|| (line.pos_at(start), "unterminated string literal".to_owned()))?;
+            pos += end + 2;
+            TokenKind::Str(line.text[start + 1..pos - 1].to_owned())
+        } else {
+            let (kind, len) = match (c, peek(pos + 1)) {
+                (b'*', b'*') => (TokenKind::Pow, 2),
+                (b'&', b'&') => (TokenKind::AndAnd, 2),
+                (b'|', b'|') => (TokenKind::OrOr, 2),
+                (b'=', b'=') => (TokenKind::Eq, 2),
+                (b'!', b'=') => (TokenKind::Ne, 2),
+                (b'<', b'=') => (TokenKind::Le, 2),
+                (b'>', b'=') => (TokenKind::Ge, 2),
+                (b'(', _) => (TokenKind::LParen, 1),
+                (b')', _) => (TokenKind::RParen, 1),
+                (b'{', _) => (TokenKind::LBrace, 1),
+                (b'}', _) => (TokenKind::RBrace, 1),
+                (b'\'', _) => (TokenKind::Quote, 1),
+                (b',', _) => (TokenKind::Comma, 1),
+                (b'=', _) => (TokenKind::Assign, 1),
+                (b'?', _) => (TokenKind::Question, 1),
+                (b':', _) => (TokenKind::Colon, 1),
+                (b'+', _) => (TokenKind::Plus, 1),
+                (b'-', _) => (TokenKind::Minus, 1),
+                (b'*', _) => (TokenKind::Star, 1),
+                (b'/', _) => (TokenKind::Slash, 1),
+                (b'^', _) => (TokenKind::Caret, 1),
+                (b'!', _) => (TokenKind::Not, 1),
+                (b'<', _) => (TokenKind::Lt, 1),
+                (b'>', _) => (TokenKind::Gt, 1),
+                _ => {
+                    let c = line.text[start..].chars().next().unwrap();
+                    return Err((line.pos_at(start), format!("unexpected character '{c}'")));
+                }
+            };
+            pos += len;
+            kind
+        };
+
+        tokens.push(Token {
+            kind,
+            text: line.text[start..pos].to_owned(),
+            pos: line.pos_at(start),
+            space_before,
+        });
+        space_before = false;
+    }
+    Ok(tokens)
+}
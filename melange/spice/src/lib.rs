@@ -0,0 +1,58 @@
+//! A parser for SPICE decks (in the ngspice dialect) that produces a [`CircuitDescription`].
+//!
+//! The following statements are supported:
+//!
+//! * element lines for resistors (`R`), capacitors (`C`), inductors (`L`), independent sources
+//!   (`V`, `I`), controlled sources (`E`, `G`, `F`, `H`), OSDI devices (`N`) and subcircuit
+//!   instances (`X`)
+//! * `.model`, `.param` and `.subckt` ... `.ends`
+//! * `.include` and `.lib` (including a section of a library file)
+//! * `.osdi <file>` and `pre_osdi <file>` inside `.control` blocks, which add a Verilog-A file
+//!   or a compiled OSDI library to [`CircuitDescription::va_files`]
+//! * the analysis cards `.op`, `.dc`, `.ac`, `.tran` and `.noise` (see [`Analysis`])
+//!
+//! SPICE is case-insensitive, all names (except device names) are therefore converted to
+//! lowercase. The nodes `0` and `gnd` are mapped to the `ground` node. Errors contain the file,
+//! line and column they occurred at.
+
+use anyhow::Result;
+use camino::Utf8Path;
+use melange_core::{Arena, CircuitDescription};
+
+pub use crate::analysis::{Analysis, DcSweep, FrequencySweep};
+use crate::parser::Parser;
+
+mod analysis;
+mod expr;
+mod lexer;
+mod parser;
+#[cfg(test)]
+mod tests;
+
+/// A parsed SPICE deck
+pub struct SpiceDeck {
+    /// The title (first line) of the deck
+    pub title: String,
+    pub circuit: CircuitDescription,
+    /// The analyses in the order they were specified in the deck
+    pub analyses: Vec<Analysis>,
+}
+
+/// Parses the SPICE deck at `path`. All expressions are allocated in `arena`.
+pub fn parse_file(path: &Utf8Path, arena: &mut Arena) -> Result<SpiceDeck> {
+    let mut parser = Parser::new(arena);
+    parser.parse_file(path, true, None)?;
+    parser.finish(circuit_name(path))
+}
+
+/// Parses the SPICE deck `src`. `path` is used in error messages and to resolve included
+/// files.
+pub fn parse_str(src: &str, path: &Utf8Path, arena: &mut Arena) -> Result<SpiceDeck> {
+    let mut parser = Parser::new(arena);
+    parser.parse_src(src, path, true, None)?;
+    parser.finish(circuit_name(path))
+}
+
+fn circuit_name(path: &Utf8Path) -> String {
+    path.file_stem().unwrap_or(path.as_str()).to_owned()
+}
@@ -0,0 +1,809 @@
+use std::fs::read_to_string;
+
+use anyhow::{anyhow, bail, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use melange_core::elaboration::{
+    CircuitInstanceDescription, CircuitModelDescription, SubcircuitDescription,
+};
+use melange_core::{Arena, CircuitDescription, CircuitParam, CircuitParamCtx, Expr, Value};
+
+use crate::analysis::{Analysis, DcSweep, FrequencySweep};
+use crate::lexer::{logical_lines, tokenize, Line, Pos, Token, TokenKind};
+use crate::SpiceDeck;
+
+/// Cards that only affect output or simulator options. These are exposed by the melange API
+/// instead and are skipped.
+const IGNORED_CARDS: [&str; 14] = [
+    ".options", ".option", ".opt", ".temp", ".save", ".print", ".plot", ".probe", ".ic",
+    ".nodeset", ".meas", ".measure", ".four", ".width",
+];
+
+const WAVEFORMS: [&str; 7] = ["pulse", "sin", "exp", "pwl", "sffm", "am", "trrandom"];
+
+/// The master, terminal connections and parameters of an element card
+type Element = (String, Vec<String>, Vec<(String, Expr)>);
+
+/// The parameters, models and instances defined at the top level or inside a subcircuit.
+struct Scope {
+    ctx: CircuitParamCtx,
+    parameters: Vec<(CircuitParam, Expr)>,
+    instances: Vec<CircuitInstanceDescription>,
+    models: Vec<CircuitModelDescription>,
+}
+
+impl Scope {
+    fn new(arena: &mut Arena) -> Scope {
+        Scope {
+            ctx: arena.add_ctx(),
+            parameters: Vec::new(),
+            instances: Vec::new(),
+            models: Vec::new(),
+        }
+    }
+}
+
+struct Subcircuit {
+    name: String,
+    ports: Vec<String>,
+    scope: Scope,
+}
+
+/// The tokens of a single statement.
+pub(crate) struct Cursor<'t> {
+    tokens: &'t [Token],
+    /// position after the last token, used to report unexpected end of statements
+    end: Pos,
+    file: &'t Utf8Path,
+}
+
+impl<'t> Cursor<'t> {
+    pub(crate) fn peek(&self) -> Option<&'t Token> {
+        self.tokens.first()
+    }
+
+    pub(crate) fn peek_kind(&self) -> Option<&'t TokenKind> {
+        self.peek().map(|tok| &tok.kind)
+    }
+
+    pub(crate) fn nth_kind(&self, n: usize) -> Option<&'t TokenKind> {
+        self.tokens.get(n).map(|tok| &tok.kind)
+    }
+
+    pub(crate) fn bump(&mut self) -> Option<&'t Token> {
+        let (first, rem) = self.tokens.split_first()?;
+        self.tokens = rem;
+        Some(first)
+    }
+
+    pub(crate) fn at(&self, kind: &TokenKind) -> bool {
+        self.peek_kind() == Some(kind)
+    }
+
+    pub(crate) fn eat(&mut self, kind: &TokenKind) -> bool {
+        let res = self.at(kind);
+        if res {
+            self.bump();
+        }
+        res
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// The position of the next token (or the end of the statement).
+    pub(crate) fn pos(&self) -> Pos {
+        self.peek().map_or(self.end, |tok| tok.pos)
+    }
+
+    pub(crate) fn error_at(&self, pos: Pos, msg: impl std::fmt::Display) -> anyhow::Error {
+        anyhow!("{}:{}: {}", self.file, pos, msg)
+    }
+
+    pub(crate) fn error(&self, msg: impl std::fmt::Display) -> anyhow::Error {
+        self.error_at(self.pos(), msg)
+    }
+
+    pub(crate) fn expect(&mut self, kind: &TokenKind, what: &str) -> Result<&'t Token> {
+        match self.peek() {
+            Some(tok) if &tok.kind == kind => {
+                self.bump();
+                Ok(tok)
+            }
+            Some(tok) => Err(self.error(format!("expected {what} but found '{}'", tok.text))),
+            None => Err(self.error(format!("expected {what} but found end of line"))),
+        }
+    }
+
+    /// Expects a name, numbers are accepted as well because node names are often numeric.
+    fn expect_name(&mut self, what: &str) -> Result<&'t Token> {
+        match self.peek() {
+            Some(tok @ Token { kind: TokenKind::Ident | TokenKind::Number(_), .. }) => {
+                self.bump();
+                Ok(tok)
+            }
+            Some(tok) => Err(self.error(format!("expected {what} but found '{}'", tok.text))),
+            None => Err(self.error(format!("expected {what} but found end of line"))),
+        }
+    }
+
+    fn expect_end(&self) -> Result<()> {
+        match self.peek() {
+            Some(tok) => Err(self.error(format!("expected end of line but found '{}'", tok.text))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the next tokens are `<name> =`
+    fn at_assignment(&self) -> bool {
+        self.nth_kind(0) == Some(&TokenKind::Ident) && self.nth_kind(1) == Some(&TokenKind::Assign)
+    }
+
+    /// Whether the next tokens are `params:`
+    fn at_params_marker(&self) -> bool {
+        matches!(self.peek(), Some(tok) if tok.kind == TokenKind::Ident && tok.name() == "params")
+            && self.nth_kind(1) == Some(&TokenKind::Colon)
+    }
+
+    /// Whether the next token is a keyword (the identifier `keyword`)
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(tok) if tok.kind == TokenKind::Ident && tok.name() == keyword)
+    }
+}
+
+pub(crate) struct Parser<'a> {
+    pub(crate) arena: &'a mut Arena,
+    top: Scope,
+    subckt: Option<Subcircuit>,
+    subcircuits: Vec<SubcircuitDescription>,
+    global_nodes: Vec<String>,
+    va_files: Vec<Utf8PathBuf>,
+    analyses: Vec<Analysis>,
+    title: String,
+    /// the files currently being parsed, used to detect recursive includes
+    include_stack: Vec<Utf8PathBuf>,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(arena: &'a mut Arena) -> Parser<'a> {
+        let top = Scope::new(arena);
+        Parser {
+            arena,
+            top,
+            subckt: None,
+            subcircuits: Vec::new(),
+            global_nodes: Vec::new(),
+            va_files: Vec::new(),
+            analyses: Vec::new(),
+            title: String::new(),
+            include_stack: Vec::new(),
+        }
+    }
+
+    pub(crate) fn finish(self, name: String) -> Result<SpiceDeck> {
+        if let Some(subckt) = self.subckt {
+            bail!("subcircuit '{}' is missing '.ends'", subckt.name)
+        }
+        let circuit = CircuitDescription {
+            name,
+            instances: self.top.instances.into(),
+            models: self.top.models.into(),
+            subcircuits: self.subcircuits,
+            global_nodes: self.global_nodes,
+            va_files: self.va_files,
+            ctx: self.top.ctx,
+            parameters: self.top.parameters,
+        };
+        Ok(SpiceDeck { title: self.title, circuit, analyses: self.analyses })
+    }
+
+    /// The parameter contexts that are searched (in order) when a parameter is referenced.
+    pub(crate) fn param_scopes(&self) -> impl Iterator<Item = CircuitParamCtx> {
+        let subckt = self.subckt.as_ref().map(|subckt| subckt.scope.ctx);
+        subckt.into_iter().chain([self.top.ctx, CircuitParamCtx::ROOT])
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        match &mut self.subckt {
+            Some(subckt) => &mut subckt.scope,
+            None => &mut self.top,
+        }
+    }
+
+    pub(crate) fn parse_file(
+        &mut self,
+        path: &Utf8Path,
+        is_deck: bool,
+        section: Option<&str>,
+    ) -> Result<()> {
+        let src = read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+        self.parse_src(&src, path, is_deck, section)
+    }
+
+    /// Parses the statements in `src`. The first line of a deck (`is_deck`) is its title
+    /// while included files have no title. If `section` is set, only the statements in the
+    /// `.lib <section>` ... `.endl` block are parsed.
+    pub(crate) fn parse_src(
+        &mut self,
+        src: &str,
+        path: &Utf8Path,
+        is_deck: bool,
+        section: Option<&str>,
+    ) -> Result<()> {
+        if self.include_stack.iter().any(|file| file == path) {
+            bail!("{path} includes itself recursively")
+        }
+        self.include_stack.push(path.to_owned());
+
+        let (title, lines) = logical_lines(src, is_deck);
+        if let Some(title) = title {
+            self.title = title;
+        }
+
+        let mut in_control = false;
+        let mut in_section: Option<String> = None;
+        let mut found_section = false;
+
+        for line in &lines {
+            let keyword = line.keyword();
+            let words = line.words();
+
+            // ngspice control blocks contain a scripting language, only osdi libraries are
+            // extracted from it
+            if in_control {
+                match &*keyword {
+                    ".endc" => in_control = false,
+                    "pre_osdi" => self.osdi(line, path)?,
+                    _ => (),
+                }
+                continue;
+            }
+
+            match &*keyword {
+                ".lib" if words.len() == 2 => {
+                    if let Some(outer) = &in_section {
+                        bail!(
+                            "{path}:{}: library section '{}' can not be nested inside '{outer}'",
+                            line.pos(),
+                            words[1]
+                        )
+                    }
+                    let name = words[1].to_ascii_lowercase();
+                    found_section |= section == Some(&*name);
+                    in_section = Some(name);
+                    continue;
+                }
+                ".endl" => {
+                    if in_section.take().is_none() {
+                        bail!("{path}:{}: '.endl' without matching '.lib'", line.pos())
+                    }
+                    continue;
+                }
+                // sections of libraries are only parsed when they are explicitly included
+                _ if in_section
+                    .as_deref()
+                    .map_or(section.is_some(), |name| section != Some(name)) =>
+                {
+                    continue
+                }
+                ".control" => in_control = true,
+                ".end" => break,
+                _ => self.statement(line, path)?,
+            }
+        }
+
+        if in_control {
+            bail!("{path}: '.control' is missing '.endc'")
+        }
+        if let Some(name) = in_section {
+            bail!("{path}: library section '{name}' is missing '.endl'")
+        }
+        if let (Some(section), false) = (section, found_section) {
+            bail!("{path}: library section '{section}' not found")
+        }
+
+        self.include_stack.pop();
+        Ok(())
+    }
+
+    fn statement(&mut self, line: &Line, path: &Utf8Path) -> Result<()> {
+        let keyword = line.keyword();
+        let words = line.words();
+        let pos = line.pos();
+
+        // the arguments of these cards are file paths or text and are not tokenized
+        match &*keyword {
+            ".title" => {
+                self.title = line.text[keyword.len()..].trim().to_owned();
+                return Ok(());
+            }
+            ".include" | ".inc" => {
+                let [_, file] = words[..] else {
+                    bail!("{path}:{pos}: expected '{keyword} <file>'")
+                };
+                return self.parse_file(&resolve_path(path, file), false, None);
+            }
+            ".lib" => {
+                let [_, file, section] = words[..] else {
+                    bail!("{path}:{pos}: expected '.lib <file> <section>'")
+                };
+                let section = section.to_ascii_lowercase();
+                return self.parse_file(&resolve_path(path, file), false, Some(&section));
+            }
+            ".osdi" => return self.osdi(line, path),
+            _ => (),
+        }
+
+        let tokens = tokenize(line).map_err(|(pos, msg)| anyhow!("{path}:{pos}: {msg}"))?;
+        let end = line.pos_at(line.text.len());
+        let mut cursor = Cursor { tokens: &tokens, end, file: path };
+        if !keyword.starts_with('.') {
+            return self.element(&mut cursor);
+        }
+
+        match &*keyword {
+            ".param" => {
+                cursor.bump();
+                self.param(&mut cursor)?;
+            }
+            ".model" => {
+                cursor.bump();
+                self.model(&mut cursor)?;
+            }
+            ".subckt" => {
+                cursor.bump();
+                self.subckt(&mut cursor)?;
+            }
+            ".ends" => {
+                cursor.bump();
+                self.ends(&mut cursor)?;
+            }
+            ".global" => {
+                cursor.bump();
+                while !cursor.is_empty() {
+                    let node = node_name(cursor.expect_name("a node name")?);
+                    self.global_nodes.push(node);
+                }
+            }
+            ".op" => {
+                cursor.bump();
+                cursor.expect_end()?;
+                self.analyses.push(Analysis::Op);
+            }
+            ".dc" => {
+                cursor.bump();
+                self.dc(&mut cursor)?;
+            }
+            ".ac" => {
+                cursor.bump();
+                let sweep = self.freq_sweep(&mut cursor)?;
+                cursor.expect_end()?;
+                self.analyses.push(Analysis::Ac { sweep });
+            }
+            ".tran" => {
+                cursor.bump();
+                self.tran(&mut cursor)?;
+            }
+            ".noise" => {
+                cursor.bump();
+                self.noise(&mut cursor)?;
+            }
+            ".func" => bail!("{path}:{pos}: '.func' is not supported"),
+            card if IGNORED_CARDS.contains(&card) => {
+                log::warn!("{path}:{pos}: ignoring '{card}'")
+            }
+            card => bail!("{path}:{pos}: unknown control card '{card}'"),
+        }
+        Ok(())
+    }
+
+    /// `.osdi <file>`/`pre_osdi <file>` loads compiled (`.osdi`) or Verilog-A (`.va`) models
+    fn osdi(&mut self, line: &Line, path: &Utf8Path) -> Result<()> {
+        let words = line.words();
+        let [keyword, file] = words[..] else {
+            bail!("{path}:{}: expected '{} <file>'", line.pos(), line.keyword())
+        };
+        let file = resolve_path(path, file);
+        if !matches!(file.extension(), Some("osdi" | "va")) {
+            bail!("{path}:{}: {keyword} expects a .osdi or .va file but found {file}", line.pos())
+        }
+        self.va_files.push(file);
+        Ok(())
+    }
+
+    fn param(&mut self, cursor: &mut Cursor) -> Result<()> {
+        while !cursor.is_empty() {
+            let pos = cursor.pos();
+            let name = cursor.expect(&TokenKind::Ident, "a parameter name")?.name();
+            cursor.expect(&TokenKind::Assign, "'='")?;
+            let val = self.expr(cursor, false)?;
+            cursor.eat(&TokenKind::Comma);
+            let ctx = self.scope().ctx;
+            let (param, _) =
+                self.arena.def_param(ctx, name).map_err(|err| cursor.error_at(pos, err))?;
+            self.scope().parameters.push((param, val));
+        }
+        Ok(())
+    }
+
+    /// Parses `<name>=<value>` pairs until the end of the statement
+    fn param_assignments(&mut self, cursor: &mut Cursor) -> Result<Vec<(String, Expr)>> {
+        let mut res = Vec::new();
+        while !cursor.is_empty() {
+            let name = cursor.expect(&TokenKind::Ident, "a parameter name")?.text.clone();
+            cursor.expect(&TokenKind::Assign, "'='")?;
+            let val = match cursor.peek_kind() {
+                // files (of pwl sources) are relative to the netlist that references them
+                Some(TokenKind::Str(file)) if name == "file" => {
+                    let path = resolve_path(cursor.file, file);
+                    cursor.bump();
+                    Expr::str(self.arena, path.as_str())
+                }
+                _ => self.value(cursor)?,
+            };
+            cursor.eat(&TokenKind::Comma);
+            res.push((name, val));
+        }
+        Ok(res)
+    }
+
+    /// Parses a value, values that are not enclosed in braces or quotes end at the next
+    /// whitespace.
+    fn value(&mut self, cursor: &mut Cursor) -> Result<Expr> {
+        match cursor.peek_kind() {
+            Some(TokenKind::Str(val)) => {
+                cursor.bump();
+                Ok(Expr::str(self.arena, val))
+            }
+            _ => self.expr(cursor, true),
+        }
+    }
+
+    /// Parses a value that must be a constant number (used for analysis cards)
+    fn number(&mut self, cursor: &mut Cursor) -> Result<f64> {
+        let pos = cursor.pos();
+        match self.value(cursor)? {
+            Expr::Value(Value::Num(val)) => Ok(val),
+            _ => Err(cursor.error_at(pos, "expected a constant number")),
+        }
+    }
+
+    fn model(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let name = cursor.expect(&TokenKind::Ident, "a model name")?.name();
+        // the device name is case sensitive in melange
+        let device = cursor.expect(&TokenKind::Ident, "a device name")?.text.clone();
+        let parens = cursor.eat(&TokenKind::LParen);
+        let mut parameters = Vec::new();
+        while !(cursor.is_empty() || parens && cursor.at(&TokenKind::RParen)) {
+            let name = cursor.expect(&TokenKind::Ident, "a parameter name")?.text.clone();
+            cursor.expect(&TokenKind::Assign, "'='")?;
+            let val = self.value(cursor)?;
+            cursor.eat(&TokenKind::Comma);
+            parameters.push((name, val));
+        }
+        if parens {
+            cursor.expect(&TokenKind::RParen, "')'")?;
+        }
+        cursor.expect_end()?;
+        self.scope().models.push(CircuitModelDescription { name, device, parameters });
+        Ok(())
+    }
+
+    fn subckt(&mut self, cursor: &mut Cursor) -> Result<()> {
+        if let Some(outer) = &self.subckt {
+            return Err(cursor.error(format!(
+                "subcircuit definitions can not be nested (inside subcircuit '{}')",
+                outer.name
+            )));
+        }
+        let name = cursor.expect(&TokenKind::Ident, "a subcircuit name")?.name();
+        let mut ports = Vec::new();
+        while !cursor.is_empty() && !cursor.at_assignment() && !cursor.at_params_marker() {
+            ports.push(node_name(cursor.expect_name("a port name")?));
+        }
+        if cursor.at_params_marker() {
+            cursor.bump();
+            cursor.bump();
+        }
+
+        self.subckt = Some(Subcircuit { name, ports, scope: Scope::new(self.arena) });
+        // the default values of the subcircuit parameters
+        self.param(cursor)
+    }
+
+    fn ends(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let Some(subckt) = self.subckt.take() else {
+            return Err(cursor.error("'.ends' without matching '.subckt'"));
+        };
+        if !cursor.is_empty() {
+            let name = cursor.expect(&TokenKind::Ident, "a subcircuit name")?.name();
+            if name != subckt.name {
+                return Err(cursor
+                    .error(format!("expected '.ends {}' but found '.ends {name}'", subckt.name)));
+            }
+            cursor.expect_end()?;
+        }
+        self.subcircuits.push(SubcircuitDescription {
+            name: subckt.name,
+            ports: subckt.ports,
+            ctx: subckt.scope.ctx,
+            parameters: subckt.scope.parameters,
+            instances: subckt.scope.instances,
+            models: subckt.scope.models,
+        });
+        Ok(())
+    }
+
+    fn dc(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let mut sweeps = Vec::new();
+        while !cursor.is_empty() {
+            let source = cursor.expect(&TokenKind::Ident, "a source name")?.name();
+            let start = self.number(cursor)?;
+            let stop = self.number(cursor)?;
+            let step = self.number(cursor)?;
+            sweeps.push(DcSweep { source, start, stop, step });
+        }
+        if sweeps.is_empty() {
+            return Err(cursor.error("expected a source name"));
+        }
+        self.analyses.push(Analysis::Dc { sweeps });
+        Ok(())
+    }
+
+    fn freq_sweep(&mut self, cursor: &mut Cursor) -> Result<FrequencySweep> {
+        let pos = cursor.pos();
+        let kind = cursor.expect(&TokenKind::Ident, "'dec', 'oct' or 'lin'")?.name();
+        let points_pos = cursor.pos();
+        let points = self.number(cursor)?;
+        if points < 1.0 || points.fract() != 0.0 {
+            return Err(cursor.error_at(points_pos, "expected a positive integer"));
+        }
+        let points = points as u32;
+        let start = self.number(cursor)?;
+        let stop = self.number(cursor)?;
+        let sweep = match &*kind {
+            "dec" => FrequencySweep::Dec { points, start, stop },
+            "oct" => FrequencySweep::Oct { points, start, stop },
+            "lin" => FrequencySweep::Lin { points, start, stop },
+            _ => {
+                return Err(cursor
+                    .error_at(pos, format!("expected 'dec', 'oct' or 'lin' but found {kind}")))
+            }
+        };
+        Ok(sweep)
+    }
+
+    fn tran(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let tstep = self.number(cursor)?;
+        let tstop = self.number(cursor)?;
+        let mut tstart = 0.0;
+        let mut tmax = None;
+        if !cursor.is_empty() && !cursor.at_keyword("uic") {
+            tstart = self.number(cursor)?;
+            if !cursor.is_empty() && !cursor.at_keyword("uic") {
+                tmax = Some(self.number(cursor)?);
+            }
+        }
+        let uic = cursor.at_keyword("uic");
+        if uic {
+            cursor.bump();
+        }
+        cursor.expect_end()?;
+        self.analyses.push(Analysis::Tran { tstep, tstop, tstart, tmax, uic });
+        Ok(())
+    }
+
+    fn noise(&mut self, cursor: &mut Cursor) -> Result<()> {
+        if !cursor.at_keyword("v") {
+            return Err(cursor.error("expected an output voltage 'v(<node>[,<ref>])'"));
+        }
+        cursor.bump();
+        cursor.expect(&TokenKind::LParen, "'('")?;
+        let out = node_name(cursor.expect_name("a node name")?);
+        let reference = if cursor.eat(&TokenKind::Comma) {
+            node_name(cursor.expect_name("a node name")?)
+        } else {
+            "ground".to_owned()
+        };
+        cursor.expect(&TokenKind::RParen, "')'")?;
+        let source = cursor.expect(&TokenKind::Ident, "an input source")?.name();
+        let sweep = self.freq_sweep(cursor)?;
+        // points per summary are not relevant for melange
+        if !cursor.is_empty() {
+            self.number(cursor)?;
+        }
+        cursor.expect_end()?;
+        self.analyses.push(Analysis::Noise { output: (out, reference), source, sweep });
+        Ok(())
+    }
+
+    fn nodes(&mut self, cursor: &mut Cursor, n: usize) -> Result<Vec<String>> {
+        (0..n).map(|_| cursor.expect_name("a node name").map(node_name)).collect()
+    }
+
+    /// Parses an element line, the first letter of the name determines the element type.
+    fn element(&mut self, cursor: &mut Cursor) -> Result<()> {
+        let name_tok = cursor.expect(&TokenKind::Ident, "an element name")?;
+        let name = name_tok.name();
+        let (master, terminal_connections, parameters) = match name.as_bytes()[0] {
+            b'r' => self.two_terminal(cursor, "resistor", "r")?,
+            b'c' => self.two_terminal(cursor, "capacitor", "c")?,
+            b'l' => self.two_terminal(cursor, "inductor", "l")?,
+            b'v' => self.source(cursor, "vsource")?,
+            b'i' => self.source(cursor, "isource")?,
+            b'e' | b'g' => {
+                let nodes = self.nodes(cursor, 4)?;
+                let gain = self.value(cursor)?;
+                let mut parameters = vec![("gain".to_owned(), gain)];
+                parameters.extend(self.param_assignments(cursor)?);
+                let master = if name.starts_with('e') { "vcvs" } else { "vccs" };
+                (master.to_owned(), nodes, parameters)
+            }
+            b'f' | b'h' => {
+                let nodes = self.nodes(cursor, 2)?;
+                let ctrl = cursor.expect(&TokenKind::Ident, "a voltage source")?.name();
+                let ctrl = Expr::str(self.arena, &ctrl);
+                let gain = self.value(cursor)?;
+                let mut parameters = vec![("ctrl".to_owned(), ctrl), ("gain".to_owned(), gain)];
+                parameters.extend(self.param_assignments(cursor)?);
+                let master = if name.starts_with('f') { "cccs" } else { "ccvs" };
+                (master.to_owned(), nodes, parameters)
+            }
+            // osdi devices and subcircuits: `<name> <node>* <model> <param>=<value>*`
+            b'n' | b'x' => {
+                let mut names = Vec::new();
+                while !cursor.is_empty() && !cursor.at_assignment() && !cursor.at_params_marker() {
+                    names.push(cursor.expect_name("a node name")?);
+                }
+                if cursor.at_params_marker() {
+                    cursor.bump();
+                    cursor.bump();
+                }
+                let Some(master) = names.pop() else {
+                    return Err(cursor.error("expected a model name"));
+                };
+                let nodes = names.into_iter().map(node_name).collect();
+                (master.name(), nodes, self.param_assignments(cursor)?)
+            }
+            _ => {
+                return Err(cursor
+                    .error_at(name_tok.pos, format!("unsupported element '{}'", name_tok.text)))
+            }
+        };
+
+        self.scope().instances.push(CircuitInstanceDescription {
+            name,
+            master,
+            parameters,
+            terminal_connections,
+        });
+        Ok(())
+    }
+
+    /// `<name> <node> <node> [<value>] <param>=<value>*`
+    fn two_terminal(&mut self, cursor: &mut Cursor, master: &str, param: &str) -> Result<Element> {
+        let nodes = self.nodes(cursor, 2)?;
+        let mut parameters = Vec::new();
+        if !cursor.is_empty() && !cursor.at_assignment() {
+            parameters.push((param.to_owned(), self.value(cursor)?));
+        }
+        parameters.extend(self.param_assignments(cursor)?);
+        Ok((master.to_owned(), nodes, parameters))
+    }
+
+    /// `<name> <n+> <n-> [[dc] <value>] [ac [<mag> [<phase>]]] [<waveform>]`
+    fn source(&mut self, cursor: &mut Cursor, master: &str) -> Result<Element> {
+        let nodes = self.nodes(cursor, 2)?;
+        let mut parameters = Vec::new();
+        while !cursor.is_empty() && !cursor.at_assignment() {
+            if cursor.at_keyword("dc") {
+                cursor.bump();
+                parameters.push(("dc".to_owned(), self.value(cursor)?));
+            } else if cursor.at_keyword("ac") {
+                cursor.bump();
+                let mag = if self.at_value(cursor) { self.value(cursor)? } else { 1.0.into() };
+                parameters.push(("mag".to_owned(), mag));
+                if self.at_value(cursor) {
+                    parameters.push(("phase".to_owned(), self.value(cursor)?));
+                }
+            } else if let Some(waveform) =
+                WAVEFORMS.into_iter().find(|waveform| cursor.at_keyword(waveform))
+            {
+                self.waveform(cursor, waveform, &mut parameters)?;
+            } else {
+                parameters.push(("dc".to_owned(), self.value(cursor)?));
+            }
+        }
+        parameters.extend(self.param_assignments(cursor)?);
+        Ok((master.to_owned(), nodes, parameters))
+    }
+
+    /// `<waveform> [(] <value>* [)]`, the positional values are mapped to the parameters of
+    /// the melange sources.
+    fn waveform(
+        &mut self,
+        cursor: &mut Cursor,
+        waveform: &str,
+        parameters: &mut Vec<(String, Expr)>,
+    ) -> Result<()> {
+        let (ty, names): (_, &[&str]) = match waveform {
+            "pulse" => ("pulse", &["val0", "val1", "delay", "rise", "fall", "width", "period"]),
+            "sin" => ("sine", &["sinedc", "ampl", "freq", "delay", "damp", "sinephase"]),
+            "exp" => ("exp", &["val0", "val1", "td1", "tau1", "td2", "tau2"]),
+            "sffm" => ("sine", &["sinedc", "ampl", "freq", "fmmodindex", "fmmodfreq"]),
+            "pwl" => ("pwl", &[]),
+            _ => {
+                return Err(cursor.error(format!("'{waveform}' source waveforms are not supported")))
+            }
+        };
+        cursor.bump();
+        let parens = cursor.eat(&TokenKind::LParen);
+        let mut points = Vec::new();
+        let mut names = names.iter();
+        loop {
+            let at_end = if parens {
+                cursor.is_empty() || cursor.at(&TokenKind::RParen)
+            } else {
+                !self.at_value(cursor)
+            };
+            if at_end {
+                break;
+            }
+            if ty == "pwl" {
+                // the points are passed to the source as a single string
+                points.push(self.number(cursor)?.to_string());
+            } else {
+                let Some(&name) = names.next() else {
+                    return Err(cursor.error(format!("too many values for a {waveform} waveform")));
+                };
+                parameters.push((name.to_owned(), self.value(cursor)?));
+            }
+            cursor.eat(&TokenKind::Comma);
+        }
+        if parens {
+            cursor.expect(&TokenKind::RParen, "')'")?;
+        }
+        if ty == "pwl" {
+            parameters.push(("wave".to_owned(), Expr::str(self.arena, &points.join(" "))));
+        }
+        parameters.push(("type".to_owned(), Expr::str(self.arena, ty)));
+        Ok(())
+    }
+
+    /// Whether the next token starts a value (and not a keyword or an assignment)
+    fn at_value(&self, cursor: &Cursor) -> bool {
+        match cursor.peek() {
+            Some(tok) if tok.kind == TokenKind::Ident => {
+                !cursor.at_assignment() && self.lookup_ident(&tok.name()).is_some()
+            }
+            Some(tok) => matches!(
+                tok.kind,
+                TokenKind::Number(_)
+                    | TokenKind::LBrace
+                    | TokenKind::Quote
+                    | TokenKind::LParen
+                    | TokenKind::Minus
+                    | TokenKind::Plus
+            ),
+            None => false,
+        }
+    }
+}
+
+/// Node `0` and `gnd` are the ground node
+fn node_name(tok: &Token) -> String {
+    let name = tok.name();
+    if name == "0" || name == "gnd" {
+        "ground".to_owned()
+    } else {
+        name
+    }
+}
+
+/// Resolves paths relative to the directory of the file that references them.
+fn resolve_path(file: &Utf8Path, path: &str) -> Utf8PathBuf {
+    match file.parent() {
+        Some(dir) => dir.join(path),
+        None => Utf8PathBuf::from(path),
+    }
+}
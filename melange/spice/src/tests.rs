@@ -0,0 +1,248 @@
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use melange_core::{Arena, CircuitParam, Expr, ExprEvalCtx, Value};
+
+use crate::{parse_file, parse_str, Analysis, DcSweep, FrequencySweep};
+
+/// Evaluates parameter defaults in declaration order
+fn eval_params(arena: &Arena, params: &[(CircuitParam, Expr)]) -> Vec<f64> {
+    let mut ctx = ExprEvalCtx::new(arena);
+    params
+        .iter()
+        .map(|&(param, val)| {
+            let val = val.eval_num(ctx.borrow()).unwrap();
+            ctx.set_param(param, val.into());
+            val
+        })
+        .collect()
+}
+
+#[test]
+fn elements() {
+    let src = r#"diode test circuit
+.param VDD=1.2 rval={2 * vdd * 1k}
+.osdi diode.osdi
+.model D1 diode_va (is=1e-14, n=1.05)
+V1 in 0 DC 1 AC 1 0
+R1 in out {rval}
+C1 out gnd 10pF ; load capacitance
+Lser out x 1u
+E1 a 0 in out 2
+F1 b 0 v1 0.5
+N1 x 0 d1 area=2
+I1 0 a 1mA
++ ac 2
+.op
+.end
+R2 not parsed
+"#;
+    let mut arena = Arena::new();
+    let deck = parse_str(src, Utf8Path::new("netlists/test.sp"), &mut arena).unwrap();
+    assert_eq!(deck.title, "diode test circuit");
+    let desc = &deck.circuit;
+    assert_eq!(desc.va_files, vec![Utf8PathBuf::from("netlists/diode.osdi")]);
+    assert_eq!(eval_params(&arena, &desc.parameters), vec![1.2, 2400.0]);
+
+    let model = &desc.models.raw[0];
+    assert_eq!((&*model.name, &*model.device), ("d1", "diode_va"));
+    assert_eq!(model.parameters.len(), 2);
+
+    let instances: Vec<_> = desc
+        .instances
+        .iter()
+        .map(|inst| {
+            let params: Vec<_> = inst.parameters.iter().map(|(name, _)| &**name).collect();
+            (&*inst.name, &*inst.master, inst.terminal_connections.join(" "), params)
+        })
+        .collect();
+    assert_eq!(
+        instances,
+        vec![
+            ("v1", "vsource", "in ground".to_owned(), vec!["dc", "mag", "phase"]),
+            ("r1", "resistor", "in out".to_owned(), vec!["r"]),
+            ("c1", "capacitor", "out ground".to_owned(), vec!["c"]),
+            ("lser", "inductor", "out x".to_owned(), vec!["l"]),
+            ("e1", "vcvs", "a ground in out".to_owned(), vec!["gain"]),
+            ("f1", "cccs", "b ground".to_owned(), vec!["ctrl", "gain"]),
+            ("n1", "d1", "x ground".to_owned(), vec!["area"]),
+            ("i1", "isource", "ground a".to_owned(), vec!["dc", "mag"]),
+        ]
+    );
+    let Expr::Value(Value::Num(cap)) = desc.instances.raw[2].parameters[0].1 else {
+        unreachable!()
+    };
+    assert!((cap - 1e-11).abs() < 1e-24);
+    assert_eq!(deck.analyses, vec![Analysis::Op]);
+}
+
+#[test]
+fn waveforms() {
+    let src = "waveforms
+V1 a 0 PULSE(0 1.2 1n 1n 2n 5n 10n) AC 1
+V2 b 0 DC 0.5 SIN(0.5, 0.1, 1MEG)
+I1 0 c EXP 0 1m 1n 2n
+V3 d 0 PWL(0 0 1n 1 2n {0.5})
+V4 e 0 SFFM(0 1 10k 5 1k)
+";
+    let mut arena = Arena::new();
+    let deck = parse_str(src, Utf8Path::new("test.sp"), &mut arena).unwrap();
+    let mut ctx = ExprEvalCtx::new(&arena);
+    let instances: Vec<_> = deck
+        .circuit
+        .instances
+        .iter()
+        .map(|inst| {
+            let params: Vec<_> = inst
+                .parameters
+                .iter()
+                .map(|&(ref name, val)| match val.eval(ctx.borrow()).unwrap() {
+                    Value::Num(val) => format!("{name}={val}"),
+                    _ => format!("{name}={}", val.eval_str(ctx.borrow()).unwrap()),
+                })
+                .collect();
+            (&*inst.name, params.join(" "))
+        })
+        .collect();
+    assert_eq!(
+        instances,
+        vec![
+            (
+                "v1",
+                "val0=0 val1=1.2 delay=0.000000001 rise=0.000000001 fall=0.000000002 \
+                 width=0.000000005 period=0.00000001 type=pulse mag=1"
+                    .to_owned()
+            ),
+            ("v2", "dc=0.5 sinedc=0.5 ampl=0.1 freq=1000000 type=sine".to_owned()),
+            ("i1", "val0=0 val1=0.001 td1=0.000000001 tau1=0.000000002 type=exp".to_owned()),
+            ("v3", "wave=0 0 0.000000001 1 0.000000002 0.5 type=pwl".to_owned()),
+            ("v4", "sinedc=0 ampl=1 freq=10000 fmmodindex=5 fmmodfreq=1000 type=sine".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn analyses() {
+    let src = "analyses
+.dc v1 -1 1 0.1 vgs 0 1.2 0.3
+.ac dec 10 1 1k
+.tran 1n 1u 0 10n uic
+.noise v(out, ref) v1 lin 3 1 3
+";
+    let mut arena = Arena::new();
+    let deck = parse_str(src, Utf8Path::new("test.sp"), &mut arena).unwrap();
+    assert_eq!(
+        deck.analyses,
+        vec![
+            Analysis::Dc {
+                sweeps: vec![
+                    DcSweep { source: "v1".to_owned(), start: -1.0, stop: 1.0, step: 0.1 },
+                    DcSweep { source: "vgs".to_owned(), start: 0.0, stop: 1.2, step: 0.3 },
+                ]
+            },
+            Analysis::Ac { sweep: FrequencySweep::Dec { points: 10, start: 1.0, stop: 1e3 } },
+            Analysis::Tran { tstep: 1e-9, tstop: 1e-6, tstart: 0.0, tmax: Some(1e-8), uic: true },
+            Analysis::Noise {
+                output: ("out".to_owned(), "ref".to_owned()),
+                source: "v1".to_owned(),
+                sweep: FrequencySweep::Lin { points: 3, start: 1.0, stop: 3.0 }
+            },
+        ]
+    );
+    let Analysis::Ac { sweep } = &deck.analyses[1] else { unreachable!() };
+    let freqs = sweep.frequencies();
+    assert_eq!(freqs.len(), 31);
+    assert!((freqs[30] - 1e3).abs() < 1e-9);
+    let Analysis::Noise { sweep, .. } = &deck.analyses[3] else { unreachable!() };
+    assert_eq!(sweep.frequencies(), vec![1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn subcircuits() {
+    let src = "subcircuits
+.subckt DIVIDER in out params: ratio=0.5 r=1k
+R1 in out {r*(1-ratio)}
+R2 out 0 {r*ratio}
+.ends divider
+X1 a b divider ratio=0.25
+.global VDD 0
+";
+    let mut arena = Arena::new();
+    let deck = parse_str(src, Utf8Path::new("test.sp"), &mut arena).unwrap();
+    let desc = deck.circuit;
+    let subckt = &desc.subcircuits[0];
+    assert_eq!(subckt.name, "divider");
+    assert_eq!(subckt.ports, vec!["in".to_owned(), "out".to_owned()]);
+    assert_eq!(eval_params(&arena, &subckt.parameters), vec![0.5, 1e3]);
+    assert_eq!(subckt.instances.len(), 2);
+    let inst = &desc.instances.raw[0];
+    assert_eq!(inst.master, "divider");
+    assert_eq!(inst.terminal_connections, vec!["a".to_owned(), "b".to_owned()]);
+    assert_eq!(desc.global_nodes, vec!["vdd".to_owned(), "ground".to_owned()]);
+}
+
+#[test]
+fn include_lib() {
+    let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+        .unwrap()
+        .join(format!("melange_spice_include_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("models.lib"),
+        ".lib tt\n.param vth=0.4\n.endl tt\n.lib ff\n.param vth=0.3\n.endl ff\n",
+    )
+    .unwrap();
+    fs::write(dir.join("params.inc"), ".param vdd=1\n").unwrap();
+    fs::write(
+        dir.join("top.sp"),
+        "top\n.lib 'models.lib' FF\n.include params.inc\n.param v=vdd-vth\n.control\npre_osdi bsim.osdi\n.endc\n",
+    )
+    .unwrap();
+
+    let mut arena = Arena::new();
+    let deck = parse_file(&dir.join("top.sp"), &mut arena).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(eval_params(&arena, &deck.circuit.parameters), vec![0.3, 1.0, 0.7]);
+    assert_eq!(deck.circuit.va_files, vec![dir.join("bsim.osdi")]);
+}
+
+#[test]
+fn pwl_file() {
+    let src = "pwl file\nV1 in 0 file=\"wave.txt\"\nV2 out 0 file=\"/abs/wave.txt\"\n";
+    let mut arena = Arena::new();
+    let deck = parse_str(src, Utf8Path::new("netlists/top.sp"), &mut arena).unwrap();
+    let mut ctx = ExprEvalCtx::new(&arena);
+    let files: Vec<_> = deck
+        .circuit
+        .instances
+        .iter()
+        .map(|inst| {
+            let (name, file) = &inst.parameters[0];
+            assert_eq!(name, "file");
+            file.eval_str(ctx.borrow()).unwrap().to_owned()
+        })
+        .collect();
+    assert_eq!(files, ["netlists/wave.txt", "/abs/wave.txt"]);
+}
+
+#[test]
+fn errors() {
+    let check = |src: &str, expected: &str| {
+        let mut arena = Arena::new();
+        let err = parse_str(src, Utf8Path::new("test.sp"), &mut arena).err().unwrap();
+        assert_eq!(err.to_string(), expected);
+    };
+    check("title\n.param a=b+1\n", "test.sp:2:10: unknown parameter 'b'");
+    check("title\nR1 a\n+ b {1+\n", "test.sp:3:8: expected an expression but found end of line");
+    check("title\nQ1 c b e npn\n", "test.sp:2:1: unsupported element 'Q1'");
+    check(
+        "title\nV1 a 0 AM(1 0 1k 100 1n)\n",
+        "test.sp:2:8: 'am' source waveforms are not supported",
+    );
+    check(
+        "title\nV1 a 0 SIN(0 1 1k 0 0 0 1)\n",
+        "test.sp:2:25: too many values for a sin waveform",
+    );
+    check("title\n.foo\n", "test.sp:2:1: unknown control card '.foo'");
+    check("title\n.subckt foo a b\n", "subcircuit 'foo' is missing '.ends'");
+}
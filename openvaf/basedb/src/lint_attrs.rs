@@ -3,7 +3,7 @@ use std::sync::Arc;
 use ahash::AHashMap;
 pub use diagnostics::AttrDiagnostic;
 use syntax::ast::{self, AstToken, AttrIter, LiteralKind};
-use syntax::{AstNode, TextRange};
+use syntax::{AstNode, NodeOrToken, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize};
 use vfs::FileId;
 
 use crate::lints::{Lint, LintLevel, LintRegistry, LintSrc};
@@ -29,19 +29,35 @@ impl LintAttrTree {
 
         let mut res = LintAttrTree { overwrites: AHashMap::new(), diagnostics: Vec::new() };
 
+        // comments are only inspected if the file contains a lint directive at all
+        let has_lint_comments = cst
+            .descendants_with_tokens()
+            .filter_map(NodeOrToken::into_token)
+            .any(|token| lint_directive(&token).is_some());
+
         for (id, entry) in map.entries() {
             // quick reject to avoid looking at the ast when not necessary
             let has_attr = entry
                 .attrs
                 .iter()
                 .any(|attr| matches!(&**attr, "openvaf_allow" | "openvaf_warn" | "openvaf_deny"));
-            if has_attr {
+            let has_duplicate_attr =
+                entry.attrs.iter().enumerate().any(|(i, attr)| entry.attrs[..i].contains(attr));
+            if has_attr || has_duplicate_attr || has_lint_comments {
                 let cst = entry.syntax.to_node(cst);
                 if ast::Var::can_cast(cst.kind()) || ast::Param::can_cast(cst.kind()) {
                     continue;
                 }
-                let overwrites =
-                    resolve_overwrites(&registry, ast::attrs(&cst), &mut res.diagnostics, id);
+                if has_duplicate_attr {
+                    check_duplicate_attrs(ast::attrs(&cst), &mut res.diagnostics, id);
+                }
+                let overwrites = resolve_overwrites(
+                    &registry,
+                    ast::attrs(&cst),
+                    lint_comments(&cst),
+                    &mut res.diagnostics,
+                    id,
+                );
                 res.overwrites.extend(overwrites.map(|(lint, lvl)| ((id, lint), lvl)));
             }
         }
@@ -59,13 +75,110 @@ impl LintAttrTree {
     }
 }
 
+/// Lint levels can also be overwritten with a comment directly before an item or statement:
+///
+/// ```verilog
+/// // openvaf:allow(rounding_derivative, useless_function_call)
+/// x = floor(V(a));
+/// ```
+///
+/// This returns the level and the (trimmed) text of the comment after the directive
+/// prefix if `token` is such a comment.
+fn lint_directive(token: &SyntaxToken) -> Option<(LintLevel, &str)> {
+    if token.kind() != SyntaxKind::COMMENT {
+        return None;
+    }
+    let directive = token.text().strip_prefix("//")?.trim_start().strip_prefix("openvaf:")?;
+    let (lvl, args) = if let Some(args) = directive.strip_prefix("allow") {
+        (LintLevel::Allow, args)
+    } else if let Some(args) = directive.strip_prefix("warn") {
+        (LintLevel::Warn, args)
+    } else if let Some(args) = directive.strip_prefix("deny") {
+        (LintLevel::Deny, args)
+    } else {
+        return None;
+    };
+    Some((lvl, args.trim_end()))
+}
+
+/// Returns the comments on the lines directly before `node` (that are not separated by
+/// code or an empty line).
+pub fn lint_comments(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    let mut token = node.first_token().and_then(|token| token.prev_token());
+    std::iter::from_fn(move || loop {
+        let curr = token.take()?;
+        match curr.kind() {
+            SyntaxKind::WHITESPACE if curr.text().matches('\n').count() > 1 => return None,
+            SyntaxKind::WHITESPACE => token = curr.prev_token(),
+            SyntaxKind::COMMENT => {
+                token = curr.prev_token();
+                // only consider comments placed on their own line
+                let own_line = match &token {
+                    Some(prev) => {
+                        prev.kind() == SyntaxKind::WHITESPACE && prev.text().contains('\n')
+                    }
+                    None => true,
+                };
+                if own_line {
+                    return Some(curr);
+                }
+            }
+            _ => return None,
+        }
+    })
+}
+
+fn check_duplicate_attrs(attrs: AttrIter, err: &mut Vec<AttrDiagnostic>, src: ErasedAstId) {
+    // attrs are iterated in reverse, walk them in source order so that the later value (which is
+    // the one that is used) overwrites the earlier one
+    let attrs: Vec<_> = attrs.collect();
+    let mut seen: AHashMap<String, TextRange> = AHashMap::new();
+    for attr in attrs.into_iter().rev() {
+        if let Some(name) = attr.name() {
+            let range = attr.syntax().text_range();
+            if let Some(old) = seen.insert(name.text().to_owned(), range) {
+                err.push(AttrDiagnostic::AttributeOverwritten {
+                    old,
+                    new: range,
+                    name: name.text().to_owned(),
+                    src,
+                })
+            }
+        }
+    }
+}
+
 pub fn resolve_overwrites(
     registry: &LintRegistry,
     attrs: AttrIter,
+    comments: impl Iterator<Item = SyntaxToken>,
     err: &mut Vec<AttrDiagnostic>,
     src: ErasedAstId,
 ) -> impl Iterator<Item = (Lint, LintLevel)> {
     fn insert_lint(
+        lint_name: String,
+        range: TextRange,
+        err: &mut Vec<AttrDiagnostic>,
+        registry: &LintRegistry,
+        overwrites: &mut AHashMap<Lint, (LintLevel, TextRange)>,
+        lvl: LintLevel,
+        src: ErasedAstId,
+    ) {
+        let lint = if let Some(lint) = registry.lint_from_name(&lint_name) {
+            lint
+        } else {
+            if !lint_name.contains("::") {
+                // Plugins use plugin::lint_name. Plugin lints for unused plugins are fine
+                err.push(AttrDiagnostic::UnknownLint { range, lint: lint_name, src });
+            }
+            return;
+        };
+        if let Some((_, old)) = overwrites.insert(lint, (lvl, range)) {
+            err.push(AttrDiagnostic::LintOverwrite { old, new: range, name: lint_name, src })
+        }
+    }
+
+    fn insert_lit(
         lit: ast::Literal,
         err: &mut Vec<AttrDiagnostic>,
         registry: &LintRegistry,
@@ -77,23 +190,7 @@ pub fn resolve_overwrites(
             LiteralKind::String(lit) => {
                 let lint_name = lit.unescaped_value();
                 let range = lit.syntax().text_range();
-                let lint = if let Some(lint) = registry.lint_from_name(&lint_name) {
-                    lint
-                } else {
-                    if !lint_name.contains("::") {
-                        // Plugins use plugin::lint_name. Plugin lints for unused plugins are fine
-                        err.push(AttrDiagnostic::UnknownLint { range, lint: lint_name, src });
-                    }
-                    return;
-                };
-                if let Some((_, old)) = overwrites.insert(lint, (lvl, range)) {
-                    err.push(AttrDiagnostic::LintOverwrite {
-                        old,
-                        new: range,
-                        name: lint_name,
-                        src,
-                    })
-                }
+                insert_lint(lint_name, range, err, registry, overwrites, lvl, src)
             }
 
             _ => err.push(AttrDiagnostic::ExpectedLiteral {
@@ -102,7 +199,41 @@ pub fn resolve_overwrites(
             }),
         }
     }
+
     let mut overwrites = AHashMap::new();
+
+    // comments come first so that attributes (which are closer to the item) take precedence
+    let comments: Vec<_> = comments.collect();
+    for comment in comments.iter().rev() {
+        let (lvl, args) = match lint_directive(comment) {
+            Some(directive) => directive,
+            None => continue,
+        };
+        let start = comment.text_range().start() + TextSize::of(comment.text().trim_end())
+            - TextSize::of(args);
+        let lints = match args.strip_prefix('(').and_then(|args| args.strip_suffix(')')) {
+            Some(lints) => lints,
+            None => {
+                err.push(AttrDiagnostic::ExpectedLintList {
+                    range: comment.text_range(),
+                    directive: lvl.directive(),
+                });
+                continue;
+            }
+        };
+
+        let mut offset = start + TextSize::of('(');
+        for lint in lints.split(',') {
+            let name = lint.trim();
+            if !name.is_empty() {
+                let leading = TextSize::of(lint) - TextSize::of(lint.trim_start());
+                let range = TextRange::at(offset + leading, TextSize::of(name));
+                insert_lint(name.to_owned(), range, err, registry, &mut overwrites, lvl, src);
+            }
+            offset += TextSize::of(lint) + TextSize::of(',');
+        }
+    }
+
     for attr in attrs {
         let lvl = match attr.name() {
             Some(name) if name.text() == "openvaf_allow" => LintLevel::Allow,
@@ -113,13 +244,13 @@ pub fn resolve_overwrites(
 
         match attr.val() {
             Some(ast::Expr::Literal(lit)) if matches!(lit.kind(), LiteralKind::String(_)) => {
-                insert_lint(lit, err, registry, &mut overwrites, lvl, src)
+                insert_lit(lit, err, registry, &mut overwrites, lvl, src)
             }
 
             Some(ast::Expr::ArrayExpr(e)) => {
                 for expr in e.exprs() {
                     if let ast::Expr::Literal(lit) = expr {
-                        insert_lint(lit, err, registry, &mut overwrites, lvl, src)
+                        insert_lit(lit, err, registry, &mut overwrites, lvl, src)
                     } else {
                         err.push(AttrDiagnostic::ExpectedLiteral {
                             range: expr.syntax().text_range(),
@@ -153,13 +284,16 @@ impl LintAttrs {
         LintAttrs { parent, overwrites: AHashMap::new() }
     }
 
+    /// Resolves the lint attributes and lint comments of `node`
     pub fn resolve(
         registry: &LintRegistry,
-        attrs: AttrIter,
+        node: &SyntaxNode,
         err: &mut Vec<AttrDiagnostic>,
         parent: ErasedAstId,
     ) -> LintAttrs {
-        let overwrites = resolve_overwrites(registry, attrs, err, parent);
+        check_duplicate_attrs(ast::attrs(node), err, parent);
+        let overwrites =
+            resolve_overwrites(registry, ast::attrs(node), lint_comments(node), err, parent);
         LintAttrs { parent, overwrites: overwrites.collect() }
     }
 
@@ -3,7 +3,7 @@ use syntax::sourcemap::FileSpan;
 use syntax::TextRange;
 
 use crate::diagnostics::{text_ranges_to_unified_spans, Diagnostic, Label, LabelStyle, Report};
-use crate::lints::builtin::{lint_level_overwrite, lint_not_found};
+use crate::lints::builtin::{attribute_overwritten, lint_level_overwrite, lint_not_found};
 use crate::lints::{Lint, LintSrc};
 use crate::{BaseDB, FileId};
 
@@ -13,6 +13,8 @@ pub enum AttrDiagnostic {
     ExpectedLiteral { range: TextRange, attr: &'static str },
     UnknownLint { range: TextRange, lint: String, src: ErasedAstId },
     LintOverwrite { old: TextRange, new: TextRange, name: String, src: ErasedAstId },
+    ExpectedLintList { range: TextRange, directive: &'static str },
+    AttributeOverwritten { old: TextRange, new: TextRange, name: String, src: ErasedAstId },
 }
 
 use AttrDiagnostic::*;
@@ -25,6 +27,8 @@ impl_display! {
         ExpectedLiteral{attr,..} => "'{}' attribute expects a string literal here", attr;
         UnknownLint{lint,..} => "unknown lint '{}'",lint;
         LintOverwrite{name,..} => "lint level for '{}' was set multiple times",name;
+        ExpectedLintList{directive,..} => "'{}' comment expects a list of lints", directive;
+        AttributeOverwritten{name,..} => "attribute '{}' was set multiple times",name;
     }
 }
 
@@ -33,6 +37,7 @@ impl Diagnostic for AttrDiagnostic {
         match *self {
             UnknownLint { src, .. } => Some((lint_not_found, src.into())),
             LintOverwrite { src, .. } => Some((lint_level_overwrite, src.into())),
+            AttributeOverwritten { src, .. } => Some((attribute_overwritten, src.into())),
             _ => None,
         }
     }
@@ -87,6 +92,39 @@ impl Diagnostic for AttrDiagnostic {
                             .to_owned(),
                     ])
             }
+            ExpectedLintList { range, directive } => {
+                let FileSpan { file: file_id, range } = parse.to_file_span(range, &sm);
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id,
+                        range: range.into(),
+                        message: "expected a list of lints".to_owned(),
+                    }])
+                    .with_notes(vec![format!("help: a valid example is // {directive}(foo, bar)")])
+            }
+            AttributeOverwritten { old, new, .. } => {
+                let (file_id, [new, old]) = text_ranges_to_unified_spans(&sm, &parse, [new, old]);
+                Report::warning()
+                    .with_labels(vec![
+                        Label {
+                            style: LabelStyle::Primary,
+                            file_id,
+                            range: old.into(),
+                            message: "attribute is overwritten".to_owned(),
+                        },
+                        Label {
+                            style: LabelStyle::Secondary,
+                            file_id,
+                            range: new.into(),
+                            message: "this value is used instead".to_owned(),
+                        },
+                    ])
+                    .with_notes(vec![
+                        "help: only the last value is used; the earlier attribute has no effect"
+                            .to_owned(),
+                    ])
+            }
             UnknownLint { range, .. } => {
                 let FileSpan { file: file_id, range } = parse.to_file_span(range, &sm);
                 Report::error()
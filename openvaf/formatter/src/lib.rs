@@ -0,0 +1,263 @@
+//! A source code formatter for VerilogA.
+//!
+//! The formatter operates on the lossless syntax tree of a single (unpreprocessed) file. It
+//! normalizes indentation and the spacing between tokens but never changes the token
+//! sequence itself. Line breaks are kept as written (at most one empty line is retained) so
+//! that the structure chosen by the author of a model, like the alignment of long
+//! expressions across multiple lines, is preserved. Comments, attributes and compiler
+//! directives are kept verbatim.
+
+use std::fmt::{self, Display};
+
+use syntax::{Direction, NodeOrToken, SourceFile, SyntaxKind, SyntaxNode, SyntaxToken, TextSize};
+
+use SyntaxKind::*;
+
+const INDENT: &str = "    ";
+/// Two consecutive line breaks produce a single empty line.
+const MAX_NEWLINES: usize = 2;
+
+/// A file can only be formatted if it does not contain syntax errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatError {
+    /// The (1-based) line of the first syntax error.
+    pub line: usize,
+    /// The (1-based) column of the first syntax error.
+    pub column: usize,
+}
+
+impl FormatError {
+    fn at(text: &str, pos: TextSize) -> FormatError {
+        let prefix = &text[..usize::from(pos)];
+        let line_start = prefix.rfind('\n').map_or(0, |i| i + 1);
+        FormatError {
+            line: prefix.matches('\n').count() + 1,
+            column: prefix[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "syntax error at {}:{}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Formats the VerilogA source `text`.
+/// Returns an error if the file contains a syntax error.
+pub fn format(text: &str) -> Result<String, FormatError> {
+    let (root, errors) = SourceFile::parse_unpreprocessed(text);
+    if let Some(err) = errors.first() {
+        return Err(FormatError::at(text, err.start()));
+    }
+
+    let tokens: Vec<_> =
+        root.descendants_with_tokens().filter_map(NodeOrToken::into_token).collect();
+    let mut dst = String::with_capacity(text.len());
+    let mut newlines = 0;
+    let mut prev: Option<&SyntaxToken> = None;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind() == WHITESPACE {
+            newlines += token.text().matches('\n').count();
+            continue;
+        }
+
+        match prev {
+            Some(prev) if newlines == 0 => {
+                if space_between(prev, token) {
+                    dst.push(' ')
+                }
+            }
+            _ => {
+                if prev.is_some() {
+                    dst.extend(std::iter::repeat_n('\n', newlines.min(MAX_NEWLINES)));
+                }
+                for _ in 0..indent_level(&tokens[i..]) {
+                    dst.push_str(INDENT)
+                }
+            }
+        }
+
+        if token.kind() == COMMENT {
+            dst.push_str(token.text().trim_end())
+        } else {
+            dst.push_str(token.text())
+        }
+        newlines = 0;
+        prev = Some(token);
+    }
+
+    if !dst.is_empty() {
+        dst.push('\n')
+    }
+    Ok(dst)
+}
+
+fn indent_level(tokens: &[SyntaxToken]) -> usize {
+    let token = &tokens[0];
+    if token.kind() != COMMENT {
+        return token_indent(token);
+    }
+
+    // comments (and directives) are indented like the code that follows them
+    match tokens.iter().find(|token| !token.kind().is_trivia()) {
+        Some(next) if is_closing(next.kind()) => token_indent(next) + 1,
+        Some(next) => token_indent(next),
+        None => 0,
+    }
+}
+
+fn token_indent(token: &SyntaxToken) -> usize {
+    let mut ancestors = token.parent_ancestors();
+    let mut child = match ancestors.next() {
+        Some(parent) => parent,
+        None => return 0,
+    };
+    let mut level = 0;
+    for node in ancestors {
+        if indents(&node, &child) {
+            level += 1;
+        }
+        child = node;
+    }
+    if is_continuation(token) {
+        level += 1;
+    }
+    level
+}
+
+/// Whether the body `child` of `parent` is indented.
+fn indents(parent: &SyntaxNode, child: &SyntaxNode) -> bool {
+    let kind = child.kind();
+    match parent.kind() {
+        MODULE_DECL => is_module_item(kind),
+        DISCIPLINE_DECL => kind == DISCIPLINE_ATTR,
+        NATURE_DECL => kind == NATURE_ATTR,
+        FUNCTION => matches!(kind, PARAM_DECL | VAR_DECL | FUNCTION_ARG) || is_stmt(kind),
+        BLOCK_STMT => matches!(kind, PARAM_DECL | VAR_DECL) || is_stmt(kind),
+        CASE_STMT => kind == CASE,
+
+        // statements (that are not blocks) are indented if they are placed on their own line
+        IF_STMT => is_stmt(kind) && kind != BLOCK_STMT && !is_else_if(child),
+        // the init and incr statements are part of the header
+        FOR_STMT => is_stmt(kind) && kind != BLOCK_STMT && child.next_sibling().is_none(),
+        WHILE_STMT | EVENT_STMT | ANALOG_BEHAVIOUR | CASE => is_stmt(kind) && kind != BLOCK_STMT,
+        _ => false,
+    }
+}
+
+fn is_else_if(stmt: &SyntaxNode) -> bool {
+    stmt.kind() == IF_STMT
+        && stmt
+            .siblings_with_tokens(Direction::Prev)
+            .skip(1)
+            .find(|element| !element.kind().is_trivia())
+            .is_some_and(|element| element.kind() == ELSE_KW)
+}
+
+/// Tokens that start a new line in the middle of a statement or declaration
+/// are indented one additional level.
+fn is_continuation(token: &SyntaxToken) -> bool {
+    if token.kind() == ELSE_KW || is_closing(token.kind()) {
+        return false;
+    }
+
+    let unit = match token.parent_ancestors().find(|node| is_unit(node.kind())) {
+        Some(unit) => unit,
+        None => return false,
+    };
+
+    // attributes are commonly placed on their own line before the item they belong to
+    for element in unit.children_with_tokens() {
+        match element {
+            NodeOrToken::Token(element) if element.kind().is_trivia() => (),
+            NodeOrToken::Node(attrs) if attrs.kind() == ATTR_LIST => {
+                if attrs.text_range().contains_range(token.text_range()) {
+                    return attrs.first_token().as_ref() != Some(token);
+                }
+            }
+            NodeOrToken::Node(node) => return node.first_token().as_ref() != Some(token),
+            NodeOrToken::Token(element) => return &element != token,
+        }
+    }
+    false
+}
+
+fn space_between(prev: &SyntaxToken, next: &SyntaxToken) -> bool {
+    // escaped identifiers are terminated by whitespace
+    if prev.kind() == IDENT && prev.text().starts_with('\\') {
+        return true;
+    }
+
+    let prev_parent = prev.parent().map(|node| node.kind());
+    let next_parent = next.parent().map(|node| node.kind());
+    match (prev.kind(), next.kind()) {
+        (_, COMMENT) => true,
+        (L_PAREN | L_BRACK | ARR_START | DOT | AT, _)
+        | (_, R_PAREN | R_BRACK | R_CURLY | COMMA | SEMICOLON | DOT)
+        // calls and module ports
+        | (IDENT | SYSFUN | ROOT_KW | INITIAL_STEP_KW | FINAL_STEP_KW, L_PAREN | L_BRACK) => false,
+        (MINUS | PLUS | BANG | TILDE, _) if prev_parent == Some(PREFIX_EXPR) => false,
+        (L_ANGLE, _) if prev_parent == Some(PORT_FLOW) => false,
+        (_, R_ANGLE) if next_parent == Some(PORT_FLOW) => false,
+        (EQ, _) if prev_parent == Some(ATTR) => false,
+        (_, EQ) if next_parent == Some(ATTR) => false,
+        (COLON, _) => prev_parent != Some(RANGE),
+        (_, COLON) => !matches!(next_parent, Some(RANGE | CASE)),
+        _ => true,
+    }
+}
+
+fn is_closing(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        END_KW | ENDCASE_KW | ENDMODULE_KW | ENDFUNCTION_KW | ENDDISCIPLINE_KW | ENDNATURE_KW
+    )
+}
+
+fn is_stmt(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        EMPTY_STMT
+            | ASSIGN_STMT
+            | EXPR_STMT
+            | IF_STMT
+            | WHILE_STMT
+            | FOR_STMT
+            | CASE_STMT
+            | EVENT_STMT
+            | BLOCK_STMT
+    )
+}
+
+fn is_module_item(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        BODY_PORT_DECL
+            | NET_DECL
+            | ANALOG_BEHAVIOUR
+            | FUNCTION
+            | BRANCH_DECL
+            | VAR_DECL
+            | PARAM_DECL
+            | ALIAS_PARAM
+    )
+}
+
+/// Statements and declarations whose lines are indented together.
+fn is_unit(kind: SyntaxKind) -> bool {
+    is_stmt(kind)
+        || is_module_item(kind)
+        || matches!(
+            kind,
+            MODULE_DECL
+                | DISCIPLINE_DECL
+                | DISCIPLINE_ATTR
+                | NATURE_DECL
+                | NATURE_ATTR
+                | FUNCTION_ARG
+                | CASE
+        )
+}
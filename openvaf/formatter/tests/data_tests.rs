@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::Path;
+
+use expect_test::expect_file;
+use mini_harness::{harness, Result};
+use stdx::{ignore_dev_tests, ignore_never, is_va_file, openvaf_test_data, project_root};
+use tokens::lexer::TokenKind;
+
+/// The formatter must only change whitespace and produce a stable result.
+fn check_formatted(src: &str, formatted: &str) -> Result {
+    if significant_tokens(src) != significant_tokens(formatted) {
+        return Err("formatting changed the token sequence".into());
+    }
+    if formatter::format(formatted)? != formatted {
+        return Err("formatting is not idempotent".into());
+    }
+    Ok(())
+}
+
+fn significant_tokens(src: &str) -> Vec<&str> {
+    let mut pos = 0;
+    let mut res = Vec::new();
+    for token in lexer::tokenize(src) {
+        let len: usize = token.len.into();
+        if token.kind != TokenKind::Whitespace {
+            res.push(src[pos..pos + len].trim_end());
+        }
+        pos += len;
+    }
+    res
+}
+
+fn fmt_test(file: &Path) -> Result {
+    let src = fs::read_to_string(file)?;
+    let actual = formatter::format(&src)?;
+    expect_file![file.with_extension("va_fmt")].assert_eq(&actual);
+    check_formatted(&src, &actual)
+}
+
+fn integration_test(dir: &Path) -> Result {
+    let name = dir.file_name().unwrap().to_str().unwrap().to_lowercase();
+    let src = fs::read_to_string(dir.join(format!("{name}.va")))?;
+    let formatted = formatter::format(&src)?;
+    check_formatted(&src, &formatted)
+}
+
+harness! {
+    Test::from_dir_filtered("integration", &integration_test, &Path::is_dir, &ignore_dev_tests, &project_root().join("integration_tests")),
+    Test::from_dir_filtered("fmt", &fmt_test, &is_va_file, &ignore_never, &openvaf_test_data("fmt"))
+}
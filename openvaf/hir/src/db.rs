@@ -11,7 +11,7 @@ use basedb::{BaseDB, BaseDatabase, FileId, Vfs, VfsPath, VfsStorage, STANDARD_FL
 use hir_def::db::{HirDefDB, HirDefDatabase, InternDatabase};
 use hir_ty::db::HirTyDatabase;
 use parking_lot::RwLock;
-use salsa::ParallelDatabase;
+use salsa::{Database, Durability, ParallelDatabase};
 use stdx::Upcast;
 use typed_index_collections::TiSlice;
 
@@ -69,6 +69,27 @@ impl CompilationDB {
         CompilationUnit { root_file: self.root_file }
     }
 
+    /// Rereads the given files from the filesystem. If any file changed a new
+    /// revision is started so that all queries depending on its contents are recomputed
+    /// the next time they are requested.
+    ///
+    /// Returns whether any file changed.
+    pub fn reload_files(&mut self, files: &[FileId]) -> bool {
+        let mut changed = false;
+        {
+            let mut vfs = self.vfs.write();
+            for &file in files {
+                if let Some(path) = vfs.file_path(file).as_path() {
+                    changed |= vfs.set_file_contents(file, fs::read(path).into());
+                }
+            }
+        }
+        if changed {
+            self.salsa_runtime_mut().synthetic_write(Durability::LOW);
+        }
+        changed
+    }
+
     pub fn new<'a>(
         root_file: VfsPath,
         contents: Result<Vec<u8>, io::Error>,
@@ -19,6 +19,7 @@ use crate::{CompilationDB, HirDatabase};
 pub(crate) fn collect(db: &CompilationDB, root_file: FileId, sink: &mut impl DiagnosticSink) {
     sink.add_diagnostics(&*db.preprocess(root_file).diagnostics, root_file, db);
     sink.add_diagnostics(db.parse(root_file).errors(), root_file, db);
+    sink.add_diagnostics(&*db.lint_attr_tree(root_file).diagnostics, root_file, db);
 
     let def_map = db.def_map(root_file);
     let ast_id_map = db.ast_id_map(root_file);
@@ -127,6 +128,8 @@ fn collect_body_diagnostcs(
     ast_id_map: &AstIdMap,
 ) {
     let body_sm = db.body_source_map(def);
+    dst.add_diagnostics(&*body_sm.diagnostics, root_file, db.upcast());
+
     let diagnostics = &db.inference_result(def).diagnostics;
     for diag in diagnostics {
         let diag = InferenceDiagnosticWrapped { body_sm: &body_sm, diag, parse, db, sm };
@@ -118,6 +118,11 @@ impl CompilationUnit {
     pub fn preprocess(&self, db: &CompilationDB) -> syntax::Preprocess {
         db.preprocess(self.root_file)
     }
+
+    /// The root file and all files it includes.
+    pub fn source_files(&self, db: &CompilationDB) -> Vec<FileId> {
+        db.sourcemap(self.root_file).files()
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -183,6 +188,17 @@ impl Module {
         let scope = self.id.lookup(db).scope;
         scope.resolve_item_path(db, path).map(|id| Variable { id })
     }
+
+    /// Resolves `path` to a parameter declared in this module.
+    /// Returns an error if `path` does not exist or does not refer to a parameter.
+    pub fn lookup_param(
+        &self,
+        db: &CompilationDB,
+        path: &Path,
+    ) -> Result<Parameter, PathResolveError> {
+        let scope = self.id.lookup(db).scope;
+        scope.resolve_item_path(db, path).map(|id| Parameter { id })
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
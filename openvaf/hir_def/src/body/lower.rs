@@ -2,9 +2,9 @@ use std::mem;
 
 use basedb::lints::LintRegistry;
 use basedb::{AstIdMap, ErasedAstId, LintAttrs};
-use syntax::ast::{self, ArgListOwner, AttrIter, AttrsOwner, FunctionRef};
+use syntax::ast::{self, ArgListOwner, FunctionRef};
 use syntax::name::AsName;
-use syntax::AstPtr;
+use syntax::{AstNode, AstPtr, SyntaxNode};
 
 // use tracing::debug;
 use super::{Body, BodySourceMap};
@@ -148,7 +148,7 @@ impl LowerCtx<'_> {
             ast::Stmt::EventStmt(stmt) => return self.collect_event_stmt(stmt),
             ast::Stmt::BlockStmt(stmt) => self.collect_block(stmt),
         };
-        self.alloc_stmt(s, AstPtr::new(&stmt), stmt.attrs())
+        self.alloc_stmt(s, AstPtr::new(&stmt), stmt.syntax())
     }
 
     fn collect_event_stmt(&mut self, event_stmt: &ast::EventStmt) -> StmtId {
@@ -164,7 +164,7 @@ impl LowerCtx<'_> {
         let event = Event::Global { kind, phases };
         let stmt = Stmt::EventControl { event, body: self.collect_opt_stmt(event_stmt.stmt()) };
 
-        self.alloc_stmt(stmt, AstPtr::new(event_stmt).cast().unwrap(), event_stmt.attrs())
+        self.alloc_stmt(stmt, AstPtr::new(event_stmt).cast().unwrap(), event_stmt.syntax())
     }
 
     fn collect_case_stmt(&mut self, case_stmt: &ast::CaseStmt) -> Stmt {
@@ -234,13 +234,14 @@ impl LowerCtx<'_> {
         id
     }
 
-    fn alloc_stmt(&mut self, stmt: Stmt, ptr: AstPtr<ast::Stmt>, attrs: AttrIter) -> StmtId {
-        let attrs = LintAttrs::resolve(
-            self.registry,
-            attrs,
-            &mut self.source_map.diagnostics,
-            self.curr_scope.1,
-        );
+    fn alloc_stmt(&mut self, stmt: Stmt, ptr: AstPtr<ast::Stmt>, syntax: &SyntaxNode) -> StmtId {
+        let mut diagnostics = Vec::new();
+        let attrs = LintAttrs::resolve(self.registry, syntax, &mut diagnostics, self.curr_scope.1);
+        // block statements are part of the ast id map, errors in their lint attributes
+        // are already reported by the `LintAttrTree`
+        if !ast::BlockStmt::can_cast(syntax.kind()) {
+            self.source_map.diagnostics.append(&mut diagnostics);
+        }
         let id = self.make_stmt(stmt, Some(ptr.clone()), attrs);
         self.source_map.stmt_map.insert(ptr, id);
 
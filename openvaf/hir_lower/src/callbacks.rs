@@ -170,6 +170,17 @@ impl CallBackKind {
     pub fn tracked(&self) -> bool {
         !matches!(self, CallBackKind::Print { .. })
     }
+
+    /// Replaces every interned string `name` with `f(name)`.
+    pub fn remap_literals(&mut self, f: impl FnOnce(Spur) -> Spur) {
+        match self {
+            CallBackKind::BuiltinLimit { name, .. }
+            | CallBackKind::WhiteNoise { name, .. }
+            | CallBackKind::FlickerNoise { name, .. } => *name = f(*name),
+            CallBackKind::NoiseTable(table) => table.name = f(table.name),
+            _ => (),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -1,4 +1,5 @@
 use std::iter::FilterMap;
+use std::mem;
 
 use ahash::{AHashMap, AHashSet};
 use bitset::HybridBitSet;
@@ -6,7 +7,7 @@ use hir::{
     Branch, BranchWrite, CompilationDB, Module, Node, ParamSysFun, Parameter, Type, Variable,
 };
 use indexmap::IndexMap;
-use lasso::Rodeo;
+use lasso::{Rodeo, Spur};
 use mir::builder::InstBuilder;
 use mir::{DataFlowGraph, FuncRef, Function, Inst, KnownDerivatives, Param, Unknown, Value};
 use mir_build::{FunctionBuilder, FunctionBuilderContext, RetBuilder};
@@ -395,6 +396,20 @@ impl HirInterner {
             }
         })
     }
+
+    /// Replaces every interned string `name` referenced by `func` or this interner with
+    /// `f(name)`. `f` must be injective. This allows moving a function that was lowered
+    /// with one string interner to another.
+    pub fn remap_literals(&mut self, func: &mut Function, mut f: impl FnMut(Spur) -> Spur) {
+        func.dfg.remap_str_consts(&mut f);
+        self.callbacks.raw = mem::take(&mut self.callbacks.raw)
+            .into_iter()
+            .map(|mut callback| {
+                callback.remap_literals(&mut f);
+                callback
+            })
+            .collect();
+    }
 }
 
 pub struct MirBuilder<'a> {
@@ -1,5 +1,8 @@
 use basedb::diagnostics::{Diagnostic, Label, LabelStyle, Report};
-use basedb::lints::builtin::{const_simparam, trivial_probe, variant_const_simparam};
+use basedb::lints::builtin::{
+    const_simparam, constant_overflow, infinite_loop, noise_derivative, rounding_derivative,
+    standard_nature_constants, trivial_probe, useless_function_call, variant_const_simparam,
+};
 use basedb::lints::{self, Lint, LintSrc};
 use basedb::{AstIdMap, BaseDB, FileId};
 pub use body::BodyValidationDiagnostic;
@@ -130,6 +133,25 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                 let src = self.body_sm.lint_src(stmt, trivial_probe);
                 Some((trivial_probe, src))
             }
+            BodyValidationDiagnostic::InfiniteLoop { stmt, .. } => {
+                Some((infinite_loop, self.body_sm.lint_src(stmt, infinite_loop)))
+            }
+            BodyValidationDiagnostic::ConstantOverflow { stmt, .. } => {
+                Some((constant_overflow, self.body_sm.lint_src(stmt, constant_overflow)))
+            }
+            BodyValidationDiagnostic::UselessFunctionCall { stmt, .. } => {
+                Some((useless_function_call, self.body_sm.lint_src(stmt, useless_function_call)))
+            }
+            BodyValidationDiagnostic::RoundingDerivative { stmt, .. } => {
+                Some((rounding_derivative, self.body_sm.lint_src(stmt, rounding_derivative)))
+            }
+            BodyValidationDiagnostic::NoiseDerivative { stmt, .. } => {
+                Some((noise_derivative, self.body_sm.lint_src(stmt, noise_derivative)))
+            }
+            BodyValidationDiagnostic::StandardNatureConstant { stmt, .. } => Some((
+                standard_nature_constants,
+                self.body_sm.lint_src(stmt, standard_nature_constants),
+            )),
             _ => None,
         }
     }
@@ -502,6 +524,107 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
 
                 res
             }
+            BodyValidationDiagnostic::InfiniteLoop { cond, .. } => {
+                let FileSpan { range, file } = self.expr_src(cond);
+                Report::error()
+                    .with_message("loop condition is always true".to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "always true".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: Verilog-A has no break statement, this loop never terminates"
+                            .to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::ConstantOverflow { expr, literal, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                let (message, label) = if literal {
+                    ("integer literal is out of range", "does not fit into a 32-bit integer")
+                } else {
+                    ("integer overflow in constant expression", "overflows a 32-bit integer")
+                };
+                Report::error()
+                    .with_message(message.to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: label.to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: use a real literal (for example 1e10) for large values".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::UselessFunctionCall { expr, func, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message(format!("result of call to '{func:?}' is unused"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "result is discarded".to_owned(),
+                    }])
+                    .with_notes(vec![format!(
+                        "help: '{func:?}' has no side effects, this statement does nothing"
+                    )])
+            }
+            BodyValidationDiagnostic::RoundingDerivative { expr, func, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message(format!("derivative of '{func:?}' is always zero"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "argument depends on a probe".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: the result is treated as a constant when the jacobian is computed\nthis can cause convergence problems".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::NoiseDerivative { expr, operator, operator_fun, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                let operator = self.expr_src(operator);
+                Report::warning()
+                    .with_message(format!("noise source inside '{operator_fun:?}' is ignored"))
+                    .with_labels(vec![
+                        Label {
+                            style: LabelStyle::Primary,
+                            file_id: file,
+                            range: range.into(),
+                            message: "noise source".to_owned(),
+                        },
+                        Label {
+                            style: LabelStyle::Secondary,
+                            file_id: operator.file,
+                            range: operator.range.into(),
+                            message: format!("noise is not propagated through '{operator_fun:?}'"),
+                        },
+                    ])
+                    .with_notes(vec![
+                        "help: the derivative (and integral) of a noise source is always zero"
+                            .to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::StandardNatureConstant { expr, constant, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message(format!("literal approximates the physical constant `{constant}"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: format!("help: use `{constant} instead"),
+                    }])
+                    .with_notes(vec![
+                        "help: `include \"constants.vams\" to use the standard physical constants"
+                            .to_owned(),
+                    ])
+            }
         }
     }
 
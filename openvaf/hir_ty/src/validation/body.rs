@@ -7,8 +7,9 @@ use hir_def::{
     NatureId, NodeId, ParamId, Path, Stmt, StmtId, VarId,
 };
 use stdx::impl_display;
-use syntax::ast::AssignOp;
+use syntax::ast::{self, AssignOp, BinaryOp, UnaryOp};
 use syntax::name::{AsIdent, Name};
+use syntax::AstNode;
 
 use crate::builtin::{
     ABSDELAY_MAX, DDT_TOL, IDT_IC_ASSERT_TOL, NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES,
@@ -16,7 +17,7 @@ use crate::builtin::{
     TRANSITION_DELAY_RISET_FALLT_TOL,
 };
 use crate::db::HirTyDB;
-use crate::inference::{BranchWrite, InferenceResult, ResolvedFun};
+use crate::inference::{AssignDst, BranchWrite, InferenceResult, ResolvedFun};
 use crate::lower::BranchKind;
 use crate::types::{Signature, Ty};
 
@@ -96,6 +97,83 @@ pub enum BodyValidationDiagnostic {
         node1: NodeId,
         node2: NodeId,
     },
+
+    InfiniteLoop {
+        cond: ExprId,
+        stmt: StmtId,
+    },
+
+    ConstantOverflow {
+        expr: ExprId,
+        literal: bool,
+        stmt: StmtId,
+    },
+
+    UselessFunctionCall {
+        expr: ExprId,
+        func: BuiltIn,
+        stmt: StmtId,
+    },
+
+    RoundingDerivative {
+        expr: ExprId,
+        func: BuiltIn,
+        stmt: StmtId,
+    },
+
+    NoiseDerivative {
+        expr: ExprId,
+        operator: ExprId,
+        operator_fun: BuiltIn,
+        stmt: StmtId,
+    },
+
+    StandardNatureConstant {
+        expr: ExprId,
+        constant: &'static str,
+        stmt: StmtId,
+    },
+}
+
+/// The physical constants defined in `constants.vams` (including all historical revisions).
+const STANDARD_NATURE_CONSTANTS: [(&str, &[f64]); 5] = [
+    ("P_Q", &[1.60219e-19, 1.6021918e-19, 1.602176462e-19, 1.602176565e-19]),
+    ("P_C", &[2.99792458e8]),
+    ("P_K", &[1.38062e-23, 1.3806226e-23, 1.3806503e-23, 1.3806488e-23]),
+    ("P_H", &[6.62620e-34, 6.6260755e-34, 6.62606876e-34, 6.62606957e-34]),
+    ("P_EPS0", &[8.854214871e-12, 8.85418792394420013968e-12, 8.854187817e-12]),
+];
+
+/// Literals that deviate less than this (relative) tolerance from a physical constant are
+/// assumed to be hand-written approximations of that constant
+const STANDARD_NATURE_CONSTANT_TOL: f64 = 1e-4;
+
+fn standard_nature_constant(val: f64) -> Option<&'static str> {
+    STANDARD_NATURE_CONSTANTS.iter().find_map(|(name, vals)| {
+        vals.iter()
+            .any(|known| ((val - known) / known).abs() < STANDARD_NATURE_CONSTANT_TOL)
+            .then_some(*name)
+    })
+}
+
+/// Builtin functions without side effects whose result is meaningless if discarded
+fn is_pure(call: BuiltIn) -> bool {
+    !matches!(
+        call,
+        BuiltIn::display
+            | BuiltIn::strobe
+            | BuiltIn::write
+            | BuiltIn::monitor
+            | BuiltIn::debug
+            | BuiltIn::finish
+            | BuiltIn::stop
+            | BuiltIn::fatal
+            | BuiltIn::warning
+            | BuiltIn::error
+            | BuiltIn::info
+            | BuiltIn::discontinuity
+            | BuiltIn::bound_step
+    ) && !call.is_unsupported()
 }
 
 impl BodyValidationDiagnostic {
@@ -120,8 +198,15 @@ impl BodyValidationDiagnostic {
             non_const_dominator: Box::default(),
             non_trivial_branches: HashSet::default(),
             trivial_probes: HashMap::default(),
+            probe_dependent_vars: HashSet::default(),
+            in_condition: false,
+            diff_operator: None,
         };
 
+        if ctx == BodyCtx::AnalogBlock {
+            validator.probe_dependent_vars = validator.probe_dependent_vars();
+        }
+
         for stmt in &*body.entry_stmts {
             validator.validate_stmt(*stmt)
         }
@@ -195,9 +280,140 @@ struct BodyValidator<'a> {
     non_const_dominator: Box<[ExprId]>,
     non_trivial_branches: HashSet<BranchWrite>,
     trivial_probes: HashMap<BranchWrite, Vec<(StmtId, ExprId)>>,
+    /// Variables whose value (may) depend on a probe
+    probe_dependent_vars: HashSet<VarId>,
+    /// Whether the condition of a statement or select expression is currently validated
+    in_condition: bool,
+    /// The innermost analog operator that differentiates/integrates its arguments
+    /// which encloses the current expression
+    diff_operator: Option<(BuiltIn, ExprId)>,
 }
 
 impl BodyValidator<'_> {
+    /// Finds all variables that are (directly or indirectly) assigned a value
+    /// that depends on a probe
+    fn probe_dependent_vars(&self) -> HashSet<VarId> {
+        let assignments: Vec<_> = self
+            .body
+            .stmts
+            .iter_enumerated()
+            .filter_map(|(stmt, data)| match *data {
+                Stmt::Assignment { val, assignment_kind: AssignOp::Assign, .. } => {
+                    match self.infer.assignment_destination.get(&stmt) {
+                        Some(AssignDst::Var(var)) => Some((*var, val)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut res = HashSet::default();
+        loop {
+            let mut changed = false;
+            for (var, val) in &assignments {
+                if !res.contains(var) && self.depends_on_probe(*val, &res) {
+                    res.insert(*var);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return res;
+            }
+        }
+    }
+
+    fn depends_on_probe(&self, expr: ExprId, probe_dependent_vars: &HashSet<VarId>) -> bool {
+        match self.body.exprs[expr] {
+            Expr::Call { .. } => {
+                // only branch and port probes depend on the operating point,
+                // system functions like $temperature are constant during a simulation
+                if let Some(ResolvedFun::BuiltIn(BuiltIn::potential | BuiltIn::flow)) =
+                    self.infer.resolved_calls.get(&expr)
+                {
+                    return true;
+                }
+            }
+            Expr::Path { port: false, .. } => {
+                return matches!(self.infer.expr_types[expr], Ty::Var(_, var) if probe_dependent_vars.contains(&var))
+            }
+            _ => (),
+        }
+
+        let mut res = false;
+        self.body.exprs[expr].walk_child_exprs(|child| {
+            res = res || self.depends_on_probe(child, probe_dependent_vars)
+        });
+        res
+    }
+
+    /// Evaluates integer expressions that only consist of literals
+    /// Returns `None` if the expression is not constant or overflows
+    fn const_int(&self, expr: ExprId) -> Option<i32> {
+        match self.body.exprs[expr] {
+            Expr::Literal(Literal::Int(val)) => Some(val),
+            Expr::UnaryOp { expr, op: UnaryOp::Identity } => self.const_int(expr),
+            Expr::UnaryOp { expr, op: UnaryOp::Neg } => self.const_int(expr)?.checked_neg(),
+            Expr::BinaryOp { lhs, rhs, op: Some(op) } => {
+                let (lhs, rhs) = (self.const_int(lhs)?, self.const_int(rhs)?);
+                match op {
+                    BinaryOp::Addition => lhs.checked_add(rhs),
+                    BinaryOp::Subtraction => lhs.checked_sub(rhs),
+                    BinaryOp::Multiplication => lhs.checked_mul(rhs),
+                    BinaryOp::Power => lhs.checked_pow(rhs.try_into().ok()?),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `expr` is an arithmetic operation on constant integers that overflows
+    fn const_int_overflows(&self, expr: ExprId) -> bool {
+        if let Expr::BinaryOp { lhs, rhs, op: Some(op) } = self.body.exprs[expr] {
+            if let (Some(lhs), Some(rhs)) = (self.const_int(lhs), self.const_int(rhs)) {
+                return match op {
+                    BinaryOp::Addition => lhs.checked_add(rhs).is_none(),
+                    BinaryOp::Subtraction => lhs.checked_sub(rhs).is_none(),
+                    BinaryOp::Multiplication => lhs.checked_mul(rhs).is_none(),
+                    BinaryOp::Power => rhs >= 0 && lhs.checked_pow(rhs as u32).is_none(),
+                    _ => false,
+                };
+            }
+        }
+        false
+    }
+
+    /// Whether `expr` was written in a macro body. Macros are named constants so literals
+    /// inside them are not considered magic numbers.
+    fn is_macro_expansion(&self, expr: ExprId) -> bool {
+        let body_sm = self.db.body_source_map(self.owner);
+        let root_file = self.owner.file(self.db.upcast());
+        let range = match &body_sm.expr_map_back[expr] {
+            Some(ptr) => ptr.range(),
+            None => return false,
+        };
+        let sm = self.db.sourcemap(root_file);
+        let ctx = self.db.parse(root_file).to_ctx_span(range, &sm).ctx;
+        sm.is_macro_expansion(ctx)
+    }
+
+    fn int_literal_overflows(&self, expr: ExprId) -> bool {
+        let body_sm = self.db.body_source_map(self.owner);
+        let parse = self.db.parse(self.owner.file(self.db.upcast()));
+        let lit = match &body_sm.expr_map_back[expr] {
+            Some(ptr) => ptr.to_node(parse.tree().syntax()),
+            None => return false,
+        };
+        match lit {
+            ast::Expr::Literal(lit) => match lit.kind() {
+                ast::LiteralKind::IntNumber(lit) => lit.try_value().is_none(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     fn validate_stmt(&mut self, stmt: StmtId) {
         let cond = match self.body.stmts[stmt] {
             Stmt::Assignment { dst, val, assignment_kind } => {
@@ -228,14 +444,36 @@ impl BodyValidator<'_> {
             Stmt::Missing | Stmt::Empty => return,
 
             Stmt::Expr(e) => {
+                // the bodies of parameters and variables are desugared into expression statements
+                let is_stmt = matches!(
+                    self.owner,
+                    DefWithBodyId::ModuleId { .. } | DefWithBodyId::FunctionId(_)
+                );
+                if let Some(ResolvedFun::BuiltIn(func)) = self.infer.resolved_calls.get(&e) {
+                    if is_stmt && is_pure(*func) {
+                        self.diagnostics.push(BodyValidationDiagnostic::UselessFunctionCall {
+                            expr: e,
+                            func: *func,
+                            stmt,
+                        })
+                    }
+                }
                 self.validate_expr(e, stmt);
                 return;
             }
 
-            Stmt::If { cond, .. }
-            | Stmt::ForLoop { cond, .. }
-            | Stmt::WhileLoop { cond, .. }
-            | Stmt::Case { discr: cond, .. } => cond,
+            Stmt::ForLoop { cond, .. } | Stmt::WhileLoop { cond, .. } => {
+                let always_true = match self.body.exprs[cond] {
+                    Expr::Literal(Literal::Float(val)) => f64::from(val) != 0.0,
+                    _ => self.const_int(cond).map_or(false, |val| val != 0),
+                };
+                if always_true {
+                    self.diagnostics.push(BodyValidationDiagnostic::InfiniteLoop { cond, stmt })
+                }
+                cond
+            }
+
+            Stmt::If { cond, .. } | Stmt::Case { discr: cond, .. } => cond,
         };
 
         self.validate_condition(cond, stmt, |s| {
@@ -249,6 +487,7 @@ impl BodyValidator<'_> {
         stmt: StmtId,
         f: impl FnOnce(&mut Self),
     ) -> Option<Box<[ExprId]>> {
+        let in_condition = replace(&mut self.in_condition, true);
         if self.ctx == BodyCtx::AnalogBlock || self.ctx == BodyCtx::Conditional {
             let mut non_const_access = Vec::new();
             ExprValidator {
@@ -258,6 +497,7 @@ impl BodyValidator<'_> {
                 stmt,
             }
             .validate_expr(cond);
+            self.in_condition = in_condition;
 
             if !non_const_access.is_empty() {
                 let non_const_dominator =
@@ -269,6 +509,7 @@ impl BodyValidator<'_> {
             }
         } else {
             self.validate_expr(cond, stmt);
+            self.in_condition = in_condition;
         }
 
         f(self);
@@ -533,6 +774,37 @@ impl ExprValidator<'_, '_> {
                 return;
             }
 
+            Expr::Literal(Literal::Int(i32::MAX)) => {
+                if self.parent.int_literal_overflows(expr) {
+                    self.report(BodyValidationDiagnostic::ConstantOverflow {
+                        expr,
+                        literal: true,
+                        stmt: self.stmt,
+                    })
+                }
+            }
+
+            Expr::Literal(Literal::Float(val)) => {
+                let constant = standard_nature_constant(val.into());
+                if let Some(constant) = constant.filter(|_| !self.parent.is_macro_expansion(expr)) {
+                    self.report(BodyValidationDiagnostic::StandardNatureConstant {
+                        expr,
+                        constant,
+                        stmt: self.stmt,
+                    })
+                }
+            }
+
+            Expr::BinaryOp { .. } => {
+                if self.parent.const_int_overflows(expr) {
+                    self.report(BodyValidationDiagnostic::ConstantOverflow {
+                        expr,
+                        literal: false,
+                        stmt: self.stmt,
+                    })
+                }
+            }
+
             _ => (),
         }
 
@@ -588,6 +860,35 @@ impl ExprValidator<'_, '_> {
             _ => (),
         }
 
+        match call {
+            BuiltIn::white_noise
+            | BuiltIn::flicker_noise
+            | BuiltIn::noise_table
+            | BuiltIn::noise_table_log => {
+                if let Some((operator_fun, operator)) = self.parent.diff_operator {
+                    self.report(BodyValidationDiagnostic::NoiseDerivative {
+                        expr,
+                        operator,
+                        operator_fun,
+                        stmt: self.stmt,
+                    })
+                }
+            }
+            BuiltIn::floor | BuiltIn::ceil
+                if !self.parent.in_condition
+                    && args.first().map_or(false, |arg| {
+                        self.parent.depends_on_probe(*arg, &self.parent.probe_dependent_vars)
+                    }) =>
+            {
+                self.report(BodyValidationDiagnostic::RoundingDerivative {
+                    expr,
+                    func: call,
+                    stmt: self.stmt,
+                })
+            }
+            _ => (),
+        }
+
         match (call, signature) {
             (BuiltIn::potential | BuiltIn::flow, Some(NATURE_ACCESS_NODES)) => {
                 let hi = self.parent.infer.expr_types[args[0]].unwrap_node();
@@ -748,9 +1049,16 @@ impl ExprValidator<'_, '_> {
             _ => (),
         }
 
+        let diff_operator =
+            if matches!(call, BuiltIn::ddt | BuiltIn::idt | BuiltIn::idtmod | BuiltIn::ddx) {
+                replace(&mut self.parent.diff_operator, Some((call, expr)))
+            } else {
+                self.parent.diff_operator
+            };
         for arg in args {
             self.validate_expr(*arg)
         }
+        self.parent.diff_operator = diff_operator;
     }
 
     fn validate_const_expr(&mut self, expr: ExprId) {
@@ -158,8 +158,10 @@ fn main() {
         "lto",
         "debuginfopdb",
         "windowsmanifest",
-        "libdriver", // "coverage",
-                     // "instrumentation",
+        "libdriver",
+        "orcjit",
+        // "coverage",
+        // "instrumentation",
     ];
 
     let components = output(Command::new(&llvm_config).arg("--components"));
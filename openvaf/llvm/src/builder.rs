@@ -39,6 +39,9 @@ extern "C" {
         then_bb: &'a BasicBlock,
         else_bb: &'a BasicBlock,
     ) -> &'a Value;
+    /// Marks the loop closed by `back_edge` as free of loop carried memory dependencies
+    /// (except for allocas) so that it can be vectorized, see `OpenVafWrapper.cpp`
+    pub fn LLVMMarkLoopParallel<'a>(fun: &'a Value, back_edge: &'a Value);
 
     pub fn LLVMBuildExtractValue<'a>(
         arg1: &Builder<'a>,
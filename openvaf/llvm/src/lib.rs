@@ -32,6 +32,7 @@ pub mod context;
 pub mod initialization;
 // pub mod lld;
 pub mod module;
+pub mod orc;
 pub mod pass_manager;
 pub mod support;
 pub mod targets;
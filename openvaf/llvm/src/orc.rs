@@ -0,0 +1,183 @@
+//! Bindings to the LLJIT of LLVM's ORC JIT. The JIT is used to load object files into the current
+//! process without invoking a system linker.
+
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::mem::MaybeUninit;
+use std::path::Path;
+use std::ptr::{self, NonNull};
+
+use libc::{c_char, c_void};
+
+use crate::support::LLVMString;
+use crate::MemoryBuffer;
+
+pub enum OrcLLJIT {}
+pub enum OrcLLJITBuilder {}
+pub enum OrcJITDylib {}
+pub enum OrcDefinitionGenerator {}
+pub enum OpaqueError {}
+
+pub type OrcExecutorAddress = u64;
+pub type OrcSymbolPredicate = Option<extern "C" fn(ctx: *mut c_void, sym: *mut c_void) -> i32>;
+
+extern "C" {
+    fn LLVMOrcCreateLLJIT(
+        result: &mut Option<NonNull<OrcLLJIT>>,
+        builder: Option<NonNull<OrcLLJITBuilder>>,
+    ) -> Option<NonNull<OpaqueError>>;
+    fn LLVMOrcDisposeLLJIT(jit: NonNull<OrcLLJIT>) -> Option<NonNull<OpaqueError>>;
+    fn LLVMOrcLLJITGetMainJITDylib(jit: NonNull<OrcLLJIT>) -> NonNull<OrcJITDylib>;
+    fn LLVMOrcLLJITGetGlobalPrefix(jit: NonNull<OrcLLJIT>) -> c_char;
+    fn LLVMOrcLLJITAddObjectFile(
+        jit: NonNull<OrcLLJIT>,
+        dylib: NonNull<OrcJITDylib>,
+        obj: &'static mut MemoryBuffer,
+    ) -> Option<NonNull<OpaqueError>>;
+    fn LLVMOrcLLJITLookup(
+        jit: NonNull<OrcLLJIT>,
+        result: &mut OrcExecutorAddress,
+        name: *const c_char,
+    ) -> Option<NonNull<OpaqueError>>;
+    fn LLVMOrcCreateDynamicLibrarySearchGeneratorForProcess(
+        result: &mut Option<NonNull<OrcDefinitionGenerator>>,
+        global_prefix: c_char,
+        filter: OrcSymbolPredicate,
+        filter_ctx: *mut c_void,
+    ) -> Option<NonNull<OpaqueError>>;
+    fn LLVMOrcJITDylibAddGenerator(
+        dylib: NonNull<OrcJITDylib>,
+        generator: NonNull<OrcDefinitionGenerator>,
+    );
+
+    fn LLVMGetErrorMessage(err: NonNull<OpaqueError>) -> *mut c_char;
+    fn LLVMDisposeErrorMessage(msg: *mut c_char);
+
+    fn LLVMCreateMemoryBufferWithContentsOfFile(
+        path: *const c_char,
+        out_mem_buf: &mut Option<&'static mut MemoryBuffer>,
+        out_message: *mut *mut c_char,
+    ) -> crate::Bool;
+}
+
+/// An error produced by the JIT.
+#[derive(Clone, PartialEq, Eq)]
+pub struct JitError(String);
+
+impl JitError {
+    /// # Safety
+    /// `err` must be a valid error returned by LLVM, the error is consumed
+    unsafe fn new(err: NonNull<OpaqueError>) -> JitError {
+        let msg = LLVMGetErrorMessage(err);
+        let res = CStr::from_ptr(msg).to_string_lossy().into_owned();
+        LLVMDisposeErrorMessage(msg);
+        JitError(res)
+    }
+}
+
+impl Debug for JitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for JitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for JitError {}
+
+impl From<LLVMString> for JitError {
+    fn from(err: LLVMString) -> JitError {
+        JitError(err.to_string())
+    }
+}
+
+fn check(err: Option<NonNull<OpaqueError>>) -> Result<(), JitError> {
+    match err {
+        Some(err) => Err(unsafe { JitError::new(err) }),
+        None => Ok(()),
+    }
+}
+
+/// A JIT for the host that links object files into the current process. Undefined symbols
+/// are resolved from the symbols of the current process (for example libm).
+///
+/// The code loaded into the JIT is freed when the JIT is dropped.
+pub struct Jit {
+    raw: NonNull<OrcLLJIT>,
+}
+
+// LLJIT is thread safe, all functions used here are internally synchronized
+unsafe impl Send for Jit {}
+unsafe impl Sync for Jit {}
+
+impl Jit {
+    pub fn new() -> Result<Jit, JitError> {
+        crate::initialization::require_inited();
+        unsafe {
+            let mut raw = None;
+            check(LLVMOrcCreateLLJIT(&mut raw, None))?;
+            let jit = Jit { raw: raw.unwrap() };
+
+            let mut generator = None;
+            let prefix = LLVMOrcLLJITGetGlobalPrefix(jit.raw);
+            check(LLVMOrcCreateDynamicLibrarySearchGeneratorForProcess(
+                &mut generator,
+                prefix,
+                None,
+                ptr::null_mut(),
+            ))?;
+            LLVMOrcJITDylibAddGenerator(LLVMOrcLLJITGetMainJITDylib(jit.raw), generator.unwrap());
+            Ok(jit)
+        }
+    }
+
+    /// Adds an object file (for example produced by
+    /// [`LLVMTargetMachineEmitToMemoryBuffer`](crate::LLVMTargetMachineEmitToMemoryBuffer)) to
+    /// the JIT. The symbols it defines become available to [`lookup`](Jit::lookup).
+    pub fn add_object(&self, obj: &'static mut MemoryBuffer) -> Result<(), JitError> {
+        unsafe {
+            let dylib = LLVMOrcLLJITGetMainJITDylib(self.raw);
+            check(LLVMOrcLLJITAddObjectFile(self.raw, dylib, obj))
+        }
+    }
+
+    /// Reads the object file at `path` and adds it to the JIT.
+    pub fn add_object_file(&self, path: &Path) -> Result<(), JitError> {
+        let path_ = path
+            .to_str()
+            .and_then(|path| CString::new(path).ok())
+            .ok_or_else(|| JitError(format!("invalid object file path {}", path.display())))?;
+        let mut buf = None;
+        let mut err = MaybeUninit::uninit();
+        let failed = unsafe {
+            LLVMCreateMemoryBufferWithContentsOfFile(path_.as_ptr(), &mut buf, err.as_mut_ptr())
+        };
+        if failed != 0 {
+            let err = unsafe { LLVMString::new(err.assume_init()) };
+            return Err(JitError(format!("failed to read {}: {err}", path.display())));
+        }
+        self.add_object(buf.unwrap())
+    }
+
+    /// Returns the address of the (unmangled) symbol `name`. The code that defines the symbol
+    /// is linked on the first lookup, unresolved symbols are reported at that point.
+    pub fn lookup(&self, name: &str) -> Result<*mut c_void, JitError> {
+        let name = CString::new(name).unwrap();
+        let mut addr = 0;
+        unsafe { check(LLVMOrcLLJITLookup(self.raw, &mut addr, name.as_ptr()))? };
+        Ok(addr as usize as *mut c_void)
+    }
+}
+
+impl Drop for Jit {
+    fn drop(&mut self) {
+        if let Err(err) = check(unsafe { LLVMOrcDisposeLLJIT(self.raw) }) {
+            eprintln!("error: failed to dispose JIT: {err}")
+        }
+    }
+}
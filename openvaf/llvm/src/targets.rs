@@ -6,8 +6,8 @@ use libc::{c_uint, c_ulonglong};
 
 use crate::support::LLVMString;
 use crate::{
-    Bool, CodeGenFileType, CodeModel, Module, OptLevel, RelocMode, Target, TargetData,
-    TargetMachine, Type,
+    Bool, CodeGenFileType, CodeModel, MemoryBuffer, Module, OptLevel, RelocMode, Target,
+    TargetData, TargetMachine, Type,
 };
 
 extern "C" {
@@ -34,13 +34,13 @@ extern "C" {
         codegen: CodeGenFileType,
         ErrorMessage: *mut *mut c_char,
     ) -> Bool;
-    // pub fn LLVMTargetMachineEmitToMemoryBuffer(
-    //     T: LLVMTargetMachineRef,
-    //     M: LLVMModuleRef,
-    //     codegen: LLVMCodeGenFileType,
-    //     ErrorMessage: *mut *mut ::libc::c_char,
-    //     OutMemBuf: *mut LLVMMemoryBufferRef,
-    // ) -> LLVMBool;
+    pub fn LLVMTargetMachineEmitToMemoryBuffer(
+        target: &TargetMachine,
+        module: &Module,
+        codegen: CodeGenFileType,
+        ErrorMessage: *mut *mut c_char,
+        OutMemBuf: &mut Option<&'static mut MemoryBuffer>,
+    ) -> Bool;
     pub fn LLVMGetHostCPUName() -> *const c_char;
     pub fn LLVMGetHostCPUFeatures() -> *const c_char;
 
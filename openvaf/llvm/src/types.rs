@@ -69,13 +69,13 @@ extern "C" {
     //pub fn LLVMIsLiteralStruct(struct_ty: &Type) -> Bool;
 
     //// Core->Types->Sequential
-    // pub fn LLVMGetElementType<'a>(ty: &'a Type) -> &'a Type;
+    pub fn LLVMGetElementType(ty: &Type) -> &Type;
     ///// Get the subtypes of the given type.
     //pub fn LLVMGetSubtypes<'a>(ty: &'a Type, arr: *mut &'a Type);
     ///// Return the number of types in the derived type.
     //pub fn LLVMGetNumContainedTypes<'a>(ty: &'a Type) -> c_uint;
     pub fn LLVMArrayType<'a>(elem: &'a Type, elem_cnt: c_uint) -> &'a Type;
-    // pub fn LLVMGetArrayLength(ArrayTy: &'a Type) -> c_uint;
+    pub fn LLVMGetArrayLength(arr_ty: &Type) -> c_uint;
     pub fn LLVMPointerType<'a>(elem: &'a Type, address_space: AddressSpace) -> &'a Type;
     // pub fn LLVMGetPointerAddressSpace(PointerTy: &'a Type) -> c_uint;
     // pub fn LLVMVectorType(ElementType: &'a Type, ElementCount: c_uint) -> &'a Type;
@@ -1,3 +1,5 @@
+#include "llvm/Analysis/ValueTracking.h"
+#include "llvm/IR/InstIterator.h"
 #include "llvm/IR/Instructions.h"
 #include "llvm/Support/CrashRecoveryContext.h"
 #include <llvm/IR/Attributes.h>
@@ -40,6 +42,33 @@ void LLVMPurgeAttrs(LLVMValueRef V) {
   }
 }
 
+// Mark the loop with the backedge `BackEdge` as parallel: All loads and stores in `Fn`
+// (except those that access an alloca) are placed in a single access group and the
+// loop is annotated with `llvm.loop.parallel_accesses`. This tells the loop vectorizer
+// that different iterations of the loop never access the same memory, so it does not
+// need to prove that itself (which it can't do for indirect accesses).
+//
+// https://llvm.org/docs/LangRef.html#llvm-loop-parallel-accesses-metadata
+void LLVMMarkLoopParallel(LLVMValueRef Fn, LLVMValueRef BackEdge) {
+  Function *F = unwrap<Function>(Fn);
+  LLVMContext &Ctx = F->getContext();
+  MDNode *AccessGroup = MDNode::getDistinct(Ctx, {});
+  for (Instruction &I : instructions(F)) {
+    Value *Ptr = getLoadStorePointerOperand(&I);
+    if (!Ptr || isa<AllocaInst>(getUnderlyingObject(Ptr))) {
+      continue;
+    }
+    I.setMetadata(LLVMContext::MD_access_group, AccessGroup);
+  }
+
+  Metadata *ParallelAccesses[] = {MDString::get(Ctx, "llvm.loop.parallel_accesses"),
+                                  AccessGroup};
+  Metadata *LoopProperties[] = {nullptr, MDNode::get(Ctx, ParallelAccesses)};
+  MDNode *LoopID = MDNode::getDistinct(Ctx, LoopProperties);
+  LoopID->replaceOperandWith(0, LoopID);
+  unwrap<Instruction>(BackEdge)->setMetadata(LLVMContext::MD_loop, LoopID);
+}
+
 void LLVMPassManagerBuilderSLPVectorize(LLVMPassManagerBuilderRef PMB) {
   PassManagerBuilder *Builder = unwrap(PMB);
   Builder->SLPVectorize = true;
@@ -227,6 +227,11 @@ impl DataFlowGraph {
         self.values.sconst(val)
     }
 
+    /// Replaces every string constant `val` with `f(val)`. `f` must be injective.
+    pub fn remap_str_consts(&mut self, f: impl FnMut(Spur) -> Spur) {
+        self.values.remap_str_consts(f)
+    }
+
     pub fn bconst(&mut self, val: bool) -> Value {
         if val {
             TRUE
@@ -123,3 +123,21 @@ fn phi() {
         &[(b1, v4)]
     );
 }
+
+#[test]
+fn remap_str_consts() {
+    let mut literals = lasso::Rodeo::new();
+    let foo = literals.get_or_intern("foo");
+    let bar = literals.get_or_intern("bar");
+    let baz = literals.get_or_intern("baz");
+
+    let mut dfg = DataFlowGraph::new();
+    let v_foo = dfg.sconst(foo);
+    let v_bar = dfg.sconst(bar);
+    dfg.remap_str_consts(|val| if val == foo { bar } else { baz });
+
+    assert_eq!(dfg.value_def(v_foo), ValueDef::Const(Const::Str(bar)));
+    assert_eq!(dfg.value_def(v_bar), ValueDef::Const(Const::Str(baz)));
+    assert_eq!(dfg.sconst(bar), v_foo);
+    assert_eq!(dfg.sconst(baz), v_bar);
+}
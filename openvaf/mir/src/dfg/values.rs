@@ -442,6 +442,17 @@ impl DfgValues {
         self.defs[dst].ty = ValueDataType::Sconst { val };
     }
 
+    /// Replaces every string constant `val` with `f(val)`. `f` must be injective.
+    /// Used to move a function to a different string interner.
+    pub fn remap_str_consts(&mut self, mut f: impl FnMut(Spur) -> Spur) {
+        for def in &mut self.defs {
+            if let ValueDataType::Sconst { val } = &mut def.ty {
+                *val = f(*val);
+            }
+        }
+        self.str_consts = self.str_consts.drain().map(|(val, dst)| (f(val), dst)).collect();
+    }
+
     #[inline]
     pub fn resolve_alias(&self, mut val: Value) -> Value {
         while let ValueDataType::Alias(res) = self.defs[val].ty {
@@ -152,7 +152,7 @@ pub struct FuncRef(u32);
 impl_idx_from!(FuncRef(u32));
 
 impl_debug_display! {
-    match FuncRef {FuncRef(i) => "inst{}", i;}
+    match FuncRef {FuncRef(i) => "fn{}", i;}
 }
 
 impl FuncRef {
@@ -32,9 +32,11 @@ use bforest::Set;
 use stdx::packed_option::PackedOption;
 use typed_index_collections::TiVec;
 
+pub use crate::flowgraph::loops::{Loop, LoopForest};
 pub use crate::flowgraph::transversal::{Postorder, ReversePostorder};
 use crate::{Block, Function, InstructionData};
 
+mod loops;
 mod transversal;
 
 pub type PredecessorIter<'a> = bforest::SetIter<'a, Block>;
@@ -0,0 +1,100 @@
+use bitset::BitSet;
+
+use crate::{Block, ControlFlowGraph, DominatorTree, Function};
+
+/// A natural loop in the CFG.
+///
+/// A natural loop is defined by a header block that dominates one or more
+/// blocks (latches) which jump back to the header. The loop body consists of
+/// all blocks that can reach one of the latches without passing through the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: Block,
+    pub blocks: BitSet<Block>,
+}
+
+impl Loop {
+    #[inline]
+    pub fn contains(&self, bb: Block) -> bool {
+        self.blocks.contains(bb)
+    }
+
+    /// Returns the unique block outside of the loop that enters the loop.
+    /// That block must jump unconditionally to the loop header, so that
+    /// instructions placed at the end of the block are executed exactly once
+    /// before the loop is entered.
+    pub fn preheader(&self, cfg: &ControlFlowGraph) -> Option<Block> {
+        let mut preds = cfg.pred_iter(self.header).filter(|&bb| !self.contains(bb));
+        let preheader = preds.next()?;
+        if preds.next().is_some() || cfg.unique_succ(preheader) != Some(self.header) {
+            return None;
+        }
+        Some(preheader)
+    }
+}
+
+/// All natural loops of a function. Loops that share a header are merged.
+/// Irreducible cycles (that have no dominating header) are not detected.
+#[derive(Debug, Clone, Default)]
+pub struct LoopForest {
+    /// Loops ordered so that nested (inner) loops always come before
+    /// the loops that contain them.
+    pub loops: Vec<Loop>,
+}
+
+impl LoopForest {
+    /// Detects all natural loops in `func`. The dominator tree must have been computed
+    /// with `dom` set to true for the current CFG.
+    pub fn compute(&mut self, func: &Function, cfg: &ControlFlowGraph, dom_tree: &DominatorTree) {
+        self.loops.clear();
+        let num_blocks = func.layout.num_blocks();
+        let mut stack = Vec::new();
+
+        // inner loop headers are dominated by the outer loop headers so
+        // they are always visited first in postorder
+        for &header in dom_tree.cfg_postorder() {
+            let mut blocks = BitSet::new_empty(num_blocks);
+            for latch in cfg.pred_iter(header) {
+                if dom_tree.dominates(latch, header) && blocks.insert(latch) {
+                    stack.push(latch);
+                }
+            }
+            if blocks.is_empty() {
+                continue;
+            }
+
+            blocks.insert(header);
+            while let Some(bb) = stack.pop() {
+                if bb == header {
+                    continue;
+                }
+                for pred in cfg.pred_iter(bb) {
+                    // skip unreachable blocks
+                    if dom_tree.dominates(pred, header) && blocks.insert(pred) {
+                        stack.push(pred);
+                    }
+                }
+            }
+
+            self.loops.push(Loop { header, blocks })
+        }
+    }
+
+    pub fn with_function(
+        func: &Function,
+        cfg: &ControlFlowGraph,
+        dom_tree: &DominatorTree,
+    ) -> Self {
+        let mut res = Self::default();
+        res.compute(func, cfg, dom_tree);
+        res
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.loops.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Loop> {
+        self.loops.iter()
+    }
+}
@@ -49,7 +49,7 @@ pub use crate::dfg::{
 };
 pub use crate::dominators::DominatorTree;
 pub use crate::entities::{AnyEntity, Block, FuncRef, Inst, Param, Use, Value};
-pub use crate::flowgraph::ControlFlowGraph;
+pub use crate::flowgraph::{ControlFlowGraph, LoopForest};
 pub use crate::instructions::{
     InstructionData, InstructionFormat, Opcode, PhiMap, PhiNode, ValueList, ValueListPool,
 };
@@ -67,7 +67,7 @@ pub struct FunctionSignature {
 
 impl_display! {
     match FunctionSignature{
-        FunctionSignature{name, params, returns, has_sideeffects} => "{}fn %{}({}) -> {}", if *has_sideeffects{""}else{"const "}, name, params, returns;
+        FunctionSignature{name, params, returns, has_sideeffects} => "{}fn {}({}) -> {}", if *has_sideeffects{""}else{"const "}, write::DisplayName(name), params, returns;
     }
 }
 
@@ -148,7 +148,7 @@ pub fn decorate_function<FW: FuncWriter>(
     interner: &dyn lasso::Resolver,
 ) -> fmt::Result {
     write!(w, "function ")?;
-    write!(w, "%{}", func.name)?;
+    write!(w, "{}", DisplayName(&func.name))?;
     write!(w, "(")?;
 
     let mut params: Vec<(usize, Value)> = func
@@ -329,6 +329,21 @@ pub fn write_operands(w: &mut dyn Write, dfg: &DataFlowGraph, inst: Inst) -> fmt
 //     }
 // }
 
+/// Displays the name of a function (or function signature) as `%name`.
+/// Names that are not plain identifiers are quoted (and escaped) so that they can be
+/// read back by the `mir-reader` crate.
+pub struct DisplayName<'a>(pub &'a str);
+
+impl fmt::Display for DisplayName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.chars().all(|c| c == '_' || c.is_ascii_alphanumeric()) {
+            write!(f, "%{}", self.0)
+        } else {
+            write!(f, "%{:?}", self.0)
+        }
+    }
+}
+
 /// Displayable slice of values.
 struct DisplayValues<'a>(&'a [Value]);
 
@@ -6,7 +6,7 @@ use mir_interpret::{Data, Interpreter};
 use mir_reader::parse_function;
 use typed_index_collections::TiSlice;
 
-use crate::auto_diff;
+use crate::{auto_diff, auto_diff_second_order, second_derivative};
 
 fn check_simple(src: &str, data_flow_result: Expect) {
     let (mut func, _) = parse_function(src).unwrap();
@@ -82,6 +82,49 @@ fn check_num(src: &str, data_flow_result: Expect, args: &[f64], num: f64) {
     }
 }
 
+fn check_second_order(src: &str, args: &[f64], second_derivatives: &[(u32, u32, u32, f64)]) {
+    let (mut func, _) = parse_function(src).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+
+    let unknowns = [10u32.into(), 11u32.into()].into_iter().collect();
+
+    let mut call1 = HybridBitSet::new_empty();
+    call1.insert(0u32.into(), 2);
+    let ddx_calls = [(0u32.into(), (call1, HybridBitSet::new_empty()))].into_iter().collect();
+
+    let unknowns = KnownDerivatives { unknowns, ddx_calls };
+
+    let mut dom_tree = DominatorTree::default();
+    dom_tree.compute(&func, &cfg, true, false, true);
+    let extra_second_derivatives: Vec<_> = second_derivatives
+        .iter()
+        .map(|&(val, unknown1, unknown2, _)| (val.into(), unknown1.into(), unknown2.into()))
+        .collect();
+    let derivatives =
+        auto_diff_second_order(&mut func, &dom_tree, &unknowns, &[], &extra_second_derivatives);
+    assert!(func.validate());
+
+    let mut interpret = Interpreter::new(
+        &func,
+        TiSlice::from_ref(&[]),
+        TiSlice::from_ref(Data::from_f64_slice(args)),
+    );
+    interpret.run();
+    let margin = F64Margin::default().epsilon(10f64 * f64::EPSILON);
+    for (&(val, unknown1, unknown2), &(_, _, _, expected)) in
+        extra_second_derivatives.iter().zip(second_derivatives)
+    {
+        let derivative = second_derivative(&derivatives, val, unknown1, unknown2);
+        let actual: f64 = interpret.state.read(derivative);
+        assert!(
+            actual.approx_eq(expected, margin),
+            "ddx(ddx({val}, {unknown1:?}), {unknown2:?}) = {actual} does not match expected value {expected}\n{}",
+            func.to_debug_string()
+        );
+    }
+}
+
 #[test]
 fn phi() {
     let src = r##"
@@ -108,8 +151,8 @@ fn phi() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10, v11, v12) {
-            inst0 = const fn %ddx_v10(1) -> 1
-            inst1 = const fn %ddx_v11(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
+            fn1 = const fn %ddx_v11(1) -> 1
             v3 = fconst 0.0
 
         block0:
@@ -150,7 +193,7 @@ fn exp_second_order() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10, v11, v12) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
 
         block0:
             v13 = exp v10
@@ -182,7 +225,7 @@ fn sin_second_order() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10, v11) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
 
         block0:
             v12 = fmul v10, v11
@@ -215,7 +258,7 @@ fn sin_exp_second_order() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
 
         block0:
             v12 = sin v10
@@ -253,7 +296,7 @@ fn third_order_ln_sin_exp() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
 
         block0:
             v12 = sin v10
@@ -325,7 +368,7 @@ fn third_order_ln_sinh_exp() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
 
         block0:
             v12 = sinh v10
@@ -391,7 +434,7 @@ fn third_order_asin() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
             v11 = fconst 0x1.0000000000000p1
@@ -452,7 +495,7 @@ fn third_order_acos() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
             v11 = fconst 0x1.0000000000000p1
@@ -516,7 +559,7 @@ fn third_order_acosh() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
             v11 = fconst 0x1.0000000000000p1
@@ -577,7 +620,7 @@ fn third_order_tan() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
 
@@ -621,7 +664,7 @@ fn third_order_tanh() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
 
@@ -664,7 +707,7 @@ fn second_order_pow() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
 
@@ -711,7 +754,7 @@ fn third_order_atan() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
 
@@ -762,7 +805,7 @@ fn third_order_atanh() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10) {
-            inst0 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
 
@@ -813,8 +856,8 @@ fn third_order_log10() {
         }"##;
     let expect = expect![[r#"
         function %bar(v11) {
-            inst0 = const fn %(0) -> 0
-            inst1 = const fn %ddx_v10(1) -> 1
+            fn0 = const fn %(0) -> 0
+            fn1 = const fn %ddx_v10(1) -> 1
             v3 = fconst 0.0
             v10 = fconst 0x1.bcb7b1526e50ep-2
 
@@ -876,9 +919,9 @@ fn subgraph() {
         }"##;
     let expect = expect![[r#"
         function %bar(v10, v11, v12) {
-            inst0 = const fn %ddx_v10(1) -> 1
-            inst1 = const fn %ddx_v11(1) -> 1
-            inst2 = const fn %ddx_v12(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
+            fn1 = const fn %ddx_v11(1) -> 1
+            fn2 = const fn %ddx_v12(1) -> 1
             v3 = fconst 0.0
             v6 = fconst 0x1.0000000000000p0
             v20 = fconst 0x1.bcb7b1526e50ep-2
@@ -928,3 +971,39 @@ fn subgraph() {
 
     check_simple(src, expect);
 }
+
+#[test]
+fn extra_second_order() {
+    let src = r##"
+        function %bar(v10, v11) {
+        block0:
+            v12 = fmul v10, v11
+            v13 = exp v12
+            v100 = optbarrier v13
+        }"##;
+    let (x, y) = (0.7f64, 1.3f64);
+    let val = (x * y).exp();
+    check_second_order(
+        src,
+        &[x, y],
+        &[(13, 0, 0, y * y * val), (13, 0, 1, (1.0 + x * y) * val), (13, 1, 1, x * x * val)],
+    );
+}
+
+#[test]
+fn extra_second_order_live_derivative() {
+    // the first order derivative of v12 is already live due to the ddx call
+    let src = r##"
+        function %bar(v10, v11) {
+            fn0 = const fn %ddx_v10(1) -> 1
+
+        block0:
+            v12 = fmul v10, v10
+            v13 = fmul v12, v11
+            v14 = sin v13
+            v15 = call fn0 (v14)
+            v100 = optbarrier v15
+        }"##;
+    let (x, y) = (0.7f64, 1.3f64);
+    check_second_order(src, &[x, y], &[(12, 0, 0, 2.0), (13, 0, 1, 2.0 * x), (13, 1, 1, 0.0)]);
+}
@@ -2,6 +2,7 @@ mod builder;
 mod intern;
 mod live_derivatives;
 mod postorder;
+mod reverse;
 mod subgraph;
 
 use ahash::AHashMap;
@@ -9,22 +10,95 @@ pub use builder::build_derivatives;
 pub use live_derivatives::LiveDerivatives;
 use mir::{
     DataFlowGraph, DominatorTree, Function, Inst, InstructionData, KnownDerivatives, Opcode, Value,
+    F_ZERO,
 };
 
 use crate::intern::{Derivative, DerivativeIntern};
 
 pub fn auto_diff(
+    func: impl AsMut<Function>,
+    dom_tree: &DominatorTree,
+    derivatives: &KnownDerivatives,
+    extra_derivatives: &[(Value, mir::Unknown)],
+) -> AHashMap<(Value, mir::Unknown), Value> {
+    auto_diff_second_order(func, dom_tree, derivatives, extra_derivatives, &[])
+}
+
+/// How the derivatives requested with `extra_derivatives` are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DiffMode {
+    /// Propagates the derivatives by all unknowns alongside the original computation.
+    #[default]
+    Forward,
+    /// Propagates adjoints backwards from every requested value. Cheaper than forward mode if
+    /// there are many unknowns and few values whose derivatives are required.
+    Reverse,
+}
+
+/// Like [`auto_diff`] but generates `extra_derivatives` with the selected [`DiffMode`].
+/// Derivatives requested with `ddx` calls are always generated in forward mode.
+pub fn auto_diff_with_mode(
+    mut func: impl AsMut<Function>,
+    dom_tree: &DominatorTree,
+    derivatives: &KnownDerivatives,
+    extra_derivatives: &[(Value, mir::Unknown)],
+    mode: DiffMode,
+) -> AHashMap<(Value, mir::Unknown), Value> {
+    match mode {
+        DiffMode::Forward => auto_diff(func, dom_tree, derivatives, extra_derivatives),
+        DiffMode::Reverse => {
+            reverse::auto_diff_reverse(func.as_mut(), dom_tree, derivatives, extra_derivatives)
+        }
+    }
+}
+
+/// Like [`auto_diff`] but additionally generates the second order derivatives
+/// `ddx(ddx(val, unknown1), unknown2)` for every `(val, unknown1, unknown2)` in
+/// `extra_second_derivatives`. Higher order derivatives are stored as derivatives of derivatives
+/// in the returned map, use [`second_derivative`] to look them up.
+///
+/// Hessian-vector products can be formed from these entries without materializing the
+/// complete hessian by only requesting the second derivatives of the residuals of interest.
+/// The OSDI backend uses this for the residuals selected with `--second-derivatives` and
+/// VerilogAE for variables with a `second_derivative` attribute.
+///
+/// Nested `ddx` calls in Verilog-A source do not require this function, they are
+/// differentiated by [`auto_diff`] like any other call.
+pub fn auto_diff_second_order(
     mut func: impl AsMut<Function>,
     dom_tree: &DominatorTree,
     derivatives: &KnownDerivatives,
     extra_derivatives: &[(Value, mir::Unknown)],
+    extra_second_derivatives: &[(Value, mir::Unknown, mir::Unknown)],
 ) -> AHashMap<(Value, mir::Unknown), Value> {
     let func = func.as_mut();
     let mut intern = DerivativeIntern::new(derivatives);
-    let live_derivative = LiveDerivatives::build(func, &mut intern, extra_derivatives, dom_tree);
+    let live_derivative = LiveDerivatives::build(
+        func,
+        &mut intern,
+        extra_derivatives,
+        extra_second_derivatives,
+        dom_tree,
+    );
     build_derivatives(func, &mut intern, &live_derivative, dom_tree.cfg_postorder())
 }
 
+/// Looks up `ddx(ddx(val, unknown1), unknown2)` in the derivatives returned by
+/// [`auto_diff_second_order`]. Derivatives that are not present are zero.
+pub fn second_derivative(
+    derivatives: &AHashMap<(Value, mir::Unknown), Value>,
+    val: Value,
+    unknown1: mir::Unknown,
+    unknown2: mir::Unknown,
+) -> Value {
+    // mixed derivatives commute so the derivative may have been generated in either order
+    let lookup = |unknown1, unknown2| {
+        let first_order = derivatives.get(&(val, unknown1))?;
+        derivatives.get(&(*first_order, unknown2)).copied()
+    };
+    lookup(unknown1, unknown2).or_else(|| lookup(unknown2, unknown1)).unwrap_or(F_ZERO)
+}
+
 fn is_zero_call(dfg: &DataFlowGraph, inst: Inst, intern: &DerivativeIntern) -> bool {
     if let InstructionData::Call { func_ref, .. } = dfg.insts[inst] {
         !intern.ddx_calls.contains_key(&func_ref)
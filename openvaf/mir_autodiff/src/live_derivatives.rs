@@ -17,6 +17,8 @@ struct LiveDerivativeBuilder<'a, 'b> {
     func: &'a Function,
     intern: &'a mut DerivativeIntern<'b>,
     reachable_derivatives: SparseBitMatrix<Inst, Derivative>,
+    /// derivatives that were explicitly requested by the caller
+    extra_derivatives: SparseBitMatrix<Inst, Derivative>,
     post_order_parts: PostorderParts<'a>,
     visited: BitSet<Inst>,
 }
@@ -35,7 +37,8 @@ impl<'a, 'b> LiveDerivativeBuilder<'a, 'b> {
             },
             func,
             intern,
-            reachable_derivatives: mat,
+            reachable_derivatives: mat.clone(),
+            extra_derivatives: mat,
             post_order_parts,
             visited: BitSet::default(),
         }
@@ -177,6 +180,9 @@ impl<'a, 'b> LiveDerivativeBuilder<'a, 'b> {
         let func = self.func;
         while let Some(inst) = workqueue.pop() {
             let mut dst = self.live_derivatives.compute_inst(inst, func, self.intern);
+            if let Some(extra) = self.extra_derivatives.row(inst) {
+                dst.union(extra, self.intern.num_derivatives());
+            }
             if let InstructionData::Call { func_ref, .. } = func.dfg.insts[inst] {
                 if self.intern.ddx_calls.contains_key(&func_ref) {
                     let old = dst.clone();
@@ -230,10 +236,34 @@ impl<'a, 'b> LiveDerivativeBuilder<'a, 'b> {
         self.live_derivatives.mat.intersect(&reachable_derivatives);
     }
 
-    fn insert_extra_derivative(&mut self, extra_derivatives: &[(Value, mir::Unknown)]) {
+    fn insert_extra_derivative(&mut self, inst: Inst, derivative: Derivative) {
+        let num_derivatives = self.intern.num_derivatives();
+        self.extra_derivatives.ensure_row(inst).insert_growable(derivative, num_derivatives);
+        self.live_derivatives.mat.ensure_row(inst).insert_growable(derivative, num_derivatives);
+    }
+
+    fn insert_extra_derivatives(
+        &mut self,
+        extra_derivatives: &[(Value, mir::Unknown)],
+        extra_second_derivatives: &[(Value, mir::Unknown, mir::Unknown)],
+    ) {
         for (val, unknown) in extra_derivatives {
             if let ValueDef::Result(inst, _) = self.func.dfg.value_def(*val) {
-                self.live_derivatives.mat.insert(inst, self.intern.to_derivative(*unknown));
+                self.insert_extra_derivative(inst, self.intern.to_derivative(*unknown));
+            }
+        }
+
+        for &(val, unknown1, unknown2) in extra_second_derivatives {
+            if let ValueDef::Result(inst, _) = self.func.dfg.value_def(val) {
+                let first_order = self.intern.to_derivative(unknown1);
+                let (derivative, created) =
+                    self.intern.raise_order_with(first_order, unknown2, |_| true).unwrap();
+                if created {
+                    self.populate_reachable(derivative)
+                }
+                // the second order derivative is computed from the first order derivative
+                self.insert_extra_derivative(inst, first_order);
+                self.insert_extra_derivative(inst, derivative);
             }
         }
     }
@@ -255,17 +285,22 @@ impl LiveDerivatives {
         func: &Function,
         intern: &mut DerivativeIntern,
         extra_derivatives: &[(Value, mir::Unknown)],
+        extra_second_derivatives: &[(Value, mir::Unknown, mir::Unknown)],
         dom_tree: &DominatorTree,
     ) -> LiveDerivatives {
         let mut builder = LiveDerivativeBuilder::new(func, intern);
         builder.populate_reachable_unknowns();
-        builder.insert_extra_derivative(extra_derivatives);
+        builder.insert_extra_derivatives(extra_derivatives, extra_second_derivatives);
         let mut workqueue = builder.initial_live_derivative_workque();
         builder.live_derivative_fixpoint(&mut workqueue);
         builder.strip_unneeded_derivatives();
         let (mut res, buf) = builder.finish();
 
-        res.run_subgraph_opt(func, intern, extra_derivatives, dom_tree, buf);
+        let outputs = extra_derivatives
+            .iter()
+            .map(|&(val, _)| val)
+            .chain(extra_second_derivatives.iter().map(|&(val, _, _)| val));
+        res.run_subgraph_opt(func, intern, outputs, dom_tree, buf);
 
         res
     }
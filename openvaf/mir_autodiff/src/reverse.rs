@@ -0,0 +1,334 @@
+use ahash::{AHashMap, AHashSet};
+use bitset::BitSet;
+use indexmap::IndexMap;
+use mir::builder::InstBuilder;
+use mir::cursor::FuncCursor;
+use mir::{
+    DominatorTree, Function, Inst, InstructionData, KnownDerivatives, Opcode, Unknown, Value,
+    F_LOG10_E, F_ONE, F_TWO, F_ZERO,
+};
+
+use crate::builder::build_derivatives;
+use crate::intern::DerivativeIntern;
+use crate::live_derivatives::LiveDerivatives;
+use crate::{is_zero_call, zero_derivative};
+
+#[cfg(test)]
+mod tests;
+
+/// Generates the derivatives in `extra_derivatives` with reverse mode (adjoint) differentiation.
+///
+/// For every requested value a single sweep walks its dependencies backwards and accumulates the
+/// derivatives by all requested unknowns at once. The cost of a sweep is therefore independent of
+/// the number of unknowns. Values that can not be differentiated locally (phis, ddx calls and
+/// functions that require additional control flow) terminate the sweep. Their derivatives are
+/// generated in forward mode and combined with the adjoints afterwards.
+pub(crate) fn auto_diff_reverse(
+    func: &mut Function,
+    dom_tree: &DominatorTree,
+    derivatives: &KnownDerivatives,
+    extra_derivatives: &[(Value, Unknown)],
+) -> AHashMap<(Value, Unknown), Value> {
+    let mut intern = DerivativeIntern::new(derivatives);
+
+    let mut outputs: IndexMap<Value, Vec<Unknown>, ahash::RandomState> = IndexMap::default();
+    for &(val, unknown) in extra_derivatives {
+        let unknowns = outputs.entry(val).or_default();
+        if !unknowns.contains(&unknown) {
+            unknowns.push(unknown)
+        }
+    }
+
+    let mut forward_derivatives = Vec::new();
+    let mut sweeps = Vec::new();
+    for (val, unknowns) in outputs {
+        if let Some(cone) = Cone::new(func, &intern, val) {
+            for &leaf in &cone.leaves {
+                forward_derivatives.extend(unknowns.iter().map(|&unknown| (leaf, unknown)));
+            }
+            sweeps.push((cone, unknowns));
+        } else {
+            forward_derivatives.extend(unknowns.iter().map(|&unknown| (val, unknown)));
+        }
+    }
+
+    let live_derivatives =
+        LiveDerivatives::build(func, &mut intern, &forward_derivatives, &[], dom_tree);
+    let mut derivative_values =
+        build_derivatives(func, &mut intern, &live_derivatives, dom_tree.cfg_postorder());
+
+    for (cone, unknowns) in sweeps {
+        cone.build_adjoints(func, &unknowns, &mut derivative_values);
+    }
+
+    derivative_values
+}
+
+/// The instructions a value depends upon that are differentiated in reverse mode.
+struct Cone {
+    output: Value,
+    /// instructions in topological order (every instruction appears after its arguments)
+    insts: Vec<Inst>,
+    members: BitSet<Inst>,
+    /// values that the sweep depends upon but that are differentiated in forward mode
+    leaves: Vec<Value>,
+}
+
+enum NodeKind {
+    Inst(Inst),
+    /// a value with a derivative that is calculated in forward mode
+    Leaf,
+    /// a value with a derivative of zero
+    Constant,
+}
+
+fn node_kind(func: &Function, intern: &DerivativeIntern, val: Value) -> NodeKind {
+    let inst = match func.dfg.value_def(val).inst() {
+        Some(inst) if !intern.unknowns.contains(&val) => inst,
+        // unknowns always have a known derivative
+        _ => return NodeKind::Leaf,
+    };
+
+    if zero_derivative(&func.dfg, inst) || is_zero_call(&func.dfg, inst, intern) {
+        return NodeKind::Constant;
+    }
+
+    match func.dfg.insts[inst].opcode() {
+        // these require control flow or are already handled by the forward mode builder
+        Opcode::Phi | Opcode::Call | Opcode::Pow | Opcode::Atan2 | Opcode::Hypot => NodeKind::Leaf,
+        _ => NodeKind::Inst(inst),
+    }
+}
+
+impl Cone {
+    fn new(func: &Function, intern: &DerivativeIntern, output: Value) -> Option<Cone> {
+        let root = match node_kind(func, intern, output) {
+            NodeKind::Inst(inst) => inst,
+            _ => return None,
+        };
+
+        let mut cone = Cone {
+            output,
+            insts: Vec::new(),
+            members: BitSet::new_empty(func.dfg.num_insts()),
+            leaves: Vec::new(),
+        };
+        let mut visited_leaves = AHashSet::new();
+
+        // iterative post order dfs along the arguments of each instruction
+        let mut stack = vec![(root, false)];
+        while let Some((inst, expanded)) = stack.pop() {
+            if expanded {
+                cone.insts.push(inst);
+                continue;
+            }
+            if !cone.members.insert(inst) {
+                continue;
+            }
+            stack.push((inst, true));
+            for &arg in func.dfg.instr_args(inst) {
+                match node_kind(func, intern, arg) {
+                    NodeKind::Inst(arg_inst) if !cone.members.contains(arg_inst) => {
+                        stack.push((arg_inst, false))
+                    }
+                    NodeKind::Leaf if visited_leaves.insert(arg) => cone.leaves.push(arg),
+                    _ => (),
+                }
+            }
+        }
+
+        Some(cone)
+    }
+
+    fn build_adjoints(
+        &self,
+        func: &mut Function,
+        unknowns: &[Unknown],
+        derivatives: &mut AHashMap<(Value, Unknown), Value>,
+    ) {
+        let root = func.dfg.value_def(self.output).unwrap_inst();
+        let mut cursor = FuncCursor::new(func).after_inst_no_phi(root);
+
+        let mut adjoints: AHashMap<Value, Value> = AHashMap::new();
+        adjoints.insert(self.output, F_ONE);
+        let mut results = vec![F_ZERO; unknowns.len()];
+
+        for &inst in self.insts.iter().rev() {
+            let res = cursor.func.dfg.first_result(inst);
+            let adjoint = match adjoints.get(&res) {
+                Some(&adjoint) => adjoint,
+                None => continue,
+            };
+
+            let args = cursor.func.dfg.instr_args(inst);
+            let arg0 = args[0];
+            let arg1 = args.get(1).copied();
+
+            let contributes = |cursor: &FuncCursor, arg: Value| {
+                self.is_member(cursor.func, arg)
+                    || unknowns.iter().any(|&unknown| derivatives.contains_key(&(arg, unknown)))
+            };
+
+            let mut partials = [None, None];
+            if contributes(&cursor, arg0) {
+                partials[0] = Some((arg0, partial(&mut cursor, inst, 0)));
+            }
+            if let Some(arg1) = arg1 {
+                if contributes(&cursor, arg1) {
+                    partials[1] = Some((arg1, partial(&mut cursor, inst, 1)));
+                }
+            }
+
+            for (arg, partial) in partials.into_iter().flatten() {
+                let contribution = partial.apply(&mut cursor, adjoint);
+                if self.is_member(cursor.func, arg) {
+                    let adjoint = adjoints.entry(arg).or_insert(F_ZERO);
+                    *adjoint = add(&mut cursor, *adjoint, contribution);
+                    continue;
+                }
+
+                for (&unknown, result) in unknowns.iter().zip(&mut results) {
+                    if let Some(&derivative) = derivatives.get(&(arg, unknown)) {
+                        let val = mul(&mut cursor, contribution, derivative);
+                        *result = add(&mut cursor, *result, val);
+                    }
+                }
+            }
+        }
+
+        for (&unknown, result) in unknowns.iter().zip(results) {
+            if result != F_ZERO {
+                derivatives.insert((self.output, unknown), result);
+            }
+        }
+    }
+
+    fn is_member(&self, func: &Function, val: Value) -> bool {
+        matches!(func.dfg.value_def(val).inst(), Some(inst) if self.members.contains(inst))
+    }
+}
+
+/// The local derivative of an instruction by one of its arguments.
+enum Partial {
+    One,
+    NegOne,
+    Mul(Value),
+    Div(Value),
+}
+
+impl Partial {
+    fn apply(self, cursor: &mut FuncCursor, adjoint: Value) -> Value {
+        match self {
+            Partial::One => adjoint,
+            Partial::NegOne => cursor.ins().fneg(adjoint),
+            Partial::Mul(val) => mul(cursor, adjoint, val),
+            Partial::Div(val) => cursor.ins().fdiv(adjoint, val),
+        }
+    }
+}
+
+fn partial(cursor: &mut FuncCursor, inst: Inst, arg: usize) -> Partial {
+    let res = cursor.func.dfg.first_result(inst);
+    let (opcode, args) = match cursor.func.dfg.insts[inst] {
+        InstructionData::Unary { opcode, arg } => (opcode, [arg, arg]),
+        InstructionData::Binary { opcode, args } => (opcode, args),
+        _ => unreachable!("only unary and binary instructions are differentiated in reverse mode"),
+    };
+    let [arg0, arg1] = args;
+
+    match opcode {
+        Opcode::OptBarrier | Opcode::Fadd => Partial::One,
+        Opcode::Fsub if arg == 0 => Partial::One,
+        Opcode::Fneg | Opcode::Fsub => Partial::NegOne,
+        // (f*g)' -> f'*g + g'*f
+        Opcode::Fmul if arg == 0 => Partial::Mul(arg1),
+        Opcode::Fmul => Partial::Mul(arg0),
+        // (f/g)' -> f'/g - g'*(f/g)/g
+        Opcode::Fdiv if arg == 0 => Partial::Div(arg1),
+        Opcode::Fdiv => {
+            let val = cursor.ins().fdiv(res, arg1);
+            Partial::Mul(cursor.ins().fneg(val))
+        }
+
+        // exp(x) -> exp(x)
+        Opcode::Exp => Partial::Mul(res),
+        // sqrt(x) -> 1/2sqrt(x)
+        Opcode::Sqrt => Partial::Div(cursor.ins().fmul(F_TWO, res)),
+        // ln(x) -> 1/x
+        Opcode::Ln => Partial::Div(arg0),
+        // log(x) -> log(e)/x
+        Opcode::Log => Partial::Mul(cursor.ins().fdiv(F_LOG10_E, arg0)),
+        // sin(x) -> cos(x)
+        Opcode::Sin => Partial::Mul(cursor.ins().cos(arg0)),
+        // cos(x) -> -sin(x)
+        Opcode::Cos => {
+            let sin = cursor.ins().sin(arg0);
+            Partial::Mul(cursor.ins().fneg(sin))
+        }
+        // tan(x) -> 1 + tan^2(x)
+        Opcode::Tan => {
+            let tan_2 = cursor.ins().fmul(res, res);
+            Partial::Mul(cursor.ins().fadd(F_ONE, tan_2))
+        }
+        // asin(x) -> 1/sqrt(1-x^2)
+        Opcode::Asin => {
+            let arg_squared = cursor.ins().fmul(arg0, arg0);
+            let sqrt_arg = cursor.ins().fsub(F_ONE, arg_squared);
+            Partial::Div(cursor.ins().sqrt(sqrt_arg))
+        }
+        // acos(x) -> -1/sqrt(1-x^2)
+        Opcode::Acos => {
+            let arg_squared = cursor.ins().fmul(arg0, arg0);
+            let sqrt_arg = cursor.ins().fsub(F_ONE, arg_squared);
+            let sqrt = cursor.ins().sqrt(sqrt_arg);
+            Partial::Div(cursor.ins().fneg(sqrt))
+        }
+        // atan(x) -> 1/(1 + x^2)
+        Opcode::Atan => {
+            let arg_squared = cursor.ins().fmul(arg0, arg0);
+            Partial::Div(cursor.ins().fadd(F_ONE, arg_squared))
+        }
+        // sinh(x) -> cosh(x)
+        Opcode::Sinh => Partial::Mul(cursor.ins().cosh(arg0)),
+        // cosh(x) -> sinh(x)
+        Opcode::Cosh => Partial::Mul(cursor.ins().sinh(arg0)),
+        // tanh(x) -> 1 - tanh^2(x)
+        Opcode::Tanh => {
+            let tanh_2 = cursor.ins().fmul(res, res);
+            Partial::Mul(cursor.ins().fsub(F_ONE, tanh_2))
+        }
+        // asinh(x) -> 1/sqrt(x^2 + 1)
+        Opcode::Asinh => {
+            let arg_squared = cursor.ins().fmul(arg0, arg0);
+            let sqrt_arg = cursor.ins().fadd(F_ONE, arg_squared);
+            Partial::Div(cursor.ins().sqrt(sqrt_arg))
+        }
+        // acosh(x) -> 1/sqrt(x^2 - 1)
+        Opcode::Acosh => {
+            let arg_squared = cursor.ins().fmul(arg0, arg0);
+            let sqrt_arg = cursor.ins().fsub(arg_squared, F_ONE);
+            Partial::Div(cursor.ins().sqrt(sqrt_arg))
+        }
+        // atanh(x) -> 1/(1-x^2)
+        Opcode::Atanh => {
+            let arg_squared = cursor.ins().fmul(arg0, arg0);
+            Partial::Div(cursor.ins().fsub(F_ONE, arg_squared))
+        }
+        opcode => unreachable!("{opcode} is not differentiated in reverse mode"),
+    }
+}
+
+fn add(cursor: &mut FuncCursor, lhs: Value, rhs: Value) -> Value {
+    match (lhs, rhs) {
+        (F_ZERO, val) | (val, F_ZERO) => val,
+        (lhs, rhs) => cursor.ins().fadd(lhs, rhs),
+    }
+}
+
+fn mul(cursor: &mut FuncCursor, lhs: Value, rhs: Value) -> Value {
+    match (lhs, rhs) {
+        (F_ZERO, _) | (_, F_ZERO) => F_ZERO,
+        (F_ONE, val) | (val, F_ONE) => val,
+        (lhs, rhs) => cursor.ins().fmul(lhs, rhs),
+    }
+}
@@ -0,0 +1,260 @@
+use ahash::AHashMap;
+use bitset::HybridBitSet;
+use expect_test::{expect, Expect};
+use float_cmp::{ApproxEq, F64Margin};
+use mir::{ControlFlowGraph, DominatorTree, Function, KnownDerivatives, Unknown, Value};
+use mir_interpret::{Data, Interpreter};
+use mir_reader::parse_function;
+use typed_index_collections::TiSlice;
+
+use crate::{auto_diff_with_mode, DiffMode};
+
+type Derivatives = AHashMap<(Value, Unknown), Value>;
+
+fn known_derivatives(num_unknowns: u32) -> KnownDerivatives {
+    let unknowns = (0..num_unknowns).map(|i| (10 + i).into()).collect();
+    let ddx_calls = (0..num_unknowns)
+        .map(|i| {
+            let mut call = HybridBitSet::new_empty();
+            call.insert(i.into(), num_unknowns as usize);
+            (i.into(), (call, HybridBitSet::new_empty()))
+        })
+        .collect();
+    KnownDerivatives { unknowns, ddx_calls }
+}
+
+fn diff(
+    src: &str,
+    num_unknowns: u32,
+    outputs: &[u32],
+    mode: DiffMode,
+) -> (Function, Derivatives, Vec<(Value, Unknown)>) {
+    let (mut func, _) = parse_function(src).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    let mut dom_tree = DominatorTree::default();
+    dom_tree.compute(&func, &cfg, true, false, true);
+
+    let known = known_derivatives(num_unknowns);
+    let extra_derivatives: Vec<_> = outputs
+        .iter()
+        .flat_map(|&val| (0..num_unknowns).map(move |unknown| (val.into(), unknown.into())))
+        .collect();
+    let derivatives = auto_diff_with_mode(&mut func, &dom_tree, &known, &extra_derivatives, mode);
+    assert!(func.validate());
+    (func, derivatives, extra_derivatives)
+}
+
+fn eval(
+    func: &Function,
+    derivatives: &Derivatives,
+    extra_derivatives: &[(Value, Unknown)],
+    args: &[f64],
+) -> Vec<f64> {
+    let mut interpret = Interpreter::new(
+        func,
+        TiSlice::from_ref(&[]),
+        TiSlice::from_ref(Data::from_f64_slice(args)),
+    );
+    interpret.run();
+    extra_derivatives
+        .iter()
+        .map(|key| derivatives.get(key).map_or(0.0, |&val| interpret.state.read(val)))
+        .collect()
+}
+
+/// Compares the derivatives of `outputs` by all unknowns generated in reverse mode to
+/// those generated in forward mode.
+fn check_against_forward(src: &str, num_unknowns: u32, outputs: &[u32], args: &[f64]) {
+    let (forward_func, forward, extra_derivatives) =
+        diff(src, num_unknowns, outputs, DiffMode::Forward);
+    let (reverse_func, reverse, _) = diff(src, num_unknowns, outputs, DiffMode::Reverse);
+    let expected = eval(&forward_func, &forward, &extra_derivatives, args);
+    let actual = eval(&reverse_func, &reverse, &extra_derivatives, args);
+
+    // both modes use different (but mathematically equivalent) formulations
+    let margin = F64Margin::default().epsilon(10f64 * f64::EPSILON);
+    for ((&(val, unknown), expected), actual) in extra_derivatives.iter().zip(expected).zip(actual)
+    {
+        assert!(
+            actual.approx_eq(expected, margin),
+            "reverse mode ddx({val}, {unknown:?}) = {actual} does not match forward mode {expected}\n{}",
+            reverse_func.to_debug_string()
+        );
+    }
+}
+
+fn check_reverse(src: &str, num_unknowns: u32, outputs: &[u32], expect: Expect) {
+    let (func, _, _) = diff(src, num_unknowns, outputs, DiffMode::Reverse);
+    expect.assert_eq(&func.to_debug_string());
+}
+
+#[test]
+fn shared_subexpression() {
+    let src = r##"
+        function %bar(v10, v11) {
+        block0:
+            v12 = fmul v10, v11
+            v13 = sin v12
+            v14 = fmul v12, v13
+            v15 = fadd v14, v12
+            v100 = optbarrier v15
+        }"##;
+    let expect = expect![[r#"
+        function %bar(v10, v11) {
+            v6 = fconst 0x1.0000000000000p0
+        block0:
+            v12 = fmul v10, v11
+            v13 = sin v12
+            v14 = fmul v12, v13
+            v15 = fadd v14, v12
+            v101 = fadd v6, v13
+            v102 = cos v12
+            v103 = fmul v12, v102
+            v104 = fadd v101, v103
+            v105 = fmul v104, v11
+            v106 = fmul v104, v10
+            v100 = optbarrier v15
+        }
+    "#]];
+    check_reverse(src, 2, &[15], expect);
+    check_against_forward(src, 2, &[15], &[0.7, 1.3]);
+}
+
+#[test]
+fn exp() {
+    let src = r##"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = exp v10
+            v16 = fmul v10, v11
+            v17 = exp v16
+            v18 = fadd v13, v17
+            v100 = optbarrier v18
+        }"##;
+    check_against_forward(src, 3, &[18], &[0.7, 1.3, 2.1]);
+}
+
+#[test]
+fn ln_sin_exp() {
+    let src = r##"
+        function %bar(v10) {
+        block0:
+            v12 = sin v10
+            v13 = exp v10
+            v16 = fmul v12, v13
+            v17 = ln v16
+            v100 = optbarrier v17
+        }"##;
+    check_against_forward(src, 1, &[16, 17], &[0.7]);
+}
+
+#[test]
+fn elementary_functions() {
+    let src = r##"
+        function %bar(v10, v11) {
+        block0:
+            v12 = fmul v10, v11
+            v13 = atan v12
+            v14 = tanh v13
+            v15 = asin v14
+            v16 = acos v14
+            v17 = atanh v14
+            v18 = fdiv v15, v16
+            v19 = sqrt v18
+            v20 = log v19
+            v21 = tan v20
+            v22 = asinh v21
+            v23 = cosh v22
+            v24 = sinh v17
+            v25 = cos v24
+            v26 = acosh v23
+            v27 = fsub v25, v26
+            v28 = fneg v27
+            v29 = fdiv v28, v10
+            v100 = optbarrier v29
+        }"##;
+    check_against_forward(src, 2, &[29], &[0.7, 0.4]);
+}
+
+#[test]
+fn subgraph() {
+    let src = r##"
+        function %bar(v10, v11, v12) {
+            v20 = fconst 0x1.bcb7b1526e50ep-2
+
+        block0:
+            v22 = fadd v10, v11
+            v14 = fadd v22, v12
+            v15 = fmul v14, v20
+            v16 = fmul v15, v20
+            v17 = fmul v16, v20
+            v23 = fmul v22, v20
+            v24 = fmul v23, v20
+            v25 = fmul v24, v20
+            v26 = fmul v25, v20
+            v27 = fmul v26, v20
+            v18 = fmul v17, v20
+            v19 = fmul v18, v20
+            v40 = fmul v19, v20
+            v41 = fmul v40, v20
+            v101 = optbarrier v41
+            v102 = optbarrier v27
+        }"##;
+    check_against_forward(src, 3, &[41, 27], &[0.7, 1.3, 2.1]);
+}
+
+#[test]
+fn phi_leaf() {
+    let src = r##"
+        function %bar(v10, v11) {
+        block0:
+            v12 = flt v10, v11
+            br v12, block1, block2
+
+        block1:
+            v13 = exp v10
+            jmp block3
+
+        block2:
+            v14 = exp v11
+            jmp block3
+
+        block3:
+            v15 = phi [v13, block1], [v14, block2]
+            v16 = fmul v15, v10
+            v17 = sin v16
+            v100 = optbarrier v17
+        }"##;
+    check_against_forward(src, 2, &[15, 17], &[0.7, 1.3]);
+    check_against_forward(src, 2, &[15, 17], &[1.3, 0.7]);
+}
+
+#[test]
+fn ddx_leaf() {
+    let src = r##"
+        function %bar(v10, v11) {
+            fn0 = const fn %ddx_v10(1) -> 1
+
+        block0:
+            v12 = fmul v10, v11
+            v13 = exp v12
+            v14 = call fn0 (v13)
+            v15 = fmul v14, v12
+            v100 = optbarrier v15
+        }"##;
+    check_against_forward(src, 2, &[15], &[0.7, 1.3]);
+}
+
+#[test]
+fn pow_leaf() {
+    let src = r##"
+        function %bar(v10, v11) {
+        block0:
+            v12 = pow v10, v11
+            v13 = fmul v12, v10
+            v14 = exp v13
+            v100 = optbarrier v14
+        }"##;
+    check_against_forward(src, 2, &[14], &[0.7, 1.3]);
+}
@@ -267,6 +267,29 @@ impl ModuleLlvm {
 
         Ok(())
     }
+
+    /// Emits an object file into memory, for example to load it into a [`llvm::orc::Jit`]
+    pub fn emit_object_to_memory(&self) -> Result<&'static mut llvm::MemoryBuffer, LLVMString> {
+        let mut err_string = MaybeUninit::uninit();
+        let mut buf = None;
+        let return_code = unsafe {
+            llvm::LLVMTargetMachineEmitToMemoryBuffer(
+                self.tm,
+                self.llmod(),
+                llvm::CodeGenFileType::ObjectFile,
+                err_string.as_mut_ptr(),
+                &mut buf,
+            )
+        };
+
+        if return_code == 1 {
+            unsafe {
+                return Err(LLVMString::new(err_string.assume_init()));
+            }
+        }
+
+        Ok(buf.unwrap())
+    }
 }
 
 impl Drop for ModuleLlvm {
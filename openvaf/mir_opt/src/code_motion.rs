@@ -0,0 +1,106 @@
+use bitset::BitSet;
+use mir::{
+    Block, ControlFlowGraph, DominatorTree, Function, Inst, InstructionData, LoopForest, Opcode,
+    ValueDef,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Moves loop invariant instructions into the preheader of their loop.
+///
+/// An instruction is invariant if all of its arguments are defined outside the loop.
+/// Loops are processed from the inside out so that instructions can be hoisted
+/// through multiple nested loops. Loops without a preheader are left untouched.
+/// `dom_tree` must contain the dominator tree (and the postorder) of the current CFG.
+pub fn loop_invariant_code_motion(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    dom_tree: &DominatorTree,
+) {
+    let loops = LoopForest::with_function(func, cfg, dom_tree);
+    let mut insts = Vec::new();
+    for loop_ in loops.iter() {
+        let preheader = if let Some(preheader) = loop_.preheader(cfg) {
+            preheader
+        } else {
+            continue;
+        };
+        let term = func.layout.block_terminator(preheader).unwrap();
+
+        // visit blocks in reverse postorder so that arguments are always hoisted before their uses
+        for &bb in dom_tree.cfg_postorder().iter().rev() {
+            if !loop_.contains(bb) {
+                continue;
+            }
+            insts.extend(func.layout.block_insts(bb));
+            for inst in insts.drain(..) {
+                if !is_speculatable(func, inst) {
+                    continue;
+                }
+                let invariant =
+                    func.dfg.instr_args(inst).iter().all(|&arg| match func.dfg.value_def(arg) {
+                        ValueDef::Result(def, _) => {
+                            !loop_.contains(func.layout.inst_block(def).unwrap())
+                        }
+                        _ => true,
+                    });
+                if invariant {
+                    func.layout.remove_inst(inst);
+                    func.layout.prepend_inst(inst, term);
+                }
+            }
+        }
+    }
+}
+
+/// Moves each instruction in `insts` to the earliest block where all of its arguments are
+/// available: the block (dominated by all others) that defines one of its arguments or the
+/// entry block. This removes these instructions from any branches (and loops) they were
+/// placed in. Instructions that can not be executed speculatively are never moved.
+/// `dom_tree` must contain the dominator tree (and the postorder) of the current CFG.
+pub fn hoist_insts(func: &mut Function, dom_tree: &DominatorTree, insts: &BitSet<Inst>) {
+    let entry = if let Some(entry) = func.layout.entry_block() { entry } else { return };
+    let mut block_insts = Vec::new();
+    for &bb in dom_tree.cfg_postorder().iter().rev() {
+        block_insts.extend(func.layout.block_insts(bb));
+        for inst in block_insts.drain(..) {
+            if !insts.contains(inst) || !is_speculatable(func, inst) {
+                continue;
+            }
+            let mut dst: Block = entry;
+            for &arg in func.dfg.instr_args(inst) {
+                if let ValueDef::Result(def, _) = func.dfg.value_def(arg) {
+                    // all arguments dominate the instruction so their blocks
+                    // are all part of the same path in the dominator tree
+                    let def_bb = func.layout.inst_block(def).unwrap();
+                    if dom_tree.dominates(def_bb, dst) {
+                        dst = def_bb;
+                    }
+                }
+            }
+            if dst == bb {
+                continue;
+            }
+            if let Some(term) = func.layout.block_terminator(dst) {
+                func.layout.remove_inst(inst);
+                func.layout.prepend_inst(inst, term);
+            }
+        }
+    }
+}
+
+/// Whether an instruction can be executed even if it would not be executed
+/// in the original program.
+fn is_speculatable(func: &Function, inst: Inst) -> bool {
+    match func.dfg.insts[inst] {
+        // calls may have sideffects or produce errors
+        InstructionData::PhiNode(_)
+        | InstructionData::Branch { .. }
+        | InstructionData::Jump { .. }
+        | InstructionData::Call { .. } => false,
+        // optbarriers mark values that must be preserved where they are;
+        // integer division by zero traps
+        ref data => !matches!(data.opcode(), Opcode::OptBarrier | Opcode::Idiv | Opcode::Irem),
+    }
+}
@@ -0,0 +1,230 @@
+use bitset::BitSet;
+use expect_test::{expect, Expect};
+use mir::{ControlFlowGraph, DominatorTree, Function};
+use mir_reader::parse_function;
+
+use crate::{hoist_insts, loop_invariant_code_motion};
+
+fn parse(src: &str) -> (Function, ControlFlowGraph, DominatorTree) {
+    let (func, _) = parse_function(src).unwrap();
+    let mut cfg = ControlFlowGraph::new();
+    cfg.compute(&func);
+    let mut dom_tree = DominatorTree::default();
+    dom_tree.compute(&func, &cfg, true, false, true);
+    (func, cfg, dom_tree)
+}
+
+fn licm_test(src: &str, expect: Expect) {
+    let (mut func, cfg, dom_tree) = parse(src);
+    loop_invariant_code_motion(&mut func, &cfg, &dom_tree);
+    assert!(func.validate());
+    expect.assert_eq(&func.to_debug_string());
+}
+
+fn hoist_test(src: &str, values: &[u32], expect: Expect) {
+    let (mut func, _, dom_tree) = parse(src);
+    let mut set = BitSet::new_empty(func.dfg.num_insts());
+    for &val in values {
+        set.insert(func.dfg.value_def(val.into()).unwrap_inst());
+    }
+    hoist_insts(&mut func, &dom_tree, &set);
+    assert!(func.validate());
+    expect.assert_eq(&func.to_debug_string());
+}
+
+#[test]
+fn simple_loop() {
+    let src = r##"
+        function %bar(v10, v11, v12) {
+            v3 = fconst 0.0
+            v4 = fconst 0x1.0000000000000p0
+        block0:
+            jmp block1
+        block1:
+            v13 = phi [v3, block0], [v18, block2]
+            v14 = flt v13, v12
+            br v14, block2, block3
+        block2:
+            v15 = fmul v10, v11
+            v16 = exp v15
+            v17 = fmul v16, v13
+            v18 = fadd v13, v4
+            jmp block1
+        block3:
+            v19 = optbarrier v13
+        }"##;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+            v3 = fconst 0.0
+            v4 = fconst 0x1.0000000000000p0
+        block0:
+            v15 = fmul v10, v11
+            v16 = exp v15
+            jmp block1
+
+        block1:
+            v13 = phi [v3, block0], [v18, block2]
+            v14 = flt v13, v12
+            br v14, block2, block3
+
+        block2:
+            v17 = fmul v16, v13
+            v18 = fadd v13, v4
+            jmp block1
+
+        block3:
+            v19 = optbarrier v13
+        }
+    "#]];
+    licm_test(src, expect);
+}
+
+#[test]
+fn nested_loop() {
+    let src = r##"
+        function %bar(v10, v11, v12) {
+            v3 = fconst 0.0
+            v4 = fconst 0x1.0000000000000p0
+        block0:
+            jmp block1
+        block1:
+            v13 = phi [v3, block0], [v20, block5]
+            v14 = flt v13, v12
+            br v14, block2, block3
+        block2:
+            jmp block4
+        block4:
+            v15 = phi [v3, block2], [v19, block6]
+            v16 = flt v15, v12
+            br v16, block6, block5
+        block6:
+            v17 = fdiv v10, v11
+            v18 = fmul v17, v13
+            v21 = fmul v18, v15
+            v19 = fadd v15, v4
+            jmp block4
+        block5:
+            v20 = fadd v13, v4
+            jmp block1
+        block3:
+            v22 = optbarrier v13
+        }"##;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+            v3 = fconst 0.0
+            v4 = fconst 0x1.0000000000000p0
+        block0:
+            v17 = fdiv v10, v11
+            jmp block1
+
+        block1:
+            v13 = phi [v3, block0], [v20, block5]
+            v14 = flt v13, v12
+            br v14, block2, block3
+
+        block2:
+            v18 = fmul v17, v13
+            jmp block4
+
+        block4:
+            v15 = phi [v3, block2], [v19, block6]
+            v16 = flt v15, v12
+            br v16, block6, block5
+
+        block6:
+            v21 = fmul v18, v15
+            v19 = fadd v15, v4
+            jmp block4
+
+        block5:
+            v20 = fadd v13, v4
+            jmp block1
+
+        block3:
+            v22 = optbarrier v13
+        }
+    "#]];
+    licm_test(src, expect);
+}
+
+#[test]
+fn no_preheader() {
+    let src = r##"
+        function %bar(v10, v11, v12) {
+            v3 = fconst 0.0
+        block0:
+            br v10, block1, block3
+        block1:
+            v13 = phi [v3, block0], [v18, block2]
+            v14 = flt v13, v12
+            br v14, block2, block3
+        block2:
+            v15 = fmul v11, v11
+            v18 = fadd v13, v15
+            jmp block1
+        block3:
+            v19 = phi [v3, block0], [v13, block1]
+            v20 = optbarrier v19
+        }"##;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+            v3 = fconst 0.0
+        block0:
+            br v10, block1, block3
+
+        block1:
+            v13 = phi [v3, block0], [v18, block2]
+            v14 = flt v13, v12
+            br v14, block2, block3
+
+        block2:
+            v15 = fmul v11, v11
+            v18 = fadd v13, v15
+            jmp block1
+
+        block3:
+            v19 = phi [v3, block0], [v13, block1]
+            v20 = optbarrier v19
+        }
+    "#]];
+    licm_test(src, expect);
+}
+
+#[test]
+fn hoist_from_branch() {
+    let src = r##"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fmul v10, v11
+            v14 = flt v12, v13
+            br v14, block1, block2
+        block1:
+            v15 = exp v13
+            v16 = fmul v15, v12
+            v17 = idiv v10, v11
+            jmp block2
+        block2:
+            v18 = phi [v13, block0], [v16, block1]
+            v19 = optbarrier v18
+        }"##;
+    let expect = expect![[r#"
+        function %bar(v10, v11, v12) {
+        block0:
+            v13 = fmul v10, v11
+            v14 = flt v12, v13
+            v15 = exp v13
+            br v14, block1, block2
+
+        block1:
+            v16 = fmul v15, v12
+            v17 = idiv v10, v11
+            jmp block2
+
+        block2:
+            v18 = phi [v13, block0], [v16, block1]
+            v19 = optbarrier v18
+        }
+    "#]];
+    // v16 is not selected and integer division is never hoisted
+    hoist_test(src, &[15, 17], expect);
+}
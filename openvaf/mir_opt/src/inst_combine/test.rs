@@ -43,8 +43,8 @@ fn diode() {
     "#;
     let expect = expect![[r#"
         function %bar(v10, v11, v12) {
-            inst0 = const fn %ddx_v10(1) -> 1
-            inst1 = const fn %ddx_v11(1) -> 1
+            fn0 = const fn %ddx_v10(1) -> 1
+            fn1 = const fn %ddx_v11(1) -> 1
             v3 = fconst 0.0
 
         block0:
@@ -1,3 +1,4 @@
+mod code_motion;
 mod const_eval;
 mod const_prop;
 mod dead_code;
@@ -8,6 +9,7 @@ mod simplify;
 mod simplify_cfg;
 mod split_tainted;
 
+pub use code_motion::{hoist_insts, loop_invariant_code_motion};
 pub use const_prop::sparse_conditional_constant_propagation;
 pub use dead_code::dead_code_elimination;
 pub use dead_code_aggressive::aggressive_dead_code_elimination;
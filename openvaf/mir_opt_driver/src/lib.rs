@@ -0,0 +1,271 @@
+//! A driver that reads textual MIR, runs a pipeline of `mir_opt` passes (and `mir_autodiff`)
+//! and prints the result. This allows writing file based regression tests for the MIR
+//! optimizations (similar to LLVM's `opt`).
+//!
+//! Besides the functions the input may contain directives (in `;` comments) that configure
+//! the pipeline:
+//!
+//! ```text
+//! ; passes: inst_combine, autodiff, dce
+//! ; unknowns: v10, v11
+//! ; ddx fn0: v10
+//! ; ddx fn1: v10, -v11
+//! ; outputs: v15
+//! ```
+//!
+//! * `passes` is the pipeline that is executed (in order).
+//! * `unknowns` are the values that `autodiff` can differentiate by.
+//! * `ddx fnN` marks `fnN` as a derivative call with respect to the sum of the listed
+//!   unknowns (unknowns prefixed with `-` are subtracted).
+//! * `outputs` are the values that dead code elimination must keep alive.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use ahash::AHashMap;
+use anyhow::{bail, Context, Result};
+use bitset::{BitSet, HybridBitSet, SparseBitMatrix};
+use mir::{
+    ControlFlowGraph, DominatorTree, FuncRef, Function, KnownDerivatives, Unknown, Value, ValueDef,
+};
+use mir_opt::{
+    aggressive_dead_code_elimination, dead_code_elimination, inst_combine,
+    loop_invariant_code_motion, simplify_cfg, simplify_cfg_no_phi_merge,
+    sparse_conditional_constant_propagation, GVN,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    InstCombine,
+    Sccp,
+    SimplifyCfg,
+    SimplifyCfgNoPhiMerge,
+    Gvn,
+    Licm,
+    Dce,
+    Adce,
+    AutoDiff,
+    RemoveOptBarriers,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 10] = [
+        Pass::InstCombine,
+        Pass::Sccp,
+        Pass::SimplifyCfg,
+        Pass::SimplifyCfgNoPhiMerge,
+        Pass::Gvn,
+        Pass::Licm,
+        Pass::Dce,
+        Pass::Adce,
+        Pass::AutoDiff,
+        Pass::RemoveOptBarriers,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::InstCombine => "inst_combine",
+            Pass::Sccp => "sccp",
+            Pass::SimplifyCfg => "simplify_cfg",
+            Pass::SimplifyCfgNoPhiMerge => "simplify_cfg_no_phi_merge",
+            Pass::Gvn => "gvn",
+            Pass::Licm => "licm",
+            Pass::Dce => "dce",
+            Pass::Adce => "adce",
+            Pass::AutoDiff => "autodiff",
+            Pass::RemoveOptBarriers => "remove_opt_barriers",
+        }
+    }
+}
+
+impl FromStr for Pass {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Pass> {
+        match Pass::ALL.iter().find(|pass| pass.name() == s) {
+            Some(&pass) => Ok(pass),
+            None => bail!("unknown pass '{s}'"),
+        }
+    }
+}
+
+/// The pipeline (and the information required by the passes) that is applied to
+/// every function in the input.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    pub passes: Vec<Pass>,
+    pub unknowns: Vec<Value>,
+    pub ddx_calls: Vec<(FuncRef, Vec<(Value, bool)>)>,
+    pub outputs: Vec<Value>,
+}
+
+impl Pipeline {
+    /// Reads the directives from the comments in `src`.
+    pub fn from_directives(src: &str) -> Result<Pipeline> {
+        let mut pipeline = Pipeline::default();
+        for (line, text) in src.lines().enumerate() {
+            let Some(directive) = text.trim_start().strip_prefix(';') else { continue };
+            let Some((key, args)) = directive.split_once(':') else { continue };
+            let key = key.trim();
+            let args = args.split(',').map(str::trim).filter(|arg| !arg.is_empty());
+            let res = match key {
+                "passes" => args.map(Pass::from_str).collect::<Result<_>>().map(|passes| {
+                    pipeline.passes = passes;
+                }),
+                "unknowns" => args.map(parse_value).collect::<Result<_>>().map(|unknowns| {
+                    pipeline.unknowns = unknowns;
+                }),
+                "outputs" => args.map(parse_value).collect::<Result<_>>().map(|outputs| {
+                    pipeline.outputs = outputs;
+                }),
+                _ => match key.strip_prefix("ddx") {
+                    Some(func_ref) => parse_ddx_call(func_ref.trim(), args).map(|call| {
+                        pipeline.ddx_calls.push(call);
+                    }),
+                    None => continue,
+                },
+            };
+            res.with_context(|| format!("invalid directive in line {}", line + 1))?;
+        }
+        Ok(pipeline)
+    }
+
+    pub fn set_passes(&mut self, passes: &str) -> Result<()> {
+        self.passes = passes
+            .split(',')
+            .map(str::trim)
+            .filter(|pass| !pass.is_empty())
+            .map(Pass::from_str)
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    fn known_derivatives(&self) -> Result<KnownDerivatives> {
+        let unknowns = self.unknowns.iter().copied().collect();
+        let num_unknowns = self.unknowns.len();
+        let to_unknown = |val: Value| -> Result<Unknown> {
+            match self.unknowns.iter().position(|&unknown| unknown == val) {
+                Some(pos) => Ok(Unknown(pos as u32)),
+                None => bail!("{val} is not an unknown"),
+            }
+        };
+        let mut ddx_calls = AHashMap::new();
+        for (func_ref, args) in &self.ddx_calls {
+            let mut pos = HybridBitSet::new_empty();
+            let mut neg = HybridBitSet::new_empty();
+            for &(val, negative) in args {
+                let dst = if negative { &mut neg } else { &mut pos };
+                dst.insert(to_unknown(val)?, num_unknowns);
+            }
+            ddx_calls.insert(*func_ref, (pos, neg));
+        }
+        Ok(KnownDerivatives { unknowns, ddx_calls })
+    }
+
+    fn output_values(&self, func: &Function) -> BitSet<Value> {
+        let mut res = BitSet::new_empty(func.dfg.num_values());
+        for &val in &self.outputs {
+            res.insert(val);
+        }
+        res
+    }
+
+    /// Runs the pipeline on `func`. The function is validated after every pass.
+    pub fn run(&self, func: &mut Function) -> Result<()> {
+        let mut cfg = ControlFlowGraph::new();
+        let mut dom_tree = DominatorTree::default();
+
+        for &pass in &self.passes {
+            cfg.compute(func);
+            match pass {
+                Pass::InstCombine => inst_combine(func),
+                Pass::Sccp => sparse_conditional_constant_propagation(func, &cfg),
+                Pass::SimplifyCfg => simplify_cfg(func, &mut cfg),
+                Pass::SimplifyCfgNoPhiMerge => simplify_cfg_no_phi_merge(func, &mut cfg),
+                Pass::Gvn => {
+                    dom_tree.compute(func, &cfg, true, false, false);
+                    let num_params = func
+                        .dfg
+                        .values()
+                        .filter(|&val| matches!(func.dfg.value_def(val), ValueDef::Param(_)))
+                        .count();
+                    let mut gvn = GVN::default();
+                    gvn.init(func, &dom_tree, num_params as u32);
+                    gvn.solve(func);
+                    gvn.remove_unnecessary_insts(func, &dom_tree);
+                    gvn.clear(func);
+                }
+                Pass::Licm => {
+                    dom_tree.compute(func, &cfg, true, false, false);
+                    loop_invariant_code_motion(func, &cfg, &dom_tree);
+                }
+                Pass::Dce => dead_code_elimination(func, &self.output_values(func)),
+                Pass::Adce => {
+                    let outputs = self.output_values(func);
+                    dom_tree.compute(func, &cfg, false, true, false);
+                    let mut control_dep = SparseBitMatrix::new_square(0);
+                    dom_tree.compute_postdom_frontiers(&cfg, &mut control_dep);
+                    aggressive_dead_code_elimination(
+                        func,
+                        &mut cfg,
+                        &|val, _| outputs.contains(val),
+                        &control_dep,
+                    );
+                }
+                Pass::AutoDiff => {
+                    dom_tree.compute(func, &cfg, true, false, true);
+                    mir_autodiff::auto_diff(&mut *func, &dom_tree, &self.known_derivatives()?, &[]);
+                }
+                Pass::RemoveOptBarriers => func.remove_opt_barriers(),
+            }
+            if !func.validate() {
+                bail!("{} produced invalid MIR:\n{func:?}", pass.name())
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_value(text: &str) -> Result<Value> {
+    let num = text.strip_prefix('v').and_then(|num| num.parse::<u32>().ok());
+    match num {
+        Some(num) => Ok(Value::from(num)),
+        None => bail!("expected a value (vN) found '{text}'"),
+    }
+}
+
+fn parse_ddx_call<'a>(
+    func_ref: &str,
+    args: impl Iterator<Item = &'a str>,
+) -> Result<(FuncRef, Vec<(Value, bool)>)> {
+    let func_ref = func_ref
+        .strip_prefix("fn")
+        .and_then(|num| num.parse().ok())
+        .and_then(FuncRef::with_number)
+        .with_context(|| format!("expected a function reference (fnN) found '{func_ref}'"))?;
+    let args = args
+        .map(|arg| match arg.strip_prefix('-') {
+            Some(arg) => Ok((parse_value(arg.trim())?, true)),
+            None => Ok((parse_value(arg)?, false)),
+        })
+        .collect::<Result<_>>()?;
+    Ok((func_ref, args))
+}
+
+/// Parses all functions in `src`, runs `pipeline` on them and returns the printed result.
+pub fn run(src: &str, pipeline: &Pipeline) -> Result<String> {
+    let (funcs, literals) = match mir_reader::parse_functions(src) {
+        Ok(res) => res,
+        Err(err) => bail!("failed to parse MIR: {err}"),
+    };
+    let mut res = String::new();
+    for (i, mut func) in funcs.into_iter().enumerate() {
+        pipeline.run(&mut func).with_context(|| format!("failed to optimize %{}", func.name))?;
+        if i != 0 {
+            res.push('\n');
+        }
+        write!(res, "{}", func.print(&literals)).unwrap();
+    }
+    Ok(res)
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::io::{self, Read};
+use std::process::exit;
+
+use anyhow::{Context, Result};
+use clap::{Arg, Command};
+use mir_opt_driver::{run, Pass, Pipeline};
+
+const PASSES: &str = "passes";
+const INPUT: &str = "input";
+
+fn main_command() -> Command {
+    let passes: Vec<_> = Pass::ALL.iter().map(|pass| pass.name()).collect();
+    Command::new("mir-opt")
+        .about("Runs MIR optimization passes on textual MIR and prints the result.")
+        .args([
+            Arg::new(PASSES)
+                .short('p')
+                .long(PASSES)
+                .value_name("PASSES")
+                .help("Comma separated list of passes to run (overwrites the passes directive)")
+                .long_help(format!(
+                    "Comma separated list of passes to run (overwrites the passes directive).\nAvailable passes: {}",
+                    passes.join(", ")
+                )),
+            Arg::new(INPUT)
+                .value_name("FILE")
+                .help("The MIR file to optimize (reads from stdin if omitted)"),
+        ])
+}
+
+fn wrapped_main() -> Result<()> {
+    let matches = main_command().get_matches();
+    let src = match matches.get_one::<String>(INPUT) {
+        Some(path) => fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?,
+        None => {
+            let mut src = String::new();
+            io::stdin().read_to_string(&mut src).context("failed to read stdin")?;
+            src
+        }
+    };
+
+    let mut pipeline = Pipeline::from_directives(&src)?;
+    if let Some(passes) = matches.get_one::<String>(PASSES) {
+        pipeline.set_passes(passes)?;
+    }
+    print!("{}", run(&src, &pipeline)?);
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = wrapped_main() {
+        eprintln!("error: {err:?}");
+        exit(1)
+    }
+}
@@ -0,0 +1,27 @@
+use std::fs;
+use std::path::Path;
+
+use expect_test::expect_file;
+use mini_harness::{harness, Result};
+use mir_opt_driver::{run, Pipeline};
+use stdx::{ignore_never, openvaf_test_data};
+
+fn is_mir_file(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("mir")
+}
+
+fn mir_opt_test(file: &Path) -> Result {
+    let src = fs::read_to_string(file).unwrap();
+    let pipeline = Pipeline::from_directives(&src)?;
+    let res = run(&src, &pipeline)?;
+    expect_file![file.with_extension("snap")].assert_eq(&res);
+
+    // the output must be valid input again
+    let reparsed = run(&res, &Pipeline::default())?;
+    assert_eq!(res, reparsed);
+    Ok(())
+}
+
+harness! {
+    Test::from_dir_filtered("mir_opt", &mir_opt_test, &is_mir_file, &ignore_never, &openvaf_test_data("mir_opt"))
+}
@@ -307,7 +307,17 @@ impl<'a> Lexer<'a> {
 
         assert_eq!(self.lookahead, Some('%'));
 
-        while matches!(self.next_ch(), Some('_' | '0'..='9' | 'a'..='z' | 'A'..='Z')) {}
+        // names that are not plain identifiers are quoted: %"$limit[\"pnjlim\"]"
+        if self.next_ch() == Some('"') {
+            return match self.scan_string()?.token {
+                Token::String(name) => token(Token::Name(name), loc),
+                _ => unreachable!(),
+            };
+        }
+
+        while matches!(self.lookahead, Some('_' | '0'..='9' | 'a'..='z' | 'A'..='Z')) {
+            self.next_ch();
+        }
 
         let end = self.pos;
         token(Token::Name(&self.source[begin..end]), loc)
@@ -323,7 +333,7 @@ impl<'a> Lexer<'a> {
         while let Some(c) = self.next_ch() {
             if c == '\\' {
                 if let Some(ch) = self.next_ch() {
-                    if !matches!(ch, '0' | 'n' | 'r' | 't' | '\\' | '"') {
+                    if !matches!(ch, '0' | 'n' | 'r' | 't' | '\\' | '"' | '\'' | 'u') {
                         return error(LexError::InvalidEscapeSequence, self.loc());
                     }
                 } else {
@@ -384,6 +394,7 @@ impl<'a> Lexer<'a> {
             return match self.lookahead {
                 None => None,
                 Some(';') => Some(self.scan_comment()),
+                Some('/') if self.looking_at("//") => Some(self.scan_comment()),
                 Some('(') => Some(self.scan_char(Token::LPar)),
                 Some(')') => Some(self.scan_char(Token::RPar)),
                 Some('{') => Some(self.scan_char(Token::LBrace)),
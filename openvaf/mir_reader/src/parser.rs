@@ -120,6 +120,34 @@ impl Default for VariableArgs {
     }
 }
 
+/// Resolves the escape sequences produced by the `Debug` implementation of `str`
+/// (which is used to print strings and quoted names).
+fn unescape(text: &str) -> Option<String> {
+    let mut res = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        let c = match chars.next()? {
+            '0' => '\0',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let rem = chars.as_str().strip_prefix('{')?;
+                let (code, rem) = rem.split_once('}')?;
+                chars = rem.chars();
+                char::from_u32(u32::from_str_radix(code, 16).ok()?)?
+            }
+            c => c,
+        };
+        res.push(c);
+    }
+    Some(res)
+}
+
 /// After some quick benchmarks a program should never have more than 100,000 blocks.
 const MAX_BLOCKS_IN_A_FUNCTION: u32 = 100_000;
 
@@ -201,6 +229,8 @@ impl<'a> Parser<'a> {
     fn token(&mut self) -> Option<Token<'a>> {
         while self.lookahead.is_none() {
             match self.lex.next() {
+                // comments are not preserved
+                Some(Ok(LocatedToken { token: Token::Comment(_), .. })) => (),
                 Some(Ok(LocatedToken { token, location })) => {
                     self.lookahead = Some(token);
                     self.loc = location;
@@ -300,10 +330,11 @@ impl<'a> Parser<'a> {
         }
     }
 
-    // Match and consume a boolean immediate.
+    // Match and consume a string immediate.
     fn match_str(&mut self, err_msg: &str) -> ParseResult<Spur> {
         if let Some(Token::String(text)) = self.token() {
             self.consume();
+            let text = unescape(text).ok_or_else(|| self.error("invalid escape sequence"))?;
             Ok(self.interner.get_or_intern(text))
         } else {
             err!(self.loc, err_msg)
@@ -325,10 +356,11 @@ impl<'a> Parser<'a> {
     /// Return an optional source location if no real location is present.
     fn optional_srcloc(&mut self) -> ParseResult<SourceLoc> {
         if let Some(Token::SourceLoc(text)) = self.token() {
-            match i32::from_str_radix(text, 16) {
+            // negative source locations are printed as their two's complement
+            match u32::from_str_radix(text, 16) {
                 Ok(num) => {
                     self.consume();
-                    Ok(SourceLoc::new(num))
+                    Ok(SourceLoc::new(num as i32))
                 }
                 Err(_) => err!(self.loc, "invalid source location: {}", text),
             }
@@ -460,7 +492,7 @@ impl<'a> Parser<'a> {
         match self.token() {
             Some(Token::Name(s)) => {
                 self.consume();
-                s.parse().map_err(|_| self.error("invalid test case or libcall name"))
+                unescape(s).ok_or_else(|| self.error("invalid function name"))
             }
 
             _ => err!(self.loc, "expected external name"),
@@ -666,7 +698,12 @@ impl<'a> Parser<'a> {
         ctx.function.layout.append_inst_to_bb(inst, block);
 
         if !srcloc.is_default() {
-            ctx.function.srclocs[inst] = srcloc;
+            if ctx.function.srclocs.len() <= inst.into() {
+                ctx.function.srclocs.resize(inst.into(), SourceLoc::default());
+                ctx.function.srclocs.push(srcloc);
+            } else {
+                ctx.function.srclocs[inst] = srcloc;
+            }
         }
 
         if results.len() != num_results {
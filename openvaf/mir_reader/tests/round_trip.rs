@@ -0,0 +1,37 @@
+//! Checks that the MIR in the test data (and the snapshots) can be parsed and printed again
+//! without any changes.
+
+use std::fs;
+use std::path::Path;
+
+use mini_harness::{harness, Result};
+use mir_reader::parse_functions;
+use stdx::{ignore_never, openvaf_test_data};
+
+fn is_mir_file(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("mir")
+}
+
+fn is_mir_snapshot(path: &Path) -> bool {
+    path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.ends_with("_mir.snap"))
+}
+
+fn is_snapshot(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("snap")
+}
+
+fn round_trip(file: &Path) -> Result {
+    let src = fs::read_to_string(file).unwrap();
+    let (funcs, literals) = parse_functions(&src)?;
+    let printed: Vec<_> = funcs.iter().map(|func| func.print(&literals).to_string()).collect();
+    assert_eq!(printed.join("\n"), src);
+    Ok(())
+}
+
+harness! {
+    Test::from_dir_filtered("mir", &round_trip, &is_mir_file, &ignore_never, &openvaf_test_data("mir")),
+    Test::from_dir_filtered("dae", &round_trip, &is_mir_snapshot, &ignore_never, &openvaf_test_data("dae")),
+    Test::from_dir_filtered("init", &round_trip, &is_mir_snapshot, &ignore_never, &openvaf_test_data("init")),
+    Test::from_dir_filtered("contributions", &round_trip, &is_mir_snapshot, &ignore_never, &openvaf_test_data("contributions")),
+    Test::from_dir_filtered("mir_opt", &round_trip, &is_snapshot, &ignore_never, &openvaf_test_data("mir_opt"))
+}
@@ -32,15 +32,37 @@ pub fn main_command() -> Command {
             supported_targets(),
             target_cpu(),
             codegen_opts(),
+            sensitivity(),
+            second_derivatives(),
+            diff_mode(),
             interface(),
             expand(),
             dump_json(),
+            time_passes(),
+            dump_mir(),
+            dump_mir_dir(),
+            watch(),
+            manifest(),
             input(),
         ])
+        .subcommand(fmt_command())
         .subcommand_required(false)
+        .subcommand_negates_reqs(true)
         .arg_required_else_help(true)
 }
 
+pub fn fmt_command() -> Command {
+    Command::new(FMT)
+        .about("Formats Verilog-A files in place.")
+        .long_about(
+            "Formats Verilog-A files in place.
+Only indentation and the whitespace between tokens are changed. Line breaks, comments,
+attributes and compiler directives are preserved. Branches of conditional compilation
+that are not active (using only the macros defined within the file) are kept verbatim.",
+        )
+        .args([check(), files()])
+}
+
 pub const INTERFACE: &str = "interface";
 pub const BATCHMODE: &str = "batchmode";
 pub const DRYRUN: &str = "dry-run";
@@ -49,7 +71,11 @@ pub const SUPPORTED_TARGETS: &str = "supported-targets";
 pub const LINTS: &str = "lints";
 pub const TARGET_CPU: &str = "target_cpu";
 pub const CODEGEN: &str = "codegen";
+pub const SENSITIVITY: &str = "sensitivity";
+pub const SECOND_DERIVATIVES: &str = "second-derivatives";
+pub const DIFF_MODE: &str = "diff-mode";
 pub const INPUT: &str = "input";
+pub const MANIFEST: &str = "manifest";
 pub const INCLUDE: &str = "include";
 pub const OUTPUT: &str = "output";
 pub const CACHE_DIR: &str = "cache-dir";
@@ -57,6 +83,13 @@ pub const OPT_LVL: &str = "opt_lvl";
 pub const DEFINE: &str = "define";
 pub const PRINT_EXPANSION: &str = "print-expansion";
 pub const DUMP_JSON: &str = "dump-json";
+pub const TIME_PASSES: &str = "time-passes";
+pub const DUMP_MIR: &str = "dump-mir";
+pub const DUMP_MIR_DIR: &str = "dump-mir-dir";
+pub const WATCH: &str = "watch";
+pub const FMT: &str = "fmt";
+pub const CHECK: &str = "check";
+pub const FILES: &str = "files";
 pub const ALLOW: &str = "allow";
 pub const WARN: &str = "warn";
 pub const DENY: &str = "deny";
@@ -141,10 +174,79 @@ fn codegen_opts() -> Arg {
         .value_hint(ValueHint::Other)
 }
 
+fn sensitivity() -> Arg {
+    Arg::new(SENSITIVITY)
+        .long(SENSITIVITY)
+        .help("Compute the sensitivity of the residual to a parameter.")
+        .long_help("Compute the sensitivity of the residual to a parameter.\nThe derivatives of the residual by the selected parameters are made available\nto the simulator with the load_sensitivity_resist/load_sensitivity_react OSDI functions.\nCan be passed multiple times.")
+        .value_name("PARAM")
+        .action(ArgAction::Append)
+        .required(false)
+        .value_hint(ValueHint::Other)
+}
+
+fn second_derivatives() -> Arg {
+    Arg::new(SECOND_DERIVATIVES)
+        .long(SECOND_DERIVATIVES)
+        .help("Compute the second derivatives of the residual of a node.")
+        .long_help("Compute the second derivatives of the residual of a node.\nThe derivatives of the residual of the selected node by all pairs of unknowns are made available\nto the simulator with the load_second_derivatives_resist/load_second_derivatives_react OSDI functions.\nCan be passed multiple times.")
+        .value_name("NODE")
+        .action(ArgAction::Append)
+        .required(false)
+        .value_hint(ValueHint::Other)
+}
+
+fn diff_mode() -> Arg {
+    Arg::new(DIFF_MODE)
+        .long(DIFF_MODE)
+        .help("Select how derivatives are generated.")
+        .long_help("Select how the derivatives of the residual are generated.\n\npossible values\n\nforward - propagate the derivatives by all unknowns alongside each value\nreverse - propagate adjoints backwards from every residual, faster for models with many internal nodes")
+        .value_name("MODE")
+        .value_hint(ValueHint::Other)
+        .value_parser(["forward", "reverse"])
+        .hide_possible_values(true)
+        .default_value("forward")
+        .required(false)
+}
+
 fn input() -> Arg {
     input_file_path_arg(INPUT)
-        .help("The root Verilog-A file.")
-        .required_unless_present_any([LINTS, SUPPORTED_TARGETS])
+        .help("The root Verilog-A file(s).")
+        .long_help("The root Verilog-A file(s).\nIf multiple files are passed, the modules of all files are compiled into a single library.")
+        .num_args(1..)
+        .action(ArgAction::Append)
+        .required_unless_present_any([LINTS, SUPPORTED_TARGETS, MANIFEST])
+}
+
+fn files() -> Arg {
+    input_file_path_arg(FILES)
+        .help("The Verilog-A files to format.")
+        .num_args(1..)
+        .action(ArgAction::Append)
+        .required(true)
+}
+
+fn check() -> Arg {
+    flag(CHECK, CHECK)
+        .help("Check whether the files are formatted without modifying them.")
+        .long_help(
+            "Check whether the files are formatted without modifying them.
+Files that are not formatted are listed and a non-zero exit code is returned.",
+        )
+}
+
+fn manifest() -> Arg {
+    input_file_path_arg(MANIFEST)
+        .long(MANIFEST)
+        .help("Compile all files listed in a manifest into a single library.")
+        .long_help(
+            "Compile all files listed in a manifest into a single library.
+Each line of the manifest contains a root Verilog-A file optionally followed by
+-D MACRO[=VALUE] and -I DIR arguments that only apply to that file.
+Relative paths are resolved relative to the manifest. Lines starting with # are ignored.",
+        )
+        .conflicts_with(INPUT)
+        .required(false)
 }
 
 fn include_dir() -> Arg {
@@ -271,6 +373,70 @@ fn dump_json() -> Arg {
     flag(DUMP_JSON, "dump-json").help("Abort after lowering and serialize MIR as json.")
 }
 
+fn time_passes() -> Arg {
+    flag(TIME_PASSES, TIME_PASSES).help("Print how long each compilation stage takes.").long_help(
+        "Print how long each compilation stage takes.
+The time of each stage is summed over all modules. Modules are compiled in parallel
+so the total can exceed the wall time of the compilation.
+Can also be enabled by setting the OPENVAF_TIME_PASSES environment variable.",
+    )
+}
+
+fn dump_mir() -> Arg {
+    Arg::new(DUMP_MIR)
+        .long(DUMP_MIR)
+        .help("Write the MIR to files after the selected stages.")
+        .long_help(
+            "Write the MIR to files after the selected stages.
+Accepts a comma separated list of stages or all. The MIR of each module is written
+to <DUMP_MIR_DIR>/<module>.<index>.<stage>.mir.
+Can also be set with the OPENVAF_DUMP_MIR environment variable.
+
+possible values
+
+lower - directly after lowering
+optimize - after the initial optimization
+topology - after the branches and noise sources were extracted
+dae - after the residual and jacobian were built
+sparsify - after the DAE system was optimized again
+hoist - after operating point independent code was hoisted
+init - after operating point independent code was moved to the instance setup
+model_param_setup - after the parameter setup functions were built",
+        )
+        .value_name("STAGES")
+        .value_hint(ValueHint::Other)
+        .required(false)
+}
+
+fn dump_mir_dir() -> Arg {
+    Arg::new(DUMP_MIR_DIR)
+        .long(DUMP_MIR_DIR)
+        .help("Directory the MIR is written to by --dump-mir.")
+        .long_help(
+            "Directory the MIR is written to by --dump-mir.
+Defaults to mir_dump (or the OPENVAF_DUMP_MIR_DIR environment variable).",
+        )
+        .value_name("DIR")
+        .value_hint(ValueHint::DirPath)
+        .value_parser(ValueParser::new(|raw: &str| {
+            Ok::<_, anyhow::Error>(Utf8Path::new(raw).to_owned())
+        }))
+        .requires(DUMP_MIR)
+        .required(false)
+}
+
+fn watch() -> Arg {
+    flag(WATCH, WATCH)
+        .short('w')
+        .help("Recompile whenever a source file changes.")
+        .long_help(
+            "Recompile whenever a source file changes.
+The root files and all included files are watched for changes.
+The output library is replaced atomically so simulators can reload it at any time.",
+        )
+        .conflicts_with_all([DRYRUN, PRINT_EXPANSION, DUMP_JSON])
+}
+
 fn def_arg() -> Arg {
     Arg::new(DEFINE)
         .short('D')
@@ -1,15 +1,21 @@
+use std::fs;
 use std::io::Write;
 use std::process::exit;
+use std::sync::Arc;
 
 use anyhow::{bail, Context, Result};
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::ArgMatches;
-use openvaf::{builtin_lints, get_target_names, host_triple, AbsPathBuf, LintLevel, OptLevel};
+use openvaf::{
+    builtin_lints, get_target_names, host_triple, AbsPathBuf, DebugOpts, DiffMode, Input,
+    LintLevel, MirDump, OptLevel,
+};
 use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
 
 use crate::cli_def::{
-    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, DEFINE, DENY, DRYRUN, INCLUDE, INPUT, LINTS, OPT_LVL,
-    OUTPUT, SUPPORTED_TARGETS, TARGET, TARGET_CPU, WARN,
+    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, DEFINE, DENY, DIFF_MODE, DRYRUN, DUMP_MIR, DUMP_MIR_DIR,
+    INCLUDE, INPUT, LINTS, MANIFEST, OPT_LVL, OUTPUT, SECOND_DERIVATIVES, SENSITIVITY,
+    SUPPORTED_TARGETS, TARGET, TARGET_CPU, TIME_PASSES, WARN,
 };
 use crate::{CompilationDestination, Opts};
 
@@ -23,7 +29,16 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         exit(0)
     }
 
-    let input: Utf8PathBuf = matches.get_one::<Utf8PathBuf>(INPUT).unwrap().clone();
+    let manifest = matches.get_one::<Utf8PathBuf>(MANIFEST);
+    let inputs = if let Some(manifest) = manifest {
+        read_manifest(manifest)?
+    } else {
+        matches
+            .get_many::<Utf8PathBuf>(INPUT)
+            .unwrap()
+            .map(|path| Input::new(path.clone()))
+            .collect()
+    };
 
     let mut lints = Vec::new();
 
@@ -60,8 +75,12 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
     } else {
         let lib_file = if let Some(output) = matches.get_one::<Utf8PathBuf>(OUTPUT) {
             output.clone()
+        } else if let Some(manifest) = manifest {
+            manifest.with_extension("osdi")
+        } else if let [input] = &*inputs {
+            input.path.with_extension("osdi")
         } else {
-            input.with_extension("osdi")
+            bail!("no output file specified\nhelp: use --output to set the output file when compiling multiple files")
         };
 
         CompilationDestination::Path { lib_file }
@@ -71,6 +90,20 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         .get_many::<String>(CODEGEN)
         .map_or_else(Vec::new, |values| values.cloned().collect());
 
+    let sensitivities = matches
+        .get_many::<String>(SENSITIVITY)
+        .map_or_else(Vec::new, |values| values.cloned().collect());
+
+    let second_derivatives = matches
+        .get_many::<String>(SECOND_DERIVATIVES)
+        .map_or_else(Vec::new, |values| values.cloned().collect());
+
+    let diff_mode = match &**matches.get_one::<String>(DIFF_MODE).unwrap() {
+        "forward" => DiffMode::Forward,
+        "reverse" => DiffMode::Reverse,
+        mode => bail!("unknown diff mode {mode}"),
+    };
+
     let defines = matches
         .get_many::<String>(DEFINE)
         .map_or_else(Vec::new, |values| values.cloned().collect());
@@ -104,10 +137,15 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
     let target_cpu: String =
         matches.get_one(TARGET_CPU).cloned().unwrap_or_else(|| default_cpu.to_owned());
 
+    let debug = debug_opts(&matches)?;
+
     Ok(Opts {
-        input,
+        inputs,
         lints,
         codegen_opts,
+        sensitivities,
+        second_derivatives,
+        diff_mode,
         defines,
         include,
         output,
@@ -115,9 +153,83 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         target,
         target_cpu,
         dry_run: matches.get_flag(DRYRUN),
+        debug,
     })
 }
 
+/// The command line arguments take precedence over the `OPENVAF_TIME_PASSES`
+/// and `OPENVAF_DUMP_MIR` environment variables.
+fn debug_opts(matches: &ArgMatches) -> Result<DebugOpts> {
+    let mut debug = DebugOpts::from_env().map_err(anyhow::Error::msg)?;
+    if matches.get_flag(TIME_PASSES) {
+        debug.time_passes = Some(Arc::default());
+    }
+    if let Some(stages) = matches.get_one::<String>(DUMP_MIR) {
+        let dir = match matches.get_one::<Utf8PathBuf>(DUMP_MIR_DIR) {
+            Some(dir) => dir.clone().into(),
+            None => {
+                debug.dump_mir.as_ref().map_or_else(|| "mir_dump".into(), |dump| dump.dir.clone())
+            }
+        };
+        let dump = MirDump::new(dir, stages).map_err(anyhow::Error::msg)?;
+        debug.dump_mir = Some(Arc::new(dump));
+    }
+    Ok(debug)
+}
+
+/// Reads a manifest that lists one root file per line.
+/// Each file may be followed by `-D` and `-I` arguments that only apply to that file:
+///
+/// ```text
+/// # comment
+/// bsim4.va -D BSIM4_NOISE
+/// psp103.va -I psp103/include
+/// ```
+fn read_manifest(manifest: &Utf8Path) -> Result<Vec<Input>> {
+    let contents =
+        fs::read_to_string(manifest).with_context(|| format!("failed to read {manifest}"))?;
+    let dir = manifest.parent().unwrap_or(Utf8Path::new(""));
+
+    let mut inputs = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let location = || format!("{manifest}:{}", i + 1);
+
+        let mut args = line.split_whitespace();
+        let mut input = Input::new(dir.join(args.next().unwrap()));
+        while let Some(arg) = args.next() {
+            let (flag, inline_val) =
+                if arg.is_char_boundary(2) { arg.split_at(2) } else { (arg, "") };
+            let val = if inline_val.is_empty() { args.next() } else { Some(inline_val) };
+            let val = val.with_context(|| format!("{}: missing value for {flag}", location()))?;
+            match flag {
+                "-D" => input.defines.push(val.to_owned()),
+                "-I" => {
+                    let path = dir.join(val);
+                    let path = path
+                        .canonicalize()
+                        .with_context(|| format!("{}: failed to resolve {path}", location()))?;
+                    input.include.push(AbsPathBuf::assert(path))
+                }
+                _ => bail!(
+                    "{}: unknown argument {arg}\nhelp: only -D and -I are allowed",
+                    location()
+                ),
+            }
+        }
+        inputs.push(input);
+    }
+
+    if inputs.is_empty() {
+        bail!("{manifest} does not contain any files")
+    }
+
+    Ok(inputs)
+}
+
 fn print_lints() {
     let mut stdout = termcolor::StandardStream::stdout(ColorChoice::Auto);
 
@@ -8,10 +8,12 @@ use clap::ArgMatches;
 use mimalloc::MiMalloc;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-use cli_def::{main_command, INPUT};
-use openvaf::{compile, expand, CompilationDestination, CompilationTermination, Opts};
+use cli_def::{main_command, FILES, FMT, INPUT, MANIFEST};
+use openvaf::{
+    compile, expand, format_files, watch, CompilationDestination, CompilationTermination, Opts,
+};
 
-use crate::cli_def::{DUMP_JSON, PRINT_EXPANSION};
+use crate::cli_def::{CHECK, DUMP_JSON, PRINT_EXPANSION, WATCH};
 use crate::cli_process::matches_to_opts;
 
 mod cli_def;
@@ -25,7 +27,15 @@ static GLOBAL: MiMalloc = MiMalloc;
 pub fn main() {
     let matches = main_command().get_matches();
     crash_report::install_panic_handler();
-    let input: Utf8PathBuf = matches.get_one(INPUT).cloned().unwrap_or_else(Utf8PathBuf::new);
+    let (action, input) = if let Some(fmt_matches) = matches.subcommand_matches(FMT) {
+        let files = fmt_matches.get_many::<Utf8PathBuf>(FILES).into_iter().flatten();
+        ("format", files.map(|path| path.as_str()).collect::<Vec<_>>().join(", "))
+    } else if let Some(manifest) = matches.get_one::<Utf8PathBuf>(MANIFEST) {
+        ("compile", manifest.to_string())
+    } else {
+        let inputs = matches.get_many::<Utf8PathBuf>(INPUT).into_iter().flatten();
+        ("compile", inputs.map(|path| path.as_str()).collect::<Vec<_>>().join(", "))
+    };
     let env = env_logger::Env::default().filter("OPENVAF_LOG").write_style("OPENVAF_LOG_STYLE");
     env_logger::Builder::new()
         .format_timestamp(None)
@@ -52,7 +62,7 @@ pub fn main() {
             stderr.set_color(ColorSpec::new().set_bold(true)).unwrap();
             write!(&mut stderr, ":").unwrap();
             stderr.set_color(&ColorSpec::new()).unwrap();
-            writeln!(&mut stderr, " failed to compile {input}").unwrap();
+            writeln!(&mut stderr, " failed to {action} {input}").unwrap();
         }
     }
 }
@@ -60,10 +70,18 @@ pub fn main() {
 pub const DATA_ERROR: i32 = 65;
 
 fn wrapped_main(matches: ArgMatches) -> Result<i32> {
+    if let Some(fmt_matches) = matches.subcommand_matches(FMT) {
+        return fmt(fmt_matches);
+    }
     let print_expansion = matches.get_flag(PRINT_EXPANSION);
     let dump_json_ = matches.get_flag(DUMP_JSON);
+    let watch_ = matches.get_flag(WATCH);
     let opts = matches_to_opts(matches)?;
     *ARGS.lock().unwrap() = Some(opts.clone());
+    if watch_ {
+        watch(&opts)?;
+        return Ok(0);
+    }
     if print_expansion {
         let res = match expand(&opts)? {
             CompilationTermination::Compiled { .. } => 0,
@@ -92,3 +110,17 @@ fn wrapped_main(matches: ArgMatches) -> Result<i32> {
 
     Ok(res)
 }
+
+fn fmt(matches: &ArgMatches) -> Result<i32> {
+    let check = matches.get_flag(CHECK);
+    let files: Vec<_> =
+        matches.get_many::<Utf8PathBuf>(FILES).into_iter().flatten().cloned().collect();
+    let changed = format_files(&files, check)?;
+    if check && !changed.is_empty() {
+        for file in changed {
+            println!("{file} is not formatted");
+        }
+        return Ok(1);
+    }
+    Ok(0)
+}
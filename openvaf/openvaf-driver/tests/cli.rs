@@ -45,8 +45,38 @@ fn link_diode() -> Result {
     Ok(())
 }
 
+fn link_bundle() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf");
+    xshell::cmd!(
+        sh,
+        "{openvaf} -O 0 -o integration_tests/bundle.osdi integration_tests/DIODE/diode.va integration_tests/RESISTOR/resistor.va"
+    )
+    .run()?;
+    sh.remove_path("integration_tests/bundle.osdi").unwrap();
+    Ok(())
+}
+
+fn fmt_check() -> Result {
+    let sh = xshell::Shell::new().unwrap();
+    sh.change_dir(project_root());
+    let openvaf = cargo_bin("openvaf");
+    xshell::cmd!(sh, "{openvaf} fmt --check openvaf/test_data/fmt/module.va_fmt").run()?;
+    if xshell::cmd!(sh, "{openvaf} fmt --check openvaf/test_data/fmt/module.va")
+        .quiet()
+        .run()
+        .is_ok()
+    {
+        return Err("unformatted file passed the check".into());
+    }
+    Ok(())
+}
+
 harness! {
     Test::new("cli::link_diode", &link_diode),
+    Test::new("cli::fmt_check", &fmt_check),
+    Test::new("cli::link_bundle", &link_bundle),
     Test::from_list(
         "cli::smoke_test",
          &smoke_test,
@@ -0,0 +1,215 @@
+//! Compares calling the OSDI `eval` function once per instance with a single call to
+//! `eval_soa` for a large number of instances of the same model. The instances are packed
+//! into the structure of arrays layout once upfront (like a simulator that stores its
+//! instances in that layout would), so packing is not part of the measurement.
+//! Run with `cargo bench -p openvaf --bench eval_soa`.
+
+use std::ptr;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+use libc::{c_char, c_void};
+use llvm::OptLevel;
+use openvaf::{CompilationDestination, CompilationTermination, DebugOpts, DiffMode};
+use stdx::iter::zip;
+use stdx::project_root;
+use target::spec::Target;
+
+use crate::load::{load_osdi_lib, EvalFlags, OsdiInstance, OsdiModel, OsdiSimInfo, OsdiSimParas};
+
+#[allow(dead_code)]
+#[path = "../tests/load/mod.rs"]
+mod load;
+
+const NUM_INSTANCES: usize = 4096;
+const NUM_ITERATIONS: usize = 20;
+
+/// Instances that are connected to disjoint nodes of a shared solution vector
+/// (like in a circuit of many identical transistors).
+struct Circuit {
+    model: OsdiModel,
+    instances: Vec<OsdiInstance>,
+    handles: Vec<*mut c_void>,
+    /// the instance data of all instances in the structure of arrays layout
+    soa: Vec<u64>,
+    ret_flags: Vec<u32>,
+    solve: Vec<f64>,
+    prev_state: Vec<f64>,
+    next_state: Vec<f64>,
+    /// null terminated (empty) list of simulator parameters
+    sim_params: [*mut c_char; 1],
+    // the instances write into these through the pointers stored in the instance data
+    _matrix_resist: Vec<f64>,
+    _matrix_react: Vec<f64>,
+}
+
+impl Circuit {
+    fn new(dir: &str, file: &str) -> Result<Circuit> {
+        let root: Utf8PathBuf = project_root().join("integration_tests").try_into().unwrap();
+        let root_file = root.join(dir).join(file);
+        let opts = openvaf::Opts {
+            defines: Vec::new(),
+            codegen_opts: Vec::new(),
+            sensitivities: Vec::new(),
+            second_derivatives: Vec::new(),
+            diff_mode: DiffMode::Forward,
+            lints: Vec::new(),
+            inputs: vec![openvaf::Input::new(root_file.clone())],
+            output: CompilationDestination::Path { lib_file: root_file.with_extension("osdi") },
+            include: Vec::new(),
+            opt_lvl: OptLevel::Aggressive,
+            target: Target::host_target().unwrap(),
+            target_cpu: "native".to_owned(),
+            debug: DebugOpts::default(),
+            dry_run: false,
+        };
+        let lib_file = match openvaf::compile(&opts)? {
+            CompilationTermination::Compiled { lib_file } => lib_file,
+            CompilationTermination::FatalDiagnostic => {
+                anyhow::bail!("openvaf: compilation of {root_file} failed")
+            }
+        };
+        let desc = unsafe { &load_osdi_lib(&lib_file)?[0] };
+
+        let model = desc.new_model();
+        model.process_params()?;
+
+        let num_entries = desc.num_jacobian_entries as usize;
+        let num_states = desc.num_states as usize;
+        let mut matrix_resist = vec![0.0; NUM_INSTANCES * num_entries];
+        let mut matrix_react = vec![0.0; NUM_INSTANCES * num_entries];
+        // node 0 is ground
+        let mut num_nodes = 1;
+        let mut instances = Vec::with_capacity(NUM_INSTANCES);
+        for i in 0..NUM_INSTANCES {
+            let mut instance = model.new_instance();
+            let internal_nodes = instance.process_params(&model, desc.num_terminals, 300.0)?;
+            for node in instance.node_mapping() {
+                let idx = node.get();
+                node.set(if idx == u32::MAX { 0 } else { num_nodes + idx });
+            }
+            num_nodes += desc.num_terminals + internal_nodes.len() as u32;
+
+            for (j, (entry, ptr_resist)) in
+                zip(desc.matrix_entries(), instance.matrix_ptrs_resist()).enumerate()
+            {
+                let pos = i * num_entries + j;
+                unsafe {
+                    ptr_resist.set(matrix_resist.as_mut_ptr().add(pos));
+                    if entry.react_ptr_off != u32::MAX {
+                        let react_ptr: *mut *mut f64 =
+                            (instance.data as *mut u8).add(entry.react_ptr_off as usize).cast();
+                        *react_ptr = matrix_react.as_mut_ptr().add(pos);
+                    }
+                }
+            }
+
+            for j in 0..num_states {
+                unsafe {
+                    let state_idx: *mut u32 =
+                        (instance.data as *mut u8).add(desc.state_idx_off as usize).cast();
+                    *state_idx.add(j) = (i * num_states + j) as u32;
+                }
+            }
+
+            instances.push(instance);
+        }
+
+        // use a different operating point for each node
+        let solve = (0..num_nodes).map(|node| 0.1 + 0.8 * (node % 13) as f64 / 13.0).collect();
+        let handles = instances.iter().map(|_| b"bench\0".as_ptr() as *mut c_void).collect();
+        let mut soa = vec![0u64; (NUM_INSTANCES * desc.instance_size as usize).div_ceil(8)];
+        for (i, instance) in instances.iter().enumerate() {
+            desc.pack_soa(
+                soa.as_mut_ptr() as *mut c_void,
+                NUM_INSTANCES as u32,
+                i as u32,
+                instance.data,
+            );
+        }
+
+        Ok(Circuit {
+            model,
+            instances,
+            handles,
+            soa,
+            ret_flags: vec![0; NUM_INSTANCES],
+            solve,
+            prev_state: vec![0.0; NUM_INSTANCES * num_states],
+            next_state: vec![0.0; NUM_INSTANCES * num_states],
+            sim_params: [ptr::null_mut()],
+            _matrix_resist: matrix_resist,
+            _matrix_react: matrix_react,
+        })
+    }
+
+    fn sim_info(&mut self) -> OsdiSimInfo {
+        let flags = EvalFlags::CALC_RESIST_JACOBIAN
+            | EvalFlags::CALC_RESIST_RESIDUAL
+            | EvalFlags::CALC_REACT_JACOBIAN
+            | EvalFlags::CALC_REACT_RESIDUAL
+            | EvalFlags::ANALYSIS_TRAN;
+        OsdiSimInfo {
+            paras: OsdiSimParas {
+                names: self.sim_params.as_mut_ptr(),
+                vals: ptr::null_mut(),
+                names_str: self.sim_params.as_mut_ptr(),
+                vals_str: ptr::null_mut(),
+            },
+            abstime: 0.0,
+            prev_solve: self.solve.as_mut_ptr(),
+            prev_state: self.prev_state.as_mut_ptr(),
+            next_state: self.next_state.as_mut_ptr(),
+            flags: flags.bits(),
+        }
+    }
+
+    fn eval_scalar(&mut self) -> Duration {
+        let mut sim_info = self.sim_info();
+        let desc = self.model.descriptor;
+        let start = Instant::now();
+        for _ in 0..NUM_ITERATIONS {
+            for (&handle, instance) in zip(&self.handles, &self.instances) {
+                desc.eval(handle, instance.data, self.model.data, &mut sim_info);
+            }
+        }
+        start.elapsed()
+    }
+
+    fn eval_soa(&mut self) -> Duration {
+        let mut sim_info = self.sim_info();
+        let desc = self.model.descriptor;
+        let start = Instant::now();
+        for _ in 0..NUM_ITERATIONS {
+            desc.eval_soa(
+                self.handles.as_mut_ptr(),
+                self.soa.as_mut_ptr() as *mut c_void,
+                self.model.data,
+                &mut sim_info,
+                NUM_INSTANCES as u32,
+                self.ret_flags.as_mut_ptr(),
+            );
+        }
+        start.elapsed()
+    }
+}
+
+fn main() -> Result<()> {
+    for (dir, file) in [("PSP103", "psp103.va"), ("BSIM4", "bsim4.va")] {
+        let mut circuit = Circuit::new(dir, file)?;
+        // warm up caches
+        circuit.eval_scalar();
+        circuit.eval_soa();
+        let scalar = circuit.eval_scalar();
+        let soa = circuit.eval_soa();
+        let per_eval = |time: Duration| time / (NUM_INSTANCES * NUM_ITERATIONS) as u32;
+        println!(
+            "{dir}: eval {:?}/instance, eval_soa {:?}/instance (speedup {:.2}x)",
+            per_eval(scalar),
+            per_eval(soa),
+            scalar.as_secs_f64() / soa.as_secs_f64()
+        );
+    }
+    Ok(())
+}
@@ -8,8 +8,7 @@ use hir::CompilationDB;
 use crate::Opts;
 
 // TODO: use high level hir API instead of low leve database API
-fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
-    let mut hash_builder = md5::Context::new();
+fn hash(hash_builder: &mut md5::Context, db: &CompilationDB, defines: &[String]) {
     let cu = db.compilation_unit();
 
     // hash settings
@@ -20,7 +19,6 @@ fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
         hash_builder.consume(def)
     }
 
-    hash_builder.consume(env!("CARGO_PKG_VERSION"));
     let lints = db.global_lint_overwrites(cu.root_file());
     if cfg!(debug_assertions) && !lints.is_empty() {
         assert_eq!(size_of::<Option<LintLevel>>(), size_of_val(&lints.raw[0]));
@@ -44,12 +42,26 @@ fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
             hash_builder.consume(" ");
         }
     }
-
-    hash_builder.compute()
 }
 
-pub fn file_name(db: &CompilationDB, opts: &Opts) -> String {
-    let hash = u128::from_ne_bytes(*hash(db, &opts.defines));
+pub fn file_name(dbs: &[CompilationDB], opts: &Opts) -> String {
+    let mut hash_builder = md5::Context::new();
+    hash_builder.consume(env!("CARGO_PKG_VERSION"));
+    hash_builder.consume(dbs.len().to_ne_bytes());
+    for (db, input) in dbs.iter().zip(&opts.inputs) {
+        let defines: Vec<_> = input.all_defines(opts).cloned().collect();
+        hash(&mut hash_builder, db, &defines);
+    }
+    hash_builder.consume(opts.sensitivities.len().to_ne_bytes());
+    for param in &opts.sensitivities {
+        hash_builder.consume(param)
+    }
+    hash_builder.consume(opts.second_derivatives.len().to_ne_bytes());
+    for node in &opts.second_derivatives {
+        hash_builder.consume(node)
+    }
+    hash_builder.consume([opts.diff_mode as u8, opts.opt_lvl as u8]);
+    let hash = u128::from_ne_bytes(*hash_builder.compute());
     let hash = base_n::encode(hash, base_n::CASE_INSENSITIVE);
     format!("{}.osdi", hash)
 }
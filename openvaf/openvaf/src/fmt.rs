@@ -0,0 +1,24 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+use camino::Utf8PathBuf;
+
+pub use formatter::{format, FormatError};
+
+/// Formats each of `files` in place. If `check` is set the files are not modified.
+/// Returns the files whose formatting was (or would be) changed.
+pub fn format_files(files: &[Utf8PathBuf], check: bool) -> Result<Vec<Utf8PathBuf>> {
+    let mut changed = Vec::new();
+    for file in files {
+        let src = fs::read_to_string(file).with_context(|| format!("failed to read {file}"))?;
+        let formatted = format(&src).with_context(|| format!("failed to format {file}"))?;
+        if formatted == src {
+            continue;
+        }
+        if !check {
+            fs::write(file, &formatted).with_context(|| format!("failed to write {file}"))?;
+        }
+        changed.push(file.clone());
+    }
+    Ok(changed)
+}
@@ -0,0 +1,55 @@
+use std::ffi::c_void;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use llvm::orc::Jit;
+use mir_llvm::LLVMBackend;
+use target::spec::Target;
+
+use crate::{collect_units, compilation_dbs, print_finished, Opts};
+
+pub enum JitTermination {
+    Compiled { lib: JitLibrary },
+    FatalDiagnostic,
+}
+
+/// An OSDI library that was compiled into the memory of the current process by [`compile_jit`].
+/// The code of the library (and all pointers into it) are only valid while it is alive.
+pub struct JitLibrary {
+    jit: Jit,
+}
+
+impl JitLibrary {
+    /// Returns the address of the symbol `name` exported by the library (for example
+    /// `OSDI_DESCRIPTORS`).
+    pub fn get(&self, name: &str) -> Result<*mut c_void> {
+        let res = self.jit.lookup(name).with_context(|| format!("failed to find symbol {name}"))?;
+        Ok(res)
+    }
+}
+
+/// Compiles the inputs of `opts` into the memory of the current process without
+/// writing a library to disk or invoking a linker. [`Opts::output`] and
+/// [`Opts::dry_run`] are ignored and [`Opts::target`] must be the host target.
+pub fn compile_jit(opts: &Opts) -> Result<JitTermination> {
+    let start = Instant::now();
+    let host =
+        Target::host_target().context("openvaf does currently not support this hardware/os")?;
+    if host.llvm_target != opts.target.llvm_target {
+        bail!("can not JIT compile for {} on {}", opts.target.llvm_target, host.llvm_target);
+    }
+
+    let dbs = compilation_dbs(opts)?;
+    let modules = match opts.debug.time("frontend", || collect_units(opts, &dbs))? {
+        Some(modules) => modules,
+        None => return Ok(JitTermination::FatalDiagnostic),
+    };
+
+    let back = LLVMBackend::new(&opts.codegen_opts, &opts.target, opts.target_cpu.clone(), &[]);
+    let jit = Jit::new()?;
+    let units: Vec<_> = dbs.iter().zip(&modules).map(|(db, modules)| (db, &**modules)).collect();
+    osdi::compile_bundle_jit(&units, "osdi_jit", &opts.target, &back, opts.opt_lvl, &jit)?;
+
+    print_finished(opts, start)?;
+    Ok(JitTermination::Compiled { lib: JitLibrary { jit } })
+}
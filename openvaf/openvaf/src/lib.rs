@@ -1,26 +1,36 @@
-use std::fs::{create_dir_all, remove_file};
+use std::collections::HashMap;
+use std::fs::{create_dir_all, remove_file, rename};
 use std::io::Write;
 use std::time::Instant;
 
-use anyhow::Context;
 use anyhow::Result;
+use anyhow::{bail, Context};
 use basedb::diagnostics::{ConsoleSink, DiagnosticSink};
 use basedb::BaseDB;
-use camino::Utf8PathBuf;
-use hir::CompilationDB;
+use camino::{Utf8Path, Utf8PathBuf};
+use hir::{CompilationDB, Type};
 use linker::link;
 use mir_llvm::LLVMBackend;
-use sim_back::collect_modules;
+use sim_back::{collect_modules, ModuleInfo};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
 pub use basedb::lints::builtin as builtin_lints;
 pub use basedb::lints::LintLevel;
 pub use llvm::OptLevel;
 pub use paths::AbsPathBuf;
+pub use sim_back::debug::{DebugOpts, MirDump, PassTimings};
+pub use sim_back::DiffMode;
 pub use target::host_triple;
 pub use target::spec::{get_target_names, Target};
 
 mod cache;
+mod fmt;
+mod jit;
+mod watch;
+
+pub use fmt::{format, format_files, FormatError};
+pub use jit::{compile_jit, JitLibrary, JitTermination};
+pub use watch::watch;
 
 #[derive(Debug, Clone)]
 pub enum CompilationDestination {
@@ -33,18 +43,74 @@ pub enum CompilationTermination {
     FatalDiagnostic,
 }
 
+/// A root Verilog-A file that is compiled as a separate compilation unit.
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub path: Utf8PathBuf,
+    /// Additional macro definitions for this file (appended to [`Opts::defines`]).
+    pub defines: Vec<String>,
+    /// Additional include directories for this file (searched before [`Opts::include`]).
+    pub include: Vec<AbsPathBuf>,
+}
+
+impl Input {
+    pub fn new(path: Utf8PathBuf) -> Input {
+        Input { path, defines: Vec::new(), include: Vec::new() }
+    }
+
+    pub(crate) fn all_defines<'a>(
+        &'a self,
+        opts: &'a Opts,
+    ) -> impl Iterator<Item = &'a String> + 'a {
+        opts.defines.iter().chain(&self.defines)
+    }
+
+    fn compilation_db(&self, opts: &Opts) -> Result<CompilationDB> {
+        let path =
+            self.path.canonicalize().with_context(|| format!("failed to resolve {}", self.path))?;
+        let include: Vec<_> = self.include.iter().chain(&opts.include).cloned().collect();
+        let defines: Vec<_> = self.all_defines(opts).cloned().collect();
+        CompilationDB::new_fs(AbsPathBuf::assert(path), &include, &defines, &opts.lints)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Opts {
     pub dry_run: bool,
     pub defines: Vec<String>,
     pub codegen_opts: Vec<String>,
+    /// Names of the parameters that are treated as differentiation variables.
+    /// The derivatives of the residual by these parameters are exposed with the
+    /// `load_sensitivity_resist`/`load_sensitivity_react` OSDI functions.
+    pub sensitivities: Vec<String>,
+    /// Names of the nodes whose residuals are differentiated twice by the unknowns.
+    /// The second derivatives are exposed with the
+    /// `load_second_derivatives_resist`/`load_second_derivatives_react` OSDI functions.
+    pub second_derivatives: Vec<String>,
+    /// How the derivatives of the residual are generated. Reverse mode can be
+    /// faster to compile and evaluate for models with many internal nodes.
+    pub diff_mode: DiffMode,
     pub lints: Vec<(String, LintLevel)>,
-    pub input: Utf8PathBuf,
+    /// The root files that are compiled. All modules are linked into a single library.
+    pub inputs: Vec<Input>,
     pub output: CompilationDestination,
     pub include: Vec<AbsPathBuf>,
     pub opt_lvl: OptLevel,
     pub target: Target,
     pub target_cpu: String,
+    /// Options for debugging the compiler: timing of the compilation stages
+    /// and dumping the MIR after each stage.
+    pub debug: DebugOpts,
+}
+
+impl Opts {
+    /// Human readable name of the compiled inputs used in status messages.
+    pub fn name(&self) -> String {
+        match &*self.inputs {
+            [input] => input.path.file_name().unwrap_or_default().to_owned(),
+            inputs => format!("{} files", inputs.len()),
+        }
+    }
 }
 // pub fn dump_json(opts: &Opts) -> Result<CompilationTermination> {
 //     let input =
@@ -107,25 +173,24 @@ pub struct Opts {
 pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     let start = Instant::now();
 
-    let input =
-        opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
-    let input = AbsPathBuf::assert(input);
-    let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
-    let cu = db.compilation_unit();
-
-    let preprocess = cu.preprocess(&db);
-    for token in preprocess.ts.iter() {
-        let span = token.span.to_file_span(&preprocess.sm);
-        let text = db.file_text(span.file).unwrap();
-        print!("{}", &text[span.range]);
-    }
-    println!();
+    for input in &opts.inputs {
+        let db = input.compilation_db(opts)?;
+        let cu = db.compilation_unit();
+
+        let preprocess = cu.preprocess(&db);
+        for token in preprocess.ts.iter() {
+            let span = token.span.to_file_span(&preprocess.sm);
+            let text = db.file_text(span.file).unwrap();
+            print!("{}", &text[span.range]);
+        }
+        println!();
 
-    let mut sink = ConsoleSink::new(&db);
-    sink.add_diagnostics(&*preprocess.diagnostics, cu.root_file(), &db);
+        let mut sink = ConsoleSink::new(&db);
+        sink.add_diagnostics(&*preprocess.diagnostics, cu.root_file(), &db);
 
-    if sink.summary(&opts.input.file_name().unwrap()) {
-        return Ok(CompilationTermination::FatalDiagnostic);
+        if sink.summary(&input.path.file_name().unwrap()) {
+            return Ok(CompilationTermination::FatalDiagnostic);
+        }
     }
 
     let seconds = Instant::elapsed(&start).as_secs_f64();
@@ -133,24 +198,31 @@ pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     stderr.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
     write!(&mut stderr, "Finished")?;
     stderr.set_color(&ColorSpec::new())?;
-    writeln!(&mut stderr, " preprocessing {} in {:.2}s", opts.input.file_name().unwrap(), seconds)?;
+    writeln!(&mut stderr, " preprocessing {} in {:.2}s", opts.name(), seconds)?;
 
     Ok(CompilationTermination::Compiled { lib_file: Utf8PathBuf::default() })
 }
 
 pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
     let start = Instant::now();
+    let dbs = compilation_dbs(opts)?;
+    compile_dbs(opts, &dbs, start)
+}
 
-    let input =
-        opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
-    let input = AbsPathBuf::assert(input);
-    let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+fn compilation_dbs(opts: &Opts) -> Result<Vec<CompilationDB>> {
+    opts.inputs.iter().map(|input| input.compilation_db(opts)).collect()
+}
 
+fn compile_dbs(
+    opts: &Opts,
+    dbs: &[CompilationDB],
+    start: Instant,
+) -> Result<CompilationTermination> {
     let lib_file = match &opts.output {
         CompilationDestination::Cache { cache_dir } => {
-            let file_name = cache::file_name(&db, opts);
+            let file_name = cache::file_name(dbs, opts);
             let lib_file = cache_dir.join(file_name);
-            if cfg!(not(debug_assertions)) && lib_file.exists() {
+            if cfg!(not(debug_assertions)) && lib_file.exists() && !opts.debug.enabled() {
                 return Ok(CompilationTermination::Compiled { lib_file });
             }
             create_dir_all(cache_dir).context("failed to create cache directory")?;
@@ -159,34 +231,157 @@ pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
         CompilationDestination::Path { lib_file } => lib_file.clone(),
     };
 
-    let modules = if let Some(modules) = collect_modules(&db, false, &mut ConsoleSink::new(&db)) {
-        modules
-    } else {
-        return Ok(CompilationTermination::FatalDiagnostic);
+    let modules = match opts.debug.time("frontend", || collect_units(opts, dbs))? {
+        Some(modules) => modules,
+        None => return Ok(CompilationTermination::FatalDiagnostic),
     };
 
     let back = LLVMBackend::new(&opts.codegen_opts, &opts.target, opts.target_cpu.clone(), &[]);
     if opts.dry_run {
         return Ok(CompilationTermination::Compiled { lib_file });
     }
-    let paths = osdi::compile(&db, &modules, &lib_file, &opts.target, &back, true, opts.opt_lvl);
+    let units: Vec<_> = dbs.iter().zip(&modules).map(|(db, modules)| (db, &**modules)).collect();
+    // the library is linked to a temporary file first and then renamed so that a simulator
+    // never observes a partially written library (for example in watch mode)
+    let tmp_file = lib_file.with_file_name(format!(".{}.tmp", lib_file.file_name().unwrap()));
+    let paths = osdi::compile_bundle(&units, &tmp_file, &opts.target, &back, true, opts.opt_lvl);
     // TODO configure linker
-    link(None, &opts.target, lib_file.as_ref(), |linker| {
-        for path in &paths {
-            linker.add_object(path);
-        }
+    opts.debug.time("link", || {
+        link(None, &opts.target, tmp_file.as_ref(), |linker| {
+            for path in &paths {
+                linker.add_object(path);
+            }
+        })
     })?;
 
     for obj_file in paths {
         remove_file(obj_file).context("failed to delete intermediate compile artifact")?;
     }
+    rename(&tmp_file, &lib_file).with_context(|| format!("failed to write {lib_file}"))?;
+
+    print_finished(opts, start)?;
+    Ok(CompilationTermination::Compiled { lib_file })
+}
+
+/// Collects the modules of all compilation units and applies the settings from `opts`.
+/// Returns `None` if a fatal diagnostic was emitted.
+fn collect_units(opts: &Opts, dbs: &[CompilationDB]) -> Result<Option<Vec<Vec<ModuleInfo>>>> {
+    // collect the diagnostics of all files before aborting
+    let mut modules = Vec::with_capacity(dbs.len());
+    let mut fatal_diagnostic = false;
+    for db in dbs {
+        match collect_modules(db, false, &mut ConsoleSink::new(db)) {
+            Some(unit_modules) => modules.push(unit_modules),
+            None => fatal_diagnostic = true,
+        }
+    }
+    if fatal_diagnostic {
+        return Ok(None);
+    }
+    check_duplicate_modules(opts, dbs, &modules)?;
+    select_sensitivities(opts, dbs, &mut modules)?;
+    select_second_derivatives(opts, dbs, &mut modules)?;
+    for module in modules.iter_mut().flatten() {
+        module.diff_mode = opts.diff_mode;
+        module.code_motion = opts.opt_lvl != OptLevel::None;
+        module.debug = opts.debug.clone();
+    }
+    Ok(Some(modules))
+}
 
+fn print_finished(opts: &Opts, start: Instant) -> Result<()> {
     let seconds = Instant::elapsed(&start).as_secs_f64();
     let mut stderr = StandardStream::stderr(ColorChoice::Auto);
     stderr.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
     write!(&mut stderr, "Finished")?;
     stderr.set_color(&ColorSpec::new())?;
-    writeln!(&mut stderr, " building {} in {:.2}s", opts.input.file_name().unwrap(), seconds)?;
+    writeln!(&mut stderr, " building {} in {:.2}s", opts.name(), seconds)?;
+    if let Some(timings) = &opts.debug.time_passes {
+        write!(&mut stderr, "{}", timings.take_report())?;
+    }
+    Ok(())
+}
 
-    Ok(CompilationTermination::Compiled { lib_file })
+/// Marks the parameters requested with [`Opts::sensitivities`] in every
+/// module that declares them.
+fn select_sensitivities(
+    opts: &Opts,
+    dbs: &[CompilationDB],
+    modules: &mut [Vec<ModuleInfo>],
+) -> Result<()> {
+    for name in &opts.sensitivities {
+        let mut found = false;
+        for (db, modules) in dbs.iter().zip(&mut *modules) {
+            for module in modules {
+                let param = match module.find_param(name) {
+                    Some(param) => param,
+                    None => continue,
+                };
+                if param.ty(db) != Type::Real {
+                    bail!(
+                        "can not compute the sensitivity to parameter `{name}` of module `{}`\nhelp: only real parameters are supported",
+                        module.module.name(db)
+                    );
+                }
+                if !module.sensitivities.contains(&param) {
+                    module.sensitivities.push(param);
+                }
+                found = true;
+            }
+        }
+        if !found {
+            bail!("unknown parameter `{name}` passed to --sensitivity");
+        }
+    }
+    Ok(())
+}
+
+/// Marks the nodes requested with [`Opts::second_derivatives`] in every
+/// module that declares them.
+fn select_second_derivatives(
+    opts: &Opts,
+    dbs: &[CompilationDB],
+    modules: &mut [Vec<ModuleInfo>],
+) -> Result<()> {
+    for name in &opts.second_derivatives {
+        let mut found = false;
+        for (db, modules) in dbs.iter().zip(&mut *modules) {
+            for module in modules {
+                let node = match module.find_node(db, name) {
+                    Some(node) => node,
+                    None => continue,
+                };
+                if !module.second_derivatives.contains(&node) {
+                    module.second_derivatives.push(node);
+                }
+                found = true;
+            }
+        }
+        if !found {
+            bail!("unknown node `{name}` passed to --second-derivatives");
+        }
+    }
+    Ok(())
+}
+
+/// OSDI simulators identify models by their module name so all modules
+/// linked into the same library must have a unique name.
+fn check_duplicate_modules(
+    opts: &Opts,
+    dbs: &[CompilationDB],
+    modules: &[Vec<ModuleInfo>],
+) -> Result<()> {
+    let mut names: HashMap<String, &Utf8Path> = HashMap::new();
+    for ((db, modules), input) in dbs.iter().zip(modules).zip(&opts.inputs) {
+        for module in modules {
+            let name = module.module.name(db);
+            if let Some(prev) = names.insert(name.clone(), input.path.as_path()) {
+                bail!(
+                    "module `{name}` is defined in both {prev} and {}\nhelp: modules compiled into the same library must have unique names",
+                    input.path
+                );
+            }
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,102 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+use basedb::BaseDB;
+use hir::CompilationDB;
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use crate::{compilation_dbs, compile_dbs, CompilationTermination, Opts};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Compiles `opts` and recompiles whenever the root file or any included file changes.
+/// The compilation databases are reused between runs so that only queries affected by
+/// a change are recomputed. This function only returns if an error occurs.
+pub fn watch(opts: &Opts) -> Result<()> {
+    let mut dbs = compilation_dbs(opts)?;
+    loop {
+        match compile_dbs(opts, &dbs, Instant::now()) {
+            Ok(CompilationTermination::Compiled { lib_file }) => println!("{lib_file}"),
+            Ok(CompilationTermination::FatalDiagnostic) => (),
+            // errors like a failed link step should not stop watching
+            Err(err) => print_error(&err)?,
+        }
+
+        let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+        stderr.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)).set_bold(true))?;
+        write!(&mut stderr, "Watching")?;
+        stderr.set_color(&ColorSpec::new())?;
+        writeln!(&mut stderr, " {} for changes", opts.name())?;
+
+        // files can be added or removed by an edit so they must be recollected every time
+        let mut files: Vec<_> = dbs.iter().map(WatchedFiles::new).collect();
+        loop {
+            sleep(POLL_INTERVAL);
+            let mut changed = false;
+            for (db, files) in dbs.iter_mut().zip(&mut files) {
+                // only touching a file updates the modification time without changing
+                // the contents, this is detected by `reload_files`
+                if files.poll() {
+                    changed |= db.reload_files(&files.ids);
+                }
+            }
+            if changed {
+                break;
+            }
+        }
+    }
+}
+
+struct WatchedFiles {
+    ids: Vec<basedb::FileId>,
+    paths: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl WatchedFiles {
+    fn new(db: &CompilationDB) -> WatchedFiles {
+        let ids = db.compilation_unit().source_files(db);
+        let paths = ids
+            .iter()
+            .filter_map(|&file| {
+                let path = db.file_path(file).as_path()?.as_ref().to_owned();
+                let mtime = modification_time(&path);
+                Some((path, mtime))
+            })
+            .collect();
+        WatchedFiles { ids, paths }
+    }
+
+    /// Returns whether any file was modified since the last call.
+    fn poll(&mut self) -> bool {
+        let mut modified = false;
+        for (path, mtime) in &mut self.paths {
+            let new_mtime = modification_time(path);
+            if new_mtime != *mtime {
+                *mtime = new_mtime;
+                modified = true;
+            }
+        }
+        modified
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|info| info.modified()).ok()
+}
+
+fn print_error(err: &anyhow::Error) -> Result<()> {
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+    for cause in err.chain() {
+        stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))?;
+        write!(&mut stderr, "error")?;
+        stderr.set_color(ColorSpec::new().set_bold(true))?;
+        write!(&mut stderr, ":")?;
+        stderr.set_color(&ColorSpec::new())?;
+        writeln!(&mut stderr, " {cause}")?;
+    }
+    Ok(())
+}
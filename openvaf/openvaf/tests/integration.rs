@@ -1,32 +1,57 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::f64::consts;
+use std::fs;
 use std::path::Path;
 
-use camino::Utf8Path;
+use basedb::diagnostics::ConsoleSink;
+use camino::{Utf8Path, Utf8PathBuf};
 use expect_test::expect_file;
 use float_cmp::assert_approx_eq;
+use hir::CompilationDB;
+use lasso::Rodeo;
 use llvm::OptLevel;
 use mini_harness::{harness, Result};
-use openvaf::{CompilationDestination, CompilationTermination};
+use openvaf::{
+    CompilationDestination, CompilationTermination, DebugOpts, DiffMode, JitTermination,
+};
+use paths::AbsPathBuf;
+use sim_back::{collect_modules, CompiledModule, SimUnknownKind};
+use sim_interpret::{Analysis, SimInfo, Simulator};
 use stdx::{ignore_dev_tests, openvaf_test_data, project_root};
 use target::spec::Target;
 
-use crate::load::{load_osdi_lib, EvalFlags, OsdiDescriptor};
+use crate::load::{load_osdi_jit, load_osdi_lib, EvalFlags, OsdiDescriptor, OsdiInstance};
 use crate::mock_sim::{MockSimulation, ALPHA};
 
 mod load;
 mod mock_sim;
 
+thread_local! {
+    /// Libraries that were already compiled and loaded. Recompiling a library
+    /// would overwrite the file while it is still mapped into memory.
+    static LOADED: RefCell<HashMap<Utf8PathBuf, &'static OsdiDescriptor>> = Default::default();
+}
+
 fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
+    if let Some(desc) = LOADED.with(|loaded| loaded.borrow().get(root_file).copied()) {
+        return desc;
+    }
+
     let openvaf_opts = openvaf::Opts {
         defines: Vec::new(),
         codegen_opts: Vec::new(),
+        sensitivities: Vec::new(),
+        second_derivatives: Vec::new(),
+        diff_mode: DiffMode::Forward,
         lints: Vec::new(),
-        input: root_file.to_path_buf(),
+        inputs: vec![openvaf::Input::new(root_file.to_path_buf())],
         output: CompilationDestination::Path { lib_file: root_file.with_extension("osdi") },
         include: Vec::new(),
         opt_lvl: OptLevel::Aggressive,
         target: Target::host_target().unwrap(),
         target_cpu: "native".to_owned(),
+        debug: DebugOpts::default(),
         dry_run: false,
     };
 
@@ -39,6 +64,7 @@ fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
     };
     let libs = unsafe { load_osdi_lib(&lib_file).unwrap() };
     assert_eq!(libs.len(), 1);
+    LOADED.with(|loaded| loaded.borrow_mut().insert(root_file.to_owned(), &libs[0]));
     &libs[0]
 }
 
@@ -166,6 +192,54 @@ fn test_limit() -> Result<()> {
     Ok(())
 }
 
+fn test_eval_soa() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let desc = test_descriptor(&openvaf_test_data("osdi").join("diode_lim.va"))?;
+    let model = desc.new_model();
+    model.process_params()?;
+
+    // a single instance evaluated with the scalar eval
+    let mut reference = model.new_instance();
+    let mut reference_sim = reference.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    // two instances in parallel evaluated with a single call to eval_soa
+    let mut instances = [model.new_instance(), model.new_instance()];
+    let [first, second] = &mut instances;
+    let mut sim = first.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    first.connect_parallel(second, &model, desc.num_terminals, 300.0)?;
+
+    for vd in [0.0, 0.3, 0.7] {
+        reference_sim.set_voltage("A", vd);
+        sim.set_voltage("A", vd);
+
+        let ref_flags = reference.eval(&model, &mut reference_sim, EvalFlags::empty());
+        reference.load_dae(&model, &mut reference_sim);
+        let flags = OsdiInstance::eval_soa(&instances, &model, &mut sim, EvalFlags::empty());
+        for instance in &instances {
+            instance.load_dae(&model, &mut sim);
+        }
+
+        assert_eq!(flags, [ref_flags; 2]);
+        for node in ["A", "C"] {
+            let (resist, react) = reference_sim.read_residual(node);
+            assert_approx_eq!(sim.read_residual(node), 2.0 * resist, 2.0 * react);
+            for node2 in ["A", "C"] {
+                let (resist, react) = reference_sim.read_jacobian(node, node2);
+                assert_approx_eq!(sim.read_jacobian(node, node2), 2.0 * resist, 2.0 * react);
+            }
+        }
+
+        reference_sim.clear();
+        sim.clear();
+    }
+    Ok(())
+}
+
 macro_rules! assert_approx_eq {
     ($val: expr, $expect: expr) => {
         let resist = $val;
@@ -212,8 +286,406 @@ fn test_noise() -> Result<()> {
     Ok(())
 }
 
+fn test_sensitivity() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const V_AC: f64 = 0.5;
+    const R: f64 = 2.0;
+
+    let root_file = openvaf_test_data("osdi").join("sensitivity.va");
+    let root_file: Utf8PathBuf = root_file.try_into().unwrap();
+    let lib_file = root_file.with_extension("osdi");
+    let openvaf_opts = openvaf::Opts {
+        defines: Vec::new(),
+        codegen_opts: Vec::new(),
+        sensitivities: vec!["r".to_owned(), "c0".to_owned()],
+        second_derivatives: Vec::new(),
+        diff_mode: DiffMode::Forward,
+        lints: Vec::new(),
+        inputs: vec![openvaf::Input::new(root_file)],
+        output: CompilationDestination::Path { lib_file: lib_file.clone() },
+        include: Vec::new(),
+        opt_lvl: OptLevel::None,
+        target: Target::host_target().unwrap(),
+        target_cpu: "native".to_owned(),
+        debug: DebugOpts::default(),
+        dry_run: false,
+    };
+    let res = openvaf::compile(&openvaf_opts)?;
+    assert!(matches!(res, CompilationTermination::Compiled { .. }));
+    let desc = unsafe { &load_osdi_lib(&lib_file)?[0] };
+
+    let params: Vec<_> = desc
+        .sensitivity_params()
+        .iter()
+        .map(|&id| unsafe { load::osdi_str(*desc.params()[id as usize].name) })
+        .collect();
+    assert_eq!(params, ["r", "c0"]);
+
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    sim.set_voltage("a", V_AC);
+    instance.eval(&model, &mut sim, EvalFlags::CALC_SENSITIVITY);
+
+    // ddx(V(a, c) / r, r) = -V(a, c) / r^2
+    instance.load_sensitivity(&model, &mut sim, 0);
+    for (node, sign) in [("a", 1.0), ("c", -1.0)] {
+        let (resist, react) = sim.read_residual(node);
+        float_cmp::assert_approx_eq!(f64, resist, -sign * V_AC / (R * R), epsilon = 1e-12);
+        float_cmp::assert_approx_eq!(f64, react, 0.0, epsilon = 1e-12);
+    }
+    sim.clear();
+
+    // ddx(c0 * V(a, c)^2, c0) = V(a, c)^2
+    instance.load_sensitivity(&model, &mut sim, 1);
+    for (node, sign) in [("a", 1.0), ("c", -1.0)] {
+        let (resist, react) = sim.read_residual(node);
+        float_cmp::assert_approx_eq!(f64, resist, 0.0, epsilon = 1e-12);
+        float_cmp::assert_approx_eq!(f64, react, sign * V_AC * V_AC, epsilon = 1e-12);
+    }
+    Ok(())
+}
+
+fn test_second_derivative() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const K: f64 = 2.0;
+    const V_AC: f64 = 0.5;
+
+    let root_file = openvaf_test_data("osdi").join("second_derivative.va");
+    let root_file: Utf8PathBuf = root_file.try_into().unwrap();
+    let desc = compile_and_load(&root_file);
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    sim.set_voltage("a", V_AC);
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+    instance.load_dae(&model, &mut sim);
+
+    // i = k * V(a, c)^3
+    // ddx(ddx(i, V(a)), V(a)) = 6 * k * V(a, c)
+    // ddx(ddx(i, V(a)), V(c)) = -6 * k * V(a, c)
+    for (node, sign) in [("a", 1.0), ("c", -1.0)] {
+        let (resist, react) = sim.read_residual(node);
+        float_cmp::assert_approx_eq!(f64, resist, sign * 6.0 * K * V_AC, epsilon = 1e-12);
+        float_cmp::assert_approx_eq!(f64, react, -sign * 6.0 * K * V_AC, epsilon = 1e-12);
+        for (node2, sign2) in [("a", 1.0), ("c", -1.0)] {
+            let (resist, react) = sim.read_jacobian(node, node2);
+            float_cmp::assert_approx_eq!(f64, resist, sign * sign2 * 6.0 * K, epsilon = 1e-12);
+            float_cmp::assert_approx_eq!(f64, react, -sign * sign2 * 6.0 * K, epsilon = 1e-12);
+        }
+    }
+    Ok(())
+}
+
+fn test_second_derivatives_output() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const K: f64 = 2.0;
+    const C0: f64 = 3.0;
+    const V_AC: f64 = 0.5;
+
+    let root_file = openvaf_test_data("osdi").join("second_derivatives_output.va");
+    let root_file: Utf8PathBuf = root_file.try_into().unwrap();
+    let lib_file = root_file.with_extension("osdi");
+    let openvaf_opts = openvaf::Opts {
+        defines: Vec::new(),
+        codegen_opts: Vec::new(),
+        sensitivities: Vec::new(),
+        second_derivatives: vec!["a".to_owned()],
+        diff_mode: DiffMode::Forward,
+        lints: Vec::new(),
+        inputs: vec![openvaf::Input::new(root_file)],
+        output: CompilationDestination::Path { lib_file: lib_file.clone() },
+        include: Vec::new(),
+        opt_lvl: OptLevel::None,
+        target: Target::host_target().unwrap(),
+        target_cpu: "native".to_owned(),
+        debug: DebugOpts::default(),
+        dry_run: false,
+    };
+    let res = openvaf::compile(&openvaf_opts)?;
+    assert!(matches!(res, CompilationTermination::Compiled { .. }));
+    let desc = unsafe { &load_osdi_lib(&lib_file)?[0] };
+
+    let node_name = |node: u32| unsafe { load::osdi_str(desc.nodes()[node as usize].name) };
+    let entries: Vec<_> = desc
+        .second_derivatives()
+        .iter()
+        .map(|entry| {
+            (
+                node_name(entry.residual),
+                node_name(entry.nodes.node_1),
+                node_name(entry.nodes.node_2),
+            )
+        })
+        .collect();
+    assert_eq!(entries, [("a", "a", "a"), ("a", "a", "c"), ("a", "c", "c")]);
+
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    sim.set_voltage("a", V_AC);
+    instance.eval(&model, &mut sim, EvalFlags::CALC_SECOND_DERIVATIVES);
+    let (resist, react) = instance.load_second_derivatives(&model);
+
+    // I(a) = k * V(a, c)^3, Q(a) = c0 * V(a) * V(c)
+    let expected = [(6.0 * K * V_AC, 0.0), (-6.0 * K * V_AC, C0), (6.0 * K * V_AC, 0.0)];
+    for ((resist, react), (expected_resist, expected_react)) in
+        resist.into_iter().zip(react).zip(expected)
+    {
+        float_cmp::assert_approx_eq!(f64, resist, expected_resist, epsilon = 1e-12);
+        float_cmp::assert_approx_eq!(f64, react, expected_react, epsilon = 1e-12);
+    }
+    Ok(())
+}
+
+/// Compiles `diode_lim.va` with the JIT and compares the results to the library that was linked.
+fn test_jit() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let root_file = openvaf_test_data("osdi").join("diode_lim.va");
+    let reference = test_descriptor(&root_file)?;
+    let openvaf_opts = openvaf::Opts {
+        defines: Vec::new(),
+        codegen_opts: Vec::new(),
+        sensitivities: Vec::new(),
+        second_derivatives: Vec::new(),
+        diff_mode: DiffMode::Forward,
+        lints: Vec::new(),
+        inputs: vec![openvaf::Input::new(root_file.try_into().unwrap())],
+        output: CompilationDestination::Cache { cache_dir: Utf8PathBuf::new() },
+        include: Vec::new(),
+        opt_lvl: OptLevel::Aggressive,
+        target: Target::host_target().unwrap(),
+        target_cpu: "native".to_owned(),
+        debug: DebugOpts::default(),
+        dry_run: false,
+    };
+    let lib = match openvaf::compile_jit(&openvaf_opts)? {
+        JitTermination::Compiled { lib } => lib,
+        JitTermination::FatalDiagnostic => panic!("openvaf: compilation of diode_lim.va failed"),
+    };
+    let descs = unsafe { load_osdi_jit(lib)? };
+    assert_eq!(descs.len(), 1);
+    let desc = &descs[0];
+    assert_eq!(format!("{desc:?}"), format!("{reference:?}"));
+
+    let mut models = [desc.new_model(), reference.new_model()];
+    let mut sims = Vec::new();
+    let mut instances = Vec::new();
+    for (model, desc) in models.iter_mut().zip([desc, reference]) {
+        model.set_real_param(1, 1e-12);
+        model.set_real_param(5, 10e-9);
+        model.process_params()?;
+        let mut instance = model.new_instance();
+        sims.push(instance.mock_simulation(model, desc.num_terminals, 300.0)?);
+        instances.push(instance);
+    }
+
+    for vd in [0.0, 0.3, 0.7] {
+        for ((model, instance), sim) in models.iter().zip(&instances).zip(&mut sims) {
+            sim.next_iter();
+            sim.set_voltage("A", vd);
+            instance.eval(model, sim, EvalFlags::ENABLE_LIM);
+            instance.load_dae(model, sim);
+        }
+        for node in ["A", "C"] {
+            let (resist, react) = sims[1].read_residual(node);
+            assert_approx_eq!(sims[0].read_residual(node), resist, react);
+            for node2 in ["A", "C"] {
+                let (resist, react) = sims[1].read_jacobian(node, node2);
+                assert_approx_eq!(sims[0].read_jacobian(node, node2), resist, react);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Evaluates `diode_lim.va` with the interpreter and checks the results against the OSDI library.
+fn test_interpret() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const IS: f64 = 1e-12;
+    const CJ0: f64 = 10e-9;
+    const RS: f64 = 2.0;
+    const NODES: [&str; 3] = ["A", "C", "CI"];
+
+    let root_file = openvaf_test_data("osdi").join("diode_lim.va");
+    let desc = test_descriptor(&root_file)?;
+    let model = desc.new_model();
+    model.set_real_param(1, IS);
+    model.set_real_param(2, RS);
+    model.set_real_param(5, CJ0);
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    let db = CompilationDB::new_fs(AbsPathBuf::assert(root_file), &[], &[], &[])?;
+    let module = collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let mut interpreter = Simulator::new(literals);
+    let mut interpreter_model = sim_interpret::Model::new();
+    assert!(interpreter_model.set_param_by_name(&module, "is", IS));
+    assert!(interpreter_model.set_param_by_name(&module, "rs", RS));
+    assert!(interpreter_model.set_param_by_name(&module, "cj0", CJ0));
+    assert!(interpreter_model.setup(&module, &mut interpreter).is_ok());
+    let mut interpreter_instance = sim_interpret::Instance::new();
+    let res = interpreter_instance.setup(
+        &module,
+        &interpreter_model,
+        300.0,
+        desc.num_terminals,
+        &mut interpreter,
+    );
+    assert!(res.is_ok());
+
+    let unknown = |name: &str| {
+        module
+            .dae_system
+            .unknowns
+            .iter()
+            .copied()
+            .find(|unknown| {
+                matches!(unknown, SimUnknownKind::KirchoffLaw(node) if node.name(&db) == name)
+            })
+            .unwrap()
+    };
+
+    let mut prev_state = vec![0.0];
+    for (i, va) in [0.0, 0.5, 0.9, 0.7].into_iter().enumerate() {
+        let init_lim = i == 0;
+        let flags = if init_lim {
+            EvalFlags::INIT_LIM | EvalFlags::ENABLE_LIM
+        } else {
+            EvalFlags::ENABLE_LIM
+        };
+        sim.next_iter();
+        sim.set_voltage("A", va);
+        instance.eval(&model, &mut sim, flags);
+        instance.load_dae(&model, &mut sim);
+
+        let mut prev_solve = vec![0.0; module.dae_system.unknowns.len()];
+        prev_solve[usize::from(module.dae_system.unknowns.unwrap_index(&unknown("A")))] = va;
+        let sim_info = SimInfo {
+            analysis: Analysis::Tran,
+            abstime: 0.0,
+            prev_solve: &prev_solve,
+            prev_state: &prev_state,
+            enable_lim: true,
+            init_lim,
+        };
+        let res =
+            interpreter_instance.eval(&module, &interpreter_model, &sim_info, &mut interpreter);
+        prev_state = res.next_state.raw.clone();
+
+        for node in NODES {
+            let row = unknown(node);
+            // osdi subtracts the lim_rhs when loading the residual
+            let residual = module.dae_system.unknowns.index(&row).map_or((0.0, 0.0), |i| {
+                let residual = &res.residual[i];
+                (residual.resist - residual.resist_lim_rhs, residual.react - residual.react_lim_rhs)
+            });
+            let (resist, react) = sim.read_residual(node);
+            assert_approx_eq!(residual, resist, react);
+            for node2 in NODES {
+                if sim.jacobian_info.contains(&(
+                    sim.nodes.get_index_of(node).unwrap() as u32,
+                    sim.nodes.get_index_of(node2).unwrap() as u32,
+                )) {
+                    let (resist, react) = sim.read_jacobian(node, node2);
+                    assert_approx_eq!(res.jacobian(&module, row, unknown(node2)), resist, react);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn test_bundle() -> Result<()> {
+    let root: Utf8PathBuf = project_root().join("integration_tests").try_into().unwrap();
+    let lib_file = openvaf_test_data("osdi").join("bundle.osdi");
+    let lib_file: Utf8PathBuf = lib_file.try_into().unwrap();
+    let mut openvaf_opts = openvaf::Opts {
+        defines: Vec::new(),
+        codegen_opts: Vec::new(),
+        sensitivities: Vec::new(),
+        second_derivatives: Vec::new(),
+        diff_mode: DiffMode::Forward,
+        lints: Vec::new(),
+        inputs: vec![
+            openvaf::Input::new(root.join("RESISTOR").join("resistor.va")),
+            openvaf::Input::new(root.join("DIODE").join("diode.va")),
+        ],
+        output: CompilationDestination::Path { lib_file: lib_file.clone() },
+        include: Vec::new(),
+        opt_lvl: OptLevel::None,
+        target: Target::host_target().unwrap(),
+        target_cpu: "native".to_owned(),
+        debug: DebugOpts::default(),
+        dry_run: false,
+    };
+
+    let res = openvaf::compile(&openvaf_opts)?;
+    assert!(matches!(res, CompilationTermination::Compiled { .. }));
+    let libs = unsafe { load_osdi_lib(&lib_file)? };
+    let names: Vec<_> = libs.iter().map(|desc| unsafe { load::osdi_str(desc.name) }).collect();
+    assert_eq!(names, ["resistor_va", "diode_va"]);
+
+    // the same module can not be linked into a library twice
+    openvaf_opts.inputs.push(openvaf::Input::new(root.join("DIODE").join("diode.va")));
+    openvaf_opts.dry_run = true;
+    assert!(openvaf::compile(&openvaf_opts).is_err());
+    Ok(())
+}
+
+/// Changing an included file must invalidate the cached queries of the compilation database
+/// (used by watch mode).
+fn test_reload_files() -> Result<()> {
+    let dir = std::env::temp_dir().join("openvaf_reload_files");
+    fs::create_dir_all(&dir)?;
+    let root_file = dir.join("root.va");
+    let include_file = dir.join("module.va");
+    let module_src = |name: &str| {
+        format!("module {name}(inout electrical a);\n    analog I(a) <+ V(a);\nendmodule\n")
+    };
+    fs::write(&root_file, "`include \"disciplines.vams\"\n`include \"module.va\"\n")?;
+    fs::write(&include_file, module_src("foo"))?;
+
+    let mut db = CompilationDB::new_fs(AbsPathBuf::assert(root_file), &[], &[], &[])?;
+    let module_names = |db: &CompilationDB| -> Vec<String> {
+        let modules = collect_modules(db, false, &mut ConsoleSink::new(db)).unwrap();
+        modules.iter().map(|module| module.module.name(db)).collect()
+    };
+    assert_eq!(module_names(&db), ["foo"]);
+
+    let files = db.compilation_unit().source_files(&db);
+    assert!(!db.reload_files(&files));
+    assert_eq!(module_names(&db), ["foo"]);
+
+    fs::write(&include_file, module_src("bar"))?;
+    assert!(db.reload_files(&files));
+    assert_eq!(module_names(&db), ["bar"]);
+    Ok(())
+}
+
 harness! {
     // TODO: run this in CI, somehow this test is flakey tough regarding the linker invocation (and really slow)
     Test::from_dir("integration", &integration_test, &ignore_dev_tests, &project_root().join("integration_tests")),
-    [Test::new("$limit", &test_limit),Test::new("noise", &test_noise)]
+    [Test::new("$limit", &test_limit),Test::new("noise", &test_noise),Test::new("eval_soa", &test_eval_soa),Test::new("sensitivity", &test_sensitivity),Test::new("second_derivative", &test_second_derivative),Test::new("second_derivatives_output", &test_second_derivatives_output),Test::new("bundle", &test_bundle),Test::new("interpret", &test_interpret),Test::new("jit", &test_jit),Test::new("reload_files", &test_reload_files)]
 }
@@ -12,13 +12,14 @@ use bitflags::bitflags;
 use camino::Utf8Path;
 use libc::c_void;
 use libloading::Library;
+use openvaf::JitLibrary;
 use stdx::format_to;
 use stdx::iter::zip;
 
 #[allow(warnings)]
-mod osdi_0_3;
+mod osdi_0_4;
 
-pub use osdi_0_3::*;
+pub use osdi_0_4::*;
 
 impl OsdiDescriptor {
     pub fn nodes(&self) -> &[OsdiNode] {
@@ -33,6 +34,19 @@ impl OsdiDescriptor {
         unsafe { slice::from_raw_parts(self.param_opvar, self.num_params as usize) }
     }
 
+    pub fn sensitivity_params(&self) -> &[u32] {
+        // # SAFETY: OsdiDescriptor can only be constructed from FFI and is assumed to contain
+        // valid data
+        unsafe { slice::from_raw_parts(self.sensitivity_params, self.num_sensitivities as usize) }
+    }
+
+    pub fn second_derivatives(&self) -> &[OsdiSecondDerivative] {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            slice::from_raw_parts(self.second_derivatives, self.num_second_derivatives as usize)
+        }
+    }
+
     pub fn collapsible(&self) -> &[OsdiNodePair] {
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
         unsafe { slice::from_raw_parts(self.collapsible, self.num_collapsible as usize) }
@@ -324,29 +338,43 @@ impl OsdiInstance {
 pub unsafe fn load_osdi_lib(path: &Utf8Path) -> Result<&'static [OsdiDescriptor]> {
     let lib = Library::new(path)?;
     let lib = Box::leak(Box::new(lib));
+    load_osdi_symbols(|sym| {
+        let sym = format!("{sym}\0");
+        Ok(*lib.get::<*mut c_void>(sym.as_bytes())?)
+    })
+}
+
+/// Loads the descriptors from a library compiled with [`openvaf::compile_jit`]
+pub unsafe fn load_osdi_jit(lib: JitLibrary) -> Result<&'static [OsdiDescriptor]> {
+    let lib = Box::leak(Box::new(lib));
+    load_osdi_symbols(|sym| lib.get(sym))
+}
 
-    let major_version: &u32 = *lib.get(b"OSDI_VERSION_MAJOR\0")?;
-    let minor_version: &u32 = *lib.get(b"OSDI_VERSION_MINOR\0")?;
+unsafe fn load_osdi_symbols(
+    get: impl Fn(&str) -> Result<*mut c_void>,
+) -> Result<&'static [OsdiDescriptor]> {
+    let major_version = *(get("OSDI_VERSION_MAJOR")? as *const u32);
+    let minor_version = *(get("OSDI_VERSION_MINOR")? as *const u32);
 
-    if *major_version != 0 || *minor_version != 3 {
+    if major_version != 0 || minor_version != 4 {
         bail!("invalid version v{major_version}.{minor_version}",);
     }
 
-    let num_descriptors: &u32 = *lib.get(b"OSDI_NUM_DESCRIPTORS\0")?;
-    let descriptors: *const OsdiDescriptor = *lib.get(b"OSDI_DESCRIPTORS\0")?;
+    let num_descriptors = *(get("OSDI_NUM_DESCRIPTORS")? as *const u32);
+    let descriptors = get("OSDI_DESCRIPTORS")? as *const OsdiDescriptor;
 
     let descriptors: &[OsdiDescriptor] =
-        slice::from_raw_parts(descriptors, *num_descriptors as usize);
+        slice::from_raw_parts(descriptors, num_descriptors as usize);
 
-    if let Ok(osdi_log_ptr) =
-        lib.get::<*mut unsafe extern "C" fn(*mut c_void, *const c_char, u32)>(b"osdi_log\0")
-    {
+    if let Ok(osdi_log_ptr) = get("osdi_log") {
+        let osdi_log_ptr =
+            osdi_log_ptr as *mut unsafe extern "C" fn(*mut c_void, *const c_char, u32);
         osdi_log_ptr.write(osdi_log)
     }
-    if let Ok(osdi_lim_table) = lib.get(b"OSDI_LIM_TABLE\0") {
-        let lim_table_base: *mut OsdiLimFunction = *osdi_lim_table;
-        let lim_table_len: &u32 = *lib.get(b"OSDI_LIM_TABLE_LEN\0")?;
-        let lim_table = slice::from_raw_parts_mut(lim_table_base, *lim_table_len as usize);
+    if let Ok(osdi_lim_table) = get("OSDI_LIM_TABLE") {
+        let lim_table_base = osdi_lim_table as *mut OsdiLimFunction;
+        let lim_table_len = *(get("OSDI_LIM_TABLE_LEN")? as *const u32);
+        let lim_table = slice::from_raw_parts_mut(lim_table_base, lim_table_len as usize);
         for lim_func in lim_table {
             if osdi_str(lim_func.name) == "pnjlim" {
                 assert_eq!(lim_func.num_args, 2);
@@ -540,6 +568,8 @@ bitflags! {
         const ANALYSIS_IC = ANALYSIS_IC;
         const ANALYSIS_STATIC = ANALYSIS_STATIC;
         const ANALYSIS_NODESET = ANALYSIS_NODESET;
+        const CALC_SENSITIVITY = CALC_SENSITIVITY;
+        const CALC_SECOND_DERIVATIVES = CALC_SECOND_DERIVATIVES;
     }
 }
 
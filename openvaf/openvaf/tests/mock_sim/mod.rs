@@ -218,40 +218,138 @@ impl OsdiInstance {
         self.descriptor.load_jacobian_resist(self.data, model.data);
         self.descriptor.load_jacobian_react(self.data, model.data, 1.0);
     }
+
+    pub fn load_sensitivity(&self, model: &OsdiModel, sim: &mut MockSimulation, sensitivity: u32) {
+        self.descriptor.load_sensitivity_resist(
+            self.data,
+            model.data,
+            sensitivity,
+            sim.residual_resist.as_mut_ptr(),
+        );
+        self.descriptor.load_sensitivity_react(
+            self.data,
+            model.data,
+            sensitivity,
+            sim.residual_react.as_mut_ptr(),
+        );
+    }
+
+    /// Returns the resistive and reactive second derivatives in the order of
+    /// `OsdiDescriptor::second_derivatives`.
+    pub fn load_second_derivatives(&self, model: &OsdiModel) -> (Vec<f64>, Vec<f64>) {
+        let len = self.descriptor.num_second_derivatives as usize;
+        let mut resist = vec![0.0; len];
+        let mut react = vec![0.0; len];
+        self.descriptor.load_second_derivatives_resist(self.data, model.data, resist.as_mut_ptr());
+        self.descriptor.load_second_derivatives_react(self.data, model.data, react.as_mut_ptr());
+        (resist, react)
+    }
+
     pub fn eval(
         &self,
         model: &OsdiModel,
         sim: &mut MockSimulation,
-        mut flags: EvalFlags,
+        flags: EvalFlags,
     ) -> EvalRetFlags {
-        // always calculate everything
-        flags |= EvalFlags::CALC_RESIST_JACOBIAN
-            | EvalFlags::CALC_RESIST_RESIDUAL
-            | EvalFlags::CALC_RESIST_LIM_RHS
-            | EvalFlags::CALC_REACT_JACOBIAN
-            | EvalFlags::CALC_REACT_RESIDUAL
-            | EvalFlags::CALC_REACT_LIM_RHS
-            | EvalFlags::CALC_NOISE;
-        let sim_params = OsdiSimParas {
-            names: &mut ptr::null_mut(),
-            vals: ptr::null_mut(),
-            names_str: &mut ptr::null_mut(),
-            vals_str: ptr::null_mut(),
-        };
-        let mut sim_info = OsdiSimInfo {
-            paras: sim_params,
-            abstime: 0.0,
-            prev_solve: sim.solve.as_ptr() as *mut f64,
-            prev_state: sim.state_1.as_mut_ptr(),
-            next_state: sim.state_2.as_mut_ptr(),
-            flags: flags.bits(),
-        };
-        let flags = self.descriptor.eval(
-            b"foo\0".as_ptr() as *mut c_void,
-            self.data,
-            model.data,
-            &mut sim_info,
-        );
+        let flags = with_sim_info(sim, flags, |sim_info| {
+            self.descriptor.eval(b"foo\0".as_ptr() as *mut c_void, self.data, model.data, sim_info)
+        });
         EvalRetFlags::from_bits(flags).unwrap()
     }
+
+    /// Evaluates all `instances` with a single call to `eval_soa`. The instance data is
+    /// packed into a structure of arrays before the call and unpacked again afterwards.
+    pub fn eval_soa(
+        instances: &[OsdiInstance],
+        model: &OsdiModel,
+        sim: &mut MockSimulation,
+        flags: EvalFlags,
+    ) -> Vec<EvalRetFlags> {
+        let desc = model.descriptor;
+        let num_insts = instances.len() as u32;
+        let mut soa = vec![0u64; (instances.len() * desc.instance_size as usize).div_ceil(8)];
+        let soa_data = soa.as_mut_ptr() as *mut c_void;
+        for (i, instance) in instances.iter().enumerate() {
+            desc.pack_soa(soa_data, num_insts, i as u32, instance.data);
+        }
+
+        let mut handles: Vec<_> =
+            instances.iter().map(|_| b"foo\0".as_ptr() as *mut c_void).collect();
+        let mut ret_flags = vec![0u32; instances.len()];
+        with_sim_info(sim, flags, |sim_info| {
+            desc.eval_soa(
+                handles.as_mut_ptr(),
+                soa_data,
+                model.data,
+                sim_info,
+                num_insts,
+                ret_flags.as_mut_ptr(),
+            )
+        });
+
+        for (i, instance) in instances.iter().enumerate() {
+            desc.unpack_soa(soa_data, num_insts, i as u32, instance.data);
+        }
+        ret_flags.into_iter().map(|flags| EvalRetFlags::from_bits(flags).unwrap()).collect()
+    }
+
+    /// Connects `other` in parallel to this instance: `other` is evaluated
+    /// with the same node voltages and loads into the same matrix entries.
+    pub(super) fn connect_parallel(
+        &self,
+        other: &mut OsdiInstance,
+        model: &OsdiModel,
+        connected_terminals: u32,
+        temp: f64,
+    ) -> Result<()> {
+        other.process_params(model, connected_terminals, temp)?;
+        for (dst, src) in zip(other.node_mapping(), self.node_mapping()) {
+            dst.set(src.get())
+        }
+        for (dst, src) in zip(other.matrix_ptrs_resist(), self.matrix_ptrs_resist()) {
+            dst.set(src.get())
+        }
+        for entry in self.descriptor.matrix_entries() {
+            if entry.react_ptr_off != u32::MAX {
+                unsafe {
+                    let src: *mut *mut f64 =
+                        (self.data as *mut u8).add(entry.react_ptr_off as usize).cast();
+                    let dst: *mut *mut f64 =
+                        (other.data as *mut u8).add(entry.react_ptr_off as usize).cast();
+                    *dst = *src;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn with_sim_info<T>(
+    sim: &mut MockSimulation,
+    mut flags: EvalFlags,
+    eval: impl FnOnce(&mut OsdiSimInfo) -> T,
+) -> T {
+    // always calculate everything
+    flags |= EvalFlags::CALC_RESIST_JACOBIAN
+        | EvalFlags::CALC_RESIST_RESIDUAL
+        | EvalFlags::CALC_RESIST_LIM_RHS
+        | EvalFlags::CALC_REACT_JACOBIAN
+        | EvalFlags::CALC_REACT_RESIDUAL
+        | EvalFlags::CALC_REACT_LIM_RHS
+        | EvalFlags::CALC_NOISE;
+    let sim_params = OsdiSimParas {
+        names: &mut ptr::null_mut(),
+        vals: ptr::null_mut(),
+        names_str: &mut ptr::null_mut(),
+        vals_str: ptr::null_mut(),
+    };
+    let mut sim_info = OsdiSimInfo {
+        paras: sim_params,
+        abstime: 0.0,
+        prev_solve: sim.solve.as_ptr() as *mut f64,
+        prev_state: sim.state_1.as_mut_ptr(),
+        next_state: sim.state_2.as_mut_ptr(),
+        flags: flags.bits(),
+    };
+    eval(&mut sim_info)
 }
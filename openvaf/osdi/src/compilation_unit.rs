@@ -20,7 +20,7 @@ use typed_index_collections::TiVec;
 use typed_indexmap::TiSet;
 
 use crate::inst_data::OsdiInstanceData;
-use crate::metadata::osdi_0_3::{
+use crate::metadata::osdi_0_4::{
     stdlib_bitcode, OsdiTys, LOG_FMT_ERR, LOG_LVL_DEBUG, LOG_LVL_DISPLAY, LOG_LVL_ERR,
     LOG_LVL_FATAL, LOG_LVL_INFO, LOG_LVL_WARN,
 };
@@ -121,8 +121,13 @@ impl<'a> OsdiModule<'a> {
         db: &'a CompilationDB,
         module: &'a CompiledModule,
         lim_table: &'a TiSet<OsdiLimId, OsdiLimFunction>,
+        unit: usize,
     ) -> Self {
-        let sym = base_n::encode(module.info.module.uuid(db) as u128, base_n::CASE_INSENSITIVE);
+        let mut sym = base_n::encode(module.info.module.uuid(db) as u128, base_n::CASE_INSENSITIVE);
+        // module ids are only unique within a single compilation unit
+        if unit != 0 {
+            sym = format!("{sym}_{unit}");
+        }
         let CompiledModule {
             info,
             dae_system,
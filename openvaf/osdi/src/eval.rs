@@ -1,10 +1,11 @@
 use hir_lower::{CallBackKind, CurrentKind, LimitState, ParamKind};
 use llvm::IntPredicate::{IntNE, IntULT};
 use llvm::{
-    LLVMAppendBasicBlockInContext, LLVMBuildAlloca, LLVMBuildAnd, LLVMBuildBr, LLVMBuildCall2,
-    LLVMBuildCondBr, LLVMBuildICmp, LLVMBuildInBoundsGEP2, LLVMBuildIntCast2, LLVMBuildLoad2,
-    LLVMBuildOr, LLVMBuildRet, LLVMBuildStore, LLVMCreateBuilderInContext, LLVMDisposeBuilder,
-    LLVMGetParam, LLVMPositionBuilderAtEnd, UNNAMED,
+    LLVMAddIncoming, LLVMAppendBasicBlockInContext, LLVMBuildAdd, LLVMBuildAlloca, LLVMBuildAnd,
+    LLVMBuildBr, LLVMBuildCall2, LLVMBuildCondBr, LLVMBuildICmp, LLVMBuildInBoundsGEP2,
+    LLVMBuildIntCast2, LLVMBuildLoad2, LLVMBuildOr, LLVMBuildPhi, LLVMBuildRet, LLVMBuildRetVoid,
+    LLVMBuildStore, LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetInsertBlock,
+    LLVMGetParam, LLVMMarkLoopParallel, LLVMPositionBuilderAtEnd, TargetData, UNNAMED,
 };
 use log::info;
 use mir_llvm::{Builder, BuilderVal, CallbackFun, MemLoc};
@@ -13,11 +14,11 @@ use typed_index_collections::TiVec;
 
 use crate::bitfield::{is_flag_set, is_flag_set_mem, is_flag_unset};
 use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit};
-use crate::inst_data::OsdiInstanceParam;
-use crate::metadata::osdi_0_3::{
+use crate::inst_data::{InstPtr, OsdiInstanceParam, SoaInstances};
+use crate::metadata::osdi_0_4::{
     ANALYSIS_IC, CALC_NOISE, CALC_OP, CALC_REACT_JACOBIAN, CALC_REACT_LIM_RHS, CALC_REACT_RESIDUAL,
-    CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, ENABLE_LIM, EVAL_RET_FLAG_LIM,
-    INIT_LIM,
+    CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, CALC_SECOND_DERIVATIVES,
+    CALC_SENSITIVITY, ENABLE_LIM, EVAL_RET_FLAG_LIM, INIT_LIM,
 };
 use crate::metadata::OsdiLimFunction;
 use crate::OsdiLimId;
@@ -35,17 +36,178 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
     pub fn eval(&self) -> &'ll llvm::Value {
         let llfunc = self.eval_prototype();
+        let OsdiCompilationUnit { cx, module, .. } = self;
+
+        let mut builder = Builder::new(cx, module.eval, llfunc);
+
+        let handle = unsafe { LLVMGetParam(llfunc, 0) };
+        let instance = unsafe { LLVMGetParam(llfunc, 1) };
+        let model = unsafe { LLVMGetParam(llfunc, 2) };
+        let sim_info = unsafe { LLVMGetParam(llfunc, 3) };
+
+        unsafe {
+            let ret_flags = builder.alloca(cx.ty_int());
+            self.build_eval(&mut builder, handle, instance.into(), model, sim_info, ret_flags);
+            let ret_flags = builder.load(cx.ty_int(), ret_flags);
+            builder.ret(ret_flags);
+        }
+
+        llfunc
+    }
+
+    pub fn eval_soa_prototype(&self) -> &'ll llvm::Value {
+        let name = &format!("eval_soa_{}", &self.module.sym);
+        let cx = &self.cx;
+
+        let ty_ptr = cx.ty_ptr();
+
+        let fun_ty =
+            cx.ty_func(&[ty_ptr, ty_ptr, ty_ptr, ty_ptr, cx.ty_int(), ty_ptr], cx.ty_void());
+        cx.declare_ext_fn(name, fun_ty)
+    }
+
+    /// Generates a function that evaluates `num_insts` instances of the same model. The
+    /// instance data is stored as a structure of arrays (see `SoaInstances`), the handles
+    /// are passed as an array and the model and the `OsdiSimInfo` are shared by all
+    /// instances. The flags that `eval` would return for each instance are written to the
+    /// `ret_flags` array.
+    ///
+    /// The instances are evaluated in a loop that is marked as free of memory dependencies
+    /// between iterations so that LLVM can vectorize it (evaluating multiple instances at
+    /// once). Models whose evaluation can't be vectorized (for example because they call
+    /// `$limit`) still work but evaluate one instance after another.
+    pub fn eval_soa(&self, target_data: &TargetData) -> &'ll llvm::Value {
+        let llfunc = self.eval_soa_prototype();
+        let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
+
+        let mut builder = Builder::new(cx, module.eval, llfunc);
+
+        unsafe {
+            let handles = LLVMGetParam(llfunc, 0);
+            let data = LLVMGetParam(llfunc, 1);
+            let model = LLVMGetParam(llfunc, 2);
+            let sim_info = LLVMGetParam(llfunc, 3);
+            let num_insts = LLVMGetParam(llfunc, 4);
+            let ret_flags_arr = LLVMGetParam(llfunc, 5);
+
+            let ret_flags = builder.alloca(cx.ty_int());
+            let num_insts =
+                LLVMBuildIntCast2(builder.llbuilder, num_insts, cx.ty_size(), llvm::False, UNNAMED);
+
+            let entry = builder.prepend_pos;
+            let header = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let body = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let exit = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            LLVMBuildBr(builder.llbuilder, header);
+
+            LLVMPositionBuilderAtEnd(builder.llbuilder, header);
+            let idx = LLVMBuildPhi(builder.llbuilder, cx.ty_size(), UNNAMED);
+            let has_next = LLVMBuildICmp(builder.llbuilder, IntULT, idx, num_insts, UNNAMED);
+            LLVMBuildCondBr(builder.llbuilder, has_next, body, exit);
+
+            LLVMPositionBuilderAtEnd(builder.llbuilder, body);
+            builder.prepend_pos = body;
+            let handle = builder.gep(cx.ty_ptr(), handles, &[idx]);
+            let handle = builder.load(cx.ty_ptr(), handle);
+            let soa = SoaInstances::new(inst_data, target_data, data, num_insts, idx);
+            self.build_eval(&mut builder, handle, InstPtr::Soa(&soa), model, sim_info, ret_flags);
+
+            let flags = builder.load(cx.ty_int(), ret_flags);
+            let dst = builder.gep(cx.ty_int(), ret_flags_arr, &[idx]);
+            builder.store(dst, flags);
+            let next_idx = LLVMBuildAdd(builder.llbuilder, idx, cx.const_usize(1), UNNAMED);
+            let latch = LLVMGetInsertBlock(builder.llbuilder);
+            let back_edge = LLVMBuildBr(builder.llbuilder, header);
+            LLVMAddIncoming(
+                idx,
+                [cx.const_usize(0), next_idx].as_ptr(),
+                [entry, latch].as_ptr(),
+                2,
+            );
+
+            LLVMPositionBuilderAtEnd(builder.llbuilder, exit);
+            builder.ret_void();
+
+            LLVMMarkLoopParallel(llfunc, back_edge);
+        }
+
+        llfunc
+    }
+
+    pub fn pack_soa_prototype(&self) -> &'ll llvm::Value {
+        self.copy_soa_prototype("pack_soa")
+    }
+
+    pub fn unpack_soa_prototype(&self) -> &'ll llvm::Value {
+        self.copy_soa_prototype("unpack_soa")
+    }
+
+    fn copy_soa_prototype(&self, name: &str) -> &'ll llvm::Value {
+        let name = &format!("{name}_{}", &self.module.sym);
+        let cx = &self.cx;
+
+        let ty_ptr = cx.ty_ptr();
+
+        let fun_ty = cx.ty_func(&[ty_ptr, cx.ty_int(), cx.ty_int(), ty_ptr], cx.ty_void());
+        cx.declare_ext_fn(name, fun_ty)
+    }
+
+    /// Generates `pack_soa` which copies an instance into the structure of arrays used by
+    /// `eval_soa`.
+    pub fn pack_soa(&self, target_data: &TargetData) -> &'ll llvm::Value {
+        let llfunc = self.pack_soa_prototype();
+        self.copy_soa(llfunc, target_data, true);
+        llfunc
+    }
+
+    /// Generates `unpack_soa` which copies an instance out of the structure of arrays used by
+    /// `eval_soa`.
+    pub fn unpack_soa(&self, target_data: &TargetData) -> &'ll llvm::Value {
+        let llfunc = self.unpack_soa_prototype();
+        self.copy_soa(llfunc, target_data, false);
+        llfunc
+    }
+
+    fn copy_soa(&self, llfunc: &'ll llvm::Value, target_data: &TargetData, to_soa: bool) {
+        let OsdiCompilationUnit { inst_data, cx, .. } = self;
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+
+            let data = LLVMGetParam(llfunc, 0);
+            let num_insts = LLVMGetParam(llfunc, 1);
+            let num_insts =
+                LLVMBuildIntCast2(llbuilder, num_insts, cx.ty_size(), llvm::False, UNNAMED);
+            let idx = LLVMGetParam(llfunc, 2);
+            let idx = LLVMBuildIntCast2(llbuilder, idx, cx.ty_size(), llvm::False, UNNAMED);
+            let instance = LLVMGetParam(llfunc, 3);
+
+            let soa = SoaInstances::new(inst_data, target_data, data, num_insts, idx);
+            inst_data.copy_soa(cx, instance, &soa, to_soa, llbuilder);
+            LLVMBuildRetVoid(llbuilder);
+
+            LLVMDisposeBuilder(llbuilder);
+        }
+    }
+
+    /// Builds the evaluation of the instance `inst`. The flags that are returned by `eval` are
+    /// stored in `ret_flags`. The builder is positioned at the end of the function afterwards.
+    fn build_eval(
+        &self,
+        builder: &mut Builder<'_, '_, 'll>,
+        handle: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
+        model: &'ll llvm::Value,
+        sim_info: &'ll llvm::Value,
+        ret_flags: &'ll llvm::Value,
+    ) {
         let OsdiCompilationUnit { inst_data, model_data, cx, module, .. } = self;
 
         let func = module.eval;
         let intern = module.intern;
 
-        let mut builder = Builder::new(cx, func, llfunc);
-
-        let handle = unsafe { llvm::LLVMGetParam(llfunc, 0) };
-        let instance = unsafe { llvm::LLVMGetParam(llfunc, 1) };
-        let model = unsafe { llvm::LLVMGetParam(llfunc, 2) };
-        let sim_info = unsafe { llvm::LLVMGetParam(llfunc, 3) };
         let sim_info_ty = self.tys.osdi_sim_info;
 
         // let simparam_ty = self.tys.osdi_sim_paras;
@@ -70,16 +232,15 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         let flags = MemLoc::struct_gep(sim_info, sim_info_ty, cx.ty_int(), 5, cx);
 
-        let ret_flags = unsafe { builder.alloca(cx.ty_int()) };
         unsafe { builder.store(ret_flags, cx.const_int(0)) };
 
-        let connected_ports = unsafe { inst_data.load_connected_ports(&builder, instance) };
+        let connected_ports = unsafe { inst_data.load_connected_ports(builder, inst) };
         let prev_solve: TiVec<_, _> = module
             .dae_system
             .unknowns
             .indices()
             .map(|node| unsafe {
-                inst_data.read_node_voltage(cx, node, instance, prev_result, builder.llbuilder)
+                inst_data.read_node_voltage(cx, node, inst, prev_result, builder.llbuilder)
             })
             .collect();
 
@@ -93,7 +254,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         };
 
         let state_idx: TiVec<LimitState, _> = (0..intern.lim_state.len())
-            .map(|i| unsafe { inst_data.read_state_idx(cx, i.into(), instance, builder.llbuilder) })
+            .map(|i| unsafe { inst_data.read_state_idx(cx, i.into(), inst, builder.llbuilder) })
             .collect();
 
         let true_ = cx.const_bool(true);
@@ -110,7 +271,12 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     match *kind {
                         ParamKind::Param(param) => {
                             return inst_data
-                                .param_loc(cx, OsdiInstanceParam::User(param), instance)
+                                .param_loc(
+                                    cx,
+                                    OsdiInstanceParam::User(param),
+                                    inst,
+                                    builder.llbuilder,
+                                )
                                 .unwrap_or_else(|| model_data.param_loc(cx, param, model).unwrap())
                                 .into()
                         }
@@ -140,13 +306,13 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                             get_prev_solve(SimUnknownKind::Implicit(equation))
                         }
                         ParamKind::Temperature => {
-                            return inst_data.temperature_loc(cx, instance).into()
+                            return inst_data.temperature_loc(cx, inst, builder.llbuilder).into()
                         }
                         ParamKind::ParamGiven { param } => {
                             let inst_given = inst_data.is_param_given(
                                 cx,
                                 OsdiInstanceParam::User(param),
-                                instance,
+                                inst,
                                 builder.llbuilder,
                             );
                             match inst_given {
@@ -176,8 +342,9 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         }
                         ParamKind::ParamSysFun(param) => inst_data
                             .read_param(
+                                cx,
                                 OsdiInstanceParam::Builtin(param),
-                                instance,
+                                inst,
                                 builder.llbuilder,
                             )
                             .unwrap(),
@@ -191,8 +358,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                             LLVMBuildAnd(builder.llbuilder, is_not_dc, is_not_ic, UNNAMED)
                         }
                         ParamKind::PrevState(state) => {
-                            let idx =
-                                inst_data.read_state_idx(cx, state, instance, builder.llbuilder);
+                            let idx = inst_data.read_state_idx(cx, state, inst, builder.llbuilder);
                             return MemLoc {
                                 ptr: prev_state,
                                 ptr_ty: cx.ty_double(),
@@ -202,8 +368,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                             .into();
                         }
                         ParamKind::NewState(state) => {
-                            let idx =
-                                inst_data.read_state_idx(cx, state, instance, builder.llbuilder);
+                            let idx = inst_data.read_state_idx(cx, state, inst, builder.llbuilder);
 
                             return MemLoc {
                                 ptr: next_state,
@@ -224,14 +389,14 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         let cache_vals = (0..module.init.cache_slots.len()).map(|i| unsafe {
             let slot = i.into();
-            let val = inst_data.load_cache_slot(module, builder.llbuilder, slot, instance);
+            let val = inst_data.load_cache_slot(cx, module, builder.llbuilder, slot, inst);
             BuilderVal::Eager(val)
         });
 
         params.extend(cache_vals);
         builder.params = params;
 
-        builder.callbacks = general_callbacks(intern, &mut builder, ret_flags, handle, simparam);
+        builder.callbacks = general_callbacks(intern, builder, ret_flags, handle, simparam);
 
         for (func, kind) in intern.callbacks.iter_enumerated() {
             let cb = match *kind {
@@ -295,60 +460,71 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
                 let store_matrix = |builder: &Builder<'_, '_, 'll>| {
                     for entry in module.dae_system.jacobian.keys() {
-                        inst_data.store_jacobian(entry, instance, builder, reactive)
+                        inst_data.store_jacobian(entry, inst, builder, reactive)
                     }
                 };
-                Self::build_store_results(&builder, llfunc, &flags, jacobian_flag, &store_matrix);
+                Self::build_store_results(builder, &flags, jacobian_flag, &store_matrix);
 
                 let store_residual = |builder: &Builder<'_, '_, 'll>| {
                     for unknown in module.dae_system.unknowns.indices() {
-                        inst_data.store_residual(unknown, instance, builder, reactive);
+                        inst_data.store_residual(unknown, inst, builder, reactive);
                     }
                 };
-                Self::build_store_results(&builder, llfunc, &flags, residual_flag, &store_residual);
+                Self::build_store_results(builder, &flags, residual_flag, &store_residual);
 
                 let store_lim_rhs = |builder: &Builder<'_, '_, 'll>| {
                     for unknown in module.dae_system.unknowns.indices() {
-                        inst_data.store_lim_rhs(unknown, instance, builder, reactive);
+                        inst_data.store_lim_rhs(unknown, inst, builder, reactive);
                     }
                 };
-                Self::build_store_results(&builder, llfunc, &flags, lim_rhs_flag, &store_lim_rhs);
+                Self::build_store_results(builder, &flags, lim_rhs_flag, &store_lim_rhs);
             }
 
             let store_opvars = |builder: &Builder<'_, '_, 'll>| {
                 for (_, &eval_output) in &inst_data.opvars {
-                    inst_data.store_eval_output(eval_output, instance, builder)
+                    inst_data.store_eval_output(eval_output, inst, builder)
                 }
             };
-            Self::build_store_results(&builder, llfunc, &flags, CALC_OP, &store_opvars);
+            Self::build_store_results(builder, &flags, CALC_OP, &store_opvars);
             let store_noise = |builder: &Builder<'_, '_, 'll>| {
                 for source in &inst_data.noise {
                     for eval_output in source.eval_outputs() {
-                        inst_data.store_eval_output(eval_output, instance, builder)
+                        inst_data.store_eval_output(eval_output, inst, builder)
                     }
                 }
             };
-            Self::build_store_results(&builder, llfunc, &flags, CALC_NOISE, &store_noise);
-
-            inst_data.store_bound_step(instance, &builder);
+            Self::build_store_results(builder, &flags, CALC_NOISE, &store_noise);
+            let store_sensitivities = |builder: &Builder<'_, '_, 'll>| {
+                for entry in &inst_data.sensitivities {
+                    inst_data.store_sensitivity(entry, inst, builder)
+                }
+            };
+            Self::build_store_results(builder, &flags, CALC_SENSITIVITY, &store_sensitivities);
+            let store_second_derivatives = |builder: &Builder<'_, '_, 'll>| {
+                for entry in &inst_data.second_derivatives {
+                    inst_data.store_second_derivative(entry, inst, builder)
+                }
+            };
+            Self::build_store_results(
+                builder,
+                &flags,
+                CALC_SECOND_DERIVATIVES,
+                &store_second_derivatives,
+            );
 
-            let ret_flags = builder.load(cx.ty_int(), ret_flags);
-            builder.ret(ret_flags);
+            inst_data.store_bound_step(inst, builder);
         }
-
-        llfunc
     }
 
     unsafe fn build_store_results(
         builder: &Builder<'_, '_, 'll>,
-        llfunc: &'ll llvm::Value,
         flags: &MemLoc<'ll>,
         flag: u32,
         store_val: &dyn Fn(&Builder<'_, '_, 'll>),
     ) {
         let cx = builder.cx;
-        let bb = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
-        let next_bb = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+        let bb = LLVMAppendBasicBlockInContext(cx.llcx, builder.fun, UNNAMED);
+        let next_bb = LLVMAppendBasicBlockInContext(cx.llcx, builder.fun, UNNAMED);
 
         let is_set = is_flag_set_mem(cx, flag, flags, builder.llbuilder);
         LLVMBuildCondBr(builder.llbuilder, is_set, bb, next_bb);
@@ -1,11 +1,15 @@
+use std::mem::swap;
+
 use ahash::RandomState;
 use hir::{CompilationDB, ParamSysFun, Parameter, Variable};
 use hir_lower::{HirInterner, LimitState, ParamKind, PlaceKind};
 use indexmap::IndexMap;
 use llvm::{
-    IntPredicate, LLVMBuildFAdd, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildICmp, LLVMBuildIntCast2,
-    LLVMBuildLoad2, LLVMBuildStore, LLVMBuildStructGEP2, LLVMConstInt, LLVMOffsetOfElement,
-    LLVMSetFastMath, TargetData, UNNAMED,
+    IntPredicate, LLVMBuildAdd, LLVMBuildFAdd, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildICmp,
+    LLVMBuildIntCast2, LLVMBuildLoad2, LLVMBuildMul, LLVMBuildStore, LLVMBuildStructGEP2,
+    LLVMConstInt, LLVMCountStructElementTypes, LLVMGetArrayLength, LLVMGetElementType,
+    LLVMGetTypeKind, LLVMOffsetOfElement, LLVMSetFastMath, LLVMStructGetTypeAtIndex, TargetData,
+    TypeKind, UNNAMED,
 };
 use mir::{strip_optbarrier, Const, Function, Param, ValueDef, F_ZERO};
 use mir_llvm::{CodegenCx, MemLoc};
@@ -156,6 +160,66 @@ impl MatrixEntry {
     }
 }
 
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct SensitivityEntry {
+    pub row: SimUnknown,
+    /// position of the parameter in `OsdiDescriptor::sensitivity_params`
+    pub sensitivity: u32,
+    pub resist: Option<EvalOutput>,
+    pub react: Option<EvalOutput>,
+}
+
+impl SensitivityEntry {
+    pub fn new<'ll>(
+        entry: &dae::SensitivityEntry,
+        module: &OsdiModule<'_>,
+        slots: &mut TiMap<EvalOutputSlot, mir::Value, &'ll llvm::Type>,
+        ty_real: &'ll llvm::Type,
+    ) -> SensitivityEntry {
+        let mut get_output = |mut val| {
+            val = strip_optbarrier(module.eval, val);
+            if val == F_ZERO {
+                None
+            } else {
+                Some(EvalOutput::new(module, val, slots, false, ty_real))
+            }
+        };
+        let sensitivity =
+            module.info.sensitivities.iter().position(|&param| param == entry.param).unwrap();
+        SensitivityEntry {
+            row: entry.row,
+            sensitivity: sensitivity as u32,
+            resist: get_output(entry.resist),
+            react: get_output(entry.react),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct SecondDerivativeEntry {
+    pub resist: Option<EvalOutput>,
+    pub react: Option<EvalOutput>,
+}
+
+impl SecondDerivativeEntry {
+    pub fn new<'ll>(
+        entry: &dae::SecondDerivativeEntry,
+        module: &OsdiModule<'_>,
+        slots: &mut TiMap<EvalOutputSlot, mir::Value, &'ll llvm::Type>,
+        ty_real: &'ll llvm::Type,
+    ) -> SecondDerivativeEntry {
+        let mut get_output = |mut val| {
+            val = strip_optbarrier(module.eval, val);
+            if val == F_ZERO {
+                None
+            } else {
+                Some(EvalOutput::new(module, val, slots, false, ty_real))
+            }
+        };
+        SecondDerivativeEntry { resist: get_output(entry.resist), react: get_output(entry.react) }
+    }
+}
+
 #[derive(Debug)]
 pub struct NoiseSource {
     pub factor: EvalOutput,
@@ -187,6 +251,54 @@ impl NoiseSource {
     }
 }
 
+/// Pointer to the data of an instance
+#[derive(Clone, Copy)]
+pub enum InstPtr<'a, 'll> {
+    /// pointer to an instance struct (`OsdiInstanceData::ty`)
+    Struct(&'ll llvm::Value),
+    /// an instance stored in a structure of arrays
+    Soa(&'a SoaInstances<'ll>),
+}
+
+impl<'ll> From<&'ll llvm::Value> for InstPtr<'_, 'll> {
+    fn from(ptr: &'ll llvm::Value) -> Self {
+        InstPtr::Struct(ptr)
+    }
+}
+
+/// The data of `num_insts` instances stored as a structure of arrays (used by `eval_soa`).
+/// Each field of the instance struct is replaced by an array that contains the field of all
+/// instances, so that the same field of consecutive instances is adjacent in memory:
+///
+/// * the array for field `f` starts at byte `num_insts * offset_of(f)`
+/// * element `k` of an array field is stored at index `k * num_insts + i` for instance `i`
+///
+/// The structure of arrays therefore occupies exactly `num_insts * instance_size` bytes.
+pub struct SoaInstances<'ll> {
+    pub data: &'ll llvm::Value,
+    /// the number of instances (`usize`)
+    pub num_insts: &'ll llvm::Value,
+    /// the instance that is accessed (`usize`)
+    pub idx: &'ll llvm::Value,
+    field_offsets: Box<[u64]>,
+}
+
+impl<'ll> SoaInstances<'ll> {
+    pub fn new(
+        inst_data: &OsdiInstanceData<'ll>,
+        target_data: &TargetData,
+        data: &'ll llvm::Value,
+        num_insts: &'ll llvm::Value,
+        idx: &'ll llvm::Value,
+    ) -> SoaInstances<'ll> {
+        let num_fields = unsafe { LLVMCountStructElementTypes(inst_data.ty) };
+        let field_offsets = (0..num_fields)
+            .map(|elem| unsafe { LLVMOffsetOfElement(target_data, inst_data.ty, elem) })
+            .collect();
+        SoaInstances { data, num_insts, idx, field_offsets }
+    }
+}
+
 pub struct OsdiInstanceData<'ll> {
     /// llvm type for the instance data struct
     pub ty: &'ll llvm::Type,
@@ -208,6 +320,8 @@ pub struct OsdiInstanceData<'ll> {
     pub noise: Vec<NoiseSource>,
     pub opvars: IndexMap<Variable, EvalOutput, RandomState>,
     pub jacobian: TiVec<MatrixEntryId, MatrixEntry>,
+    pub sensitivities: Vec<SensitivityEntry>,
+    pub second_derivatives: Vec<SecondDerivativeEntry>,
     pub bound_step: Option<EvalOutputSlot>,
 }
 
@@ -260,6 +374,18 @@ impl<'ll> OsdiInstanceData<'ll> {
             .iter()
             .map(|entry| MatrixEntry::new(entry, module, &mut eval_outputs, ty_f64, &mut num_react))
             .collect();
+        let sensitivities = module
+            .dae_system
+            .sensitivities
+            .iter()
+            .map(|entry| SensitivityEntry::new(entry, module, &mut eval_outputs, ty_f64))
+            .collect();
+        let second_derivatives = module
+            .dae_system
+            .second_derivatives
+            .iter()
+            .map(|entry| SecondDerivativeEntry::new(entry, module, &mut eval_outputs, ty_f64))
+            .collect();
         let noise = module
             .dae_system
             .noise_sources
@@ -322,17 +448,19 @@ impl<'ll> OsdiInstanceData<'ll> {
             noise,
             opvars,
             jacobian,
+            sensitivities,
+            second_derivatives,
             bound_step,
         }
     }
 
     pub unsafe fn store_bound_step(
         &self,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         builder: &mir_llvm::Builder<'_, '_, 'll>,
     ) {
         if let Some(slot) = self.bound_step {
-            self.store_eval_output_slot(slot, ptr, builder);
+            self.store_eval_output_slot(slot, inst, builder);
         }
     }
 
@@ -365,37 +493,38 @@ impl<'ll> OsdiInstanceData<'ll> {
         (ptr, ty)
     }
 
-    pub fn nth_param_loc(
+    pub unsafe fn nth_param_loc(
         &self,
         cx: &CodegenCx<'_, 'll>,
         pos: u32,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
+        llbuilder: &llvm::Builder<'ll>,
     ) -> MemLoc<'ll> {
-        let ty = self.params.get_index(pos as usize).unwrap().1;
         let elem = NUM_CONST_FIELDS + pos;
-        MemLoc::struct_gep(ptr, self.ty, ty, elem, cx)
+        self.field_loc(cx, elem, None, inst, llbuilder)
     }
 
-    pub fn param_loc(
+    pub unsafe fn param_loc(
         &self,
         cx: &CodegenCx<'_, 'll>,
         param: OsdiInstanceParam,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
+        llbuilder: &llvm::Builder<'ll>,
     ) -> Option<MemLoc<'ll>> {
         let pos = self.params.get_index_of(&param)? as u32;
-        let res = self.nth_param_loc(cx, pos, ptr);
+        let res = self.nth_param_loc(cx, pos, inst, llbuilder);
         Some(res)
     }
 
     pub unsafe fn read_param(
         &self,
+        cx: &CodegenCx<'_, 'll>,
         param: OsdiInstanceParam,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         llbuilder: &llvm::Builder<'ll>,
     ) -> Option<&'ll llvm::Value> {
-        let (ptr, ty) = self.param_ptr(param, ptr, llbuilder)?;
-        let val = LLVMBuildLoad2(llbuilder, ty, ptr, UNNAMED);
-        Some(val)
+        let loc = self.param_loc(cx, param, inst, llbuilder)?;
+        Some(loc.read(llbuilder))
     }
 
     pub unsafe fn store_nth_param(
@@ -462,6 +591,86 @@ impl<'ll> OsdiInstanceData<'ll> {
         Some(off)
     }
 
+    /// Returns the memory location of field `elem` of the instance `inst`. If an index
+    /// `idx` is passed the field is an array and the location of the element `idx` is
+    /// returned instead.
+    unsafe fn field_loc(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        elem: u32,
+        idx: Option<&'ll llvm::Value>,
+        inst: InstPtr<'_, 'll>,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> MemLoc<'ll> {
+        let mut ty = LLVMStructGetTypeAtIndex(self.ty, elem);
+        if idx.is_some() {
+            ty = LLVMGetElementType(ty);
+        }
+        match inst {
+            InstPtr::Struct(ptr) => {
+                let mut indices = vec![cx.const_unsigned_int(0), cx.const_unsigned_int(elem)];
+                indices.extend(idx);
+                MemLoc { ptr, ptr_ty: self.ty, ty, indices: indices.into_boxed_slice() }
+            }
+            InstPtr::Soa(soa) => {
+                let mut off = cx.const_usize(soa.field_offsets[elem as usize] as usize);
+                off = LLVMBuildMul(llbuilder, soa.num_insts, off, UNNAMED);
+                let arr =
+                    LLVMBuildGEP2(llbuilder, cx.ty_char(), soa.data, [off].as_ptr(), 1, UNNAMED);
+                let mut pos = soa.idx;
+                if let Some(idx) = idx {
+                    let idx = LLVMBuildIntCast2(llbuilder, idx, cx.ty_size(), llvm::False, UNNAMED);
+                    let off = LLVMBuildMul(llbuilder, idx, soa.num_insts, UNNAMED);
+                    pos = LLVMBuildAdd(llbuilder, off, pos, UNNAMED);
+                }
+                MemLoc { ptr: arr, ptr_ty: ty, ty, indices: vec![pos].into_boxed_slice() }
+            }
+        }
+    }
+
+    unsafe fn field_ptr(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        elem: u32,
+        idx: Option<&'ll llvm::Value>,
+        inst: InstPtr<'_, 'll>,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        self.field_loc(cx, elem, idx, inst, llbuilder).to_ptr(llbuilder)
+    }
+
+    /// Copies all fields of the instance struct `inst` into the structure of arrays `soa` or
+    /// the other way around if `to_soa` is false.
+    pub unsafe fn copy_soa(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        inst: &'ll llvm::Value,
+        soa: &SoaInstances<'ll>,
+        to_soa: bool,
+        llbuilder: &llvm::Builder<'ll>,
+    ) {
+        for elem in 0..LLVMCountStructElementTypes(self.ty) {
+            let ty = LLVMStructGetTypeAtIndex(self.ty, elem);
+            let indices = if LLVMGetTypeKind(ty) == TypeKind::Array {
+                let len = LLVMGetArrayLength(ty);
+                (0..len).map(|i| Some(cx.const_unsigned_int(i))).collect()
+            } else {
+                vec![None]
+            };
+
+            for idx in indices {
+                let mut src = self.field_ptr(cx, elem, idx, inst.into(), llbuilder);
+                let mut dst = self.field_ptr(cx, elem, idx, InstPtr::Soa(soa), llbuilder);
+                if !to_soa {
+                    swap(&mut src, &mut dst);
+                }
+                let ty = if idx.is_some() { LLVMGetElementType(ty) } else { ty };
+                let val = LLVMBuildLoad2(llbuilder, ty, src, UNNAMED);
+                LLVMBuildStore(llbuilder, val, dst);
+            }
+        }
+    }
+
     unsafe fn eval_output_slot_ptr(
         &self,
         llbuilder: &llvm::Builder<'ll>,
@@ -494,23 +703,24 @@ impl<'ll> OsdiInstanceData<'ll> {
     pub unsafe fn store_eval_output_slot(
         &self,
         slot: EvalOutputSlot,
-        inst_ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         builder: &mir_llvm::Builder<'_, '_, 'll>,
     ) {
         let val = *self.eval_outputs.get_index(slot).unwrap().0;
         let val = builder.values[val].get(builder);
-        let (ptr, _) = self.eval_output_slot_ptr(builder.llbuilder, inst_ptr, slot);
+        let elem = self.eval_output_slot_elem(slot);
+        let ptr = self.field_ptr(builder.cx, elem, None, inst, builder.llbuilder);
         builder.store(ptr, val)
     }
 
     pub unsafe fn store_eval_output(
         &self,
         output: EvalOutput,
-        inst_ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         builder: &mir_llvm::Builder<'_, '_, 'll>,
     ) {
         if let EvalOutput::Calculated(slot) = output {
-            self.store_eval_output_slot(slot, inst_ptr, builder)
+            self.store_eval_output_slot(slot, inst, builder)
         }
     }
 
@@ -540,22 +750,24 @@ impl<'ll> OsdiInstanceData<'ll> {
         &self,
         cx: &CodegenCx<'_, 'll>,
         pos: u32,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         llbuilder: &llvm::Builder<'ll>,
     ) -> &'ll llvm::Value {
-        let arr_ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, PARAM_GIVEN, UNNAMED);
-        bitfield::is_set(cx, pos, arr_ptr, self.param_given, llbuilder)
+        let (word, mask) = bitfield::word_index_and_mask(pos);
+        let word = cx.const_unsigned_int(word);
+        let word = self.field_loc(cx, PARAM_GIVEN, Some(word), inst, llbuilder).read(llbuilder);
+        bitfield::is_flag_set(cx, mask, word, llbuilder)
     }
 
     pub unsafe fn is_param_given(
         &self,
         cx: &CodegenCx<'_, 'll>,
         param: OsdiInstanceParam,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         llbuilder: &llvm::Builder<'ll>,
     ) -> Option<&'ll llvm::Value> {
         let pos = self.params.get_index_of(&param)?;
-        let res = self.is_nth_param_given(cx, pos as u32, ptr, llbuilder);
+        let res = self.is_nth_param_given(cx, pos as u32, inst, llbuilder);
         Some(res)
     }
 
@@ -589,40 +801,33 @@ impl<'ll> OsdiInstanceData<'ll> {
         &self,
         cx: &CodegenCx<'_, 'll>,
         node: SimUnknown,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         llbuilder: &llvm::Builder<'ll>,
     ) -> &'ll llvm::Value {
-        let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, NODE_MAPPING, UNNAMED);
-        let zero = cx.const_int(0);
         let node = cx.const_unsigned_int(node.into());
-        let ptr =
-            LLVMBuildGEP2(llbuilder, self.node_mapping, ptr, [zero, node].as_ptr(), 2, UNNAMED);
-        LLVMBuildLoad2(llbuilder, cx.ty_int(), ptr, UNNAMED)
+        self.field_loc(cx, NODE_MAPPING, Some(node), inst, llbuilder).read(llbuilder)
     }
 
     pub unsafe fn read_state_idx(
         &self,
         cx: &CodegenCx<'_, 'll>,
         idx: LimitState,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         llbuilder: &llvm::Builder<'ll>,
     ) -> &'ll llvm::Value {
-        let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, STATE_IDX, UNNAMED);
-        let zero = cx.const_int(0);
         let state = cx.const_unsigned_int(idx.into());
-        let ptr = LLVMBuildGEP2(llbuilder, self.state_idx, ptr, [zero, state].as_ptr(), 2, UNNAMED);
-        LLVMBuildLoad2(llbuilder, cx.ty_int(), ptr, UNNAMED)
+        self.field_loc(cx, STATE_IDX, Some(state), inst, llbuilder).read(llbuilder)
     }
 
     pub unsafe fn read_node_voltage(
         &self,
         cx: &CodegenCx<'_, 'll>,
         node: SimUnknown,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         prev_result: &'ll llvm::Value,
         llbuilder: &llvm::Builder<'ll>,
     ) -> &'ll llvm::Value {
-        let off = self.read_node_off(cx, node, ptr, llbuilder);
+        let off = self.read_node_off(cx, node, inst, llbuilder);
         let ptr = LLVMBuildGEP2(llbuilder, cx.ty_double(), prev_result, [off].as_ptr(), 1, UNNAMED);
         LLVMBuildLoad2(llbuilder, cx.ty_double(), ptr, UNNAMED)
     }
@@ -643,14 +848,14 @@ impl<'ll> OsdiInstanceData<'ll> {
     pub unsafe fn store_lim_rhs(
         &self,
         node: SimUnknown,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         builder: &mir_llvm::Builder<'_, '_, 'll>,
         reactive: bool,
     ) -> bool {
         let dst = &self.residual[node];
         let slot = if reactive { dst.react_lim_rhs } else { dst.resist_lim_rhs };
         if let Some(slot) = slot.expand() {
-            self.store_eval_output_slot(slot, ptr, builder);
+            self.store_eval_output_slot(slot, inst, builder);
             true
         } else {
             false
@@ -673,14 +878,14 @@ impl<'ll> OsdiInstanceData<'ll> {
     pub unsafe fn store_residual(
         &self,
         node: SimUnknown,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         builder: &mir_llvm::Builder<'_, '_, 'll>,
         reactive: bool,
     ) -> bool {
         let residual = &self.residual[node];
         let slot = if reactive { residual.react } else { residual.resist };
         if let Some(slot) = slot.expand() {
-            self.store_eval_output_slot(slot, ptr, builder);
+            self.store_eval_output_slot(slot, inst, builder);
             true
         } else {
             false
@@ -698,7 +903,7 @@ impl<'ll> OsdiInstanceData<'ll> {
         llbuilder: &llvm::Builder<'ll>,
         negate: bool,
     ) {
-        let off = self.read_node_off(cx, node, ptr, llbuilder);
+        let off = self.read_node_off(cx, node, ptr.into(), llbuilder);
         let dst = LLVMBuildGEP2(llbuilder, cx.ty_double(), dst, [off].as_ptr(), 1, UNNAMED);
         let old = LLVMBuildLoad2(llbuilder, cx.ty_double(), dst, UNNAMED);
         let val = if negate {
@@ -713,14 +918,36 @@ impl<'ll> OsdiInstanceData<'ll> {
     pub unsafe fn store_jacobian(
         &self,
         entry: MatrixEntryId,
-        inst_ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
         builder: &mir_llvm::Builder<'_, '_, 'll>,
         reactive: bool,
     ) {
         let entry = &self.jacobian[entry];
         let dst = if reactive { entry.react } else { entry.resist };
         if let Some(EvalOutput::Calculated(slot)) = dst {
-            self.store_eval_output_slot(slot, inst_ptr, builder)
+            self.store_eval_output_slot(slot, inst, builder)
+        }
+    }
+
+    pub unsafe fn store_sensitivity(
+        &self,
+        entry: &SensitivityEntry,
+        inst: InstPtr<'_, 'll>,
+        builder: &mir_llvm::Builder<'_, '_, 'll>,
+    ) {
+        for output in [entry.resist, entry.react].into_iter().flatten() {
+            self.store_eval_output(output, inst, builder)
+        }
+    }
+
+    pub unsafe fn store_second_derivative(
+        &self,
+        entry: &SecondDerivativeEntry,
+        inst: InstPtr<'_, 'll>,
+        builder: &mir_llvm::Builder<'_, '_, 'll>,
+    ) {
+        for output in [entry.resist, entry.react].into_iter().flatten() {
+            self.store_eval_output(output, inst, builder)
         }
     }
 
@@ -769,13 +996,15 @@ impl<'ll> OsdiInstanceData<'ll> {
 
     pub unsafe fn load_cache_slot(
         &self,
+        cx: &CodegenCx<'_, 'll>,
         module: &OsdiModule,
         llbuilder: &llvm::Builder<'ll>,
         slot: CacheSlot,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
     ) -> &'ll llvm::Value {
-        let (ptr, ty) = self.cache_slot_ptr(llbuilder, slot, ptr);
-        let mut val = LLVMBuildLoad2(llbuilder, ty, ptr, UNNAMED);
+        let ty = self.cache_slots[slot];
+        let elem = self.cache_slot_elem(slot);
+        let mut val = self.field_loc(cx, elem, None, inst, llbuilder).read(llbuilder);
 
         if module.init.cache_slots[slot] == hir::Type::Bool {
             val = LLVMBuildICmp(
@@ -827,9 +1056,10 @@ impl<'ll> OsdiInstanceData<'ll> {
     pub unsafe fn temperature_loc(
         &self,
         cx: &CodegenCx<'_, 'll>,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
+        llbuilder: &llvm::Builder<'ll>,
     ) -> MemLoc<'ll> {
-        MemLoc::struct_gep(ptr, self.ty, cx.ty_double(), TEMPERATURE, cx)
+        self.field_loc(cx, TEMPERATURE, None, inst, llbuilder)
     }
 
     pub unsafe fn store_temperature(
@@ -845,10 +1075,10 @@ impl<'ll> OsdiInstanceData<'ll> {
     pub unsafe fn load_connected_ports(
         &self,
         builder: &mir_llvm::Builder<'_, '_, 'll>,
-        ptr: &'ll llvm::Value,
+        inst: InstPtr<'_, 'll>,
     ) -> &'ll llvm::Value {
-        let ptr = builder.struct_gep(self.ty, ptr, CONNECTED);
-        builder.load(builder.cx.ty_int(), ptr)
+        let loc = self.field_loc(builder.cx, CONNECTED, None, inst, builder.llbuilder);
+        loc.read(builder.llbuilder)
     }
 
     pub unsafe fn store_connected_ports(
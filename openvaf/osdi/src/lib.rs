@@ -3,18 +3,21 @@ use camino::{Utf8Path, Utf8PathBuf};
 use hir::{CompilationDB, ParamSysFun, Type};
 use hir_lower::{CallBackKind, HirInterner, ParamKind};
 use lasso::Rodeo;
-use llvm::{LLVMDisposeTargetData, OptLevel};
-use mir_llvm::{CodegenCx, LLVMBackend};
+use llvm::orc::{Jit, JitError};
+use llvm::{LLVMABISizeOfType, LLVMDisposeTargetData, OptLevel};
+use mir_llvm::{CodegenCx, LLVMBackend, ModuleLlvm};
 use salsa::ParallelDatabase;
 use sim_back::{CompiledModule, ModuleInfo};
+use stdx::iter::zip;
 use stdx::{impl_debug_display, impl_idx_from};
 use target::spec::Target;
 use typed_indexmap::TiSet;
 
 use std::ffi::CString;
+use std::sync::Mutex;
 
 use crate::compilation_unit::{new_codegen, OsdiCompilationUnit, OsdiModule};
-use crate::metadata::osdi_0_3::OsdiTys;
+use crate::metadata::osdi_0_4::OsdiTys;
 use crate::metadata::OsdiLimFunction;
 
 mod access;
@@ -29,7 +32,7 @@ mod load;
 mod noise;
 mod setup;
 
-const OSDI_VERSION: (u32, u32) = (0, 3);
+const OSDI_VERSION: (u32, u32) = (0, 4);
 
 pub fn compile(
     db: &CompilationDB,
@@ -40,27 +43,110 @@ pub fn compile(
     emit: bool,
     opt_lvl: OptLevel,
 ) -> Vec<Utf8PathBuf> {
+    compile_bundle(&[(db, modules)], dst, target, back, emit, opt_lvl)
+}
+
+/// Compiles the modules of multiple compilation units into a single library.
+/// All descriptors are exported in one `OSDI_DESCRIPTORS` table (in the order
+/// of `units`). The caller must ensure that module names are unique across all units.
+pub fn compile_bundle(
+    units: &[(&CompilationDB, &[ModuleInfo])],
+    dst: &Utf8Path,
+    target: &Target,
+    back: &LLVMBackend,
+    emit: bool,
+    opt_lvl: OptLevel,
+) -> Vec<Utf8PathBuf> {
+    let num_modules: usize = units.iter().map(|(_, modules)| modules.len()).sum();
+    let mut paths: Vec<Utf8PathBuf> = (0..num_modules * 4)
+        .map(|i| {
+            let num = base_n::encode((i + 1) as u128, CASE_INSENSITIVE);
+            let extension = format!("o{num}");
+            dst.with_extension(extension)
+        })
+        .collect();
+    paths.push(dst.with_extension("o"));
+
+    let name = dst.file_stem().expect("destination is a file");
+    let emit_object: &EmitFn = &|i, llmod| {
+        assert_eq!(llmod.emit_object(paths[i].as_ref()), Ok(()));
+    };
+    compile_objects(units, name, target, back, emit.then_some(emit_object), opt_lvl);
+    paths
+}
+
+/// Compiles the modules of multiple compilation units into memory and loads them into `jit`
+/// (see [`compile_bundle`]). The OSDI symbols can be looked up in the JIT afterwards.
+pub fn compile_bundle_jit(
+    units: &[(&CompilationDB, &[ModuleInfo])],
+    name: &str,
+    target: &Target,
+    back: &LLVMBackend,
+    opt_lvl: OptLevel,
+    jit: &Jit,
+) -> Result<(), JitError> {
+    let err = Mutex::new(None);
+    let emit_object: &EmitFn = &|_, llmod| {
+        let res = llmod.emit_object_to_memory().map_err(JitError::from);
+        if let Err(new_err) = res.and_then(|obj| jit.add_object(obj)) {
+            err.lock().unwrap().get_or_insert(new_err);
+        }
+    };
+    compile_objects(units, name, target, back, Some(emit_object), opt_lvl);
+    match err.into_inner().unwrap() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+type EmitFn<'a> = dyn Fn(usize, &ModuleLlvm) + Sync + 'a;
+
+/// Generates the LLVM modules for all OSDI functions of `units` and passes
+/// each module to `emit` (if present) once it is complete. The modules are
+/// numbered by the index of the object file that is produced: four objects
+/// for each module followed by the module that contains the descriptors.
+fn compile_objects(
+    units: &[(&CompilationDB, &[ModuleInfo])],
+    name: &str,
+    target: &Target,
+    back: &LLVMBackend,
+    emit: Option<&EmitFn>,
+    opt_lvl: OptLevel,
+) {
+    let module_infos: Vec<_> = units
+        .iter()
+        .enumerate()
+        .flat_map(|(unit, &(db, modules))| modules.iter().map(move |module| (unit, db, module)))
+        .collect();
+
+    // lower and optimize all modules in parallel. Each module uses its own string
+    // interner, these are merged in the original module order afterwards so that
+    // the generated code is deterministic
+    let mut modules: Vec<Option<(CompiledModule, Rodeo)>> =
+        module_infos.iter().map(|_| None).collect();
+    rayon_core::scope(|scope| {
+        for (&(_, db, module), dst) in zip(&module_infos, &mut modules) {
+            let db = db.snapshot();
+            scope.spawn(move |_| {
+                let mut literals = Rodeo::new();
+                let mir = CompiledModule::new(&db, module, &mut literals);
+                *dst = Some((mir, literals))
+            });
+        }
+    });
+
     let mut literals = Rodeo::new();
     let mut lim_table = TiSet::default();
-    let modules: Vec<_> = modules
-        .iter()
-        .map(|module| {
-            let mir = CompiledModule::new(db, module, &mut literals);
+    let modules: Vec<_> = zip(&module_infos, modules)
+        .map(|(&(unit, _, _), mir)| {
+            let (mut mir, local_literals) = mir.unwrap();
+            mir.remap_literals(&local_literals, &mut literals);
             for cb in mir.intern.callbacks.iter() {
                 if let CallBackKind::BuiltinLimit { name, num_args } = *cb {
                     lim_table.ensure(OsdiLimFunction { name, num_args: num_args - 2 });
                 }
             }
-            mir
-        })
-        .collect();
-    let name = dst.file_stem().expect("destination is a file").to_owned();
-
-    let mut paths: Vec<Utf8PathBuf> = (0..modules.len() * 4)
-        .map(|i| {
-            let num = base_n::encode((i + 1) as u128, CASE_INSENSITIVE);
-            let extension = format!("o{num}");
-            dst.with_extension(extension)
+            (unit, mir)
         })
         .collect();
 
@@ -71,24 +157,24 @@ pub fn compile(
 
     let modules: Vec<_> = modules
         .iter()
-        .map(|module| {
-            let unit = OsdiModule::new(db, module, &lim_table);
-            unit.intern_names(&mut literals, db);
-            unit
+        .map(|(unit, module)| {
+            let db = units[*unit].0;
+            let osdi_module = OsdiModule::new(db, module, &lim_table, *unit);
+            osdi_module.intern_names(&mut literals, db);
+            (*unit, osdi_module)
         })
         .collect();
 
-    let db = db.snapshot();
-
-    let main_file = dst.with_extension("o");
+    let dbs: Vec<_> = units.iter().map(|(db, _)| db.snapshot()).collect();
 
     rayon_core::scope(|scope| {
-        let db = db;
+        let dbs = &dbs;
         let literals_ = &literals;
         let target_data_ = &target_data;
-        let paths = &paths;
 
-        for (i, module) in modules.iter().enumerate() {
+        for (i, (unit, module)) in modules.iter().enumerate() {
+            let db = &dbs[*unit];
+            let debug = &module.info.debug;
             let _db = db.snapshot();
             scope.spawn(move |_| {
                 let access = format!("access_{}", &module.sym);
@@ -100,10 +186,9 @@ pub fn compile(
                 cguint.access_function();
                 debug_assert!(llmod.verify_and_print());
 
-                if emit {
-                    let path = &paths[i * 4];
-                    llmod.optimize();
-                    assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
+                if let Some(emit) = emit {
+                    debug.time("llvm_optimize", || llmod.optimize());
+                    debug.time("llvm_emit", || emit(i * 4, &llmod));
                 }
             });
 
@@ -118,10 +203,9 @@ pub fn compile(
                 cguint.setup_model();
                 debug_assert!(llmod.verify_and_print());
 
-                if emit {
-                    let path = &paths[i * 4 + 1];
+                if let Some(emit) = emit {
                     // llmod.optimize();
-                    assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
+                    debug.time("llvm_emit", || emit(i * 4 + 1, &llmod));
                 }
             });
 
@@ -136,10 +220,9 @@ pub fn compile(
                 cguint.setup_instance();
                 debug_assert!(llmod.verify_and_print());
 
-                if emit {
-                    let path = &paths[i * 4 + 2];
-                    llmod.optimize();
-                    assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
+                if let Some(emit) = emit {
+                    debug.time("llvm_optimize", || llmod.optimize());
+                    debug.time("llvm_emit", || emit(i * 4 + 2, &llmod));
                 }
             });
 
@@ -153,13 +236,15 @@ pub fn compile(
 
                 // println!("{:?}", module.eval);
                 cguint.eval();
+                cguint.eval_soa(target_data_);
+                cguint.pack_soa(target_data_);
+                cguint.unpack_soa(target_data_);
                 // println!("{}", llmod.to_str());
                 debug_assert!(llmod.verify_and_print());
 
-                if emit {
-                    let path = &paths[i * 4 + 3];
-                    llmod.optimize();
-                    assert_eq!(llmod.emit_object(path.as_ref()), Ok(()))
+                if let Some(emit) = emit {
+                    debug.time("llvm_optimize", || llmod.optimize());
+                    debug.time("llvm_emit", || emit(i * 4 + 3, &llmod));
                 }
             });
         }
@@ -170,9 +255,10 @@ pub fn compile(
 
         let descriptors: Vec<_> = modules
             .iter()
-            .map(|module| {
-                let cguint = OsdiCompilationUnit::new(&db, module, &cx, &tys, false);
-                let descriptor = cguint.descriptor(target_data, &db);
+            .map(|(unit, module)| {
+                let db = &dbs[*unit];
+                let cguint = OsdiCompilationUnit::new(db, module, &cx, &tys, false);
+                let descriptor = cguint.descriptor(target_data, db);
                 descriptor.to_ll_val(&cx, &tys)
             })
             .collect();
@@ -184,6 +270,15 @@ pub fn compile(
             cx.const_unsigned_int(descriptors.len() as u32),
             true,
         );
+        // new OSDI versions only append fields to the descriptor, simulators can use the
+        // size to iterate OSDI_DESCRIPTORS with the header of an older version
+        let descriptor_size = unsafe { LLVMABISizeOfType(target_data, tys.osdi_descriptor) };
+        cx.export_val(
+            "OSDI_DESCRIPTOR_SIZE",
+            cx.ty_int(),
+            cx.const_unsigned_int(descriptor_size as u32),
+            true,
+        );
         cx.export_val(
             "OSDI_VERSION_MAJOR",
             cx.ty_int(),
@@ -220,17 +315,15 @@ pub fn compile(
 
         debug_assert!(llmod.verify_and_print());
 
-        if emit {
+        if let Some(emit) = emit {
             // println!("{}", llmod.to_str());
             llmod.optimize();
             // println!("{}", llmod.to_str());
-            assert_eq!(llmod.emit_object(main_file.as_ref()), Ok(()))
+            emit(modules.len() * 4, &llmod);
         }
     });
 
-    paths.push(main_file);
     unsafe { LLVMDisposeTargetData(target_data) };
-    paths
 }
 
 impl OsdiModule<'_> {
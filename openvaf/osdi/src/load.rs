@@ -1,8 +1,8 @@
 use llvm::{
-    LLVMAppendBasicBlockInContext, LLVMBuildCall2, LLVMBuildFAdd, LLVMBuildFDiv, LLVMBuildFMul,
-    LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildRetVoid, LLVMBuildStore, LLVMCreateBuilderInContext,
-    LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd, LLVMSetFastMath,
-    LLVMSetPartialFastMath, UNNAMED,
+    LLVMAddCase, LLVMAppendBasicBlockInContext, LLVMBuildBr, LLVMBuildCall2, LLVMBuildFAdd,
+    LLVMBuildFDiv, LLVMBuildFMul, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildLoad2, LLVMBuildRetVoid,
+    LLVMBuildStore, LLVMBuildSwitch, LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetParam,
+    LLVMPositionBuilderAtEnd, LLVMSetFastMath, LLVMSetPartialFastMath, UNNAMED,
 };
 use sim_back::dae::NoiseSourceKind;
 use stdx::iter::zip;
@@ -135,6 +135,97 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         llfunc
     }
 
+    pub fn load_sensitivity(&self, reactive: bool) -> &'ll llvm::Value {
+        let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
+        let ptr_ty = cx.ty_ptr();
+        let fun_ty = cx.ty_func(&[ptr_ty, ptr_ty, cx.ty_int(), ptr_ty], cx.ty_void());
+        let name = &format!(
+            "load_sensitivity_{}_{}",
+            if reactive { "react" } else { "resist" },
+            module.sym
+        );
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let exit = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+
+            // get params
+            let inst = LLVMGetParam(llfunc, 0);
+            let model = LLVMGetParam(llfunc, 1);
+            let sensitivity = LLVMGetParam(llfunc, 2);
+            let dst = LLVMGetParam(llfunc, 3);
+
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            let num_sensitivities = module.info.sensitivities.len() as u32;
+            let switch = LLVMBuildSwitch(llbuilder, sensitivity, exit, num_sensitivities);
+
+            for i in 0..num_sensitivities {
+                let bb = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+                LLVMAddCase(switch, cx.const_unsigned_int(i), bb);
+                LLVMPositionBuilderAtEnd(llbuilder, bb);
+                for entry in inst_data.sensitivities.iter().filter(|entry| entry.sensitivity == i) {
+                    let val = if reactive { entry.react } else { entry.resist };
+                    if let Some(val) = val {
+                        let contrib = self.load_eval_output(val, inst, model, llbuilder);
+                        inst_data
+                            .store_contrib(cx, entry.row, inst, dst, contrib, llbuilder, false);
+                    }
+                }
+                LLVMBuildBr(llbuilder, exit);
+            }
+
+            LLVMPositionBuilderAtEnd(llbuilder, exit);
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+
+    pub fn load_second_derivatives(&self, reactive: bool) -> &'ll llvm::Value {
+        let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
+        let ptr_ty = cx.ty_ptr();
+        let fun_ty = cx.ty_func(&[ptr_ty, ptr_ty, ptr_ty], cx.ty_void());
+        let name = &format!(
+            "load_second_derivatives_{}_{}",
+            if reactive { "react" } else { "resist" },
+            module.sym
+        );
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+
+            // get params
+            let inst = LLVMGetParam(llfunc, 0);
+            let model = LLVMGetParam(llfunc, 1);
+            let dst = LLVMGetParam(llfunc, 2);
+
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            for (i, entry) in inst_data.second_derivatives.iter().enumerate() {
+                let val = if reactive { entry.react } else { entry.resist };
+                if let Some(val) = val {
+                    let contrib = self.load_eval_output(val, inst, model, llbuilder);
+                    let off = cx.const_unsigned_int(i as u32);
+                    let dst =
+                        LLVMBuildGEP2(llbuilder, cx.ty_double(), dst, [off].as_ptr(), 1, UNNAMED);
+                    let old = LLVMBuildLoad2(llbuilder, cx.ty_double(), dst, UNNAMED);
+                    let val = LLVMBuildFAdd(llbuilder, old, contrib, UNNAMED);
+                    LLVMSetFastMath(val);
+                    LLVMBuildStore(llbuilder, val, dst);
+                }
+            }
+
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+
     pub fn load_lim_rhs(&self, reactive: bool) -> &'ll llvm::Value {
         let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
         let void_ptr = cx.ty_ptr();
@@ -197,9 +288,13 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         continue;
                     };
 
-                    let voltage = self
-                        .inst_data
-                        .read_node_voltage(self.cx, node_deriv, inst, prev_solve, llbuilder);
+                    let voltage = self.inst_data.read_node_voltage(
+                        self.cx,
+                        node_deriv,
+                        inst.into(),
+                        prev_solve,
+                        llbuilder,
+                    );
                     let val = LLVMBuildFMul(llbuilder, ddx, voltage, UNNAMED);
                     LLVMSetFastMath(val);
                     res = match res {
@@ -15,16 +15,18 @@ use crate::inst_data::{
     OsdiInstanceParam, COLLAPSED, JACOBIAN_PTR_REACT, JACOBIAN_PTR_RESIST, NODE_MAPPING, STATE_IDX,
 };
 use crate::load::JacobianLoadType;
-use crate::metadata::osdi_0_3::{
+use crate::metadata::osdi_0_4::{
     OsdiDescriptor, OsdiJacobianEntry, OsdiNode, OsdiNodePair, OsdiNoiseSource, OsdiParamOpvar,
-    OsdiTys, JACOBIAN_ENTRY_REACT, JACOBIAN_ENTRY_REACT_CONST, JACOBIAN_ENTRY_RESIST,
-    JACOBIAN_ENTRY_RESIST_CONST, PARA_KIND_INST, PARA_KIND_MODEL, PARA_KIND_OPVAR, PARA_TY_INT,
-    PARA_TY_REAL, PARA_TY_STR,
+    OsdiSecondDerivative, OsdiTys, JACOBIAN_ENTRY_REACT, JACOBIAN_ENTRY_REACT_CONST,
+    JACOBIAN_ENTRY_RESIST, JACOBIAN_ENTRY_RESIST_CONST, PARA_KIND_INST, PARA_KIND_MODEL,
+    PARA_KIND_OPVAR, PARA_TY_INT, PARA_TY_REAL, PARA_TY_STR,
 };
 use crate::ty_len;
 
 #[allow(unused_parens, dead_code)]
 pub mod osdi_0_3;
+#[allow(unused_parens, dead_code)]
+pub mod osdi_0_4;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct OsdiLimFunction {
@@ -34,7 +36,7 @@ pub struct OsdiLimFunction {
 
 impl OsdiLimFunction {
     pub fn to_ll_val<'ll>(self, ctx: &CodegenCx<'_, 'll>, tys: &'ll OsdiTys) -> &'ll llvm::Value {
-        osdi_0_3::OsdiLimFunction {
+        osdi_0_4::OsdiLimFunction {
             name: ctx.literals.resolve(&self.name).to_owned(),
             num_args: self.num_args,
             func_ptr: ctx.const_null_ptr(),
@@ -181,6 +183,28 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         }
     }
 
+    /// The ids (positions in `param_opvar`) of the parameters selected for sensitivity analysis.
+    pub fn sensitivity_params(&self) -> Vec<u32> {
+        let OsdiCompilationUnit { inst_data, model_data, module, .. } = self;
+        module
+            .info
+            .sensitivities
+            .iter()
+            .map(|param| {
+                if let Some(pos) = inst_data.params.get_index_of(&OsdiInstanceParam::User(*param)) {
+                    return pos as u32;
+                }
+                let pos = model_data
+                    .params
+                    .keys()
+                    .filter(|param| !module.info.params[*param].is_instance)
+                    .position(|it| it == param)
+                    .unwrap();
+                (inst_data.params.len() + pos) as u32
+            })
+            .collect()
+    }
+
     pub fn jacobian_entries(&self, target_data: &TargetData) -> Vec<OsdiJacobianEntry> {
         let OsdiCompilationUnit { inst_data, module, .. } = self;
         let mut jacobian_ptr_react_offset =
@@ -220,6 +244,18 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             .collect()
     }
 
+    pub fn second_derivatives(&self) -> Vec<OsdiSecondDerivative> {
+        self.module
+            .dae_system
+            .second_derivatives
+            .iter()
+            .map(|entry| OsdiSecondDerivative {
+                residual: entry.row.into(),
+                nodes: OsdiNodePair { node_1: entry.col1.into(), node_2: entry.col2.into() },
+            })
+            .collect()
+    }
+
     pub fn collapsible(&self) -> Vec<OsdiNodePair> {
         self.module
             .node_collapse
@@ -308,6 +344,17 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 num_states: self.module.intern.lim_state.len() as u32,
                 load_limit_rhs_resist: self.load_lim_rhs(false),
                 load_limit_rhs_react: self.load_lim_rhs(true),
+                eval_soa: self.eval_soa_prototype(),
+                pack_soa: self.pack_soa_prototype(),
+                unpack_soa: self.unpack_soa_prototype(),
+                num_sensitivities: module.info.sensitivities.len() as u32,
+                sensitivity_params: self.sensitivity_params(),
+                load_sensitivity_resist: self.load_sensitivity(false),
+                load_sensitivity_react: self.load_sensitivity(true),
+                num_second_derivatives: module.dae_system.second_derivatives.len() as u32,
+                second_derivatives: self.second_derivatives(),
+                load_second_derivatives_resist: self.load_second_derivatives(false),
+                load_second_derivatives_react: self.load_second_derivatives(true),
             }
         }
     }
@@ -240,7 +240,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             let i = i as u32;
 
             let is_inst_given =
-                unsafe { inst_data.is_nth_param_given(cx, i, instance, builder.llbuilder) };
+                unsafe { inst_data.is_nth_param_given(cx, i, instance.into(), builder.llbuilder) };
             let is_given = unsafe {
                 let is_given_model =
                     model_data.is_nth_inst_param_given(cx, i, model, builder.llbuilder);
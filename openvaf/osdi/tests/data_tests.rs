@@ -1,6 +1,7 @@
+use std::fs;
 use std::path::Path;
 
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use hir::diagnostics::ConsoleSink;
 use hir::CompilationDB;
 use llvm::OptLevel;
@@ -8,7 +9,7 @@ use mini_harness::{harness, Result};
 use mir_llvm::LLVMBackend;
 use paths::AbsPathBuf;
 use sim_back::collect_modules;
-use stdx::{ignore_slow_tests, project_root};
+use stdx::{ignore_slow_tests, openvaf_test_data, project_root};
 use target::spec::Target;
 
 fn test_compile(root_file: &Path) {
@@ -29,6 +30,37 @@ fn integration_test(dir: &Path) -> Result {
     Ok(())
 }
 
+/// Modules are compiled in parallel, the generated code must not depend on the order
+/// in which they finish.
+fn deterministic_bundle() -> Result {
+    let root = project_root().join("integration_tests");
+    let units: Vec<_> = ["RESISTOR", "DIODE", "STRINGS", "DIODE_CMC", "EKV"]
+        .iter()
+        .map(|name| {
+            let root_file = root.join(name).join(format!("{}.va", name.to_lowercase()));
+            let root_file = AbsPathBuf::assert(root_file.canonicalize().unwrap());
+            let db = CompilationDB::new_fs(root_file, &[], &[], &[]).unwrap();
+            let modules = collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap();
+            (db, modules)
+        })
+        .collect();
+    let units: Vec<_> = units.iter().map(|(db, modules)| (db, &**modules)).collect();
+    let target = Target::host_target().unwrap();
+    let back = LLVMBackend::new(&[], &target, "native".to_owned(), &[]);
+    let dst: Utf8PathBuf = openvaf_test_data("osdi").join("deterministic.o").try_into().unwrap();
+
+    let compile = || -> Vec<Vec<u8>> {
+        let objects = osdi::compile_bundle(&units, &dst, &target, &back, true, OptLevel::None);
+        objects.iter().map(|object| fs::read(object).unwrap()).collect()
+    };
+    let expected = compile();
+    for _ in 0..4 {
+        assert!(compile() == expected, "the generated object files differ between runs");
+    }
+    Ok(())
+}
+
 harness! {
-    Test::from_dir("integration", &integration_test, &ignore_slow_tests, &project_root().join("integration_tests"))
+    Test::from_dir("integration", &integration_test, &ignore_slow_tests, &project_root().join("integration_tests")),
+    [Test::new("deterministic_bundle", &deterministic_bundle)]
 }
@@ -232,10 +232,27 @@ impl SourceMap {
         (decl.file, ranges)
     }
 
+    /// Whether the tokens in `ctx` were produced by expanding a macro (as opposed to
+    /// tokens from the root file or an included file)
+    pub fn is_macro_expansion(&self, ctx: SourceContext) -> bool {
+        // files are always declared by their entire text while the body
+        // of a macro is preceded by (at least) `define NAME
+        self.ctx_tree[ctx].decl.range.start() != TextSize::from(0)
+    }
+
     pub fn ctx_data(&self, ctx: SourceContext) -> &SourceContextData {
         &self.ctx_tree[ctx]
     }
 
+    /// All files that contributed to the preprocessed source:
+    /// The root file and every file that was (transitively) included.
+    pub fn files(&self) -> Vec<FileId> {
+        let mut files: Vec<_> = self.ctx_tree.iter().map(|ctx| ctx.decl.file).collect();
+        files.sort_unstable();
+        files.dedup();
+        files
+    }
+
     pub(crate) fn add_ctx(&mut self, decl: FileSpan, call_site: CtxSpan) -> SourceContext {
         self.ctx_tree.push_and_get_key(SourceContextData { decl, call_site: Some(call_site) })
     }
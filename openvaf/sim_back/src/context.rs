@@ -4,9 +4,9 @@ use hir_lower::{HirInterner, MirBuilder, PlaceKind};
 use lasso::Rodeo;
 use mir::{Block, ControlFlowGraph, DominatorTree, Function, Inst, Value};
 use mir_opt::{
-    aggressive_dead_code_elimination, dead_code_elimination, inst_combine, propagate_direct_taint,
-    propagate_taint, simplify_cfg, simplify_cfg_no_phi_merge,
-    sparse_conditional_constant_propagation, GVN,
+    aggressive_dead_code_elimination, dead_code_elimination, hoist_insts, inst_combine,
+    loop_invariant_code_motion, propagate_direct_taint, propagate_taint, simplify_cfg,
+    simplify_cfg_no_phi_merge, sparse_conditional_constant_propagation, GVN,
 };
 use stdx::packed_option::PackedOption;
 
@@ -77,6 +77,9 @@ impl<'a> Context<'a> {
             simplify_cfg_no_phi_merge(&mut self.func, &mut self.cfg);
         }
         self.compute_domtree(true, true, false);
+        if self.module.code_motion {
+            loop_invariant_code_motion(&mut self.func, &self.cfg, &self.dom_tree);
+        }
 
         let mut gvn = GVN::default();
         gvn.init(&self.func, &self.dom_tree, self.intern.params.len() as u32);
@@ -187,4 +190,25 @@ impl<'a> Context<'a> {
             &mut self.op_dependent_insts,
         )
     }
+
+    /// Moves instructions that only depend on operating point independent values
+    /// (parameters, temperature, ...) out of branches that depend on the operating point.
+    /// Otherwise these instructions are (implicitly) tainted by the branch condition
+    /// and would be recomputed in every `eval` call instead of `setup_instance`.
+    /// `op_dependent_insts` must be refreshed afterwards.
+    pub fn hoist_op_independent_insts(&mut self) {
+        self.refresh_op_dependent_insts();
+        let mut dom_frontiers = SparseBitMatrix::new_square(self.func.layout.num_blocks());
+        self.dom_tree.compute_dom_frontiers(&self.cfg, &mut dom_frontiers);
+        let mut data_dependent = BitSet::new_empty(self.func.dfg.num_insts());
+        propagate_direct_taint(
+            &self.func,
+            &dom_frontiers,
+            self.op_dependent_vals.iter().copied(),
+            &mut data_dependent,
+        );
+        let mut control_dependent = self.op_dependent_insts.clone();
+        control_dependent.subtract(&data_dependent);
+        hoist_insts(&mut self.func, &self.dom_tree, &control_dependent);
+    }
 }
@@ -1,3 +1,4 @@
+use hir::Parameter;
 use indexmap::IndexSet;
 use mir::{strip_optbarrier, Value, F_ZERO};
 use stdx::{impl_debug_display, impl_idx_from};
@@ -43,6 +44,14 @@ pub struct DaeSystem {
     pub small_signal_parameters: IndexSet<Value, ahash::RandomState>,
     /// noise
     pub noise_sources: Vec<NoiseSource>,
+    /// The derivatives of the residual by the parameters selected in
+    /// [`ModuleInfo::sensitivities`](crate::ModuleInfo::sensitivities)
+    /// S_ip = (ddx(I_i, p), ddx(Q_i, p))
+    pub sensitivities: Vec<SensitivityEntry>,
+    /// The second derivatives of the residuals selected in
+    /// [`ModuleInfo::second_derivatives`](crate::ModuleInfo::second_derivatives)
+    /// H_ijk = (ddx(ddx(I_i, x_j), x_k), ddx(ddx(Q_i, x_j), x_k)) with j <= k
+    pub second_derivatives: Vec<SecondDerivativeEntry>,
 }
 
 impl DaeSystem {
@@ -96,6 +105,18 @@ impl DaeSystem {
             matrix_entry.resist = sparsify(matrix_entry.resist);
             matrix_entry.react = sparsify(matrix_entry.react);
             matrix_entry.resist != F_ZERO || matrix_entry.react != F_ZERO
+        });
+
+        self.sensitivities.retain_mut(|entry| {
+            entry.resist = sparsify(entry.resist);
+            entry.react = sparsify(entry.react);
+            entry.resist != F_ZERO || entry.react != F_ZERO
+        });
+
+        self.second_derivatives.retain_mut(|entry| {
+            entry.resist = sparsify(entry.resist);
+            entry.react = sparsify(entry.react);
+            entry.resist != F_ZERO || entry.react != F_ZERO
         })
     }
 }
@@ -210,6 +231,24 @@ pub struct MatrixEntry {
     pub react: Value,
 }
 
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct SensitivityEntry {
+    pub row: SimUnknown,
+    pub param: Parameter,
+    pub resist: Value,
+    pub react: Value,
+}
+
+/// A second derivative of a residual, `col1 <= col2` because mixed derivatives commute.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct SecondDerivativeEntry {
+    pub row: SimUnknown,
+    pub col1: SimUnknown,
+    pub col2: SimUnknown,
+    pub resist: Value,
+    pub react: Value,
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct MatrixEntryId(u32);
 impl_idx_from!(MatrixEntryId(u32));
@@ -1,9 +1,10 @@
+use std::collections::BTreeMap;
 use std::mem::replace;
 use std::vec;
 
 use ahash::AHashMap;
 use bitset::BitSet;
-use hir::{BranchWrite, CompilationDB, Node, ParamSysFun};
+use hir::{BranchWrite, CompilationDB, Node, ParamSysFun, Parameter};
 use hir_lower::{HirInterner, ImplicitEquation, ParamKind};
 use indexmap::IndexSet;
 use mir::builder::InstBuilder;
@@ -12,11 +13,13 @@ use mir::{
     strip_optbarrier, Block, ControlFlowGraph, DominatorTree, Inst, KnownDerivatives, Unknown,
     Value, FALSE, F_ZERO, TRUE, F_ONE
 };
-use mir_autodiff::auto_diff;
+use mir_autodiff::{auto_diff_second_order, auto_diff_with_mode, second_derivative, DiffMode};
 use typed_index_collections::TiVec;
 
 use crate::context::Context;
-use crate::dae::{DaeSystem, MatrixEntry, Residual, SimUnknown};
+use crate::dae::{
+    DaeSystem, MatrixEntry, Residual, SecondDerivativeEntry, SensitivityEntry, SimUnknown,
+};
 use crate::noise::NoiseSource;
 use crate::topology::{BranchInfo, Contribution};
 use crate::util::{add, is_op_dependent, update_optbarrier};
@@ -62,11 +65,15 @@ pub(super) struct Builder<'a> {
     pub(super) dom_tree: &'a mut DominatorTree,
     pub(super) op_dependent_insts: &'a BitSet<Inst>,
     pub(super) output_values: &'a mut BitSet<Value>,
+    pub(super) sensitivities: &'a [Parameter],
+    pub(super) second_derivatives: &'a [Node],
+    pub(super) diff_mode: DiffMode,
 }
 
 impl<'a> Builder<'a> {
     pub(super) fn new(ctx: &'a mut Context) -> Self {
         ctx.compute_outputs(false);
+        let module = ctx.module;
         let mut builder = Self {
             system: DaeSystem::default(),
             cursor: FuncCursor::new(&mut ctx.func).at_exit(),
@@ -76,14 +83,17 @@ impl<'a> Builder<'a> {
             dom_tree: &mut ctx.dom_tree,
             op_dependent_insts: &ctx.op_dependent_insts,
             output_values: &mut ctx.output_values,
+            sensitivities: &module.sensitivities,
+            second_derivatives: &module.second_derivatives,
+            diff_mode: module.diff_mode,
         };
 
         // ensure ports are the first unknowns and always have an unknown
-        for port in ctx.module.module.ports(builder.db) {
+        for port in module.module.ports(builder.db) {
             builder.build_node(port)
         }
 
-        for node in ctx.module.module.internal_nodes(builder.db) {
+        for node in module.module.internal_nodes(builder.db) {
             builder.build_node(node)
         }
 
@@ -92,18 +102,41 @@ impl<'a> Builder<'a> {
 
     pub(super) fn finish(mut self) -> DaeSystem {
         let sim_unknown_reads = self.sim_unknown_reads();
-        let derivative_info = self.intern.unknowns(&self.cursor, true);
-        let extra_derivatives = self
+        let mut derivative_info = self.intern.unknowns(&self.cursor, true);
+        let sensitivity_unknowns = self.sensitivity_unknowns(&mut derivative_info);
+        let mut extra_derivatives = self
             .jacobian_derivatives(sim_unknown_reads.iter().map(|&(_, val)| val), &derivative_info);
+        extra_derivatives.extend(self.sensitivity_derivatives(&sensitivity_unknowns));
+        let jacobian_columns = self.jacobian_columns(&sim_unknown_reads, &derivative_info);
+        let extra_second_derivatives = self.second_order_derivatives(&jacobian_columns);
         // TODO(pref): incrementially update dom_tree (for switch branches) instead
         self.dom_tree.compute(self.cursor.func, self.cfg, true, false, true);
-        let derivatives =
-            auto_diff(&mut *self.cursor.func, self.dom_tree, &derivative_info, &extra_derivatives);
+        let derivatives = if extra_second_derivatives.is_empty() {
+            auto_diff_with_mode(
+                &mut *self.cursor.func,
+                self.dom_tree,
+                &derivative_info,
+                &extra_derivatives,
+                self.diff_mode,
+            )
+        } else {
+            // second order derivatives are always generated in forward mode
+            auto_diff_second_order(
+                &mut *self.cursor.func,
+                self.dom_tree,
+                &derivative_info,
+                &extra_derivatives,
+                &extra_second_derivatives,
+            )
+        };
         drop(extra_derivatives);
+        drop(extra_second_derivatives);
         // auto_diff may in an unlikely case add extra bb at the end, ensure we are building everything at the end
         self.cursor.goto_exit();
 
         self.build_jacobian(&sim_unknown_reads, &derivative_info, &derivatives);
+        self.build_sensitivities(&sensitivity_unknowns, &derivatives);
+        self.build_second_derivatives(&jacobian_columns, &derivatives);
         self.build_lim_rhs(&derivative_info, derivatives);
         self.ensure_optbarriers();
         self.system
@@ -331,6 +364,161 @@ impl<'a> Builder<'a> {
         res
     }
 
+    /// Registers the parameters selected for sensitivity analysis as
+    /// additional unknowns. The unknowns are appended to the end of
+    /// `derivative_info` so the indices used by `ddx` calls remain valid.
+    fn sensitivity_unknowns(
+        &self,
+        derivative_info: &mut KnownDerivatives,
+    ) -> Vec<(Parameter, Unknown)> {
+        self.sensitivities
+            .iter()
+            .filter_map(|&param| {
+                let val = *self.intern.params.raw.get(&ParamKind::Param(param))?;
+                if self.cursor.func.dfg.value_dead(val) {
+                    return None;
+                }
+                let (unknown, _) = derivative_info.unknowns.ensure(val);
+                Some((param, unknown))
+            })
+            .collect()
+    }
+
+    fn sensitivity_derivatives(&self, unknowns: &[(Parameter, Unknown)]) -> Vec<(Value, Unknown)> {
+        let mut res = Vec::new();
+        for residual in &self.system.residual {
+            for val in [residual.resist, residual.react] {
+                if self.cursor.func.dfg.value_def(val).as_const().is_none() {
+                    res.extend(unknowns.iter().map(|&(_, unknown)| (val, unknown)))
+                }
+            }
+        }
+        res
+    }
+
+    fn build_sensitivities(
+        &mut self,
+        unknowns: &[(Parameter, Unknown)],
+        derivatives: &AHashMap<(Value, Unknown), Value>,
+    ) {
+        for (row, residual) in self.system.residual.iter_enumerated() {
+            for &(param, unknown) in unknowns {
+                let get = |val| derivatives.get(&(val, unknown)).copied().unwrap_or(F_ZERO);
+                let resist = get(residual.resist);
+                let react = get(residual.react);
+                if resist != F_ZERO || react != F_ZERO {
+                    self.system.sensitivities.push(SensitivityEntry { row, param, resist, react })
+                }
+            }
+        }
+    }
+
+    /// Returns the columns of the jacobian together with the unknowns whose derivatives
+    /// contribute to them (with the sign of the contribution). This is the same mapping
+    /// `build_jacobian` uses to assemble the matrix (including limited values).
+    fn jacobian_columns(
+        &self,
+        sim_unknown_reads: &[(ParamKind, Value)],
+        derivative_info: &KnownDerivatives,
+    ) -> Vec<(SimUnknown, Unknown, bool)> {
+        let mut res = Vec::new();
+        let mut add_column = |sim_unknown: SimUnknownKind, val, negate| {
+            let col = if let Some(col) = self.system.unknowns.index(&sim_unknown) {
+                col
+            } else {
+                return;
+            };
+            if let Some(lim_vals) = self.intern.lim_state.raw.get(&val) {
+                for (lim_val, negate_lim) in lim_vals {
+                    if let Some(unknown) = derivative_info.unknowns.index(lim_val) {
+                        res.push((col, unknown, negate != *negate_lim));
+                    }
+                }
+            }
+            if let Some(unknown) = derivative_info.unknowns.index(&val) {
+                res.push((col, unknown, negate));
+            }
+        };
+        for &(kind, val) in sim_unknown_reads {
+            let unknown = match kind {
+                ParamKind::Voltage { hi, lo } => {
+                    if let Some(lo) = lo {
+                        add_column(SimUnknownKind::KirchoffLaw(lo), val, true);
+                    }
+                    SimUnknownKind::KirchoffLaw(hi)
+                }
+                ParamKind::ImplicitUnknown(equation) => SimUnknownKind::Implicit(equation),
+                ParamKind::Current(kind) => SimUnknownKind::Current(kind),
+                _ => continue,
+            };
+            add_column(unknown, val, false);
+        }
+        res
+    }
+
+    /// The rows of the residuals selected in `ModuleInfo::second_derivatives`.
+    fn second_derivative_rows(&self) -> impl Iterator<Item = SimUnknown> + '_ {
+        self.second_derivatives
+            .iter()
+            .filter_map(|&node| self.system.unknowns.index(&SimUnknownKind::KirchoffLaw(node)))
+    }
+
+    fn second_order_derivatives(
+        &self,
+        columns: &[(SimUnknown, Unknown, bool)],
+    ) -> Vec<(Value, Unknown, Unknown)> {
+        let unknowns: IndexSet<_, ahash::RandomState> =
+            columns.iter().map(|&(_, unknown, _)| unknown).collect();
+        let mut res = Vec::new();
+        for row in self.second_derivative_rows() {
+            let residual = &self.system.residual[row];
+            for val in [residual.resist, residual.react] {
+                if self.cursor.func.dfg.value_def(val).as_const().is_some() {
+                    continue;
+                }
+                for (i, &unknown1) in unknowns.iter().enumerate() {
+                    res.extend(unknowns.iter().skip(i).map(|&unknown2| (val, unknown1, unknown2)))
+                }
+            }
+        }
+        res
+    }
+
+    fn build_second_derivatives(
+        &mut self,
+        columns: &[(SimUnknown, Unknown, bool)],
+        derivatives: &AHashMap<(Value, Unknown), Value>,
+    ) {
+        let rows: Vec<_> = self.second_derivative_rows().collect();
+        for row in rows {
+            let residual = self.system.residual[row];
+            let mut entries = BTreeMap::new();
+            for &(col1, unknown1, negate1) in columns {
+                for &(col2, unknown2, negate2) in columns {
+                    if col1 > col2 {
+                        continue;
+                    }
+                    let (resist, react) = entries.entry((col1, col2)).or_insert((F_ZERO, F_ZERO));
+                    for (dst, val) in [(resist, residual.resist), (react, residual.react)] {
+                        let ddx = second_derivative(derivatives, val, unknown1, unknown2);
+                        add(&mut self.cursor, dst, ddx, negate1 != negate2);
+                    }
+                }
+            }
+            for ((col1, col2), (resist, react)) in entries {
+                if resist != F_ZERO || react != F_ZERO {
+                    self.system.second_derivatives.push(SecondDerivativeEntry {
+                        row,
+                        col1,
+                        col2,
+                        resist,
+                        react,
+                    })
+                }
+            }
+        }
+    }
+
     pub(super) fn build_branch(&mut self, branch: BranchWrite, contributions: &BranchInfo) {
         let current = branch.into();
         // contributions.is_voltage_src is a Value that is used for choosing the branch type (voltage, current)
@@ -676,5 +864,19 @@ impl<'a> Builder<'a> {
             entry.resist = ensure_optbarrier(entry.resist, is_kirchoff);
             entry.react = ensure_optbarrier(entry.react, is_kirchoff);
         }
+
+        for entry in &mut self.system.sensitivities {
+            let is_kirchoff =
+                matches!(self.system.unknowns[entry.row], SimUnknownKind::KirchoffLaw(_));
+            entry.resist = ensure_optbarrier(entry.resist, is_kirchoff);
+            entry.react = ensure_optbarrier(entry.react, is_kirchoff);
+        }
+
+        for entry in &mut self.system.second_derivatives {
+            let is_kirchoff =
+                matches!(self.system.unknowns[entry.row], SimUnknownKind::KirchoffLaw(_));
+            entry.resist = ensure_optbarrier(entry.resist, is_kirchoff);
+            entry.react = ensure_optbarrier(entry.react, is_kirchoff);
+        }
     }
 }
@@ -1,11 +1,18 @@
-use std::fs;
+use std::ffi::c_void;
+use std::{fs, ptr};
 
 use expect_test::expect_file;
 use hir::diagnostics::ConsoleSink;
-use hir::CompilationDB;
+use hir::{CompilationDB, Node};
+use hir_lower::ParamKind;
 use indoc::indoc;
 use lasso::Rodeo;
+use mir::{FuncRef, Param, Value, F_ZERO};
+use mir_autodiff::DiffMode;
+use mir_interpret::{Data, Func, Interpreter, InterpreterState};
+use stdx::iter::zip;
 use stdx::{integration_test_dir, openvaf_test_data};
+use typed_index_collections::TiVec;
 
 use crate::context::{Context, OptimizationStage};
 use crate::dae::DaeSystem;
@@ -146,3 +153,204 @@ fn dyn_switch_branch() {
     "#};
     run_test(src);
 }
+
+#[test]
+fn sensitivity() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module sensitivity(inout a, inout c);
+            electrical a, c;
+            parameter real r=1.0, c0=1.0, unused=1.0;
+            aliasparam cap = c0;
+            analog begin
+                I(a, c) <+ V(a, c) / r;
+                I(a, c) <+ ddt(c0 * V(a, c));
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let params = ["r", "cap", "unused"].map(|name| module.find_param(name).unwrap());
+    module.sensitivities.extend(params);
+    let mut literals = Rodeo::new();
+    let mut context = Context::new(&db, &mut literals, &module);
+    context.compute_outputs(true);
+    context.compute_cfg();
+    context.optimize(OptimizationStage::Initial);
+    let topology = topology::Topology::new(&mut context);
+    let mut dae_system = DaeSystem::new(&mut context, topology);
+    context.compute_cfg();
+    context.optimize(OptimizationStage::Final);
+    dae_system.sparsify(&mut context);
+    assert!(context.func.validate());
+
+    let [r, c0, _] = params;
+    let entries: Vec<_> = dae_system
+        .sensitivities
+        .iter()
+        .map(|entry| (entry.row, entry.param, entry.resist != F_ZERO, entry.react != F_ZERO))
+        .collect();
+    let (node0, node1) = (0u32.into(), 1u32.into());
+    assert_eq!(
+        entries,
+        [
+            (node0, r, true, false),
+            (node0, c0, false, true),
+            (node1, r, true, false),
+            (node1, c0, false, true),
+        ]
+    );
+}
+
+#[test]
+fn second_derivatives() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module second_derivatives(inout a, inout c);
+            electrical a, c;
+            parameter real r=1.0, k=1.0, c0=1.0;
+            analog begin
+                I(a, c) <+ V(a, c) / r + k * V(a) * V(a) * V(a);
+                I(a, c) <+ ddt(c0 * V(a) * V(c));
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let node = module.find_node(&db, "a").unwrap();
+    module.second_derivatives.push(node);
+    let mut literals = Rodeo::new();
+    let mut context = Context::new(&db, &mut literals, &module);
+    context.compute_outputs(true);
+    context.compute_cfg();
+    context.optimize(OptimizationStage::Initial);
+    let topology = topology::Topology::new(&mut context);
+    let mut dae_system = DaeSystem::new(&mut context, topology);
+    context.compute_cfg();
+    context.optimize(OptimizationStage::Final);
+    dae_system.sparsify(&mut context);
+    assert!(context.func.validate());
+
+    // only the residual of node a is differentiated, the linear resistor does not contribute
+    let entries: Vec<_> = dae_system
+        .second_derivatives
+        .iter()
+        .map(|entry| {
+            (entry.row, entry.col1, entry.col2, entry.resist != F_ZERO, entry.react != F_ZERO)
+        })
+        .collect();
+    let (node0, node1) = (0u32.into(), 1u32.into());
+    assert_eq!(entries, [(node0, node0, node0, true, false), (node0, node0, node1, false, true)]);
+}
+
+/// Callback used to interpret the DAE system: returns the last argument (the default of
+/// `$simparam`, the argument of `ddt`). Forward and reverse mode call the same callbacks so the
+/// exact semantics are irrelevant.
+fn return_last_arg(state: &mut InterpreterState, args: &[Value], rets: &[Value], _: *mut c_void) {
+    let val = args.last().map_or(0f64, |&arg| state.read(arg));
+    for &ret in rets {
+        state.write(ret, val)
+    }
+}
+
+#[test]
+fn reverse_mode() {
+    let src = fs::read_to_string(integration_test_dir("DIODE").join("diode.va")).unwrap();
+    let db = CompilationDB::new_virtual(&src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut jacobian = |diff_mode| {
+        module.diff_mode = diff_mode;
+        let mut literals = Rodeo::new();
+        let mut context = Context::new(&db, &mut literals, &module);
+        context.compute_outputs(true);
+        context.compute_cfg();
+        context.optimize(OptimizationStage::Initial);
+        let topology = topology::Topology::new(&mut context);
+        let mut dae_system = DaeSystem::new(&mut context, topology);
+        context.compute_cfg();
+        context.optimize(OptimizationStage::Final);
+        dae_system.sparsify(&mut context);
+        assert!(context.func.validate());
+
+        // operating point with self heating and a series resistance
+        let potential = |node: Node| match &*node.name(&db) {
+            "A" => 0.65,
+            "CI" => 0.2,
+            "dT" => 3.0,
+            _ => 0.0,
+        };
+        let args: TiVec<Param, Data> = context
+            .intern
+            .params
+            .raw
+            .keys()
+            .map(|kind| match *kind {
+                ParamKind::Param(param) => match &*param.name(&db) {
+                    "rs" => 2.0,
+                    "zetars" => 0.5,
+                    "n" => 1.2,
+                    "cj0" => 1e-12,
+                    "vj" => 0.9,
+                    "m" => 0.4,
+                    "rth" => 100.0,
+                    "zetarth" => 0.3,
+                    "zetais" => 3.0,
+                    "ea" => 1.11,
+                    "tnom" => 300.0,
+                    "minr" => 1e-3,
+                    _ => 1e-14,
+                }
+                .into(),
+                ParamKind::Voltage { hi, lo } => (potential(hi) - lo.map_or(0.0, potential)).into(),
+                ParamKind::Temperature => 310.0.into(),
+                ParamKind::ParamGiven { .. }
+                | ParamKind::PortConnected { .. }
+                | ParamKind::EnableIntegration => true.into(),
+                ParamKind::EnableLim => false.into(),
+                ParamKind::ParamSysFun(_) => 1.0.into(),
+                _ => 0.0.into(),
+            })
+            .collect();
+        let calls: TiVec<FuncRef, (Func, *mut c_void)> = context
+            .intern
+            .callbacks
+            .indices()
+            .map(|_| (return_last_arg as Func, ptr::null_mut()))
+            .collect();
+        let mut interpreter = Interpreter::new(&context.func, &calls, &args);
+        interpreter.run();
+        let read = |val| interpreter.state.read::<f64>(val);
+        dae_system
+            .jacobian
+            .iter()
+            .map(|entry| {
+                (
+                    entry.row,
+                    entry.col,
+                    (entry.resist != F_ZERO, entry.react != F_ZERO),
+                    (read(entry.resist), read(entry.react)),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // both modes must produce the same sparsity pattern and the same values
+    let forward = jacobian(DiffMode::Forward);
+    let reverse = jacobian(DiffMode::Reverse);
+    assert_eq!(forward.len(), reverse.len());
+    for (forward, reverse) in zip(forward, reverse) {
+        let (row, col, sparsity, (resist, react)) = forward;
+        let (reverse_row, reverse_col, reverse_sparsity, (reverse_resist, reverse_react)) = reverse;
+        assert_eq!((row, col, sparsity), (reverse_row, reverse_col, reverse_sparsity));
+        for (val, reverse_val) in [(resist, reverse_resist), (react, reverse_react)] {
+            assert!(val.is_finite(), "J[{row:?}, {col:?}] = {val}");
+            assert!(
+                (val - reverse_val).abs() <= 1e-9 * val.abs().max(reverse_val.abs()),
+                "J[{row:?}, {col:?}]: forward mode {val} does not match reverse mode {reverse_val}"
+            );
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Tools for debugging the compiler itself: timing the individual compilation stages
+//! (`--time-passes`) and writing the MIR to files after each stage (`--dump-mir`).
+//! None of these options change the generated code.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lasso::Rodeo;
+use mir::Function;
+
+#[cfg(test)]
+mod tests;
+
+/// The stages of [`CompiledModule::new`](crate::CompiledModule::new) after which the MIR
+/// can be dumped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// MIR directly after lowering from HIR.
+    Lower,
+    /// After the initial optimization (before any derivatives are generated).
+    Optimize,
+    /// After the topology (branches, small signal network, noise) was extracted.
+    Topology,
+    /// After the DAE system (residual and jacobian) was built.
+    Dae,
+    /// After the second optimization and sparsification of the DAE system.
+    Sparsify,
+    /// After operating point independent instructions were hoisted.
+    Hoist,
+    /// After the operating point independent instructions were moved to the
+    /// initialization function.
+    Init,
+    /// After the parameter initialization was added to the initialization function and
+    /// the model parameter setup function was built.
+    ModelParamSetup,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 8] = [
+        Stage::Lower,
+        Stage::Optimize,
+        Stage::Topology,
+        Stage::Dae,
+        Stage::Sparsify,
+        Stage::Hoist,
+        Stage::Init,
+        Stage::ModelParamSetup,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Lower => "lower",
+            Stage::Optimize => "optimize",
+            Stage::Topology => "topology",
+            Stage::Dae => "dae",
+            Stage::Sparsify => "sparsify",
+            Stage::Hoist => "hoist",
+            Stage::Init => "init",
+            Stage::ModelParamSetup => "model_param_setup",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Stage> {
+        Stage::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+}
+
+/// Options that help debugging the compiler (see the module documentation).
+#[derive(Debug, Clone, Default)]
+pub struct DebugOpts {
+    /// Records how long each compilation stage takes. Shared by all modules.
+    pub time_passes: Option<Arc<PassTimings>>,
+    /// Writes the MIR to files after the selected stages.
+    pub dump_mir: Option<Arc<MirDump>>,
+}
+
+impl DebugOpts {
+    /// Reads the options from the `OPENVAF_TIME_PASSES` and `OPENVAF_DUMP_MIR` environment
+    /// variables. `OPENVAF_DUMP_MIR` accepts the same stages as [`MirDump::new`] and the
+    /// files are written to `OPENVAF_DUMP_MIR_DIR` (by default `mir_dump`).
+    pub fn from_env() -> Result<DebugOpts, String> {
+        let time_passes = std::env::var_os("OPENVAF_TIME_PASSES")
+            .filter(|val| !val.is_empty() && val != "0")
+            .map(|_| Arc::default());
+        let dump_mir = match std::env::var("OPENVAF_DUMP_MIR") {
+            Ok(stages) if !stages.is_empty() => {
+                let dir = std::env::var_os("OPENVAF_DUMP_MIR_DIR")
+                    .map_or_else(|| PathBuf::from("mir_dump"), PathBuf::from);
+                Some(Arc::new(MirDump::new(dir, &stages)?))
+            }
+            _ => None,
+        };
+        Ok(DebugOpts { time_passes, dump_mir })
+    }
+
+    /// Whether any of the options is enabled. Cached results are ignored in that case
+    /// because the output would be missing.
+    pub fn enabled(&self) -> bool {
+        self.time_passes.is_some() || self.dump_mir.is_some()
+    }
+
+    /// Runs `f` and records its runtime as `pass` if `--time-passes` is enabled.
+    pub fn time<T>(&self, pass: &'static str, f: impl FnOnce() -> T) -> T {
+        match &self.time_passes {
+            Some(timings) => timings.time(pass, f),
+            None => f(),
+        }
+    }
+
+    pub(crate) fn dump_mir(
+        &self,
+        module: &str,
+        stage: Stage,
+        funcs: &[&Function],
+        literals: &Rodeo,
+    ) {
+        if let Some(dump) = &self.dump_mir {
+            if dump.stages.contains(&stage) {
+                dump.write(module, stage, funcs, literals)
+            }
+        }
+    }
+}
+
+/// The accumulated runtime of each pass. Modules are compiled in parallel so the total
+/// time of a pass can exceed the wall time of the compilation.
+#[derive(Debug, Default)]
+pub struct PassTimings {
+    passes: Mutex<Vec<(&'static str, Duration, u32)>>,
+}
+
+impl PassTimings {
+    pub fn time<T>(&self, pass: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let res = f();
+        self.record(pass, start.elapsed());
+        res
+    }
+
+    pub fn record(&self, pass: &'static str, duration: Duration) {
+        let mut passes = self.passes.lock().unwrap();
+        match passes.iter_mut().find(|(name, _, _)| *name == pass) {
+            Some((_, total, count)) => {
+                *total += duration;
+                *count += 1;
+            }
+            None => passes.push((pass, duration, 1)),
+        }
+    }
+
+    /// Formats the recorded timings (in the order the passes first ran) and resets them
+    /// so that the next compilation (in watch mode) starts from zero.
+    pub fn take_report(&self) -> String {
+        let passes = std::mem::take(&mut *self.passes.lock().unwrap());
+        let mut res = String::new();
+        for (pass, total, count) in passes {
+            write!(res, "time: {:>9.3}ms\t{pass}", total.as_secs_f64() * 1000.0).unwrap();
+            if count > 1 {
+                write!(res, " ({count} runs)").unwrap();
+            }
+            res.push('\n');
+        }
+        res
+    }
+}
+
+/// Writes the MIR after the selected stages to `{dir}/{module}.{index}.{stage}.mir`. The
+/// index is the position of the stage in the pipeline so that the files sort in order.
+/// All functions that exist at that stage are written to the same file and can be read
+/// with `mir_reader` (and the `mir-opt` driver).
+#[derive(Debug, Clone)]
+pub struct MirDump {
+    pub dir: PathBuf,
+    pub stages: Vec<Stage>,
+}
+
+impl MirDump {
+    /// `stages` is a comma separated list of stage names or `all`.
+    pub fn new(dir: PathBuf, stages: &str) -> Result<MirDump, String> {
+        let mut res = Vec::new();
+        for name in stages.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            if name == "all" {
+                res.extend(Stage::ALL);
+                continue;
+            }
+            match Stage::from_name(name) {
+                Some(stage) => res.push(stage),
+                None => {
+                    let known: Vec<_> = Stage::ALL.iter().map(|stage| stage.name()).collect();
+                    return Err(format!(
+                        "unknown MIR stage `{name}`\nhelp: expected `all` or one of {}",
+                        known.join(", ")
+                    ));
+                }
+            }
+        }
+        Ok(MirDump { dir, stages: res })
+    }
+
+    fn write(&self, module: &str, stage: Stage, funcs: &[&Function], literals: &Rodeo) {
+        let index = Stage::ALL.iter().position(|it| *it == stage).unwrap();
+        let path = self.dir.join(format!("{module}.{index:02}.{}.mir", stage.name()));
+        let mut contents = String::new();
+        for (i, func) in funcs.iter().enumerate() {
+            if i != 0 {
+                contents.push('\n');
+            }
+            write!(contents, "{}", func.print(literals)).unwrap();
+        }
+        if let Err(err) = fs::create_dir_all(&self.dir).and_then(|_| fs::write(&path, contents)) {
+            eprintln!("warning: failed to write {}: {err}", path.display());
+        }
+    }
+}
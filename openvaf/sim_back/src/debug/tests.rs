@@ -0,0 +1,57 @@
+use std::fs;
+use std::sync::Arc;
+
+use hir::diagnostics::ConsoleSink;
+use hir::CompilationDB;
+use indoc::indoc;
+use lasso::Rodeo;
+
+use crate::debug::{MirDump, PassTimings, Stage};
+use crate::CompiledModule;
+
+#[test]
+fn parse_stages() {
+    let dump = MirDump::new("dump".into(), "lower, init").unwrap();
+    assert_eq!(dump.stages, [Stage::Lower, Stage::Init]);
+    let dump = MirDump::new("dump".into(), "all").unwrap();
+    assert_eq!(dump.stages, Stage::ALL);
+    assert!(MirDump::new("dump".into(), "lower,codegen").is_err());
+}
+
+#[test]
+fn dump_and_time() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module diode(inout a, inout c);
+            electrical a, c;
+            parameter real is = 1e-14;
+            analog I(a, c) <+ is * (limexp(V(a, c) / $vt) - 1);
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+
+    let dir = std::env::temp_dir().join(format!("openvaf_mir_dump_{}", std::process::id()));
+    let timings = Arc::new(PassTimings::default());
+    module.debug.time_passes = Some(timings.clone());
+    module.debug.dump_mir = Some(Arc::new(MirDump::new(dir.clone(), "all").unwrap()));
+    module.code_motion = true;
+    let mut literals = Rodeo::new();
+    CompiledModule::new(&db, &module, &mut literals);
+
+    for (i, stage) in Stage::ALL.into_iter().enumerate() {
+        let file = dir.join(format!("diode.{i:02}.{}.mir", stage.name()));
+        let mir = fs::read_to_string(&file).unwrap();
+        let (funcs, _) = mir_reader::parse_functions(&mir).unwrap();
+        let expected = if matches!(stage, Stage::Init | Stage::ModelParamSetup) { 2 } else { 1 };
+        assert_eq!(funcs.len(), expected, "{}", file.display());
+    }
+    fs::remove_dir_all(dir).unwrap();
+
+    let report = timings.take_report();
+    for pass in ["lower", "optimize", "topology", "dae", "sparsify", "hoist", "init"] {
+        assert!(report.contains(&format!("\t{pass}\n")), "{report}");
+    }
+    assert_eq!(timings.take_report(), "");
+}
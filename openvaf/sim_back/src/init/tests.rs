@@ -93,3 +93,51 @@ fn analysis() {
     "#};
     run_test(src);
 }
+
+#[test]
+fn code_motion() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module code_motion(inout a, inout c);
+            electrical a, c;
+            parameter real g0=1e-3;
+            parameter real tc=1e-3;
+            analog begin
+                if (V(a, c) > 0)
+                    I(a, c) <+ g0 * exp(tc * ($temperature - 300)) * V(a, c);
+                else
+                    I(a, c) <+ g0 * V(a, c);
+            end
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut module =
+        crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut cached_vals = |code_motion| {
+        module.code_motion = code_motion;
+        let mut literals = Rodeo::new();
+        let mut cx = Context::new(&db, &mut literals, &module);
+        cx.compute_outputs(true);
+        cx.compute_cfg();
+        cx.optimize(OptimizationStage::Initial);
+        let topology = Topology::new(&mut cx);
+        let mut dae_system = DaeSystem::new(&mut cx, topology);
+        cx.compute_cfg();
+        let gvn = cx.optimize(OptimizationStage::PostDerivative);
+        dae_system.sparsify(&mut cx);
+        if code_motion {
+            cx.hoist_op_independent_insts();
+        }
+        cx.refresh_op_dependent_insts();
+        let init = Initialization::new(&mut cx, gvn);
+        assert!(cx.func.validate());
+        assert!(init.func.validate());
+        init.cached_vals.len()
+    };
+
+    // the temperature dependent conductance is only computed in an operating point
+    // dependent branch and is therefore only cached if it is hoisted out of the branch
+    let without = cached_vals(false);
+    let with = cached_vals(true);
+    assert!(with > without, "{with} <= {without}");
+}
@@ -1,20 +1,23 @@
 use hir::{BranchWrite, CompilationDB, Node};
 use hir_lower::{CurrentKind, HirInterner, ImplicitEquation};
-use lasso::Rodeo;
+use lasso::{Key, Rodeo, Spur};
 use mir::Function;
 use mir_opt::{simplify_cfg, sparse_conditional_constant_propagation};
 use stdx::impl_debug_display;
 
+pub use mir_autodiff::DiffMode;
 pub use module_info::{collect_modules, ModuleInfo};
 
 use crate::context::{Context, OptimizationStage};
 use crate::dae::DaeSystem;
+use crate::debug::Stage;
 use crate::init::Initialization;
 use crate::node_collapse::NodeCollapse;
 use crate::topology::Topology;
 
 mod context;
 pub mod dae;
+pub mod debug;
 pub mod init;
 mod module_info;
 pub mod node_collapse;
@@ -53,55 +56,87 @@ pub struct CompiledModule<'a> {
 }
 
 impl<'a> CompiledModule<'a> {
+    /// Lowers and optimizes a single module. To compile multiple modules in parallel each
+    /// module can use its own `literals` which are merged afterwards with
+    /// [`CompiledModule::remap_literals`].
     pub fn new(
         db: &CompilationDB,
         module: &'a ModuleInfo,
         literals: &mut Rodeo,
     ) -> CompiledModule<'a> {
-        let mut cx = Context::new(db, literals, module);
-        cx.compute_outputs(true);
-        cx.compute_cfg();
-        cx.optimize(OptimizationStage::Initial);
+        let debug = &module.debug;
+        let name = module.module.name(db);
+        let dump = |stage, funcs: &[&Function], literals: &Rodeo| {
+            debug.dump_mir(&name, stage, funcs, literals)
+        };
+
+        let mut cx = debug.time("lower", || {
+            let mut cx = Context::new(db, literals, module);
+            cx.compute_outputs(true);
+            cx.compute_cfg();
+            cx
+        });
+        dump(Stage::Lower, &[&cx.func], literals);
+        debug.time("optimize", || cx.optimize(OptimizationStage::Initial));
         debug_assert!(cx.func.validate());
+        dump(Stage::Optimize, &[&cx.func], literals);
 
-        let topology = Topology::new(&mut cx);
+        let topology = debug.time("topology", || Topology::new(&mut cx));
         debug_assert!(cx.func.validate());
-        let mut dae_system = DaeSystem::new(&mut cx, topology);
+        dump(Stage::Topology, &[&cx.func], literals);
+        let mut dae_system = debug.time("dae", || DaeSystem::new(&mut cx, topology));
         debug_assert!(cx.func.validate());
-        cx.compute_cfg();
-        let gvn = cx.optimize(OptimizationStage::PostDerivative);
-        dae_system.sparsify(&mut cx);
+        dump(Stage::Dae, &[&cx.func], literals);
+        let gvn = debug.time("sparsify", || {
+            cx.compute_cfg();
+            let gvn = cx.optimize(OptimizationStage::PostDerivative);
+            dae_system.sparsify(&mut cx);
+            gvn
+        });
         debug_assert!(cx.func.validate());
+        dump(Stage::Sparsify, &[&cx.func], literals);
 
-        cx.refresh_op_dependent_insts();
-        let mut init = Initialization::new(&mut cx, gvn);
-        let node_collapse = NodeCollapse::new(&init, &dae_system, &cx);
+        if module.code_motion {
+            debug.time("hoist", || cx.hoist_op_independent_insts());
+            debug_assert!(cx.func.validate());
+            dump(Stage::Hoist, &[&cx.func], literals);
+        }
+        let mut init = debug.time("init", || {
+            cx.refresh_op_dependent_insts();
+            Initialization::new(&mut cx, gvn)
+        });
+        let node_collapse =
+            debug.time("node_collapse", || NodeCollapse::new(&init, &dae_system, &cx));
         debug_assert!(cx.func.validate());
         debug_assert!(init.func.validate());
+        dump(Stage::Init, &[&cx.func, &init.func], literals);
 
         // TODO: refactor param intilization to use tables
-        let inst_params: Vec<_> = module
-            .params
-            .iter()
-            .filter_map(|(param, info)| info.is_instance.then_some(*param))
-            .collect();
-        init.intern.insert_param_init(db, &mut init.func, literals, false, true, &inst_params);
-
-        let mut model_param_setup = Function::default();
-        let model_params: Vec<_> = module.params.keys().copied().collect();
-        let mut model_param_intern = HirInterner::default();
-        model_param_intern.insert_param_init(
-            db,
-            &mut model_param_setup,
-            literals,
-            false,
-            true,
-            &model_params,
-        );
-        cx.cfg.compute(&model_param_setup);
-        simplify_cfg(&mut model_param_setup, &mut cx.cfg);
-        sparse_conditional_constant_propagation(&mut model_param_setup, &cx.cfg);
-        simplify_cfg(&mut model_param_setup, &mut cx.cfg);
+        let (model_param_setup, model_param_intern) = debug.time("model_param_setup", || {
+            let inst_params: Vec<_> = module
+                .params
+                .iter()
+                .filter_map(|(param, info)| info.is_instance.then_some(*param))
+                .collect();
+            let mut model_param_setup = Function::default();
+            let model_params: Vec<_> = module.params.keys().copied().collect();
+            let mut model_param_intern = HirInterner::default();
+            init.intern.insert_param_init(db, &mut init.func, literals, false, true, &inst_params);
+            model_param_intern.insert_param_init(
+                db,
+                &mut model_param_setup,
+                literals,
+                false,
+                true,
+                &model_params,
+            );
+            cx.cfg.compute(&model_param_setup);
+            simplify_cfg(&mut model_param_setup, &mut cx.cfg);
+            sparse_conditional_constant_propagation(&mut model_param_setup, &cx.cfg);
+            simplify_cfg(&mut model_param_setup, &mut cx.cfg);
+            (model_param_setup, model_param_intern)
+        });
+        dump(Stage::ModelParamSetup, &[&init.func, &model_param_setup], literals);
 
         CompiledModule {
             eval: cx.func,
@@ -114,4 +149,19 @@ impl<'a> CompiledModule<'a> {
             node_collapse,
         }
     }
+
+    /// Moves all strings this module was compiled with from `local` to `literals` and
+    /// updates every reference to them. Strings are interned in the order they were
+    /// created in `local` so the result does not depend on which other modules were
+    /// merged into `literals` concurrently.
+    pub fn remap_literals(&mut self, local: &Rodeo, literals: &mut Rodeo) {
+        let map: Vec<Spur> = local.strings().map(|str| literals.get_or_intern(str)).collect();
+        let f = |spur: Spur| map[spur.into_usize()];
+        self.intern.remap_literals(&mut self.eval, f);
+        self.init.intern.remap_literals(&mut self.init.func, f);
+        self.model_param_intern.remap_literals(&mut self.model_param_setup, f);
+        for source in &mut self.dae_system.noise_sources {
+            source.name = f(source.name);
+        }
+    }
 }
@@ -1,15 +1,18 @@
 use ahash::AHashSet;
 use hir::diagnostics::{BaseDB, ConsoleSink, Diagnostic, FileId, Label, LabelStyle, Report};
 use hir::{
-    CompilationDB, CompilationUnit, DiagnosticSink, Module, ParamSysFun, Parameter,
+    CompilationDB, CompilationUnit, DiagnosticSink, Module, Node, ParamSysFun, Parameter,
     ResolvedAliasParameter, ScopeDef, Variable,
 };
 use indexmap::IndexMap;
+use mir_autodiff::DiffMode;
 use smol_str::SmolStr;
 use syntax::ast::{self, Expr};
 use syntax::sourcemap::FileSpan;
 use syntax::AstNode;
 
+use crate::debug::DebugOpts;
+
 #[cfg(test)]
 mod tests;
 
@@ -45,6 +48,21 @@ pub struct ModuleInfo {
     pub params: IndexMap<Parameter, ParamInfo, ahash::RandomState>,
     pub sys_fun_alias: IndexMap<ParamSysFun, Vec<SmolStr>, ahash::RandomState>,
     pub op_vars: IndexMap<Variable, OpVar, ahash::RandomState>,
+    /// Parameters that are treated as additional differentiation variables.
+    /// The derivatives of the residual by these parameters are available
+    /// as [`DaeSystem::sensitivities`](crate::dae::DaeSystem::sensitivities).
+    pub sensitivities: Vec<Parameter>,
+    /// Nodes whose residuals are differentiated twice by the unknowns of the system.
+    /// The second derivatives are available as
+    /// [`DaeSystem::second_derivatives`](crate::dae::DaeSystem::second_derivatives).
+    pub second_derivatives: Vec<Node>,
+    /// How the derivatives of the residual (the jacobian and sensitivities) are generated.
+    pub diff_mode: DiffMode,
+    /// Whether loop invariant code motion and hoisting of operating point independent
+    /// instructions out of operating point dependent branches is performed on the MIR.
+    pub code_motion: bool,
+    /// Options for debugging the compiler (timing and MIR dumps).
+    pub debug: DebugOpts,
 }
 
 impl ModuleInfo {
@@ -193,7 +211,30 @@ impl ModuleInfo {
             }
         }
 
-        ModuleInfo { module, params, op_vars, sys_fun_alias }
+        ModuleInfo {
+            module,
+            params,
+            op_vars,
+            sys_fun_alias,
+            sensitivities: Vec::new(),
+            second_derivatives: Vec::new(),
+            diff_mode: DiffMode::Forward,
+            code_motion: false,
+            debug: DebugOpts::default(),
+        }
+    }
+
+    /// Looks up a parameter by its name or one of its aliases.
+    pub fn find_param(&self, name: &str) -> Option<Parameter> {
+        self.params.iter().find_map(|(param, info)| {
+            (info.name == name || info.alias.iter().any(|alias| alias == name)).then_some(*param)
+        })
+    }
+
+    /// Looks up a port or internal node by its name.
+    pub fn find_node(&self, db: &CompilationDB, name: &str) -> Option<Node> {
+        let mut nodes = self.module.ports(db).into_iter().chain(self.module.internal_nodes(db));
+        nodes.find(|node| node.name(db) == name)
     }
 }
 
@@ -0,0 +1,273 @@
+//! A small subset of Verilog-A that the fuzzer generates. Every program that can be
+//! represented is well typed, so the compiler must accept it.
+
+use std::fmt::{self, Display, Formatter};
+
+use mir_opt_driver::Pass;
+
+/// The number of real variables (`v0`, `v1`, ...).
+pub const NUM_VARS: u8 = 4;
+/// The number of real parameters (`p0`, `p1`, ...).
+pub const NUM_PARAMS: u8 = 3;
+/// Loops can be nested up to this depth, each level has its own counter (`i0`, `i1`, ...).
+pub const MAX_LOOP_DEPTH: u8 = 2;
+/// The nodes of the module: the ports `a` and `b` and the internal node `x`.
+pub const NODES: [&str; 3] = ["a", "b", "x"];
+
+/// A generated test case: the analog block of a module together with the values
+/// it is evaluated with and the optimization pipeline that is checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub body: Vec<Stmt>,
+    /// The default values of the parameters.
+    pub params: [f64; NUM_PARAMS as usize],
+    /// The node voltages (indexed like [`NODES`]) at which the module is evaluated.
+    pub voltages: [f64; NODES.len()],
+    /// The passes that are applied to the lowered MIR.
+    pub passes: Vec<Pass>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Assign(u8, Expr),
+    If(Cond, Vec<Stmt>, Vec<Stmt>),
+    /// `for (i{depth} = 0; i{depth} < count; i{depth} = i{depth} + 1)`
+    For {
+        depth: u8,
+        count: u8,
+        body: Vec<Stmt>,
+    },
+    Contribute {
+        branch: Branch,
+        ddt: bool,
+        val: Expr,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Branch {
+    pub hi: u8,
+    pub lo: Option<u8>,
+}
+
+impl Display for Branch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.lo {
+            Some(lo) => write!(f, "{}, {}", NODES[self.hi as usize], NODES[lo as usize]),
+            None => write!(f, "{}", NODES[self.hi as usize]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Param(u8),
+    Var(u8),
+    /// The loop counter of the given depth (converted to real).
+    Counter(u8),
+    Voltage(Branch),
+    Neg(Box<Expr>),
+    Call(Func, Vec<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Select(Box<Cond>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Num(_) | Expr::Param(_) | Expr::Var(_) | Expr::Counter(_) | Expr::Voltage(_) => {
+                Vec::new()
+            }
+            Expr::Neg(arg) => vec![&**arg],
+            Expr::Call(_, args) => args.iter().collect(),
+            Expr::Binary(_, lhs, rhs) => vec![&**lhs, &**rhs],
+            Expr::Select(_, then_val, else_val) => vec![&**then_val, &**else_val],
+        }
+    }
+}
+
+/// Builtin functions. Functions that are only defined on part of the real axis are
+/// wrapped so that they are always evaluated inside their domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tanh,
+    Atan,
+    Exp,
+    Abs,
+    /// `sqrt(abs(x) + 0.1)`
+    Sqrt,
+    /// `ln(abs(x) + 0.1)`
+    Ln,
+    Floor,
+    Min,
+    Max,
+    Hypot,
+    /// `pow(abs(x) + 0.5, y)`
+    Pow,
+    /// `x / (abs(y) + 0.5)`
+    Div,
+}
+
+impl Func {
+    pub const ALL: [Func; 14] = [
+        Func::Sin,
+        Func::Cos,
+        Func::Tanh,
+        Func::Atan,
+        Func::Exp,
+        Func::Abs,
+        Func::Sqrt,
+        Func::Ln,
+        Func::Floor,
+        Func::Min,
+        Func::Max,
+        Func::Hypot,
+        Func::Pow,
+        Func::Div,
+    ];
+
+    pub fn num_args(self) -> usize {
+        match self {
+            Func::Min | Func::Max | Func::Hypot | Func::Pow | Func::Div => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cond {
+    Cmp(CmpOp, Expr, Expr),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+    Not(Box<Cond>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+impl Display for Case {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "`include \"disciplines.vams\"")?;
+        writeln!(f, "module fuzz(a, b);")?;
+        writeln!(f, "    inout a, b;")?;
+        writeln!(f, "    electrical a, b, x;")?;
+        for (i, val) in self.params.iter().enumerate() {
+            writeln!(f, "    parameter real p{i} = {val:?};")?;
+        }
+        let vars: Vec<_> = (0..NUM_VARS).map(|i| format!("v{i}")).collect();
+        writeln!(f, "    real {};", vars.join(", "))?;
+        let counters: Vec<_> = (0..MAX_LOOP_DEPTH).map(|i| format!("i{i}")).collect();
+        writeln!(f, "    integer {};", counters.join(", "))?;
+        writeln!(f, "    analog begin")?;
+        for stmt in &self.body {
+            write_stmt(f, stmt, 2)?;
+        }
+        writeln!(f, "    end")?;
+        writeln!(f, "endmodule")
+    }
+}
+
+fn write_block(f: &mut Formatter<'_>, stmts: &[Stmt], indent: usize) -> fmt::Result {
+    writeln!(f, "begin")?;
+    for stmt in stmts {
+        write_stmt(f, stmt, indent + 1)?;
+    }
+    write!(f, "{:1$}end", "", indent * 4)
+}
+
+fn write_stmt(f: &mut Formatter<'_>, stmt: &Stmt, indent: usize) -> fmt::Result {
+    write!(f, "{:1$}", "", indent * 4)?;
+    match stmt {
+        Stmt::Assign(var, val) => write!(f, "v{var} = {val};")?,
+        Stmt::If(cond, then_stmts, else_stmts) => {
+            write!(f, "if ({cond}) ")?;
+            write_block(f, then_stmts, indent)?;
+            if !else_stmts.is_empty() {
+                write!(f, " else ")?;
+                write_block(f, else_stmts, indent)?;
+            }
+        }
+        Stmt::For { depth, count, body } => {
+            write!(f, "for (i{depth} = 0; i{depth} < {count}; i{depth} = i{depth} + 1) ")?;
+            write_block(f, body, indent)?;
+        }
+        Stmt::Contribute { branch, ddt: true, val } => write!(f, "I({branch}) <+ ddt({val});")?,
+        Stmt::Contribute { branch, ddt: false, val } => write!(f, "I({branch}) <+ {val};")?,
+    }
+    writeln!(f)
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(val) => write!(f, "{val:?}"),
+            Expr::Param(param) => write!(f, "p{param}"),
+            Expr::Var(var) => write!(f, "v{var}"),
+            Expr::Counter(depth) => write!(f, "i{depth}"),
+            Expr::Voltage(branch) => write!(f, "V({branch})"),
+            Expr::Neg(arg) => write!(f, "-({arg})"),
+            Expr::Call(func, args) => match (func, &**args) {
+                (Func::Sin, [arg]) => write!(f, "sin({arg})"),
+                (Func::Cos, [arg]) => write!(f, "cos({arg})"),
+                (Func::Tanh, [arg]) => write!(f, "tanh({arg})"),
+                (Func::Atan, [arg]) => write!(f, "atan({arg})"),
+                (Func::Exp, [arg]) => write!(f, "exp({arg})"),
+                (Func::Abs, [arg]) => write!(f, "abs({arg})"),
+                (Func::Sqrt, [arg]) => write!(f, "sqrt(abs({arg}) + 0.1)"),
+                (Func::Ln, [arg]) => write!(f, "ln(abs({arg}) + 0.1)"),
+                (Func::Floor, [arg]) => write!(f, "floor({arg})"),
+                (Func::Min, [lhs, rhs]) => write!(f, "min({lhs}, {rhs})"),
+                (Func::Max, [lhs, rhs]) => write!(f, "max({lhs}, {rhs})"),
+                (Func::Hypot, [lhs, rhs]) => write!(f, "hypot({lhs}, {rhs})"),
+                (Func::Pow, [lhs, rhs]) => write!(f, "pow(abs({lhs}) + 0.5, {rhs})"),
+                (Func::Div, [lhs, rhs]) => write!(f, "({lhs} / (abs({rhs}) + 0.5))"),
+                (func, args) => unreachable!("{func:?} called with {} arguments", args.len()),
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let op = match op {
+                    BinOp::Add => "+",
+                    BinOp::Sub => "-",
+                    BinOp::Mul => "*",
+                };
+                write!(f, "({lhs} {op} {rhs})")
+            }
+            Expr::Select(cond, then_val, else_val) => {
+                write!(f, "({cond} ? {then_val} : {else_val})")
+            }
+        }
+    }
+}
+
+impl Display for Cond {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Cond::Cmp(op, lhs, rhs) => {
+                let op = match op {
+                    CmpOp::Lt => "<",
+                    CmpOp::Gt => ">",
+                    CmpOp::Le => "<=",
+                    CmpOp::Ge => ">=",
+                };
+                write!(f, "({lhs} {op} {rhs})")
+            }
+            Cond::And(lhs, rhs) => write!(f, "({lhs} && {rhs})"),
+            Cond::Or(lhs, rhs) => write!(f, "({lhs} || {rhs})"),
+            Cond::Not(arg) => write!(f, "!{arg}"),
+        }
+    }
+}
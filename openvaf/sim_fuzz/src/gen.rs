@@ -0,0 +1,187 @@
+//! Generates a [`Case`] from unstructured bytes (provided by the fuzzer or a random number
+//! generator). Every byte string is a valid input: once the bytes run out all decisions
+//! pick the first (simplest) alternative so generation always terminates.
+
+use mir_opt_driver::Pass;
+
+use crate::ast::{
+    BinOp, Branch, Case, CmpOp, Cond, Expr, Func, Stmt, MAX_LOOP_DEPTH, NODES, NUM_PARAMS, NUM_VARS,
+};
+
+const MAX_EXPR_DEPTH: u32 = 4;
+const MAX_STMT_DEPTH: u32 = 3;
+const MAX_STMTS: u32 = 6;
+
+/// The sequence of passes `sim_back` applies to the MIR of a module (before and after
+/// generating derivatives). Each generated pipeline is a subsequence of it so that the
+/// passes always run in an order that the compiler uses as well.
+pub const PIPELINE: [Pass; 13] = [
+    Pass::Dce,
+    Pass::Sccp,
+    Pass::InstCombine,
+    Pass::SimplifyCfgNoPhiMerge,
+    Pass::Licm,
+    Pass::Gvn,
+    Pass::Sccp,
+    Pass::InstCombine,
+    Pass::SimplifyCfg,
+    Pass::Licm,
+    Pass::Gvn,
+    Pass::Adce,
+    Pass::SimplifyCfg,
+];
+
+const CONSTANTS: [f64; 8] = [0.0, 1.0, 0.5, 2.0, 1.5, 3.0, 0.25, 10.0];
+const PARAM_VALUES: [f64; 4] = [1.0, 0.5, 2.0, 1.5];
+const VOLTAGES: [f64; 8] = [0.0, 0.7, -0.3, 1.2, 0.05, -1.0, 0.35, 2.0];
+
+pub struct Source<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Source<'a> {
+    pub fn new(data: &'a [u8]) -> Source<'a> {
+        Source { data }
+    }
+
+    fn byte(&mut self) -> u8 {
+        match self.data.split_first() {
+            Some((&byte, rem)) => {
+                self.data = rem;
+                byte
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: u32) -> u32 {
+        self.byte() as u32 % n
+    }
+
+    /// Returns `true` with a probability of `num / 8` (and `false` once the input is empty).
+    fn chance(&mut self, num: u32) -> bool {
+        self.below(8) >= 8 - num
+    }
+
+    fn choose<T: Copy>(&mut self, vals: &[T]) -> T {
+        vals[self.below(vals.len() as u32) as usize]
+    }
+}
+
+pub fn generate(data: &[u8]) -> Case {
+    let mut src = Source::new(data);
+    let params = [(); NUM_PARAMS as usize].map(|_| src.choose(&PARAM_VALUES));
+    let voltages = [(); NODES.len()].map(|_| src.choose(&VOLTAGES));
+    let passes = PIPELINE.into_iter().filter(|_| !src.chance(2)).collect();
+    let mut gen = Generator { src, loop_depth: 0 };
+    let mut body = gen.stmts(0);
+    // make sure that there is always something to check
+    body.push(Stmt::Contribute {
+        branch: Branch { hi: 0, lo: Some(1) },
+        ddt: false,
+        val: Expr::Var(0),
+    });
+    Case { body, params, voltages, passes }
+}
+
+struct Generator<'a> {
+    src: Source<'a>,
+    loop_depth: u8,
+}
+
+impl Generator<'_> {
+    fn stmts(&mut self, depth: u32) -> Vec<Stmt> {
+        let len = self.src.below(MAX_STMTS + 1);
+        (0..len).map(|_| self.stmt(depth)).collect()
+    }
+
+    fn stmt(&mut self, depth: u32) -> Stmt {
+        let nested = depth < MAX_STMT_DEPTH;
+        match self.src.below(8) {
+            2 | 3 => {
+                let branch = self.branch();
+                // analog operators can not be used inside loops
+                let ddt = self.loop_depth == 0 && self.src.chance(2);
+                Stmt::Contribute { branch, ddt, val: self.expr(0) }
+            }
+            4 if nested => {
+                let cond = self.cond(0);
+                let then_stmts = self.stmts(depth + 1);
+                let else_stmts = if self.src.chance(4) { self.stmts(depth + 1) } else { vec![] };
+                Stmt::If(cond, then_stmts, else_stmts)
+            }
+            5 if nested && self.loop_depth < MAX_LOOP_DEPTH => {
+                let count = 1 + self.src.below(3) as u8;
+                let depth_ = self.loop_depth;
+                self.loop_depth += 1;
+                let body = self.stmts(depth + 1);
+                self.loop_depth -= 1;
+                Stmt::For { depth: depth_, count, body }
+            }
+            _ => Stmt::Assign(self.src.below(NUM_VARS as u32) as u8, self.expr(0)),
+        }
+    }
+
+    fn branch(&mut self) -> Branch {
+        let hi = self.src.below(NODES.len() as u32) as u8;
+        let lo = self.src.below(NODES.len() as u32 + 1) as u8;
+        let lo = (lo != hi && (lo as usize) < NODES.len()).then_some(lo);
+        Branch { hi, lo }
+    }
+
+    fn expr(&mut self, depth: u32) -> Expr {
+        if depth >= MAX_EXPR_DEPTH {
+            return self.leaf();
+        }
+        let depth = depth + 1;
+        match self.src.below(12) {
+            6 => Expr::Neg(Box::new(self.expr(depth))),
+            7 | 8 => {
+                let func = self.src.choose(&Func::ALL);
+                let args = (0..func.num_args()).map(|_| self.expr(depth)).collect();
+                Expr::Call(func, args)
+            }
+            9 | 10 => {
+                let op = self.src.choose(&[BinOp::Add, BinOp::Sub, BinOp::Mul]);
+                Expr::Binary(op, Box::new(self.expr(depth)), Box::new(self.expr(depth)))
+            }
+            11 => Expr::Select(
+                Box::new(self.cond(depth)),
+                Box::new(self.expr(depth)),
+                Box::new(self.expr(depth)),
+            ),
+            _ => self.leaf(),
+        }
+    }
+
+    fn leaf(&mut self) -> Expr {
+        match self.src.below(6) {
+            1 => Expr::Param(self.src.below(NUM_PARAMS as u32) as u8),
+            2 => Expr::Var(self.src.below(NUM_VARS as u32) as u8),
+            3 => Expr::Counter(self.src.below(MAX_LOOP_DEPTH as u32) as u8),
+            4 | 5 => Expr::Voltage(self.branch()),
+            _ => Expr::Num(self.src.choose(&CONSTANTS)),
+        }
+    }
+
+    fn cond(&mut self, depth: u32) -> Cond {
+        if depth < MAX_EXPR_DEPTH {
+            match self.src.below(6) {
+                3 => {
+                    return Cond::And(
+                        Box::new(self.cond(depth + 1)),
+                        Box::new(self.cond(depth + 1)),
+                    )
+                }
+                4 => {
+                    return Cond::Or(Box::new(self.cond(depth + 1)), Box::new(self.cond(depth + 1)))
+                }
+                5 => return Cond::Not(Box::new(self.cond(depth + 1))),
+                _ => (),
+            }
+        }
+        let op = self.src.choose(&[CmpOp::Lt, CmpOp::Gt, CmpOp::Le, CmpOp::Ge]);
+        Cond::Cmp(op, self.expr(depth + 1), self.expr(depth + 1))
+    }
+}
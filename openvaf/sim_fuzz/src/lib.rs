@@ -0,0 +1,26 @@
+//! Differential fuzzing of the compiler pipeline. Random (but well typed) Verilog-A modules
+//! are generated from unstructured bytes ([`gen`]), compiled and checked by comparing
+//!
+//! * the interpreted MIR before and after the `mir_opt` passes and
+//! * the jacobian generated by `mir_autodiff` against finite differences of the residual.
+//!
+//! Failing cases are reduced automatically with [`minimize`]. The harness can be driven by
+//! `cargo fuzz` (see the `fuzz` directory) or by the `sim-fuzz` binary, which uses a simple
+//! pseudo random generator and needs no extra tooling.
+
+pub mod ast;
+pub mod gen;
+mod minimize;
+mod oracle;
+
+#[cfg(test)]
+mod tests;
+
+pub use crate::minimize::minimize;
+pub use crate::oracle::{check, Failure};
+
+/// Generates a case from `data` and checks it.
+pub fn fuzz_one(data: &[u8]) -> Result<(), (ast::Case, Failure)> {
+    let case = gen::generate(data);
+    check(&case).map_err(|failure| (case, failure))
+}
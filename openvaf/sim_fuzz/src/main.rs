@@ -0,0 +1,111 @@
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::{value_parser, Arg, Command};
+use sim_fuzz::{fuzz_one, minimize};
+
+const SEED: &str = "seed";
+const ITERATIONS: &str = "iterations";
+const OUT_DIR: &str = "out-dir";
+
+/// The number of random bytes each case is generated from.
+const INPUT_LEN: usize = 512;
+
+fn main_command() -> Command {
+    Command::new("sim-fuzz")
+        .about("Generates random Verilog-A modules and checks that they are compiled correctly.")
+        .args([
+            Arg::new(SEED)
+                .short('s')
+                .long(SEED)
+                .value_name("SEED")
+                .value_parser(value_parser!(u64))
+                .help("Seed of the random number generator (random by default)"),
+            Arg::new(ITERATIONS)
+                .short('n')
+                .long(ITERATIONS)
+                .value_name("N")
+                .value_parser(value_parser!(u64))
+                .default_value("1000")
+                .help("The number of cases that are generated"),
+            Arg::new(OUT_DIR)
+                .short('o')
+                .long(OUT_DIR)
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf))
+                .default_value("fuzz_failures")
+                .help("Directory where the (minimized) failing cases are written"),
+        ])
+}
+
+/// xorshift64*, sufficient to generate the input bytes.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+fn wrapped_main() -> Result<bool> {
+    let matches = main_command().get_matches();
+    let seed = match matches.get_one::<u64>(SEED) {
+        Some(&seed) => seed,
+        None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64,
+    };
+    let iterations = *matches.get_one::<u64>(ITERATIONS).unwrap();
+    let out_dir = matches.get_one::<PathBuf>(OUT_DIR).unwrap();
+
+    // panics are reported as failures, the default hook would only clutter the output
+    panic::set_hook(Box::new(|_| ()));
+
+    println!("seed: {seed}");
+    // xorshift must not be seeded with zero
+    let mut rng = Rng(seed | 1);
+    let mut data = vec![0; INPUT_LEN];
+    for i in 0..iterations {
+        rng.fill(&mut data);
+        let Err((case, failure)) = fuzz_one(&data) else { continue };
+        println!("case {i} failed: {failure}");
+        println!("minimizing...");
+        let (case, failure) = minimize(case, &failure);
+        let passes: Vec<_> = case.passes.iter().map(|pass| pass.name()).collect();
+        let contents = format!("// {failure}\n// passes: {}\n{case}", passes.join(", "));
+
+        fs::create_dir_all(out_dir)
+            .with_context(|| format!("failed to create {}", out_dir.display()))?;
+        let path = out_dir.join(format!("fuzz_{seed}_{i}.va"));
+        fs::write(&path, &contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        println!("{contents}");
+        println!("written to {}", path.display());
+        return Ok(false);
+    }
+    println!("{iterations} cases passed");
+    Ok(true)
+}
+
+fn main() {
+    match wrapped_main() {
+        Ok(true) => (),
+        Ok(false) => exit(1),
+        Err(err) => {
+            eprintln!("error: {err:?}");
+            exit(1)
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Greedy test case reduction: single reduction steps (removing a statement, replacing an
+//! expression with one of its operands, dropping a pass, ...) are applied as long as the
+//! reduced case still fails with the same kind of [`Failure`].
+
+use crate::ast::{Case, Cond, Expr, Stmt};
+use crate::oracle::{check, Failure};
+
+/// Reduces `case` while it keeps failing like `failure`. Returns the smallest case found
+/// and the failure it produces.
+pub fn minimize(case: Case, failure: &Failure) -> (Case, Failure) {
+    minimize_with(case, failure, check)
+}
+
+/// Like [`minimize`] but uses `check` instead of the oracle to test candidates.
+pub(crate) fn minimize_with(
+    mut case: Case,
+    failure: &Failure,
+    mut check: impl FnMut(&Case) -> Result<(), Failure>,
+) -> (Case, Failure) {
+    let mut failure = failure.clone();
+    'outer: loop {
+        for candidate in reductions(&case) {
+            match check(&candidate) {
+                Err(new_failure) if new_failure.same_kind(&failure) => {
+                    case = candidate;
+                    failure = new_failure;
+                    continue 'outer;
+                }
+                _ => (),
+            }
+        }
+        return (case, failure);
+    }
+}
+
+/// All cases that are one reduction step smaller than `case`. Larger reductions come first
+/// so that big chunks are removed early.
+fn reductions(case: &Case) -> Vec<Case> {
+    let mut res = Vec::new();
+    for i in 0..case.passes.len() {
+        let mut passes = case.passes.clone();
+        passes.remove(i);
+        res.push(Case { passes, ..case.clone() });
+    }
+    for body in stmts_reductions(&case.body) {
+        res.push(Case { body, ..case.clone() });
+    }
+    res
+}
+
+fn stmts_reductions(stmts: &[Stmt]) -> Vec<Vec<Stmt>> {
+    let mut res = Vec::new();
+    for i in 0..stmts.len() {
+        let mut removed = stmts.to_vec();
+        removed.remove(i);
+        res.push(removed);
+    }
+    for (i, stmt) in stmts.iter().enumerate() {
+        for replacement in stmt_reductions(stmt) {
+            let mut reduced = stmts[..i].to_vec();
+            reduced.extend(replacement);
+            reduced.extend_from_slice(&stmts[i + 1..]);
+            res.push(reduced);
+        }
+    }
+    res
+}
+
+/// Each reduction of `stmt` is a list of statements that replaces it.
+fn stmt_reductions(stmt: &Stmt) -> Vec<Vec<Stmt>> {
+    let mut res = Vec::new();
+    match stmt {
+        Stmt::Assign(var, val) => {
+            res.extend(expr_reductions(val).into_iter().map(|val| vec![Stmt::Assign(*var, val)]))
+        }
+        Stmt::If(cond, then_stmts, else_stmts) => {
+            res.push(then_stmts.clone());
+            res.push(else_stmts.clone());
+            if !else_stmts.is_empty() {
+                res.push(vec![Stmt::If(cond.clone(), then_stmts.clone(), Vec::new())]);
+            }
+            res.extend(
+                cond_reductions(cond)
+                    .into_iter()
+                    .map(|cond| vec![Stmt::If(cond, then_stmts.clone(), else_stmts.clone())]),
+            );
+            res.extend(
+                stmts_reductions(then_stmts)
+                    .into_iter()
+                    .map(|then_stmts| vec![Stmt::If(cond.clone(), then_stmts, else_stmts.clone())]),
+            );
+            res.extend(
+                stmts_reductions(else_stmts)
+                    .into_iter()
+                    .map(|else_stmts| vec![Stmt::If(cond.clone(), then_stmts.clone(), else_stmts)]),
+            );
+        }
+        Stmt::For { depth, count, body } => {
+            res.push(body.clone());
+            if *count > 1 {
+                res.push(vec![Stmt::For { depth: *depth, count: 1, body: body.clone() }]);
+            }
+            res.extend(
+                stmts_reductions(body)
+                    .into_iter()
+                    .map(|body| vec![Stmt::For { depth: *depth, count: *count, body }]),
+            );
+        }
+        Stmt::Contribute { branch, ddt, val } => {
+            if *ddt {
+                res.push(vec![Stmt::Contribute { branch: *branch, ddt: false, val: val.clone() }]);
+            }
+            res.extend(
+                expr_reductions(val)
+                    .into_iter()
+                    .map(|val| vec![Stmt::Contribute { branch: *branch, ddt: *ddt, val }]),
+            );
+        }
+    }
+    res
+}
+
+fn expr_reductions(expr: &Expr) -> Vec<Expr> {
+    let mut res: Vec<Expr> = expr.children().into_iter().cloned().collect();
+    // every reduction must make the case strictly simpler, otherwise minimization
+    // could cycle between equivalent cases: `0.0` is the simplest expression
+    match expr {
+        Expr::Num(val) if *val == 0.0 => (),
+        Expr::Num(_) | Expr::Param(_) | Expr::Var(_) | Expr::Counter(_) | Expr::Voltage(_) => {
+            res.push(Expr::Num(0.0))
+        }
+        _ => res.extend([Expr::Num(0.0), Expr::Num(1.0)]),
+    }
+    match expr {
+        Expr::Num(_) | Expr::Param(_) | Expr::Var(_) | Expr::Counter(_) | Expr::Voltage(_) => (),
+        Expr::Neg(arg) => res.extend(expr_reductions(arg).into_iter().map(boxed(Expr::Neg))),
+        Expr::Call(func, args) => {
+            for (i, arg) in args.iter().enumerate() {
+                res.extend(expr_reductions(arg).into_iter().map(|arg| {
+                    let mut args = args.clone();
+                    args[i] = arg;
+                    Expr::Call(*func, args)
+                }));
+            }
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            res.extend(
+                expr_reductions(lhs)
+                    .into_iter()
+                    .map(|lhs| Expr::Binary(*op, Box::new(lhs), rhs.clone())),
+            );
+            res.extend(
+                expr_reductions(rhs)
+                    .into_iter()
+                    .map(|rhs| Expr::Binary(*op, lhs.clone(), Box::new(rhs))),
+            );
+        }
+        Expr::Select(cond, then_val, else_val) => {
+            res.extend(
+                cond_reductions(cond)
+                    .into_iter()
+                    .map(|cond| Expr::Select(Box::new(cond), then_val.clone(), else_val.clone())),
+            );
+            res.extend(
+                expr_reductions(then_val).into_iter().map(|then_val| {
+                    Expr::Select(cond.clone(), Box::new(then_val), else_val.clone())
+                }),
+            );
+            res.extend(
+                expr_reductions(else_val).into_iter().map(|else_val| {
+                    Expr::Select(cond.clone(), then_val.clone(), Box::new(else_val))
+                }),
+            );
+        }
+    }
+    res
+}
+
+fn cond_reductions(cond: &Cond) -> Vec<Cond> {
+    let mut res = Vec::new();
+    match cond {
+        Cond::Cmp(op, lhs, rhs) => {
+            res.extend(
+                expr_reductions(lhs).into_iter().map(|lhs| Cond::Cmp(*op, lhs, rhs.clone())),
+            );
+            res.extend(
+                expr_reductions(rhs).into_iter().map(|rhs| Cond::Cmp(*op, lhs.clone(), rhs)),
+            );
+        }
+        Cond::And(lhs, rhs) | Cond::Or(lhs, rhs) => {
+            res.push((**lhs).clone());
+            res.push((**rhs).clone());
+        }
+        Cond::Not(arg) => {
+            res.push((**arg).clone());
+            res.extend(cond_reductions(arg).into_iter().map(boxed(Cond::Not)));
+        }
+    }
+    res
+}
+
+fn boxed<T, R>(f: impl Fn(Box<T>) -> R) -> impl Fn(T) -> R {
+    move |val| f(Box::new(val))
+}
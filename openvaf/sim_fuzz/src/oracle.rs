@@ -0,0 +1,334 @@
+//! The checks that are run for every generated [`Case`]:
+//!
+//! * the compiler must accept the module and must not panic,
+//! * interpreting the lowered MIR before and after the selected `mir_opt` passes
+//!   must produce the same contributions,
+//! * the jacobian produced by `sim_back` (with `mir_autodiff`) must match finite
+//!   differences of the residual.
+
+use std::ffi::c_void;
+use std::fmt::{self, Display, Formatter};
+use std::iter;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::null_mut;
+
+use hir::diagnostics::sink::Buffer;
+use hir::diagnostics::ConsoleSink;
+use hir::CompilationDB;
+use hir_lower::{HirInterner, MirBuilder, ParamKind, PlaceKind};
+use lasso::Rodeo;
+use mir::{FuncRef, Function, Param, Value};
+use mir_interpret::{Data, Func, Interpreter, InterpreterState};
+use mir_opt_driver::Pipeline;
+use sim_back::dae::SimUnknown;
+use sim_back::{collect_modules, CompiledModule, ModuleInfo, SimUnknownKind};
+use sim_interpret::{Analysis, EvalResult, Instance, Model, SetupResult, SimInfo, Simulator};
+use typed_index_collections::{TiSlice, TiVec};
+
+use crate::ast::{Case, NODES};
+
+/// The temperature that all cases are evaluated at.
+const TEMPERATURE: f64 = 300.15;
+/// The (relative) step that is used to compute finite differences.
+const FD_STEP: f64 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    /// The frontend rejected the generated module (a bug in the generator or the frontend).
+    Rejected(String),
+    /// The compiler panicked during the given stage.
+    Panic { stage: &'static str, msg: String },
+    /// Model or instance setup rejected the generated parameters or failed fatally.
+    Setup { stage: &'static str, msg: String },
+    /// An optimization pass produced MIR that fails validation.
+    InvalidMir(String),
+    /// A contribution has a different value after optimization.
+    Miscompile { output: String, expected: f64, found: f64 },
+    /// A jacobian entry does not match the finite difference of the residual.
+    Derivative { row: String, col: String, react: bool, jacobian: f64, finite_difference: f64 },
+}
+
+impl Failure {
+    /// Whether `other` is (likely) caused by the same bug. Used while minimizing.
+    pub fn same_kind(&self, other: &Failure) -> bool {
+        match (self, other) {
+            (Failure::Panic { stage, .. }, Failure::Panic { stage: other, .. })
+            | (Failure::Setup { stage, .. }, Failure::Setup { stage: other, .. }) => stage == other,
+            (Failure::Derivative { react, .. }, Failure::Derivative { react: other, .. }) => {
+                react == other
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Rejected(diagnostics) => {
+                write!(f, "the generated module was rejected:\n{diagnostics}")
+            }
+            Failure::Panic { stage, msg } => write!(f, "panic during {stage}: {msg}"),
+            Failure::Setup { stage, msg } => write!(f, "{stage} setup failed: {msg}"),
+            Failure::InvalidMir(err) => write!(f, "invalid MIR after optimization: {err}"),
+            Failure::Miscompile { output, expected, found } => {
+                write!(f, "{output} is {found:e} after optimization but {expected:e} before")
+            }
+            Failure::Derivative { row, col, react, jacobian, finite_difference } => {
+                let kind = if *react { "reactive" } else { "resistive" };
+                write!(
+                    f,
+                    "{kind} jacobian entry ({row}, {col}) is {jacobian:e} but the finite difference is {finite_difference:e}"
+                )
+            }
+        }
+    }
+}
+
+/// Runs all checks on `case`.
+pub fn check(case: &Case) -> Result<(), Failure> {
+    let src = case.to_string();
+    let db = CompilationDB::new_virtual(&src).unwrap();
+    let module = catch("frontend", || {
+        let mut buf = Buffer::no_color();
+        match collect_modules(&db, false, &mut ConsoleSink::buffer(&db, &mut buf)) {
+            Some(mut modules) => Ok(modules.remove(0)),
+            None => Err(Failure::Rejected(String::from_utf8_lossy(buf.as_slice()).into_owned())),
+        }
+    })??;
+
+    check_optimizations(&db, &module, case)?;
+    check_derivatives(&db, &module, case)
+}
+
+fn catch<T>(stage: &'static str, f: impl FnOnce() -> T) -> Result<T, Failure> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|err| {
+        let msg = match err.downcast::<String>() {
+            Ok(msg) => *msg,
+            Err(err) => err.downcast_ref::<&str>().map_or("<unknown>", |msg| msg).to_owned(),
+        };
+        Failure::Panic { stage, msg }
+    })
+}
+
+fn check_optimizations(
+    db: &CompilationDB,
+    module: &ModuleInfo,
+    case: &Case,
+) -> Result<(), Failure> {
+    let (func, intern) = catch("lowering", || {
+        let mut literals = Rodeo::new();
+        MirBuilder::new(
+            db,
+            module.module,
+            &|kind| matches!(kind, PlaceKind::Contribute { .. }),
+            &mut iter::empty(),
+        )
+        .with_equations()
+        .with_tagged_writes()
+        .build(&mut literals)
+    })?;
+
+    let outputs: Vec<_> =
+        intern.outputs.iter().filter_map(|(kind, val)| Some((kind, val.expand()?))).collect();
+    let pipeline = Pipeline {
+        passes: case.passes.clone(),
+        outputs: outputs.iter().map(|&(_, val)| val).collect(),
+        ..Pipeline::default()
+    };
+
+    let args = lowered_args(db, &intern, case);
+    let expected = catch("interpreting the lowered MIR", || interpret(&func, &args))?;
+    let mut optimized = func.clone();
+    catch("optimization", || pipeline.run(&mut optimized))?
+        .map_err(|err| Failure::InvalidMir(format!("{err:?}")))?;
+    let found = catch("interpreting the optimized MIR", || interpret(&optimized, &args))?;
+
+    for &(kind, val) in &outputs {
+        let (expected, found) = (expected.read::<f64>(val), found.read::<f64>(val));
+        if !expected.is_finite() {
+            // optimizations may legally change the result of operations on inf/NaN
+            continue;
+        }
+        if !approx_eq(expected, found, 1e-9, 0.0) {
+            return Err(Failure::Miscompile { output: format!("{kind:?}"), expected, found });
+        }
+    }
+    Ok(())
+}
+
+/// The arguments of the lowered MIR: parameters have their default value and the
+/// voltages are taken from `case`.
+fn lowered_args(db: &CompilationDB, intern: &HirInterner, case: &Case) -> TiVec<Param, Data> {
+    let voltage = |node: hir::Node| {
+        let name = node.name(db);
+        let pos = NODES.iter().position(|it| name == *it).expect("unknown node");
+        case.voltages[pos]
+    };
+    intern
+        .params
+        .raw
+        .keys()
+        .map(|kind| match *kind {
+            ParamKind::Param(param) => {
+                let name = param.name(db);
+                let idx: usize = name[1..].parse().expect("unknown parameter");
+                case.params[idx].into()
+            }
+            ParamKind::ParamGiven { .. } => false.into(),
+            ParamKind::PortConnected { .. } | ParamKind::EnableIntegration => true.into(),
+            ParamKind::EnableLim => false.into(),
+            ParamKind::Voltage { hi, lo } => {
+                let lo = lo.map_or(0.0, voltage);
+                (voltage(hi) - lo).into()
+            }
+            ParamKind::Temperature => TEMPERATURE.into(),
+            ParamKind::ParamSysFun(param) => param.default_value().into(),
+            _ => 0.0.into(),
+        })
+        .collect()
+}
+
+/// Callbacks (like `ddt`) are replaced with the identity function. That does not match
+/// their real semantics but is sufficient to check that optimizations preserve them.
+fn identity_callback(state: &mut InterpreterState, args: &[Value], rets: &[Value], _: *mut c_void) {
+    for (i, &ret) in rets.iter().enumerate() {
+        let val = args.get(i).map_or(Data::from(0.0), |&arg| state.read::<Data>(arg));
+        state.write(ret, val);
+    }
+}
+
+fn interpret(func: &Function, args: &TiSlice<Param, Data>) -> InterpreterState {
+    let calls: TiVec<FuncRef, (Func<'_>, *mut c_void)> =
+        func.dfg.signatures.iter().map(|_| (identity_callback as Func<'_>, null_mut())).collect();
+    let mut interpreter = Interpreter::new(func, &calls, args);
+    interpreter.run();
+    interpreter.state
+}
+
+fn check_setup(
+    db: &CompilationDB,
+    stage: &'static str,
+    res: &SetupResult,
+    sim: &Simulator,
+) -> Result<(), Failure> {
+    if res.is_ok() {
+        return Ok(());
+    }
+    let mut msg = String::new();
+    if !res.invalid_params.is_empty() {
+        let params: Vec<_> = res.invalid_params.iter().map(|param| param.name(db)).collect();
+        msg = format!("invalid parameters {}", params.join(", "));
+    }
+    if res.flags.fatal {
+        let messages: Vec<_> = sim.messages.iter().map(|msg| msg.text.as_str()).collect();
+        if !msg.is_empty() {
+            msg.push_str("; ");
+        }
+        msg.push_str(&format!("fatal error: {}", messages.join("; ")));
+    }
+    Err(Failure::Setup { stage, msg })
+}
+
+fn check_derivatives(db: &CompilationDB, module: &ModuleInfo, case: &Case) -> Result<(), Failure> {
+    let mut literals = Rodeo::new();
+    let compiled = catch("sim_back", || CompiledModule::new(db, module, &mut literals))?;
+    let mut sim = Simulator::new(literals);
+
+    let mut model = Model::new();
+    let mut instance = Instance::new();
+    let model_res = catch("setup", || model.setup(&compiled, &mut sim))?;
+    check_setup(db, "model", &model_res, &sim)?;
+    let instance_res =
+        catch("setup", || instance.setup(&compiled, &model, TEMPERATURE, 2, &mut sim))?;
+    check_setup(db, "instance", &instance_res, &sim)?;
+
+    let unknowns = &compiled.dae_system.unknowns;
+    let solve: Vec<f64> = unknowns
+        .iter()
+        .map(|unknown| match unknown {
+            SimUnknownKind::KirchoffLaw(node) => {
+                let name = node.name(db);
+                NODES.iter().position(|it| name == *it).map_or(0.0, |pos| case.voltages[pos])
+            }
+            _ => 0.0,
+        })
+        .collect();
+
+    type Residual = TiVec<SimUnknown, (f64, f64)>;
+    let mut eval = |solve: &[f64]| -> Result<(EvalResult, Residual), Failure> {
+        let info = SimInfo {
+            analysis: Analysis::Tran,
+            abstime: 0.0,
+            prev_solve: solve,
+            prev_state: &[],
+            enable_lim: false,
+            init_lim: false,
+        };
+        let res = catch("eval", || instance.eval(&compiled, &model, &info, &mut sim))?;
+        let residual =
+            res.residual.iter().map(|residual| (residual.resist, residual.react)).collect();
+        Ok((res, residual))
+    };
+
+    let (res, residual) = eval(&solve)?;
+    for (col, col_kind) in unknowns.iter_enumerated() {
+        let col_idx = u32::from(col) as usize;
+        let mut finite_difference = |step: f64| -> Result<Residual, Failure> {
+            let mut solve = solve.clone();
+            solve[col_idx] += step;
+            let (_, upper) = eval(&solve)?;
+            solve[col_idx] -= 2.0 * step;
+            let (_, lower) = eval(&solve)?;
+            let fd = iter::zip(upper, lower)
+                .map(|(upper, lower)| {
+                    ((upper.0 - lower.0) / (2.0 * step), (upper.1 - lower.1) / (2.0 * step))
+                })
+                .collect();
+            Ok(fd)
+        };
+
+        let step = FD_STEP * solve[col_idx].abs().max(1.0);
+        let coarse = finite_difference(step)?;
+        let fine = finite_difference(step / 8.0)?;
+
+        for (row, row_kind) in unknowns.iter_enumerated() {
+            let jacobian = compiled
+                .dae_system
+                .jacobian
+                .iter_enumerated()
+                .find(|(_, entry)| entry.row == row && entry.col == col)
+                .map_or((0.0, 0.0), |(id, _)| res.jacobian[id]);
+
+            let entries = [
+                (false, jacobian.0, coarse[row].0, fine[row].0, residual[row].0),
+                (true, jacobian.1, coarse[row].1, fine[row].1, residual[row].1),
+            ];
+            for (react, jacobian, coarse, fine, residual) in entries {
+                if !(jacobian.is_finite() && coarse.is_finite() && fine.is_finite()) {
+                    continue;
+                }
+                let abs_tol = 1e-6 * (1.0 + residual.abs());
+                // the residual is not smooth around the operating point (for example
+                // because a condition changes), finite differences are meaningless
+                if !approx_eq(coarse, fine, 1e-4, abs_tol) {
+                    continue;
+                }
+                if !approx_eq(jacobian, fine, 1e-4, abs_tol) {
+                    return Err(Failure::Derivative {
+                        row: format!("{row_kind:?}"),
+                        col: format!("{col_kind:?}"),
+                        react,
+                        jacobian,
+                        finite_difference: fine,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn approx_eq(lhs: f64, rhs: f64, rel_tol: f64, abs_tol: f64) -> bool {
+    (lhs - rhs).abs() <= rel_tol * lhs.abs().max(rhs.abs()) + abs_tol
+}
@@ -0,0 +1,69 @@
+use crate::ast::Case;
+use crate::check;
+use crate::gen::{generate, PIPELINE};
+use crate::minimize::minimize_with;
+use crate::oracle::Failure;
+
+#[test]
+fn empty_input() {
+    let case = generate(&[]);
+    assert_eq!(case.passes, PIPELINE);
+    expect_test::expect![[r#"
+        `include "disciplines.vams"
+        module fuzz(a, b);
+            inout a, b;
+            electrical a, b, x;
+            parameter real p0 = 1.0;
+            parameter real p1 = 1.0;
+            parameter real p2 = 1.0;
+            real v0, v1, v2, v3;
+            integer i0, i1;
+            analog begin
+                I(a, b) <+ v0;
+            end
+        endmodule
+    "#]]
+    .assert_eq(&case.to_string());
+    check(&case).unwrap();
+}
+
+#[test]
+fn deterministic() {
+    let data: Vec<u8> = (0..512u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+    let case: Case = generate(&data);
+    assert_eq!(case, generate(&data));
+}
+
+#[test]
+fn minimize_known_failure() {
+    // stands in for a compiler bug that miscompiles every call to `exp`
+    let oracle = |case: &Case| {
+        if case.to_string().contains("exp(") {
+            Err(Failure::Miscompile { output: "I(a, b)".to_owned(), expected: 1.0, found: 0.0 })
+        } else {
+            Ok(())
+        }
+    };
+    let data: Vec<u8> = (0..512u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+    let case = generate(&data);
+    let failure = oracle(&case).unwrap_err();
+    let (minimized, found) = minimize_with(case, &failure, oracle);
+    assert!(found.same_kind(&failure));
+    assert!(minimized.passes.is_empty());
+    expect_test::expect![[r#"
+        `include "disciplines.vams"
+        module fuzz(a, b);
+            inout a, b;
+            electrical a, b, x;
+            parameter real p0 = 1.0;
+            parameter real p1 = 1.5;
+            parameter real p2 = 1.5;
+            real v0, v1, v2, v3;
+            integer i0, i1;
+            analog begin
+                v1 = exp(0.0);
+            end
+        endmodule
+    "#]]
+    .assert_eq(&minimized.to_string());
+}
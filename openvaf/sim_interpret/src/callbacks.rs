@@ -0,0 +1,224 @@
+use std::ffi::c_void;
+use std::mem::take;
+
+use bitset::BitSet;
+use hir::{Parameter, Type};
+use hir_lower::fmt::{DisplayKind, FmtArgKind};
+use hir_lower::{CallBackKind, HirInterner, LimitState, ParamInfoKind, ParamKind};
+use lasso::Spur;
+use mir::{FuncRef, Value};
+use mir_interpret::{Func, InterpreterState};
+use sim_back::node_collapse::CollapsePair;
+use sim_back::{CompiledModule, SimUnknownKind};
+use typed_index_collections::{TiSlice, TiVec};
+
+use crate::fmt::{engineer_notation, sprintf, FmtVal};
+use crate::{Message, RetFlags, SetupResult, SimInfo, Simulator};
+
+/// The state shared by all callbacks during a single run of the interpreter.
+pub(crate) struct RunCx<'a> {
+    sim: &'a mut Simulator,
+    module: &'a CompiledModule<'a>,
+    intern: &'a HirInterner,
+    sim_info: Option<&'a SimInfo<'a>>,
+    pub(crate) next_state: TiVec<LimitState, f64>,
+    /// Only available during instance setup
+    pub(crate) collapsed: Option<BitSet<CollapsePair>>,
+    invalid_params: Vec<Parameter>,
+    pub(crate) flags: RetFlags,
+}
+
+impl<'a> RunCx<'a> {
+    pub(crate) fn new(
+        sim: &'a mut Simulator,
+        module: &'a CompiledModule<'a>,
+        intern: &'a HirInterner,
+        sim_info: Option<&'a SimInfo<'a>>,
+    ) -> RunCx<'a> {
+        let next_state =
+            sim_info.map_or_else(TiVec::new, |sim_info| sim_info.prev_state.to_vec().into());
+        RunCx {
+            sim,
+            module,
+            intern,
+            sim_info,
+            next_state,
+            collapsed: None,
+            invalid_params: Vec::new(),
+            flags: RetFlags::default(),
+        }
+    }
+
+    pub(crate) fn finish_setup(&mut self) -> SetupResult {
+        SetupResult { invalid_params: take(&mut self.invalid_params), flags: self.flags }
+    }
+
+    fn resolve(&self, state: &InterpreterState, val: Value) -> String {
+        self.sim.literals.resolve(&state.read::<Spur>(val)).to_owned()
+    }
+
+    fn fatal(&mut self, text: String) {
+        self.flags.fatal = true;
+        self.sim.messages.push(Message { kind: DisplayKind::Fatal, text });
+    }
+}
+
+#[derive(Clone, Copy)]
+struct CallbackData {
+    cx: *mut c_void,
+    func: FuncRef,
+}
+
+/// The call table passed to the interpreter. All callbacks are dispatched
+/// by the same function, which looks up the [`CallBackKind`] of the call.
+pub(crate) struct Callbacks {
+    // the table contains pointers into this allocation
+    _data: Box<[CallbackData]>,
+    table: TiVec<FuncRef, (Func<'static>, *mut c_void)>,
+}
+
+impl Callbacks {
+    /// `cx` must not be accessed while the returned callbacks are used
+    pub(crate) fn new(intern: &HirInterner, cx: &mut RunCx<'_>) -> Callbacks {
+        let cx = cx as *mut RunCx<'_> as *mut c_void;
+        let mut data: Box<[CallbackData]> =
+            intern.callbacks.indices().map(|func| CallbackData { cx, func }).collect();
+        let table = data
+            .iter_mut()
+            .map(|data| (dispatch as Func<'static>, data as *mut CallbackData as *mut c_void))
+            .collect();
+        Callbacks { _data: data, table }
+    }
+
+    pub(crate) fn table(&self) -> &TiSlice<FuncRef, (Func<'static>, *mut c_void)> {
+        &self.table
+    }
+}
+
+fn dispatch(state: &mut InterpreterState, args: &[Value], rets: &[Value], data: *mut c_void) {
+    // Safety: the table only ever contains pointers to `CallbackData` that live as long as the
+    // table. The `RunCx` outlives the interpreter run and is not accessed by anything else.
+    let CallbackData { cx, func } = unsafe { *(data as *const CallbackData) };
+    let cx = unsafe { &mut *(cx as *mut RunCx<'_>) };
+    let intern = cx.intern;
+
+    match intern.callbacks[func] {
+        CallBackKind::SimParam => {
+            let name = cx.resolve(state, args[0]);
+            let val = match cx.sim.sim_params.get(&name) {
+                Some(&val) => val,
+                None => {
+                    cx.fatal(format!("unknown $simparam {name}"));
+                    0.0
+                }
+            };
+            state.write(rets[0], val)
+        }
+        CallBackKind::SimParamOpt => {
+            let name = cx.resolve(state, args[0]);
+            let val = cx.sim.sim_params.get(&name).copied().unwrap_or_else(|| state.read(args[1]));
+            state.write(rets[0], val)
+        }
+        CallBackKind::SimParamStr => {
+            let name = cx.resolve(state, args[0]);
+            let val = match cx.sim.sim_params_str.get(&name) {
+                Some(val) => val.clone(),
+                None => {
+                    cx.fatal(format!("unknown $simparam_str {name}"));
+                    String::new()
+                }
+            };
+            let val = cx.sim.literals.get_or_intern(val);
+            state.write(rets[0], val)
+        }
+        // If these derivatives were non zero they would have been removed
+        CallBackKind::Derivative(_) | CallBackKind::NodeDerivative(_) => state.write(rets[0], 0.0),
+        // noise and time derivatives are not part of the large signal evaluation
+        CallBackKind::TimeDerivative
+        | CallBackKind::WhiteNoise { .. }
+        | CallBackKind::FlickerNoise { .. }
+        | CallBackKind::NoiseTable(_) => {
+            for &ret in rets {
+                state.write(ret, 0.0)
+            }
+        }
+        CallBackKind::ParamInfo(ParamInfoKind::Invalid, param) => cx.invalid_params.push(param),
+        CallBackKind::ParamInfo(_, _) => (),
+        CallBackKind::CollapseHint(hi, lo) => {
+            if let Some(collapsed) = &mut cx.collapsed {
+                let unknowns = &cx.module.dae_system.unknowns;
+                let hi = unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(hi));
+                let lo = lo.map(|lo| unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(lo)));
+                cx.module.node_collapse.hint(hi, lo, |pair| {
+                    collapsed.insert(pair);
+                });
+            }
+        }
+        CallBackKind::LimDiscontinuity => cx.flags.limited = true,
+        CallBackKind::Analysis => {
+            let name = cx.resolve(state, args[0]);
+            let res = cx.sim_info.is_some_and(|sim_info| sim_info.analysis.matches(&name));
+            state.write(rets[0], res as i32)
+        }
+        CallBackKind::BuiltinLimit { name, .. } => {
+            let name = cx.sim.literals.resolve(&name);
+            let lim_fn = cx.sim.lim_functions.get(name).copied();
+            let vnew = state.read(args[0]);
+            let vold = state.read(args[1]);
+            let extra: Vec<f64> = args[2..].iter().map(|&arg| state.read(arg)).collect();
+            let init = cx.sim_info.is_some_and(|sim_info| sim_info.init_lim);
+            let res = match lim_fn {
+                Some(lim_fn) => {
+                    let (res, changed) = lim_fn(init, vnew, vold, &extra);
+                    cx.flags.limited |= changed;
+                    res
+                }
+                None => {
+                    let text = format!("unknown limiting function {name}");
+                    cx.fatal(text);
+                    vnew
+                }
+            };
+            state.write(rets[0], res)
+        }
+        CallBackKind::StoreLimit(lim_state) => {
+            let val: f64 = state.read(args[0]);
+            cx.next_state[lim_state] = val;
+            // the new state is read after it has been stored (to compute the lim_rhs)
+            if let Some((_, &new_state)) =
+                intern.params.index_and_val(&ParamKind::NewState(lim_state))
+            {
+                state.write(new_state, val);
+            }
+            state.write(rets[0], val)
+        }
+        CallBackKind::Print { kind, ref arg_tys } => {
+            let fmt = cx.resolve(state, args[0]);
+            let mut vals = Vec::with_capacity(arg_tys.len());
+            for (arg, &val) in arg_tys.iter().zip(&args[1..]) {
+                match arg.kind {
+                    FmtArgKind::Binary => {
+                        vals.push(FmtVal::Str(format!("{:b}", state.read::<i32>(val))))
+                    }
+                    FmtArgKind::EngineerReal => {
+                        let (val, scale) = engineer_notation(state.read(val));
+                        vals.push(FmtVal::Real(val));
+                        vals.push(FmtVal::Int(scale as i32));
+                    }
+                    FmtArgKind::Other => {
+                        let val = match arg.ty {
+                            Type::Real => FmtVal::Real(state.read(val)),
+                            Type::Integer => FmtVal::Int(state.read(val)),
+                            Type::Bool => FmtVal::Int(state.read::<bool>(val) as i32),
+                            Type::String => FmtVal::Str(cx.resolve(state, val)),
+                            ref ty => unreachable!("{ty} can not be formatted"),
+                        };
+                        vals.push(val)
+                    }
+                }
+            }
+            let text = sprintf(&fmt, &vals);
+            cx.sim.messages.push(Message { kind, text })
+        }
+    }
+}
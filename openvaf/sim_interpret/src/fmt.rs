@@ -0,0 +1,274 @@
+//! A minimal implementation of the C `printf` format that is produced by
+//! `hir_lower` for `$display` and similar system tasks.
+
+use std::slice::Iter;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FmtVal {
+    Int(i32),
+    Real(f64),
+    Str(String),
+}
+
+const SCALE_CHARS: [char; 11] = ['a', 'f', 'p', 'n', 'u', 'm', ' ', 'k', 'M', 'G', 'T'];
+
+/// Splits `val` into a mantissa (between 1 and 1000) and the character of its SI prefix.
+pub(crate) fn engineer_notation(val: f64) -> (f64, char) {
+    if val == 0.0 || !val.is_finite() {
+        return (val, ' ');
+    }
+    let exp = (val.abs().log10() / 3.0).floor().clamp(-6.0, 4.0);
+    let scale = SCALE_CHARS[(exp as i32 + 6) as usize];
+    (val / 10f64.powi(3 * exp as i32), scale)
+}
+
+#[derive(Default)]
+struct Spec {
+    left_align: bool,
+    plus: bool,
+    space: bool,
+    zero_pad: bool,
+    alternate: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+pub(crate) fn sprintf(fmt: &str, args: &[FmtVal]) -> String {
+    let mut res = String::with_capacity(fmt.len());
+    let mut args = args.iter();
+
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            res.push(c);
+            continue;
+        }
+
+        let mut spec = Spec::default();
+        while let Some(&c) = chars.peek() {
+            match c {
+                '-' => spec.left_align = true,
+                '+' => spec.plus = true,
+                ' ' => spec.space = true,
+                '0' => spec.zero_pad = true,
+                '#' => spec.alternate = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        if chars.peek() == Some(&'*') {
+            chars.next();
+            let width = next_int(&mut args);
+            spec.left_align |= width < 0;
+            spec.width = width.unsigned_abs() as usize;
+        } else {
+            while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                spec.width = spec.width * 10 + digit as usize;
+                chars.next();
+            }
+        }
+
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            if chars.peek() == Some(&'*') {
+                chars.next();
+                spec.precision = usize::try_from(next_int(&mut args)).ok();
+            } else {
+                let mut precision = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    precision = precision * 10 + digit as usize;
+                    chars.next();
+                }
+                spec.precision = Some(precision);
+            }
+        }
+
+        // length modifiers are meaningless here
+        while matches!(chars.peek(), Some('l' | 'h')) {
+            chars.next();
+        }
+
+        let conversion = match chars.next() {
+            Some(c) => c,
+            None => {
+                res.push('%');
+                break;
+            }
+        };
+
+        let (sign, body) = match conversion {
+            '%' => {
+                res.push('%');
+                continue;
+            }
+            'd' | 'i' => {
+                let val = next_int(&mut args);
+                let mut body = val.unsigned_abs().to_string();
+                if let Some(precision) = spec.precision {
+                    spec.zero_pad = false;
+                    while body.len() < precision {
+                        body.insert(0, '0');
+                    }
+                }
+                (sign_prefix(&spec, val < 0), body)
+            }
+            'o' | 'x' | 'X' => {
+                let val = next_int(&mut args) as u32;
+                let body = match conversion {
+                    'o' if spec.alternate => format!("0{val:o}"),
+                    'o' => format!("{val:o}"),
+                    'x' if spec.alternate => format!("{val:#x}"),
+                    'x' => format!("{val:x}"),
+                    _ if spec.alternate => format!("0X{val:X}"),
+                    _ => format!("{val:X}"),
+                };
+                ("", body)
+            }
+            'c' => {
+                spec.zero_pad = false;
+                let val = next_int(&mut args);
+                ("", char::from_u32(val as u32).unwrap_or(char::REPLACEMENT_CHARACTER).to_string())
+            }
+            's' => {
+                spec.zero_pad = false;
+                let mut body = match args.next() {
+                    Some(FmtVal::Str(val)) => val.clone(),
+                    Some(FmtVal::Int(val)) => val.to_string(),
+                    Some(FmtVal::Real(val)) => val.to_string(),
+                    None => String::new(),
+                };
+                if let Some(precision) = spec.precision {
+                    body = body.chars().take(precision).collect();
+                }
+                ("", body)
+            }
+            'e' | 'E' | 'f' | 'F' | 'g' | 'G' => {
+                let val = match args.next() {
+                    Some(&FmtVal::Real(val)) => val,
+                    Some(&FmtVal::Int(val)) => val as f64,
+                    _ => 0.0,
+                };
+                let body = fmt_float(val.abs(), conversion, &spec);
+                if !val.is_finite() {
+                    spec.zero_pad = false;
+                }
+                (sign_prefix(&spec, val.is_sign_negative() && !val.is_nan()), body)
+            }
+            c => {
+                // unknown conversion, print unchanged
+                res.push('%');
+                res.push(c);
+                continue;
+            }
+        };
+
+        let len = sign.len() + body.chars().count();
+        let padding = spec.width.saturating_sub(len);
+        if spec.left_align {
+            res.push_str(sign);
+            res.push_str(&body);
+            res.extend((0..padding).map(|_| ' '));
+        } else if spec.zero_pad {
+            res.push_str(sign);
+            res.extend((0..padding).map(|_| '0'));
+            res.push_str(&body);
+        } else {
+            res.extend((0..padding).map(|_| ' '));
+            res.push_str(sign);
+            res.push_str(&body);
+        }
+    }
+    res
+}
+
+fn next_int(args: &mut Iter<'_, FmtVal>) -> i32 {
+    match args.next() {
+        Some(&FmtVal::Int(val)) => val,
+        Some(&FmtVal::Real(val)) => val as i32,
+        _ => 0,
+    }
+}
+
+fn sign_prefix(spec: &Spec, negative: bool) -> &'static str {
+    if negative {
+        "-"
+    } else if spec.plus {
+        "+"
+    } else if spec.space {
+        " "
+    } else {
+        ""
+    }
+}
+
+/// Formats a non negative float like C's `printf`.
+fn fmt_float(val: f64, conversion: char, spec: &Spec) -> String {
+    let upper = conversion.is_ascii_uppercase();
+    if !val.is_finite() {
+        let res = if val.is_nan() { "nan" } else { "inf" };
+        return if upper { res.to_uppercase() } else { res.to_owned() };
+    }
+
+    let precision = spec.precision.unwrap_or(6);
+    let res = match conversion.to_ascii_lowercase() {
+        'f' => {
+            let mut res = format!("{val:.precision$}");
+            if spec.alternate && precision == 0 {
+                res.push('.')
+            }
+            res
+        }
+        'e' => fmt_exp(val, precision, spec.alternate),
+        _ => {
+            let precision = precision.max(1);
+            // the exponent after rounding to the requested number of significant digits
+            let exp = if val == 0.0 {
+                0
+            } else {
+                let formatted = format!("{:.*e}", precision - 1, val);
+                formatted.split_once('e').unwrap().1.parse::<i32>().unwrap()
+            };
+            let mut res = if exp < -4 || exp >= precision as i32 {
+                fmt_exp(val, precision - 1, spec.alternate)
+            } else {
+                let precision = (precision as i32 - 1 - exp) as usize;
+                format!("{val:.precision$}")
+            };
+            if !spec.alternate {
+                res = strip_trailing_zeros(res);
+            }
+            res
+        }
+    };
+
+    if upper {
+        res.to_uppercase()
+    } else {
+        res
+    }
+}
+
+fn fmt_exp(val: f64, precision: usize, alternate: bool) -> String {
+    let formatted = format!("{val:.precision$e}");
+    let (mantissa, exp) = formatted.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let sign = if exp < 0 { '-' } else { '+' };
+    let dot = if alternate && precision == 0 { "." } else { "" };
+    format!("{mantissa}{dot}e{sign}{:02}", exp.abs())
+}
+
+fn strip_trailing_zeros(val: String) -> String {
+    let (mantissa, exp) = match val.find('e') {
+        Some(pos) => val.split_at(pos),
+        None => (val.as_str(), ""),
+    };
+    if !mantissa.contains('.') {
+        return val;
+    }
+    let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+    format!("{mantissa}{exp}")
+}
@@ -0,0 +1,36 @@
+use super::{engineer_notation, sprintf, FmtVal};
+
+#[test]
+fn integers() {
+    let args = [FmtVal::Int(42), FmtVal::Int(-7), FmtVal::Int(255), FmtVal::Int(8)];
+    assert_eq!(sprintf("%d %d %x %o", &args), "42 -7 ff 10");
+    assert_eq!(sprintf("[%5d|%-5d|%05d|%X]", &args), "[   42|-7   |00255|8]");
+    let args = [FmtVal::Int(6), FmtVal::Int(3)];
+    assert_eq!(sprintf("[%*d]%%", &args), "[     3]%");
+}
+
+#[test]
+fn reals() {
+    let args = [FmtVal::Real(1.5), FmtVal::Real(-0.000123), FmtVal::Real(1234567.0)];
+    assert_eq!(sprintf("%f %e %g", &args), "1.500000 -1.230000e-04 1.23457e+06");
+    let args = [FmtVal::Real(0.0001), FmtVal::Real(100.0), FmtVal::Real(2.5)];
+    assert_eq!(sprintf("%g %g %+.3e", &args), "0.0001 100 +2.500e+00");
+    let args = [FmtVal::Real(3.14359), FmtVal::Real(-2.0)];
+    assert_eq!(sprintf("%8.2f|%-8.1f|", &args), "    3.14|-2.0    |");
+}
+
+#[test]
+fn strings() {
+    let args = [FmtVal::Str("foo".to_owned()), FmtVal::Int(b'x' as i32)];
+    assert_eq!(sprintf("%5s|%c|%m", &args), "  foo|x|%m");
+}
+
+#[test]
+fn engineer() {
+    assert_eq!(engineer_notation(1.5e-9), (1.5, 'n'));
+    assert_eq!(engineer_notation(-47e3), (-47.0, 'k'));
+    assert_eq!(engineer_notation(0.0), (0.0, ' '));
+    let (val, scale) = engineer_notation(2.2e-6);
+    let args = [FmtVal::Real(val), FmtVal::Int(scale as i32)];
+    assert_eq!(sprintf("%.1f%c", &args), "2.2u");
+}
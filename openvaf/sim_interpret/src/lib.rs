@@ -0,0 +1,547 @@
+//! An interpreter based backend for [`CompiledModule`].
+//!
+//! The OSDI backend compiles a module to machine code with LLVM. This crate instead
+//! evaluates the MIR functions of a module (model setup, instance setup and eval) with
+//! [`mir_interpret`]. It is much slower but does not require an LLVM toolchain and
+//! serves as a reference implementation that results of the JIT/AOT backends can be
+//! checked against.
+
+use ahash::AHashMap;
+use bitset::BitSet;
+use hir::{ParamSysFun, Parameter};
+use hir_lower::fmt::DisplayKind;
+use hir_lower::{CurrentKind, HirInterner, LimitState, ParamKind, PlaceKind};
+use lasso::Rodeo;
+use mir::{Function, Param};
+use mir_interpret::{Interpreter, InterpreterState};
+use sim_back::dae::{MatrixEntryId, SimUnknown};
+use sim_back::init::CacheSlot;
+use sim_back::node_collapse::CollapsePair;
+use sim_back::{CompiledModule, SimUnknownKind};
+use typed_index_collections::{TiSlice, TiVec};
+
+pub use mir_interpret::Data;
+
+use crate::callbacks::{Callbacks, RunCx};
+pub use crate::lim::LimFunction;
+
+mod callbacks;
+mod fmt;
+pub mod lim;
+
+#[cfg(test)]
+mod tests;
+
+/// The environment that the simulator provides to the model:
+/// simulator parameters, limiting functions and the log.
+pub struct Simulator {
+    /// The interner that was used while compiling the module. All string
+    /// constants (and string parameters) are resolved with this interner.
+    pub literals: Rodeo,
+    /// Values returned by `$simparam`.
+    pub sim_params: AHashMap<String, f64>,
+    /// Values returned by `$simparam$str`.
+    pub sim_params_str: AHashMap<String, String>,
+    /// Functions that implement `$limit` calls with a builtin limiting function.
+    /// By default `pnjlim`, `fetlim` and `limvds` (as implemented by ngspice) are available.
+    pub lim_functions: AHashMap<String, LimFunction>,
+    /// Messages produced by `$display`, `$strobe`, `$warning` and similar
+    /// as well as fatal errors encountered by the callbacks.
+    pub messages: Vec<Message>,
+}
+
+impl Simulator {
+    pub fn new(literals: Rodeo) -> Simulator {
+        Simulator {
+            literals,
+            sim_params: AHashMap::default(),
+            sim_params_str: AHashMap::default(),
+            lim_functions: lim::builtin_functions(),
+            messages: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub kind: DisplayKind,
+    pub text: String,
+}
+
+/// Flags that indicate special conditions encountered while running
+/// one of the functions of a module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetFlags {
+    /// A `$limit` function changed its argument (or `$discontinuity(-1)` was called).
+    pub limited: bool,
+    /// A fatal error occurred (unknown `$simparam`, unknown limiting function).
+    pub fatal: bool,
+}
+
+/// The result of model or instance setup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetupResult {
+    /// Parameters whose values are outside of their allowed range.
+    pub invalid_params: Vec<Parameter>,
+    pub flags: RetFlags,
+}
+
+impl SetupResult {
+    pub fn is_ok(&self) -> bool {
+        self.invalid_params.is_empty() && !self.flags.fatal
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Analysis {
+    Dc,
+    Ac,
+    Noise,
+    Tran,
+    /// The initial operating point computation of a transient analysis.
+    Ic,
+}
+
+impl Analysis {
+    /// Whether `analysis(name)` returns true during this analysis.
+    pub fn matches(self, name: &str) -> bool {
+        match name {
+            "dc" => self == Analysis::Dc,
+            "ac" => self == Analysis::Ac,
+            "noise" => self == Analysis::Noise,
+            "tran" => self == Analysis::Tran,
+            "ic" => self == Analysis::Ic,
+            "static" => matches!(self, Analysis::Dc | Analysis::Ic),
+            _ => false,
+        }
+    }
+
+    fn enable_integration(self) -> bool {
+        !matches!(self, Analysis::Dc | Analysis::Ic)
+    }
+}
+
+/// The state of the simulation that `eval` is called with.
+#[derive(Debug, Clone, Copy)]
+pub struct SimInfo<'a> {
+    pub analysis: Analysis,
+    pub abstime: f64,
+    /// The solution of the last iteration indexed by [`SimUnknown`].
+    pub prev_solve: &'a [f64],
+    /// The limiting state of the last iteration indexed by [`LimitState`].
+    pub prev_state: &'a [f64],
+    pub enable_lim: bool,
+    /// Passed to limiting functions to indicate that this is the first iteration.
+    pub init_lim: bool,
+}
+
+/// A model with its parameters. Parameter values must have the type of the parameter
+/// (strings must be interned with [`Simulator::literals`]).
+#[derive(Clone, Default)]
+pub struct Model {
+    params: AHashMap<Parameter, Data>,
+    sys_fun_params: AHashMap<ParamSysFun, f64>,
+    /// Model parameters after [`Model::setup`] was called.
+    vals: AHashMap<Parameter, Data>,
+}
+
+impl Model {
+    pub fn new() -> Model {
+        Model::default()
+    }
+
+    pub fn set_param(&mut self, param: Parameter, val: impl Into<Data>) {
+        self.params.insert(param, val.into());
+    }
+
+    /// Sets the parameter named `name`, returns `false` if the module has no such parameter.
+    pub fn set_param_by_name(
+        &mut self,
+        module: &CompiledModule<'_>,
+        name: &str,
+        val: impl Into<Data>,
+    ) -> bool {
+        if let Some(param) = module.info.find_param(name) {
+            self.set_param(param, val);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Sets the default value of a builtin instance parameter like `$mfactor` for all instances.
+    pub fn set_sys_fun_param(&mut self, param: ParamSysFun, val: f64) {
+        self.sys_fun_params.insert(param, val);
+    }
+
+    pub fn is_given(&self, param: Parameter) -> bool {
+        self.params.contains_key(&param)
+    }
+
+    /// The value of a model parameter after setup. Instance parameters are only
+    /// available from [`Instance::param`].
+    pub fn param(&self, param: Parameter) -> Option<Data> {
+        self.vals.get(&param).copied()
+    }
+
+    /// Runs the `model_param_setup` function, which computes default
+    /// values and validates the model parameters.
+    pub fn setup(&mut self, module: &CompiledModule<'_>, sim: &mut Simulator) -> SetupResult {
+        let func = &module.model_param_setup;
+        let intern = &module.model_param_intern;
+        let args: TiVec<Param, Data> = intern
+            .params
+            .raw
+            .keys()
+            .map(|kind| match *kind {
+                ParamKind::Param(param) => self.params.get(&param).copied().unwrap_or(Data::UNDEF),
+                ParamKind::ParamGiven { param } => self.is_given(param).into(),
+                ParamKind::ParamSysFun(param) => self.sys_fun_param(param).into(),
+                ref kind => unreachable!("{kind:?} can not be used during model setup"),
+            })
+            .collect();
+
+        let mut cx = RunCx::new(sim, module, intern, None);
+        let state = run(func, intern, &args, &mut cx);
+
+        self.vals.clear();
+        for (param, info) in &module.info.params {
+            if info.is_instance {
+                continue;
+            }
+            let val = intern.outputs[&PlaceKind::Param(*param)].unwrap_unchecked();
+            self.vals.insert(*param, state.read(val));
+        }
+
+        let mut res = cx.finish_setup();
+        res.invalid_params.retain(|param| !module.info.params[param].is_instance);
+        res
+    }
+
+    fn sys_fun_param(&self, param: ParamSysFun) -> f64 {
+        self.sys_fun_params.get(&param).copied().unwrap_or_else(|| param.default_value())
+    }
+}
+
+/// An instance of a model. Instance parameters that are not given
+/// for the instance are taken from the model.
+#[derive(Clone)]
+pub struct Instance {
+    params: AHashMap<Parameter, Data>,
+    sys_fun_params: AHashMap<ParamSysFun, f64>,
+    // filled by setup
+    vals: AHashMap<Parameter, Data>,
+    cache: TiVec<CacheSlot, Data>,
+    collapsed: BitSet<CollapsePair>,
+    temperature: f64,
+    connected_terminals: u32,
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Instance {
+            params: AHashMap::default(),
+            sys_fun_params: AHashMap::default(),
+            vals: AHashMap::default(),
+            cache: TiVec::new(),
+            collapsed: BitSet::new_empty(0),
+            temperature: 0.0,
+            connected_terminals: 0,
+        }
+    }
+}
+
+impl Instance {
+    pub fn new() -> Instance {
+        Instance::default()
+    }
+
+    pub fn set_param(&mut self, param: Parameter, val: impl Into<Data>) {
+        self.params.insert(param, val.into());
+    }
+
+    /// Sets the parameter named `name`, returns `false` if the module has no such parameter.
+    pub fn set_param_by_name(
+        &mut self,
+        module: &CompiledModule<'_>,
+        name: &str,
+        val: impl Into<Data>,
+    ) -> bool {
+        if let Some(param) = module.info.find_param(name) {
+            self.set_param(param, val);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_sys_fun_param(&mut self, param: ParamSysFun, val: f64) {
+        self.sys_fun_params.insert(param, val);
+    }
+
+    /// The value of an instance parameter after setup.
+    pub fn param(&self, param: Parameter) -> Option<Data> {
+        self.vals.get(&param).copied()
+    }
+
+    /// Whether the nodes of `pair` (see [`sim_back::node_collapse::NodeCollapse::pairs`])
+    /// were collapsed during setup.
+    pub fn is_collapsed(&self, pair: CollapsePair) -> bool {
+        self.collapsed.contains(pair)
+    }
+
+    /// Runs the instance setup (`init`) function, which computes the instance parameters,
+    /// the operating point independent values cached for `eval` and the collapsed nodes.
+    /// The first `connected_terminals` ports of the module are connected.
+    pub fn setup(
+        &mut self,
+        module: &CompiledModule<'_>,
+        model: &Model,
+        temperature: f64,
+        connected_terminals: u32,
+        sim: &mut Simulator,
+    ) -> SetupResult {
+        let func = &module.init.func;
+        let intern = &module.init.intern;
+        let args: TiVec<Param, Data> = intern
+            .params
+            .raw
+            .keys()
+            .map(|kind| match *kind {
+                ParamKind::Param(param) => {
+                    if module.info.params[&param].is_instance {
+                        self.params
+                            .get(&param)
+                            .or_else(|| model.params.get(&param))
+                            .copied()
+                            .unwrap_or(Data::UNDEF)
+                    } else {
+                        model.vals[&param]
+                    }
+                }
+                ParamKind::ParamGiven { param } => {
+                    (self.params.contains_key(&param) || model.is_given(param)).into()
+                }
+                ParamKind::ParamSysFun(param) => self.sys_fun_param(model, param).into(),
+                ParamKind::Temperature => temperature.into(),
+                ParamKind::PortConnected { port } => {
+                    let idx =
+                        module.dae_system.unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(port));
+                    (u32::from(idx) < connected_terminals).into()
+                }
+                ref kind => unreachable!("{kind:?} can not be used during instance setup"),
+            })
+            .collect();
+
+        let mut cx = RunCx::new(sim, module, intern, None);
+        cx.collapsed = Some(BitSet::new_empty(module.node_collapse.num_pairs() as usize));
+        let state = run(func, intern, &args, &mut cx);
+
+        self.vals.clear();
+        for (param, info) in &module.info.params {
+            if !info.is_instance {
+                continue;
+            }
+            let val = intern.outputs[&PlaceKind::Param(*param)].unwrap_unchecked();
+            self.vals.insert(*param, state.read(val));
+        }
+
+        self.cache = vec![Data::UNDEF; module.init.cache_slots.len()].into();
+        for (&val, &slot) in &module.init.cached_vals {
+            self.cache[slot] = state.read(val);
+        }
+
+        for (&kind, val) in &intern.outputs {
+            if let PlaceKind::CollapseImplicitEquation(eq) = kind {
+                let collapse: Data = state.read(val.unwrap_unchecked());
+                if !collapse.is_undef() && collapse.bool() {
+                    let eq = module.dae_system.unknowns.unwrap_index(&SimUnknownKind::Implicit(eq));
+                    let collapsed = cx.collapsed.as_mut().unwrap();
+                    module.node_collapse.hint(eq, None, |pair| {
+                        collapsed.insert(pair);
+                    });
+                }
+            }
+        }
+
+        self.temperature = temperature;
+        self.connected_terminals = connected_terminals;
+        self.collapsed = cx.collapsed.take().unwrap();
+        let mut res = cx.finish_setup();
+        res.invalid_params.retain(|param| module.info.params[param].is_instance);
+        res
+    }
+
+    /// Evaluates the `eval` function of the module at the operating point `sim_info.prev_solve`.
+    /// [`Instance::setup`] must have been called before.
+    pub fn eval(
+        &self,
+        module: &CompiledModule<'_>,
+        model: &Model,
+        sim_info: &SimInfo<'_>,
+        sim: &mut Simulator,
+    ) -> EvalResult {
+        let func = &module.eval;
+        let intern = &module.intern;
+        let unknowns = &module.dae_system.unknowns;
+        let prev_solve = TiSlice::<SimUnknown, f64>::from_ref(sim_info.prev_solve);
+        let prev_state = TiSlice::<LimitState, f64>::from_ref(sim_info.prev_state);
+        let voltage = |node| {
+            unknowns.index(&SimUnknownKind::KirchoffLaw(node)).map_or(0.0, |node| prev_solve[node])
+        };
+        let unknown = |kind| unknowns.index(&kind).map_or(0.0, |unknown| prev_solve[unknown]);
+
+        let mut args: TiVec<Param, Data> = intern
+            .params
+            .raw
+            .keys()
+            .map(|kind| match *kind {
+                ParamKind::Param(param) => {
+                    self.vals.get(&param).or_else(|| model.vals.get(&param)).copied().unwrap()
+                }
+                ParamKind::ParamGiven { param } => {
+                    (self.params.contains_key(&param) || model.is_given(param)).into()
+                }
+                ParamKind::Voltage { hi, lo } => (voltage(hi) - lo.map_or(0.0, voltage)).into(),
+                ParamKind::Current(CurrentKind::Port(_)) => 0.0.into(),
+                ParamKind::Current(kind) => unknown(SimUnknownKind::Current(kind)).into(),
+                ParamKind::ImplicitUnknown(eq) => unknown(SimUnknownKind::Implicit(eq)).into(),
+                ParamKind::Abstime => sim_info.abstime.into(),
+                ParamKind::Temperature => self.temperature.into(),
+                ParamKind::EnableIntegration => sim_info.analysis.enable_integration().into(),
+                ParamKind::EnableLim => sim_info.enable_lim.into(),
+                // the new state is updated by the StoreLimit callback
+                ParamKind::PrevState(state) | ParamKind::NewState(state) => {
+                    prev_state[state].into()
+                }
+                ParamKind::ParamSysFun(param) => self.sys_fun_param(model, param).into(),
+                ParamKind::PortConnected { port } => {
+                    let idx = unknowns.unwrap_index(&SimUnknownKind::KirchoffLaw(port));
+                    (u32::from(idx) < self.connected_terminals).into()
+                }
+                ParamKind::HiddenState(_) => unreachable!("hidden state is not supported"),
+            })
+            .collect();
+        args.extend(self.cache.iter().copied());
+
+        let mut cx = RunCx::new(sim, module, intern, Some(sim_info));
+        let state = run(func, intern, &args, &mut cx);
+        let read = |val| state.read::<f64>(val);
+
+        let residual = module
+            .dae_system
+            .residual
+            .iter()
+            .map(|residual| ResidualValues {
+                resist: read(residual.resist),
+                react: read(residual.react),
+                resist_lim_rhs: read(residual.resist_lim_rhs),
+                react_lim_rhs: read(residual.react_lim_rhs),
+            })
+            .collect();
+        let jacobian = module
+            .dae_system
+            .jacobian
+            .iter()
+            .map(|entry| (read(entry.resist), read(entry.react)))
+            .collect();
+        let sensitivities = module
+            .dae_system
+            .sensitivities
+            .iter()
+            .map(|entry| (read(entry.resist), read(entry.react)))
+            .collect();
+        let opvars = module
+            .info
+            .op_vars
+            .keys()
+            .map(|var| state.read(intern.outputs[&PlaceKind::Var(*var)].unwrap_unchecked()))
+            .collect();
+        let bound_step = intern
+            .outputs
+            .get(&PlaceKind::BoundStep)
+            .and_then(|val| val.expand())
+            .map(|val| state.read::<Data>(val))
+            .filter(|val| !val.is_undef())
+            .map(Data::f64);
+
+        EvalResult {
+            residual,
+            jacobian,
+            sensitivities,
+            opvars,
+            bound_step,
+            next_state: cx.next_state,
+            flags: cx.flags,
+        }
+    }
+
+    fn sys_fun_param(&self, model: &Model, param: ParamSysFun) -> f64 {
+        self.sys_fun_params.get(&param).copied().unwrap_or_else(|| model.sys_fun_param(param))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ResidualValues {
+    pub resist: f64,
+    pub react: f64,
+    pub resist_lim_rhs: f64,
+    pub react_lim_rhs: f64,
+}
+
+/// The outputs of a single `eval` call.
+#[derive(Clone)]
+pub struct EvalResult {
+    pub residual: TiVec<SimUnknown, ResidualValues>,
+    /// Resistive and reactive part of each entry in [`sim_back::dae::DaeSystem::jacobian`].
+    pub jacobian: TiVec<MatrixEntryId, (f64, f64)>,
+    /// Resistive and reactive part of each entry in [`sim_back::dae::DaeSystem::sensitivities`].
+    pub sensitivities: Vec<(f64, f64)>,
+    /// The values of the operating point variables (in the order of `ModuleInfo::op_vars`).
+    pub opvars: Vec<Data>,
+    pub bound_step: Option<f64>,
+    /// The limiting state that must be passed as `prev_state` to the next iteration.
+    pub next_state: TiVec<LimitState, f64>,
+    pub flags: RetFlags,
+}
+
+impl EvalResult {
+    /// Returns the resistive and reactive residual of `unknown`.
+    pub fn residual(&self, module: &CompiledModule<'_>, unknown: SimUnknownKind) -> (f64, f64) {
+        module.dae_system.unknowns.index(&unknown).map_or((0.0, 0.0), |unknown| {
+            (self.residual[unknown].resist, self.residual[unknown].react)
+        })
+    }
+
+    /// Returns the resistive and reactive derivative of the residual of `row` by `col`.
+    pub fn jacobian(
+        &self,
+        module: &CompiledModule<'_>,
+        row: SimUnknownKind,
+        col: SimUnknownKind,
+    ) -> (f64, f64) {
+        let unknowns = &module.dae_system.unknowns;
+        let (row, col) = match (unknowns.index(&row), unknowns.index(&col)) {
+            (Some(row), Some(col)) => (row, col),
+            _ => return (0.0, 0.0),
+        };
+        module
+            .dae_system
+            .jacobian
+            .iter_enumerated()
+            .find(|(_, entry)| entry.row == row && entry.col == col)
+            .map_or((0.0, 0.0), |(id, _)| self.jacobian[id])
+    }
+}
+
+fn run(
+    func: &Function,
+    intern: &HirInterner,
+    args: &TiSlice<Param, Data>,
+    cx: &mut RunCx<'_>,
+) -> InterpreterState {
+    let callbacks = Callbacks::new(intern, cx);
+    let mut interpreter = Interpreter::new(func, callbacks.table(), args);
+    interpreter.run();
+    interpreter.state
+}
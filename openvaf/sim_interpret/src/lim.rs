@@ -0,0 +1,128 @@
+//! The limiting functions that are commonly provided by simulators for `$limit`.
+//! The implementations follow ngspice.
+
+use ahash::AHashMap;
+
+/// A limiting function called for `$limit(access, "name", args...)`. Receives whether this is
+/// the first iteration, the new and old value of the limited quantity and the extra arguments.
+/// Returns the limited value and whether limiting changed the value.
+pub type LimFunction = fn(init: bool, vnew: f64, vold: f64, args: &[f64]) -> (f64, bool);
+
+pub(crate) fn builtin_functions() -> AHashMap<String, LimFunction> {
+    let mut res = AHashMap::default();
+    res.insert("pnjlim".to_owned(), pnjlim as LimFunction);
+    res.insert("fetlim".to_owned(), fetlim as LimFunction);
+    res.insert("limvds".to_owned(), limvds as LimFunction);
+    res
+}
+
+/// Limits the voltage across a pn junction, expects the arguments `vt` and `vcrit`.
+pub fn pnjlim(init: bool, mut vnew: f64, vold: f64, args: &[f64]) -> (f64, bool) {
+    let (vt, vcrit) = (args[0], args[1]);
+    if init {
+        return (vcrit, true);
+    }
+
+    if vnew > vcrit && (vnew - vold).abs() > vt + vt {
+        if vold > 0.0 {
+            let arg = 1.0 + (vnew - vold) / vt;
+            vnew = if arg > 0.0 { vold + vt * arg.ln() } else { vcrit };
+        } else {
+            vnew = vt * (vnew / vt).ln();
+        }
+        (vnew, true)
+    } else if vnew < 0.0 {
+        let arg = if vold > 0.0 { -vold - 1.0 } else { 2.0 * vold - 1.0 };
+        if vnew < arg {
+            (arg, true)
+        } else {
+            (vnew, false)
+        }
+    } else {
+        (vnew, false)
+    }
+}
+
+/// Limits the change of a fet gate voltage, expects the threshold voltage as argument.
+pub fn fetlim(init: bool, vnew: f64, vold: f64, args: &[f64]) -> (f64, bool) {
+    let vto = args[0];
+    if init {
+        return (vto + 0.5, true);
+    }
+
+    let vtsthi = (2.0 * (vold - vto)).abs() + 2.0;
+    let vtstlo = (vold - vto).abs() + 1.0;
+    let vtox = vto + 3.5;
+    let delv = vnew - vold;
+
+    let res = if vold >= vto {
+        if vold >= vtox {
+            if delv <= 0.0 {
+                // going off
+                if vnew >= vtox {
+                    if -delv > vtstlo {
+                        vold - vtstlo
+                    } else {
+                        vnew
+                    }
+                } else {
+                    vnew.max(vto + 2.0)
+                }
+            } else if delv >= vtsthi {
+                // staying on
+                vold + vtsthi
+            } else {
+                vnew
+            }
+        } else if delv <= 0.0 {
+            // middle region, decreasing
+            vnew.max(vto - 0.5)
+        } else {
+            // middle region, increasing
+            vnew.min(vto + 4.0)
+        }
+    } else if delv <= 0.0 {
+        // off
+        if -delv > vtsthi {
+            vold - vtsthi
+        } else {
+            vnew
+        }
+    } else {
+        let vtemp = vto + 0.5;
+        if vnew <= vtemp {
+            if delv > vtstlo {
+                vold + vtstlo
+            } else {
+                vnew
+            }
+        } else {
+            vtemp
+        }
+    };
+
+    (res, res != vnew)
+}
+
+/// Limits the change of a fet drain-source voltage.
+pub fn limvds(init: bool, vnew: f64, vold: f64, _args: &[f64]) -> (f64, bool) {
+    if init {
+        return (0.1, true);
+    }
+
+    let res = if vold >= 3.5 {
+        if vnew > vold {
+            vnew.min(3.0 * vold + 2.0)
+        } else if vnew < 3.5 {
+            vnew.max(2.0)
+        } else {
+            vnew
+        }
+    } else if vnew > vold {
+        vnew.min(4.0)
+    } else {
+        vnew.max(-0.5)
+    };
+
+    (res, res != vnew)
+}
@@ -0,0 +1,209 @@
+use float_cmp::assert_approx_eq;
+use hir::diagnostics::ConsoleSink;
+use hir::CompilationDB;
+use hir_lower::fmt::DisplayKind;
+use indoc::indoc;
+use lasso::Rodeo;
+use sim_back::{collect_modules, CompiledModule, SimUnknownKind};
+
+use crate::{Analysis, Instance, Message, Model, SimInfo, Simulator};
+
+fn with_module(src: &str, f: impl FnOnce(&CompilationDB, &CompiledModule<'_>, &mut Simulator)) {
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let compiled = CompiledModule::new(&db, &module, &mut literals);
+    let mut sim = Simulator::new(literals);
+    f(&db, &compiled, &mut sim)
+}
+
+fn node(db: &CompilationDB, module: &CompiledModule<'_>, name: &str) -> SimUnknownKind {
+    module
+        .dae_system
+        .unknowns
+        .iter()
+        .copied()
+        .find(
+            |unknown| matches!(unknown, SimUnknownKind::KirchoffLaw(node) if node.name(db) == name),
+        )
+        .unwrap()
+}
+
+fn solve(db: &CompilationDB, module: &CompiledModule<'_>, voltages: &[(&str, f64)]) -> Vec<f64> {
+    let mut res = vec![0.0; module.dae_system.unknowns.len()];
+    for &(name, voltage) in voltages {
+        res[usize::from(module.dae_system.unknowns.unwrap_index(&node(db, module, name)))] =
+            voltage;
+    }
+    res
+}
+
+fn dc(prev_solve: &[f64]) -> SimInfo<'_> {
+    SimInfo {
+        analysis: Analysis::Dc,
+        abstime: 0.0,
+        prev_solve,
+        prev_state: &[],
+        enable_lim: false,
+        init_lim: false,
+    }
+}
+
+#[test]
+fn resistor() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module resistor(a, b);
+            inout a, b;
+            electrical a, b;
+            parameter real r = 1.0 from (0:inf);
+            parameter real tnom = 300.0;
+            parameter real zeta = 1.0;
+            analog I(a, b) <+ V(a, b) / (r * pow($temperature / tnom, zeta));
+        endmodule
+    "#};
+    with_module(src, |db, module, sim| {
+        let mut model = Model::new();
+        assert!(model.set_param_by_name(module, "r", 2.0));
+        assert!(!model.set_param_by_name(module, "foo", 2.0));
+        assert!(model.setup(module, sim).is_ok());
+        assert_approx_eq!(
+            f64,
+            model.param(module.info.find_param("tnom").unwrap()).unwrap().f64(),
+            300.0
+        );
+
+        let mut instance = Instance::new();
+        assert!(instance.setup(module, &model, 600.0, 2, sim).is_ok());
+        let prev_solve = solve(db, module, &[("a", 1.0), ("b", 0.2)]);
+        let res = instance.eval(module, &model, &dc(&prev_solve), sim);
+
+        let (a, b) = (node(db, module, "a"), node(db, module, "b"));
+        assert_approx_eq!(f64, res.residual(module, a).0, 0.2);
+        assert_approx_eq!(f64, res.residual(module, b).0, -0.2);
+        assert_approx_eq!(f64, res.jacobian(module, a, a).0, 0.25);
+        assert_approx_eq!(f64, res.jacobian(module, a, b).0, -0.25);
+        assert_approx_eq!(f64, res.jacobian(module, b, b).0, 0.25);
+        assert_eq!(res.flags, Default::default());
+
+        let mut model = Model::new();
+        model.set_param_by_name(module, "r", -1.0);
+        let res = model.setup(module, sim);
+        assert_eq!(res.invalid_params, vec![module.info.find_param("r").unwrap()]);
+    })
+}
+
+#[test]
+fn limit() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module diode(a, c);
+            inout a, c;
+            electrical a, c;
+            parameter real is = 1e-14 from (0:inf);
+            real vd, vt, vcrit;
+            analog begin
+                vt = 0.025;
+                vcrit = vt * ln(vt / (1.4142135623730951 * is));
+                vd = $limit(V(a, c), "pnjlim", vt, vcrit);
+                I(a, c) <+ is * (exp(vd / vt) - 1);
+            end
+        endmodule
+    "#};
+    with_module(src, |db, module, sim| {
+        let mut model = Model::new();
+        assert!(model.setup(module, sim).is_ok());
+        let mut instance = Instance::new();
+        assert!(instance.setup(module, &model, 300.0, 2, sim).is_ok());
+
+        let is: f64 = 1e-14;
+        let vt = 0.025;
+        let vcrit = vt * (vt / (2f64.sqrt() * is)).ln();
+        let a = node(db, module, "a");
+        let prev_solve = solve(db, module, &[("a", 0.9)]);
+        let prev_state = [0.0];
+
+        // without limiting the voltage is used unchanged
+        let sim_info = SimInfo { prev_state: &prev_state, ..dc(&prev_solve) };
+        let res = instance.eval(module, &model, &sim_info, sim);
+        let expected = is * ((0.9 / vt).exp() - 1.0);
+        assert_approx_eq!(f64, res.residual(module, a).0, expected, ulps = 32);
+        assert!(!res.flags.limited);
+
+        let sim_info = SimInfo {
+            enable_lim: true,
+            init_lim: true,
+            prev_state: &prev_state,
+            ..dc(&prev_solve)
+        };
+        let res = instance.eval(module, &model, &sim_info, sim);
+        assert!(res.flags.limited);
+        assert_approx_eq!(f64, res.next_state.raw[0], vcrit);
+        assert_approx_eq!(
+            f64,
+            res.residual(module, a).0,
+            is * ((vcrit / vt).exp() - 1.0),
+            ulps = 32
+        );
+        let res_a = res.residual[module.dae_system.unknowns.unwrap_index(&a)];
+        let conductance = is / vt * (vcrit / vt).exp();
+        assert_approx_eq!(f64, res_a.resist_lim_rhs, conductance * (vcrit - 0.9), epsilon = 1e-15);
+
+        // the new state is passed to the next iteration
+        let next_state = res.next_state.raw.clone();
+        let sim_info = SimInfo { enable_lim: true, prev_state: &next_state, ..dc(&prev_solve) };
+        let res = instance.eval(module, &model, &sim_info, sim);
+        assert!(res.flags.limited);
+        assert!(res.next_state.raw[0] > vcrit && res.next_state.raw[0] < 0.9);
+    })
+}
+
+#[test]
+fn callbacks() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module callbacks(a, c);
+            inout a, c;
+            electrical a, c;
+            real g;
+            analog begin
+                g = $simparam("gmin");
+                if (analysis("tran"))
+                    g = 2 * g;
+                $display("V=%g g=%e", V(a, c), g);
+                I(a, c) <+ g * V(a, c);
+            end
+        endmodule
+    "#};
+    with_module(src, |db, module, sim| {
+        let mut model = Model::new();
+        assert!(model.setup(module, sim).is_ok());
+        let mut instance = Instance::new();
+        assert!(instance.setup(module, &model, 300.0, 2, sim).is_ok());
+
+        let a = node(db, module, "a");
+        let prev_solve = solve(db, module, &[("a", 2.0)]);
+        sim.sim_params.insert("gmin".to_owned(), 1e-3);
+        let res = instance.eval(module, &model, &dc(&prev_solve), sim);
+        assert_approx_eq!(f64, res.residual(module, a).0, 2e-3);
+        let sim_info = SimInfo { analysis: Analysis::Tran, ..dc(&prev_solve) };
+        let res = instance.eval(module, &model, &sim_info, sim);
+        assert_approx_eq!(f64, res.residual(module, a).0, 4e-3);
+        assert_eq!(
+            sim.messages,
+            vec![
+                Message { kind: DisplayKind::Display, text: "V=2 g=1.000000e-03\n".to_owned() },
+                Message { kind: DisplayKind::Display, text: "V=2 g=2.000000e-03\n".to_owned() },
+            ]
+        );
+
+        sim.messages.clear();
+        sim.sim_params.clear();
+        let res = instance.eval(module, &model, &dc(&prev_solve), sim);
+        assert!(res.flags.fatal);
+        assert_eq!(
+            sim.messages[0],
+            Message { kind: DisplayKind::Fatal, text: "unknown $simparam gmin".to_owned() }
+        );
+    })
+}
@@ -275,8 +275,15 @@ impl ast::SiRealNumber {
 }
 
 impl ast::IntNumber {
+    /// The value of this literal. Literals that do not fit into a 32-bit integer
+    /// saturate to `i32::MAX`.
     pub fn value(&self) -> i32 {
-        self.syntax.text().parse().unwrap()
+        self.try_value().unwrap_or(i32::MAX)
+    }
+
+    /// The value of this literal or `None` if it does not fit into a 32-bit integer.
+    pub fn try_value(&self) -> Option<i32> {
+        self.syntax.text().parse().ok()
     }
 }
 
@@ -199,6 +199,19 @@ impl SourceFile {
 
         Parse::new(green, errors, ctx_map)
     }
+
+    /// Parses `text` without running the preprocessor. Compiler directives become trivia
+    /// (`COMMENT` tokens starting with a backtick) and macro references are parsed as
+    /// identifiers. The returned tree contains the complete text of the file which makes
+    /// it suitable for source transformations like formatting.
+    ///
+    /// The returned ranges point to the tokens where syntax errors were encountered.
+    pub fn parse_unpreprocessed(text: &str) -> (SyntaxNode, Vec<TextRange>) {
+        let (green, errors) = parsing::parse_raw(text);
+        let root = SyntaxNode::new_root(green);
+        assert_eq!(root.kind(), SyntaxKind::SOURCE_FILE);
+        (root, errors)
+    }
 }
 
 /// Matches a `SyntaxNode` against an `ast` type.
@@ -1,3 +1,4 @@
+mod raw;
 mod tree_builder;
 
 use ::preprocessor::sourcemap::SourceContext;
@@ -9,6 +10,8 @@ use crate::parsing::tree_builder::SyntaxTreeBuilder;
 use crate::syntax_node::GreenNode;
 use crate::SyntaxError;
 
+pub(crate) use raw::parse_raw;
+
 pub(crate) fn parse_text(
     sources: &dyn SourceProvider,
     root_file: FileId,
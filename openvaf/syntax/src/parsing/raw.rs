@@ -0,0 +1,499 @@
+//! Parsing of a single file without running the preprocessor first.
+//!
+//! The normal parser operates on preprocessed tokens which can not be mapped back to the
+//! original source text in a lossless manner (macros are expanded, includes are inlined and
+//! directives are removed). Tools like the formatter require a syntax tree that contains
+//! every byte of the file instead. To that end compiler directives are turned into trivia and
+//! macro references are treated as identifiers.
+//!
+//! Compact models commonly use macros in ways that can not be parsed without expansion.
+//! A couple of heuristics cover the common cases:
+//!
+//! * Macro calls that start a line and are not followed by an operator (usually parameter
+//!   declarations or statements) are turned into trivia. If the macro call is the body of a
+//!   statement (like `if (cond) `MACRO(args)`) it is followed by an empty statement that does
+//!   not contain any text.
+//! * Macro calls that directly follow an operand (usually attributes) are turned into trivia.
+//! * Conditional compilation is evaluated using the macros defined within the file (macros
+//!   from included files or the command line are unknown). Only the branch that is active is
+//!   parsed, the other branches are trivia.
+//!
+//! Files that use macros differently produce syntax errors.
+
+use std::mem;
+
+use ahash::AHashSet;
+use rowan::{GreenNodeBuilder, Language};
+use tokens::lexer::{Token, TokenKind};
+
+use crate::syntax_node::{GreenNode, VerilogALanguage};
+use crate::{SyntaxKind, TextRange, TextSize};
+
+/// The directives that consume the remainder of their line.
+/// All other backticked identifiers are macro references.
+const DIRECTIVES: [&str; 22] = [
+    "begin_keywords",
+    "celldefine",
+    "default_discipline",
+    "default_nettype",
+    "default_transition",
+    "define",
+    "else",
+    "elsif",
+    "end_keywords",
+    "endcelldefine",
+    "endif",
+    "ifdef",
+    "ifndef",
+    "include",
+    "line",
+    "nounconnected_drive",
+    "pragma",
+    "resetall",
+    "timescale",
+    "unconnected_drive",
+    "undef",
+    "undefineall",
+];
+
+pub(crate) fn parse_raw(text: &str) -> (GreenNode, Vec<TextRange>) {
+    let (tokens, mut errors) = RawLexer::new(text).lex();
+    let parser_tokens: Vec<_> =
+        tokens.iter().filter(|(kind, _)| !kind.is_trivia()).map(|(kind, _)| *kind).collect();
+
+    let mut builder = RawTreeBuilder {
+        text,
+        tokens: &tokens,
+        token_pos: 0,
+        state: State::PendingStart,
+        inner: GreenNodeBuilder::new(),
+        errors: &mut errors,
+    };
+    for step in parser::parse(&parser_tokens).iter() {
+        match step {
+            parser::Step::Token { kind } => builder.token(kind),
+            parser::Step::Enter { kind } => builder.start_node(kind),
+            parser::Step::Exit => builder.finish_node(),
+            parser::Step::Error { .. } => builder.error(),
+        }
+    }
+    let green = builder.finish();
+    errors.sort_by_key(|range| range.start());
+    errors.dedup();
+    (green, errors)
+}
+
+struct RawLexer<'a> {
+    text: &'a str,
+    raw_tokens: Vec<Token>,
+    ranges: Vec<TextRange>,
+    tokens: Vec<(SyntaxKind, TextRange)>,
+    errors: Vec<TextRange>,
+    defines: AHashSet<&'a str>,
+    /// Whether a branch was already taken for each enclosing `` `ifdef ``
+    conditionals: Vec<bool>,
+}
+
+impl<'a> RawLexer<'a> {
+    fn new(text: &'a str) -> RawLexer<'a> {
+        let raw_tokens = lexer::tokenize(text);
+        let mut ranges = Vec::with_capacity(raw_tokens.len());
+        let mut pos = TextSize::from(0);
+        for token in &raw_tokens {
+            ranges.push(TextRange::at(pos, token.len));
+            pos += token.len;
+        }
+        RawLexer {
+            text,
+            tokens: Vec::with_capacity(raw_tokens.len()),
+            raw_tokens,
+            ranges,
+            errors: Vec::new(),
+            defines: AHashSet::default(),
+            conditionals: Vec::new(),
+        }
+    }
+
+    fn lex(mut self) -> (Vec<(SyntaxKind, TextRange)>, Vec<TextRange>) {
+        let mut i = 0;
+        while i < self.raw_tokens.len() {
+            let range = self.ranges[i];
+            let src = &self.text[range];
+            let mut is_stmt_body = false;
+            let end = match self.raw_tokens[i].kind {
+                TokenKind::Define { end } => {
+                    if let Some(name) = self.directive_arg(i) {
+                        self.defines.insert(name);
+                    }
+                    end
+                }
+                // the preprocessor reports unterminated defines, here we simply consume the
+                // rest of the line
+                TokenKind::IllegalDefine => self.line_end(i),
+                TokenKind::CompilerDirective if DIRECTIVES.contains(&&src[1..]) => {
+                    self.directive(i, &src[1..])
+                }
+                TokenKind::CompilerDirective => {
+                    let end = self.macro_call_end(i);
+                    let next = self.next_significant(end);
+                    let is_stmt =
+                        (self.starts_line() || self.in_stmt_position()) && !continues_expr(next);
+                    let is_attr = self.follows_operand()
+                        && matches!(next, Some(TokenKind::Semi | TokenKind::Comma));
+                    if is_stmt || is_attr {
+                        is_stmt_body = is_stmt && self.requires_stmt();
+                        end
+                    } else {
+                        self.tokens.push((SyntaxKind::IDENT, range));
+                        i += 1;
+                        continue;
+                    }
+                }
+                kind => {
+                    let (syntax_kind, error) = kind.to_syntax(src);
+                    if error.is_some() {
+                        self.errors.push(range);
+                    }
+                    if let Some(syntax_kind) = syntax_kind {
+                        self.tokens.push((syntax_kind, range));
+                    }
+                    i += 1;
+                    continue;
+                }
+            };
+
+            // trailing whitespace is kept separate so that line breaks remain visible
+            let mut end = end;
+            while end > i + 1 && self.raw_tokens[end - 1].kind == TokenKind::Whitespace {
+                end -= 1;
+            }
+            let range = range.cover(self.ranges[end - 1]);
+            self.tokens.push((SyntaxKind::COMMENT, range));
+            if is_stmt_body {
+                self.tokens.push((SyntaxKind::SEMICOLON, TextRange::empty(range.end())));
+            }
+            i = end;
+        }
+
+        (self.tokens, self.errors)
+    }
+
+    /// Handles the directive `name` at `start` and returns the index of the first token
+    /// that is not part of the resulting trivia.
+    fn directive(&mut self, start: usize, name: &str) -> usize {
+        match name {
+            "undef" => {
+                if let Some(name) = self.directive_arg(start) {
+                    self.defines.remove(name);
+                }
+            }
+            "ifdef" | "ifndef" => {
+                let defined =
+                    self.directive_arg(start).map_or(false, |it| self.defines.contains(it));
+                let active = defined == (name == "ifdef");
+                self.conditionals.push(active);
+                if !active {
+                    return self.next_branch(start);
+                }
+            }
+            "elsif" | "else" => {
+                match self.conditionals.last().copied() {
+                    Some(false) => (),
+                    // a previous branch was active, skip all remaining branches
+                    Some(true) => {
+                        self.conditionals.pop();
+                        let endif = self.conditional_end(start);
+                        return self.line_end(endif);
+                    }
+                    // the preprocessor reports the unmatched directive
+                    None => return self.line_end(start),
+                }
+                let active = name == "else"
+                    || self.directive_arg(start).map_or(false, |it| self.defines.contains(it));
+                if !active {
+                    return self.next_branch(start);
+                }
+                if let Some(taken) = self.conditionals.last_mut() {
+                    *taken = true;
+                }
+            }
+            "endif" => {
+                self.conditionals.pop();
+            }
+            _ => (),
+        }
+        self.line_end(start)
+    }
+
+    /// Returns the first identifier after the directive at `start`.
+    fn directive_arg(&self, start: usize) -> Option<&'a str> {
+        let text = self.text;
+        let (token, range) = self.raw_tokens[start + 1..]
+            .iter()
+            .zip(&self.ranges[start + 1..])
+            .find(|(token, _)| token.kind != TokenKind::Whitespace)?;
+        matches!(token.kind, TokenKind::SimpleIdent | TokenKind::EscapedIdent)
+            .then(|| &text[*range])
+    }
+
+    /// Returns the index of the first token after the line that contains the directive at
+    /// `start`.
+    fn line_end(&self, start: usize) -> usize {
+        let mut end = start + 1;
+        while end < self.raw_tokens.len() {
+            if self.raw_tokens[end].kind == TokenKind::Whitespace {
+                let src = &self.text[self.ranges[end]];
+                // escaped newlines continue the directive
+                if src.contains('\n') && !src.starts_with('\\') {
+                    break;
+                }
+            }
+            end += 1;
+        }
+        end
+    }
+
+    /// Returns the index of the next `` `elsif ``, `` `else `` or `` `endif `` that belongs to
+    /// the same conditional as the directive at `start`.
+    fn next_branch(&self, start: usize) -> usize {
+        self.find_conditional(start, &["elsif", "else", "endif"])
+    }
+
+    /// Returns the index of the `` `endif `` that closes the conditional containing the
+    /// directive at `start`.
+    fn conditional_end(&self, start: usize) -> usize {
+        self.find_conditional(start, &["endif"])
+    }
+
+    fn find_conditional(&self, start: usize, directives: &[&str]) -> usize {
+        let mut depth = 0u32;
+        for i in start + 1..self.raw_tokens.len() {
+            if self.raw_tokens[i].kind != TokenKind::CompilerDirective {
+                continue;
+            }
+            let name = &self.text[self.ranges[i]][1..];
+            if depth == 0 && directives.contains(&name) {
+                return i;
+            }
+            match name {
+                "ifdef" | "ifndef" => depth += 1,
+                "endif" => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+        }
+        // the preprocessor reports the missing `endif
+        self.raw_tokens.len()
+    }
+
+    /// Returns the index of the first token after the macro reference at `start`
+    /// including its arguments.
+    fn macro_call_end(&self, start: usize) -> usize {
+        if self.raw_tokens.get(start + 1).map(|token| token.kind) != Some(TokenKind::OpenParen) {
+            return start + 1;
+        }
+        let mut depth = 0u32;
+        for (i, token) in self.raw_tokens.iter().enumerate().skip(start + 1) {
+            match token.kind {
+                TokenKind::OpenParen => depth += 1,
+                TokenKind::CloseParen if depth == 1 => return i + 1,
+                TokenKind::CloseParen => depth -= 1,
+                _ => (),
+            }
+        }
+        self.raw_tokens.len()
+    }
+
+    /// Returns the kind of the first token after `start` that is not trivia or a macro call.
+    fn next_significant(&self, mut start: usize) -> Option<TokenKind> {
+        while let Some(token) = self.raw_tokens.get(start) {
+            match token.kind {
+                TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment { .. } => {
+                    start += 1
+                }
+                TokenKind::CompilerDirective
+                    if !DIRECTIVES.contains(&&self.text[self.ranges[start]][1..]) =>
+                {
+                    start = self.macro_call_end(start)
+                }
+                kind => return Some(kind),
+            }
+        }
+        None
+    }
+
+    fn starts_line(&self) -> bool {
+        match self.tokens.last() {
+            Some(&(SyntaxKind::WHITESPACE, range)) => {
+                self.text[range].contains('\n') || self.tokens.len() == 1
+            }
+            Some(_) => false,
+            None => true,
+        }
+    }
+
+    fn prev_significant(&self) -> Option<SyntaxKind> {
+        self.tokens.iter().rev().map(|(kind, _)| *kind).find(|kind| !kind.is_trivia())
+    }
+
+    /// Whether a statement or declaration can start at the current position.
+    fn in_stmt_position(&self) -> bool {
+        matches!(
+            self.prev_significant(),
+            None | Some(
+                SyntaxKind::SEMICOLON
+                    | SyntaxKind::R_PAREN
+                    | SyntaxKind::BEGIN_KW
+                    | SyntaxKind::END_KW
+                    | SyntaxKind::ELSE_KW
+                    | SyntaxKind::ENDCASE_KW
+                    | SyntaxKind::ENDFUNCTION_KW
+                    | SyntaxKind::ENDMODULE_KW
+                    | SyntaxKind::ENDNATURE_KW
+                    | SyntaxKind::ENDDISCIPLINE_KW
+            )
+        )
+    }
+
+    /// Whether a statement must follow the current position (the body of an `if`, `else`,
+    /// loop or event control).
+    fn requires_stmt(&self) -> bool {
+        matches!(self.prev_significant(), Some(SyntaxKind::R_PAREN | SyntaxKind::ELSE_KW))
+    }
+
+    fn follows_operand(&self) -> bool {
+        matches!(
+            self.prev_significant(),
+            Some(
+                SyntaxKind::IDENT
+                    | SyntaxKind::INT_NUMBER
+                    | SyntaxKind::STD_REAL_NUMBER
+                    | SyntaxKind::SI_REAL_NUMBER
+                    | SyntaxKind::STR_LIT
+                    | SyntaxKind::INF_KW
+                    | SyntaxKind::R_PAREN
+                    | SyntaxKind::R_BRACK
+            )
+        )
+    }
+}
+
+/// Whether a macro call placed before a token of `kind` is part of an expression.
+fn continues_expr(kind: Option<TokenKind>) -> bool {
+    use TokenKind::*;
+    matches!(
+        kind,
+        Some(
+            Semi | Comma
+                | Dot
+                | OpenBracket
+                | CloseParen
+                | Question
+                | Colon
+                | Eq
+                | Contribute
+                | Lt
+                | Gt
+                | Minus
+                | Plus
+                | Star
+                | Slash
+                | Caret
+                | Percent
+                | And
+                | Or
+                | Eq2
+                | Neq
+                | Leq
+                | Geq
+                | Pipe2
+                | Amp2
+                | Shl
+                | Shr
+                | ShlA
+                | ShrA
+                | Pow
+        )
+    )
+}
+
+enum State {
+    PendingStart,
+    Normal,
+    PendingFinish,
+}
+
+struct RawTreeBuilder<'a> {
+    text: &'a str,
+    tokens: &'a [(SyntaxKind, TextRange)],
+    token_pos: usize,
+    state: State,
+    inner: GreenNodeBuilder<'static>,
+    errors: &'a mut Vec<TextRange>,
+}
+
+impl RawTreeBuilder<'_> {
+    fn token(&mut self, kind: SyntaxKind) {
+        match mem::replace(&mut self.state, State::Normal) {
+            State::PendingStart => unreachable!(),
+            State::PendingFinish => self.inner.finish_node(),
+            State::Normal => (),
+        }
+        self.eat_trivia();
+        self.do_token(kind);
+    }
+
+    fn start_node(&mut self, kind: SyntaxKind) {
+        match mem::replace(&mut self.state, State::Normal) {
+            State::PendingStart => {
+                self.inner.start_node(VerilogALanguage::kind_to_raw(kind));
+                return;
+            }
+            State::PendingFinish => self.inner.finish_node(),
+            State::Normal => (),
+        }
+        self.eat_trivia();
+        self.inner.start_node(VerilogALanguage::kind_to_raw(kind));
+    }
+
+    fn finish_node(&mut self) {
+        match mem::replace(&mut self.state, State::PendingFinish) {
+            State::PendingStart => unreachable!(),
+            State::PendingFinish => self.inner.finish_node(),
+            State::Normal => (),
+        }
+    }
+
+    fn error(&mut self) {
+        let range = self.tokens[self.token_pos..]
+            .iter()
+            .find(|(kind, _)| !kind.is_trivia())
+            .map_or_else(|| TextRange::empty(TextSize::of(self.text)), |(_, range)| *range);
+        self.errors.push(range);
+    }
+
+    fn finish(mut self) -> GreenNode {
+        match mem::replace(&mut self.state, State::Normal) {
+            State::PendingFinish => {
+                self.eat_trivia();
+                self.inner.finish_node()
+            }
+            State::PendingStart | State::Normal => unreachable!(),
+        }
+        self.inner.finish()
+    }
+
+    fn eat_trivia(&mut self) {
+        while let Some(&(kind, _)) = self.tokens.get(self.token_pos) {
+            if !kind.is_trivia() {
+                break;
+            }
+            self.do_token(kind);
+        }
+    }
+
+    fn do_token(&mut self, kind: SyntaxKind) {
+        let (_, range) = self.tokens[self.token_pos];
+        self.token_pos += 1;
+        self.inner.token(VerilogALanguage::kind_to_raw(kind), &self.text[range]);
+    }
+}
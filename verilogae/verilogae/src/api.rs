@@ -3,12 +3,7 @@ use std::os::raw::c_char;
 use std::panic::catch_unwind;
 use std::{ptr, slice};
 
-#[cfg(not(windows))]
-use libloading::os::unix::Library;
-#[cfg(windows)]
-use libloading::os::windows::Library;
-
-use crate::{export_vfs, load};
+use crate::{export_vfs, load, Model};
 
 #[repr(C)]
 #[derive(Default)]
@@ -45,15 +40,12 @@ macro_rules! expose_ptrs {
         ///
         /// # Safety
         ///
-        /// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+        /// `lib` must be a valid pointer returned by the `load` functions
         #[no_mangle]
         pub unsafe extern "C" fn $name(lib: *const c_void) -> *$mut $ty {
             catch_unwind(||{
-                let lib = Library::from_raw(lib as _);
-                let res = access_ptr::<$ty>(&lib, $sym.as_bytes()) as _;
-                // forget library so it doesn't get closed
-                std::mem::forget(lib);
-                res
+                let lib = &*(lib as *const Model);
+                access_ptr::<$ty>(lib, $sym) as _
             }).unwrap_or_else(|_|ptr::null::<$ty>() as _)
         }
     )*
@@ -87,15 +79,12 @@ macro_rules! expose_consts{
         ///
         /// # Safety
         ///
-        /// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+        /// `lib` must be a valid pointer returned by the `load` functions
         #[no_mangle]
         pub unsafe extern "C" fn $name(lib: *const c_void) -> $ty {
             catch_unwind(||{
-                let lib = Library::from_raw(lib as _);
-                let res = access_val(&lib, $sym.as_bytes());
-                // forget library so it doesn't get closed
-                std::mem::forget(lib);
-                res
+                let lib = &*(lib as *const Model);
+                access_val(lib, $sym)
             }).ok().unwrap_or_else(<$ty>::default)
         }
     )*
@@ -133,21 +122,15 @@ macro_rules! expose_named_ptrs {
         ///
         /// # Safety
         ///
-        /// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+        /// `lib` must be a valid pointer returned by the `load` functions
         #[doc = concat!("`sym_name` must batch the schema fun.{NUM}", $sym)]
         #[no_mangle]
         pub unsafe extern "C" fn $name(lib: *const c_void, fun: *const c_char) -> *$mut $ty {
             catch_unwind(||{
-                let fun = CStr::from_ptr(fun);
-                let mut sym_name = fun.to_bytes().to_vec();
-                sym_name.push(b'.');
-                sym_name.extend_from_slice($sym.as_bytes());
-                sym_name.push(b'\0');
-                let lib = Library::from_raw(lib as _);
-                let res = access_ptr::<$ty>(&lib, &sym_name) as _;
-                // forget library so it doesn't get closed
-                std::mem::forget(lib);
-                res
+                let fun = CStr::from_ptr(fun).to_string_lossy();
+                let sym_name = format!("{fun}.{}", $sym);
+                let lib = &*(lib as *const Model);
+                access_ptr::<$ty>(lib, &sym_name) as _
                 }
             )
             .unwrap_or_else(|_| ptr::null::<$ty>() as _)
@@ -178,20 +161,14 @@ macro_rules! expose_named_consts {
         ///
         /// # Safety
         ///
-        /// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+        /// `lib` must be a valid pointer returned by the `load` functions
         #[no_mangle]
         pub unsafe extern "C" fn $name(lib: *const c_void, fun: *const c_char) -> $ty {
             catch_unwind(||{
-                let fun = CStr::from_ptr(fun);
-                let mut sym_name = fun.to_bytes().to_vec();
-                sym_name.push(b'.');
-                sym_name.extend_from_slice($sym.as_bytes());
-                sym_name.push(b'\0');
-                let lib = Library::from_raw(lib as _);
-                let res = access_val(&lib, &sym_name);
-                // forget library so it doesn't get closed
-                std::mem::forget(lib);
-                res
+                let fun = CStr::from_ptr(fun).to_string_lossy();
+                let sym_name = format!("{fun}.{}", $sym);
+                let lib = &*(lib as *const Model);
+                access_val(lib, &sym_name)
                 }
             )
             .ok().unwrap_or_else(<$ty>::default)
@@ -260,21 +237,18 @@ pub type VaeFun = Option<
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 #[no_mangle]
 pub unsafe extern "C" fn verilogae_init_modelcard(lib: *const c_void) -> ModelcardInit {
     catch_unwind(|| {
-        let lib = Library::from_raw(lib as _);
-        let res = match lib.get(b"init_modelcard\0") {
-            Ok(val) => Some(*val),
+        let lib = &*(lib as *const Model);
+        match lib.get("init_modelcard") {
+            Ok(val) => std::mem::transmute::<*mut c_void, ModelcardInit>(val),
             Err(err) => {
                 eprintln!("error: failed to access init_modelcard\n\n{}", err);
                 None
             }
-        };
-        // forget library so it doesn't get closed
-        std::mem::forget(lib);
-        res
+        }
     })
     .ok()
     .flatten()
@@ -284,22 +258,19 @@ pub unsafe extern "C" fn verilogae_init_modelcard(lib: *const c_void) -> Modelca
 ///
 /// # Safety
 ///
-/// `lib` must be a valid pointer returned by the `load` functions or `dlopen`
+/// `lib` must be a valid pointer returned by the `load` functions
 #[no_mangle]
 pub unsafe extern "C" fn verilogae_fun_ptr(lib: *const c_void, fun: *const c_char) -> VaeFun {
     catch_unwind(|| {
-        let fun = CStr::from_ptr(fun);
-        let lib = Library::from_raw(lib as _);
-        let res = match lib.get(fun.to_bytes()) {
-            Ok(val) => Some(*val),
+        let fun = CStr::from_ptr(fun).to_string_lossy();
+        let lib = &*(lib as *const Model);
+        match lib.get(&fun) {
+            Ok(val) => std::mem::transmute::<*mut c_void, VaeFun>(val),
             Err(err) => {
-                eprintln!("error: failed to access {}\n\n{}", fun.to_string_lossy(), err);
+                eprintln!("error: failed to access {}\n\n{}", fun, err);
                 None
             }
-        };
-        // forget library so it doesn't get closed
-        std::mem::forget(lib);
-        res
+        }
     })
     .ok()
     .flatten()
@@ -310,17 +281,14 @@ pub unsafe extern "C" fn verilogae_fun_ptr(lib: *const c_void, fun: *const c_cha
 #[no_mangle]
 pub unsafe extern "C" fn verilogae_module_name(lib: *const c_void) -> *const c_char {
     catch_unwind(|| {
-        let lib = Library::from_raw(lib as _);
-        let res = match lib.get::<*const *const c_char>("module_name\0".as_bytes()) {
-            Ok(val) => **val,
+        let lib = &*(lib as *const Model);
+        match lib.get("module_name") {
+            Ok(val) => *(val as *const *const c_char),
             Err(err) => {
                 eprintln!("error: failed to access module_name\n\n{}", err);
                 std::ptr::null()
             }
-        };
-        // forget library so it doesn't get closed
-        std::mem::forget(lib);
-        res
+        }
     })
     .unwrap_or(ptr::null())
 }
@@ -402,38 +370,26 @@ pub unsafe extern "C" fn verilogae_call_fun_parallel(
     0
 }
 
-unsafe fn access_ptr<T>(lib: &Library, sym_name: &[u8]) -> *const T {
-    match access_global(lib, sym_name) {
-        Ok(val) => val,
+unsafe fn access_ptr<T>(lib: &Model, sym_name: &str) -> *const T {
+    match lib.get(sym_name) {
+        Ok(val) => val as *const T,
         Err(err) => {
-            eprintln!("error: failed to access {}\n\n{}", String::from_utf8_lossy(sym_name), err);
+            eprintln!("error: failed to access {}\n\n{}", sym_name, err);
             ptr::null()
         }
     }
 }
 
-unsafe fn access_val<T: Copy + Default>(lib: &Library, sym_name: &[u8]) -> T {
-    match access_global(lib, sym_name) {
-        Ok(val) => *val,
+unsafe fn access_val<T: Copy + Default>(lib: &Model, sym_name: &str) -> T {
+    match lib.get(sym_name) {
+        Ok(val) => *(val as *const T),
         Err(err) => {
-            eprintln!("error: failed to access {}\n\n{}", String::from_utf8_lossy(sym_name), err);
+            eprintln!("error: failed to access {}\n\n{}", sym_name, err);
             T::default()
         }
     }
 }
 
-// false positive
-#[allow(clippy::explicit_auto_deref)]
-unsafe fn access_global<'a, T>(
-    lib: &'a Library,
-    sym_name: &[u8],
-) -> Result<&'a T, libloading::Error> {
-    lib.get(sym_name).map(|val| {
-        let val: &'a T = *val;
-        val
-    })
-}
-
 #[no_mangle]
 pub extern "C" fn verilogae_new_opts() -> *mut Opts {
     Box::into_raw(Box::default())
@@ -588,7 +544,7 @@ pub unsafe extern "C" fn verilogae_load(
 
     if let Ok(res) = res {
         match res {
-            Ok(lib) => return lib.into_raw() as *const c_void,
+            Ok(lib) => return Box::into_raw(Box::new(lib)) as *const c_void,
             Err(err) => eprintln!("{:?}", err),
         }
     }
@@ -14,6 +14,7 @@ use typed_indexmap::TiSet;
 use crate::compiler_db::{
     current_name, voltage_name, CompilationDB, FuncSpec, InternedModel, ModelInfo,
 };
+use crate::middle::Sensitivities;
 
 pub fn sim_param_stub<'ll>(cx: &CodegenCx<'_, 'll>) -> CallbackFun<'ll> {
     cx.const_callback(&[cx.ty_ptr()], cx.const_real(0.0))
@@ -292,6 +293,7 @@ impl CodegenCtx<'_, '_> {
         func: &Function,
         cfg: &ControlFlowGraph,
         intern: &HirInterner,
+        sensitivities: &Sensitivities,
         dst: &Utf8Path,
     ) {
         let module =
@@ -409,7 +411,7 @@ impl CodegenCtx<'_, '_> {
             let out = llvm::LLVMGetParam(llfun, 9);
             let out = builder.gep(ret_ty, out, &[offset]);
 
-            let ret_val = intern.outputs[&PlaceKind::Var(spec.var)].unwrap();
+            let ret_val = spec.ret_val(intern, sensitivities);
             let ret_val = builder.values[ret_val].get(&builder);
 
             builder.store(out, ret_val);
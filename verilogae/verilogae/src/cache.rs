@@ -56,6 +56,6 @@ pub(crate) fn lookup(
     let hash = base_n::encode(hash, base_n::CASE_INSENSITIVE);
     let extension = if full_compile { "mod" } else { "modinfo" };
     let path = opts.cache_dir()?.join(format!("{}.{}", hash, extension));
-    let exists = !cfg!(debug_assertions) && path.exists();
+    let exists = !cfg!(debug_assertions) && path.is_dir();
     Ok((path, exists))
 }
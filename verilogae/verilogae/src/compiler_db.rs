@@ -122,6 +122,12 @@ impl Diagnostic for IllegalType {
 pub struct FuncSpec {
     pub var: Variable,
     pub dependency_breaking: Box<[Variable]>,
+    /// When set the function calculates the derivative of `var` by this parameter
+    /// instead of the value of `var` itself.
+    pub sensitivity: Option<Parameter>,
+    /// When set the function calculates the second derivative `ddx(ddx(var, param1), param2)`
+    /// instead of the value of `var` itself.
+    pub second_derivative: Option<(Parameter, Parameter)>,
     pub prefix: String,
 }
 
@@ -295,12 +301,120 @@ impl ModelInfo {
                         vec![]
                     };
 
-                    let n = functions.len();
-                    functions.push(FuncSpec {
-                        var,
-                        dependency_breaking: dependency_breaking.into_boxed_slice(),
-                        prefix: format!("fun.{}", base_n::encode(n as _, base_n::CASE_INSENSITIVE)),
-                    });
+                    let dependency_breaking = dependency_breaking.into_boxed_slice();
+                    let mut real_param =
+                        |attr: &ast::Attr,
+                         expr: Expr,
+                         attr_name: &'static str,
+                         expected: &'static str| {
+                            let expr = if let Expr::PathExpr(expr) = expr {
+                                expr
+                            } else {
+                                add_diagnostic(
+                                    attr.clone(),
+                                    &IllegalExpr { expr, expected, attr: attr_name },
+                                );
+                                return None;
+                            };
+                            let path = hir::Path::resolve(expr.path().unwrap()).unwrap();
+                            let param = match module.lookup_param(db, &path) {
+                                Ok(param) => param,
+                                Err(err) => {
+                                    add_diagnostic(attr.clone(), &IllegalPath { expr, err });
+                                    return None;
+                                }
+                            };
+                            if !matches!(param.ty(db), Type::Real) {
+                                add_diagnostic(
+                                    attr.clone(),
+                                    &IllegalType {
+                                        range: attr.syntax().text_range(),
+                                        allowed: "derivatives by real parameters",
+                                    },
+                                );
+                                return None;
+                            }
+                            Some(param)
+                        };
+
+                    let sensitivities = if let Some(attr) = var.get_attr(db, &ast, "sensitivity") {
+                        let exprs = match attr.val() {
+                            Some(Expr::ArrayExpr(expr)) => expr.exprs().collect(),
+                            Some(expr) => vec![expr],
+                            None => vec![],
+                        };
+                        exprs
+                            .into_iter()
+                            .filter_map(|expr| {
+                                real_param(
+                                    &attr,
+                                    expr,
+                                    "sensitivity",
+                                    "a path or an array of paths",
+                                )
+                            })
+                            .collect()
+                    } else {
+                        vec![]
+                    };
+
+                    // a single parameter selects the pure second derivative by that parameter
+                    const SECOND_DERIVATIVE_EXPECTED: &str = "a path or an array of two paths";
+                    let second_derivative = var
+                        .get_attr(db, &ast, "second_derivative")
+                        .map(|attr| {
+                            let exprs = match attr.val() {
+                                Some(Expr::ArrayExpr(expr)) => expr.exprs().collect(),
+                                Some(expr) => vec![expr],
+                                None => vec![],
+                            };
+                            let num_exprs = exprs.len();
+                            let params: Vec<_> = exprs
+                                .into_iter()
+                                .filter_map(|expr| {
+                                    real_param(
+                                        &attr,
+                                        expr,
+                                        "second_derivative",
+                                        SECOND_DERIVATIVE_EXPECTED,
+                                    )
+                                })
+                                .collect();
+                            (attr, num_exprs, params)
+                        })
+                        .and_then(|(attr, num_exprs, params)| match *params {
+                            [param] if num_exprs == 1 => Some((param, param)),
+                            [param1, param2] if num_exprs == 2 => Some((param1, param2)),
+                            _ => {
+                                if let Some(expr) = attr.val() {
+                                    add_diagnostic(
+                                        attr.clone(),
+                                        &IllegalExpr {
+                                            expr,
+                                            expected: SECOND_DERIVATIVE_EXPECTED,
+                                            attr: "second_derivative",
+                                        },
+                                    );
+                                }
+                                None
+                            }
+                        });
+
+                    let sensitivities = sensitivities.into_iter().map(|param| (Some(param), None));
+                    let second_derivative = second_derivative.map(|params| (None, Some(params)));
+                    for (sensitivity, second_derivative) in
+                        [(None, None)].into_iter().chain(sensitivities).chain(second_derivative)
+                    {
+                        let n = functions.len();
+                        let prefix = base_n::encode(n as _, base_n::CASE_INSENSITIVE);
+                        functions.push(FuncSpec {
+                            var,
+                            dependency_breaking: dependency_breaking.clone(),
+                            sensitivity,
+                            second_derivative,
+                            prefix: format!("fun.{prefix}"),
+                        });
+                    }
                 }
 
                 ScopeDef::Parameter(param) => {
@@ -444,7 +558,18 @@ impl ModelInfo {
             .functions
             .iter()
             .map(|fun| {
-                let name = literals.get_or_intern(&*self.var_names[&fun.var]);
+                let name = &self.var_names[&fun.var];
+                let name = match (fun.sensitivity, fun.second_derivative) {
+                    (Some(param), _) => {
+                        literals.get_or_intern(format!("ddx({name}, {})", param.name(db)))
+                    }
+                    (None, Some((param1, param2))) => literals.get_or_intern(format!(
+                        "ddx(ddx({name}, {}), {})",
+                        param1.name(db),
+                        param2.name(db)
+                    )),
+                    (None, None) => literals.get_or_intern(&**name),
+                };
                 let prefix = literals.get_or_intern(&fun.prefix);
                 InternedFunction { name, prefix }
             })
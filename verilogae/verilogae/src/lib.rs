@@ -1,3 +1,4 @@
+use std::ffi::c_void;
 use std::fs;
 use std::io::Write;
 use std::time::Instant;
@@ -6,7 +7,7 @@ use anyhow::{bail, Context, Result};
 use basedb::VfsStorage;
 use camino::{Utf8Path, Utf8PathBuf};
 use lasso::Rodeo;
-use linker::link;
+use llvm::orc::Jit;
 use mir_llvm::LLVMBackend;
 use salsa::ParallelDatabase;
 use stdx::iter::zip;
@@ -20,12 +21,6 @@ use crate::middle::{build_module_mir, build_param_init_mir};
 use crate::opts::abs_path;
 pub use llvm::OptLevel;
 
-#[cfg(windows)]
-use libloading::os::windows::Library;
-
-#[cfg(unix)]
-use libloading::os::unix::Library;
-
 pub mod api;
 mod back;
 mod cache;
@@ -56,20 +51,44 @@ pub fn export_vfs(path: &Utf8Path, opts: &Opts) -> Result<Box<[VfsEntry]>> {
     Ok(res)
 }
 
-pub fn load(path: &Utf8Path, full_compile: bool, opts: &Opts) -> Result<Library> {
-    let lib = build_local_model(path, full_compile, opts)?;
-    let lib = unsafe { Library::new(lib).expect("failed to open lib") };
-    Ok(lib)
+/// A compiled model that was loaded into the current process by [`load`].
+/// The code of the model (and all pointers into it) are only valid while it is alive.
+pub struct Model {
+    jit: Jit,
+}
+
+impl Model {
+    /// Returns the address of the symbol `name` exported by the model.
+    pub fn get(&self, name: &str) -> Result<*mut c_void> {
+        let res = self.jit.lookup(name).with_context(|| format!("failed to find symbol {name}"))?;
+        Ok(res)
+    }
+}
+
+pub fn load(path: &Utf8Path, full_compile: bool, opts: &Opts) -> Result<Model> {
+    let dir = build_local_model(path, full_compile, opts)?;
+    let jit = Jit::new()?;
+    let mut objects = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("failed to read {dir}"))? {
+        objects.push(entry?.path());
+    }
+    // load the objects in a deterministic order
+    objects.sort_unstable();
+    for obj in objects {
+        jit.add_object_file(&obj)?;
+    }
+    Ok(Model { jit })
 }
 
+/// Compiles the model into a directory of object files inside the cache (or finds it there).
 fn build_local_model(path: &Utf8Path, full_compile: bool, opts: &Opts) -> Result<Utf8PathBuf> {
     let db = compiler_db::new(path, opts)?;
-    let (file, found) = cache::lookup(&db, full_compile, opts)?;
+    let (dir, found) = cache::lookup(&db, full_compile, opts)?;
     if found {
-        return Ok(file);
+        return Ok(dir);
     }
 
-    build_model(db, path, full_compile, true, opts, &file).map(|_| file)
+    build_model(db, path, full_compile, true, opts, &dir).map(|_| dir)
 }
 
 fn build_model(
@@ -95,17 +114,20 @@ fn build_model(
     let cg_opts: Vec<_> = opts.cg_flags().map(str::to_owned).collect();
     let target = opts.target()?;
     let backend = LLVMBackend::new(&cg_opts, &target, target_cpu.to_owned(), &[]);
-    let cache_dir = opts.cache_dir()?;
 
-    std::fs::create_dir_all(&cache_dir).unwrap();
-    if let Some(parent) = dst.parent() {
-        std::fs::create_dir_all(parent).unwrap();
+    // the objects are written to a temporary directory first so that a partially written
+    // model is never picked up from the cache
+    let tmp_dir = Utf8PathBuf::from(format!("{dst}.tmp"));
+    #[allow(unused_must_use)]
+    {
+        fs::remove_dir_all(&tmp_dir);
     }
+    fs::create_dir_all(&tmp_dir).with_context(|| format!("failed to create {tmp_dir}"))?;
 
-    let mut object_files = vec![cache_dir.join(format!("{}_modelinfo.o", file))];
+    let mut object_files = vec![tmp_dir.join("modelinfo.o")];
 
     if full_compile {
-        let (func, intern, mut literals, cfg) = build_module_mir(&db, &info);
+        let (func, intern, mut literals, sensitivities, cfg) = build_module_mir(&db, &info);
         let interned_model = info.intern_model(&db, &mut literals);
         let param_init = build_param_init_mir(&db, &info, &mut literals);
 
@@ -118,12 +140,8 @@ fn build_model(
 
         cx.compile_model_info(&object_files[0], interned_model, param_init.0, param_init.1);
 
-        let dst_name = dst.file_name().to_owned().unwrap();
-        object_files.extend(
-            info.functions
-                .iter()
-                .map(|fun| cache_dir.join(format!("{}{}.o", dst_name, fun.prefix))),
-        );
+        object_files
+            .extend(info.functions.iter().map(|fun| tmp_dir.join(format!("{}.o", fun.prefix))));
 
         // ensure all voltage/current names are in the interner so that the interner can be
         // shared (readonly) betwenn threads
@@ -135,8 +153,8 @@ fn build_model(
                 let db_snap = db.snapshot();
                 s.spawn(|_| {
                     let db_snap = db_snap;
-                    let (func, cfg) = spec.slice_mir(&func, &cfg, &intern);
-                    cx.gen_func_obj(&db_snap, spec, &func, &cfg, &intern, file)
+                    let (func, cfg) = spec.slice_mir(&func, &cfg, &intern, &sensitivities);
+                    cx.gen_func_obj(&db_snap, spec, &func, &cfg, &intern, &sensitivities, file)
                 })
             }
         })
@@ -156,18 +174,14 @@ fn build_model(
         cx.compile_model_info(&object_files[0], interned_model, param_init.0, param_init.1);
     }
 
-    // TODO configure linker
-    link(None, &target, dst, |linker| {
-        for obj in &object_files {
-            linker.add_object(obj)
-        }
-    })
-    .context("linking failed!")?;
-
+    // older versions cached a linked library instead of a directory
     #[allow(unused_must_use)]
-    for file in object_files {
-        fs::remove_file(file);
+    if dst.is_file() {
+        fs::remove_file(dst);
+    } else {
+        fs::remove_dir_all(dst);
     }
+    fs::rename(&tmp_dir, dst).with_context(|| format!("failed to move {tmp_dir} to {dst}"))?;
 
     let seconds = Instant::elapsed(&start).as_secs_f64();
     let mut stderr = StandardStream::stderr(Auto);
@@ -1,9 +1,12 @@
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use bitset::{BitSet, SparseBitMatrix};
+use hir::{Parameter, Variable};
 use hir_lower::{CallBackKind, HirInterner, MirBuilder, ParamKind, PlaceKind};
 use lasso::Rodeo;
-use mir::{ControlFlowGraph, DominatorTree, Function, ValueDef};
-use mir_autodiff::auto_diff;
+use mir::builder::InstBuilder;
+use mir::cursor::{Cursor, FuncCursor};
+use mir::{ControlFlowGraph, DominatorTree, Function, Value, ValueDef, F_ZERO};
+use mir_autodiff::{auto_diff_second_order, second_derivative};
 use mir_opt::{
     aggressive_dead_code_elimination, dead_code_elimination, inst_combine, simplify_cfg,
     sparse_conditional_constant_propagation,
@@ -11,14 +14,28 @@ use mir_opt::{
 
 use crate::compiler_db::{CompilationDB, FuncSpec, ModelInfo};
 
+/// The derivatives `ddx(var, param)` requested with the `sensitivity` attribute (stored with
+/// `None`) and `ddx(ddx(var, param1), param2)` requested with the `second_derivative`
+/// attribute (stored with `Some(param2)`)
+pub type Sensitivities = AHashMap<(Variable, Parameter, Option<Parameter>), Value>;
+
 impl FuncSpec {
+    pub fn ret_val(&self, intern: &HirInterner, sensitivities: &Sensitivities) -> Value {
+        match (self.sensitivity, self.second_derivative) {
+            (Some(param), _) => sensitivities[&(self.var, param, None)],
+            (None, Some((param1, param2))) => sensitivities[&(self.var, param1, Some(param2))],
+            (None, None) => intern.outputs[&PlaceKind::Var(self.var)].unwrap(),
+        }
+    }
+
     pub fn slice_mir(
         &self,
         func: &Function,
         cfg: &ControlFlowGraph,
         intern: &HirInterner,
+        sensitivities: &Sensitivities,
     ) -> (Function, ControlFlowGraph) {
-        let ret_val = intern.outputs[&PlaceKind::Var(self.var)].unwrap();
+        let ret_val = self.ret_val(intern, sensitivities);
         let mut func = func.clone();
         let mut cfg = cfg.clone();
 
@@ -63,7 +80,7 @@ impl FuncSpec {
 pub fn build_module_mir(
     db: &CompilationDB,
     info: &ModelInfo,
-) -> (Function, HirInterner, Rodeo, ControlFlowGraph) {
+) -> (Function, HirInterner, Rodeo, Sensitivities, ControlFlowGraph) {
     let dep_break: AHashSet<_> =
         info.functions.iter().flat_map(|func| func.dependency_breaking.iter().copied()).collect();
 
@@ -113,16 +130,77 @@ pub fn build_module_mir(
 
     let mut dom_tree = DominatorTree::default();
     dom_tree.compute(&func, &cfg, true, false, true);
-    let unknowns = intern.unknowns(&mut func, false);
-    auto_diff(&mut func, &dom_tree, &unknowns, &[]);
+    let mut unknowns = intern.unknowns(&mut func, false);
+
+    // parameters selected with the sensitivity and second_derivative attributes become
+    // additional unknowns
+    let mut param_unknown = |param| {
+        let val = *intern.params.raw.get(&ParamKind::Param(param))?;
+        if func.dfg.value_dead(val) {
+            return None;
+        }
+        Some(unknowns.unknowns.ensure(val).0)
+    };
+    let mut requested = Vec::new();
+    let mut requested_second_order = Vec::new();
+    for fun in &info.functions {
+        let out = intern.outputs[&PlaceKind::Var(fun.var)].unwrap();
+        if let Some(param) = fun.sensitivity {
+            if let Some(unknown) = param_unknown(param) {
+                requested.push((fun.var, param, out, unknown));
+            }
+        }
+        if let Some((param1, param2)) = fun.second_derivative {
+            if let (Some(unknown1), Some(unknown2)) = (param_unknown(param1), param_unknown(param2))
+            {
+                requested_second_order.push((fun.var, (param1, param2), out, (unknown1, unknown2)));
+            }
+        }
+    }
+    let extra_derivatives: Vec<_> =
+        requested.iter().map(|&(_, _, out, unknown)| (out, unknown)).collect();
+    let extra_second_derivatives: Vec<_> = requested_second_order
+        .iter()
+        .map(|&(_, _, out, (unknown1, unknown2))| (out, unknown1, unknown2))
+        .collect();
+    let derivatives = auto_diff_second_order(
+        &mut func,
+        &dom_tree,
+        &unknowns,
+        &extra_derivatives,
+        &extra_second_derivatives,
+    );
+
+    let mut sensitivities = Sensitivities::new();
+    for fun in &info.functions {
+        if let Some(param) = fun.sensitivity {
+            sensitivities.insert((fun.var, param, None), F_ZERO);
+        }
+        if let Some((param1, param2)) = fun.second_derivative {
+            sensitivities.insert((fun.var, param1, Some(param2)), F_ZERO);
+        }
+    }
+    for (var, param, out, unknown) in requested {
+        if let Some(&val) = derivatives.get(&(out, unknown)) {
+            let val = FuncCursor::new(&mut func).at_exit().ins().ensure_optbarrier(val);
+            sensitivities.insert((var, param, None), val);
+        }
+    }
+    for (var, (param1, param2), out, (unknown1, unknown2)) in requested_second_order {
+        let val = second_derivative(&derivatives, out, unknown1, unknown2);
+        if val != F_ZERO {
+            let val = FuncCursor::new(&mut func).at_exit().ins().ensure_optbarrier(val);
+            sensitivities.insert((var, param1, Some(param2)), val);
+        }
+    }
+
     cfg.clear();
     cfg.compute(&func);
     sparse_conditional_constant_propagation(&mut func, &cfg);
     inst_combine(&mut func);
     simplify_cfg(&mut func, &mut cfg);
 
-    (func, intern, literals, cfg)
-    // (func, intern, literals, Vec::new(), cfg)
+    (func, intern, literals, sensitivities, cfg)
 }
 
 pub fn build_param_init_mir(
@@ -71,7 +71,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_functions(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -82,7 +82,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_function_symbols(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -93,7 +93,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_opvars(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -104,7 +104,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_real_params(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -115,7 +115,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_real_param_units(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -126,7 +126,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_real_param_descriptions(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -137,7 +137,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_real_param_groups(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -148,7 +148,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_int_params(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -159,7 +159,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_int_param_units(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -170,7 +170,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_int_param_descriptions(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -181,7 +181,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_int_param_groups(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -192,7 +192,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_str_params(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -203,7 +203,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_str_param_units(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -214,7 +214,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_str_param_descriptions(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -225,7 +225,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_str_param_groups(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -236,7 +236,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_nodes(
         lib: *const ::std::os::raw::c_void,
     ) -> *const *const ::std::os::raw::c_char;
@@ -247,7 +247,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_function_cnt(lib: *const ::std::os::raw::c_void) -> usize;
 }
 extern "C" {
@@ -256,7 +256,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_opvars_cnt(lib: *const ::std::os::raw::c_void) -> usize;
 }
 extern "C" {
@@ -265,7 +265,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_real_param_cnt(lib: *const ::std::os::raw::c_void) -> usize;
 }
 extern "C" {
@@ -274,7 +274,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_int_param_cnt(lib: *const ::std::os::raw::c_void) -> usize;
 }
 extern "C" {
@@ -283,7 +283,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_str_param_cnt(lib: *const ::std::os::raw::c_void) -> usize;
 }
 extern "C" {
@@ -292,7 +292,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_node_cnt(lib: *const ::std::os::raw::c_void) -> usize;
 }
 extern "C" {
@@ -301,7 +301,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}params.real"]
     pub fn verilogae_real_fun_params(
         lib: *const ::std::os::raw::c_void,
@@ -314,7 +314,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}params.integer"]
     pub fn verilogae_int_fun_params(
         lib: *const ::std::os::raw::c_void,
@@ -327,7 +327,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}params.string"]
     pub fn verilogae_str_fun_params(
         lib: *const ::std::os::raw::c_void,
@@ -340,7 +340,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}depbreak.real"]
     pub fn verilogae_real_fun_depbreak(
         lib: *const ::std::os::raw::c_void,
@@ -353,7 +353,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}depbreak.integer"]
     pub fn verilogae_int_fun_depbreak(
         lib: *const ::std::os::raw::c_void,
@@ -366,7 +366,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}voltages"]
     pub fn verilogae_fun_voltages(
         lib: *const ::std::os::raw::c_void,
@@ -379,7 +379,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}currents"]
     pub fn verilogae_fun_currents(
         lib: *const ::std::os::raw::c_void,
@@ -392,7 +392,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}voltages.default"]
     pub fn verilogae_fun_voltage_defaults(
         lib: *const ::std::os::raw::c_void,
@@ -405,7 +405,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     #[doc = "`sym_name` must batch the schema fun.{NUM}currents.default"]
     pub fn verilogae_fun_current_defaults(
         lib: *const ::std::os::raw::c_void,
@@ -418,7 +418,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_real_fun_param_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -430,7 +430,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_int_fun_param_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -442,7 +442,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_str_fun_param_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -454,7 +454,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_real_fun_depbreak_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -466,7 +466,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_int_fun_depbreak_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -478,7 +478,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_fun_voltage_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -490,7 +490,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_fun_current_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -502,7 +502,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_fun_voltage_default_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -514,7 +514,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_fun_current_default_cnt(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,
@@ -525,7 +525,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_init_modelcard(lib: *const ::std::os::raw::c_void) -> ModelcardInit;
 }
 extern "C" {
@@ -533,7 +533,7 @@ extern "C" {
     #[doc = ""]
     #[doc = " # Safety"]
     #[doc = ""]
-    #[doc = " `lib` must be a valid pointer returned by the `load` functions or `dlopen`"]
+    #[doc = " `lib` must be a valid pointer returned by the `load` functions"]
     pub fn verilogae_fun_ptr(
         lib: *const ::std::os::raw::c_void,
         fun: *const ::std::os::raw::c_char,